@@ -0,0 +1,71 @@
+//! Game database lookups in a large library
+//!
+//! Writes a database of 50 000 Heroic games into a home directory of its own
+//! and times `nvproton prepare --dry-run --ids` resolving every one of them,
+//! next to resolving a single game (process startup and loading the
+//! database). Run with `cargo bench --bench lookups`.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const GAMES: usize = 50_000;
+/// Linux caps a single argument at 128 KiB, so the IDs go in several `--ids`
+const IDS_PER_ARG: usize = 5_000;
+const RUNS: usize = 5;
+
+fn main() {
+    let home = tempfile::tempdir().unwrap();
+    write_database(home.path());
+
+    let ids: Vec<String> = (0..GAMES).map(|i| format!("game{}", i)).collect();
+    let one = fastest(home.path(), &ids[..1]);
+    let all = fastest(home.path(), &ids);
+    // With indexed lookups both are dominated by loading the database; a
+    // scan per lookup makes the second one take minutes
+    println!("resolving 1 game:      {:?}", one);
+    println!("resolving {} games: {:?}", GAMES, all);
+}
+
+/// A database of Heroic games keyed by `heroic:gameN`
+fn write_database(home: &Path) {
+    let dir = home.join(".config/nvproton/games");
+    fs::create_dir_all(&dir).unwrap();
+    let mut yaml = String::from("entries:\n");
+    for i in 0..GAMES {
+        writeln!(
+            yaml,
+            "  heroic:game{i}:\n    source: heroic\n    name: Game {i}\n    install_dir: /games/game{i}\n    last_seen: 0"
+        )
+        .unwrap();
+    }
+    fs::write(dir.join("games.yaml"), yaml).unwrap();
+}
+
+/// Fastest of several runs resolving `ids`
+fn fastest(home: &Path, ids: &[String]) -> Duration {
+    (0..RUNS).map(|_| prepare_dry_run(home, ids)).min().unwrap()
+}
+
+fn prepare_dry_run(home: &Path, ids: &[String]) -> Duration {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_nvproton"));
+    cmd.args(["prepare", "--dry-run"]);
+    for chunk in ids.chunks(IDS_PER_ARG) {
+        cmd.arg("--ids").arg(chunk.join(","));
+    }
+    let started = Instant::now();
+    let status = cmd
+        .env("HOME", home)
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("XDG_DATA_HOME")
+        .env_remove("XDG_STATE_HOME")
+        .env_remove("NVPROTON_CONTEXT")
+        .stdout(Stdio::null())
+        .status()
+        .unwrap();
+    let elapsed = started.elapsed();
+    assert!(status.success(), "nvproton prepare failed");
+    elapsed
+}
@@ -0,0 +1,260 @@
+//! User-defined command aliases
+//!
+//! Aliases live in the config and are expanded before the command line is
+//! parsed, so `nvproton er --dry-run` with
+//!
+//! ```yaml
+//! aliases:
+//!   er: run 1245620 --profile competitive
+//! ```
+//!
+//! runs `nvproton run 1245620 --profile competitive --dry-run`. Built-in
+//! commands always win over aliases of the same name, and an expansion is
+//! not expanded again.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+
+use anyhow::{Result, bail};
+use clap::CommandFactory;
+
+use crate::cli::{AliasCommand, Cli};
+use crate::config::{self, ConfigManager, TrackedConfig};
+
+/// Global options that take a value, to find the command after them
+const VALUE_OPTIONS: &[&str] = &["--context"];
+
+/// Names of the built-in commands, including clap's `help`
+fn builtin_commands() -> Vec<String> {
+    let mut names: Vec<String> = Cli::command()
+        .get_subcommands()
+        .map(|cmd| cmd.get_name().to_string())
+        .collect();
+    names.push("help".into());
+    names
+}
+
+/// Index of the command word in `args` (after the program name and global options)
+fn command_index(args: &[String]) -> Option<usize> {
+    let mut i = 1;
+    while i < args.len() {
+        let arg = &args[i];
+        if arg == "--" {
+            return None;
+        }
+        if !arg.starts_with('-') {
+            return Some(i);
+        }
+        if VALUE_OPTIONS.contains(&arg.as_str()) {
+            i += 1;
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Replace an alias in the command position of `args` with its expansion
+pub fn expand(mut args: Vec<String>, aliases: &BTreeMap<String, String>) -> Result<Vec<String>> {
+    let Some(index) = command_index(&args) else {
+        return Ok(args);
+    };
+    let Some(expansion) = aliases.get(&args[index]) else {
+        return Ok(args);
+    };
+    if builtin_commands().contains(&args[index]) {
+        return Ok(args);
+    }
+    let words = split_words(expansion)?;
+    if words.is_empty() {
+        bail!("alias '{}' is empty", args[index]);
+    }
+    args.splice(index..=index, words);
+    Ok(args)
+}
+
+/// Expand an alias using the config, loading it only for unknown commands
+pub fn expand_args(args: Vec<OsString>) -> Result<Vec<OsString>> {
+    // Aliases are text; command lines with non-UTF-8 arguments are left alone
+    let Ok(words) = args
+        .iter()
+        .map(|arg| arg.clone().into_string())
+        .collect::<Result<Vec<String>, _>>()
+    else {
+        return Ok(args);
+    };
+    let Some(index) = command_index(&words) else {
+        return Ok(args);
+    };
+    if builtin_commands().contains(&words[index]) {
+        return Ok(args);
+    }
+    let portable = words[1..index].iter().any(|arg| arg == "--portable");
+    let manager = match config::portable_root(portable) {
+        Some(root) => ConfigManager::portable(&root),
+        None => ConfigManager::new()?,
+    };
+    let aliases = manager.load()?.aliases;
+    Ok(expand(words, &aliases)?
+        .into_iter()
+        .map(OsString::from)
+        .collect())
+}
+
+/// Split an alias into words, honoring single and double quotes
+fn split_words(line: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => match chars.next() {
+                Some(next) => word.push(next),
+                None => bail!("unterminated escape in '{}'", line),
+            },
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') => {
+                if let Some(next) = chars.next() {
+                    word.push(next);
+                }
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        bail!("unterminated quote in '{}'", line);
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Quote a word for an alias expansion if it needs it
+pub(crate) fn quote_word(word: &str) -> String {
+    if !word.is_empty() && !word.contains(|c: char| c.is_whitespace() || "'\"\\".contains(c)) {
+        return word.to_string();
+    }
+    format!("\"{}\"", word.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub fn handle_alias(command: AliasCommand, config: &mut TrackedConfig) -> Result<()> {
+    match command {
+        AliasCommand::List => {
+            if config.aliases.is_empty() {
+                println!("No aliases defined. Add one with 'nvproton alias add <name> <command>'");
+            }
+            for (name, expansion) in &config.aliases {
+                println!("{} = {}", name, expansion);
+            }
+        }
+        AliasCommand::Add { name, command } => {
+            if name.is_empty() || name.starts_with('-') || name.contains(char::is_whitespace) {
+                bail!("invalid alias name '{}'", name);
+            }
+            if builtin_commands().contains(&name) {
+                bail!("'{}' is a built-in command and cannot be an alias", name);
+            }
+            // A single argument is a quoted command line; several are the words
+            let expansion = match command.as_slice() {
+                [line] => line.clone(),
+                words => words
+                    .iter()
+                    .map(|w| quote_word(w))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            };
+            if split_words(&expansion)?.is_empty() {
+                bail!("alias '{}' needs a command", name);
+            }
+            println!("{} = {}", name, expansion);
+            config.edit().aliases.insert(name, expansion);
+        }
+        AliasCommand::Remove { name } => {
+            if config.edit().aliases.remove(&name).is_none() {
+                bail!("alias '{}' not found", name);
+            }
+            println!("Removed alias '{}'", name);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split(' ').map(String::from).collect()
+    }
+
+    #[test]
+    fn test_expand_alias_in_command_position() {
+        let aliases = BTreeMap::from([
+            (
+                "er".to_string(),
+                "run --name \"Elden Ring\" --profile competitive".to_string(),
+            ),
+            ("status".to_string(), "run 1".to_string()),
+        ]);
+
+        assert_eq!(
+            expand(args("nvproton --context desk er --dry-run"), &aliases).unwrap(),
+            vec![
+                "nvproton",
+                "--context",
+                "desk",
+                "run",
+                "--name",
+                "Elden Ring",
+                "--profile",
+                "competitive",
+                "--dry-run"
+            ]
+        );
+        // Built-in commands and arguments are never expanded
+        assert_eq!(
+            expand(args("nvproton status"), &aliases).unwrap(),
+            args("nvproton status")
+        );
+        assert_eq!(
+            expand(args("nvproton run er"), &aliases).unwrap(),
+            args("nvproton run er")
+        );
+    }
+
+    #[test]
+    fn test_split_and_quote_words() {
+        assert_eq!(
+            split_words(r#"run 'a b' "c \"d\"" e\ f"#).unwrap(),
+            vec!["run", "a b", "c \"d\"", "e f"]
+        );
+        assert!(split_words("run 'open").is_err());
+        let line = ["run", "--name", "Elden Ring", ""]
+            .iter()
+            .map(|w| quote_word(w))
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(line, r#"run --name "Elden Ring" """#);
+        assert_eq!(
+            split_words(&line).unwrap(),
+            vec!["run", "--name", "Elden Ring", ""]
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Anti-cheat runtimes for launches outside Steam
+//!
+//! Proton only loads the Linux builds of Easy Anti-Cheat and BattlEye when
+//! `PROTON_EAC_RUNTIME` / `PROTON_BATTLEYE_RUNTIME` point at Valve's runtime
+//! apps. Steam sets them for its own games; Epic, GOG and manually added
+//! games need them set by nvproton, or the anti-cheat refuses to start.
+
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::config::NvConfig;
+use crate::steam::client::SteamClient;
+
+/// Anti-cheat with a Proton runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AntiCheat {
+    EasyAntiCheat,
+    BattlEye,
+}
+
+impl AntiCheat {
+    pub fn name(self) -> &'static str {
+        match self {
+            AntiCheat::EasyAntiCheat => "Easy Anti-Cheat",
+            AntiCheat::BattlEye => "BattlEye",
+        }
+    }
+
+    /// Steam app shipping the runtime
+    pub fn runtime_app_id(self) -> &'static str {
+        match self {
+            AntiCheat::EasyAntiCheat => "1826330",
+            AntiCheat::BattlEye => "1161040",
+        }
+    }
+
+    pub fn runtime_name(self) -> &'static str {
+        match self {
+            AntiCheat::EasyAntiCheat => "Proton EasyAntiCheat Runtime",
+            AntiCheat::BattlEye => "Proton BattlEye Runtime",
+        }
+    }
+
+    /// Variable pointing Proton at the runtime
+    pub fn env_var(self) -> &'static str {
+        match self {
+            AntiCheat::EasyAntiCheat => "PROTON_EAC_RUNTIME",
+            AntiCheat::BattlEye => "PROTON_BATTLEYE_RUNTIME",
+        }
+    }
+
+    /// Whether a file or directory name belongs to this anti-cheat
+    fn matches(self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        match self {
+            AntiCheat::EasyAntiCheat => {
+                name.starts_with("easyanticheat") || name == "start_protected_game.exe"
+            }
+            AntiCheat::BattlEye => {
+                name == "battleye" || name.starts_with("beservice") || name.starts_with("beclient")
+            }
+        }
+    }
+
+    /// The runtime installed through Steam, if any
+    pub fn runtime(self, config: &NvConfig) -> Option<PathBuf> {
+        SteamClient::from_config(config)?.app_install_dir(self.runtime_app_id())
+    }
+}
+
+/// Anti-cheats shipped in a game's install directory
+pub fn detect(install_dir: &Path) -> Vec<AntiCheat> {
+    let mut found = Vec::new();
+    // The anti-cheat sits next to the executable or one level below
+    for entry in WalkDir::new(install_dir)
+        .min_depth(1)
+        .max_depth(3)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        let name = entry.file_name().to_string_lossy();
+        for anticheat in [AntiCheat::EasyAntiCheat, AntiCheat::BattlEye] {
+            if !found.contains(&anticheat) && anticheat.matches(&name) {
+                found.push(anticheat);
+            }
+        }
+        if found.len() == 2 {
+            break;
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_detect_anticheat() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(detect(dir.path()).is_empty());
+
+        fs::create_dir_all(dir.path().join("Binaries/Win64/EasyAntiCheat")).unwrap();
+        assert_eq!(detect(dir.path()), vec![AntiCheat::EasyAntiCheat]);
+
+        fs::write(dir.path().join("Binaries/BEService_x64.exe"), "").unwrap();
+        let found = detect(dir.path());
+        assert_eq!(found.len(), 2);
+        assert!(found.contains(&AntiCheat::BattlEye));
+    }
+}
@@ -1,18 +1,49 @@
 //! Shader cache management for DXVK, vkd3d-proton, and NVIDIA
 //!
 //! Provides unified cache management with:
-//! - Per-game cache isolation
+//! - Per-game cache isolation, in directories named by source and ID
+//!   (`dxvk/steam-1245620`) so a Lutris and a Heroic "doom" don't share one
 //! - Cache size monitoring
 //! - Cleanup utilities
 //! - Cache import/export for sharing
+//! - Relocation of caches and prefixes to another disk
 //!
 //! Note: Many functions here are reserved for future nvshader integration.
 #![allow(dead_code)]
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::{Context, Result, bail};
+use once_cell::sync::OnceCell;
+use walkdir::WalkDir;
+
+use crate::cli::{CacheArgs, CacheCommand, CacheMigrateArgs, CacheRelocateArgs};
+use crate::config::{ConfigManager, NvConfig};
+use crate::detection::pe::PeInfo;
+use crate::detection::{DetectedGame, GameDatabase, GameId, GameSource};
+use crate::notify;
+use crate::term::Progress;
+use crate::{disk_usage, dxvk};
+
+/// Cache base chosen at startup (portable mode), instead of ~/.cache/nvproton
+static CACHE_BASE: OnceCell<PathBuf> = OnceCell::new();
+
+/// Use `path` as the cache base for the rest of the process
+pub fn set_cache_base(path: PathBuf) {
+    let _ = CACHE_BASE.set(path);
+}
 
-use anyhow::{Context, Result};
+/// Base directory for nvproton caches and downloads
+pub fn cache_base() -> PathBuf {
+    CACHE_BASE.get().cloned().unwrap_or_else(|| {
+        dirs::cache_dir()
+            .map(|d| d.join("nvproton"))
+            .unwrap_or_else(|| PathBuf::from("/tmp/nvproton-cache"))
+    })
+}
 
 /// Cache types managed by nvproton
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -51,6 +82,10 @@ impl CacheType {
     }
 }
 
+/// Cache types isolated per game; the Mesa cache is shared
+pub const GAME_CACHE_TYPES: [CacheType; 3] =
+    [CacheType::Dxvk, CacheType::Vkd3d, CacheType::NvidiaGl];
+
 /// Cache directory structure
 pub struct CachePaths {
     /// Base cache directory (~/.cache/nvproton)
@@ -68,10 +103,10 @@ pub struct CachePaths {
 impl CachePaths {
     /// Create cache paths with default locations
     pub fn new() -> Self {
-        let base = dirs::cache_dir()
-            .map(|d| d.join("nvproton"))
-            .unwrap_or_else(|| PathBuf::from("/tmp/nvproton-cache"));
+        Self::under(cache_base())
+    }
 
+    fn under(base: PathBuf) -> Self {
         Self {
             dxvk: base.join("dxvk"),
             vkd3d: base.join("vkd3d"),
@@ -92,9 +127,71 @@ impl CachePaths {
         }
     }
 
-    /// Get game-specific cache path
-    pub fn for_game(&self, cache_type: CacheType, game_id: &str) -> PathBuf {
-        self.get(cache_type).join(game_id)
+    /// Get game-specific cache path (`dxvk/steam-1245620`)
+    pub fn for_game(&self, cache_type: CacheType, game: &GameId) -> PathBuf {
+        self.get(cache_type).join(game.dir_name())
+    }
+
+    /// Cache path named by the bare game ID, as before caches were
+    /// namespaced by source
+    fn legacy_for_game(&self, cache_type: CacheType, game: &GameId) -> PathBuf {
+        self.get(cache_type).join(&game.id)
+    }
+
+    /// Where a game's cache is read from: its namespaced directory, or the
+    /// bare-ID one until [`Self::migrate_game`] moves it
+    ///
+    /// The fallback is kept for one release after the namespacing.
+    pub fn find_for_game(&self, cache_type: CacheType, game: &GameId) -> PathBuf {
+        let path = self.for_game(cache_type, game);
+        let legacy = self.legacy_for_game(cache_type, game);
+        if !path.exists() && legacy.is_dir() {
+            legacy
+        } else {
+            path
+        }
+    }
+
+    /// Per-game cache directories, by type
+    pub fn game_dirs(&self, game: &GameId) -> Vec<(CacheType, PathBuf)> {
+        GAME_CACHE_TYPES
+            .iter()
+            .map(|&cache_type| (cache_type, self.for_game(cache_type, game)))
+            .collect()
+    }
+
+    /// Per-game cache directories to read, by type (see [`Self::find_for_game`])
+    pub fn find_game_dirs(&self, game: &GameId) -> Vec<(CacheType, PathBuf)> {
+        GAME_CACHE_TYPES
+            .iter()
+            .map(|&cache_type| (cache_type, self.find_for_game(cache_type, game)))
+            .collect()
+    }
+
+    /// Bare-ID cache directories of a game that haven't been namespaced yet
+    fn legacy_game_dirs(&self, game: &GameId) -> Vec<(PathBuf, PathBuf)> {
+        GAME_CACHE_TYPES
+            .iter()
+            .map(|&cache_type| {
+                (
+                    self.legacy_for_game(cache_type, game),
+                    self.for_game(cache_type, game),
+                )
+            })
+            .filter(|(legacy, path)| legacy.is_dir() && !path.exists())
+            .collect()
+    }
+
+    /// Rename a game's bare-ID cache directories to namespaced ones,
+    /// returning the new directories
+    pub fn migrate_game(&self, game: &GameId) -> Result<Vec<PathBuf>> {
+        let mut moved = Vec::new();
+        for (legacy, path) in self.legacy_game_dirs(game) {
+            fs::rename(&legacy, &path)
+                .with_context(|| format!("failed to move cache {:?} to {:?}", legacy, path))?;
+            moved.push(path);
+        }
+        Ok(moved)
     }
 
     /// Ensure all cache directories exist
@@ -154,32 +251,55 @@ impl CacheManager {
     }
 
     /// Set up cache paths for a game and return environment variables
-    pub fn setup_for_game(&self, game_id: &str) -> Result<Vec<(String, String)>> {
+    ///
+    /// This is the one place cache environment variables are built; the
+    /// paths are absolute so they also work in Steam launch options. Caches
+    /// in bare-ID directories are moved to the game's namespaced ones first,
+    /// unless another game in `db` shares the ID and may own them (see
+    /// `cache migrate`).
+    /// DXVK and vkd3d-proton caches are skipped for games whose executable
+    /// imports none of the APIs they translate.
+    pub fn setup_for_game(
+        &self,
+        game: &DetectedGame,
+        db: &GameDatabase,
+    ) -> Result<Vec<(String, String)>> {
         let mut env_vars = Vec::new();
+        let game_id = game.game_id();
+        let pe_info = PeInfo::from_metadata(&game.metadata);
 
-        // DXVK
-        let dxvk_path = self.paths.for_game(CacheType::Dxvk, game_id);
-        fs::create_dir_all(&dxvk_path)?;
-        env_vars.push((
-            CacheType::Dxvk.env_var().to_string(),
-            dxvk_path.to_string_lossy().to_string(),
-        ));
-
-        // vkd3d-proton
-        let vkd3d_path = self.paths.for_game(CacheType::Vkd3d, game_id);
-        fs::create_dir_all(&vkd3d_path)?;
-        env_vars.push((
-            CacheType::Vkd3d.env_var().to_string(),
-            vkd3d_path.to_string_lossy().to_string(),
-        ));
+        if db.shares_id(&game_id) {
+            for (legacy, _) in self.paths.legacy_game_dirs(&game_id) {
+                log::info!("leaving {:?} in place, another game shares its ID", legacy);
+            }
+        } else {
+            for path in self.paths.migrate_game(&game_id)? {
+                log::info!("moved the {} cache to {:?}", game_id, path);
+            }
+        }
 
-        // NVIDIA GL
-        let gl_path = self.paths.for_game(CacheType::NvidiaGl, game_id);
-        fs::create_dir_all(&gl_path)?;
-        env_vars.push((
-            CacheType::NvidiaGl.env_var().to_string(),
-            gl_path.to_string_lossy().to_string(),
-        ));
+        // DXVK, vkd3d-proton and NVIDIA GL
+        for (cache_type, path) in self.paths.game_dirs(&game_id) {
+            let used = match (cache_type, &pe_info) {
+                (CacheType::Dxvk, Some(info)) => info.uses_dxvk(),
+                (CacheType::Vkd3d, Some(info)) => info.uses_d3d12(),
+                _ => true,
+            };
+            if !used {
+                log::debug!(
+                    "{} doesn't use {}, skipping its cache",
+                    game_id,
+                    cache_type.name()
+                );
+                continue;
+            }
+            fs::create_dir_all(&path)
+                .with_context(|| format!("Failed to create cache dir: {:?}", path))?;
+            env_vars.push((
+                cache_type.env_var().to_string(),
+                path.to_string_lossy().to_string(),
+            ));
+        }
 
         // Mesa (shared, not per-game)
         env_vars.push((
@@ -214,10 +334,10 @@ impl CacheManager {
     }
 
     /// Get cache info for a specific game
-    pub fn get_game_cache(&self, game_id: &str) -> Result<GameCacheInfo> {
-        let dxvk_path = self.paths.for_game(CacheType::Dxvk, game_id);
-        let vkd3d_path = self.paths.for_game(CacheType::Vkd3d, game_id);
-        let gl_path = self.paths.for_game(CacheType::NvidiaGl, game_id);
+    pub fn get_game_cache(&self, game: &GameId) -> Result<GameCacheInfo> {
+        let dxvk_path = self.paths.find_for_game(CacheType::Dxvk, game);
+        let vkd3d_path = self.paths.find_for_game(CacheType::Vkd3d, game);
+        let gl_path = self.paths.find_for_game(CacheType::NvidiaGl, game);
 
         let dxvk_size = Self::dir_size(&dxvk_path).unwrap_or(0);
         let vkd3d_size = Self::dir_size(&vkd3d_path).unwrap_or(0);
@@ -228,7 +348,7 @@ impl CacheManager {
             .or_else(|| Self::last_modified(&gl_path));
 
         Ok(GameCacheInfo {
-            game_id: game_id.to_string(),
+            game_id: game.to_string(),
             dxvk_size,
             vkd3d_size,
             gl_size,
@@ -261,11 +381,10 @@ impl CacheManager {
     }
 
     /// Clear cache for a specific game
-    pub fn clear_game(&self, game_id: &str) -> Result<u64> {
+    pub fn clear_game(&self, game: &GameId) -> Result<u64> {
         let mut freed = 0u64;
 
-        for cache_type in [CacheType::Dxvk, CacheType::Vkd3d, CacheType::NvidiaGl] {
-            let path = self.paths.for_game(cache_type, game_id);
+        for (_, path) in self.paths.find_game_dirs(game) {
             if path.exists() {
                 freed += Self::dir_size(&path).unwrap_or(0);
                 fs::remove_dir_all(&path)
@@ -385,6 +504,215 @@ impl Default for CacheManager {
     }
 }
 
+/// Handle the `cache` command
+pub fn handle_cache(args: CacheArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
+    match args.command {
+        CacheCommand::Relocate(relocate_args) => handle_relocate(relocate_args, manager, config),
+        CacheCommand::Migrate(migrate_args) => handle_migrate(migrate_args, manager),
+    }
+}
+
+/// Move per-game caches from bare-ID directories to namespaced ones
+///
+/// A bare ID several games share (a Lutris and a Heroic "doom") can't tell
+/// whose cache it is, so it is left in place and reported; launching a game
+/// moves it then, like it does for every other game not migrated here.
+fn handle_migrate(args: CacheMigrateArgs, manager: &ConfigManager) -> Result<()> {
+    let db = GameDatabase::load_or_default(manager.paths())?;
+    let paths = CachePaths::new();
+    let mut by_id: BTreeMap<&str, Vec<&GameId>> = BTreeMap::new();
+    for (game, _) in db.records() {
+        by_id.entry(game.id.as_str()).or_default().push(game);
+    }
+
+    let (mut moved, mut skipped) = (0, 0);
+    for games in by_id.values() {
+        if let [game] = games.as_slice() {
+            for (legacy, path) in paths.legacy_game_dirs(game) {
+                println!("{:?} -> {:?}", legacy, path);
+                if !args.dry_run {
+                    fs::rename(&legacy, &path).with_context(|| {
+                        format!("failed to move cache {:?} to {:?}", legacy, path)
+                    })?;
+                }
+                moved += 1;
+            }
+            continue;
+        }
+        let owners: Vec<String> = games.iter().map(ToString::to_string).collect();
+        let shared: BTreeSet<PathBuf> = games
+            .iter()
+            .flat_map(|game| paths.legacy_game_dirs(game))
+            .map(|(legacy, _)| legacy)
+            .collect();
+        for legacy in shared {
+            println!(
+                "Skipping {:?}: it may belong to {}",
+                legacy,
+                owners.join(" or ")
+            );
+            skipped += 1;
+        }
+    }
+
+    match (moved, args.dry_run) {
+        (0, _) if skipped == 0 => println!("All game caches are namespaced by source"),
+        (_, true) => println!("{} cache directories would be moved", moved),
+        (_, false) => println!("Moved {} cache directories", moved),
+    }
+    Ok(())
+}
+
+/// Move the cache base (or one game's prefix) under `--to`, leaving a symlink behind
+///
+/// Caches go to `<to>/cache`, prefixes to `<to>/prefixes/<source>-<id>`. The old
+/// location becomes a symlink so Proton, Steam and nvproton keep finding them.
+fn handle_relocate(
+    args: CacheRelocateArgs,
+    manager: &ConfigManager,
+    config: &NvConfig,
+) -> Result<()> {
+    let started = Instant::now();
+    // The symlink left behind must not depend on the current directory
+    let target_base = std::path::absolute(&args.to)
+        .with_context(|| format!("invalid target directory {:?}", args.to))?;
+
+    let (source, target) = match args.game {
+        Some(ref game_id) => {
+            let db = GameDatabase::load_or_default(manager.paths())?;
+            let game = db.resolve_game(game_id)?;
+            let prefix = prefix_dir(config, &game)
+                .with_context(|| format!("no Wine prefix known for {}", game.name))?;
+            (
+                prefix,
+                target_base.join("prefixes").join(game.game_id().dir_name()),
+            )
+        }
+        None => (CachePaths::new().base, target_base.join("cache")),
+    };
+
+    if !source.exists() {
+        bail!("{:?} does not exist, nothing to relocate", source);
+    }
+    let size = directory_size(&source);
+    println!(
+        "Moving {:?} ({}) to {:?}...",
+        source,
+        format_bytes(size),
+        target
+    );
+    relocate_dir(&source, &target, &Progress::bytes("Copying", size))?;
+    verify_relocation(&source, &target)?;
+    println!("Done. {:?} now links to {:?}", source, target);
+    notify::finished(
+        &config.notifications,
+        notify::Kind::Cache,
+        started.elapsed(),
+        "Cache relocation finished",
+        &format!("Moved {} to {}", format_bytes(size), target.display()),
+    );
+    Ok(())
+}
+
+/// Directory holding a game's Wine prefix
+fn prefix_dir(config: &NvConfig, game: &DetectedGame) -> Option<PathBuf> {
+    match game.source {
+        // compatdata/<appid> also holds Proton's tracked files and version stamp
+        GameSource::Steam => dxvk::game_prefix(game)?.parent().map(Path::to_path_buf),
+        GameSource::Heroic => disk_usage::heroic_prefix(
+            config.library_paths.heroic.as_ref()?,
+            game.metadata.get("app_name")?,
+        ),
+        _ => None,
+    }
+}
+
+/// Move `source` to `target` and replace it with a symlink to `target`
+///
+/// An already relocated `source` (a symlink) is moved again from where it
+/// points. Moves across filesystems fall back to copy and delete, with the
+/// bytes copied counted on `progress`. A relative `target` is taken from the
+/// current directory.
+pub fn relocate_dir(source: &Path, target: &Path, progress: &Progress) -> Result<()> {
+    let target = std::path::absolute(target)
+        .with_context(|| format!("invalid target directory {:?}", target))?;
+    let target = target.as_path();
+    let current = if source.is_symlink() {
+        fs::read_link(source).with_context(|| format!("failed to read link {:?}", source))?
+    } else {
+        source.to_path_buf()
+    };
+    if current == target {
+        bail!("{:?} already lives at {:?}", source, target);
+    }
+    if target.exists() && fs::read_dir(target)?.next().is_some() {
+        bail!("{:?} already exists and is not empty", target);
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory {:?}", parent))?;
+    }
+    if target.exists() {
+        fs::remove_dir(target)?;
+    }
+
+    if fs::rename(&current, target).is_err() {
+        copy_tree(&current, target, progress)?;
+        fs::remove_dir_all(&current)
+            .with_context(|| format!("failed to remove {:?} after copying", current))?;
+    }
+
+    if source.is_symlink() {
+        fs::remove_file(source)?;
+    }
+    std::os::unix::fs::symlink(target, source)
+        .with_context(|| format!("failed to link {:?} to {:?}", source, target))
+}
+
+/// Copy a directory tree, preserving symlinks (Wine prefixes rely on them)
+fn copy_tree(source: &Path, target: &Path, progress: &Progress) -> Result<()> {
+    for entry in WalkDir::new(source) {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(source)?;
+        let destination = target.join(relative);
+        let file_type = entry.file_type();
+        if file_type.is_symlink() {
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &destination)?;
+        } else if file_type.is_dir() {
+            fs::create_dir_all(&destination)?;
+        } else {
+            let copied = fs::copy(entry.path(), &destination).with_context(|| {
+                format!("failed to copy {:?} to {:?}", entry.path(), destination)
+            })?;
+            progress.inc(copied, relative.display());
+        }
+    }
+    Ok(())
+}
+
+/// Check the old path resolves to the new location and is writable
+fn verify_relocation(source: &Path, target: &Path) -> Result<()> {
+    let resolved = fs::canonicalize(source)
+        .with_context(|| format!("{:?} does not resolve after relocation", source))?;
+    if resolved != fs::canonicalize(target)? {
+        bail!(
+            "{:?} resolves to {:?}, expected {:?}",
+            source,
+            resolved,
+            target
+        );
+    }
+    let probe = source.join(".nvproton-relocate-check");
+    fs::write(&probe, b"ok").with_context(|| format!("{:?} is not writable", source))?;
+    fs::remove_file(&probe)?;
+    Ok(())
+}
+
+/// Total size of the files under a directory (0 if it does not exist)
+pub fn directory_size(path: &Path) -> u64 {
+    CacheManager::dir_size(path).unwrap_or(0)
+}
+
 /// Format bytes as human-readable string
 pub fn format_bytes(bytes: u64) -> String {
     const KB: u64 = 1024;
@@ -420,4 +748,132 @@ mod tests {
         assert_eq!(CacheType::Dxvk.name(), "dxvk");
         assert_eq!(CacheType::Vkd3d.name(), "vkd3d");
     }
+
+    #[test]
+    fn test_game_caches_are_namespaced_by_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = CachePaths::under(dir.path().to_path_buf());
+        let doom = GameId::new(GameSource::Lutris, "doom");
+        let namespaced = dir.path().join("dxvk").join("lutris-doom");
+        assert_eq!(paths.for_game(CacheType::Dxvk, &doom), namespaced);
+        assert_eq!(paths.find_for_game(CacheType::Dxvk, &doom), namespaced);
+
+        // A cache from before the namespacing is read in place until migrated
+        let legacy = dir.path().join("dxvk").join("doom");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("doom.dxvk-cache"), "state").unwrap();
+        assert_eq!(paths.find_for_game(CacheType::Dxvk, &doom), legacy);
+        assert_eq!(
+            paths.migrate_game(&doom).unwrap(),
+            std::slice::from_ref(&namespaced)
+        );
+        assert!(!legacy.exists());
+        assert!(namespaced.join("doom.dxvk-cache").is_file());
+        assert_eq!(paths.find_for_game(CacheType::Dxvk, &doom), namespaced);
+        assert!(paths.migrate_game(&doom).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_setup_leaves_caches_of_shared_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let caches = CacheManager {
+            paths: CachePaths::under(dir.path().to_path_buf()),
+        };
+        let game = |source| DetectedGame {
+            id: "1145360".into(),
+            name: "Hades".into(),
+            source,
+            install_dir: PathBuf::from("/games/Hades"),
+            executable: None,
+            fingerprint: None,
+            metadata: Default::default(),
+        };
+        let (steam, lutris) = (game(GameSource::Steam), game(GameSource::Lutris));
+        let legacy = dir.path().join("dxvk").join("1145360");
+        fs::create_dir_all(&legacy).unwrap();
+
+        // The bare-ID cache may be Lutris's as well, so it stays
+        let mut db = GameDatabase::default();
+        db.merge_detected(&[steam.clone(), lutris], 0);
+        caches.setup_for_game(&steam, &db).unwrap();
+        assert!(legacy.is_dir());
+        assert!(dir.path().join("dxvk").join("steam-1145360").is_dir());
+
+        fs::remove_dir(dir.path().join("dxvk").join("steam-1145360")).unwrap();
+        let mut db = GameDatabase::default();
+        db.merge_detected(std::slice::from_ref(&steam), 0);
+        caches.setup_for_game(&steam, &db).unwrap();
+        assert!(!legacy.exists());
+    }
+
+    #[test]
+    fn test_setup_and_clear_use_the_same_game_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let caches = CacheManager {
+            paths: CachePaths::under(dir.path().to_path_buf()),
+        };
+        let celeste = DetectedGame {
+            id: "celeste".into(),
+            name: "Celeste".into(),
+            source: GameSource::Lutris,
+            install_dir: PathBuf::from("/games/Celeste"),
+            executable: None,
+            fingerprint: None,
+            metadata: Default::default(),
+        };
+        let game_id = celeste.game_id();
+        let env = caches
+            .setup_for_game(&celeste, &GameDatabase::default())
+            .unwrap();
+
+        // One absolute directory per isolated cache type, plus the shared Mesa cache
+        let game_dirs = caches.paths.game_dirs(&game_id);
+        assert_eq!(env.len(), GAME_CACHE_TYPES.len() + 1);
+        for ((key, value), (cache_type, path)) in env.iter().zip(&game_dirs) {
+            assert_eq!(key, cache_type.env_var());
+            assert_eq!(Path::new(value), path);
+            assert!(path.is_absolute() && path.is_dir());
+        }
+        assert_eq!(env.last().unwrap().0, CacheType::Mesa.env_var());
+
+        fs::write(game_dirs[0].1.join("celeste.dxvk-cache"), [0u8; 64]).unwrap();
+        assert_eq!(caches.clear_game(&game_id).unwrap(), 64);
+        assert!(game_dirs.iter().all(|(_, path)| !path.exists()));
+    }
+
+    #[test]
+    fn test_relocate_dir_leaves_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("cache");
+        fs::create_dir_all(source.join("dxvk")).unwrap();
+        fs::write(source.join("dxvk").join("game.dxvk-cache"), "state").unwrap();
+
+        let first = dir.path().join("nvme").join("cache");
+        relocate_dir(&source, &first, &Progress::hidden()).unwrap();
+        assert!(source.is_symlink());
+        assert_eq!(
+            fs::read_to_string(source.join("dxvk").join("game.dxvk-cache")).unwrap(),
+            "state"
+        );
+        verify_relocation(&source, &first).unwrap();
+
+        // Relocating again moves the real directory and repoints the link
+        let second = dir.path().join("hdd").join("cache");
+        relocate_dir(&source, &second, &Progress::hidden()).unwrap();
+        assert!(!first.exists());
+        assert_eq!(fs::read_link(&source).unwrap(), second);
+
+        // A relative target still leaves an absolute, working link
+        let local = tempfile::tempdir_in(".").unwrap();
+        let cwd = std::env::current_dir().unwrap();
+        let relative = local.path().strip_prefix(&cwd).unwrap().join("cache");
+        relocate_dir(&source, &relative, &Progress::hidden()).unwrap();
+        assert!(fs::read_link(&source).unwrap().is_absolute());
+        verify_relocation(&source, &relative).unwrap();
+
+        // Copies across filesystems count the bytes copied
+        let progress = Progress::bytes("Copying", 5);
+        copy_tree(&relative, &dir.path().join("copy"), &progress).unwrap();
+        assert_eq!(progress.position(), (5, 5));
+    }
 }
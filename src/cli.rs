@@ -9,6 +9,22 @@ use clap::{Args, Parser, Subcommand, ValueEnum};
     after_help = "Examples:\n  nvproton run 1245620              # Run Elden Ring by Steam AppID\n  nvproton run --name \"Elden Ring\"  # Run by game name\n  nvproton prepare 1245620          # Pre-warm shaders before launch\n  nvproton games list               # List detected games"
 )]
 pub struct Cli {
+    /// Config context to use (default: NVPROTON_CONTEXT, then the context matching this hostname)
+    #[arg(long, global = true, value_name = "NAME")]
+    pub context: Option<String>,
+
+    /// Keep config, state and caches in nvproton-data next to the binary
+    #[arg(long, global = true)]
+    pub portable: bool,
+
+    /// Print errors as JSON objects on stderr (exit codes are the same either way)
+    #[arg(long, global = true)]
+    pub json_errors: bool,
+
+    /// Color text output (auto: on a terminal, unless NO_COLOR is set)
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -35,11 +51,46 @@ pub enum Commands {
     Gamemode(GamemodeArgs),
     /// Manage nvproton configuration
     Config(ConfigArgs),
+    /// Manage command aliases (e.g. 'nvproton er' for 'nvproton run 1245620')
+    Alias(AliasArgs),
+    /// Revert the last change to the game database or profile bindings
+    Undo(UndoArgs),
+    /// Show system status and driver readiness
+    #[command(visible_alias = "doctor")]
+    Status(StatusArgs),
+    /// Analyze Proton logs for common failures
+    Logs(LogsArgs),
+    /// Show crash triage reports for a game
+    Report(ReportArgs),
+    /// Browse per-game screenshots and replays
+    Media(MediaArgs),
+    /// Back up and restore save games
+    Saves(SavesArgs),
+    /// List games running through nvproton
+    Ps(PsArgs),
+    /// Stop a game running through nvproton
+    Stop(StopArgs),
+    /// Inventory Wine / Proton runtimes and the games using them
+    Runtimes(RuntimesArgs),
+    /// Run the nvproton background daemon
+    Daemon(DaemonArgs),
+    /// Wrap a launcher command (Steam launch options: nvproton wrap -- %command%)
+    Wrap(WrapArgs),
+    /// Per-game DXVK / vkd3d-proton version overrides
+    Dxvk(DxvkArgs),
+    /// NVAPI / DLSS setup for Proton games
+    Nvapi(NvapiArgs),
+    /// Manage shader caches and prefixes
+    Cache(CacheArgs),
+    /// Update nvproton to the latest GitHub release
+    SelfUpdate(SelfUpdateArgs),
+    /// Install and update the nvshader/nvlatency/nvsync libraries
+    Libs(LibsArgs),
 }
 
 #[derive(Debug, Args)]
 pub struct RunArgs {
-    /// Steam AppID or game identifier
+    /// Steam AppID or game identifier (default: detected when run from Steam)
     #[arg(value_name = "GAME_ID")]
     pub game_id: Option<String>,
 
@@ -47,6 +98,89 @@ pub struct RunArgs {
     #[arg(long)]
     pub name: Option<String>,
 
+    /// Run the most recently launched game again, with its last profile
+    #[arg(long, conflicts_with_all = ["game_id", "name", "recent"])]
+    pub last: bool,
+
+    /// Pick one of the five most recently played games
+    #[arg(long, conflicts_with_all = ["game_id", "name"])]
+    pub recent: bool,
+
+    #[command(flatten)]
+    pub launch: LaunchOptions,
+
+    /// Launch a named target instead of the game's executable (see `games targets`)
+    #[arg(long, value_name = "NAME")]
+    pub target: Option<String>,
+
+    /// Skip shader pre-warming
+    #[arg(long)]
+    pub no_prewarm: bool,
+
+    /// Start Heroic, Lutris and manually added games with umu-run, for protonfixes
+    #[arg(long)]
+    pub umu: bool,
+
+    /// Wait for a pending Steam update to finish before launching
+    #[arg(long)]
+    pub wait_for_update: bool,
+
+    /// Watch the kernel log for NVIDIA Xid errors while the game runs
+    #[arg(long)]
+    pub monitor: bool,
+
+    /// Sample GPU temperature, power, clocks and VRAM (1 Hz) during the run
+    #[arg(long)]
+    pub telemetry: bool,
+
+    /// The arguments after -- are the launcher's command to wrap (Steam launch options: `run --wrapper -- %command%`)
+    #[arg(long, requires = "game_args")]
+    pub wrapper: bool,
+
+    /// Stay the game's parent process when wrapping a Steam launch (session history, crash reports)
+    #[arg(long)]
+    pub supervise: bool,
+
+    /// Record a reproducibility manifest (fingerprint, versions, env, profile, caches)
+    #[arg(long)]
+    pub manifest: bool,
+
+    /// Abort instead of warning when VRAM or RAM headroom is short or two layers set a variable differently
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Launch even if the game is already running through nvproton
+    #[arg(long)]
+    pub force: bool,
+
+    /// Seconds Steam or Heroic get to start the game before the launch counts as failed
+    #[arg(long, value_name = "SECS", default_value = "120")]
+    pub launch_timeout: u64,
+
+    /// Launch once more when the game dies right after starting
+    #[arg(long, value_enum, default_value_t = RetryPolicy::Off)]
+    pub retry: RetryPolicy,
+
+    /// Exits within this many seconds count as dying right after starting
+    #[arg(long, value_name = "SECS", default_value = "10")]
+    pub retry_within: u64,
+
+    /// Dry run - show what would be done without launching
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Write a standalone POSIX launch script instead of launching
+    #[arg(long, value_name = "PATH")]
+    pub emit_script: Option<String>,
+
+    /// Additional arguments to pass to the game
+    #[arg(last = true)]
+    pub game_args: Vec<String>,
+}
+
+/// Launch tunables shared by `run` and `wrap`
+#[derive(Debug, Args)]
+pub struct LaunchOptions {
     /// Profile to apply
     #[arg(short, long)]
     pub profile: Option<String>,
@@ -55,25 +189,156 @@ pub struct RunArgs {
     #[arg(long)]
     pub reflex: bool,
 
-    /// Target frame rate (0 = unlimited)
-    #[arg(long, default_value = "0")]
-    pub fps: u32,
+    /// Disable Reflex when the config or profile enables it
+    #[arg(long, conflicts_with = "reflex")]
+    pub no_reflex: bool,
+
+    /// Target frame rate (0 = unlimited; default from run_defaults.fps)
+    #[arg(long)]
+    pub fps: Option<u32>,
 
     /// Enable VRR (G-Sync/FreeSync)
     #[arg(long)]
     pub vrr: bool,
 
-    /// Skip shader pre-warming
+    /// Disable VRR when the config or profile enables it
+    #[arg(long, conflicts_with = "vrr")]
+    pub no_vrr: bool,
+
+    /// Enable the MangoHud overlay (MANGOHUD=1)
     #[arg(long)]
-    pub no_prewarm: bool,
+    pub mangohud: bool,
 
-    /// Dry run - show what would be done without launching
+    /// Disable MangoHud when the config or profile enables it
+    #[arg(long, conflicts_with = "mangohud")]
+    pub no_mangohud: bool,
+
+    /// VK_EXT_descriptor_heap mode for DX12 games
+    #[arg(long, value_enum, default_value_t = DescriptorHeapMode::Auto)]
+    pub descriptor_heap: DescriptorHeapMode,
+
+    /// Enable Proton logging (PROTON_LOG=1)
     #[arg(long)]
-    pub dry_run: bool,
+    pub proton_log: bool,
 
-    /// Additional arguments to pass to the game
-    #[arg(last = true)]
-    pub game_args: Vec<String>,
+    /// Expose NVAPI to the game (PROTON_ENABLE_NVAPI=1)
+    #[arg(long)]
+    pub proton_enable_nvapi: bool,
+
+    /// Report the NVIDIA GPU as AMD (PROTON_HIDE_NVIDIA_GPU=1)
+    #[arg(long)]
+    pub proton_hide_nvidia_gpu: bool,
+
+    /// Disable esync (PROTON_NO_ESYNC=1)
+    #[arg(long)]
+    pub proton_no_esync: bool,
+
+    /// Disable fsync (PROTON_NO_FSYNC=1)
+    #[arg(long)]
+    pub proton_no_fsync: bool,
+
+    /// Wine synchronization: esync, fsync, ntsync, or auto for the fastest the kernel supports
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub sync: Option<SyncMode>,
+
+    /// Use Wine's Wayland driver instead of Xwayland when the Proton/Wine build ships it
+    #[arg(long)]
+    pub wine_wayland: bool,
+
+    /// DXVK HUD: a preset (fps, frametimes, stutter, gpu-mem, full) or DXVK_HUD items
+    #[arg(long, value_name = "PRESET|ITEMS", value_parser = crate::dxvk_hud::hud_value)]
+    pub dxvk_hud: Option<String>,
+
+    /// DXVK log level (none, error, warn, info, debug, trace)
+    #[arg(long, value_name = "LEVEL", value_parser = crate::dxvk_hud::parse_log_level)]
+    pub dxvk_log_level: Option<String>,
+
+    /// Enable DLSS Frame Generation (NVAPI and Reflex for games shipping nvngx_dlssg.dll)
+    #[arg(long)]
+    pub dlss_fg: bool,
+
+    /// Enable NVIDIA Smooth Motion (driver frame interpolation, 575+ on RTX 40 series or newer)
+    #[arg(long)]
+    pub smooth_motion: bool,
+
+    /// Keep an instant replay with gpu-screen-recorder while the game runs (profile section `replay`)
+    #[arg(long)]
+    pub replay: bool,
+
+    /// Keep the default shader cache locations instead of per-game directories
+    #[arg(long)]
+    pub no_cache_isolation: bool,
+
+    /// Resolve the profile and shader caches again instead of reusing the cached launch plan
+    #[arg(long)]
+    pub no_plan_cache: bool,
+
+    /// Layer the streaming profile fragment even if OBS isn't detected
+    #[arg(long)]
+    pub streaming: bool,
+
+    /// SDL controller mapping string or gamecontrollerdb file
+    #[arg(long, value_name = "MAPPING|FILE")]
+    pub controller_config: Option<String>,
+
+    /// Show or hide Steam's virtual gamepad (off exposes the physical controllers)
+    #[arg(long, value_enum)]
+    pub steam_input: Option<SteamInputMode>,
+}
+
+/// Wrap a command composed by a launcher (Steam launch options: `nvproton wrap -- %command%`)
+#[derive(Debug, Args)]
+pub struct WrapArgs {
+    /// Game identifier (default: detected from SteamAppId/SteamGameId)
+    #[arg(value_name = "GAME_ID")]
+    pub game_id: Option<String>,
+
+    #[command(flatten)]
+    pub launch: LaunchOptions,
+
+    /// The launcher's command line (%command%)
+    #[arg(last = true, required = true)]
+    pub command: Vec<String>,
+}
+
+/// Steam Input handling for a launch
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SteamInputMode {
+    On,
+    Off,
+}
+
+/// VK_EXT_descriptor_heap mode for vkd3d-proton
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DescriptorHeapMode {
+    /// Enable when the driver supports it (595+)
+    Auto,
+    /// Always enable
+    On,
+    /// Never enable
+    Off,
+}
+
+/// Wine synchronization mechanism for a launch
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SyncMode {
+    Auto,
+    Esync,
+    Fsync,
+    Ntsync,
+}
+
+/// How `run --retry` launches a game again after it died right away
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum RetryPolicy {
+    /// Don't retry
+    Off,
+    /// Retry without shader pre-warming
+    NoPrewarm,
+    /// Also clear the game's shader caches first (stale after driver updates)
+    CleanCache,
+    /// Also drop Reflex, DLSS-FG, Smooth Motion and descriptor heaps given on the command line
+    Safe,
 }
 
 #[derive(Debug, Args)]
@@ -86,6 +351,26 @@ pub struct PrepareArgs {
     #[arg(long)]
     pub name: Option<String>,
 
+    /// Prepare every game in the database
+    #[arg(long, conflicts_with_all = ["game_id", "name", "profile"])]
+    pub all: bool,
+
+    /// Prepare these games (comma-separated IDs)
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["game_id", "name", "profile", "all"])]
+    pub ids: Vec<String>,
+
+    /// With --all, only games from this source (steam, heroic, lutris, legendary)
+    #[arg(long, value_parser = crate::detection::GameSource::parse_arg, requires = "all")]
+    pub source: Option<crate::detection::GameSource>,
+
+    /// With --all, only games with all of these tags (comma-separated)
+    #[arg(long = "tag", value_name = "TAG", value_delimiter = ',', value_parser = crate::query::parse_tag, requires = "all")]
+    pub tags: Vec<String>,
+
+    /// With --all or --ids, number of games prepared concurrently (default: run_defaults.jobs, else CPU count)
+    #[arg(long)]
+    pub jobs: Option<usize>,
+
     /// Profile to apply
     #[arg(short, long)]
     pub profile: Option<String>,
@@ -97,6 +382,10 @@ pub struct PrepareArgs {
     /// Show progress during shader compilation
     #[arg(long, default_value = "true")]
     pub progress: bool,
+
+    /// List the games that would be prepared without preparing them
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 #[derive(Debug, Args)]
@@ -117,23 +406,148 @@ pub enum GamesCommand {
     SetProfile(GamesSetProfileArgs),
     /// Show game launch command
     Info(GamesInfoArgs),
+    /// Show recorded play sessions
+    Session(GamesSessionArgs),
+    /// Choose the launch source for a game installed from several sources
+    Prefer(GamesPreferArgs),
+    /// Show disk usage per game (install, prefix and shader caches)
+    Du(GamesDuArgs),
+    /// Compare the launch manifests of two sessions
+    DiffSession(GamesDiffSessionArgs),
+    /// Show or set the wrapper commands of a game
+    Wrappers(GamesWrappersArgs),
+    /// Show, add or remove the tags of a game
+    Tag(GamesTagArgs),
+    /// Override the executable detection picked for a game
+    SetExe(GamesSetExeArgs),
+    /// Show or set the arguments and working directory of a game
+    SetArgs(GamesSetArgsArgs),
+    /// Show, add or remove the named launch targets of a game
+    Targets(GamesTargetsArgs),
+    /// Write import files for other launchers and frontends
+    Export(GamesExportArgs),
 }
 
 #[derive(Debug, Args)]
 pub struct GamesListArgs {
-    /// Filter by source (steam, heroic, lutris)
+    /// Filter by source (steam, heroic, lutris, legendary)
+    #[arg(long, value_parser = crate::detection::GameSource::parse_arg)]
+    pub source: Option<crate::detection::GameSource>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// List every source's entry instead of one per game
+    #[arg(long)]
+    pub all: bool,
+
+    /// Only games using this profile ("none" for games without one)
+    #[arg(long, value_parser = crate::query::ProfileFilter::parse)]
+    pub profile: Option<crate::query::ProfileFilter>,
+
+    /// Only games whose executable is missing
     #[arg(long)]
-    pub source: Option<String>,
+    pub missing_exe: bool,
+
+    /// Only games whose install is larger than this (e.g. 50G)
+    #[arg(long, value_name = "SIZE", value_parser = crate::query::parse_size)]
+    pub larger_than: Option<u64>,
+
+    /// Only games not played within this period (e.g. 90d)
+    #[arg(long, value_name = "AGE", value_parser = crate::query::parse_age)]
+    pub not_played_since: Option<u64>,
+
+    /// Only games with all of these tags (comma-separated)
+    #[arg(long = "tag", value_name = "TAG", value_delimiter = ',', value_parser = crate::query::parse_tag)]
+    pub tags: Vec<String>,
+
+    /// Fields to emit with --format json or yaml, in this order (comma-separated)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub fields: Vec<GameField>,
+}
+
+/// Field of a game in `games list` output
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum GameField {
+    Id,
+    Name,
+    Source,
+    InstallDir,
+    Executable,
+    Fingerprint,
+    Metadata,
+}
+
+#[derive(Debug, Args)]
+pub struct GamesDuArgs {
+    /// Column to sort by (largest first, except name)
+    #[arg(long, value_enum, default_value_t = DuSort::Total)]
+    pub sort: DuSort,
+
+    /// Re-measure every directory instead of reusing cached sizes
+    #[arg(long)]
+    pub refresh: bool,
 
     /// Output format
     #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
     pub format: OutputFormat,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DuSort {
+    Total,
+    Install,
+    Prefix,
+    Cache,
+    Name,
+}
+
+#[derive(Debug, Args)]
+pub struct GamesPreferArgs {
+    /// Game ID from any of its sources
+    pub game_id: String,
+
+    /// Source to launch from (steam, heroic, lutris, legendary)
+    #[arg(long, value_parser = crate::detection::GameSource::parse_arg)]
+    pub source: crate::detection::GameSource,
+}
+
 #[derive(Debug, Args)]
 pub struct GamesShowArgs {
     /// Steam AppID or game identifier
     pub game_id: String,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct GamesExportArgs {
+    /// Frontend to write import files for
+    #[arg(long, value_enum)]
+    pub format: ExportFormat,
+
+    /// Directory the files are written to
+    #[arg(long, short, value_name = "DIR")]
+    pub output: String,
+
+    /// Only games with all of these tags (comma-separated)
+    #[arg(long = "tag", value_name = "TAG", value_delimiter = ',', value_parser = crate::query::parse_tag)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// Sideloaded apps for Heroic Games Launcher
+    Heroic,
+    /// Lutris installer scripts
+    Lutris,
+    /// Pegasus Frontend metadata
+    Pegasus,
+    /// EmulationStation Desktop Edition gamelist
+    EsDe,
 }
 
 #[derive(Debug, Args)]
@@ -145,15 +559,118 @@ pub struct GamesScanArgs {
     /// Generate fingerprints for executables
     #[arg(long)]
     pub fingerprint: bool,
+
+    /// Only update games that already have all of these tags (comma-separated)
+    #[arg(long = "tag", value_name = "TAG", value_delimiter = ',', value_parser = crate::query::parse_tag)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Args)]
 pub struct GamesSetProfileArgs {
+    /// Game ID, or a selection such as 'source:steam AND tag:competitive' (omit with --tag)
+    #[arg(value_name = "GAME_ID|SELECTION")]
+    pub target: Option<String>,
+
+    /// Profile name to assign
+    pub profile: Option<String>,
+
+    /// Assign the profile to every game with all of these tags (comma-separated)
+    #[arg(long = "tag", value_name = "TAG", value_delimiter = ',', value_parser = crate::query::parse_tag)]
+    pub tags: Vec<String>,
+
+    /// Show the affected games without changing the database
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct GamesTagArgs {
     /// Steam AppID or game identifier
     pub game_id: String,
 
-    /// Profile name to assign
-    pub profile: String,
+    /// Tags to add (comma-separated, e.g. coop,vr,backlog)
+    #[arg(value_delimiter = ',', value_parser = crate::query::parse_tag)]
+    pub tags: Vec<String>,
+
+    /// Remove the given tags instead of adding them
+    #[arg(long, requires = "tags")]
+    pub remove: bool,
+
+    /// Remove all tags of the game (before adding the given ones)
+    #[arg(long, conflicts_with = "remove")]
+    pub clear: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct GamesSetExeArgs {
+    /// Steam AppID or game identifier
+    pub game_id: String,
+
+    /// Executable to launch (relative paths are looked up in the install directory first)
+    #[arg(required_unless_present = "clear")]
+    pub path: Option<String>,
+
+    /// Go back to the executable detection picks
+    #[arg(long, conflicts_with = "path")]
+    pub clear: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct GamesSetArgsArgs {
+    /// Steam AppID or game identifier
+    pub game_id: String,
+
+    /// Replace the game's arguments; placeholders like ${game.dir} are expanded at launch
+    #[arg(last = true)]
+    pub args: Vec<String>,
+
+    /// Working directory for the game ("" to unset), placeholders allowed
+    #[arg(long, value_name = "DIR")]
+    pub cwd: Option<String>,
+
+    /// Remove the game's arguments and working directory
+    #[arg(long, conflicts_with_all = ["args", "cwd"])]
+    pub clear: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct GamesTargetsArgs {
+    /// Steam AppID or game identifier
+    pub game_id: String,
+
+    /// Add or replace a target (relative paths are looked up in the install directory first)
+    #[arg(long, num_args = 2, value_names = ["NAME", "PATH"], conflicts_with = "remove")]
+    pub add: Vec<String>,
+
+    /// Arguments of the target given with --add (whitespace-separated)
+    #[arg(long, requires = "add", allow_hyphen_values = true)]
+    pub args: Option<String>,
+
+    /// Description of the target given with --add
+    #[arg(long, requires = "add")]
+    pub description: Option<String>,
+
+    /// Remove a target added with --add
+    #[arg(long, value_name = "NAME")]
+    pub remove: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct GamesWrappersArgs {
+    /// Steam AppID or game identifier
+    pub game_id: String,
+
+    /// Replace the game's wrappers, outermost first (repeatable, e.g. --set gamemoderun)
+    #[arg(long = "set", value_name = "COMMAND", conflicts_with = "clear")]
+    pub set: Vec<String>,
+
+    /// Priority of the wrappers given with --set (lower runs outermost)
+    #[arg(long, requires = "set")]
+    pub priority: Option<i32>,
+
+    /// Remove all wrappers of the game
+    #[arg(long)]
+    pub clear: bool,
 }
 
 #[derive(Debug, Args)]
@@ -161,13 +678,43 @@ pub struct GamesInfoArgs {
     /// Steam AppID or game identifier
     pub game_id: String,
 
-    /// Show full launch command
+    /// Show the launch plan `run` would use, with a standalone command line
     #[arg(long)]
     pub command: bool,
+
+    #[command(flatten)]
+    pub launch: LaunchOptions,
+}
+
+#[derive(Debug, Args)]
+pub struct GamesSessionArgs {
+    /// Steam AppID or game identifier
+    pub game_id: String,
+
+    /// Show details and telemetry for the most recent session
+    #[arg(long)]
+    pub last: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct GamesDiffSessionArgs {
+    /// Steam AppID or game identifier
+    pub game_id: String,
+
+    /// First session: "last", an index from `games session` (1 = most recent) or a start timestamp
+    pub a: String,
+
+    /// Second session
+    pub b: String,
 }
 
 #[derive(Debug, Args)]
 pub struct DetectArgs {
+    /// Detect in a copied layout: DIR stands in for the home directory, and
+    /// absolute paths launchers record are looked up under DIR
+    #[arg(long, global = true, value_name = "DIR")]
+    pub root: Option<String>,
+
     #[command(subcommand)]
     pub command: DetectCommand,
 }
@@ -177,6 +724,7 @@ pub enum DetectCommand {
     Steam(DetectSourceArgs),
     Heroic(DetectSourceArgs),
     Lutris(DetectSourceArgs),
+    Legendary(DetectSourceArgs),
     All(DetectAllArgs),
 }
 
@@ -188,6 +736,10 @@ pub struct DetectSourceArgs {
     pub update_db: bool,
     #[arg(long)]
     pub fingerprint: bool,
+    /// Explain on stderr every entry considered, why entries were skipped and
+    /// how executables were picked
+    #[arg(long)]
+    pub explain: bool,
 }
 
 #[derive(Debug, Args)]
@@ -198,6 +750,13 @@ pub struct DetectAllArgs {
     pub update_db: bool,
     #[arg(long)]
     pub fingerprint: bool,
+    /// Only show games that are not in the database yet
+    #[arg(long)]
+    pub diff: bool,
+    /// Explain on stderr every entry considered, why entries were skipped and
+    /// how executables were picked
+    #[arg(long)]
+    pub explain: bool,
 }
 
 #[derive(Clone, Debug, ValueEnum)]
@@ -207,6 +766,29 @@ pub enum OutputFormat {
     Yaml,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+/// On-disk format of the config, profiles and game database
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum FileFormat {
+    Yaml,
+    Toml,
+}
+
+/// Format of an exported profile
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ProfileFormat {
+    #[value(alias = "text")]
+    Yaml,
+    Json,
+    Toml,
+}
+
 #[derive(Debug, Args)]
 pub struct ProfileArgs {
     #[command(subcommand)]
@@ -254,8 +836,8 @@ pub struct ProfileImportArgs {
 #[derive(Debug, Args)]
 pub struct ProfileExportArgs {
     pub name: String,
-    #[arg(long, value_enum, default_value_t = OutputFormat::Yaml)]
-    pub format: OutputFormat,
+    #[arg(long, value_enum, default_value_t = ProfileFormat::Yaml)]
+    pub format: ProfileFormat,
     #[arg(long)]
     pub path: Option<String>,
 }
@@ -271,6 +853,80 @@ pub enum ConfigCommand {
     Show,
     Paths,
     Reset,
+    /// Set a value by dotted key (e.g., library_paths.steam ~/.steam/steam)
+    Set {
+        key: String,
+        value: String,
+    },
+    /// Rewrite the config file in another format (the old file is kept as .bak)
+    Convert {
+        #[arg(long, value_enum)]
+        to: FileFormat,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct AliasArgs {
+    #[command(subcommand)]
+    pub command: AliasCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AliasCommand {
+    /// List configured aliases
+    List,
+    /// Define an alias (e.g., alias add er run 1245620 --profile competitive)
+    Add {
+        name: String,
+        /// Command line the alias expands to, without 'nvproton'
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// Remove an alias
+    Remove { name: String },
+}
+
+#[derive(Debug, Args)]
+pub struct UndoArgs {
+    /// List the recorded operations, newest first
+    #[arg(long, conflicts_with_all = ["force", "dry_run"])]
+    pub list: bool,
+
+    /// Undo even if the files changed again afterwards
+    #[arg(long)]
+    pub force: bool,
+
+    /// Show what would be undone without changing anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct StatusArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Include driver branch and installation paths
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Exit 0 if DX12 ready, 1 otherwise
+    #[arg(long)]
+    pub check: bool,
+
+    /// Offer fixes for the problems found (cache directories, launch environment,
+    /// Steam launch options, companion libraries, kernel modesetting)
+    #[arg(long)]
+    pub fix: bool,
+
+    /// Apply fixes without asking
+    #[arg(short, long, requires = "fix")]
+    pub yes: bool,
+
+    /// Write a sysctl drop-in raising vm.max_map_count (asks for sudo)
+    #[arg(long)]
+    pub apply_sysctl: bool,
 }
 
 fn parse_kv_pair(s: &str) -> Result<(String, String), String> {
@@ -326,7 +982,7 @@ pub struct LaunchOptionsArgs {
     #[arg(long, default_value = "0")]
     pub fps: u32,
 
-    /// Use dedicated shader cache path
+    /// Use nvproton's per-game shader cache directories
     #[arg(long)]
     pub shader_cache: bool,
 
@@ -491,3 +1147,311 @@ pub enum GamemodeCommand {
     /// Show launch command prefix for GameMode
     Prefix,
 }
+
+// ============================================================================
+// Log Commands
+// ============================================================================
+
+#[derive(Debug, Args)]
+pub struct LogsArgs {
+    #[command(subcommand)]
+    pub command: LogsCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum LogsCommand {
+    /// Analyze an existing Proton log (steam-<appid>.log)
+    Analyze {
+        /// Path to the log file
+        path: String,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+}
+
+// ============================================================================
+// Report Commands
+// ============================================================================
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct ReportArgs {
+    #[command(subcommand)]
+    pub command: Option<ReportCommand>,
+    /// Game ID the report belongs to
+    #[arg(required = true)]
+    pub game_id: Option<String>,
+    /// List all stored reports instead of showing the latest
+    #[arg(long)]
+    pub list: bool,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+    /// Write the report to a file (for attaching to bug reports)
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ReportCommand {
+    /// Submit the latest crash report (home paths scrubbed) to the reporting endpoint
+    Share(ReportShareArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ReportShareArgs {
+    /// Game ID the report belongs to
+    pub game_id: String,
+}
+
+#[derive(Debug, Args)]
+pub struct MediaArgs {
+    #[command(subcommand)]
+    pub command: MediaCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MediaCommand {
+    /// List a game's captures, grouped by session
+    List(MediaListArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct MediaListArgs {
+    /// Game ID
+    pub game_id: String,
+}
+
+#[derive(Debug, Args)]
+pub struct PsArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct StopArgs {
+    /// Game ID
+    pub game_id: String,
+
+    /// Seconds to wait for the game to exit after SIGTERM
+    #[arg(long, value_name = "SECS", default_value = "10")]
+    pub timeout: u64,
+}
+
+#[derive(Debug, Args)]
+pub struct SavesArgs {
+    #[command(subcommand)]
+    pub command: SavesCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SavesCommand {
+    /// Back up a game's save files
+    Backup(SavesGameArgs),
+    /// Restore a backup (the current saves are backed up first)
+    Restore(SavesRestoreArgs),
+    /// Show where a game's saves are and its backups
+    List(SavesGameArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct SavesGameArgs {
+    /// Game ID
+    pub game_id: String,
+}
+
+#[derive(Debug, Args)]
+pub struct SavesRestoreArgs {
+    /// Game ID
+    pub game_id: String,
+
+    /// Timestamp of the backup to restore, as shown by 'saves list' (default: latest)
+    #[arg(long)]
+    pub at: Option<u64>,
+}
+
+#[derive(Debug, Args)]
+pub struct RuntimesArgs {
+    #[command(subcommand)]
+    pub command: RuntimesCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RuntimesCommand {
+    /// List Wine / Proton builds from Steam, Lutris, Heroic and the system
+    List(RuntimesListArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct RuntimesListArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+// ============================================================================
+// Daemon Commands
+// ============================================================================
+
+#[derive(Debug, Args)]
+pub struct DaemonArgs {
+    /// Serve Prometheus metrics on this address (overrides daemon.metrics_addr)
+    #[arg(long, value_name = "ADDR")]
+    pub metrics_addr: Option<String>,
+
+    /// Expose the org.ghostkellz.nvproton D-Bus service (overrides daemon.dbus)
+    #[arg(long)]
+    pub dbus: bool,
+
+    /// Re-warm shader caches after Proton / Wine version changes (overrides daemon.watch_compat_tools)
+    #[arg(long)]
+    pub watch_compat_tools: bool,
+}
+
+// ============================================================================
+// DXVK / vkd3d-proton Overrides
+// ============================================================================
+
+#[derive(Debug, Args)]
+pub struct DxvkArgs {
+    #[command(subcommand)]
+    pub command: DxvkCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DxvkCommand {
+    /// Download a release and install its DLLs into a game's prefix
+    Install(DxvkInstallArgs),
+    /// Restore the prefix DLLs replaced by an override
+    Remove(DxvkRemoveArgs),
+    /// List games with a graphics override
+    List,
+}
+
+#[derive(Debug, Args)]
+pub struct DxvkInstallArgs {
+    /// Release version (e.g., 2.5.3)
+    pub version: String,
+    /// Game ID to install the override for
+    #[arg(long)]
+    pub game: String,
+    /// Install vkd3d-proton instead of DXVK
+    #[arg(long)]
+    pub vkd3d: bool,
+    /// Wine prefix (default: the game's Steam compatdata prefix)
+    #[arg(long)]
+    pub prefix: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct DxvkRemoveArgs {
+    /// Game ID to remove the override from
+    pub game_id: String,
+    /// Remove only the vkd3d-proton override
+    #[arg(long)]
+    pub vkd3d: bool,
+}
+
+// ============================================================================
+// NVAPI Commands
+// ============================================================================
+
+#[derive(Debug, Args)]
+pub struct NvapiArgs {
+    #[command(subcommand)]
+    pub command: NvapiCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum NvapiCommand {
+    /// Show dxvk-nvapi, nvngx and DLSS availability for a game
+    Status(NvapiGameArgs),
+    /// Enable NVAPI for a game and expose the driver's nvngx in its prefix
+    Enable(NvapiGameArgs),
+    /// Stop setting PROTON_ENABLE_NVAPI for a game
+    Disable(NvapiGameArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct NvapiGameArgs {
+    /// Game ID
+    pub game_id: String,
+    /// Wine prefix (default: the game's Steam compatdata prefix)
+    #[arg(long)]
+    pub prefix: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    pub command: CacheCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CacheCommand {
+    /// Move caches (or a game's prefix) to another disk, leaving a symlink behind
+    Relocate(CacheRelocateArgs),
+    /// Move per-game caches named by bare ID to directories named by source and ID
+    Migrate(CacheMigrateArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct CacheRelocateArgs {
+    /// New base directory (caches go to <DIR>/cache, prefixes to <DIR>/prefixes/<source>-<id>)
+    #[arg(long, value_name = "DIR")]
+    pub to: String,
+    /// Move this game's Wine prefix instead of the shader caches
+    #[arg(long, value_name = "GAME_ID")]
+    pub game: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct CacheMigrateArgs {
+    /// Show the directories that would be moved without moving them
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SelfUpdateArgs {
+    /// Only report whether an update is available
+    #[arg(long)]
+    pub check_only: bool,
+    /// Release channel
+    #[arg(long, value_enum, default_value_t = UpdateChannel::Stable)]
+    pub channel: UpdateChannel,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum UpdateChannel {
+    /// Tagged releases
+    Stable,
+    /// Latest build, including prereleases
+    Nightly,
+}
+
+#[derive(Debug, Args)]
+pub struct LibsArgs {
+    #[command(subcommand)]
+    pub command: LibsCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum LibsCommand {
+    /// Show which companion libraries the FFI loader finds
+    Status,
+    /// Download the library versions matching this nvproton release
+    Update(LibsUpdateArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct LibsUpdateArgs {
+    /// Only report which libraries would be updated
+    #[arg(long)]
+    pub check_only: bool,
+    /// Release channel whose library manifest to use
+    #[arg(long, value_enum, default_value_t = UpdateChannel::Stable)]
+    pub channel: UpdateChannel,
+}
@@ -1,14 +1,22 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use directories::ProjectDirs;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use crate::cli::ConfigCommand;
+use crate::cli::{ConfigCommand, FileFormat};
+use crate::errors::NvError;
+use crate::format;
+use crate::yaml_edit;
 
-const CONFIG_FILE_BASENAME: &str = "config.yaml";
+const CONFIG_FILE_STEM: &str = "config";
+const PORTABLE_DIR_NAME: &str = "nvproton-data";
+const DROP_IN_DIR_NAME: &str = "config.d";
+const SYSTEM_DROP_IN_DIR: &str = "/etc/nvproton/config.d";
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct NvConfig {
@@ -20,6 +28,350 @@ pub struct NvConfig {
     pub profile: ProfileConfig,
     #[serde(default)]
     pub vkd3d: Vkd3dConfig,
+    #[serde(default)]
+    pub daemon: DaemonConfig,
+    #[serde(default)]
+    pub streaming: StreamingConfig,
+    #[serde(default)]
+    pub reporting: ReportingConfig,
+    #[serde(default)]
+    pub display: DisplayConfig,
+    #[serde(default)]
+    pub vram: VramConfig,
+    #[serde(default)]
+    pub memory: MemoryConfig,
+    #[serde(default)]
+    pub media: MediaConfig,
+    #[serde(default)]
+    pub saves: SavesConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub umu: UmuConfig,
+    #[serde(default)]
+    pub run_defaults: RunDefaults,
+    /// Environment variables added to every launch (profiles override them)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Command aliases: `nvproton <name>` runs `nvproton <expansion>`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+    /// Per-machine overrides, selected with `--context` or by hostname
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub contexts: BTreeMap<String, ConfigContext>,
+    /// Name of the context applied to this config
+    #[serde(skip)]
+    pub active_context: Option<String>,
+    /// Values the active context replaced, restored when saving
+    #[serde(skip)]
+    context_base: Option<(LibraryPaths, Option<String>)>,
+    /// Drop-in overrides merged at load, kept out of the saved config
+    #[serde(skip)]
+    drop_ins: Option<DropIns>,
+}
+
+/// Drop-in files merged onto the config file
+#[derive(Debug, Clone)]
+struct DropIns {
+    files: Vec<PathBuf>,
+    /// All drop-ins merged, in load order
+    overlay: serde_yaml::Value,
+    /// The config file alone, with defaults filled in
+    base: serde_yaml::Value,
+}
+
+/// A loaded config that remembers whether a command changed it
+///
+/// Reads go through `Deref`; changes must go through [`TrackedConfig::edit`]
+/// so read-only commands never rewrite the config file.
+#[derive(Debug)]
+pub struct TrackedConfig {
+    config: NvConfig,
+    dirty: bool,
+}
+
+impl TrackedConfig {
+    pub fn new(config: NvConfig) -> Self {
+        Self {
+            config,
+            dirty: false,
+        }
+    }
+
+    /// Mutable access, marking the config for saving
+    pub fn edit(&mut self) -> &mut NvConfig {
+        self.dirty = true;
+        &mut self.config
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+}
+
+impl std::ops::Deref for TrackedConfig {
+    type Target = NvConfig;
+
+    fn deref(&self) -> &NvConfig {
+        &self.config
+    }
+}
+
+/// Settings that differ between machines sharing one config directory
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ConfigContext {
+    /// Select this context automatically on the host with this name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// Library paths to use instead of the top-level ones (unset fields keep the top-level value)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library_paths: Option<LibraryPaths>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+}
+
+impl NvConfig {
+    /// Apply a config context
+    ///
+    /// Uses the requested name, then `NVPROTON_CONTEXT`, then the context whose
+    /// `hostname` matches this machine. Without contexts this is a no-op.
+    pub fn select_context(&mut self, requested: Option<&str>) -> Result<()> {
+        let requested = requested.map(String::from).or_else(|| {
+            std::env::var("NVPROTON_CONTEXT")
+                .ok()
+                .filter(|v| !v.is_empty())
+        });
+        let name = match requested {
+            Some(name) => {
+                if !self.contexts.contains_key(&name) {
+                    bail!("unknown config context '{}'", name);
+                }
+                name
+            }
+            None => {
+                let Some(host) = hostname() else {
+                    return Ok(());
+                };
+                match self
+                    .contexts
+                    .iter()
+                    .find(|(_, ctx)| ctx.hostname.as_deref() == Some(host.as_str()))
+                {
+                    Some((name, _)) => name.clone(),
+                    None => return Ok(()),
+                }
+            }
+        };
+
+        let context = self.contexts[&name].clone();
+        self.context_base = Some((
+            self.library_paths.clone(),
+            self.profile.default_profile.clone(),
+        ));
+        if let Some(paths) = context.library_paths {
+            if paths.steam.is_some() {
+                self.library_paths.steam = paths.steam;
+            }
+            if paths.heroic.is_some() {
+                self.library_paths.heroic = paths.heroic;
+            }
+            if paths.lutris.is_some() {
+                self.library_paths.lutris = paths.lutris;
+            }
+            if paths.legendary.is_some() {
+                self.library_paths.legendary = paths.legendary;
+            }
+        }
+        if context.default_profile.is_some() {
+            self.profile.default_profile = context.default_profile;
+        }
+        self.active_context = Some(name);
+        Ok(())
+    }
+
+    /// Drop-in files merged into this config
+    pub fn drop_in_files(&self) -> &[PathBuf] {
+        self.drop_ins
+            .as_ref()
+            .map(|d| d.files.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The config without values that came from drop-ins
+    ///
+    /// A value still equal to the drop-in's is reverted to the config file's;
+    /// one changed since load was set by the user and is kept.
+    fn without_drop_ins(&self) -> Result<NvConfig> {
+        let Some(drop_ins) = &self.drop_ins else {
+            return Ok(self.clone());
+        };
+        let mut value = serde_yaml::to_value(self).context("failed to serialize config")?;
+        restore_overridden(&mut value, &drop_ins.overlay, Some(&drop_ins.base));
+        let mut config: NvConfig =
+            serde_yaml::from_value(value).context("failed to rebuild config without drop-ins")?;
+        config.active_context = self.active_context.clone();
+        config.context_base = self.context_base.clone();
+        Ok(config)
+    }
+
+    /// The config as stored on disk
+    ///
+    /// Values the active context overrides are written back to that context;
+    /// everything else goes to the top level.
+    fn to_persisted(&self) -> NvConfig {
+        let mut config = self.clone();
+        let (Some(name), Some((base_paths, base_profile))) =
+            (&self.active_context, &self.context_base)
+        else {
+            return config;
+        };
+        let Some(context) = config.contexts.get_mut(name) else {
+            return config;
+        };
+
+        let mut paths = base_paths.clone();
+        if let Some(overrides) = context.library_paths.as_mut() {
+            for (overridden, current, base) in [
+                (
+                    &mut overrides.steam,
+                    &self.library_paths.steam,
+                    &mut paths.steam,
+                ),
+                (
+                    &mut overrides.heroic,
+                    &self.library_paths.heroic,
+                    &mut paths.heroic,
+                ),
+                (
+                    &mut overrides.lutris,
+                    &self.library_paths.lutris,
+                    &mut paths.lutris,
+                ),
+                (
+                    &mut overrides.legendary,
+                    &self.library_paths.legendary,
+                    &mut paths.legendary,
+                ),
+            ] {
+                if overridden.is_some() {
+                    *overridden = current.clone();
+                } else {
+                    *base = current.clone();
+                }
+            }
+        } else {
+            paths = self.library_paths.clone();
+        }
+        config.library_paths = paths;
+
+        if context.default_profile.is_some() {
+            context.default_profile = self.profile.default_profile.clone();
+            config.profile.default_profile = base_profile.clone();
+        }
+        config
+    }
+}
+
+/// Merge `overlay` onto `base`; mappings merge recursively, other values replace
+fn merge_values(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
+    match (base, overlay) {
+        (serde_yaml::Value::Mapping(base), serde_yaml::Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Revert values in `current` that still hold the overlay's value to `base`
+fn restore_overridden(
+    current: &mut serde_yaml::Value,
+    overlay: &serde_yaml::Value,
+    base: Option<&serde_yaml::Value>,
+) {
+    let (serde_yaml::Value::Mapping(current), serde_yaml::Value::Mapping(overlay)) =
+        (current, overlay)
+    else {
+        return;
+    };
+    for (key, overlay_value) in overlay {
+        let base_value = base.and_then(|b| b.get(key));
+        let Some(current_value) = current.get_mut(key) else {
+            continue;
+        };
+        if overlay_value.is_mapping() && current_value.is_mapping() {
+            restore_overridden(current_value, overlay_value, base_value);
+        } else if current_value == overlay_value {
+            match base_value {
+                Some(base_value) => *current_value = base_value.clone(),
+                None => {
+                    current.remove(key);
+                }
+            }
+        }
+    }
+}
+
+/// Set a config value by dotted key (e.g., `library_paths.steam`)
+///
+/// The value is parsed as YAML, so `true`/`60` become a bool/number. Lists
+/// also take comma-separated values (`steam,heroic`).
+fn set_config_value(config: &mut NvConfig, key: &str, value: &str) -> Result<()> {
+    let mut parsed: serde_yaml::Value = serde_yaml::from_str(value)
+        .unwrap_or_else(|_| serde_yaml::Value::String(value.to_string()));
+
+    let mut tree = serde_yaml::to_value(&*config).context("failed to serialize config")?;
+    let mut node = &mut tree;
+    for segment in key.split('.') {
+        let serde_yaml::Value::Mapping(map) = node else {
+            bail!("'{}' is not a config section", key);
+        };
+        node = map
+            .entry(serde_yaml::Value::String(segment.to_string()))
+            .or_insert(serde_yaml::Value::Null);
+    }
+    if let (serde_yaml::Value::Sequence(_), serde_yaml::Value::String(list)) = (&*node, &parsed) {
+        parsed = list
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| serde_yaml::Value::String(item.to_string()))
+            .collect();
+    }
+    *node = parsed;
+
+    let mut updated: NvConfig =
+        serde_yaml::from_value(tree).with_context(|| format!("invalid value for '{}'", key))?;
+
+    // Unknown keys are dropped by deserialization
+    let check = serde_yaml::to_value(&updated)?;
+    if key
+        .split('.')
+        .try_fold(&check, |node, segment| node.get(segment))
+        .is_none()
+    {
+        bail!("unknown config key '{}'", key);
+    }
+    updated.detectors.validate()?;
+
+    updated.active_context = config.active_context.take();
+    updated.context_base = config.context_base.take();
+    *config = updated;
+    Ok(())
+}
+
+fn hostname() -> Option<String> {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .ok()
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,36 +382,426 @@ pub struct LibraryPaths {
     pub heroic: Option<PathBuf>,
     #[serde(default)]
     pub lutris: Option<PathBuf>,
+    /// Standalone legendary (Epic) config directory
+    #[serde(default)]
+    pub legendary: Option<PathBuf>,
 }
 
 impl Default for LibraryPaths {
     fn default() -> Self {
-        let home = std::env::var("HOME").map(PathBuf::from).ok();
-        let steam = home.as_ref().map(|h| h.join(".local/share/Steam"));
-        let heroic = home.as_ref().map(|h| h.join(".config/heroic"));
-        let lutris = home.as_ref().map(|h| h.join(".local/share/lutris"));
+        match std::env::var("HOME") {
+            Ok(home) => Self::in_home(Path::new(&home)),
+            Err(_) => Self {
+                steam: None,
+                heroic: None,
+                lutris: None,
+                legendary: None,
+            },
+        }
+    }
+}
+
+impl LibraryPaths {
+    /// Launcher directories of a home directory
+    pub fn in_home(home: &Path) -> Self {
+        let steam = crate::steam::client::SteamClient::discover(home)
+            .map(|steam| steam.root().to_path_buf())
+            .unwrap_or_else(|| home.join(".local/share/Steam"));
+        // Native installs first, then Flatpak
+        let heroic = first_existing(
+            home,
+            &[
+                ".config/heroic",
+                ".var/app/com.heroicgameslauncher.hgl/config/heroic",
+            ],
+        );
         Self {
-            steam,
-            heroic,
-            lutris,
+            steam: Some(steam),
+            heroic: Some(heroic),
+            lutris: Some(home.join(".local/share/lutris")),
+            legendary: Some(first_existing(home, &[".config/legendary"])),
         }
     }
 }
 
+/// First of `candidates` (relative to home) that exists, else the first candidate
+fn first_existing(home: &Path, candidates: &[&str]) -> PathBuf {
+    candidates
+        .iter()
+        .map(|c| home.join(c))
+        .find(|p| p.exists())
+        .unwrap_or_else(|| home.join(candidates[0]))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct DetectorConfig {
+    /// Sources `games scan` and `detect all` run; empty runs all of them
     #[serde(default)]
     pub enabled_sources: Vec<String>,
+    /// Executables never fingerprinted: glob patterns or directories
     #[serde(default)]
     pub fingerprint_ignore: Vec<PathBuf>,
 }
 
+impl DetectorConfig {
+    /// Names accepted in `enabled_sources`
+    pub const SOURCES: [&str; 4] = ["steam", "heroic", "lutris", "legendary"];
+
+    fn validate(&self) -> Result<()> {
+        for source in &self.enabled_sources {
+            if !Self::SOURCES.contains(&source.to_lowercase().as_str()) {
+                bail!(
+                    "unknown source '{}' in detectors.enabled_sources (expected {})",
+                    source,
+                    Self::SOURCES.join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn source_enabled(&self, source: &str) -> bool {
+        self.enabled_sources.is_empty()
+            || self
+                .enabled_sources
+                .iter()
+                .any(|enabled| enabled.eq_ignore_ascii_case(source))
+    }
+
+    /// The `fingerprint_ignore` entry an executable matches
+    ///
+    /// A pattern matches the executable or any directory above it, so both
+    /// `/mnt/nfs` and `/mnt/nfs/*` skip everything on that mount.
+    pub fn fingerprint_ignored(&self, exe: &Path) -> Option<&Path> {
+        self.fingerprint_ignore
+            .iter()
+            .find(|ignore| {
+                exe.starts_with(ignore)
+                    || glob::Pattern::new(&ignore.to_string_lossy())
+                        .is_ok_and(|pattern| exe.ancestors().any(|path| pattern.matches_path(path)))
+            })
+            .map(PathBuf::as_path)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ProfileConfig {
     #[serde(default)]
     pub default_profile: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DaemonConfig {
+    /// Address for the Prometheus metrics endpoint (e.g., "127.0.0.1:9877")
+    #[serde(default)]
+    pub metrics_addr: Option<String>,
+    /// Register the org.ghostkellz.nvproton service on the session bus
+    #[serde(default)]
+    pub dbus: bool,
+    /// Commands run when the daemon sees a game start or stop, an Xid error or thermal throttling
+    #[serde(default)]
+    pub events: DaemonEvents,
+    /// Re-warm shader caches when a game's Proton / Wine version changes
+    #[serde(default)]
+    pub watch_compat_tools: bool,
+}
+
+/// Shell commands run by the daemon for each event
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DaemonEvents {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_game_start: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_game_stop: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_xid_error: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_thermal_throttle: Vec<String>,
+}
+
+impl DaemonEvents {
+    pub fn is_empty(&self) -> bool {
+        self.on_game_start.is_empty()
+            && self.on_game_stop.is_empty()
+            && self.on_xid_error.is_empty()
+            && self.on_thermal_throttle.is_empty()
+    }
+}
+
+/// Profile fragment layered onto launches while streaming/recording with OBS
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamingConfig {
+    /// Apply the fragment automatically when OBS is detected
+    #[serde(default = "default_true")]
+    pub auto_detect: bool,
+
+    /// Profile settings (same sections as a profile) applied on top of the game's profile
+    #[serde(default = "default_streaming_fragment")]
+    pub fragment: serde_yaml::Mapping,
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        Self {
+            auto_detect: true,
+            fragment: default_streaming_fragment(),
+        }
+    }
+}
+
+fn default_streaming_fragment() -> serde_yaml::Mapping {
+    // Cap at 60 fps so NVENC keeps headroom for encoding
+    serde_yaml::from_str("dxvk:\n  frame_rate: 60\n").expect("valid streaming fragment")
+}
+
+/// Launch adjustments for the Wayland or X11 session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayConfig {
+    /// Set SDL_VIDEODRIVER / QT_QPA_PLATFORM for the session unless already set
+    ///
+    /// Off by default: SDL before 2.0.22 doesn't understand the `wayland,x11`
+    /// fallback list and fails to open a window.
+    #[serde(default)]
+    pub session_env: bool,
+
+    /// On Wayland, have `run --vrr` switch the compositor's adaptive sync for the game
+    #[serde(default = "default_true")]
+    pub compositor_vrr: bool,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            session_env: false,
+            compositor_vrr: true,
+        }
+    }
+}
+
+/// VRAM headroom check before `run` launches a game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VramConfig {
+    /// Warn when less VRAM than this is free (0 disables the check)
+    #[serde(default = "default_min_free_vram_mb")]
+    pub min_free_mb: u64,
+
+    /// Report any process holding at least this much VRAM (0 only reports browsers and LLM runners)
+    #[serde(default = "default_heavy_process_mb")]
+    pub heavy_process_mb: u64,
+}
+
+impl Default for VramConfig {
+    fn default() -> Self {
+        Self {
+            min_free_mb: default_min_free_vram_mb(),
+            heavy_process_mb: default_heavy_process_mb(),
+        }
+    }
+}
+
+fn default_min_free_vram_mb() -> u64 {
+    2048
+}
+
+fn default_heavy_process_mb() -> u64 {
+    1024
+}
+
+/// Memory checks of `status` and before `run` launches a game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryConfig {
+    /// Warn when less RAM than this is available
+    #[serde(default = "default_min_available_mb")]
+    pub min_available_mb: u64,
+
+    /// Check available RAM and vm.max_map_count before each launch
+    #[serde(default = "default_true")]
+    pub check_before_launch: bool,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            min_available_mb: default_min_available_mb(),
+            check_before_launch: true,
+        }
+    }
+}
+
+fn default_min_available_mb() -> u64 {
+    4096
+}
+
+/// Per-game screenshot and replay directories
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MediaConfig {
+    /// Give each game launched with `run` its own capture directory
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Base directory for the per-game directories (default: ~/Videos/nvproton)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+
+    /// Directories whose files saved during a session move into the game's directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collect_from: Vec<PathBuf>,
+}
+
+/// Save game backups
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavesConfig {
+    /// Back up a game's saves after each session launched with `run`
+    #[serde(default)]
+    pub backup_on_exit: bool,
+
+    /// Backups kept per game; older ones are removed
+    #[serde(default = "default_saves_keep")]
+    pub keep: usize,
+
+    /// Ludusavi manifest listing save locations (default: ~/.config/ludusavi/manifest.yaml)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<PathBuf>,
+}
+
+impl Default for SavesConfig {
+    fn default() -> Self {
+        Self {
+            backup_on_exit: false,
+            keep: default_saves_keep(),
+            manifest: None,
+        }
+    }
+}
+
+fn default_saves_keep() -> usize {
+    10
+}
+
+/// Desktop notifications when long operations finish
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationsConfig {
+    /// Send notifications at all
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Only notify about scans and prepares that took at least this long
+    #[serde(default = "default_notify_min_secs")]
+    pub min_duration_secs: u64,
+
+    /// `games scan` finished
+    #[serde(default = "default_true")]
+    pub scan: bool,
+
+    /// `prepare` finished pre-warming shaders
+    #[serde(default = "default_true")]
+    pub prepare: bool,
+
+    /// Cache maintenance finished (relocation, re-warming after Proton changes)
+    #[serde(default = "default_true")]
+    pub cache: bool,
+
+    /// A game crashed and a triage report was saved
+    #[serde(default = "default_true")]
+    pub crash: bool,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_duration_secs: default_notify_min_secs(),
+            scan: true,
+            prepare: true,
+            cache: true,
+            crash: true,
+        }
+    }
+}
+
+fn default_notify_min_secs() -> u64 {
+    10
+}
+
+/// Flags every `run` and `wrap` starts from
+///
+/// A profile's `run` section overrides these per game, and flags given to
+/// the command override both (`--no-reflex`, `--fps 0`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct RunDefaults {
+    /// Reflex low-latency mode (`--reflex`)
+    #[serde(default)]
+    pub reflex: bool,
+
+    /// VRR (`--vrr`)
+    #[serde(default)]
+    pub vrr: bool,
+
+    /// Frame rate limit, 0 for unlimited (`--fps`)
+    #[serde(default)]
+    pub fps: u32,
+
+    /// MangoHud overlay (`--mangohud`)
+    #[serde(default)]
+    pub mangohud: bool,
+
+    /// Games `prepare --all` and `prepare --ids` work on at once (`--jobs`, default: CPU count)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jobs: Option<usize>,
+}
+
+/// Values a profile's `run` section or the command line sets over [`RunDefaults`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RunOverrides {
+    pub reflex: Option<bool>,
+    pub vrr: Option<bool>,
+    pub fps: Option<u32>,
+    pub mangohud: Option<bool>,
+}
+
+impl RunDefaults {
+    /// These defaults with the set values of `overrides` replacing them
+    pub fn with(mut self, overrides: RunOverrides) -> Self {
+        self.reflex = overrides.reflex.unwrap_or(self.reflex);
+        self.vrr = overrides.vrr.unwrap_or(self.vrr);
+        self.fps = overrides.fps.unwrap_or(self.fps);
+        self.mangohud = overrides.mangohud.unwrap_or(self.mangohud);
+        self
+    }
+}
+
+/// Launching non-Steam games through umu-launcher
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UmuConfig {
+    /// Start Heroic, Lutris and manually added games with `umu-run` (like `run --umu`)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// PROTONPATH: a Proton directory, or "GE-Proton" for the latest GE build (default: UMU-Proton)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proton: Option<String>,
+
+    /// umu-database CSV used to look up GAMEIDs by store and codename
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<PathBuf>,
+
+    /// GAMEID per nvproton game ID (`source:id`), taking precedence over the database
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub game_ids: BTreeMap<String, String>,
+}
+
+/// Anonymous compatibility reports (opt-in)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ReportingConfig {
+    /// Submit a compatibility datapoint after each session
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// URL reports are POSTed to as JSON
+    #[serde(default)]
+    pub endpoint: Option<String>,
+}
+
 fn default_true() -> bool {
     true
 }
@@ -116,15 +858,11 @@ impl Vkd3dConfig {
 
         // Add descriptor_heap if enabled
         match self.descriptor_heap.as_str() {
-            "on" | "true" | "enabled" => {
-                if !flags.contains(&"descriptor_heap".to_string()) {
-                    flags.push("descriptor_heap".to_string());
-                }
+            "on" | "true" | "enabled" if !flags.contains(&"descriptor_heap".to_string()) => {
+                flags.push("descriptor_heap".to_string());
             }
-            "auto" => {
-                if has_descriptor_heap && !flags.contains(&"descriptor_heap".to_string()) {
-                    flags.push("descriptor_heap".to_string());
-                }
+            "auto" if has_descriptor_heap && !flags.contains(&"descriptor_heap".to_string()) => {
+                flags.push("descriptor_heap".to_string());
             }
             _ => {}
         }
@@ -143,6 +881,11 @@ pub struct ConfigPaths {
     pub user_config_dir: PathBuf,
     pub games_dir: PathBuf,
     pub profiles_dir: PathBuf,
+    pub state_dir: PathBuf,
+    /// Per-boot state such as session locks (`$XDG_RUNTIME_DIR/nvproton`)
+    pub runtime_dir: PathBuf,
+    /// Directories of `*.yaml`/`*.toml` overrides merged onto the config, in order
+    pub drop_in_dirs: Vec<PathBuf>,
 }
 
 impl ConfigPaths {
@@ -157,8 +900,30 @@ impl ConfigPaths {
             .with_context(|| format!("failed to create games dir at {:?}", self.games_dir))?;
         fs::create_dir_all(&self.profiles_dir)
             .with_context(|| format!("failed to create profiles dir at {:?}", self.profiles_dir))?;
+        fs::create_dir_all(&self.state_dir)
+            .with_context(|| format!("failed to create state dir at {:?}", self.state_dir))?;
         Ok(())
     }
+
+    /// Read a YAML file of the state dir, or the default when there is none yet
+    pub fn load_state<T: DeserializeOwned + Default>(&self, name: &str) -> Result<T> {
+        let path = self.state_dir.join(name);
+        if !path.exists() {
+            return Ok(T::default());
+        }
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("failed to read {:?}", path))?;
+        serde_yaml::from_str(&content).with_context(|| format!("failed to parse {:?}", path))
+    }
+
+    /// Write a YAML file of the state dir
+    pub fn save_state<T: Serialize>(&self, name: &str, state: &T) -> Result<()> {
+        fs::create_dir_all(&self.state_dir)
+            .with_context(|| format!("failed to create directory {:?}", self.state_dir))?;
+        let path = self.state_dir.join(name);
+        fs::write(&path, serde_yaml::to_string(state)?)
+            .with_context(|| format!("failed to write {:?}", path))
+    }
 }
 
 #[derive(Debug)]
@@ -171,41 +936,125 @@ impl ConfigManager {
         let project_dirs = ProjectDirs::from("com", "ghostkellz", "nvproton")
             .context("unable to resolve project directories")?;
         let base_config = project_dirs.config_dir().to_path_buf();
+        let state_dir = project_dirs
+            .state_dir()
+            .map(|d| d.to_path_buf())
+            .unwrap_or_else(|| project_dirs.data_local_dir().join("state"));
+        let runtime_dir = project_dirs
+            .runtime_dir()
+            .map(|d| d.to_path_buf())
+            .unwrap_or_else(|| state_dir.join("run"));
         let paths = ConfigPaths {
             user_config_dir: base_config.clone(),
             games_dir: base_config.join("games"),
             profiles_dir: base_config.join("profiles"),
+            state_dir,
+            runtime_dir,
+            drop_in_dirs: vec![
+                PathBuf::from(SYSTEM_DROP_IN_DIR),
+                base_config.join(DROP_IN_DIR_NAME),
+            ],
         };
         Ok(Self { paths })
     }
 
+    /// Keep config and state under `root` (portable installs)
+    pub fn portable(root: &Path) -> Self {
+        let base_config = root.join("config");
+        let paths = ConfigPaths {
+            user_config_dir: base_config.clone(),
+            games_dir: base_config.join("games"),
+            profiles_dir: base_config.join("profiles"),
+            state_dir: root.join("state"),
+            runtime_dir: root.join("run"),
+            drop_in_dirs: vec![base_config.join(DROP_IN_DIR_NAME)],
+        };
+        Self { paths }
+    }
+
     pub fn load(&self) -> Result<NvConfig> {
         self.paths.ensure()?;
         let path = self.config_path();
-        if path.exists() {
+        let config = if path.exists() {
             let contents = fs::read_to_string(&path)
                 .with_context(|| format!("failed to read config file at {:?}", path))?;
-            let config: NvConfig = if path.extension().and_then(|ext| ext.to_str()) == Some("toml")
-            {
-                toml::from_str(&contents).context("failed to parse TOML config")?
-            } else {
-                serde_yaml::from_str(&contents).context("failed to parse YAML config")?
-            };
-            Ok(config)
+            FileFormat::of(&path)
+                .parse(&contents)
+                .context(NvError::ConfigInvalid(path.display().to_string()))?
         } else {
             let config = NvConfig::default();
             self.save(&config)?;
-            Ok(config)
+            config
+        };
+        self.apply_drop_ins(config)
+    }
+
+    /// Merge drop-in files onto a loaded config, in directory then file name order
+    fn apply_drop_ins(&self, config: NvConfig) -> Result<NvConfig> {
+        let mut files = Vec::new();
+        for dir in &self.paths.drop_in_dirs {
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            let mut found: Vec<PathBuf> = entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| FileFormat::from_path(path).is_some())
+                .collect();
+            found.sort();
+            files.extend(found);
         }
+        if files.is_empty() {
+            return Ok(config);
+        }
+
+        let mut overlay = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+        for file in &files {
+            let contents = fs::read_to_string(file)
+                .with_context(|| format!("failed to read config drop-in {:?}", file))?;
+            let value: serde_yaml::Value = FileFormat::of(file)
+                .parse(&contents)
+                .context(NvError::ConfigInvalid(file.display().to_string()))?;
+            if !value.is_null() {
+                merge_values(&mut overlay, value);
+            }
+        }
+
+        let base = serde_yaml::to_value(&config).context("failed to serialize config")?;
+        let mut merged = base.clone();
+        merge_values(&mut merged, overlay.clone());
+        let mut config: NvConfig = serde_yaml::from_value(merged).context(
+            NvError::ConfigInvalid(format!("{} (with drop-ins)", self.config_path().display())),
+        )?;
+        config.drop_ins = Some(DropIns {
+            files,
+            overlay,
+            base,
+        });
+        Ok(config)
     }
 
     pub fn save(&self, config: &NvConfig) -> Result<()> {
+        let config = &config.to_persisted().without_drop_ins()?;
         self.paths.ensure()?;
         let path = self.config_path();
-        let encoded = if path.extension().and_then(|ext| ext.to_str()) == Some("toml") {
-            toml::to_string_pretty(config).context("failed to serialize config to TOML")?
+        let encoded = if FileFormat::of(&path) == FileFormat::Toml {
+            FileFormat::Toml.encode(config)?
         } else {
-            serde_yaml::to_string(config).context("failed to serialize config to YAML")?
+            let value = serde_yaml::to_value(config).context("failed to serialize config")?;
+            // Edit the existing file in place to keep comments and key order
+            let edited = fs::read_to_string(&path).ok().and_then(|original| {
+                yaml_edit::edit_in_place(&original, &value, |text| {
+                    let config: NvConfig = serde_yaml::from_str(text)?;
+                    Ok(serde_yaml::to_value(config)?)
+                })
+            });
+            match edited {
+                Some(edited) => edited,
+                None => {
+                    serde_yaml::to_string(&value).context("failed to serialize config to YAML")?
+                }
+            }
         };
         let mut file = fs::File::create(&path)
             .with_context(|| format!("failed to open config file at {:?}", path))?;
@@ -214,8 +1063,39 @@ impl ConfigManager {
         Ok(())
     }
 
+    /// Rewrite the config file in `to`, keeping the old file as `.bak`
+    pub fn convert(&self, to: FileFormat) -> Result<PathBuf> {
+        let from = self.config_path();
+        if FileFormat::of(&from) == to {
+            bail!("config is already {}", to.extension().to_uppercase());
+        }
+        // Convert the file alone, without drop-ins or context selection
+        let contents = fs::read_to_string(&from)
+            .with_context(|| format!("failed to read config file at {:?}", from))?;
+        let config: NvConfig = FileFormat::of(&from)
+            .parse(&contents)
+            .with_context(|| format!("failed to parse config file at {:?}", from))?;
+
+        let to_path =
+            self.paths
+                .user_config_dir
+                .join(format!("{}.{}", CONFIG_FILE_STEM, to.extension()));
+        fs::write(&to_path, to.encode(&config)?)
+            .with_context(|| format!("failed to write config file at {:?}", to_path))?;
+        let mut backup = from.clone().into_os_string();
+        backup.push(".bak");
+        fs::rename(&from, &backup).with_context(|| format!("failed to move {:?} aside", from))?;
+        Ok(to_path)
+    }
+
     pub fn reset(&self) -> Result<NvConfig> {
         let config = NvConfig::default();
+        // Start from a fresh file rather than editing the old one
+        let path = self.config_path();
+        if path.exists() {
+            fs::remove_file(&path)
+                .with_context(|| format!("failed to remove config file at {:?}", path))?;
+        }
         self.save(&config)?;
         Ok(config)
     }
@@ -225,31 +1105,236 @@ impl ConfigManager {
     }
 
     pub fn config_path(&self) -> PathBuf {
-        self.paths.user_config_dir.join(CONFIG_FILE_BASENAME)
+        format::path_in(
+            &self.paths.user_config_dir,
+            CONFIG_FILE_STEM,
+            FileFormat::Yaml,
+        )
     }
 }
 
+/// Data directory of a portable install: `nvproton-data` next to the binary
+///
+/// Used when `--portable` is given or the directory already exists.
+pub fn portable_root(requested: bool) -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let root = exe.parent()?.join(PORTABLE_DIR_NAME);
+    (requested || root.is_dir()).then_some(root)
+}
+
 pub fn handle_config(
     command: ConfigCommand,
     manager: &ConfigManager,
-    config: &mut NvConfig,
+    config: &mut TrackedConfig,
 ) -> Result<()> {
     match command {
         ConfigCommand::Show => {
+            if let Some(name) = &config.active_context {
+                println!("# context: {}", name);
+            }
+            for file in config.drop_in_files() {
+                println!("# drop-in: {}", file.display());
+            }
             println!(
                 "{}",
-                serde_yaml::to_string(config).context("failed to serialize config for display")?
+                serde_yaml::to_string(&**config)
+                    .context("failed to serialize config for display")?
             );
         }
         ConfigCommand::Paths => {
             println!("config: {:?}", manager.config_path());
             println!("profiles: {:?}", manager.paths().profiles_dir);
             println!("games: {:?}", manager.paths().games_dir);
+            println!("state: {:?}", manager.paths().state_dir);
+            println!("runtime: {:?}", manager.paths().runtime_dir);
+            for dir in &manager.paths().drop_in_dirs {
+                println!("drop-ins: {:?}", dir);
+            }
+            println!("cache: {:?}", crate::cache::cache_base());
         }
         ConfigCommand::Reset => {
-            *config = manager.reset()?;
+            *config.edit() = manager.reset()?;
             println!("configuration reset to defaults");
         }
+        ConfigCommand::Set { key, value } => {
+            set_config_value(config.edit(), &key, &value)?;
+            println!("{} = {}", key, value);
+        }
+        ConfigCommand::Convert { to } => {
+            let from = manager.config_path();
+            let path = manager.convert(to)?;
+            println!(
+                "config converted to {:?} (previous file kept as {:?}.bak)",
+                path, from
+            );
+            println!(
+                "new profiles and the game database will use {}",
+                to.extension()
+            );
+        }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_overrides_are_saved_back_to_context() {
+        let mut config: NvConfig = serde_yaml::from_str(
+            "library_paths:\n  steam: /home/me/.local/share/Steam\ncontexts:\n  laptop:\n    library_paths:\n      steam: /mnt/games/Steam\n    default_profile: battery\n",
+        )
+        .unwrap();
+        config.select_context(Some("laptop")).unwrap();
+        assert_eq!(
+            config.library_paths.steam,
+            Some(PathBuf::from("/mnt/games/Steam"))
+        );
+        assert_eq!(config.profile.default_profile.as_deref(), Some("battery"));
+
+        config.library_paths.steam = Some(PathBuf::from("/mnt/ssd/Steam"));
+        let saved = config.to_persisted();
+        assert_eq!(
+            saved.library_paths.steam,
+            Some(PathBuf::from("/home/me/.local/share/Steam"))
+        );
+        assert_eq!(saved.profile.default_profile, None);
+        let laptop = &saved.contexts["laptop"];
+        assert_eq!(
+            laptop.library_paths.as_ref().unwrap().steam,
+            Some(PathBuf::from("/mnt/ssd/Steam"))
+        );
+
+        assert!(config.select_context(Some("desktop")).is_err());
+    }
+
+    #[test]
+    fn test_drop_ins_are_merged_but_not_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ConfigManager::portable(dir.path());
+        fs::create_dir_all(dir.path().join("config/config.d")).unwrap();
+        fs::write(
+            manager.config_path(),
+            "# my library\nlibrary_paths:\n  steam: /home/me/.local/share/Steam\nenv:\n  DXVK_HUD: fps\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("config/config.d/50-distro.yaml"),
+            "library_paths:\n  lutris: /opt/lutris\nenv:\n  PROTON_LOG: \"1\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("config/config.d/90-site.toml"),
+            "[env]\nPROTON_LOG = \"0\"\n",
+        )
+        .unwrap();
+
+        let mut config = manager.load().unwrap();
+        assert_eq!(config.drop_in_files().len(), 2);
+        assert_eq!(
+            config.library_paths.lutris,
+            Some(PathBuf::from("/opt/lutris"))
+        );
+        assert_eq!(
+            config.library_paths.steam,
+            Some(PathBuf::from("/home/me/.local/share/Steam"))
+        );
+        assert_eq!(config.env["PROTON_LOG"], "0");
+
+        config.env.insert("DXVK_HUD".into(), "full".into());
+        manager.save(&config).unwrap();
+        let text = fs::read_to_string(manager.config_path()).unwrap();
+        assert!(text.starts_with("# my library\n"));
+        let saved: NvConfig = serde_yaml::from_str(&text).unwrap();
+        assert_eq!(saved.env.get("PROTON_LOG"), None);
+        assert_eq!(saved.env["DXVK_HUD"], "full");
+        assert_eq!(saved.library_paths.lutris, None);
+    }
+
+    #[test]
+    fn test_convert_to_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ConfigManager::portable(dir.path());
+        let mut config = manager.load().unwrap();
+        config.env.insert("DXVK_HUD".into(), "fps".into());
+        manager.save(&config).unwrap();
+
+        let path = manager.convert(FileFormat::Toml).unwrap();
+        assert_eq!(manager.config_path(), path);
+        assert!(dir.path().join("config/config.yaml.bak").exists());
+        let loaded = manager.load().unwrap();
+        assert_eq!(loaded.env["DXVK_HUD"], "fps");
+        assert!(manager.convert(FileFormat::Toml).is_err());
+    }
+
+    #[test]
+    fn test_tracked_config_is_dirty_only_after_edit() {
+        let mut config = TrackedConfig::new(NvConfig::default());
+        assert!(config.env.is_empty());
+        assert!(!config.is_dirty());
+        config.edit().env.insert("DXVK_HUD".into(), "fps".into());
+        assert!(config.is_dirty());
+    }
+
+    #[test]
+    fn test_set_config_value() {
+        let mut config = NvConfig::default();
+        set_config_value(&mut config, "library_paths.steam", "/mnt/games/Steam").unwrap();
+        assert_eq!(
+            config.library_paths.steam,
+            Some(PathBuf::from("/mnt/games/Steam"))
+        );
+        set_config_value(&mut config, "streaming.auto_detect", "false").unwrap();
+        assert!(!config.streaming.auto_detect);
+        assert!(set_config_value(&mut config, "library_paths.origin", "/x").is_err());
+    }
+
+    #[test]
+    fn test_detector_sources_and_fingerprint_ignore() {
+        let mut config = NvConfig::default();
+        assert!(config.detectors.source_enabled("lutris"));
+        set_config_value(&mut config, "detectors.enabled_sources", "steam, heroic").unwrap();
+        assert_eq!(config.detectors.enabled_sources, ["steam", "heroic"]);
+        assert!(config.detectors.source_enabled("Steam"));
+        assert!(!config.detectors.source_enabled("lutris"));
+        assert!(
+            set_config_value(&mut config, "detectors.enabled_sources", "steam,origin").is_err()
+        );
+        assert_eq!(config.detectors.enabled_sources.len(), 2);
+
+        set_config_value(
+            &mut config,
+            "detectors.fingerprint_ignore",
+            "/mnt/nfs,**/Uninstall*.exe",
+        )
+        .unwrap();
+        let ignored = |exe: &str| config.detectors.fingerprint_ignored(Path::new(exe));
+        assert_eq!(
+            ignored("/mnt/nfs/Hades/Hades.exe"),
+            Some(Path::new("/mnt/nfs"))
+        );
+        assert!(ignored("/games/Hades/UninstallHades.exe").is_some());
+        assert_eq!(ignored("/games/Hades/Hades.exe"), None);
+    }
+
+    #[test]
+    fn test_run_defaults_layering() {
+        let mut config = NvConfig::default();
+        set_config_value(&mut config, "run_defaults.reflex", "true").unwrap();
+        set_config_value(&mut config, "run_defaults.fps", "144").unwrap();
+        let profile = RunOverrides {
+            fps: Some(60),
+            mangohud: Some(true),
+            ..Default::default()
+        };
+        let cli = RunOverrides {
+            reflex: Some(false),
+            ..Default::default()
+        };
+        let toggles = config.run_defaults.with(profile).with(cli);
+        assert!(!toggles.reflex);
+        assert_eq!(toggles.fps, 60);
+        assert!(toggles.mangohud);
+    }
+}
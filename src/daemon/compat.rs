@@ -0,0 +1,263 @@
+//! Proton / Wine version watcher
+//!
+//! With `daemon.watch_compat_tools`, the daemon records which Proton or Wine
+//! build every game runs with: the Steam compatibility tool (including the
+//! build in its `version` file, so Proton Experimental updates count), Heroic's
+//! `wineVersion` and the `wine.version` of Lutris games. When it changes, the
+//! game's DXVK / vkd3d-proton caches were built by another translation layer
+//! and are likely stale: the game is flagged, a desktop notification is sent
+//! and `nvproton prepare --force` re-warms its shaders once the game isn't
+//! running. Flags survive daemon restarts until the re-warm succeeds, or until
+//! it has failed `MAX_REWARM_ATTEMPTS` times in a row.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ConfigPaths, NvConfig};
+use crate::detection::{DetectedGame, GameDatabase};
+use crate::notify;
+use crate::running;
+use crate::runtimes::compat_tool;
+
+/// Reading every game's configuration is cheap, but not free
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Failed re-warms before a game is unflagged instead of retried every check
+const MAX_REWARM_ATTEMPTS: u32 = 5;
+
+/// Last seen tool of each game, and games whose caches need a re-warm
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct ToolState {
+    #[serde(default)]
+    tools: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    stale: BTreeSet<String>,
+    /// Failed re-warms of each flagged game
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    failures: BTreeMap<String, u32>,
+}
+
+impl ToolState {
+    fn path(paths: &ConfigPaths) -> PathBuf {
+        paths.state_dir.join("compat_tools.yaml")
+    }
+
+    fn load(paths: &ConfigPaths) -> Result<Self> {
+        let path = Self::path(paths);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("failed to read {:?}", path))?;
+        serde_yaml::from_str(&content).with_context(|| format!("failed to parse {:?}", path))
+    }
+
+    fn save(&self, paths: &ConfigPaths) -> Result<()> {
+        let path = Self::path(paths);
+        fs::write(&path, serde_yaml::to_string(self)?)
+            .with_context(|| format!("failed to write {:?}", path))
+    }
+
+    /// Record the current tool of a game, returning the previous one when it
+    /// changed (the game is flagged stale)
+    fn update(&mut self, game_id: &str, tool: String) -> Option<String> {
+        match self.tools.insert(game_id.to_string(), tool.clone()) {
+            Some(previous) if previous != tool => {
+                self.stale.insert(game_id.to_string());
+                self.failures.remove(game_id);
+                Some(previous)
+            }
+            _ => None,
+        }
+    }
+
+    /// Clear the flag of a re-warmed game
+    fn rewarmed(&mut self, game_id: &str) {
+        self.stale.remove(game_id);
+        self.failures.remove(game_id);
+    }
+
+    /// Count a failed re-warm; returns true when the game was unflagged
+    /// because it failed too often
+    fn rewarm_failed(&mut self, game_id: &str) -> bool {
+        let failures = self.failures.entry(game_id.to_string()).or_default();
+        *failures += 1;
+        if *failures < MAX_REWARM_ATTEMPTS {
+            return false;
+        }
+        self.rewarmed(game_id);
+        true
+    }
+}
+
+/// Check every game once; returns the games whose tool changed as
+/// `(game, previous, current)`
+fn check(
+    paths: &ConfigPaths,
+    config: &NvConfig,
+    state: &mut ToolState,
+) -> Result<Vec<(DetectedGame, String, String)>> {
+    let db = GameDatabase::load_or_default(paths)?;
+    let mut changes = Vec::new();
+    for game in db.games() {
+        let Some(tool) = compat_tool(config, &game) else {
+            continue;
+        };
+        if let Some(previous) = state.update(&game.game_id().to_string(), tool.clone()) {
+            changes.push((game, previous, tool));
+        }
+    }
+    Ok(changes)
+}
+
+/// Re-warm the shaders of a flagged game through `nvproton prepare --force`
+fn rewarm(game_id: &str) -> Result<bool> {
+    let exe = std::env::current_exe().context("failed to locate nvproton executable")?;
+    let status = Command::new(exe)
+        .args(["prepare", game_id, "--force"])
+        .stdout(Stdio::null())
+        .status()
+        .context("failed to run nvproton prepare")?;
+    Ok(status.success())
+}
+
+/// Watch for tool changes in the background
+pub fn watch(config: NvConfig, paths: ConfigPaths) {
+    thread::spawn(move || {
+        loop {
+            if let Err(e) = watch_once(&config, &paths) {
+                log::warn!("compat tool check failed: {:#}", e);
+            }
+            thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
+
+fn watch_once(config: &NvConfig, paths: &ConfigPaths) -> Result<()> {
+    let mut state = ToolState::load(paths)?;
+    let before = state.clone();
+    for (game, previous, current) in check(paths, config, &mut state)? {
+        println!(
+            "{} ({}) now runs with {} instead of {}",
+            game.name, game.id, current, previous
+        );
+        let body = format!(
+            "{} changed from {} to {}; its shader caches will be re-warmed",
+            game.name, previous, current
+        );
+        notify::finished(
+            &config.notifications,
+            notify::Kind::Cache,
+            Duration::ZERO,
+            "Proton version changed",
+            &body,
+        );
+    }
+
+    // Caches of a running game are in use; try again on the next check
+    let running: BTreeSet<String> = running::running_sessions(paths)
+        .into_iter()
+        .map(|session| session.game_id)
+        .collect();
+    for game_id in state.stale.clone() {
+        if running.contains(&game_id) {
+            continue;
+        }
+        match rewarm(&game_id) {
+            Ok(true) => {
+                println!("Re-warmed the shader caches of {}", game_id);
+                state.rewarmed(&game_id);
+                continue;
+            }
+            Ok(false) => log::warn!("re-warming {} failed", game_id),
+            Err(e) => log::warn!("re-warming {} failed: {:#}", game_id, e),
+        }
+        if state.rewarm_failed(&game_id) {
+            log::warn!(
+                "giving up on re-warming {} after {} attempts; run `nvproton prepare {} --force` manually",
+                game_id,
+                MAX_REWARM_ATTEMPTS,
+                game_id
+            );
+        }
+    }
+    if state != before {
+        state.save(paths)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtimes::lutris_wine_version;
+
+    #[test]
+    fn test_tool_changes_flag_caches() {
+        let dir = tempfile::tempdir().unwrap();
+        let lutris = dir.path().join("lutris");
+        fs::create_dir_all(lutris.join("games")).unwrap();
+        fs::create_dir_all(lutris.join("runners")).unwrap();
+        fs::write(
+            lutris.join("runners/wine.yml"),
+            "wine:\n  version: wine-ge-8-26\n",
+        )
+        .unwrap();
+        assert_eq!(
+            lutris_wine_version(&lutris, "diablo-iv").as_deref(),
+            Some("wine-ge-8-26")
+        );
+        fs::write(
+            lutris.join("games/diablo-iv-1700000000.yml"),
+            "game:\n  exe: Diablo IV.exe\nwine:\n  version: lutris-GE-Proton9-20\n",
+        )
+        .unwrap();
+        assert_eq!(
+            lutris_wine_version(&lutris, "diablo-iv").as_deref(),
+            Some("lutris-GE-Proton9-20")
+        );
+
+        let mut state = ToolState::default();
+        assert_eq!(state.update("diablo-iv", "wine-ge-8-26".into()), None);
+        assert_eq!(state.update("diablo-iv", "wine-ge-8-26".into()), None);
+        assert!(state.stale.is_empty());
+        assert_eq!(
+            state.update("diablo-iv", "lutris-GE-Proton9-20".into()),
+            Some("wine-ge-8-26".into())
+        );
+        assert!(state.stale.contains("diablo-iv"));
+    }
+
+    #[test]
+    fn test_failed_rewarms_give_up() {
+        let mut state = ToolState::default();
+        state.update("steam:1091500", "proton_8".into());
+        state.update("steam:1091500", "proton_9".into());
+        for _ in 1..MAX_REWARM_ATTEMPTS {
+            assert!(!state.rewarm_failed("steam:1091500"));
+            assert!(state.stale.contains("steam:1091500"));
+        }
+        assert!(state.rewarm_failed("steam:1091500"));
+        assert!(state.stale.is_empty());
+        assert!(state.failures.is_empty());
+
+        // Another tool change flags the game again with a fresh count
+        state.update("steam:1091500", "proton_experimental".into());
+        assert!(!state.rewarm_failed("steam:1091500"));
+        state.rewarmed("steam:1091500");
+        assert_eq!(state, {
+            let mut expected = ToolState::default();
+            expected
+                .tools
+                .insert("steam:1091500".into(), "proton_experimental".into());
+            expected
+        });
+    }
+}
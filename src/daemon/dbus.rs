@@ -0,0 +1,169 @@
+//! D-Bus service for desktop integration
+//!
+//! Owns `org.ghostkellz.nvproton` on the session bus and exports
+//! `/org/ghostkellz/nvproton` with the `org.ghostkellz.nvproton.Manager`
+//! interface:
+//! - `Launch(game_id)` / `Prepare(game_id)` run the matching CLI command
+//! - `RunningGames()` lists games launched through the daemon
+//! - `GameStarted(game_id)` / `GameStopped(game_id, exit_code)` signals
+//!
+//! Shell extensions can use the signals for a "Now Playing" indicator or to
+//! switch performance modes while a game runs.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use anyhow::{Context, Result};
+use zbus::object_server::SignalEmitter;
+use zbus::{blocking, fdo, interface};
+
+pub const BUS_NAME: &str = "org.ghostkellz.nvproton";
+pub const OBJECT_PATH: &str = "/org/ghostkellz/nvproton";
+pub const INTERFACE: &str = "org.ghostkellz.nvproton.Manager";
+
+/// Games launched through the daemon (game ID -> process ID)
+type RunningGames = Arc<Mutex<BTreeMap<String, u32>>>;
+
+struct Manager {
+    exe: PathBuf,
+    running: RunningGames,
+}
+
+#[interface(name = "org.ghostkellz.nvproton.Manager")]
+impl Manager {
+    /// Launch a game through `nvproton run`
+    fn launch(
+        &self,
+        game_id: String,
+        #[zbus(connection)] conn: &zbus::Connection,
+    ) -> fdo::Result<()> {
+        let mut child = self.start(&game_id)?;
+        let conn = blocking::Connection::from(conn.clone());
+        let running = Arc::clone(&self.running);
+        // Signals are emitted from the waiter thread so the blocking calls
+        // never run on the bus executor
+        thread::spawn(move || {
+            emit(&conn, "GameStarted", &(game_id.as_str(),));
+            let exit_code = child.wait().ok().and_then(|s| s.code()).unwrap_or(-1);
+            if let Ok(mut running) = running.lock() {
+                running.remove(&game_id);
+            }
+            emit(&conn, "GameStopped", &(game_id.as_str(), exit_code));
+        });
+
+        Ok(())
+    }
+
+    /// Prepare a game (shader pre-warm, profile binding) through `nvproton prepare`
+    fn prepare(&self, game_id: String) -> fdo::Result<()> {
+        Command::new(&self.exe)
+            .args(["prepare", &game_id])
+            .spawn()
+            .map_err(|e| fdo::Error::Failed(format!("failed to prepare {}: {}", game_id, e)))?;
+        Ok(())
+    }
+
+    /// Game IDs currently running through the daemon
+    fn running_games(&self) -> Vec<String> {
+        self.running
+            .lock()
+            .map(|r| r.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Emitted when a game is launched
+    #[zbus(signal)]
+    async fn game_started(emitter: &SignalEmitter<'_>, game_id: &str) -> zbus::Result<()>;
+
+    /// Emitted when a game exits
+    #[zbus(signal)]
+    async fn game_stopped(
+        emitter: &SignalEmitter<'_>,
+        game_id: &str,
+        exit_code: i32,
+    ) -> zbus::Result<()>;
+}
+
+impl Manager {
+    fn is_running(&self, game_id: &str) -> bool {
+        self.running.lock().is_ok_and(|r| r.contains_key(game_id))
+    }
+
+    /// Spawn `nvproton run` for a game that isn't running yet and track it
+    fn start(&self, game_id: &str) -> fdo::Result<Child> {
+        if self.is_running(game_id) {
+            return Err(fdo::Error::Failed(format!(
+                "{} is already running",
+                game_id
+            )));
+        }
+
+        let child = Command::new(&self.exe)
+            .args(["run", game_id])
+            .spawn()
+            .map_err(|e| fdo::Error::Failed(format!("failed to launch {}: {}", game_id, e)))?;
+
+        if let Ok(mut running) = self.running.lock() {
+            running.insert(game_id.to_string(), child.id());
+        }
+        Ok(child)
+    }
+}
+
+fn emit<B>(conn: &blocking::Connection, signal: &str, body: &B)
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    if let Err(e) = conn.emit_signal(None::<&str>, OBJECT_PATH, INTERFACE, signal, body) {
+        log::warn!("failed to emit {} signal: {}", signal, e);
+    }
+}
+
+/// Register the service on the session bus
+///
+/// The returned connection must be kept alive for the service to stay up.
+pub fn serve() -> Result<blocking::Connection> {
+    let manager = Manager {
+        exe: std::env::current_exe().context("failed to locate nvproton executable")?,
+        running: RunningGames::default(),
+    };
+
+    let conn = blocking::connection::Builder::session()
+        .context("failed to connect to the D-Bus session bus")?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, manager)?
+        .build()
+        .with_context(|| format!("failed to register {} on the session bus", BUS_NAME))?;
+
+    println!("D-Bus service {} registered", BUS_NAME);
+    Ok(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager(exe: &str) -> Manager {
+        Manager {
+            exe: PathBuf::from(exe),
+            running: RunningGames::default(),
+        }
+    }
+
+    #[test]
+    fn test_launch_tracks_running_games() {
+        let service = manager("true");
+        let mut child = service.start("steam:1145360").unwrap();
+        assert_eq!(service.running_games(), ["steam:1145360"]);
+        assert!(service.start("steam:1145360").is_err());
+        child.wait().unwrap();
+
+        let missing = manager("/nonexistent/nvproton");
+        assert!(missing.start("steam:1145360").is_err());
+        assert!(missing.running_games().is_empty());
+        assert!(missing.prepare("steam:1145360".into()).is_err());
+    }
+}
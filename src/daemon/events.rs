@@ -0,0 +1,326 @@
+//! Scriptable event hooks
+//!
+//! Commands listed under `daemon.events` run when the daemon notices an
+//! event, for home automation without patching nvproton:
+//!
+//! ```yaml
+//! daemon:
+//!   events:
+//!     on_game_start: ["hass-cli state turn_on light.bias_lighting"]
+//!     on_game_stop: ["pactl set-default-sink speakers"]
+//!     on_xid_error: ["notify-send \"GPU error $NVPROTON_XID\""]
+//!     on_thermal_throttle: ["~/bin/fans max"]
+//! ```
+//!
+//! Each command runs through `sh -c` with the event in `NVPROTON_*`
+//! environment variables and as JSON on stdin. Games starting and stopping
+//! are seen through the session locks of `nvproton run`, so games launched
+//! from Steam, the CLI or D-Bus all count; Xid errors come from the kernel
+//! log and thermal throttling from NVML.
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::config::{ConfigPaths, DaemonEvents};
+use crate::ffi::{self, NVML_CLOCKS_EVENT_HW_THERMAL, NVML_CLOCKS_EVENT_SW_THERMAL};
+use crate::running::{self, RunningSession};
+use crate::session::SessionHistory;
+use crate::xid::{XidError, XidMonitor};
+
+/// How often running sessions and the GPU are checked
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Something a hook can react to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    GameStart {
+        game_id: String,
+        name: String,
+        pid: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        profile: Option<String>,
+    },
+    GameStop {
+        game_id: String,
+        name: String,
+        runtime_secs: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        exit_code: Option<i32>,
+    },
+    XidError {
+        code: u32,
+        description: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        device: Option<String>,
+        message: String,
+    },
+    ThermalThrottle {
+        /// "software" (driver) and/or "hardware" slowdown
+        reasons: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        temperature_c: Option<u32>,
+    },
+}
+
+impl Event {
+    fn commands<'a>(&self, hooks: &'a DaemonEvents) -> &'a [String] {
+        match self {
+            Event::GameStart { .. } => &hooks.on_game_start,
+            Event::GameStop { .. } => &hooks.on_game_stop,
+            Event::XidError { .. } => &hooks.on_xid_error,
+            Event::ThermalThrottle { .. } => &hooks.on_thermal_throttle,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Event::GameStart { .. } => "game_start",
+            Event::GameStop { .. } => "game_stop",
+            Event::XidError { .. } => "xid_error",
+            Event::ThermalThrottle { .. } => "thermal_throttle",
+        }
+    }
+
+    /// `NVPROTON_*` variables describing the event
+    fn env(&self) -> Vec<(&'static str, String)> {
+        let mut env = vec![("NVPROTON_EVENT", self.name().to_string())];
+        match self {
+            Event::GameStart {
+                game_id,
+                name,
+                pid,
+                profile,
+            } => {
+                env.push(("NVPROTON_GAME_ID", game_id.clone()));
+                env.push(("NVPROTON_GAME_NAME", name.clone()));
+                env.push(("NVPROTON_PID", pid.to_string()));
+                if let Some(profile) = profile {
+                    env.push(("NVPROTON_PROFILE", profile.clone()));
+                }
+            }
+            Event::GameStop {
+                game_id,
+                name,
+                runtime_secs,
+                exit_code,
+            } => {
+                env.push(("NVPROTON_GAME_ID", game_id.clone()));
+                env.push(("NVPROTON_GAME_NAME", name.clone()));
+                env.push(("NVPROTON_RUNTIME_SECS", runtime_secs.to_string()));
+                if let Some(code) = exit_code {
+                    env.push(("NVPROTON_EXIT_CODE", code.to_string()));
+                }
+            }
+            Event::XidError {
+                code, description, ..
+            } => {
+                env.push(("NVPROTON_XID", code.to_string()));
+                env.push(("NVPROTON_XID_DESCRIPTION", description.clone()));
+            }
+            Event::ThermalThrottle {
+                reasons,
+                temperature_c,
+            } => {
+                env.push(("NVPROTON_THROTTLE_REASONS", reasons.join(",")));
+                if let Some(temperature) = temperature_c {
+                    env.push(("NVPROTON_GPU_TEMPERATURE", temperature.to_string()));
+                }
+            }
+        }
+        env
+    }
+}
+
+impl From<&XidError> for Event {
+    fn from(xid: &XidError) -> Self {
+        Event::XidError {
+            code: xid.code,
+            description: xid.description().to_string(),
+            device: xid.device.clone(),
+            message: xid.message.clone(),
+        }
+    }
+}
+
+/// Run the hooks for `event` in the background
+pub fn fire(hooks: &DaemonEvents, event: &Event) {
+    for command in event.commands(hooks) {
+        let command = command.clone();
+        let event = event.clone();
+        thread::spawn(move || match run_command(&command, &event) {
+            Ok(status) if !status.success() => {
+                log::warn!("{} hook '{}' exited with {}", event.name(), command, status)
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("{} hook '{}' failed: {:#}", event.name(), command, e),
+        });
+    }
+}
+
+/// Run one hook command and wait for it
+fn run_command(command: &str, event: &Event) -> Result<ExitStatus> {
+    let mut child = Command::new("sh")
+        .args(["-c", command])
+        .envs(event.env())
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run '{}'", command))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Hooks that don't read stdin close it early; that's fine
+        let _ = stdin.write_all(serde_json::to_string(event)?.as_bytes());
+    }
+    Ok(child.wait()?)
+}
+
+/// Watch for events in the background and run the configured hooks
+pub fn watch(hooks: DaemonEvents, paths: ConfigPaths) {
+    let hooks = Arc::new(hooks);
+    let xid_monitor = if hooks.on_xid_error.is_empty() {
+        None
+    } else {
+        let hooks = Arc::clone(&hooks);
+        XidMonitor::start_with(move |xid| fire(&hooks, &Event::from(xid)))
+    };
+
+    thread::spawn(move || {
+        // Owned by the watcher for the lifetime of the daemon
+        let _xid_monitor = xid_monitor;
+        let nvml = if hooks.on_thermal_throttle.is_empty() {
+            None
+        } else {
+            ffi::load_nvml()
+                .map_err(|e| log::warn!("thermal throttle hooks need NVML: {}", e))
+                .ok()
+        };
+        let mut sessions = running::running_sessions(&paths);
+        let mut throttled = false;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            let current = running::running_sessions(&paths);
+            for event in session_events(&sessions, &current, &paths) {
+                fire(&hooks, &event);
+            }
+            sessions = current;
+
+            if let Some(nvml) = &nvml {
+                let reasons = thermal_reasons(nvml.clocks_event_reasons().unwrap_or(0));
+                if !reasons.is_empty() && !throttled {
+                    let event = Event::ThermalThrottle {
+                        reasons: reasons.clone(),
+                        temperature_c: nvml.temperature(),
+                    };
+                    fire(&hooks, &event);
+                }
+                throttled = !reasons.is_empty();
+            }
+        }
+    });
+}
+
+/// Start and stop events between two snapshots of the running sessions
+fn session_events(
+    before: &[RunningSession],
+    after: &[RunningSession],
+    paths: &ConfigPaths,
+) -> Vec<Event> {
+    let key = |s: &RunningSession| (s.game_id.clone(), s.pid, s.started_at);
+    let before_keys: HashSet<_> = before.iter().map(key).collect();
+    let after_keys: HashSet<_> = after.iter().map(key).collect();
+
+    let mut events = Vec::new();
+    for session in before.iter().filter(|s| !after_keys.contains(&key(s))) {
+        // `run` records the session before it releases the lock
+        let recorded = SessionHistory::load(paths, &session.game_id)
+            .ok()
+            .and_then(|history| history.last().cloned())
+            .filter(|last| last.started_at >= session.started_at);
+        events.push(Event::GameStop {
+            game_id: session.game_id.clone(),
+            name: session.name.clone(),
+            runtime_secs: recorded
+                .as_ref()
+                .map_or(session.uptime_secs(), |last| last.runtime_secs),
+            exit_code: recorded.and_then(|last| last.exit_code),
+        });
+    }
+    for session in after.iter().filter(|s| !before_keys.contains(&key(s))) {
+        events.push(Event::GameStart {
+            game_id: session.game_id.clone(),
+            name: session.name.clone(),
+            pid: session.game_pid.unwrap_or(session.pid),
+            profile: session.profile.clone(),
+        });
+    }
+    events
+}
+
+fn thermal_reasons(reasons: u64) -> Vec<String> {
+    let mut names = Vec::new();
+    if reasons & NVML_CLOCKS_EVENT_SW_THERMAL != 0 {
+        names.push("software".to_string());
+    }
+    if reasons & NVML_CLOCKS_EVENT_HW_THERMAL != 0 {
+        names.push("hardware".to_string());
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigManager;
+
+    #[test]
+    fn test_session_events_and_hook_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ConfigManager::portable(dir.path());
+        let session = RunningSession {
+            game_id: "1145360".into(),
+            name: "Hades".into(),
+            pid: 4242,
+            process_start: None,
+            game_pid: None,
+            started_at: 1_760_000_000,
+            profile: Some("competitive".into()),
+        };
+
+        let started = session_events(&[], std::slice::from_ref(&session), manager.paths());
+        assert_eq!(
+            started,
+            vec![Event::GameStart {
+                game_id: "1145360".into(),
+                name: "Hades".into(),
+                pid: 4242,
+                profile: Some("competitive".into()),
+            }]
+        );
+        let stopped = session_events(std::slice::from_ref(&session), &[], manager.paths());
+        assert_eq!(stopped.len(), 1);
+        assert!(matches!(
+            stopped[0],
+            Event::GameStop {
+                exit_code: None,
+                ..
+            }
+        ));
+
+        let out = dir.path().join("hook.out");
+        let command = format!("cat > {:?}; echo \"$NVPROTON_EVENT\" >> {:?}", out, out);
+        assert!(run_command(&command, &started[0]).unwrap().success());
+        let written = std::fs::read_to_string(&out).unwrap();
+        assert!(written.starts_with("{\"event\":\"game_start\",\"game_id\":\"1145360\""));
+        assert!(written.ends_with("game_start\n"));
+
+        assert_eq!(thermal_reasons(0x60), vec!["software", "hardware"]);
+        assert!(thermal_reasons(0x1).is_empty());
+    }
+}
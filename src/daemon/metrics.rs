@@ -0,0 +1,240 @@
+//! Prometheus metrics endpoint
+//!
+//! Serves `GET /metrics` in the Prometheus text exposition format with
+//! per-game session counters, launch failures, cache sizes and current GPU
+//! telemetry read from NVML at scrape time.
+
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+
+use anyhow::{Context, Result};
+
+use crate::cache::CacheManager;
+use crate::config::ConfigPaths;
+use crate::ffi;
+use crate::session::SessionHistory;
+
+/// Serve metrics on `addr` until the process is stopped
+pub fn serve(addr: &str, paths: &ConfigPaths) -> Result<()> {
+    let listener = TcpListener::bind(addr)
+        .with_context(|| format!("failed to bind metrics endpoint {}", addr))?;
+    println!("Serving Prometheus metrics on http://{}/metrics", addr);
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = handle_connection(stream, paths) {
+                    log::debug!("metrics request failed: {}", e);
+                }
+            }
+            Err(e) => log::warn!("metrics connection failed: {}", e),
+        }
+    }
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream, paths: &ConfigPaths) -> Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", "text/plain; version=0.0.4", render(paths)?),
+        (Some("GET"), Some("/")) => (
+            "200 OK",
+            "text/html",
+            "<a href=\"/metrics\">metrics</a>\n".to_string(),
+        ),
+        _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
+
+/// Render all metrics in Prometheus text format
+pub fn render(paths: &ConfigPaths) -> Result<String> {
+    let mut out = String::new();
+
+    let histories = SessionHistory::load_all(paths)?;
+    metric_header(
+        &mut out,
+        "nvproton_sessions_total",
+        "counter",
+        "Recorded game sessions",
+    );
+    for (game_id, history) in &histories {
+        sample(
+            &mut out,
+            "nvproton_sessions_total",
+            &[("game_id", game_id)],
+            history.sessions.len() as f64,
+        );
+    }
+    metric_header(
+        &mut out,
+        "nvproton_session_seconds_total",
+        "counter",
+        "Total playtime across recorded sessions",
+    );
+    for (game_id, history) in &histories {
+        let seconds: u64 = history.sessions.iter().map(|s| s.runtime_secs).sum();
+        sample(
+            &mut out,
+            "nvproton_session_seconds_total",
+            &[("game_id", game_id)],
+            seconds as f64,
+        );
+    }
+    metric_header(
+        &mut out,
+        "nvproton_launch_failures_total",
+        "counter",
+        "Sessions that exited with an error",
+    );
+    for (game_id, history) in &histories {
+        let failures = history.sessions.iter().filter(|s| !s.success).count();
+        sample(
+            &mut out,
+            "nvproton_launch_failures_total",
+            &[("game_id", game_id)],
+            failures as f64,
+        );
+    }
+    metric_header(
+        &mut out,
+        "nvproton_xid_errors_total",
+        "counter",
+        "NVIDIA Xid errors seen during sessions",
+    );
+    for (game_id, history) in &histories {
+        let xids: usize = history.sessions.iter().map(|s| s.xid_errors.len()).sum();
+        sample(
+            &mut out,
+            "nvproton_xid_errors_total",
+            &[("game_id", game_id)],
+            xids as f64,
+        );
+    }
+
+    if let Ok(cache) = CacheManager::new()
+        && let Ok(stats) = cache.get_stats()
+    {
+        metric_header(
+            &mut out,
+            "nvproton_cache_size_bytes",
+            "gauge",
+            "Shader cache size",
+        );
+        for stat in &stats {
+            sample(
+                &mut out,
+                "nvproton_cache_size_bytes",
+                &[("cache", &stat.cache_type)],
+                stat.total_size_bytes as f64,
+            );
+        }
+    }
+
+    if let Ok(nvml) = ffi::load_nvml() {
+        let reading = nvml.read();
+        let gauges = [
+            (
+                "nvproton_gpu_temperature_celsius",
+                "GPU core temperature",
+                reading.temperature_c.map(f64::from),
+            ),
+            (
+                "nvproton_gpu_power_watts",
+                "GPU board power draw",
+                reading.power_mw.map(|mw| f64::from(mw) / 1000.0),
+            ),
+            (
+                "nvproton_gpu_clock_mhz",
+                "GPU graphics clock",
+                reading.graphics_clock_mhz.map(f64::from),
+            ),
+            (
+                "nvproton_gpu_memory_clock_mhz",
+                "GPU memory clock",
+                reading.memory_clock_mhz.map(f64::from),
+            ),
+            (
+                "nvproton_gpu_memory_used_bytes",
+                "GPU memory in use",
+                reading.vram_used_bytes.map(|b| b as f64),
+            ),
+            (
+                "nvproton_gpu_memory_total_bytes",
+                "GPU memory installed",
+                reading.vram_total_bytes.map(|b| b as f64),
+            ),
+        ];
+        for (name, help, value) in gauges {
+            if let Some(value) = value {
+                metric_header(&mut out, name, "gauge", help);
+                sample(&mut out, name, &[], value);
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+fn metric_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    if labels.is_empty() {
+        let _ = writeln!(out, "{} {}", name, value);
+        return;
+    }
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+        .collect();
+    let _ = writeln!(out, "{}{{{}}} {}", name, labels.join(","), value);
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_format() {
+        let mut out = String::new();
+        sample(
+            &mut out,
+            "nvproton_sessions_total",
+            &[("game_id", "1245620")],
+            3.0,
+        );
+        sample(&mut out, "nvproton_gpu_power_watts", &[], 212.5);
+        assert_eq!(
+            out,
+            "nvproton_sessions_total{game_id=\"1245620\"} 3\nnvproton_gpu_power_watts 212.5\n"
+        );
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c"), "a\\\"b\\\\c");
+    }
+}
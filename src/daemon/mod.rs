@@ -0,0 +1,59 @@
+//! nvproton background daemon
+//!
+//! Long-running services that complement the one-shot CLI:
+//! - Prometheus metrics endpoint (`daemon.metrics_addr`)
+//! - D-Bus service for desktop integration (`daemon.dbus`)
+//! - Scripts run on game, Xid and thermal events (`daemon.events`)
+//! - Shader re-warming after Proton / Wine changes (`daemon.watch_compat_tools`)
+
+pub mod compat;
+pub mod dbus;
+pub mod events;
+pub mod metrics;
+
+use std::thread;
+
+use anyhow::{Result, bail};
+
+use crate::cli::DaemonArgs;
+use crate::config::{ConfigManager, NvConfig};
+
+/// Handle the `daemon` command
+pub fn handle_daemon(args: DaemonArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
+    let metrics_addr = args
+        .metrics_addr
+        .or_else(|| config.daemon.metrics_addr.clone());
+    let dbus_enabled = args.dbus || config.daemon.dbus;
+    let events = &config.daemon.events;
+    let watch_compat_tools = args.watch_compat_tools || config.daemon.watch_compat_tools;
+
+    if metrics_addr.is_none() && !dbus_enabled && events.is_empty() && !watch_compat_tools {
+        bail!(
+            "no daemon services enabled; pass --metrics-addr/--dbus/--watch-compat-tools or set daemon.metrics_addr/daemon.dbus/daemon.events/daemon.watch_compat_tools in the config"
+        );
+    }
+
+    if !events.is_empty() {
+        events::watch(events.clone(), manager.paths().clone());
+        println!("Running event hooks from daemon.events");
+    }
+
+    if watch_compat_tools {
+        compat::watch(config.clone(), manager.paths().clone());
+        println!("Watching Proton / Wine versions for stale shader caches");
+    }
+
+    // Keep the bus connection alive for the lifetime of the daemon
+    let _dbus = if dbus_enabled {
+        Some(dbus::serve()?)
+    } else {
+        None
+    };
+
+    match metrics_addr {
+        Some(addr) => metrics::serve(&addr, manager.paths()),
+        None => loop {
+            thread::park();
+        },
+    }
+}
@@ -1,20 +1,54 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fs;
+use std::io::{self, IsTerminal, Write};
 use std::path::PathBuf;
+use std::sync::OnceLock;
 
-use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Deserializer, Serialize};
 
+use crate::cli::FileFormat;
 use crate::config::ConfigPaths;
-use crate::detection::steam::is_excluded_appid;
-use crate::detection::{DetectedGame, GameSource};
+use crate::detection::pe::{self, PeInfo};
+use crate::detection::{DetectedGame, GameId, GameSource, fingerprint};
+use crate::errors::NvError;
+use crate::format;
+use crate::steam::client::is_excluded_appid;
+use crate::targets::LaunchTarget;
+use crate::wrappers::WrapperSpec;
 
-const DATABASE_FILE: &str = "games.yaml";
+const DATABASE_STEM: &str = "games";
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct GameDatabase {
-    #[serde(default)]
-    pub entries: HashMap<String, GameRecord>,
+    #[serde(default, deserialize_with = "migrate_keys")]
+    entries: HashMap<GameId, GameRecord>,
+    /// Entries chosen with `games prefer` for games installed from several sources
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub preferred: BTreeSet<GameId>,
+    /// Entries by bare game ID, highest-priority source first, built on the
+    /// first lookup
+    #[serde(skip)]
+    index: OnceLock<HashMap<String, Vec<GameId>>>,
+}
+
+/// How a freshly detected game compares to the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryChange {
+    /// Not in the database yet
+    New,
+    /// Known, but installed elsewhere or with another executable
+    Updated,
+    Unchanged,
+}
+
+/// One game, possibly installed from several sources
+#[derive(Debug, Clone)]
+pub struct GameGroup {
+    /// Entry used for launching (preferred source)
+    pub primary: DetectedGame,
+    /// The same game from other sources
+    pub duplicates: Vec<DetectedGame>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +58,9 @@ pub struct GameRecord {
     pub install_dir: PathBuf,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub executable: Option<PathBuf>,
+    /// Executable set with `games set-exe`; detection never replaces it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub executable_override: Option<PathBuf>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub fingerprint: Option<String>,
     pub last_seen: u64,
@@ -31,125 +68,683 @@ pub struct GameRecord {
     pub metadata: HashMap<String, String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub profile: Option<String>,
+    /// Wrapper commands for this game (see `games wrappers`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wrappers: Vec<WrapperSpec>,
+    /// User-defined tags (see `games tag`)
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+    /// Named launch targets (see `games targets`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<LaunchTarget>,
+    /// Arguments added to the command line, with placeholders (see `games set-args`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Working directory, with placeholders
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
 }
 
 impl GameDatabase {
     pub fn load_or_default(paths: &ConfigPaths) -> Result<Self> {
-        let db_path = paths.games_dir.join(DATABASE_FILE);
-        if !db_path.exists() {
+        let Some(db_path) = format::locate(&paths.games_dir, DATABASE_STEM) else {
             return Ok(Self::default());
-        }
+        };
         let contents = fs::read_to_string(&db_path)
             .with_context(|| format!("failed to read game database at {:?}", db_path))?;
-        let db: GameDatabase =
-            serde_yaml::from_str(&contents).context("failed to parse game database YAML")?;
+        let db: GameDatabase = FileFormat::of(&db_path)
+            .parse(&contents)
+            .with_context(|| format!("failed to parse game database at {:?}", db_path))?;
         Ok(db)
     }
 
+    /// File the database is saved to: the existing one, or a new one in the
+    /// config's format
+    pub fn path(paths: &ConfigPaths) -> PathBuf {
+        format::path_in(
+            &paths.games_dir,
+            DATABASE_STEM,
+            format::preferred(&paths.user_config_dir),
+        )
+    }
+
     pub fn save(&self, paths: &ConfigPaths) -> Result<()> {
-        let db_path = paths.games_dir.join(DATABASE_FILE);
+        let db_path = Self::path(paths);
         fs::create_dir_all(&paths.games_dir).with_context(|| {
             format!("failed to create games directory at {:?}", paths.games_dir)
         })?;
-        let encoded = serde_yaml::to_string(self).context("failed to serialize game database")?;
+        let encoded = FileFormat::of(&db_path)
+            .encode(self)
+            .context("failed to serialize game database")?;
         fs::write(&db_path, encoded)
             .with_context(|| format!("failed to write game database at {:?}", db_path))?;
         Ok(())
     }
 
     pub fn merge_detected(&mut self, games: &[DetectedGame], timestamp: u64) {
+        self.index = OnceLock::new();
         for game in games {
             let entry = self
                 .entries
-                .entry(game_key(game))
+                .entry(game.game_id())
                 .or_insert_with(|| GameRecord {
                     source: game.source.clone(),
                     name: game.name.clone(),
                     install_dir: game.install_dir.clone(),
                     executable: game.executable.clone(),
+                    executable_override: None,
                     fingerprint: game.fingerprint.clone(),
                     last_seen: timestamp,
                     metadata: game.metadata.clone(),
                     profile: None,
+                    wrappers: Vec::new(),
+                    tags: BTreeSet::new(),
+                    targets: Vec::new(),
+                    args: Vec::new(),
+                    cwd: None,
                 });
             entry.install_dir = game.install_dir.clone();
             entry.executable = game.executable.clone();
-            entry.fingerprint = game.fingerprint.clone().or(entry.fingerprint.clone());
+            // An overridden executable keeps the fingerprint it was set with
+            if entry.executable_override.is_none() {
+                entry.fingerprint = game.fingerprint.clone().or(entry.fingerprint.clone());
+            }
             entry.last_seen = timestamp;
             entry.metadata.extend(game.metadata.clone());
         }
     }
 
-    /// Get a game by ID (searches all sources)
-    pub fn get(&self, game_id: &str) -> Option<DetectedGame> {
-        // Try direct key lookup first
-        for (key, record) in &self.entries {
-            if key.ends_with(&format!(":{}", game_id)) || key == game_id {
-                return Some(record_to_detected(game_id, record));
+    /// Compare a detected game with its entry (same source and ID)
+    pub fn compare(&self, game: &DetectedGame) -> EntryChange {
+        match self.entries.get(&game.game_id()) {
+            None => EntryChange::New,
+            Some(record)
+                if record.install_dir != game.install_dir
+                    || record.executable != game.executable
+                    || (game.fingerprint.is_some()
+                        && record.executable_override.is_none()
+                        && record.fingerprint != game.fingerprint) =>
+            {
+                EntryChange::Updated
             }
+            Some(_) => EntryChange::Unchanged,
         }
-        None
+    }
+
+    /// Get a game by its ID
+    pub fn get(&self, id: &GameId) -> Option<DetectedGame> {
+        Some(self.entries.get(id)?.to_game(&id.id))
+    }
+
+    /// The record of a game
+    pub fn record(&self, id: &GameId) -> Option<&GameRecord> {
+        self.entries.get(id)
+    }
+
+    fn record_mut(&mut self, id: &GameId) -> Option<&mut GameRecord> {
+        self.entries.get_mut(id)
+    }
+
+    /// Entries a `source:id` or bare ID names, highest-priority source first
+    fn matching(&self, query: &str) -> Vec<&GameId> {
+        if let Some(id) = GameId::parse(query) {
+            return self
+                .entries
+                .get_key_value(&id)
+                .map(|(id, _)| id)
+                .into_iter()
+                .collect();
+        }
+        self.index
+            .get_or_init(|| build_index(&self.entries))
+            .get(query)
+            .map_or_else(Vec::new, |ids| ids.iter().collect())
+    }
+
+    /// Whether other entries share `id`'s bare ID (a Lutris slug that is also
+    /// a Steam appid), so files named by the bare ID can't be told apart
+    pub fn shares_id(&self, id: &GameId) -> bool {
+        self.matching(&id.id).len() > 1
+    }
+
+    /// The game per-game state written under a bare ID (before it was keyed
+    /// by source) belongs to, unless several entries share the ID
+    pub fn legacy_owner(&self, key: &str) -> Option<&GameId> {
+        if GameId::parse(key).is_some() {
+            return None;
+        }
+        match self.matching(key).as_slice() {
+            [id] => Some(id),
+            _ => None,
+        }
+    }
+
+    /// The entry a `source:id` or bare ID names
+    ///
+    /// An ID installed from several sources resolves to the highest-priority
+    /// source, like duplicates do without `games prefer`. Game IDs given on
+    /// the command line go through [`Self::resolve`] instead.
+    pub fn lookup(&self, query: &str) -> Option<&GameId> {
+        self.matching(query).into_iter().next()
+    }
+
+    /// The entry a game ID given on the command line names
+    ///
+    /// A bare ID installed from several sources (a Lutris slug that is also a
+    /// Steam appid) is ambiguous: on a terminal the user picks one, otherwise
+    /// the command fails, listing the `source:id` forms to use instead.
+    pub fn resolve(&self, query: &str) -> Result<GameId> {
+        self.resolve_with(query, io::stdin().is_terminal())
+    }
+
+    /// [`Self::resolve`], asking on ambiguous IDs only when `interactive`
+    fn resolve_with(&self, query: &str, interactive: bool) -> Result<GameId> {
+        let matching = self.matching(query);
+        match matching.as_slice() {
+            [] => Err(NvError::GameNotFound(query.to_string()).into()),
+            [id] => Ok((*id).clone()),
+            _ if interactive => self.choose(query, &matching),
+            _ => {
+                let ids: Vec<String> = matching.iter().map(ToString::to_string).collect();
+                bail!(
+                    "'{}' names several games, use one of: {}",
+                    query,
+                    ids.join(", ")
+                )
+            }
+        }
+    }
+
+    /// The game a game ID given on the command line names (see [`Self::resolve`])
+    pub fn resolve_game(&self, query: &str) -> Result<DetectedGame> {
+        let id = self.resolve(query)?;
+        Ok(self.entries[&id].to_game(&id.id))
+    }
+
+    /// Ask which of several entries with the same ID is meant
+    ///
+    /// The question goes to stderr, so JSON and YAML output stays parseable.
+    fn choose(&self, query: &str, matching: &[&GameId]) -> Result<GameId> {
+        eprintln!("'{}' names several games:", query);
+        for (i, id) in matching.iter().enumerate() {
+            eprintln!("  {}) {} ({})", i + 1, self.entries[*id].name, id);
+        }
+        eprint!("Choose [1-{}]: ", matching.len());
+        io::stderr().flush()?;
+        let mut answer = String::new();
+        io::stdin()
+            .read_line(&mut answer)
+            .context("failed to read the choice")?;
+        let answer = answer.trim();
+        answer
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| matching.get(n.checked_sub(1)?))
+            .map(|id| (*id).clone())
+            .with_context(|| format!("invalid choice '{}'", answer))
+    }
+
+    /// IDs and records of all games (excluding Steam internals like Proton/Runtime)
+    pub fn records(&self) -> impl Iterator<Item = (&GameId, &GameRecord)> + '_ {
+        self.entries.iter().filter(|(id, _)| {
+            // Skip excluded Steam apps (Proton, Runtime, Redistributables)
+            !(id.source == GameSource::Steam && is_excluded_appid(&id.id))
+        })
     }
 
     /// Iterate over all games (excluding Steam internals like Proton/Runtime)
+    ///
+    /// Each game is built from its record; filter [`Self::records`] first
+    /// when only some are needed.
     pub fn games(&self) -> impl Iterator<Item = DetectedGame> + '_ {
-        self.entries.iter().filter_map(|(key, record)| {
-            let id = key.split(':').nth(1).unwrap_or(key);
-            // Skip excluded Steam apps (Proton, Runtime, Redistributables)
-            if record.source == GameSource::Steam && is_excluded_appid(id) {
-                return None;
+        self.records().map(|(id, record)| record.to_game(&id.id))
+    }
+
+    /// Games with duplicates across sources folded together
+    ///
+    /// Entries are linked when their normalized names or executable
+    /// fingerprints match. The primary entry is the one picked with
+    /// `games prefer`, otherwise the highest-priority source.
+    pub fn unique_games(&self) -> Vec<GameGroup> {
+        let mut groups: Vec<GameGroup> = self
+            .groups()
+            .into_iter()
+            .map(|mut members| {
+                let primary_index = members
+                    .iter()
+                    .position(|(id, _)| self.preferred.contains(id))
+                    .unwrap_or_else(|| {
+                        members
+                            .iter()
+                            .enumerate()
+                            .min_by_key(|(_, (_, game))| source_priority(&game.source))
+                            .map(|(i, _)| i)
+                            .unwrap_or(0)
+                    });
+                let (_, primary) = members.remove(primary_index);
+                GameGroup {
+                    primary,
+                    duplicates: members.into_iter().map(|(_, game)| game).collect(),
+                }
+            })
+            .collect();
+        groups.sort_by(|a, b| a.primary.name.cmp(&b.primary.name));
+        groups
+    }
+
+    /// Choose which source launches a game installed from several sources
+    pub fn prefer(&mut self, game_id: &GameId, source: &GameSource) -> Result<DetectedGame> {
+        let Some(members) = self
+            .groups()
+            .into_iter()
+            .find(|members| members.iter().any(|(id, _)| id == game_id))
+        else {
+            return Err(NvError::GameNotFound(game_id.to_string()).into());
+        };
+        let Some((id, game)) = members
+            .iter()
+            .find(|(_, game)| game.source == *source)
+            .cloned()
+        else {
+            let sources: Vec<String> = members.iter().map(|(_, g)| g.source.to_string()).collect();
+            bail!(
+                "'{}' is not installed from {} (available: {})",
+                game_id,
+                source,
+                sources.join(", ")
+            );
+        };
+
+        for (member, _) in &members {
+            self.preferred.remove(member);
+        }
+        self.preferred.insert(id);
+        Ok(game)
+    }
+
+    /// Group visible entries that are the same game
+    fn groups(&self) -> Vec<Vec<(GameId, DetectedGame)>> {
+        let mut entries: Vec<(GameId, DetectedGame)> = self
+            .records()
+            .map(|(id, record)| (id.clone(), record.to_game(&id.id)))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // Union-find over matching names and fingerprints
+        let mut parent: Vec<usize> = (0..entries.len()).collect();
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
             }
-            Some(record_to_detected(id, record))
-        })
+            i
+        }
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for (i, (_, game)) in entries.iter().enumerate() {
+            let name_key = format!("name:{}", normalize_name(&game.name));
+            let fingerprint_key = game.fingerprint.as_ref().map(|f| format!("fp:{}", f));
+            for key in std::iter::once(name_key).chain(fingerprint_key) {
+                match seen.get(&key) {
+                    Some(&other) => {
+                        let (a, b) = (root(&mut parent, i), root(&mut parent, other));
+                        parent[a] = b;
+                    }
+                    None => {
+                        seen.insert(key, i);
+                    }
+                }
+            }
+        }
+
+        let mut groups: HashMap<usize, Vec<(GameId, DetectedGame)>> = HashMap::new();
+        for (i, entry) in entries.into_iter().enumerate() {
+            groups.entry(root(&mut parent, i)).or_default().push(entry);
+        }
+        groups.into_values().collect()
     }
 
     /// Remove excluded Steam apps from database (cleanup)
     pub fn cleanup_excluded(&mut self) -> usize {
+        self.index = OnceLock::new();
         let before = self.entries.len();
-        self.entries.retain(|key, record| {
-            if record.source == GameSource::Steam {
-                let id = key.split(':').nth(1).unwrap_or(key);
-                !is_excluded_appid(id)
-            } else {
-                true
-            }
-        });
+        self.entries
+            .retain(|id, _| !(id.source == GameSource::Steam && is_excluded_appid(&id.id)));
         before - self.entries.len()
     }
 
     /// Set profile for a game
-    pub fn set_game_profile(&mut self, game_id: &str, profile: &str) {
-        for (key, record) in &mut self.entries {
-            if key.ends_with(&format!(":{}", game_id)) || key == game_id {
-                record.profile = Some(profile.to_string());
-                break;
-            }
+    pub fn set_game_profile(&mut self, game_id: &GameId, profile: &str) {
+        if let Some(record) = self.record_mut(game_id) {
+            record.profile = Some(profile.to_string());
         }
     }
 
-    /// Get profile for a game
-    pub fn get_game_profile(&self, game_id: &str) -> Option<&str> {
-        for (key, record) in &self.entries {
-            if key.ends_with(&format!(":{}", game_id)) || key == game_id {
-                return record.profile.as_deref();
+    /// Replace the wrapper commands of a game, returning false if it is unknown
+    pub fn set_game_wrappers(&mut self, game_id: &GameId, wrappers: Vec<WrapperSpec>) -> bool {
+        self.record_mut(game_id)
+            .map(|record| record.wrappers = wrappers)
+            .is_some()
+    }
+
+    /// Wrapper commands of a game
+    pub fn get_game_wrappers(&self, game_id: &GameId) -> &[WrapperSpec] {
+        self.record(game_id).map_or(&[], |record| &record.wrappers)
+    }
+
+    /// Replace the launch targets of a game, returning false if it is unknown
+    pub fn set_game_targets(&mut self, game_id: &GameId, targets: Vec<LaunchTarget>) -> bool {
+        self.record_mut(game_id)
+            .map(|record| record.targets = targets)
+            .is_some()
+    }
+
+    /// Launch targets added to a game
+    pub fn get_game_targets(&self, game_id: &GameId) -> &[LaunchTarget] {
+        self.record(game_id).map_or(&[], |record| &record.targets)
+    }
+
+    /// Replace the arguments and working directory of a game, returning
+    /// false if it is unknown
+    pub fn set_game_args(
+        &mut self,
+        game_id: &GameId,
+        args: Vec<String>,
+        cwd: Option<String>,
+    ) -> bool {
+        self.record_mut(game_id)
+            .map(|record| {
+                record.args = args;
+                record.cwd = cwd;
+            })
+            .is_some()
+    }
+
+    /// Arguments and working directory set for a game
+    pub fn get_game_args(&self, game_id: &GameId) -> (&[String], Option<&str>) {
+        self.record(game_id)
+            .map_or((&[], None), |record| (&record.args, record.cwd.as_deref()))
+    }
+
+    /// Tags of a game, or None if it is unknown
+    pub fn get_game_tags(&self, game_id: &GameId) -> Option<&BTreeSet<String>> {
+        self.record(game_id).map(|record| &record.tags)
+    }
+
+    /// Replace the tags of a game, returning false if it is unknown
+    pub fn set_game_tags(&mut self, game_id: &GameId, tags: BTreeSet<String>) -> bool {
+        self.record_mut(game_id)
+            .map(|record| record.tags = tags)
+            .is_some()
+    }
+
+    /// Whether a game has every one of `tags`
+    pub fn has_tags(&self, game_id: &GameId, tags: &[String]) -> bool {
+        tags.is_empty()
+            || self
+                .get_game_tags(game_id)
+                .is_some_and(|set| tags.iter().all(|tag| set.contains(tag)))
+    }
+
+    /// Override the executable of a game (None restores the detected one),
+    /// returning false if it is unknown
+    ///
+    /// The fingerprint follows the executable that launches.
+    pub fn set_game_executable(&mut self, game_id: &GameId, executable: Option<PathBuf>) -> bool {
+        let Some(record) = self.record_mut(game_id) else {
+            return false;
+        };
+        record.executable_override = executable;
+        let exe = record
+            .executable_override
+            .as_ref()
+            .or(record.executable.as_ref());
+        record.fingerprint = exe.and_then(|exe| fingerprint::fingerprint_file(exe).ok());
+        match exe.and_then(|exe| PeInfo::read(exe).ok()) {
+            Some(info) => info.insert_into(&mut record.metadata),
+            None => {
+                record.metadata.remove(pe::ARCH_KEY);
+                record.metadata.remove(pe::APIS_KEY);
             }
         }
-        None
+        true
+    }
+
+    /// Whether a game's executable was set with `games set-exe`
+    pub fn has_executable_override(&self, game_id: &GameId) -> bool {
+        self.record(game_id)
+            .is_some_and(|record| record.executable_override.is_some())
+    }
+
+    /// Get profile for a game
+    pub fn get_game_profile(&self, game_id: &GameId) -> Option<&str> {
+        self.record(game_id)?.profile.as_deref()
+    }
+}
+
+/// Compare titles ignoring case, punctuation and trademark symbols
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Default launch source order for duplicates
+fn source_priority(source: &GameSource) -> u8 {
+    match source {
+        GameSource::Steam => 0,
+        GameSource::Heroic => 1,
+        GameSource::Legendary => 2,
+        GameSource::Lutris => 3,
+        GameSource::Unknown => 4,
+    }
+}
+
+/// Entries for each bare game ID, highest-priority source first
+fn build_index(entries: &HashMap<GameId, GameRecord>) -> HashMap<String, Vec<GameId>> {
+    let mut ids: Vec<&GameId> = entries.keys().collect();
+    ids.sort_by_key(|id| (source_priority(&id.source), *id));
+    let mut index: HashMap<String, Vec<GameId>> = HashMap::with_capacity(ids.len());
+    for id in ids {
+        index.entry(id.id.clone()).or_default().push(id.clone());
+    }
+    index
+}
+
+/// Entries keyed by [`GameId`]
+///
+/// Keys written without their source (`1145360` rather than
+/// `steam:1145360`) take it from the record; a qualified key for the same
+/// game wins over a bare one.
+fn migrate_keys<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<GameId, GameRecord>, D::Error> {
+    let entries = HashMap::<String, GameRecord>::deserialize(deserializer)?;
+    let (qualified, bare): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .partition(|(key, record)| GameId::parse(key).is_some_and(|id| id.source == record.source));
+    let mut migrated: HashMap<GameId, GameRecord> = qualified
+        .into_iter()
+        .filter_map(|(key, record)| Some((GameId::parse(&key)?, record)))
+        .collect();
+    for (key, record) in bare {
+        let id = GameId::new(record.source.clone(), key);
+        migrated.entry(id).or_insert(record);
     }
+    Ok(migrated)
 }
 
-fn game_key(game: &DetectedGame) -> String {
-    format!("{}:{}", game.source, game.id)
+impl GameRecord {
+    /// The game this record describes, under its ID
+    pub fn to_game(&self, id: &str) -> DetectedGame {
+        DetectedGame {
+            source: self.source.clone(),
+            id: id.to_string(),
+            name: self.name.clone(),
+            install_dir: self.install_dir.clone(),
+            executable: self
+                .executable_override
+                .clone()
+                .or_else(|| self.executable.clone()),
+            fingerprint: self.fingerprint.clone(),
+            metadata: self.metadata.clone(),
+        }
+    }
 }
 
-fn record_to_detected(id: &str, record: &GameRecord) -> DetectedGame {
-    DetectedGame {
-        source: record.source.clone(),
-        id: id.to_string(),
-        name: record.name.clone(),
-        install_dir: record.install_dir.clone(),
-        executable: record.executable.clone(),
-        fingerprint: record.fingerprint.clone(),
-        metadata: record.metadata.clone(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(source: GameSource, id: &str, name: &str) -> DetectedGame {
+        DetectedGame {
+            source,
+            id: id.to_string(),
+            name: name.to_string(),
+            install_dir: PathBuf::new(),
+            executable: None,
+            fingerprint: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    fn id(key: &str) -> GameId {
+        GameId::parse(key).unwrap()
+    }
+
+    #[test]
+    fn test_duplicates_fold_into_preferred_source() {
+        let mut db = GameDatabase::default();
+        db.merge_detected(
+            &[
+                game(GameSource::Steam, "1086940", "Baldur's Gate 3"),
+                game(GameSource::Heroic, "1456460669", "Baldur’s Gate 3"),
+                game(GameSource::Lutris, "hades", "Hades"),
+            ],
+            0,
+        );
+
+        let groups = db.unique_games();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].primary.source, GameSource::Steam);
+        assert_eq!(groups[0].duplicates[0].id, "1456460669");
+
+        db.prefer(&id("steam:1086940"), &GameSource::Heroic)
+            .unwrap();
+        assert_eq!(db.unique_games()[0].primary.source, GameSource::Heroic);
+        assert!(db.prefer(&id("lutris:hades"), &GameSource::Steam).is_err());
+    }
+
+    #[test]
+    fn test_compare_detected_games() {
+        let mut db = GameDatabase::default();
+        db.merge_detected(&[game(GameSource::Steam, "1145360", "Hades")], 0);
+
+        let mut hades = game(GameSource::Steam, "1145360", "Hades");
+        assert_eq!(db.compare(&hades), EntryChange::Unchanged);
+        hades.install_dir = PathBuf::from("/mnt/games/steamapps/common/Hades");
+        assert_eq!(db.compare(&hades), EntryChange::Updated);
+        assert_eq!(
+            db.compare(&game(GameSource::Lutris, "1145360", "Hades")),
+            EntryChange::New
+        );
+    }
+
+    #[test]
+    fn test_executable_override_survives_detection() {
+        let mut db = GameDatabase::default();
+        let mut hades = game(GameSource::Steam, "1145360", "Hades");
+        hades.executable = Some(PathBuf::from("/games/Hades/unins000.exe"));
+        hades.fingerprint = Some("uninstaller".into());
+        db.merge_detected(std::slice::from_ref(&hades), 0);
+
+        let exe = PathBuf::from("/games/Hades/x64/Hades.exe");
+        let hades_id = hades.game_id();
+        assert!(db.set_game_executable(&hades_id, Some(exe.clone())));
+        assert!(!db.set_game_executable(&id("steam:1"), None));
+        db.merge_detected(std::slice::from_ref(&hades), 1);
+        assert_eq!(db.compare(&hades), EntryChange::Unchanged);
+        let stored = db.get(&hades_id).unwrap();
+        assert_eq!(stored.executable, Some(exe));
+        assert_eq!(stored.fingerprint, None);
+        assert!(db.has_executable_override(&hades_id));
+
+        db.set_game_executable(&hades_id, None);
+        assert_eq!(db.get(&hades_id).unwrap().executable, hades.executable);
+        assert!(!db.has_executable_override(&hades_id));
+    }
+
+    #[test]
+    fn test_tags_match_all_requested() {
+        let mut db = GameDatabase::default();
+        db.merge_detected(&[game(GameSource::Steam, "1145360", "Hades")], 0);
+        let tags = BTreeSet::from(["coop".to_string(), "backlog".to_string()]);
+        let hades = id("steam:1145360");
+        assert!(db.set_game_tags(&hades, tags));
+        assert!(!db.set_game_tags(&id("lutris:1145360"), BTreeSet::new()));
+
+        assert!(db.has_tags(&hades, &[]));
+        assert!(db.has_tags(&hades, &["coop".into()]));
+        assert!(!db.has_tags(&hades, &["coop".into(), "vr".into()]));
+        assert!(!db.has_tags(&id("lutris:1145360"), &["coop".into()]));
+    }
+
+    #[test]
+    fn test_lookup_by_id_and_key() {
+        let mut db = GameDatabase::default();
+        db.merge_detected(
+            &[
+                game(GameSource::Lutris, "1145360", "Hades (Lutris)"),
+                game(GameSource::Steam, "1145360", "Hades"),
+            ],
+            0,
+        );
+        assert_eq!(db.lookup("1145360"), Some(&id("steam:1145360")));
+        let lutris = db.resolve_game("lutris:1145360").unwrap();
+        assert_eq!(lutris.id, "1145360");
+        assert_eq!(lutris.name, "Hades (Lutris)");
+        assert!(db.lookup("missing").is_none());
+        assert!(db.resolve_with("heroic:1145360", false).is_err());
+        // Without a terminal to ask on, a bare ID of two games fails
+        let ambiguous = db.resolve_with("1145360", false).unwrap_err().to_string();
+        assert!(ambiguous.contains("steam:1145360, lutris:1145360"));
+
+        // The index follows new entries
+        db.merge_detected(&[game(GameSource::Heroic, "fn", "Fortnite")], 1);
+        assert_eq!(db.resolve_with("fn", false).unwrap(), id("heroic:fn"));
+    }
+
+    #[test]
+    fn test_keys_without_source_are_migrated() {
+        let yaml = "\
+entries:
+  '1145360':
+    source: steam
+    name: Hades
+    install_dir: /games/Hades
+    last_seen: 0
+  lutris:hades:
+    source: lutris
+    name: Hades
+    install_dir: /games/lutris/hades
+    last_seen: 0
+    tags: [coop]
+  hades:
+    source: lutris
+    name: Stale
+    install_dir: /old
+    last_seen: 0
+preferred: [lutris:hades]
+";
+        let db: GameDatabase = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(db.records().count(), 2);
+        assert!(db.get(&id("steam:1145360")).is_some());
+        // The qualified key wins over a bare one for the same game
+        assert!(db.has_tags(&id("lutris:hades"), &["coop".into()]));
+        assert!(db.preferred.contains(&id("lutris:hades")));
+
+        let saved = serde_yaml::to_string(&db).unwrap();
+        assert!(saved.contains("steam:1145360:"));
+        assert!(!saved.contains("'1145360':"));
     }
 }
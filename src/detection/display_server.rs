@@ -0,0 +1,153 @@
+//! Display server (Wayland / X11) detection
+//!
+//! Native games pick their windowing backend from `SDL_VIDEODRIVER` and
+//! `QT_QPA_PLATFORM`, and VRR is switched on in different places: on X11 the
+//! driver enables G-SYNC for fullscreen windows (`__GL_GSYNC_ALLOWED`,
+//! `__GL_VRR_ALLOWED`), on Wayland the compositor's adaptive sync setting
+//! decides and those variables have no effect. The session is detected once
+//! per launch; `run --dry-run` and `status` show what was picked.
+
+use std::env;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Kind of graphical session nvproton runs in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionType {
+    Wayland,
+    X11,
+    /// No display server found (TTY, SSH)
+    Unknown,
+}
+
+impl fmt::Display for SessionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionType::Wayland => write!(f, "Wayland"),
+            SessionType::X11 => write!(f, "X11"),
+            SessionType::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// What turns variable refresh rate on for a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VrrMechanism {
+    /// The NVIDIA driver, through `__GL_GSYNC_ALLOWED` / `__GL_VRR_ALLOWED`
+    Driver,
+    /// The compositor's adaptive sync setting
+    Compositor,
+    None,
+}
+
+impl fmt::Display for VrrMechanism {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VrrMechanism::Driver => write!(f, "driver (__GL_GSYNC_ALLOWED)"),
+            VrrMechanism::Compositor => write!(f, "compositor adaptive sync"),
+            VrrMechanism::None => write!(f, "none"),
+        }
+    }
+}
+
+/// The graphical session and the launch decisions that depend on it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplaySession {
+    pub session_type: SessionType,
+    /// `XDG_CURRENT_DESKTOP`, e.g. `KDE` or `GNOME`
+    pub desktop: Option<String>,
+    /// X11 clients (including Proton games) run through Xwayland
+    pub xwayland: bool,
+    pub vrr: VrrMechanism,
+}
+
+impl DisplaySession {
+    pub fn detect() -> Self {
+        Self::from_env(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+    }
+
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        // XDG_SESSION_TYPE is authoritative; the sockets cover sessions without logind
+        let session_type = match var("XDG_SESSION_TYPE").as_deref() {
+            Some("wayland") => SessionType::Wayland,
+            Some("x11") => SessionType::X11,
+            _ if var("WAYLAND_DISPLAY").is_some() => SessionType::Wayland,
+            _ if var("DISPLAY").is_some() => SessionType::X11,
+            _ => SessionType::Unknown,
+        };
+        let vrr = match session_type {
+            SessionType::Wayland => VrrMechanism::Compositor,
+            SessionType::X11 => VrrMechanism::Driver,
+            SessionType::Unknown => VrrMechanism::None,
+        };
+        Self {
+            session_type,
+            desktop: var("XDG_CURRENT_DESKTOP"),
+            xwayland: session_type == SessionType::Wayland && var("DISPLAY").is_some(),
+            vrr,
+        }
+    }
+
+    /// Session type with the desktop, e.g. `Wayland (KDE)`
+    pub fn describe(&self) -> String {
+        match &self.desktop {
+            Some(desktop) => format!("{} ({})", self.session_type, desktop),
+            None => self.session_type.to_string(),
+        }
+    }
+
+    /// Windowing backend hints for native SDL and Qt games (with
+    /// `display.session_env`)
+    pub fn backend_env(&self) -> &'static [(&'static str, &'static str)] {
+        match self.session_type {
+            // Fall back to X11 for games whose SDL or Qt lacks Wayland support
+            SessionType::Wayland => &[
+                ("SDL_VIDEODRIVER", "wayland,x11"),
+                ("QT_QPA_PLATFORM", "wayland;xcb"),
+            ],
+            SessionType::X11 => &[("SDL_VIDEODRIVER", "x11"), ("QT_QPA_PLATFORM", "xcb")],
+            SessionType::Unknown => &[],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn session(vars: &[(&str, &str)]) -> DisplaySession {
+        let vars: HashMap<_, _> = vars.iter().cloned().collect();
+        DisplaySession::from_env(|name| vars.get(name).map(|v| v.to_string()))
+    }
+
+    #[test]
+    fn test_session_type_and_vrr_mechanism() {
+        let wayland = session(&[
+            ("XDG_SESSION_TYPE", "wayland"),
+            ("DISPLAY", ":0"),
+            ("XDG_CURRENT_DESKTOP", "KDE"),
+        ]);
+        assert_eq!(wayland.session_type, SessionType::Wayland);
+        assert_eq!(wayland.vrr, VrrMechanism::Compositor);
+        assert!(wayland.xwayland);
+        assert_eq!(wayland.describe(), "Wayland (KDE)");
+
+        // Sockets decide when XDG_SESSION_TYPE is missing or unhelpful
+        let x11 = session(&[("XDG_SESSION_TYPE", "tty"), ("DISPLAY", ":1")]);
+        assert_eq!(x11.session_type, SessionType::X11);
+        assert_eq!(x11.vrr, VrrMechanism::Driver);
+        assert!(!x11.xwayland);
+        assert_eq!(
+            session(&[("WAYLAND_DISPLAY", "wayland-0")]).session_type,
+            SessionType::Wayland
+        );
+
+        let tty = session(&[]);
+        assert_eq!(tty.vrr, VrrMechanism::None);
+        assert!(tty.backend_env().is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+//! Host distribution detection
+//!
+//! Image-based distributions (Bazzite, Silverblue and other rpm-ostree
+//! systems, SteamOS) ship a read-only `/usr`, so nvproton cannot rely on
+//! libraries under `/usr/lib/nvproton` and installs user-local instead.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// How the root filesystem is managed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImmutableKind {
+    /// rpm-ostree image (Bazzite, Silverblue, Kinoite, Bluefin, ...)
+    RpmOstree,
+    /// SteamOS with its read-only root
+    SteamOs,
+    /// `/usr` is mounted read-only by some other mechanism
+    ReadOnlyUsr,
+}
+
+/// Distribution facts relevant to where nvproton looks for and installs files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistroInfo {
+    pub id: String,
+    pub name: String,
+    pub immutable: Option<ImmutableKind>,
+    /// nvproton itself runs inside a Flatpak sandbox
+    pub flatpak: bool,
+}
+
+impl DistroInfo {
+    pub fn detect() -> Self {
+        let os_release = fs::read_to_string("/etc/os-release")
+            .or_else(|_| fs::read_to_string("/usr/lib/os-release"))
+            .map(|c| parse_os_release(&c))
+            .unwrap_or_default();
+        let usr_readonly = fs::read_to_string("/proc/mounts")
+            .map(|m| is_mounted_readonly(&m, "/usr"))
+            .unwrap_or(false);
+
+        Self {
+            id: os_release.get("ID").cloned().unwrap_or_default(),
+            name: os_release
+                .get("PRETTY_NAME")
+                .or_else(|| os_release.get("NAME"))
+                .cloned()
+                .unwrap_or_else(|| "Linux".to_string()),
+            immutable: classify(
+                &os_release,
+                Path::new("/run/ostree-booted").exists(),
+                usr_readonly,
+            ),
+            flatpak: in_flatpak(),
+        }
+    }
+
+    /// Advice for installing nvproton components on this system
+    pub fn advice(&self) -> Vec<String> {
+        let user_lib = user_lib_dir()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "~/.local/lib/nvproton".to_string());
+        let mut advice = Vec::new();
+        match self.immutable {
+            Some(ImmutableKind::RpmOstree) => {
+                advice.push(format!(
+                    "Install nvshader/nvlatency/nvsync to {} instead of layering them",
+                    user_lib
+                ));
+                advice.push(
+                    "Layered packages (rpm-ostree install) need a reboot and slow down updates; \
+                     prefer user-local installs or a toolbox/distrobox"
+                        .to_string(),
+                );
+            }
+            Some(ImmutableKind::SteamOs) => {
+                advice.push(format!(
+                    "Install nvproton libraries to {}; /usr is reset by SteamOS updates",
+                    user_lib
+                ));
+                advice.push(
+                    "Avoid 'steamos-readonly disable': changes are lost on the next update"
+                        .to_string(),
+                );
+            }
+            Some(ImmutableKind::ReadOnlyUsr) => {
+                advice.push(format!(
+                    "/usr is read-only; install nvproton libraries to {}",
+                    user_lib
+                ));
+            }
+            None => {}
+        }
+        if self.flatpak {
+            advice.push(
+                "Running inside Flatpak: grant --filesystem=home (or the game library paths) \
+                 so installed games are visible"
+                    .to_string(),
+            );
+        }
+        advice
+    }
+}
+
+/// User-local library directory, preferred over `/usr/lib/nvproton`
+pub fn user_lib_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".local/lib/nvproton"))
+}
+
+/// Whether nvproton runs inside a Flatpak sandbox
+pub fn in_flatpak() -> bool {
+    Path::new("/.flatpak-info").exists()
+}
+
+fn parse_os_release(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            (
+                key.trim().to_string(),
+                value.trim().trim_matches('"').to_string(),
+            )
+        })
+        .collect()
+}
+
+fn classify(
+    os_release: &HashMap<String, String>,
+    ostree_booted: bool,
+    usr_readonly: bool,
+) -> Option<ImmutableKind> {
+    let id = os_release.get("ID").map(String::as_str).unwrap_or("");
+    if ostree_booted {
+        Some(ImmutableKind::RpmOstree)
+    } else if id == "steamos" {
+        Some(ImmutableKind::SteamOs)
+    } else if usr_readonly {
+        Some(ImmutableKind::ReadOnlyUsr)
+    } else {
+        None
+    }
+}
+
+/// Check `/proc/mounts` for a read-only mount at `mount_point`
+fn is_mounted_readonly(mounts: &str, mount_point: &str) -> bool {
+    mounts.lines().any(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        fields.len() >= 4 && fields[1] == mount_point && fields[3].split(',').any(|opt| opt == "ro")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_immutable_distros() {
+        let bazzite = parse_os_release("ID=bazzite\nPRETTY_NAME=\"Bazzite 41\"\n");
+        assert_eq!(
+            classify(&bazzite, true, true),
+            Some(ImmutableKind::RpmOstree)
+        );
+
+        let steamos = parse_os_release("ID=steamos\nVARIANT_ID=steamdeck\n");
+        assert_eq!(
+            classify(&steamos, false, false),
+            Some(ImmutableKind::SteamOs)
+        );
+
+        let arch = parse_os_release("ID=arch\n");
+        assert_eq!(classify(&arch, false, false), None);
+    }
+
+    #[test]
+    fn test_readonly_mount() {
+        let mounts = "/dev/sda2 / ext4 rw,relatime 0 0\n\
+                      composefs /usr overlay ro,relatime 0 0\n";
+        assert!(is_mounted_readonly(mounts, "/usr"));
+        assert!(!is_mounted_readonly(mounts, "/"));
+    }
+}
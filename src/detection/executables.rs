@@ -0,0 +1,132 @@
+//! Executables found by walking install directories
+//!
+//! Steam manifests don't name a game's executable, so without a launch
+//! entry in the app info cache the install directory is searched and its
+//! `.exe` files scored. On large libraries (and HDD or NFS mounts) that
+//! walk dominates a scan, so its result is kept in the state directory
+//! (`executables.yaml`), keyed by install directory and reused while the
+//! directory's modification time is unchanged and the executable exists.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::ConfigPaths;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CachedExecutable {
+    /// Install directory modification time (unix nanoseconds) when walked
+    modified: u64,
+    executable: Option<PathBuf>,
+}
+
+/// Walk results from previous scans
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExecutableCache {
+    #[serde(default)]
+    entries: BTreeMap<PathBuf, CachedExecutable>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl ExecutableCache {
+    fn path(paths: &ConfigPaths) -> PathBuf {
+        paths.state_dir.join("executables.yaml")
+    }
+
+    /// The cache, or an empty one if it is missing or unreadable
+    pub fn load(paths: &ConfigPaths) -> Self {
+        fs::read_to_string(Self::path(paths))
+            .ok()
+            .and_then(|content| serde_yaml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the cache if a walk changed it, dropping directories that are gone
+    pub fn save(&mut self, paths: &ConfigPaths) -> Result<()> {
+        if !self.changed {
+            return Ok(());
+        }
+        self.entries.retain(|dir, _| dir.is_dir());
+        let path = Self::path(paths);
+        fs::create_dir_all(&paths.state_dir)
+            .with_context(|| format!("failed to create directory {:?}", paths.state_dir))?;
+        fs::write(&path, serde_yaml::to_string(self)?)
+            .with_context(|| format!("failed to write executable cache {:?}", path))
+    }
+
+    /// The executable last found in an unchanged install directory
+    ///
+    /// `Some(None)` means the last walk found no executable.
+    pub fn lookup(&self, install_dir: &Path) -> Option<Option<PathBuf>> {
+        let cached = self.entries.get(install_dir)?;
+        if modified(install_dir)? != cached.modified {
+            return None;
+        }
+        match &cached.executable {
+            Some(exe) if !exe.is_file() => None,
+            executable => Some(executable.clone()),
+        }
+    }
+
+    pub fn insert(&mut self, install_dir: &Path, executable: Option<&Path>) {
+        let Some(modified) = modified(install_dir) else {
+            return;
+        };
+        let entry = CachedExecutable {
+            modified,
+            executable: executable.map(Path::to_path_buf),
+        };
+        self.entries.insert(install_dir.to_path_buf(), entry);
+        self.changed = true;
+    }
+}
+
+fn modified(path: &Path) -> Option<u64> {
+    fs::metadata(path)
+        .ok()?
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .and_then(|d| u64::try_from(d.as_nanos()).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigManager;
+
+    #[test]
+    fn test_lookup_until_install_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let install = dir.path().join("Hades");
+        let exe = install.join("x64").join("Hades.exe");
+        fs::create_dir_all(exe.parent().unwrap()).unwrap();
+        fs::write(&exe, "").unwrap();
+
+        let mut cache = ExecutableCache::default();
+        assert_eq!(cache.lookup(&install), None);
+        cache.insert(&install, Some(&exe));
+        assert_eq!(cache.lookup(&install), Some(Some(exe.clone())));
+
+        let paths = ConfigManager::portable(dir.path()).paths().clone();
+        cache.save(&paths).unwrap();
+        let mut loaded = ExecutableCache::load(&paths);
+        assert_eq!(loaded.lookup(&install), Some(Some(exe.clone())));
+
+        // A removed executable means walking again
+        fs::remove_file(&exe).unwrap();
+        assert_eq!(loaded.lookup(&install), None);
+        // So does a new top-level entry, which changes the directory's mtime
+        loaded.insert(&install, None);
+        assert_eq!(loaded.lookup(&install), Some(None));
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        fs::write(install.join("Hades2.exe"), "").unwrap();
+        assert_eq!(loaded.lookup(&install), None);
+    }
+}
@@ -0,0 +1,117 @@
+//! Source-qualified game IDs
+//!
+//! Launchers number their games independently, so a Lutris slug or a
+//! Heroic app name can equal a Steam appid. A [`GameId`] names one entry:
+//! it is written `source:id` (`steam:1145360`, `lutris:hades`) in the game
+//! database and on the command line, and `source-id` in directory names.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::GameSource;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GameId {
+    pub source: GameSource,
+    pub id: String,
+}
+
+impl GameId {
+    pub fn new(source: GameSource, id: impl Into<String>) -> Self {
+        Self {
+            source,
+            id: id.into(),
+        }
+    }
+
+    /// A `source:id` string, or None for a bare ID
+    pub fn parse(value: &str) -> Option<Self> {
+        let (source, id) = value.split_once(':')?;
+        let source = source.parse().ok()?;
+        (!id.is_empty()).then(|| Self::new(source, id))
+    }
+
+    /// Name for per-game directories (`steam-1145360`)
+    pub fn dir_name(&self) -> String {
+        format!("{}-{}", self.source, self.id)
+    }
+}
+
+impl fmt::Display for GameId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.source, self.id)
+    }
+}
+
+impl FromStr for GameSource {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "steam" => Ok(GameSource::Steam),
+            "heroic" => Ok(GameSource::Heroic),
+            "lutris" => Ok(GameSource::Lutris),
+            "legendary" => Ok(GameSource::Legendary),
+            "unknown" => Ok(GameSource::Unknown),
+            _ => Err(format!("unknown game source '{}'", value)),
+        }
+    }
+}
+
+impl GameSource {
+    /// Parse a `--source` value, in any case
+    pub fn parse_arg(value: &str) -> Result<Self, String> {
+        value.to_ascii_lowercase().parse()
+    }
+}
+
+impl Serialize for GameId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for GameId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::parse(&value).ok_or_else(|| {
+            serde::de::Error::custom(format!("'{}' is not a source:id game ID", value))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format() {
+        let hades = GameId::parse("steam:1145360").unwrap();
+        assert_eq!(hades, GameId::new(GameSource::Steam, "1145360"));
+        assert_eq!(hades.to_string(), "steam:1145360");
+        assert_eq!(hades.dir_name(), "steam-1145360");
+        // Only known sources qualify an ID
+        assert_eq!(GameId::parse("1145360"), None);
+        assert_eq!(GameId::parse("origin:1145360"), None);
+        assert_eq!(GameId::parse("steam:"), None);
+        assert_eq!(
+            GameId::parse("heroic:Fortnite:live").unwrap().id,
+            "Fortnite:live"
+        );
+
+        let yaml = serde_yaml::to_string(&[&hades]).unwrap();
+        assert_eq!(yaml, "- steam:1145360\n");
+        let parsed: Vec<GameId> = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed, [hades]);
+        assert!(serde_yaml::from_str::<GameId>("hades").is_err());
+    }
+
+    #[test]
+    fn test_source_argument_ignores_case() {
+        assert_eq!(GameSource::parse_arg("Steam"), Ok(GameSource::Steam));
+        assert_eq!(GameSource::parse_arg("HEROIC"), Ok(GameSource::Heroic));
+        assert!(GameSource::parse_arg("gog").is_err());
+    }
+}
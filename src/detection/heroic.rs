@@ -6,8 +6,7 @@ use anyhow::{Context, Result};
 use glob::glob;
 use serde::Deserialize;
 
-use super::fingerprint;
-use super::{DetectedGame, DetectionContext, GameSource};
+use super::{DetectedGame, DetectionContext, GameSource, describe_executable};
 
 pub struct HeroicDetector;
 
@@ -26,19 +25,25 @@ impl HeroicDetector {
             None => return Ok(Vec::new()),
         };
         if !heroic_root.exists() {
+            ctx.explain(format_args!("heroic: no config at {:?}", heroic_root));
             return Ok(Vec::new());
         }
         let mut games = Vec::new();
         let pattern = heroic_root.join("store").join("*").join("library.json");
         for entry in glob(pattern.to_string_lossy().as_ref())? {
             let path = entry?;
-            games.extend(parse_library_file(&path, include_fingerprint)?);
+            ctx.explain(format_args!("heroic: library {:?}", path));
+            games.extend(parse_library_file(ctx, &path, include_fingerprint)?);
         }
         Ok(games)
     }
 }
 
-fn parse_library_file(path: &Path, include_fingerprint: bool) -> Result<Vec<DetectedGame>> {
+fn parse_library_file(
+    ctx: &DetectionContext<'_>,
+    path: &Path,
+    include_fingerprint: bool,
+) -> Result<Vec<DetectedGame>> {
     let contents = fs::read_to_string(path)
         .with_context(|| format!("failed to read heroic library at {:?}", path))?;
     let games: HeroicLibrary = serde_json::from_str(&contents)
@@ -47,11 +52,17 @@ fn parse_library_file(path: &Path, include_fingerprint: bool) -> Result<Vec<Dete
         })
         .context("failed to parse heroic library json")?;
     let mut detected = Vec::new();
+    ctx.progress.add_total(games.games.len() as u64);
     for entry in games.games {
-        if entry.install_path.is_none() {
+        ctx.progress.inc(1, &entry.title);
+        let Some(install_path) = &entry.install_path else {
+            ctx.explain(format_args!(
+                "heroic: {} skipped: no install path (not installed)",
+                entry.app_name.as_deref().unwrap_or(&entry.title)
+            ));
             continue;
-        }
-        let install_dir = PathBuf::from(entry.install_path.unwrap());
+        };
+        let install_dir = ctx.locate(install_path);
         let identifier = if !entry.identifier.is_empty() {
             entry.identifier.clone()
         } else if let Some(app_name) = entry.app_name.clone() {
@@ -59,6 +70,10 @@ fn parse_library_file(path: &Path, include_fingerprint: bool) -> Result<Vec<Dete
         } else if !entry.title.is_empty() {
             entry.title.clone()
         } else {
+            ctx.explain(format_args!(
+                "heroic: entry in {:?} skipped: no identifier, app name or title",
+                install_dir
+            ));
             continue;
         };
         let display_name = if entry.title.is_empty() {
@@ -69,16 +84,16 @@ fn parse_library_file(path: &Path, include_fingerprint: bool) -> Result<Vec<Dete
         let executable = entry
             .executable
             .as_ref()
-            .map(PathBuf::from)
-            .or_else(|| locate_executable_hint(&install_dir, entry.launch_options.as_ref()))
-            .filter(|p| p.exists());
-        let fingerprint_value = if include_fingerprint {
-            executable
-                .as_ref()
-                .and_then(|exe| fingerprint::fingerprint_file(exe).ok())
-        } else {
-            None
-        };
+            .map(|exe| ctx.locate(exe))
+            .or_else(|| locate_executable_hint(&install_dir, entry.launch_options.as_ref()));
+        ctx.explain(format_args!(
+            "heroic: {} ({}) in {:?}, executable {}",
+            display_name,
+            identifier,
+            install_dir,
+            describe_executable(executable.as_deref())
+        ));
+        let executable = executable.filter(|p| p.exists());
         let mut metadata = HashMap::new();
         if let Some(app_name) = entry.app_name.clone() {
             metadata.insert("app_name".into(), app_name);
@@ -86,6 +101,14 @@ fn parse_library_file(path: &Path, include_fingerprint: bool) -> Result<Vec<Dete
         if let Some(platform) = entry.platform.clone() {
             metadata.insert("platform".into(), platform);
         }
+        if let Some(runner) = heroic_runner(path) {
+            metadata.insert("runner".into(), runner.into());
+        }
+        let fingerprint_value = if include_fingerprint {
+            ctx.fingerprint(&display_name, executable.as_deref(), &mut metadata)
+        } else {
+            None
+        };
         detected.push(DetectedGame {
             source: GameSource::Heroic,
             id: identifier,
@@ -99,6 +122,19 @@ fn parse_library_file(path: &Path, include_fingerprint: bool) -> Result<Vec<Dete
     Ok(detected)
 }
 
+/// Heroic runner of a `store/<store>/library.json` file, as used in
+/// `heroic://launch/<runner>/<app_name>`
+fn heroic_runner(library: &Path) -> Option<&'static str> {
+    let store = library.parent()?.file_name()?.to_str()?;
+    match store.to_ascii_lowercase().as_str() {
+        "legendary" | "epic" => Some("legendary"),
+        "gog" => Some("gog"),
+        "nile" | "amazon" => Some("nile"),
+        "sideload" => Some("sideload"),
+        _ => None,
+    }
+}
+
 fn locate_executable_hint(install_dir: &Path, hint: Option<&String>) -> Option<PathBuf> {
     match hint {
         Some(hint) if !hint.is_empty() => {
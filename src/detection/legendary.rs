@@ -0,0 +1,140 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::{DetectedGame, DetectionContext, GameSource, rebase};
+
+/// Detects games installed with the standalone `legendary` CLI (Epic)
+pub struct LegendaryDetector;
+
+impl LegendaryDetector {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn detect(
+        &self,
+        ctx: &DetectionContext<'_>,
+        include_fingerprint: bool,
+    ) -> Result<Vec<DetectedGame>> {
+        let legendary_root = match ctx.config.library_paths.legendary.as_ref() {
+            Some(path) => path.clone(),
+            None => return Ok(Vec::new()),
+        };
+        let installed = legendary_root.join("installed.json");
+        if !installed.exists() {
+            ctx.explain(format_args!("legendary: no {:?}", installed));
+            return Ok(Vec::new());
+        }
+        let mut games = parse_installed_file(&installed, ctx.root.as_deref())?;
+        ctx.progress.add_total(games.len() as u64);
+        for game in &mut games {
+            ctx.progress.inc(1, &game.name);
+            if include_fingerprint {
+                game.fingerprint =
+                    ctx.fingerprint(&game.name, game.executable.as_deref(), &mut game.metadata);
+            }
+            match &game.executable {
+                Some(exe) => ctx.explain(format_args!(
+                    "legendary: {} ({}) in {:?}, executable {:?}",
+                    game.name, game.id, game.install_dir, exe
+                )),
+                None => ctx.explain(format_args!(
+                    "legendary: {} ({}) in {:?}, recorded executable missing",
+                    game.name, game.id, game.install_dir
+                )),
+            }
+        }
+        Ok(games)
+    }
+}
+
+fn parse_installed_file(path: &Path, root: Option<&Path>) -> Result<Vec<DetectedGame>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read legendary installed games at {:?}", path))?;
+    let installed: BTreeMap<String, LegendaryGame> =
+        serde_json::from_str(&contents).context("failed to parse legendary installed.json")?;
+
+    let mut detected = Vec::new();
+    for (app_name, entry) in installed {
+        let install_dir = rebase(root, Path::new(&entry.install_path));
+        let executable = Some(install_dir.join(&entry.executable)).filter(|p| p.exists());
+
+        let mut metadata = HashMap::new();
+        metadata.insert("app_name".into(), app_name.clone());
+        if let Some(platform) = entry.platform {
+            metadata.insert("platform".into(), platform);
+        }
+        if let Some(version) = entry.version {
+            metadata.insert("version".into(), version);
+        }
+
+        detected.push(DetectedGame {
+            source: GameSource::Legendary,
+            name: if entry.title.is_empty() {
+                app_name.clone()
+            } else {
+                entry.title
+            },
+            id: app_name,
+            install_dir,
+            executable,
+            fingerprint: None,
+            metadata,
+        });
+    }
+    Ok(detected)
+}
+
+#[derive(Debug, Deserialize)]
+struct LegendaryGame {
+    #[serde(default)]
+    title: String,
+    install_path: String,
+    #[serde(default)]
+    executable: String,
+    #[serde(default)]
+    platform: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_installed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("installed.json");
+        fs::write(
+            &path,
+            r#"{
+                "Fortnite": {
+                    "app_name": "Fortnite",
+                    "title": "Fortnite",
+                    "install_path": "/games/Fortnite",
+                    "executable": "FortniteGame/Binaries/Win64/FortniteLauncher.exe",
+                    "platform": "Windows",
+                    "version": "++Fortnite+Release-30.00"
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let games = parse_installed_file(&path, None).unwrap();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].id, "Fortnite");
+        assert_eq!(games[0].source, GameSource::Legendary);
+        assert_eq!(games[0].install_dir, PathBuf::from("/games/Fortnite"));
+        assert_eq!(games[0].metadata["platform"], "Windows");
+
+        // `detect --root` looks the install up in the copied layout
+        let games = parse_installed_file(&path, Some(dir.path())).unwrap();
+        assert_eq!(games[0].install_dir, dir.path().join("games/Fortnite"));
+    }
+}
@@ -4,8 +4,7 @@ use std::path::PathBuf;
 use anyhow::{Context, Result};
 use rusqlite::Connection;
 
-use super::fingerprint;
-use super::{DetectedGame, DetectionContext, GameSource};
+use super::{DetectedGame, DetectionContext, GameSource, describe_executable};
 
 pub struct LutrisDetector;
 
@@ -25,6 +24,7 @@ impl LutrisDetector {
         };
         let db_path = lutris_root.join("pga.db");
         if !db_path.exists() {
+            ctx.explain(format_args!("lutris: no database at {:?}", db_path));
             return Ok(Vec::new());
         }
         let connection = Connection::open(&db_path)
@@ -66,20 +66,32 @@ impl LutrisDetector {
             }
         };
         let mut games = Vec::new();
+        ctx.progress.add_total(lutris_games.len() as u64);
         for entry in lutris_games {
-            let install_dir = PathBuf::from(&entry.directory);
-            let executable_path = entry.executable.as_ref().map(|exe| install_dir.join(exe));
-            let fingerprint_value = if include_fingerprint {
-                executable_path
-                    .as_ref()
-                    .and_then(|exe| fingerprint::fingerprint_file(exe).ok())
-            } else {
-                None
-            };
+            ctx.progress.inc(1, &entry.name);
+            let directory = PathBuf::from(&entry.directory);
+            let executable_path = entry
+                .executable
+                .as_ref()
+                .map(|exe| ctx.locate(directory.join(exe)));
+            let install_dir = ctx.locate(&directory);
+            ctx.explain(format_args!(
+                "lutris: {} ({}) in {:?}, runner {}, executable {}",
+                entry.name,
+                entry.slug,
+                install_dir,
+                entry.runner.as_deref().unwrap_or("none"),
+                describe_executable(executable_path.as_deref())
+            ));
             let mut metadata = HashMap::new();
             if let Some(runner) = entry.runner.clone() {
                 metadata.insert("runner".into(), runner);
             }
+            let fingerprint_value = if include_fingerprint {
+                ctx.fingerprint(&entry.name, executable_path.as_deref(), &mut metadata)
+            } else {
+                None
+            };
             games.push(DetectedGame {
                 source: GameSource::Lutris,
                 id: entry.slug.clone(),
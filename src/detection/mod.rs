@@ -1,21 +1,34 @@
 mod database;
+pub mod display_server;
+pub mod distro;
+mod executables;
 pub mod fingerprint;
+mod game_id;
 pub mod heroic;
+pub mod legendary;
 pub mod lutris;
+pub mod pe;
 pub mod proton_nv;
+pub mod runtime;
 pub mod steam;
+pub mod vulkan;
 
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::cli::{DetectArgs, DetectCommand, OutputFormat};
-use crate::config::{ConfigManager, NvConfig};
+use crate::cli::{DetectAllArgs, DetectArgs, DetectCommand, OutputFormat};
+use crate::config::{ConfigManager, LibraryPaths, NvConfig};
+use crate::term::Progress;
+use executables::ExecutableCache;
 
-pub use database::GameDatabase;
+pub use database::{EntryChange, GameDatabase};
+pub use game_id::GameId;
+pub use vulkan::VulkanCapabilities;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DetectedGame {
@@ -30,12 +43,20 @@ pub struct DetectedGame {
     pub metadata: HashMap<String, String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+impl DetectedGame {
+    /// The game's database entry ID
+    pub fn game_id(&self) -> GameId {
+        GameId::new(self.source.clone(), self.id.clone())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum GameSource {
     Steam,
     Heroic,
     Lutris,
+    Legendary,
     Unknown,
 }
 
@@ -45,28 +66,137 @@ impl fmt::Display for GameSource {
             GameSource::Steam => write!(f, "steam"),
             GameSource::Heroic => write!(f, "heroic"),
             GameSource::Lutris => write!(f, "lutris"),
+            GameSource::Legendary => write!(f, "legendary"),
             GameSource::Unknown => write!(f, "unknown"),
         }
     }
 }
 
+/// A source's detector, with whether to fingerprint executables
+pub type Detector = fn(&DetectionContext<'_>, bool) -> Result<Vec<DetectedGame>>;
+
 pub struct DetectionContext<'a> {
     pub config: &'a NvConfig,
     pub manager: &'a ConfigManager,
+    /// Copied layout detection runs in (`detect --root`)
+    pub root: Option<PathBuf>,
+    /// Report every entry considered and why it was skipped (`--explain`)
+    pub explain: bool,
+    /// Manifests and library entries worked through (`games scan`)
+    pub progress: Progress,
+    /// Executables found by earlier install directory walks
+    pub executables: RefCell<ExecutableCache>,
 }
 
 impl<'a> DetectionContext<'a> {
     pub fn new(config: &'a NvConfig, manager: &'a ConfigManager) -> Self {
-        Self { config, manager }
+        Self {
+            config,
+            manager,
+            root: None,
+            explain: false,
+            progress: Progress::hidden(),
+            executables: RefCell::new(ExecutableCache::load(manager.paths())),
+        }
+    }
+
+    /// Keep the executables found by walks for the next scan
+    ///
+    /// Walks of a copied layout (`--root`) aren't kept.
+    pub fn save_executables(&self) -> Result<()> {
+        if self.root.is_some() {
+            return Ok(());
+        }
+        self.executables.borrow_mut().save(self.manager.paths())
+    }
+
+    /// Print a detection decision on stderr with `--explain`
+    pub fn explain(&self, message: impl fmt::Display) {
+        if self.explain {
+            eprintln!("explain: {}", message);
+        }
+    }
+
+    /// Fingerprint of a game's executable, unless `detectors.fingerprint_ignore`
+    /// matches it
+    ///
+    /// The executable's architecture and graphics APIs go into `metadata`.
+    pub fn fingerprint(
+        &self,
+        name: &str,
+        exe: Option<&Path>,
+        metadata: &mut HashMap<String, String>,
+    ) -> Option<String> {
+        let exe = exe?;
+        if let Some(ignore) = self.config.detectors.fingerprint_ignored(exe) {
+            self.explain(format_args!(
+                "not fingerprinting {:?}: matches fingerprint_ignore {:?}",
+                exe, ignore
+            ));
+            return None;
+        }
+        self.progress
+            .set_detail(format_args!("fingerprinting {}", name));
+        match pe::PeInfo::read(exe) {
+            Ok(info) => info.insert_into(metadata),
+            Err(e) => self.explain(format_args!("{:#}", e)),
+        }
+        fingerprint::fingerprint_file(exe).ok()
+    }
+
+    /// Where a path recorded by a launcher is found
+    pub fn locate(&self, path: impl AsRef<Path>) -> PathBuf {
+        rebase(self.root.as_deref(), path.as_ref())
     }
 }
 
-pub fn handle_detect(
-    args: DetectArgs,
-    manager: &ConfigManager,
-    config: &mut NvConfig,
-) -> Result<()> {
-    let ctx = DetectionContext::new(config, manager);
+/// An executable path for `--explain`
+fn describe_executable(exe: Option<&Path>) -> String {
+    match exe {
+        None => "not recorded".to_string(),
+        Some(exe) if exe.exists() => format!("{:?}", exe),
+        Some(exe) => format!("{:?} (missing)", exe),
+    }
+}
+
+/// An absolute path moved under `root`, or unchanged without one
+///
+/// Paths are never resolved outside `root`, so a fixture tree can't pick up
+/// the games installed on the machine running it.
+pub fn rebase(root: Option<&Path>, path: &Path) -> PathBuf {
+    match root {
+        Some(root) => root.join(path.strip_prefix("/").unwrap_or(path)),
+        None => path.to_path_buf(),
+    }
+}
+
+pub fn handle_detect(args: DetectArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
+    let mut rooted;
+    let mut ctx = DetectionContext::new(config, manager);
+    if let Some(root) = &args.root {
+        let root = std::path::absolute(root)
+            .with_context(|| format!("invalid root directory '{}'", root))?;
+        if !root.is_dir() {
+            bail!("root directory {:?} does not exist", root);
+        }
+        rooted = config.clone();
+        rooted.library_paths = LibraryPaths::in_home(&root);
+        ctx = DetectionContext {
+            config: &rooted,
+            manager,
+            root: Some(root),
+            explain: false,
+            progress: Progress::hidden(),
+            executables: RefCell::default(),
+        };
+    }
+    ctx.explain = match &args.command {
+        DetectCommand::Steam(opts)
+        | DetectCommand::Heroic(opts)
+        | DetectCommand::Lutris(opts)
+        | DetectCommand::Legendary(opts) => opts.explain,
+        DetectCommand::All(opts) => opts.explain,
+    };
     match args.command {
         DetectCommand::Steam(opts) => {
             let games = steam::SteamDetector::new().detect(&ctx, opts.fingerprint)?;
@@ -83,18 +213,197 @@ pub fn handle_detect(
             output_games(&games, opts.format);
             maybe_update_database(&ctx, opts.update_db, &games)?;
         }
-        DetectCommand::All(opts) => {
-            let mut all_games = Vec::new();
-            all_games.extend(steam::SteamDetector::new().detect(&ctx, opts.fingerprint)?);
-            all_games.extend(heroic::HeroicDetector::new().detect(&ctx, opts.fingerprint)?);
-            all_games.extend(lutris::LutrisDetector::new().detect(&ctx, opts.fingerprint)?);
-            output_games(&all_games, opts.format);
-            maybe_update_database(&ctx, opts.update_db, &all_games)?;
+        DetectCommand::Legendary(opts) => {
+            let games = legendary::LegendaryDetector::new().detect(&ctx, opts.fingerprint)?;
+            output_games(&games, opts.format);
+            maybe_update_database(&ctx, opts.update_db, &games)?;
         }
+        DetectCommand::All(opts) => detect_all(&ctx, opts)?,
+    }
+    if let Err(e) = ctx.save_executables() {
+        log::warn!("failed to save the executable cache: {:#}", e);
     }
     Ok(())
 }
 
+/// Per-source counts for the `detect all` summary
+#[derive(Debug)]
+struct SourceSummary {
+    source: GameSource,
+    found: usize,
+    new: usize,
+    updated: usize,
+    error: Option<String>,
+}
+
+/// A game in `detect all` output, with the other launchers it is installed from
+#[derive(Serialize)]
+struct DetectedEntry<'a> {
+    #[serde(flatten)]
+    game: &'a DetectedGame,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    also_in: Vec<AlsoIn>,
+}
+
+#[derive(Serialize)]
+struct AlsoIn {
+    source: GameSource,
+    id: String,
+}
+
+/// Run every detector, folding games known to several launchers together
+///
+/// A failing detector is reported in the summary instead of aborting the
+/// others; `detect all` only fails when every detector does.
+fn detect_all(ctx: &DetectionContext<'_>, opts: DetectAllArgs) -> Result<()> {
+    let db = GameDatabase::load_or_default(ctx.manager.paths())?;
+    let detectors: [(GameSource, Detector); 4] = [
+        (GameSource::Steam, |ctx, fp| {
+            steam::SteamDetector::new().detect(ctx, fp)
+        }),
+        (GameSource::Heroic, |ctx, fp| {
+            heroic::HeroicDetector::new().detect(ctx, fp)
+        }),
+        (GameSource::Lutris, |ctx, fp| {
+            lutris::LutrisDetector::new().detect(ctx, fp)
+        }),
+        (GameSource::Legendary, |ctx, fp| {
+            legendary::LegendaryDetector::new().detect(ctx, fp)
+        }),
+    ];
+    let mut results = Vec::new();
+    for (source, detect) in detectors {
+        if !ctx.config.detectors.source_enabled(&source.to_string()) {
+            ctx.explain(format_args!("{}: not in detectors.enabled_sources", source));
+            continue;
+        }
+        results.push((source, detect(ctx, opts.fingerprint)));
+    }
+    if results.is_empty() {
+        bail!("detectors.enabled_sources names no known source");
+    }
+
+    let mut seen = HashSet::new();
+    let mut all_games = Vec::new();
+    let mut summary = Vec::new();
+    for (source, result) in results {
+        let mut row = SourceSummary {
+            source,
+            found: 0,
+            new: 0,
+            updated: 0,
+            error: None,
+        };
+        match result {
+            Ok(games) => {
+                for game in games {
+                    // The same app in two Steam libraries, for example
+                    if !seen.insert((game.source.to_string(), game.id.clone())) {
+                        continue;
+                    }
+                    row.found += 1;
+                    match db.compare(&game) {
+                        EntryChange::New => row.new += 1,
+                        EntryChange::Updated => row.updated += 1,
+                        EntryChange::Unchanged => {}
+                    }
+                    all_games.push(game);
+                }
+            }
+            Err(e) => row.error = Some(format!("{:#}", e)),
+        }
+        summary.push(row);
+    }
+    let errors: Vec<String> = summary
+        .iter()
+        .filter_map(|row| Some(format!("{}: {}", row.source, row.error.as_ref()?)))
+        .collect();
+    if errors.len() == summary.len() {
+        bail!("every detector failed:\n{}", errors.join("\n"));
+    }
+
+    let shown: Vec<DetectedGame> = all_games
+        .iter()
+        .filter(|game| !opts.diff || db.compare(game) == EntryChange::New)
+        .cloned()
+        .collect();
+    // Fold duplicates the way the database does, honouring `games prefer`
+    let mut detected = GameDatabase::default();
+    detected.preferred = db.preferred.clone();
+    detected.merge_detected(&shown, 0);
+    let groups = detected.unique_games();
+    let entries: Vec<DetectedEntry<'_>> = groups
+        .iter()
+        .map(|group| DetectedEntry {
+            game: &group.primary,
+            also_in: group
+                .duplicates
+                .iter()
+                .map(|game| AlsoIn {
+                    source: game.source.clone(),
+                    id: game.id.clone(),
+                })
+                .collect(),
+        })
+        .collect();
+
+    match opts.format {
+        OutputFormat::Text => {
+            for entry in &entries {
+                print_game(entry.game);
+                if !entry.also_in.is_empty() {
+                    let others: Vec<String> = entry
+                        .also_in
+                        .iter()
+                        .map(|other| format!("{} ({})", other.source, other.id))
+                        .collect();
+                    println!("  also in: {}", others.join(", "));
+                }
+                println!();
+            }
+            print_summary(&summary, entries.len(), opts.diff);
+        }
+        OutputFormat::Json => {
+            if let Ok(json) = serde_json::to_string_pretty(&entries) {
+                println!("{}", json);
+            }
+        }
+        OutputFormat::Yaml => {
+            if let Ok(yaml) = serde_yaml::to_string(&entries) {
+                println!("{}", yaml);
+            }
+        }
+    }
+    // On stderr, so JSON and YAML output stays parseable
+    for error in &errors {
+        eprintln!("{}", error);
+    }
+
+    maybe_update_database(ctx, opts.update_db, &all_games)
+}
+
+fn print_summary(summary: &[SourceSummary], unique: usize, diff: bool) {
+    println!(
+        "{:<10} {:>6} {:>6} {:>8} {:>7}",
+        "SOURCE", "FOUND", "NEW", "UPDATED", "ERRORS"
+    );
+    for row in summary {
+        println!(
+            "{:<10} {:>6} {:>6} {:>8} {:>7}",
+            row.source.to_string(),
+            row.found,
+            row.new,
+            row.updated,
+            usize::from(row.error.is_some())
+        );
+    }
+    let shown = if diff { "new games" } else { "games" };
+    println!(
+        "\n{} {} after folding games installed from several launchers",
+        unique, shown
+    );
+}
+
 fn maybe_update_database(
     ctx: &DetectionContext<'_>,
     update: bool,
@@ -103,6 +412,11 @@ fn maybe_update_database(
     if !update {
         return Ok(());
     }
+    if ctx.root.is_some() {
+        bail!(
+            "--update-db can't be combined with --root: the copied layout's games aren't installed here"
+        );
+    }
     let mut db = GameDatabase::load_or_default(ctx.manager.paths())?;
     let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
     db.merge_detected(games, timestamp);
@@ -113,15 +427,8 @@ fn output_games(games: &[DetectedGame], format: OutputFormat) {
     match format {
         OutputFormat::Text => {
             for game in games {
-                println!(
-                    "[{source}] {name} ({id})\n  install: {install:?}\n  executable: {exe:?}\n  fingerprint: {finger:?}\n",
-                    source = game.source,
-                    name = game.name,
-                    id = game.id,
-                    install = game.install_dir,
-                    exe = game.executable,
-                    finger = game.fingerprint
-                );
+                print_game(game);
+                println!();
             }
         }
         OutputFormat::Json => {
@@ -136,3 +443,15 @@ fn output_games(games: &[DetectedGame], format: OutputFormat) {
         }
     }
 }
+
+fn print_game(game: &DetectedGame) {
+    println!(
+        "[{source}] {name} ({id})\n  install: {install:?}\n  executable: {exe:?}\n  fingerprint: {finger:?}",
+        source = game.source,
+        name = game.name,
+        id = game.id,
+        install = game.install_dir,
+        exe = game.executable,
+        finger = game.fingerprint
+    );
+}
@@ -0,0 +1,366 @@
+//! Architecture and graphics APIs of Windows executables
+//!
+//! Reads just enough of a PE file to tell a 32-bit game from a 64-bit one
+//! and which graphics DLLs it imports (directly or delay-loaded), so
+//! launches can set up only the DXVK or vkd3d-proton caches a game uses
+//! and warn about 32-bit games under Proton's WoW64 mode. Only headers,
+//! the section table and the import tables are read, never whole files.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+/// Metadata key holding the architecture
+pub const ARCH_KEY: &str = "exe_arch";
+/// Metadata key holding the comma-separated graphics APIs
+pub const APIS_KEY: &str = "graphics_apis";
+
+/// Import directory entry in the data directories
+const IMPORT_DIRECTORY: usize = 1;
+/// Delay-load import directory entry
+const DELAY_IMPORT_DIRECTORY: usize = 13;
+/// Import descriptors read at most, against corrupt tables
+const MAX_IMPORTS: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arch {
+    X86,
+    X86_64,
+    Arm64,
+}
+
+impl Arch {
+    fn from_machine(machine: u16) -> Option<Self> {
+        match machine {
+            0x014c => Some(Arch::X86),
+            0x8664 => Some(Arch::X86_64),
+            0xaa64 => Some(Arch::Arm64),
+            _ => None,
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "x86" => Some(Arch::X86),
+            "x86_64" => Some(Arch::X86_64),
+            "arm64" => Some(Arch::Arm64),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arch::X86 => write!(f, "x86"),
+            Arch::X86_64 => write!(f, "x86_64"),
+            Arch::Arm64 => write!(f, "arm64"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GraphicsApi {
+    D3d9,
+    D3d10,
+    D3d11,
+    D3d12,
+    Vulkan,
+    OpenGl,
+}
+
+impl GraphicsApi {
+    /// The API a DLL provides, by file name
+    fn from_dll(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "d3d9.dll" => Some(GraphicsApi::D3d9),
+            "d3d10.dll" | "d3d10_1.dll" => Some(GraphicsApi::D3d10),
+            "d3d11.dll" => Some(GraphicsApi::D3d11),
+            "d3d12.dll" => Some(GraphicsApi::D3d12),
+            "vulkan-1.dll" => Some(GraphicsApi::Vulkan),
+            "opengl32.dll" => Some(GraphicsApi::OpenGl),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            GraphicsApi::D3d9 => "d3d9",
+            GraphicsApi::D3d10 => "d3d10",
+            GraphicsApi::D3d11 => "d3d11",
+            GraphicsApi::D3d12 => "d3d12",
+            GraphicsApi::Vulkan => "vulkan",
+            GraphicsApi::OpenGl => "opengl",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [
+            GraphicsApi::D3d9,
+            GraphicsApi::D3d10,
+            GraphicsApi::D3d11,
+            GraphicsApi::D3d12,
+            GraphicsApi::Vulkan,
+            GraphicsApi::OpenGl,
+        ]
+        .into_iter()
+        .find(|api| api.name() == value)
+    }
+
+    /// Translated by DXVK (d3d9 to d3d11)
+    pub fn uses_dxvk(self) -> bool {
+        matches!(
+            self,
+            GraphicsApi::D3d9 | GraphicsApi::D3d10 | GraphicsApi::D3d11
+        )
+    }
+}
+
+/// What an executable's headers tell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeInfo {
+    pub arch: Arch,
+    /// Graphics APIs imported; empty for launcher stubs that load the game
+    /// from another executable
+    pub apis: BTreeSet<GraphicsApi>,
+}
+
+impl PeInfo {
+    /// Read an executable's headers
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut file =
+            File::open(path).with_context(|| format!("failed to open executable {:?}", path))?;
+        Self::read_from(&mut file)
+            .with_context(|| format!("failed to read PE headers of {:?}", path))
+    }
+
+    fn read_from<R: Read + Seek>(file: &mut R) -> Result<Self> {
+        let dos = read_at(file, 0, 64)?;
+        if &dos[..2] != b"MZ" {
+            bail!("not a Windows executable");
+        }
+        let pe_offset = u64::from(u32_at(&dos, 0x3c));
+        let coff = read_at(file, pe_offset, 24)?;
+        if &coff[..4] != b"PE\0\0" {
+            bail!("no PE header");
+        }
+        let machine = u16_at(&coff, 4);
+        let arch = Arch::from_machine(machine)
+            .with_context(|| format!("unknown machine type {:#06x}", machine))?;
+        let sections = usize::from(u16_at(&coff, 6));
+        let optional_size = usize::from(u16_at(&coff, 20));
+
+        let optional = read_at(file, pe_offset + 24, optional_size)?;
+        let directories_at = match u16_at(&optional, 0) {
+            0x10b => 96,
+            0x20b => 112,
+            magic => bail!("unknown optional header magic {:#06x}", magic),
+        };
+        let directory = |index: usize| {
+            let at = directories_at + index * 8;
+            (at + 8 <= optional.len()).then(|| u32_at(&optional, at))
+        };
+
+        let table = read_at(file, pe_offset + 24 + optional_size as u64, sections * 40)?;
+        let sections: Vec<Section> = table.chunks_exact(40).map(Section::parse).collect();
+        let mut image = Image { file, sections };
+
+        let mut apis = BTreeSet::new();
+        let imports = [
+            // Name RVA at offset 12 of 20-byte descriptors
+            (IMPORT_DIRECTORY, 20, 12),
+            // Name RVA at offset 4 of 32-byte descriptors
+            (DELAY_IMPORT_DIRECTORY, 32, 4),
+        ];
+        for (index, size, name_at) in imports {
+            let Some(rva) = directory(index).filter(|&rva| rva != 0) else {
+                continue;
+            };
+            for dll in image.imported_dlls(rva, size, name_at)? {
+                apis.extend(GraphicsApi::from_dll(&dll));
+            }
+        }
+        Ok(Self { arch, apis })
+    }
+
+    /// Architecture and APIs recorded in a game's metadata
+    pub fn from_metadata(metadata: &HashMap<String, String>) -> Option<Self> {
+        let arch = Arch::parse(metadata.get(ARCH_KEY)?)?;
+        let apis = metadata
+            .get(APIS_KEY)
+            .map(|apis| apis.split(',').filter_map(GraphicsApi::parse).collect())
+            .unwrap_or_default();
+        Some(Self { arch, apis })
+    }
+
+    /// Record the architecture and APIs in a game's metadata
+    pub fn insert_into(&self, metadata: &mut HashMap<String, String>) {
+        metadata.insert(ARCH_KEY.into(), self.arch.to_string());
+        let apis: Vec<&str> = self.apis.iter().map(|api| api.name()).collect();
+        metadata.insert(APIS_KEY.into(), apis.join(","));
+    }
+
+    /// Architecture and APIs for display (`x86_64 (d3d11, vulkan)`)
+    pub fn describe(&self) -> String {
+        if self.apis.is_empty() {
+            return self.arch.to_string();
+        }
+        let apis: Vec<&str> = self.apis.iter().map(|api| api.name()).collect();
+        format!("{} ({})", self.arch, apis.join(", "))
+    }
+
+    /// Whether the game uses an API DXVK translates; true when no API was
+    /// found, as the game may load it from another module
+    pub fn uses_dxvk(&self) -> bool {
+        self.apis.is_empty() || self.apis.iter().any(|api| api.uses_dxvk())
+    }
+
+    /// Whether the game uses Direct3D 12; true when no API was found
+    pub fn uses_d3d12(&self) -> bool {
+        self.apis.is_empty() || self.apis.contains(&GraphicsApi::D3d12)
+    }
+}
+
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_offset: u32,
+    raw_size: u32,
+}
+
+impl Section {
+    fn parse(entry: &[u8]) -> Self {
+        Self {
+            virtual_size: u32_at(entry, 8),
+            virtual_address: u32_at(entry, 12),
+            raw_size: u32_at(entry, 16),
+            raw_offset: u32_at(entry, 20),
+        }
+    }
+}
+
+struct Image<'a, R> {
+    file: &'a mut R,
+    sections: Vec<Section>,
+}
+
+impl<R: Read + Seek> Image<'_, R> {
+    /// File offset of a relative virtual address
+    fn offset(&self, rva: u32) -> Result<u64> {
+        self.sections
+            .iter()
+            .find(|s| {
+                rva >= s.virtual_address && rva - s.virtual_address < s.virtual_size.max(s.raw_size)
+            })
+            .map(|s| u64::from(s.raw_offset) + u64::from(rva - s.virtual_address))
+            .with_context(|| format!("address {:#x} is outside every section", rva))
+    }
+
+    /// DLL names of an import table, which ends with an all-zero descriptor
+    fn imported_dlls(&mut self, rva: u32, size: usize, name_at: usize) -> Result<Vec<String>> {
+        let mut offset = self.offset(rva)?;
+        let mut dlls = Vec::new();
+        for _ in 0..MAX_IMPORTS {
+            let descriptor = read_at(self.file, offset, size)?;
+            if descriptor.iter().all(|&b| b == 0) {
+                break;
+            }
+            let name = read_at(self.file, self.offset(u32_at(&descriptor, name_at))?, 64)?;
+            let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+            dlls.push(String::from_utf8_lossy(&name[..end]).into_owned());
+            offset += size as u64;
+        }
+        Ok(dlls)
+    }
+}
+
+/// `len` bytes at `offset`, fewer at the end of the file
+fn read_at<R: Read + Seek>(file: &mut R, offset: u64, len: usize) -> Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buffer = Vec::with_capacity(len);
+    file.take(len as u64).read_to_end(&mut buffer)?;
+    if buffer.is_empty() {
+        bail!("truncated at offset {:#x}", offset);
+    }
+    // Short reads are padded, so checks on the zeros fail instead
+    buffer.resize(len, 0);
+    Ok(buffer)
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// A minimal PE32+ image with one section holding an import table
+    fn image(machine: u16, dlls: &[&str]) -> Vec<u8> {
+        let mut file = vec![0u8; 0x400];
+        file[..2].copy_from_slice(b"MZ");
+        file[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        file[0x80..0x84].copy_from_slice(b"PE\0\0");
+        file[0x84..0x86].copy_from_slice(&machine.to_le_bytes());
+        file[0x86..0x88].copy_from_slice(&1u16.to_le_bytes());
+        // 112 bytes of fields plus 16 data directories
+        let optional_size: u16 = 112 + 16 * 8;
+        file[0x94..0x96].copy_from_slice(&optional_size.to_le_bytes());
+        let optional = 0x98;
+        file[optional..optional + 2].copy_from_slice(&0x20bu16.to_le_bytes());
+        // Import directory at RVA 0x1000
+        let import_dir = optional + 112 + 8;
+        file[import_dir..import_dir + 4].copy_from_slice(&0x1000u32.to_le_bytes());
+
+        // .idata: RVA 0x1000, 0x200 bytes at file offset 0x200
+        let section = optional + usize::from(optional_size);
+        file[section..section + 6].copy_from_slice(b".idata");
+        for (at, value) in [(8, 0x200u32), (12, 0x1000), (16, 0x200), (20, 0x200)] {
+            file[section + at..section + at + 4].copy_from_slice(&value.to_le_bytes());
+        }
+        for (i, dll) in dlls.iter().enumerate() {
+            let name_rva = 0x1100 + i as u32 * 0x20;
+            let descriptor = 0x200 + i * 20;
+            file[descriptor + 12..descriptor + 16].copy_from_slice(&name_rva.to_le_bytes());
+            let name = 0x300 + i * 0x20;
+            file[name..name + dll.len()].copy_from_slice(dll.as_bytes());
+        }
+        file
+    }
+
+    #[test]
+    fn test_read_arch_and_graphics_apis() {
+        let exe = image(
+            0x8664,
+            &["KERNEL32.dll", "D3D12.dll", "dxgi.dll", "d3d11.dll"],
+        );
+        let info = PeInfo::read_from(&mut Cursor::new(exe)).unwrap();
+        assert_eq!(info.arch, Arch::X86_64);
+        assert_eq!(
+            info.apis,
+            BTreeSet::from([GraphicsApi::D3d11, GraphicsApi::D3d12])
+        );
+        assert!(info.uses_dxvk() && info.uses_d3d12());
+
+        let mut metadata = HashMap::new();
+        info.insert_into(&mut metadata);
+        assert_eq!(metadata[APIS_KEY], "d3d11,d3d12");
+        assert_eq!(PeInfo::from_metadata(&metadata), Some(info));
+
+        let old = image(0x014c, &["d3d9.dll"]);
+        let info = PeInfo::read_from(&mut Cursor::new(old)).unwrap();
+        assert_eq!(info.arch, Arch::X86);
+        assert!(!info.uses_d3d12());
+
+        assert!(PeInfo::read_from(&mut Cursor::new(b"#!/bin/sh\n".repeat(10))).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! Steam Linux Runtime (pressure-vessel) awareness
+//!
+//! When Steam wraps a game in a Steam Linux Runtime container, environment
+//! variables are passed through but the filesystem is not the host's:
+//! - `/usr`, `/lib*`, `/bin`, `/sbin` come from the runtime; the host's copies
+//!   are visible under `/run/host`
+//! - Only the home directory, `/tmp` and Steam libraries are shared by default;
+//!   other paths must be exposed with `PRESSURE_VESSEL_FILESYSTEMS_RW`
+//! - `LD_LIBRARY_PATH` and `PATH` are reset inside the container
+
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Host directories replaced by the runtime
+const RUNTIME_OWNED_DIRS: &[&str] = &["/usr", "/lib", "/lib32", "/lib64", "/bin", "/sbin"];
+
+/// Directories shared with the container by default
+const SHARED_DIRS: &[&str] = &["/tmp", "/run/host"];
+
+/// Variables pressure-vessel resets instead of passing through
+const RESET_VARS: &[&str] = &["LD_LIBRARY_PATH", "PATH"];
+
+/// Variables whose host paths pressure-vessel translates itself
+const TRANSLATED_VARS: &[&str] = &["LD_PRELOAD", "LD_AUDIT"];
+
+/// A Steam Linux Runtime container in a launch command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SteamRuntime {
+    /// Runtime suite (sniper, soldier, scout) or "unknown"
+    pub suite: String,
+}
+
+/// Detect a Steam Linux Runtime entry point in a composed launch command
+pub fn detect_container(command: &[String]) -> Option<SteamRuntime> {
+    let entry = command.iter().find(|arg| {
+        arg.contains("SteamLinuxRuntime")
+            || arg.ends_with("_v2-entry-point")
+            || arg.contains("pressure-vessel")
+    })?;
+
+    let lower = entry.to_ascii_lowercase();
+    let suite = ["sniper", "soldier", "scout"]
+        .into_iter()
+        .find(|s| lower.contains(s))
+        .unwrap_or("unknown");
+
+    Some(SteamRuntime {
+        suite: suite.to_string(),
+    })
+}
+
+/// Adjust environment variables so they work inside the container
+///
+/// Host system paths are rewritten to their `/run/host` location and other
+/// paths outside the home directory are exposed through
+/// `PRESSURE_VESSEL_FILESYSTEMS_RW`. Returns warnings for settings that
+/// cannot cross the container boundary.
+pub fn route_env(env_vars: &mut HashMap<String, String>, home: Option<&Path>) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut exposed: Vec<String> = Vec::new();
+
+    let mut keys: Vec<String> = env_vars.keys().cloned().collect();
+    keys.sort();
+
+    for key in keys {
+        if RESET_VARS.contains(&key.as_str()) {
+            warnings.push(format!(
+                "{} is reset inside the Steam Linux Runtime and will not reach the game",
+                key
+            ));
+            continue;
+        }
+        // Steam shares STEAM_COMPAT_* paths with the container itself
+        if TRANSLATED_VARS.contains(&key.as_str())
+            || key.starts_with("PRESSURE_VESSEL_")
+            || key.starts_with("STEAM_COMPAT_")
+        {
+            continue;
+        }
+
+        let value = &env_vars[&key];
+        let mut changed = false;
+        let parts: Vec<String> = value
+            .split(':')
+            .map(|part| {
+                if is_runtime_owned(part) {
+                    changed = true;
+                    format!("/run/host{}", part)
+                } else {
+                    if needs_exposing(part, home) && !exposed.iter().any(|e| e == part) {
+                        exposed.push(part.to_string());
+                    }
+                    part.to_string()
+                }
+            })
+            .collect();
+
+        if changed {
+            env_vars.insert(key, parts.join(":"));
+        }
+    }
+
+    if !exposed.is_empty() {
+        let mut filesystems: Vec<String> = env_vars
+            .get("PRESSURE_VESSEL_FILESYSTEMS_RW")
+            .map(|v| v.split(':').map(String::from).collect())
+            .unwrap_or_default();
+        for path in exposed {
+            if !filesystems.contains(&path) {
+                filesystems.push(path);
+            }
+        }
+        env_vars.insert(
+            "PRESSURE_VESSEL_FILESYSTEMS_RW".into(),
+            filesystems.join(":"),
+        );
+    }
+
+    warnings
+}
+
+fn is_runtime_owned(value: &str) -> bool {
+    RUNTIME_OWNED_DIRS
+        .iter()
+        .any(|dir| value == *dir || value.starts_with(&format!("{}/", dir)))
+}
+
+fn needs_exposing(value: &str, home: Option<&Path>) -> bool {
+    if !value.starts_with('/') {
+        return false;
+    }
+    let path = Path::new(value);
+    if home.is_some_and(|h| path.starts_with(h)) {
+        return false;
+    }
+    if SHARED_DIRS.iter().any(|dir| path.starts_with(dir)) {
+        return false;
+    }
+    // Only real filesystem locations; values like "/1" are not paths
+    path.exists()
+        || path
+            .parent()
+            .is_some_and(|p| p != Path::new("/") && p.exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmd(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_detect_sniper_container() {
+        let command = cmd(&[
+            "/home/u/.steam/steam/steamapps/common/SteamLinuxRuntime_sniper/_v2-entry-point",
+            "--verb=waitforexitandrun",
+            "--",
+            "/home/u/.steam/steam/steamapps/common/Proton 9.0/proton",
+            "waitforexitandrun",
+            "game.exe",
+        ]);
+        assert_eq!(detect_container(&command).unwrap().suite, "sniper");
+        assert!(detect_container(&cmd(&["/usr/bin/game"])).is_none());
+    }
+
+    #[test]
+    fn test_route_env() {
+        let mut env = HashMap::new();
+        env.insert(
+            "VK_ICD_FILENAMES".into(),
+            "/usr/share/vulkan/icd.d/nvidia_icd.json".into(),
+        );
+        env.insert("LD_LIBRARY_PATH".into(), "/opt/lib".into());
+        env.insert("DXVK_HUD".into(), "fps".into());
+        env.insert(
+            "DXVK_STATE_CACHE_PATH".into(),
+            "/home/u/.cache/nvproton/dxvk/1".into(),
+        );
+
+        let warnings = route_env(&mut env, Some(Path::new("/home/u")));
+        assert_eq!(
+            env["VK_ICD_FILENAMES"],
+            "/run/host/usr/share/vulkan/icd.d/nvidia_icd.json"
+        );
+        assert_eq!(env["DXVK_HUD"], "fps");
+        assert_eq!(
+            env["DXVK_STATE_CACHE_PATH"],
+            "/home/u/.cache/nvproton/dxvk/1"
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("LD_LIBRARY_PATH"));
+    }
+}
@@ -1,13 +1,15 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use glob::glob;
-use regex::Regex;
+use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
 
-use super::fingerprint;
 use super::{DetectedGame, DetectionContext, GameSource};
+use crate::steam::appinfo::LaunchConfig;
+use crate::steam::client::{SteamClient, Vdf, is_excluded_appid, parse_vdf, vdf_get};
 
 pub struct SteamDetector;
 
@@ -22,133 +24,290 @@ impl SteamDetector {
         include_fingerprint: bool,
     ) -> Result<Vec<DetectedGame>> {
         let mut games = Vec::new();
-        let steam_path = match ctx.config.library_paths.steam.as_ref() {
-            Some(path) => path.clone(),
-            None => return Ok(games),
-        };
-        if !steam_path.exists() {
+        let Some(steam) = SteamClient::from_config(ctx.config) else {
+            ctx.explain(format_args!(
+                "steam: no install at {:?}",
+                ctx.config.library_paths.steam
+            ));
             return Ok(games);
-        }
-        let library_dirs = read_library_folders(&steam_path)?;
-        for library in library_dirs {
+        };
+        let steam = steam.with_sysroot(ctx.root.clone());
+        let mut libraries = Vec::new();
+        for library in steam.libraries()? {
             let manifest_pattern = library.join("steamapps").join("appmanifest_*.acf");
-            for entry in glob(manifest_pattern.to_string_lossy().as_ref())? {
-                let path = entry?;
-                if let Some(manifest) = parse_manifest(&path)? {
-                    // Skip Steam internals (Proton, Runtime, Redistributables)
-                    if is_excluded_appid(&manifest.appid) {
-                        continue;
-                    }
+            let manifests = glob(manifest_pattern.to_string_lossy().as_ref())?
+                .collect::<Result<Vec<_>, _>>()?;
+            ctx.progress.add_total(manifests.len() as u64);
+            libraries.push((library, manifests));
+        }
 
-                    let install_dir = library
-                        .join("steamapps")
-                        .join("common")
-                        .join(&manifest.installdir);
-                    let executable = locate_primary_executable(&install_dir);
-                    let fingerprint_value = if include_fingerprint {
-                        executable
-                            .as_ref()
-                            .and_then(|exe| fingerprint::fingerprint_file(exe).ok())
-                    } else {
-                        None
-                    };
-                    let mut metadata = manifest.metadata.clone();
-                    if let Some(appid) = manifest.metadata.get("appid").cloned() {
-                        metadata.insert("appid".into(), appid);
-                    }
-                    games.push(DetectedGame {
-                        source: GameSource::Steam,
-                        id: manifest.appid,
-                        name: manifest.name,
-                        install_dir,
-                        executable,
-                        fingerprint: fingerprint_value,
-                        metadata,
-                    });
+        // Launch entries name most games' executables without walking their installs
+        let appids = libraries
+            .iter()
+            .flat_map(|(_, manifests)| manifests)
+            .filter_map(|path| path.file_stem()?.to_str()?.strip_prefix("appmanifest_"));
+        let launch_configs = steam.launch_configs_of(appids).unwrap_or_else(|e| {
+            ctx.explain(format_args!("steam: app info cache unreadable: {:#}", e));
+            BTreeMap::new()
+        });
+
+        for (library, manifests) in libraries {
+            ctx.explain(format_args!("steam: library {:?}", library));
+            for path in manifests {
+                ctx.progress
+                    .inc(1, path.file_name().unwrap_or_default().to_string_lossy());
+                let Some(manifest) = parse_manifest(&path)? else {
+                    ctx.explain(format_args!(
+                        "steam: {:?} skipped: no appid, name or installdir",
+                        path
+                    ));
+                    continue;
+                };
+                // Skip Steam internals (Proton, Runtime, Redistributables)
+                if is_excluded_appid(&manifest.appid) {
+                    ctx.explain(format_args!(
+                        "steam: {} ({}) skipped: Steam tool or runtime, not a game",
+                        manifest.name, manifest.appid
+                    ));
+                    continue;
+                }
+
+                let install_dir = library
+                    .join("steamapps")
+                    .join("common")
+                    .join(&manifest.installdir);
+                ctx.explain(format_args!(
+                    "steam: {} ({}) in {:?}",
+                    manifest.name, manifest.appid, install_dir
+                ));
+                let launch = launch_configs.get(&manifest.appid);
+                let executable = primary_executable(ctx, launch, &install_dir);
+                let mut metadata = manifest.metadata.clone();
+                let dlc = manifest.installed_dlc();
+                if !dlc.is_empty() {
+                    metadata.insert("installed_dlc".into(), dlc.join(","));
                 }
+                let fingerprint_value = if include_fingerprint {
+                    ctx.fingerprint(&manifest.name, executable.as_deref(), &mut metadata)
+                } else {
+                    None
+                };
+                games.push(DetectedGame {
+                    source: GameSource::Steam,
+                    id: manifest.appid,
+                    name: manifest.name,
+                    install_dir,
+                    executable,
+                    fingerprint: fingerprint_value,
+                    metadata,
+                });
             }
         }
         Ok(games)
     }
 }
 
-struct Manifest {
-    appid: String,
-    name: String,
-    installdir: String,
-    metadata: std::collections::HashMap<String, String>,
+/// A parsed `appmanifest_<appid>.acf`
+#[derive(Debug, Clone)]
+pub struct AppManifest {
+    pub appid: String,
+    pub name: String,
+    pub installdir: String,
+    /// Top-level `AppState` values (StateFlags, buildid, SizeOnDisk, ...)
+    pub metadata: HashMap<String, String>,
+    pub depots: Vec<Depot>,
 }
 
-fn read_library_folders(steam_root: &Path) -> Result<Vec<PathBuf>> {
-    let library_file = steam_root.join("steamapps").join("libraryfolders.vdf");
-    if !library_file.exists() {
-        return Ok(vec![steam_root.to_path_buf()]);
+/// An installed depot listed under `InstalledDepots`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Depot {
+    pub id: String,
+    pub manifest: String,
+    pub size: u64,
+    /// Set when the depot belongs to a DLC
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dlc_appid: Option<String>,
+}
+
+impl AppManifest {
+    /// Read the manifest of an installed app
+    ///
+    /// The manifest lives in the library's `steamapps` directory, two levels
+    /// above the install directory (`steamapps/common/<installdir>`).
+    pub fn locate(install_dir: &Path, appid: &str) -> Option<Self> {
+        let steamapps = install_dir.parent()?.parent()?;
+        parse_manifest(&steamapps.join(format!("appmanifest_{}.acf", appid)))
+            .ok()
+            .flatten()
     }
-    let content = fs::read_to_string(&library_file)
-        .with_context(|| format!("failed to read {:?}", library_file))?;
-    let regex = Regex::new(r#"path"\s+"([^"]+)"#)?;
-    let mut directories = vec![steam_root.to_path_buf()];
-    for captures in regex.captures_iter(&content) {
-        let path = captures.get(1).map(|m| m.as_str()).unwrap_or_default();
-        let expanded = PathBuf::from(path.replace("\\", "/"));
-        if expanded.exists() {
-            directories.push(expanded);
-        }
+
+    /// AppIDs of installed DLC
+    pub fn installed_dlc(&self) -> Vec<String> {
+        let mut dlc: Vec<String> = self
+            .depots
+            .iter()
+            .filter_map(|d| d.dlc_appid.clone())
+            .collect();
+        dlc.sort();
+        dlc.dedup();
+        dlc
+    }
+
+    pub fn install_state(&self) -> InstallState {
+        InstallState::from_metadata(&self.metadata)
     }
-    directories.sort();
-    directories.dedup();
-    Ok(directories)
 }
 
-fn parse_manifest(path: &Path) -> Result<Option<Manifest>> {
+pub(crate) fn parse_manifest(path: &Path) -> Result<Option<AppManifest>> {
     let content = fs::read_to_string(path)
         .with_context(|| format!("failed to read steam manifest at {:?}", path))?;
-    let mut appid = None;
-    let mut name = None;
-    let mut installdir = None;
-    let mut metadata = std::collections::HashMap::new();
-    let key_regex = Regex::new(r#"(?P<key>[^"]+)"\s+"(?P<value>[^"]*)"#)?;
-    for captures in key_regex.captures_iter(&content) {
-        let key = captures.name("key").unwrap().as_str();
-        let value = captures.name("value").unwrap().as_str().to_string();
-        match key {
-            "appid" => appid = Some(value.clone()),
-            "name" => name = Some(value.clone()),
-            "installdir" => installdir = Some(value.clone()),
-            _ => {}
+    Ok(manifest_from_vdf(&parse_vdf(&content)))
+}
+
+fn manifest_from_vdf(root: &[(String, Vdf)]) -> Option<AppManifest> {
+    let app_state = vdf_get(root, "AppState")?.section()?;
+    let metadata: HashMap<String, String> = app_state
+        .iter()
+        .filter_map(|(key, value)| Some((key.clone(), value.value()?.to_string())))
+        .collect();
+    let depots = vdf_get(app_state, "InstalledDepots")
+        .and_then(Vdf::section)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|(id, depot)| {
+            let depot = depot.section()?;
+            let value = |key: &str| vdf_get(depot, key).and_then(Vdf::value);
+            Some(Depot {
+                id: id.clone(),
+                manifest: value("manifest").unwrap_or_default().to_string(),
+                size: value("size").and_then(|s| s.parse().ok()).unwrap_or(0),
+                dlc_appid: value("dlcappid").map(String::from),
+            })
+        })
+        .collect();
+
+    let value = |key: &str| {
+        vdf_get(app_state, key)
+            .and_then(Vdf::value)
+            .map(String::from)
+    };
+    Some(AppManifest {
+        appid: value("appid")?,
+        name: value("name")?,
+        installdir: value("installdir")?,
+        metadata,
+        depots,
+    })
+}
+
+/// `StateFlags` bits from an appmanifest
+const STATE_UPDATE_REQUIRED: u32 = 0x2;
+const STATE_FULLY_INSTALLED: u32 = 0x4;
+const STATE_UPDATE_RUNNING: u32 = 0x100;
+const STATE_UPDATE_PAUSED: u32 = 0x200;
+const STATE_UPDATE_STARTED: u32 = 0x400;
+
+/// Install state of a Steam app as recorded in its appmanifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstallState {
+    pub state_flags: u32,
+    pub bytes_to_download: u64,
+    pub bytes_downloaded: u64,
+}
+
+impl InstallState {
+    /// Read the current state from the app's appmanifest
+    pub fn read(install_dir: &Path, appid: &str) -> Option<Self> {
+        AppManifest::locate(install_dir, appid).map(|m| m.install_state())
+    }
+
+    pub fn from_metadata(metadata: &HashMap<String, String>) -> Self {
+        let number = |key: &str| metadata.get(key).and_then(|v| v.parse::<u64>().ok());
+        Self {
+            state_flags: number("StateFlags").unwrap_or(0) as u32,
+            bytes_to_download: number("BytesToDownload").unwrap_or(0),
+            bytes_downloaded: number("BytesDownloaded").unwrap_or(0),
+        }
+    }
+
+    /// Steam has an update queued or in progress for the app
+    pub fn update_pending(&self) -> bool {
+        self.state_flags
+            & (STATE_UPDATE_REQUIRED
+                | STATE_UPDATE_RUNNING
+                | STATE_UPDATE_PAUSED
+                | STATE_UPDATE_STARTED)
+            != 0
+            || self.bytes_downloaded < self.bytes_to_download
+    }
+
+    /// Steam is actively downloading or applying the update
+    pub fn updating(&self) -> bool {
+        self.state_flags & (STATE_UPDATE_RUNNING | STATE_UPDATE_STARTED) != 0
+    }
+
+    pub fn fully_installed(&self) -> bool {
+        self.state_flags & STATE_FULLY_INSTALLED != 0 && !self.update_pending()
+    }
+
+    /// Bytes left to download for a pending update
+    pub fn remaining_bytes(&self) -> u64 {
+        self.bytes_to_download.saturating_sub(self.bytes_downloaded)
+    }
+
+    pub fn describe(&self) -> &'static str {
+        if self.fully_installed() {
+            "installed"
+        } else if self.updating() {
+            "updating"
+        } else if self.state_flags & STATE_UPDATE_PAUSED != 0 {
+            "update paused"
+        } else if self.update_pending() {
+            "update required"
+        } else {
+            "not fully installed"
         }
-        metadata.insert(key.to_string(), value);
     }
-    Ok(match (appid, name, installdir) {
-        (Some(appid), Some(name), Some(installdir)) => Some(Manifest {
-            appid,
-            name,
-            installdir,
-            metadata,
-        }),
-        _ => None,
-    })
 }
 
-/// AppIDs that are Steam internals, not actual games
-const EXCLUDED_APPIDS: &[&str] = &[
-    "228980",  // Steamworks Common Redistributables
-    "1493710", // Proton Experimental
-    "1628350", // Steam Linux Runtime 3.0 (sniper)
-    "1887720", // Proton 8.0
-    "2180100", // Proton 9.0
-    "2348590", // Proton 9.0 (another)
-    "3658110", // Proton 10.0
-    "1391110", // Steam Linux Runtime
-    "2805730", // Steam Linux Runtime (soldier)
-];
-
-pub fn is_excluded_appid(appid: &str) -> bool {
-    EXCLUDED_APPIDS.contains(&appid)
+/// A game's executable, trying the cheapest source first:
+/// 1. Steam's first Windows launch entry whose executable is installed
+/// 2. the last walk of the install directory, while it is unchanged
+/// 3. walking the install directory
+fn primary_executable(
+    ctx: &DetectionContext<'_>,
+    launch: Option<&Vec<LaunchConfig>>,
+    install_dir: &Path,
+) -> Option<PathBuf> {
+    let launch_exe = launch.into_iter().flatten().find_map(|config| {
+        let exe = install_dir.join(&config.executable);
+        let windows = exe
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"));
+        let default = matches!(config.kind.as_deref(), None | Some("default"));
+        (config.runs_here() && default && windows && exe.is_file()).then_some(exe)
+    });
+    if let Some(exe) = launch_exe {
+        ctx.explain(format_args!(
+            "  {:?} from Steam's launch options",
+            relative(&exe, install_dir)
+        ));
+        return Some(exe);
+    }
+    if let Some(exe) = ctx.executables.borrow().lookup(install_dir) {
+        ctx.explain("  install directory unchanged since the last scan, keeping its executable");
+        return exe;
+    }
+    let exe = locate_primary_executable(ctx, install_dir);
+    ctx.executables
+        .borrow_mut()
+        .insert(install_dir, exe.as_deref());
+    exe
 }
 
-fn locate_primary_executable(install_dir: &Path) -> Option<PathBuf> {
+fn locate_primary_executable(ctx: &DetectionContext<'_>, install_dir: &Path) -> Option<PathBuf> {
     if !install_dir.exists() {
+        ctx.explain("  install directory missing, no executable");
         return None;
     }
 
@@ -177,7 +336,12 @@ fn locate_primary_executable(install_dir: &Path) -> Option<PathBuf> {
                 .to_lowercase();
 
             // Skip known non-game executables
-            if is_launcher_or_tool(&filename) {
+            if let Some(pattern) = launcher_or_tool_pattern(&filename) {
+                ctx.explain(format_args!(
+                    "  skipped {:?}: name contains '{}'",
+                    relative(path, install_dir),
+                    pattern
+                ));
                 continue;
             }
 
@@ -186,17 +350,45 @@ fn locate_primary_executable(install_dir: &Path) -> Option<PathBuf> {
     }
 
     // Prioritize executables by likelihood of being the main game
-    exe_candidates.sort_by(|a, b| {
-        let a_score = score_executable(a, install_dir);
-        let b_score = score_executable(b, install_dir);
-        b_score.cmp(&a_score) // Higher score first
-    });
+    let mut scored: Vec<(PathBuf, Vec<(&'static str, i32)>)> = exe_candidates
+        .into_iter()
+        .map(|exe| {
+            let reasons = score_executable(&exe, install_dir);
+            (exe, reasons)
+        })
+        .collect();
+    let total = |reasons: &[(&str, i32)]| reasons.iter().map(|(_, points)| points).sum::<i32>();
+    scored.sort_by_key(|(_, reasons)| std::cmp::Reverse(total(reasons))); // Higher score first
+
+    if scored.is_empty() {
+        ctx.explain("  no .exe candidates");
+    }
+    for (exe, reasons) in &scored {
+        let breakdown: Vec<String> = reasons
+            .iter()
+            .map(|(reason, points)| format!("{} {:+}", reason, points))
+            .collect();
+        ctx.explain(format_args!(
+            "  {:>4} {:?} ({})",
+            total(reasons),
+            relative(exe, install_dir),
+            if breakdown.is_empty() {
+                "no heuristic matched".to_string()
+            } else {
+                breakdown.join(", ")
+            }
+        ));
+    }
+
+    scored.into_iter().next().map(|(exe, _)| exe)
+}
 
-    exe_candidates.into_iter().next()
+fn relative<'a>(path: &'a Path, dir: &Path) -> &'a Path {
+    path.strip_prefix(dir).unwrap_or(path)
 }
 
-/// Check if executable is a launcher/tool rather than the main game
-fn is_launcher_or_tool(filename: &str) -> bool {
+/// Name pattern marking an executable as a launcher/tool rather than the main game
+fn launcher_or_tool_pattern(filename: &str) -> Option<&'static str> {
     const SKIP_PATTERNS: &[&str] = &[
         "unins",
         "uninst",
@@ -228,17 +420,16 @@ fn is_launcher_or_tool(filename: &str) -> bool {
         "epic",
     ];
 
-    for pattern in SKIP_PATTERNS {
-        if filename.contains(pattern) {
-            return true;
-        }
-    }
-    false
+    SKIP_PATTERNS
+        .iter()
+        .find(|pattern| filename.contains(*pattern))
+        .copied()
 }
 
-/// Score an executable by how likely it is to be the main game
-fn score_executable(path: &Path, install_dir: &Path) -> i32 {
-    let mut score = 0;
+/// Score an executable by how likely it is to be the main game, as the
+/// points each heuristic adds
+fn score_executable(path: &Path, install_dir: &Path) -> Vec<(&'static str, i32)> {
+    let mut score = Vec::new();
 
     let filename = path
         .file_name()
@@ -259,7 +450,7 @@ fn score_executable(path: &Path, install_dir: &Path) -> i32 {
         .unwrap_or("")
         .to_lowercase();
     if dirname.contains(&exe_stem) || exe_stem.contains(&dirname.replace(" ", "")) {
-        score += 50;
+        score.push(("name matches directory", 50));
     }
 
     // Bonus: in root or bin directory (not deep subdirectories)
@@ -268,40 +459,112 @@ fn score_executable(path: &Path, install_dir: &Path) -> i32 {
         .map(|p| p.components().count())
         .unwrap_or(10);
     if depth <= 1 {
-        score += 30;
+        score.push(("in install root", 30));
     } else if depth == 2 {
         // Common pattern: game/bin/game.exe
         if let Some(parent) = path.parent() {
             let parent_name = parent.file_name().and_then(|s| s.to_str()).unwrap_or("");
             if parent_name == "bin" || parent_name == "Binaries" || parent_name == "x64" {
-                score += 25;
+                score.push(("in binary directory", 25));
             }
         }
     }
 
     // Bonus: common game executable patterns
     if filename.ends_with("-win64-shipping.exe") || filename.ends_with("_win64.exe") {
-        score += 20;
+        score.push(("shipping build", 20));
     }
     if filename.contains("game") || filename.contains("client") {
-        score += 10;
+        score.push(("game/client name", 10));
     }
 
     // Penalty: likely not the main game
     if filename.contains("server") && !filename.contains("dedicated") {
-        score -= 10;
+        score.push(("server", -10));
     }
 
     // Penalty: very small files are usually not the game
     if let Ok(metadata) = path.metadata() {
         if metadata.len() < 1_000_000 {
             // Less than 1MB
-            score -= 20;
+            score.push(("under 1 MB", -20));
         } else if metadata.len() > 50_000_000 {
             // Over 50MB, likely the real game
-            score += 15;
+            score.push(("over 50 MB", 15));
         }
     }
 
     score
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest_depots_and_dlc() {
+        let acf = r#"
+            "AppState"
+            {
+                "appid"		"1245620"
+                "name"		"ELDEN RING"
+                "StateFlags"		"4"
+                "installdir"		"ELDEN RING"
+                "InstalledDepots"
+                {
+                    "1245621"
+                    {
+                        "manifest"		"3417381390497386526"
+                        "size"		"49152000000"
+                    }
+                    "2778581"
+                    {
+                        "manifest"		"1146370617043935582"
+                        "size"		"18000000000"
+                        "dlcappid"		"2778580"
+                    }
+                }
+                "UserConfig"
+                {
+                    "language"		"english"
+                }
+            }
+        "#;
+        let manifest = manifest_from_vdf(&parse_vdf(acf)).unwrap();
+        assert_eq!(manifest.appid, "1245620");
+        assert_eq!(manifest.installdir, "ELDEN RING");
+        assert_eq!(manifest.depots.len(), 2);
+        assert_eq!(manifest.depots[0].size, 49_152_000_000);
+        assert_eq!(manifest.installed_dlc(), vec!["2778580"]);
+        assert!(manifest.install_state().fully_installed());
+        // Nested values don't leak into the top-level metadata
+        assert!(!manifest.metadata.contains_key("language"));
+        assert!(!manifest.metadata.contains_key("manifest"));
+    }
+
+    #[test]
+    fn test_install_state_flags() {
+        let metadata = |flags: &str, to_download: &str, downloaded: &str| {
+            [
+                ("StateFlags", flags),
+                ("BytesToDownload", to_download),
+                ("BytesDownloaded", downloaded),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+        };
+
+        let installed = InstallState::from_metadata(&metadata("4", "1000", "1000"));
+        assert!(installed.fully_installed());
+        assert!(!installed.update_pending());
+
+        let queued = InstallState::from_metadata(&metadata("6", "0", "0"));
+        assert!(queued.update_pending());
+        assert!(!queued.updating());
+
+        let downloading = InstallState::from_metadata(&metadata("1026", "5000", "1000"));
+        assert_eq!(downloading.describe(), "updating");
+        assert_eq!(downloading.remaining_bytes(), 4000);
+    }
+}
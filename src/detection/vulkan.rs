@@ -15,8 +15,11 @@ use std::ffi::CStr;
 /// NVIDIA vendor ID
 const NVIDIA_VENDOR_ID: u32 = 0x10DE;
 
+/// First driver branch with Smooth Motion (driver frame interpolation) on Linux
+pub const SMOOTH_MOTION_MIN_DRIVER: u32 = 575;
+
 /// Vulkan capabilities relevant for NVIDIA + vkd3d-proton
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct VulkanCapabilities {
     /// Driver version string (e.g., "595.45.04")
     pub driver_version: String,
@@ -38,23 +41,8 @@ pub struct VulkanCapabilities {
     pub gpu_name: String,
     /// Whether this is an NVIDIA GPU
     pub is_nvidia: bool,
-}
-
-impl Default for VulkanCapabilities {
-    fn default() -> Self {
-        Self {
-            driver_version: String::new(),
-            driver_branch: 0,
-            descriptor_heap: false,
-            descriptor_buffer: false,
-            raw_access_chains: false,
-            low_latency2: false,
-            extended_sparse_address_space: false,
-            present_timing: false,
-            gpu_name: String::new(),
-            is_nvidia: false,
-        }
-    }
+    /// Index of the GPU in Vulkan device enumeration order
+    pub device_index: u32,
 }
 
 impl VulkanCapabilities {
@@ -64,8 +52,7 @@ impl VulkanCapabilities {
         let entry = unsafe { ash::Entry::load() }.context("Failed to load Vulkan library")?;
 
         // Create minimal instance without extensions
-        let app_info = vk::ApplicationInfo::default()
-            .api_version(vk::make_api_version(0, 1, 3, 0));
+        let app_info = vk::ApplicationInfo::default().api_version(vk::make_api_version(0, 1, 3, 0));
 
         let create_info = vk::InstanceCreateInfo::default().application_info(&app_info);
 
@@ -78,12 +65,13 @@ impl VulkanCapabilities {
 
         let mut capabilities = VulkanCapabilities::default();
 
-        for device in devices {
+        for (index, device) in devices.into_iter().enumerate() {
             let props = unsafe { instance.get_physical_device_properties(device) };
 
             // Check if this is an NVIDIA GPU
             if props.vendor_id == NVIDIA_VENDOR_ID {
                 capabilities.is_nvidia = true;
+                capabilities.device_index = index as u32;
 
                 // Extract GPU name
                 capabilities.gpu_name = unsafe {
@@ -104,14 +92,12 @@ impl VulkanCapabilities {
                 capabilities.driver_branch = major;
 
                 // Enumerate device extensions
-                let extensions = unsafe {
-                    instance.enumerate_device_extension_properties(device)
-                }
-                .context("Failed to enumerate device extensions")?;
+                let extensions = unsafe { instance.enumerate_device_extension_properties(device) }
+                    .context("Failed to enumerate device extensions")?;
 
                 for ext in extensions {
-                    let name = unsafe { CStr::from_ptr(ext.extension_name.as_ptr()) }
-                        .to_string_lossy();
+                    let name =
+                        unsafe { CStr::from_ptr(ext.extension_name.as_ptr()) }.to_string_lossy();
 
                     match name.as_ref() {
                         "VK_EXT_descriptor_heap" => capabilities.descriptor_heap = true,
@@ -156,6 +142,17 @@ impl VulkanCapabilities {
         self.extended_sparse_address_space
     }
 
+    /// Check if the GPU is Ada (RTX 40 series) or newer, as Frame Generation
+    /// and Smooth Motion require
+    pub fn is_ada_or_newer(&self) -> bool {
+        crate::nvapi::is_frame_gen_capable(&self.gpu_name)
+    }
+
+    /// Check if Smooth Motion is available (575+ on RTX 40 series or newer)
+    pub fn supports_smooth_motion(&self) -> bool {
+        self.driver_branch >= SMOOTH_MOTION_MIN_DRIVER && self.is_ada_or_newer()
+    }
+
     /// Check if this is a beta driver
     /// Beta branches: 580.x (first descriptor_heap), 595.x (heap fix)
     /// Stable branches: 5x0.x where x is even (e.g., 560, 570, 590)
@@ -167,10 +164,10 @@ impl VulkanCapabilities {
         let branch = self.driver_branch;
 
         // Known beta branches
-        if branch >= 580 && branch < 590 {
+        if (580..590).contains(&branch) {
             return true; // 580.x beta series
         }
-        if branch >= 595 && branch < 600 {
+        if (595..600).contains(&branch) {
             return true; // 595.x beta series (current)
         }
 
@@ -355,6 +352,7 @@ impl std::fmt::Display for VulkanCapabilities {
 }
 
 #[cfg(test)]
+#[allow(clippy::field_reassign_with_default)]
 mod tests {
     use super::*;
 
@@ -384,6 +382,25 @@ mod tests {
         assert!(!caps.parse_version_ge(580, 94, 16));
     }
 
+    #[test]
+    fn test_smooth_motion_support() {
+        let mut caps = VulkanCapabilities::default();
+        caps.gpu_name = "NVIDIA GeForce RTX 4070 Laptop GPU".into();
+        caps.driver_branch = 575;
+        assert!(caps.supports_smooth_motion());
+
+        caps.driver_branch = 570;
+        assert!(!caps.supports_smooth_motion());
+
+        caps.driver_branch = 580;
+        caps.gpu_name = "NVIDIA RTX 2000 Ada Generation".into();
+        assert!(caps.is_ada_or_newer());
+        caps.gpu_name = "NVIDIA GeForce RTX 3080".into();
+        assert!(!caps.supports_smooth_motion());
+        caps.gpu_name = "NVIDIA RTX A6000".into();
+        assert!(!caps.is_ada_or_newer());
+    }
+
     #[test]
     fn test_beta_driver_detection() {
         let mut caps = VulkanCapabilities::default();
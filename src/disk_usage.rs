@@ -0,0 +1,320 @@
+//! Per-game disk footprint
+//!
+//! A game's data is scattered: the install directory, its Wine prefix
+//! (Steam `compatdata/<appid>` or Heroic's `winePrefix`) and shader caches
+//! (nvproton's per-game DXVK/vkd3d/GL caches plus Steam's
+//! `shadercache/<appid>`). `nvproton games du` sums all of them.
+//!
+//! Directory walks run in parallel and their results are cached in the
+//! state directory (`disk-usage.yaml`), keyed by path and reused for an hour
+//! while the directory's modification time is unchanged. The time only
+//! covers the directory's own entries, so files changing deeper down show
+//! up once the entry expires (or with `--refresh`).
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{self, CachePaths};
+use crate::config::{ConfigPaths, NvConfig};
+use crate::detection::{DetectedGame, GameSource};
+use crate::term::Progress;
+
+/// Disk usage of one game
+#[derive(Debug, Clone, Serialize)]
+pub struct GameUsage {
+    pub game_id: String,
+    pub name: String,
+    pub install: u64,
+    pub prefix: u64,
+    pub shader_cache: u64,
+}
+
+impl GameUsage {
+    pub fn total(&self) -> u64 {
+        self.install + self.prefix + self.shader_cache
+    }
+}
+
+/// Directories belonging to a game, grouped by kind
+#[derive(Debug, Clone, Default)]
+struct GameLocations {
+    install: Vec<PathBuf>,
+    prefix: Vec<PathBuf>,
+    shader_cache: Vec<PathBuf>,
+}
+
+impl GameLocations {
+    fn collect(config: &NvConfig, game: &DetectedGame) -> Self {
+        let cache_paths = CachePaths::new();
+        let mut locations = Self {
+            install: vec![game.install_dir.clone()],
+            ..Self::default()
+        };
+        for (_, path) in cache_paths.find_game_dirs(&game.game_id()) {
+            locations.shader_cache.push(path);
+        }
+
+        match game.source {
+            GameSource::Steam => {
+                // install_dir is <library>/steamapps/common/<dir>
+                if let Some(steamapps) = game.install_dir.parent().and_then(Path::parent) {
+                    locations
+                        .prefix
+                        .push(steamapps.join("compatdata").join(&game.id));
+                    locations
+                        .shader_cache
+                        .push(steamapps.join("shadercache").join(&game.id));
+                }
+            }
+            GameSource::Heroic => {
+                if let (Some(heroic_root), Some(app_name)) = (
+                    config.library_paths.heroic.as_ref(),
+                    game.metadata.get("app_name"),
+                ) && let Some(prefix) = heroic_prefix(heroic_root, app_name)
+                {
+                    locations.prefix.push(prefix);
+                }
+            }
+            _ => {}
+        }
+        locations
+    }
+
+    fn all(&self) -> impl Iterator<Item = &PathBuf> {
+        self.install
+            .iter()
+            .chain(&self.prefix)
+            .chain(&self.shader_cache)
+    }
+}
+
+/// Wine prefix Heroic configured for a game (`GamesConfig/<app_name>.json`)
+pub(crate) fn heroic_prefix(heroic_root: &Path, app_name: &str) -> Option<PathBuf> {
+    let path = heroic_root
+        .join("GamesConfig")
+        .join(format!("{}.json", app_name));
+    let contents = fs::read_to_string(path).ok()?;
+    let value: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    value
+        .get(app_name)?
+        .get("winePrefix")?
+        .as_str()
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+}
+
+/// How long a measured size is reused
+const SIZE_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedSize {
+    /// Directory modification time (unix seconds) when measured
+    modified: u64,
+    /// When the directory was walked (unix seconds)
+    #[serde(default)]
+    measured: u64,
+    size: u64,
+}
+
+/// Directory sizes from previous runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SizeCache {
+    #[serde(default)]
+    entries: BTreeMap<PathBuf, CachedSize>,
+}
+
+impl SizeCache {
+    fn path(paths: &ConfigPaths) -> PathBuf {
+        paths.state_dir.join("disk-usage.yaml")
+    }
+
+    pub fn load(paths: &ConfigPaths) -> Result<Self> {
+        let path = Self::path(paths);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read disk usage cache {:?}", path))?;
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("failed to parse disk usage cache {:?}", path))
+    }
+
+    pub fn save(&self, paths: &ConfigPaths) -> Result<()> {
+        let path = Self::path(paths);
+        fs::create_dir_all(&paths.state_dir)
+            .with_context(|| format!("failed to create directory {:?}", paths.state_dir))?;
+        fs::write(&path, serde_yaml::to_string(self)?)
+            .with_context(|| format!("failed to write disk usage cache {:?}", path))
+    }
+
+    fn lookup(&self, path: &Path) -> Option<u64> {
+        let cached = self.entries.get(path)?;
+        let fresh = now_secs().saturating_sub(cached.measured) < SIZE_CACHE_TTL.as_secs();
+        (fresh && modified_secs(path)? == cached.modified).then_some(cached.size)
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn modified_secs(path: &Path) -> Option<u64> {
+    fs::metadata(path)
+        .ok()?
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
+}
+
+/// Measure every location of the given games
+///
+/// Uncached directories are walked on a pool of worker threads, counted
+/// on `progress`. With `refresh`, cached sizes are ignored.
+pub fn measure(
+    config: &NvConfig,
+    games: &[DetectedGame],
+    cache: &mut SizeCache,
+    refresh: bool,
+    progress: &Progress,
+) -> Vec<GameUsage> {
+    let locations: Vec<GameLocations> = games
+        .iter()
+        .map(|game| GameLocations::collect(config, game))
+        .collect();
+
+    let mut sizes: BTreeMap<PathBuf, u64> = BTreeMap::new();
+    let mut pending: Vec<PathBuf> = Vec::new();
+    for path in locations.iter().flat_map(GameLocations::all) {
+        if sizes.contains_key(path) || pending.contains(path) {
+            continue;
+        }
+        if !path.exists() {
+            sizes.insert(path.clone(), 0);
+        } else if let Some(size) = cache.lookup(path).filter(|_| !refresh) {
+            sizes.insert(path.clone(), size);
+        } else {
+            pending.push(path.clone());
+        }
+    }
+
+    progress.add_total(pending.len() as u64);
+    let measured = now_secs();
+    for (path, size) in walk_parallel(pending, progress) {
+        if let Some(modified) = modified_secs(&path) {
+            let cached = CachedSize {
+                modified,
+                measured,
+                size,
+            };
+            cache.entries.insert(path.clone(), cached);
+        }
+        sizes.insert(path, size);
+    }
+
+    let sum = |paths: &[PathBuf]| paths.iter().map(|p| sizes[p]).sum();
+    games
+        .iter()
+        .zip(&locations)
+        .map(|(game, loc)| GameUsage {
+            game_id: game.id.clone(),
+            name: game.name.clone(),
+            install: sum(&loc.install),
+            prefix: sum(&loc.prefix),
+            shader_cache: sum(&loc.shader_cache),
+        })
+        .collect()
+}
+
+/// Size several directories concurrently
+fn walk_parallel(paths: Vec<PathBuf>, progress: &Progress) -> Vec<(PathBuf, u64)> {
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .min(paths.len().max(1));
+    let queue = Mutex::new(paths);
+    let results = Mutex::new(Vec::new());
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                loop {
+                    let Some(path) = queue.lock().unwrap().pop() else {
+                        break;
+                    };
+                    progress.set_detail(path.display());
+                    let size = cache::directory_size(&path);
+                    progress.inc(1, path.display());
+                    results.lock().unwrap().push((path, size));
+                }
+            });
+        }
+    });
+
+    results.into_inner().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walk_parallel_and_cache_lookup() {
+        let dir = tempfile::tempdir().unwrap();
+        let game = dir.path().join("game");
+        fs::create_dir_all(game.join("data")).unwrap();
+        fs::write(game.join("game.exe"), vec![0u8; 100]).unwrap();
+        fs::write(game.join("data").join("pak0"), vec![0u8; 400]).unwrap();
+
+        let progress = Progress::hidden();
+        let results = walk_parallel(vec![game.clone(), dir.path().join("missing")], &progress);
+        let size = results.iter().find(|(p, _)| *p == game).unwrap().1;
+        assert_eq!(size, 500);
+        assert_eq!(progress.position().0, 2);
+
+        let mut cache = SizeCache::default();
+        let cached = CachedSize {
+            modified: modified_secs(&game).unwrap(),
+            measured: now_secs(),
+            size: 42,
+        };
+        cache.entries.insert(game.clone(), cached.clone());
+        assert_eq!(cache.lookup(&game), Some(42));
+
+        // Changes below the top directory don't touch its mtime, so old
+        // measurements expire
+        let expired = CachedSize {
+            measured: now_secs() - SIZE_CACHE_TTL.as_secs(),
+            ..cached
+        };
+        cache.entries.insert(game.clone(), expired);
+        assert_eq!(cache.lookup(&game), None);
+    }
+
+    #[test]
+    fn test_heroic_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("GamesConfig")).unwrap();
+        fs::write(
+            dir.path().join("GamesConfig").join("Quail.json"),
+            r#"{"Quail": {"winePrefix": "/games/Prefixes/Quail"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            heroic_prefix(dir.path(), "Quail"),
+            Some(PathBuf::from("/games/Prefixes/Quail"))
+        );
+        assert_eq!(heroic_prefix(dir.path(), "Other"), None);
+    }
+}
@@ -0,0 +1,518 @@
+//! Per-game DXVK / vkd3d-proton version overrides
+//!
+//! `nvproton dxvk install <version> --game <id>` downloads a release from
+//! GitHub, copies its DLLs into the game's Wine prefix (keeping the original
+//! DLLs as `*.nvproton-orig`) and records the override in the state directory
+//! (`graphics-overrides.yaml`). Launches add matching `WINEDLLOVERRIDES` so
+//! Wine loads the native DLLs.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{DxvkArgs, DxvkCommand, DxvkInstallArgs, DxvkRemoveArgs};
+use crate::config::{ConfigManager, ConfigPaths, NvConfig};
+use crate::detection::{DetectedGame, GameDatabase, GameId, GameSource};
+
+/// Suffix for prefix DLLs replaced by an override
+const BACKUP_SUFFIX: &str = "nvproton-orig";
+
+/// Graphics translation layer that can be overridden per game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Component {
+    Dxvk,
+    Vkd3dProton,
+}
+
+impl Component {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Component::Dxvk => "dxvk",
+            Component::Vkd3dProton => "vkd3d-proton",
+        }
+    }
+
+    fn from_flag(vkd3d: bool) -> Self {
+        if vkd3d {
+            Component::Vkd3dProton
+        } else {
+            Component::Dxvk
+        }
+    }
+
+    /// Release archive name for a version
+    fn archive(&self, version: &str) -> String {
+        match self {
+            Component::Dxvk => format!("dxvk-{}.tar.gz", version),
+            Component::Vkd3dProton => format!("vkd3d-proton-{}.tar.zst", version),
+        }
+    }
+
+    fn release_url(&self, version: &str) -> String {
+        let repo = match self {
+            Component::Dxvk => "doitsujin/dxvk",
+            Component::Vkd3dProton => "HansKristian-Work/vkd3d-proton",
+        };
+        format!(
+            "https://github.com/{}/releases/download/v{}/{}",
+            repo,
+            version,
+            self.archive(version)
+        )
+    }
+
+    /// Archive subdirectory and prefix system directory for each architecture
+    fn arch_dirs(&self) -> [(&'static str, &'static str); 2] {
+        match self {
+            Component::Dxvk => [("x64", "system32"), ("x32", "syswow64")],
+            Component::Vkd3dProton => [("x64", "system32"), ("x86", "syswow64")],
+        }
+    }
+}
+
+/// An installed override
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphicsOverride {
+    pub component: Component,
+    pub version: String,
+    pub prefix: PathBuf,
+    /// DLL names without extension (e.g., d3d11, dxgi)
+    pub dlls: Vec<String>,
+    /// Unix timestamp of the install
+    pub installed_at: u64,
+}
+
+impl GraphicsOverride {
+    /// `WINEDLLOVERRIDES` entry that makes Wine prefer the installed DLLs
+    pub fn dll_overrides(&self) -> String {
+        format!("{}=n,b", self.dlls.join(","))
+    }
+}
+
+/// All recorded overrides, keyed by `source:id`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OverrideStore {
+    pub games: BTreeMap<String, Vec<GraphicsOverride>>,
+}
+
+impl OverrideStore {
+    fn path(paths: &ConfigPaths) -> PathBuf {
+        paths.state_dir.join("graphics-overrides.yaml")
+    }
+
+    /// Load the overrides, moving bare-ID entries to the game they belong to
+    pub fn load(paths: &ConfigPaths, db: &GameDatabase) -> Result<Self> {
+        let path = Self::path(paths);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read graphics overrides {:?}", path))?;
+        let mut store: Self = serde_yaml::from_str(&content)
+            .with_context(|| format!("failed to parse graphics overrides {:?}", path))?;
+        store.migrate(db);
+        Ok(store)
+    }
+
+    /// Key entries written before games were keyed by source by their game,
+    /// unless several games share the ID
+    fn migrate(&mut self, db: &GameDatabase) {
+        let legacy: Vec<_> = self
+            .games
+            .keys()
+            .filter_map(|key| Some((key.clone(), db.legacy_owner(key)?.to_string())))
+            .collect();
+        for (bare, game_id) in legacy {
+            let entries = self.games.remove(&bare).unwrap_or_default();
+            self.games.entry(game_id).or_insert(entries);
+        }
+    }
+
+    pub fn save(&self, paths: &ConfigPaths) -> Result<()> {
+        let path = Self::path(paths);
+        fs::create_dir_all(&paths.state_dir)
+            .with_context(|| format!("failed to create directory {:?}", paths.state_dir))?;
+        fs::write(&path, serde_yaml::to_string(self)?)
+            .with_context(|| format!("failed to write graphics overrides {:?}", path))
+    }
+
+    /// Overrides recorded for a game
+    pub fn get(&self, game_id: &GameId) -> &[GraphicsOverride] {
+        self.games
+            .get(&game_id.to_string())
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    fn set(&mut self, game_id: &str, entry: GraphicsOverride) {
+        let entries = self.games.entry(game_id.to_string()).or_default();
+        entries.retain(|o| o.component != entry.component);
+        entries.push(entry);
+        entries.sort_by_key(|o| o.component);
+    }
+
+    fn take(&mut self, game_id: &str, component: Component) -> Option<GraphicsOverride> {
+        let entries = self.games.get_mut(game_id)?;
+        let index = entries.iter().position(|o| o.component == component)?;
+        let entry = entries.remove(index);
+        if entries.is_empty() {
+            self.games.remove(game_id);
+        }
+        Some(entry)
+    }
+}
+
+/// The game `--game` names and the key its overrides are stored under
+///
+/// Known games are stored under their `source:id`, which `run` and `games
+/// show` read; anything else (installed with `--prefix`) under the argument.
+fn override_target(db: &GameDatabase, game_arg: &str) -> Result<(Option<DetectedGame>, String)> {
+    if db.lookup(game_arg).is_none() {
+        return Ok((None, game_arg.to_string()));
+    }
+    let game = db.resolve_game(game_arg)?;
+    let key = game.game_id().to_string();
+    Ok((Some(game), key))
+}
+
+/// Combined `WINEDLLOVERRIDES` value for a game's overrides
+pub fn wine_dll_overrides(overrides: &[GraphicsOverride]) -> Option<String> {
+    if overrides.is_empty() {
+        return None;
+    }
+    let entries: Vec<String> = overrides.iter().map(|o| o.dll_overrides()).collect();
+    Some(entries.join(";"))
+}
+
+/// Handle the `dxvk` command
+pub fn handle_dxvk(args: DxvkArgs, manager: &ConfigManager, _config: &NvConfig) -> Result<()> {
+    match args.command {
+        DxvkCommand::Install(install_args) => handle_install(install_args, manager),
+        DxvkCommand::Remove(remove_args) => handle_remove(remove_args, manager),
+        DxvkCommand::List => handle_list(manager),
+    }
+}
+
+fn handle_install(args: DxvkInstallArgs, manager: &ConfigManager) -> Result<()> {
+    let component = Component::from_flag(args.vkd3d);
+    let version = args.version.trim_start_matches('v').to_string();
+    let db = GameDatabase::load_or_default(manager.paths())?;
+    let (game, key) = override_target(&db, &args.game)?;
+
+    let prefix = match args.prefix {
+        Some(prefix) => PathBuf::from(prefix),
+        None => match game.as_ref().and_then(game_prefix) {
+            Some(prefix) => prefix,
+            None => bail!(
+                "no Wine prefix known for '{}'; pass --prefix <path>",
+                args.game
+            ),
+        },
+    };
+    if !prefix.join("drive_c").exists() {
+        bail!(
+            "{:?} is not an initialized Wine prefix (launch the game once first)",
+            prefix
+        );
+    }
+
+    let release = fetch_release(component, &version)?;
+    let dlls = install_dlls(component, &release, &prefix)?;
+
+    let entry = GraphicsOverride {
+        component,
+        version: version.clone(),
+        prefix: prefix.clone(),
+        dlls,
+        installed_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    };
+    println!(
+        "Installed {} {} into {:?} ({})",
+        component.name(),
+        version,
+        prefix,
+        entry.dlls.join(", ")
+    );
+
+    let mut store = OverrideStore::load(manager.paths(), &db)?;
+    store.set(&key, entry);
+    store.save(manager.paths())?;
+
+    if game.is_some_and(|g| g.source == GameSource::Steam) {
+        println!(
+            "Note: Proton may restore its own DLLs after a Proton update; re-run this command if so."
+        );
+    }
+    Ok(())
+}
+
+fn handle_remove(args: DxvkRemoveArgs, manager: &ConfigManager) -> Result<()> {
+    let component = Component::from_flag(args.vkd3d);
+    let db = GameDatabase::load_or_default(manager.paths())?;
+    let mut store = OverrideStore::load(manager.paths(), &db)?;
+    // Overrides of games no longer installed are removed by their stored key
+    let key = db
+        .resolve_game(&args.game_id)
+        .map_or_else(|_| args.game_id.clone(), |game| game.game_id().to_string());
+    let Some(entry) = store.take(&key, component) else {
+        bail!(
+            "no {} override recorded for '{}'",
+            component.name(),
+            args.game_id
+        );
+    };
+
+    restore_dlls(component, &entry)?;
+    store.save(manager.paths())?;
+    println!(
+        "Removed {} {} override from {:?}",
+        component.name(),
+        entry.version,
+        entry.prefix
+    );
+    Ok(())
+}
+
+fn handle_list(manager: &ConfigManager) -> Result<()> {
+    let db = GameDatabase::load_or_default(manager.paths())?;
+    let store = OverrideStore::load(manager.paths(), &db)?;
+    if store.games.is_empty() {
+        println!("No graphics overrides installed.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<12} {:<14} {:<10} Prefix",
+        "Game", "Component", "Version"
+    );
+    println!("{}", "-".repeat(70));
+    for (game_id, overrides) in &store.games {
+        for entry in overrides {
+            println!(
+                "{:<12} {:<14} {:<10} {:?}",
+                game_id,
+                entry.component.name(),
+                entry.version,
+                entry.prefix
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Proton prefix of a Steam game (`steamapps/compatdata/<appid>/pfx`)
+pub(crate) fn game_prefix(game: &DetectedGame) -> Option<PathBuf> {
+    if game.source != GameSource::Steam {
+        return None;
+    }
+    // install_dir is <library>/steamapps/common/<dir>
+    let steamapps = game.install_dir.parent()?.parent()?;
+    Some(steamapps.join("compatdata").join(&game.id).join("pfx"))
+}
+
+/// Download and unpack a release into the cache, returning its directory
+fn fetch_release(component: Component, version: &str) -> Result<PathBuf> {
+    let cache = crate::cache::cache_base().join("releases");
+    let release_dir = cache.join(format!("{}-{}", component.name(), version));
+    if release_dir.exists() {
+        return Ok(release_dir);
+    }
+    fs::create_dir_all(&cache)
+        .with_context(|| format!("failed to create directory {:?}", cache))?;
+
+    let archive = cache.join(component.archive(version));
+    let url = component.release_url(version);
+    println!("Downloading {}...", url);
+    let status = Command::new("curl")
+        .args([
+            "--fail",
+            "--location",
+            "--silent",
+            "--show-error",
+            "--output",
+        ])
+        .arg(&archive)
+        .arg(&url)
+        .status()
+        .context("failed to run curl")?;
+    if !status.success() {
+        let _ = fs::remove_file(&archive);
+        bail!(
+            "failed to download {} {} from {}",
+            component.name(),
+            version,
+            url
+        );
+    }
+
+    let status = Command::new("tar")
+        .arg("-xf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(&cache)
+        .status()
+        .context("failed to run tar")?;
+    let _ = fs::remove_file(&archive);
+    if !status.success() || !release_dir.exists() {
+        bail!("failed to unpack {:?}", archive);
+    }
+    Ok(release_dir)
+}
+
+/// Copy release DLLs into the prefix, backing up the ones they replace
+fn install_dlls(component: Component, release: &Path, prefix: &Path) -> Result<Vec<String>> {
+    let mut dlls = Vec::new();
+    for (arch, system_dir) in component.arch_dirs() {
+        let source_dir = release.join(arch);
+        let target_dir = prefix.join("drive_c").join("windows").join(system_dir);
+        if !source_dir.exists() || !target_dir.exists() {
+            continue;
+        }
+
+        for entry in fs::read_dir(&source_dir)
+            .with_context(|| format!("failed to read {:?}", source_dir))?
+            .filter_map(|e| e.ok())
+        {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(stem) = name.strip_suffix(".dll") else {
+                continue;
+            };
+            let target = target_dir.join(&name);
+            let backup = backup_path(&target);
+            if target.exists() && !backup.exists() {
+                fs::rename(&target, &backup)
+                    .with_context(|| format!("failed to back up {:?}", target))?;
+            }
+            fs::copy(entry.path(), &target)
+                .with_context(|| format!("failed to install {:?}", target))?;
+            if !dlls.iter().any(|d| d == stem) {
+                dlls.push(stem.to_string());
+            }
+        }
+    }
+
+    if dlls.is_empty() {
+        bail!("no DLLs found in {:?}", release);
+    }
+    dlls.sort();
+    Ok(dlls)
+}
+
+/// Put back the DLLs an override replaced
+fn restore_dlls(component: Component, entry: &GraphicsOverride) -> Result<()> {
+    for (_, system_dir) in component.arch_dirs() {
+        let dir = entry
+            .prefix
+            .join("drive_c")
+            .join("windows")
+            .join(system_dir);
+        for dll in &entry.dlls {
+            let target = dir.join(format!("{}.dll", dll));
+            let backup = backup_path(&target);
+            if backup.exists() {
+                fs::rename(&backup, &target)
+                    .with_context(|| format!("failed to restore {:?}", target))?;
+            } else if target.exists() {
+                fs::remove_file(&target)
+                    .with_context(|| format!("failed to remove {:?}", target))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn backup_path(dll: &Path) -> PathBuf {
+    let mut name = dll.as_os_str().to_os_string();
+    name.push(format!(".{}", BACKUP_SUFFIX));
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_and_restore_dlls() {
+        let dir = tempfile::tempdir().unwrap();
+        let release = dir.path().join("dxvk-2.5.3");
+        let prefix = dir.path().join("pfx");
+        let system32 = prefix.join("drive_c/windows/system32");
+        fs::create_dir_all(release.join("x64")).unwrap();
+        fs::create_dir_all(&system32).unwrap();
+        fs::write(release.join("x64/d3d11.dll"), "new").unwrap();
+        fs::write(release.join("x64/dxgi.dll"), "new").unwrap();
+        fs::write(system32.join("d3d11.dll"), "old").unwrap();
+
+        let dlls = install_dlls(Component::Dxvk, &release, &prefix).unwrap();
+        assert_eq!(dlls, vec!["d3d11", "dxgi"]);
+        assert_eq!(
+            fs::read_to_string(system32.join("d3d11.dll")).unwrap(),
+            "new"
+        );
+
+        let entry = GraphicsOverride {
+            component: Component::Dxvk,
+            version: "2.5.3".into(),
+            prefix: prefix.clone(),
+            dlls,
+            installed_at: 0,
+        };
+        assert_eq!(
+            wine_dll_overrides(std::slice::from_ref(&entry)).unwrap(),
+            "d3d11,dxgi=n,b"
+        );
+
+        restore_dlls(Component::Dxvk, &entry).unwrap();
+        assert_eq!(
+            fs::read_to_string(system32.join("d3d11.dll")).unwrap(),
+            "old"
+        );
+        assert!(!system32.join("dxgi.dll").exists());
+    }
+
+    #[test]
+    fn test_overrides_are_stored_under_the_game_id() {
+        let mut db = GameDatabase::default();
+        db.merge_detected(
+            &[DetectedGame {
+                id: "1145360".into(),
+                name: "Hades".into(),
+                source: GameSource::Steam,
+                install_dir: PathBuf::from("/games/Hades"),
+                executable: None,
+                fingerprint: None,
+                metadata: Default::default(),
+            }],
+            0,
+        );
+
+        let (game, key) = override_target(&db, "1145360").unwrap();
+        assert_eq!(key, "steam:1145360");
+        let mut store = OverrideStore::default();
+        store.set(
+            &key,
+            GraphicsOverride {
+                component: Component::Dxvk,
+                version: "2.5.3".into(),
+                prefix: PathBuf::from("/pfx"),
+                dlls: vec!["d3d11".into()],
+                installed_at: 0,
+            },
+        );
+        assert_eq!(store.get(&game.unwrap().game_id()).len(), 1);
+
+        // Games nvproton doesn't know keep the name they were given
+        let (game, key) = override_target(&db, "custom").unwrap();
+        assert!(game.is_none());
+        assert_eq!(key, "custom");
+    }
+}
@@ -0,0 +1,116 @@
+//! DXVK HUD and log level
+//!
+//! `run --dxvk-hud` and the profile's `dxvk.hud` take either a preset or
+//! DXVK's own comma-separated item list for `DXVK_HUD`:
+//!
+//! | preset     | shows                                               |
+//! |------------|-----------------------------------------------------|
+//! | fps        | frame rate                                          |
+//! | frametimes | frame rate and the frame time graph                 |
+//! | stutter    | frame times, shader compiler activity and pipelines |
+//! | gpu-mem    | GPU load, memory and allocations                    |
+//! | full       | everything DXVK can show                            |
+//!
+//! `--dxvk-log-level` and `dxvk.log_level` set `DXVK_LOG_LEVEL`.
+
+/// HUD presets and the `DXVK_HUD` value they stand for
+pub const HUD_PRESETS: &[(&str, &str)] = &[
+    ("fps", "fps"),
+    ("frametimes", "fps,frametimes"),
+    ("stutter", "fps,frametimes,compiler,pipelines"),
+    ("gpu-mem", "gpuload,memory,allocations"),
+    ("full", "full"),
+];
+
+/// Items DXVK's HUD understands
+const HUD_ITEMS: &[&str] = &[
+    "1",
+    "full",
+    "devinfo",
+    "fps",
+    "frametimes",
+    "submissions",
+    "drawcalls",
+    "pipelines",
+    "descriptors",
+    "memory",
+    "allocations",
+    "gpuload",
+    "version",
+    "api",
+    "cs",
+    "compiler",
+    "samplers",
+];
+
+/// HUD settings taking a value (`scale=1.5`)
+const HUD_SETTINGS: &[&str] = &["scale", "opacity"];
+
+/// Values of `DXVK_LOG_LEVEL`
+const LOG_LEVELS: &[&str] = &["none", "error", "warn", "info", "debug", "trace"];
+
+/// `DXVK_HUD` value for a preset or item list
+pub fn hud_value(spec: &str) -> Result<String, String> {
+    let spec = spec.trim();
+    if let Some((_, value)) = HUD_PRESETS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(spec))
+    {
+        return Ok(value.to_string());
+    }
+
+    for item in spec.split(',').map(str::trim) {
+        let known = match item.split_once('=') {
+            Some((setting, value)) => HUD_SETTINGS.contains(&setting) && !value.is_empty(),
+            None => HUD_ITEMS.contains(&item),
+        };
+        if !known {
+            let presets: Vec<&str> = HUD_PRESETS.iter().map(|(name, _)| *name).collect();
+            return Err(format!(
+                "unknown DXVK HUD item '{}' (presets: {}; items: {})",
+                item,
+                presets.join(", "),
+                HUD_ITEMS.join(", ")
+            ));
+        }
+    }
+    Ok(spec.to_string())
+}
+
+/// Parse a `DXVK_LOG_LEVEL` value
+pub fn parse_log_level(value: &str) -> Result<String, String> {
+    let level = value.trim().to_lowercase();
+    if LOG_LEVELS.contains(&level.as_str()) {
+        Ok(level)
+    } else {
+        Err(format!(
+            "invalid DXVK log level '{}' (one of: {})",
+            value,
+            LOG_LEVELS.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hud_presets_and_items() {
+        assert_eq!(hud_value("fps").unwrap(), "fps");
+        assert_eq!(hud_value("GPU-Mem").unwrap(), "gpuload,memory,allocations");
+        assert_eq!(
+            hud_value("fps,compiler,scale=1.5").unwrap(),
+            "fps,compiler,scale=1.5"
+        );
+        assert!(
+            hud_value("fps,frametime")
+                .unwrap_err()
+                .contains("'frametime'")
+        );
+        assert!(hud_value("scale=").is_err());
+
+        assert_eq!(parse_log_level("Debug").unwrap(), "debug");
+        assert!(parse_log_level("verbose").is_err());
+    }
+}
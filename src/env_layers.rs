@@ -0,0 +1,145 @@
+//! Conflicts between the layers of a launch environment
+//!
+//! A launch's variables come from several layers, each replacing what the
+//! ones before it set:
+//! 1. `environment`: the environment nvproton was started with
+//! 2. `config`: the config's `env` section
+//! 3. `proton-nv`: Proton-NV's defaults
+//! 4. `profile`: the game's profile, including the profiles it extends
+//! 5. `streaming`: the streaming fragment while OBS runs
+//! 6. `game`: per-game settings (`nvapi enable`, DLSS Frame Generation,
+//!    anti-cheat runtimes)
+//! 7. `flags`: command-line flags and `run_defaults`
+//!
+//! Two layers setting a variable to different values is a conflict. The
+//! later layer's value is used; `run --dry-run` lists the conflicts and
+//! `run --strict` refuses to launch.
+
+use std::collections::HashMap;
+use std::env;
+
+/// Layer of the environment nvproton was started with
+pub const ENVIRONMENT: &str = "environment";
+
+/// A variable set to different values by two layers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvConflict {
+    pub key: String,
+    /// The layer whose value was replaced, and that value
+    pub overridden: (&'static str, String),
+    /// The layer whose value is used, and that value
+    pub applied: (&'static str, String),
+}
+
+/// Tracks which layer set each variable of a launch environment
+#[derive(Debug, Default)]
+pub struct EnvLayers {
+    /// Layer and value each variable was last set by
+    origins: HashMap<String, (&'static str, String)>,
+    conflicts: Vec<EnvConflict>,
+}
+
+impl EnvLayers {
+    /// Attribute the variables added or changed since the last layer
+    pub fn record(&mut self, layer: &'static str, env_vars: &HashMap<String, String>) {
+        for (key, value) in env_vars {
+            match self.origins.get(key) {
+                Some((_, previous)) if previous == value => continue,
+                Some((earlier, previous)) if *earlier != layer => {
+                    self.conflicts.push(EnvConflict {
+                        key: key.clone(),
+                        overridden: (earlier, previous.clone()),
+                        applied: (layer, value.clone()),
+                    });
+                }
+                // A layer changing its own value keeps its conflict current
+                Some(_) => {
+                    if let Some(conflict) = self.conflicts.iter_mut().rev().find(|c| &c.key == key)
+                        && conflict.applied.0 == layer
+                    {
+                        conflict.applied.1 = value.clone();
+                    }
+                }
+                None => {}
+            }
+            self.origins.insert(key.clone(), (layer, value.clone()));
+        }
+    }
+
+    /// The conflicts, including variables that replace inherited ones
+    pub fn finish(self) -> Vec<EnvConflict> {
+        self.finish_with(|key| env::var(key).ok())
+    }
+
+    fn finish_with(self, inherited: impl Fn(&str) -> Option<String>) -> Vec<EnvConflict> {
+        let mut conflicts = self.conflicts;
+        for (key, (layer, value)) in &self.origins {
+            // The first layer to set a variable replaces the inherited value
+            let first = conflicts
+                .iter()
+                .find(|c| &c.key == key)
+                .map_or((*layer, value), |c| (c.overridden.0, &c.overridden.1));
+            if let Some(previous) = inherited(key).filter(|previous| previous != first.1) {
+                conflicts.push(EnvConflict {
+                    key: key.clone(),
+                    overridden: (ENVIRONMENT, previous),
+                    applied: (first.0, first.1.clone()),
+                });
+            }
+        }
+        // Per variable, in layer order
+        conflicts.sort_by_key(|c| (c.key.clone(), c.overridden.0 != ENVIRONMENT));
+        conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_conflicts_between_layers() {
+        let mut layers = EnvLayers::default();
+        layers.record("config", &env(&[("DXVK_HUD", "fps")]));
+        layers.record(
+            "profile",
+            &env(&[("DXVK_HUD", "fps"), ("DXVK_FRAME_RATE", "60")]),
+        );
+        // Changes within one layer aren't conflicts
+        layers.record(
+            "flags",
+            &env(&[("DXVK_HUD", "fps"), ("DXVK_FRAME_RATE", "120")]),
+        );
+        layers.record(
+            "flags",
+            &env(&[("DXVK_HUD", "full"), ("DXVK_FRAME_RATE", "144")]),
+        );
+
+        let conflicts = layers.finish_with(|key| (key == "DXVK_FRAME_RATE").then(|| "30".into()));
+        let summary: Vec<_> = conflicts
+            .iter()
+            .map(|c| {
+                (
+                    c.key.as_str(),
+                    c.overridden.0,
+                    c.applied.0,
+                    c.applied.1.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("DXVK_FRAME_RATE", ENVIRONMENT, "profile", "60"),
+                ("DXVK_FRAME_RATE", "profile", "flags", "144"),
+                ("DXVK_HUD", "config", "flags", "full"),
+            ]
+        );
+    }
+}
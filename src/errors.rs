@@ -0,0 +1,161 @@
+//! Error kinds with stable exit codes
+//!
+//! Most failures are plain `anyhow` errors and exit with 1. Failures that
+//! scripts and frontends need to tell apart carry an [`NvError`] somewhere in
+//! their chain; `main` maps it to an exit code and, with `--json-errors`,
+//! prints the error as a JSON object on stderr:
+//!
+//! ```json
+//! {"code": 2, "kind": "game_not_found", "message": "...", "causes": []}
+//! ```
+//!
+//! | Code | Kind |
+//! |-----:|------|
+//! | 1 | `error` (anything else) |
+//! | 2 | `game_not_found` |
+//! | 3 | `profile_not_found` |
+//! | 4 | `launch_failed` |
+//! | 5 | `ffi_failed` (NVML or another NVIDIA library failed to load) |
+//! | 6 | `config_invalid` |
+//! | 64 | `usage` (invalid command line) |
+
+use std::process::ExitCode;
+
+use serde::Serialize;
+use thiserror::Error;
+
+const EXIT_ERROR: u8 = 1;
+const EXIT_USAGE: u8 = 64;
+
+#[derive(Debug, Error)]
+pub enum NvError {
+    #[error(
+        "game '{0}' not found (run 'nvproton games scan' to detect games, or 'nvproton games list' to see them)"
+    )]
+    GameNotFound(String),
+    #[error("profile '{0}' not found (see 'nvproton profile list')")]
+    ProfileNotFound(String),
+    #[error("failed to launch {0}")]
+    LaunchFailed(String),
+    #[error("failed to load {0} (is the NVIDIA driver installed?)")]
+    Ffi(String),
+    #[error("invalid config file {0}")]
+    ConfigInvalid(String),
+}
+
+impl NvError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            NvError::GameNotFound(_) => 2,
+            NvError::ProfileNotFound(_) => 3,
+            NvError::LaunchFailed(_) => 4,
+            NvError::Ffi(_) => 5,
+            NvError::ConfigInvalid(_) => 6,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NvError::GameNotFound(_) => "game_not_found",
+            NvError::ProfileNotFound(_) => "profile_not_found",
+            NvError::LaunchFailed(_) => "launch_failed",
+            NvError::Ffi(_) => "ffi_failed",
+            NvError::ConfigInvalid(_) => "config_invalid",
+        }
+    }
+}
+
+/// An error as printed with `--json-errors`
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    pub code: u8,
+    pub kind: &'static str,
+    pub message: String,
+    /// Underlying causes, outermost first
+    pub causes: Vec<String>,
+}
+
+impl ErrorReport {
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        let (code, kind) = if let Some(nv) = err.downcast_ref::<NvError>() {
+            (nv.exit_code(), nv.kind())
+        } else {
+            (EXIT_ERROR, "error")
+        };
+        Self {
+            code,
+            kind,
+            message: err.to_string(),
+            causes: err.chain().skip(1).map(|c| c.to_string()).collect(),
+        }
+    }
+
+    fn usage(err: &clap::Error) -> Self {
+        let text = err.to_string();
+        let message = text.lines().next().unwrap_or_default();
+        Self {
+            code: EXIT_USAGE,
+            kind: "usage",
+            message: message.trim_start_matches("error: ").to_string(),
+            causes: Vec::new(),
+        }
+    }
+
+    fn print_json(&self) -> ExitCode {
+        match serde_json::to_string(self) {
+            Ok(json) => eprintln!("{}", json),
+            Err(_) => eprintln!("{}", self.message),
+        }
+        ExitCode::from(self.code)
+    }
+}
+
+/// Print a failed command's error and return its exit code
+pub fn report(err: &anyhow::Error, json: bool) -> ExitCode {
+    let report = ErrorReport::from_error(err);
+    if json {
+        return report.print_json();
+    }
+    eprintln!("Error: {:?}", err);
+    ExitCode::from(report.code)
+}
+
+/// Print a command-line parsing error and return the usage exit code
+pub fn report_usage(err: &clap::Error, json: bool) -> ExitCode {
+    if json {
+        return ErrorReport::usage(err).print_json();
+    }
+    let _ = err.print();
+    ExitCode::from(EXIT_USAGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_exit_code_found_through_context() {
+        let err = Err::<(), _>(NvError::GameNotFound("1091500".into()))
+            .context("failed to prepare launch")
+            .unwrap_err();
+        let report = ErrorReport::from_error(&err);
+        assert_eq!(report.code, 2);
+        assert_eq!(report.kind, "game_not_found");
+        assert_eq!(report.message, "failed to prepare launch");
+        assert!(report.causes[0].starts_with("game '1091500' not found"));
+
+        let err = Err::<(), _>(std::io::Error::other("boom"))
+            .context(NvError::LaunchFailed("steam".into()))
+            .unwrap_err();
+        assert_eq!(ErrorReport::from_error(&err).code, 4);
+
+        let err = Err::<(), _>(crate::ffi::FfiError::NotAvailable)
+            .context(NvError::Ffi("NVML".into()))
+            .unwrap_err();
+        let report = ErrorReport::from_error(&err);
+        assert_eq!((report.code, report.kind), (5, "ffi_failed"));
+
+        assert_eq!(ErrorReport::from_error(&anyhow::anyhow!("other")).code, 1);
+    }
+}
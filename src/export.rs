@@ -0,0 +1,301 @@
+//! Game database export for other frontends
+//!
+//! `nvproton games export --format <frontend> --output <dir>` writes import
+//! files that launch every game through `nvproton run`, so profiles, wrappers
+//! and hooks apply no matter where the game is started from:
+//! - heroic: sideloaded apps for `sideload_apps/library.json`, with launcher scripts
+//! - lutris: one installer per game, for `lutris -i <file>`
+//! - pegasus: a `metadata.pegasus.txt` collection
+//! - es-de: launcher scripts and a `gamelist.xml` for the ports system
+//!
+//! Games are exported once even when installed from several sources, and
+//! games the target launcher manages itself are left out.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+
+use crate::cli::{ExportFormat, GamesExportArgs};
+use crate::config::ConfigManager;
+use crate::detection::{DetectedGame, GameDatabase, GameSource};
+use crate::paths::sanitize;
+use crate::runner::{shell_quote, write_launch_script};
+
+/// Handle `games export`
+pub fn handle_export(args: GamesExportArgs, manager: &ConfigManager) -> Result<()> {
+    let db = GameDatabase::load_or_default(manager.paths())?;
+    let native = match args.format {
+        ExportFormat::Heroic => Some(GameSource::Heroic),
+        ExportFormat::Lutris => Some(GameSource::Lutris),
+        ExportFormat::Pegasus | ExportFormat::EsDe => None,
+    };
+    let games: Vec<DetectedGame> = db
+        .unique_games()
+        .into_iter()
+        .filter(|group| {
+            native.as_ref().is_none_or(|native| {
+                group.primary.source != *native
+                    && group.duplicates.iter().all(|game| game.source != *native)
+            })
+        })
+        .map(|group| group.primary)
+        .filter(|game| db.has_tags(&game.game_id(), &args.tags))
+        .collect();
+    if games.is_empty() {
+        bail!("no games to export (run 'nvproton detect' first)");
+    }
+
+    let exe = std::env::current_exe().context("failed to locate nvproton executable")?;
+    let exe = exe.to_string_lossy();
+    // Launcher scripts are referenced by absolute path
+    let output = std::path::absolute(&args.output)
+        .with_context(|| format!("invalid output directory '{}'", args.output))?;
+    fs::create_dir_all(&output).with_context(|| format!("failed to create {:?}", output))?;
+
+    let hint = match args.format {
+        ExportFormat::Heroic => {
+            let launchers = output.join("launchers");
+            let mut apps = Vec::with_capacity(games.len());
+            for game in &games {
+                let script = launcher_path(&launchers, game);
+                write_launch_script(&script, &launcher_script(&exe, game))?;
+                apps.push(SideloadApp::new(game, &script));
+            }
+            let library = serde_json::to_string_pretty(&SideloadLibrary { games: apps })?;
+            write(&output.join("library.json"), &library)?;
+            "Merge library.json into ~/.config/heroic/sideload_apps/library.json and restart Heroic"
+                .to_string()
+        }
+        ExportFormat::Lutris => {
+            for game in &games {
+                let installer = LutrisInstaller::new(&exe, game);
+                let path = output.join(format!("{}.yml", installer.slug));
+                write(&path, &serde_yaml::to_string(&installer)?)?;
+            }
+            format!(
+                "Install each game with: lutris -i {}/<game>.yml",
+                output.display()
+            )
+        }
+        ExportFormat::Pegasus => {
+            write(
+                &output.join("metadata.pegasus.txt"),
+                &render_pegasus(&exe, &games),
+            )?;
+            format!(
+                "Add {} to the game directories of Pegasus",
+                output.display()
+            )
+        }
+        ExportFormat::EsDe => {
+            for game in &games {
+                write_launch_script(&launcher_path(&output, game), &launcher_script(&exe, game))?;
+            }
+            write(&output.join("gamelist.xml"), &render_gamelist(&games))?;
+            "Copy the scripts to ~/ROMs/ports and gamelist.xml to ~/ES-DE/gamelists/ports"
+                .to_string()
+        }
+    };
+
+    println!("Exported {} games to {:?}", games.len(), output);
+    println!("{}", hint);
+    Ok(())
+}
+
+fn write(path: &Path, contents: &str) -> Result<()> {
+    fs::write(path, contents).with_context(|| format!("failed to write {:?}", path))
+}
+
+/// Command line launching a game through nvproton
+fn launch_command(exe: &str, game: &DetectedGame) -> String {
+    format!(
+        "{} run {}",
+        shell_quote(exe),
+        shell_quote(&game.game_id().to_string())
+    )
+}
+
+fn launcher_path(dir: &Path, game: &DetectedGame) -> PathBuf {
+    dir.join(format!("{}.sh", sanitize(&game.game_id().dir_name())))
+}
+
+/// Script for frontends that start an executable without arguments
+fn launcher_script(exe: &str, game: &DetectedGame) -> String {
+    format!(
+        "#!/bin/sh\n# Launches {} ({}) through nvproton\n\nexec {} \"$@\"\n",
+        game.name,
+        game.game_id(),
+        launch_command(exe, game)
+    )
+}
+
+/// Heroic's `sideload_apps/library.json`
+#[derive(Serialize)]
+struct SideloadLibrary {
+    games: Vec<SideloadApp>,
+}
+
+#[derive(Serialize)]
+struct SideloadApp {
+    runner: &'static str,
+    app_name: String,
+    title: String,
+    install: SideloadInstall,
+    folder_name: PathBuf,
+    is_installed: bool,
+    art_cover: String,
+    art_square: String,
+    #[serde(rename = "canRunOffline")]
+    can_run_offline: bool,
+}
+
+#[derive(Serialize)]
+struct SideloadInstall {
+    executable: PathBuf,
+    platform: &'static str,
+    is_dlc: bool,
+}
+
+impl SideloadApp {
+    fn new(game: &DetectedGame, script: &Path) -> Self {
+        Self {
+            runner: "sideload",
+            app_name: format!("nvproton-{}", sanitize(&game.id)),
+            title: game.name.clone(),
+            install: SideloadInstall {
+                executable: script.to_path_buf(),
+                // The launcher script is native; nvproton picks Proton itself
+                platform: "linux",
+                is_dlc: false,
+            },
+            folder_name: game.install_dir.clone(),
+            is_installed: true,
+            art_cover: String::new(),
+            art_square: String::new(),
+            can_run_offline: true,
+        }
+    }
+}
+
+/// Lutris installer script running the game through nvproton
+#[derive(Serialize)]
+struct LutrisInstaller {
+    name: String,
+    game_slug: String,
+    version: &'static str,
+    slug: String,
+    runner: &'static str,
+    script: LutrisScript,
+}
+
+#[derive(Serialize)]
+struct LutrisScript {
+    game: LutrisGame,
+}
+
+#[derive(Serialize)]
+struct LutrisGame {
+    exe: String,
+    args: String,
+}
+
+impl LutrisInstaller {
+    fn new(exe: &str, game: &DetectedGame) -> Self {
+        let game_slug = slugify(&game.name);
+        Self {
+            name: game.name.clone(),
+            slug: format!("{}-nvproton", game_slug),
+            game_slug,
+            version: "nvproton",
+            runner: "linux",
+            script: LutrisScript {
+                game: LutrisGame {
+                    exe: exe.to_string(),
+                    args: format!("run {}", shell_quote(&game.game_id().to_string())),
+                },
+            },
+        }
+    }
+}
+
+/// Lutris-style slug: lowercase words joined by dashes
+fn slugify(name: &str) -> String {
+    name.replace(['\'', '’'], "")
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Pegasus collection with one entry per game
+fn render_pegasus(exe: &str, games: &[DetectedGame]) -> String {
+    let mut out = String::from("collection: nvproton\nshortname: nvproton\n");
+    for game in games {
+        out.push_str(&format!(
+            "\ngame: {}\nlaunch: {}\nx-nvproton-id: {}\nx-nvproton-source: {}\n",
+            game.name.trim(),
+            launch_command(exe, game),
+            game.id,
+            game.source
+        ));
+    }
+    out
+}
+
+/// EmulationStation gamelist pointing at the launcher scripts
+fn render_gamelist(games: &[DetectedGame]) -> String {
+    let mut out = String::from("<?xml version=\"1.0\"?>\n<gameList>\n");
+    for game in games {
+        out.push_str(&format!(
+            "  <game>\n    <path>./{}.sh</path>\n    <name>{}</name>\n  </game>\n",
+            xml_escape(&sanitize(&game.game_id().dir_name())),
+            xml_escape(&game.name)
+        ));
+    }
+    out.push_str("</gameList>\n");
+    out
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_frontend_files_launch_through_nvproton() {
+        let game = DetectedGame {
+            source: GameSource::Heroic,
+            id: "Sugar".into(),
+            name: "Tom Clancy's Rainbow Six & Co".into(),
+            install_dir: PathBuf::from("/games/r6"),
+            executable: None,
+            fingerprint: None,
+            metadata: HashMap::new(),
+        };
+        let games = [game];
+
+        let pegasus = render_pegasus("/opt/nv proton/nvproton", &games);
+        assert!(pegasus.contains("game: Tom Clancy's Rainbow Six & Co\n"));
+        assert!(pegasus.contains("launch: '/opt/nv proton/nvproton' run heroic:Sugar\n"));
+
+        let gamelist = render_gamelist(&games);
+        assert!(gamelist.contains("<path>./heroic-Sugar.sh</path>"));
+        assert!(gamelist.contains("<name>Tom Clancy&apos;s Rainbow Six &amp; Co</name>"));
+
+        let installer = LutrisInstaller::new("/usr/bin/nvproton", &games[0]);
+        assert_eq!(installer.game_slug, "tom-clancys-rainbow-six-co");
+        assert_eq!(installer.script.game.args, "run heroic:Sugar");
+    }
+}
@@ -4,6 +4,7 @@
 //! - libnvshader.so - Shader cache management
 //! - libnvlatency.so - Reflex and latency control
 //! - libnvsync.so - VRR/G-Sync management
+//! - libnvidia-ml.so - NVML telemetry (temperature, power, clocks, VRAM)
 
 #![allow(dead_code)]
 
@@ -482,7 +483,7 @@ impl NvSyncDisplay {
 
 /// System status from nvsync
 #[repr(C)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct NvSyncStatus {
     pub nvidia_detected: bool,
     pub driver_version: [u8; 32],
@@ -493,20 +494,6 @@ pub struct NvSyncStatus {
     pub is_wayland: bool,
 }
 
-impl Default for NvSyncStatus {
-    fn default() -> Self {
-        Self {
-            nvidia_detected: false,
-            driver_version: [0; 32],
-            display_count: 0,
-            vrr_capable_count: 0,
-            vrr_enabled_count: 0,
-            compositor: [0; 32],
-            is_wayland: false,
-        }
-    }
-}
-
 impl NvSyncStatus {
     pub fn driver_version_str(&self) -> &str {
         let end = self
@@ -621,7 +608,7 @@ impl NvSync {
 
     /// Enable VRR on a display (None for all displays)
     pub fn enable_vrr(&self, display_name: Option<&str>) -> FfiResult<()> {
-        let name_cstring = display_name.map(|s| CString::new(s)).transpose()?;
+        let name_cstring = display_name.map(CString::new).transpose()?;
         let name_ptr = name_cstring
             .as_ref()
             .map(|s| s.as_ptr())
@@ -641,7 +628,7 @@ impl NvSync {
 
     /// Disable VRR on a display (None for all displays)
     pub fn disable_vrr(&self, display_name: Option<&str>) -> FfiResult<()> {
-        let name_cstring = display_name.map(|s| CString::new(s)).transpose()?;
+        let name_cstring = display_name.map(CString::new).transpose()?;
         let name_ptr = name_cstring
             .as_ref()
             .map(|s| s.as_ptr())
@@ -741,6 +728,244 @@ impl Drop for NvSync {
     }
 }
 
+// =============================================================================
+// NVML - GPU Telemetry
+// =============================================================================
+
+const NVML_SUCCESS: c_int = 0;
+const NVML_TEMPERATURE_GPU: c_int = 0;
+const NVML_CLOCK_GRAPHICS: c_int = 0;
+const NVML_CLOCK_MEM: c_int = 2;
+const NVML_ERROR_INSUFFICIENT_SIZE: c_int = 7;
+/// `usedGpuMemory` when the driver can't attribute memory to the process
+const NVML_VALUE_NOT_AVAILABLE: u64 = u64::MAX;
+
+/// nvmlClocksEventReasons bits: the driver or the GPU itself lowered the
+/// clocks because of the temperature
+pub const NVML_CLOCKS_EVENT_SW_THERMAL: u64 = 0x20;
+pub const NVML_CLOCKS_EVENT_HW_THERMAL: u64 = 0x40;
+
+/// nvmlMemory_t
+#[repr(C)]
+#[derive(Debug, Clone, Default)]
+struct NvmlMemory {
+    total: u64,
+    free: u64,
+    used: u64,
+}
+
+/// nvmlProcessInfo_t (as returned by the `_v3` process queries)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct NvmlProcessInfo {
+    pid: c_uint,
+    used_gpu_memory: u64,
+    gpu_instance_id: c_uint,
+    compute_instance_id: c_uint,
+}
+
+/// A process holding GPU memory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NvmlProcess {
+    pub pid: u32,
+    /// None when the driver doesn't attribute memory per process
+    pub vram_used_bytes: Option<u64>,
+}
+
+/// A single telemetry reading from NVML
+#[derive(Debug, Clone, Default)]
+pub struct NvmlReading {
+    pub temperature_c: Option<u32>,
+    pub power_mw: Option<u32>,
+    pub graphics_clock_mhz: Option<u32>,
+    pub memory_clock_mhz: Option<u32>,
+    pub vram_used_bytes: Option<u64>,
+    pub vram_total_bytes: Option<u64>,
+    pub vram_free_bytes: Option<u64>,
+}
+
+pub struct Nvml {
+    library: Library,
+    device: *mut c_void,
+}
+
+// NVML handles are thread-safe per the NVML documentation
+unsafe impl Send for Nvml {}
+
+impl Nvml {
+    /// Load NVML from the driver and open the GPU at `index`
+    ///
+    /// # Safety
+    /// The caller must ensure the native library is compatible with the expected ABI.
+    pub unsafe fn load<P: AsRef<Path>>(path: P, index: u32) -> FfiResult<Self> {
+        unsafe {
+            let library = Library::new(path.as_ref())?;
+
+            let init_fn: libloading::Symbol<unsafe extern "C" fn() -> c_int> =
+                library.get(b"nvmlInit_v2\0")?;
+            let result = init_fn();
+            if result != NVML_SUCCESS {
+                return Err(FfiError::Operation { code: result });
+            }
+
+            let handle_fn: libloading::Symbol<
+                unsafe extern "C" fn(c_uint, *mut *mut c_void) -> c_int,
+            > = library.get(b"nvmlDeviceGetHandleByIndex_v2\0")?;
+            let mut device = std::ptr::null_mut();
+            let result = handle_fn(index, &mut device);
+            if result != NVML_SUCCESS {
+                if let Ok(shutdown) =
+                    library.get::<unsafe extern "C" fn() -> c_int>(b"nvmlShutdown\0")
+                {
+                    shutdown();
+                }
+                return Err(FfiError::Operation { code: result });
+            }
+
+            Ok(Self { library, device })
+        }
+    }
+
+    /// GPU core temperature in degrees Celsius
+    pub fn temperature(&self) -> Option<u32> {
+        unsafe {
+            let func: libloading::Symbol<
+                unsafe extern "C" fn(*mut c_void, c_int, *mut c_uint) -> c_int,
+            > = self.library.get(b"nvmlDeviceGetTemperature\0").ok()?;
+            let mut value = 0;
+            (func(self.device, NVML_TEMPERATURE_GPU, &mut value) == NVML_SUCCESS).then_some(value)
+        }
+    }
+
+    /// Board power draw in milliwatts
+    pub fn power_usage(&self) -> Option<u32> {
+        unsafe {
+            let func: libloading::Symbol<unsafe extern "C" fn(*mut c_void, *mut c_uint) -> c_int> =
+                self.library.get(b"nvmlDeviceGetPowerUsage\0").ok()?;
+            let mut value = 0;
+            (func(self.device, &mut value) == NVML_SUCCESS).then_some(value)
+        }
+    }
+
+    fn clock(&self, clock_type: c_int) -> Option<u32> {
+        unsafe {
+            let func: libloading::Symbol<
+                unsafe extern "C" fn(*mut c_void, c_int, *mut c_uint) -> c_int,
+            > = self.library.get(b"nvmlDeviceGetClockInfo\0").ok()?;
+            let mut value = 0;
+            (func(self.device, clock_type, &mut value) == NVML_SUCCESS).then_some(value)
+        }
+    }
+
+    fn memory(&self) -> Option<NvmlMemory> {
+        unsafe {
+            let func: libloading::Symbol<
+                unsafe extern "C" fn(*mut c_void, *mut NvmlMemory) -> c_int,
+            > = self.library.get(b"nvmlDeviceGetMemoryInfo\0").ok()?;
+            let mut memory = NvmlMemory::default();
+            (func(self.device, &mut memory) == NVML_SUCCESS).then_some(memory)
+        }
+    }
+
+    /// Why the clocks are currently lowered, as nvmlClocksEventReasons bits
+    pub fn clocks_event_reasons(&self) -> Option<u64> {
+        // Renamed from "ThrottleReasons" in newer drivers
+        for symbol in [
+            &b"nvmlDeviceGetCurrentClocksEventReasons\0"[..],
+            &b"nvmlDeviceGetCurrentClocksThrottleReasons\0"[..],
+        ] {
+            unsafe {
+                let Ok(func) = self
+                    .library
+                    .get::<unsafe extern "C" fn(*mut c_void, *mut u64) -> c_int>(symbol)
+                else {
+                    continue;
+                };
+                let mut reasons = 0;
+                return (func(self.device, &mut reasons) == NVML_SUCCESS).then_some(reasons);
+            }
+        }
+        None
+    }
+
+    /// Read all telemetry values (unsupported values are left empty)
+    pub fn read(&self) -> NvmlReading {
+        let memory = self.memory();
+        NvmlReading {
+            temperature_c: self.temperature(),
+            power_mw: self.power_usage(),
+            graphics_clock_mhz: self.clock(NVML_CLOCK_GRAPHICS),
+            memory_clock_mhz: self.clock(NVML_CLOCK_MEM),
+            vram_used_bytes: memory.as_ref().map(|m| m.used),
+            vram_total_bytes: memory.as_ref().map(|m| m.total),
+            vram_free_bytes: memory.as_ref().map(|m| m.free),
+        }
+    }
+
+    fn process_list(&self, symbol: &[u8]) -> Option<Vec<NvmlProcessInfo>> {
+        unsafe {
+            let func: libloading::Symbol<
+                unsafe extern "C" fn(*mut c_void, *mut c_uint, *mut NvmlProcessInfo) -> c_int,
+            > = self.library.get(symbol).ok()?;
+            // The first call reports the count; processes may start in between
+            let mut count: c_uint = 0;
+            let mut result = func(self.device, &mut count, std::ptr::null_mut());
+            let mut infos = Vec::new();
+            while result == NVML_ERROR_INSUFFICIENT_SIZE {
+                infos = vec![NvmlProcessInfo::default(); count as usize + 4];
+                count = infos.len() as c_uint;
+                result = func(self.device, &mut count, infos.as_mut_ptr());
+            }
+            infos.truncate(count as usize);
+            (result == NVML_SUCCESS).then_some(infos)
+        }
+    }
+
+    /// Processes using the GPU for graphics or compute, each listed once
+    pub fn processes(&self) -> Vec<NvmlProcess> {
+        let mut processes: Vec<NvmlProcess> = Vec::new();
+        for symbol in [
+            &b"nvmlDeviceGetGraphicsRunningProcesses_v3\0"[..],
+            &b"nvmlDeviceGetComputeRunningProcesses_v3\0"[..],
+        ] {
+            for info in self.process_list(symbol).unwrap_or_default() {
+                if processes.iter().any(|p| p.pid == info.pid) {
+                    continue;
+                }
+                processes.push(NvmlProcess {
+                    pid: info.pid,
+                    vram_used_bytes: (info.used_gpu_memory != NVML_VALUE_NOT_AVAILABLE)
+                        .then_some(info.used_gpu_memory),
+                });
+            }
+        }
+        processes
+    }
+}
+
+impl Drop for Nvml {
+    fn drop(&mut self) {
+        unsafe {
+            if let Ok(func) = self
+                .library
+                .get::<unsafe extern "C" fn() -> c_int>(b"nvmlShutdown\0")
+            {
+                func();
+            }
+        }
+    }
+}
+
+/// Load NVML from the NVIDIA driver for the first GPU
+pub fn load_nvml() -> FfiResult<Nvml> {
+    for name in ["libnvidia-ml.so.1", "libnvidia-ml.so"] {
+        if let Ok(nvml) = unsafe { Nvml::load(name, 0) } {
+            return Ok(nvml);
+        }
+    }
+    Err(FfiError::NotAvailable)
+}
+
 // =============================================================================
 // Library Loading Helpers
 // =============================================================================
@@ -748,12 +973,12 @@ impl Drop for NvSync {
 /// Standard library search paths (ordered by priority)
 pub const LIB_PATHS: &[&str] = &[
     // User-local installations (highest priority)
-    // Note: ~/.local/lib/nvproton checked via XDG_DATA_HOME
+    // Note: ~/.local/lib/nvproton and XDG data dirs are added at runtime
     "/usr/lib/nvproton",
     "/usr/local/lib/nvproton",
     // System paths
-    "/usr/lib/x86_64-linux-gnu",  // Debian/Ubuntu multiarch
-    "/usr/lib64",                  // Fedora/RHEL
+    "/usr/lib/x86_64-linux-gnu", // Debian/Ubuntu multiarch
+    "/usr/lib64",                // Fedora/RHEL
     "/usr/lib",
     "/usr/local/lib",
     // Development paths (for testing)
@@ -782,12 +1007,23 @@ impl LibraryDiscovery {
         let search_paths = Self::build_search_paths();
 
         Self {
-            nvshader: Self::find_library_in_paths("libnvshader.so", &search_paths)
-                .or_else(|| std::env::var(ENV_SHADER_LIB).ok().map(std::path::PathBuf::from)),
-            nvlatency: Self::find_library_in_paths("libnvlatency.so", &search_paths)
-                .or_else(|| std::env::var(ENV_LATENCY_LIB).ok().map(std::path::PathBuf::from)),
-            nvsync: Self::find_library_in_paths("libnvsync.so", &search_paths)
-                .or_else(|| std::env::var(ENV_SYNC_LIB).ok().map(std::path::PathBuf::from)),
+            nvshader: Self::find_library_in_paths("libnvshader.so", &search_paths).or_else(|| {
+                std::env::var(ENV_SHADER_LIB)
+                    .ok()
+                    .map(std::path::PathBuf::from)
+            }),
+            nvlatency: Self::find_library_in_paths("libnvlatency.so", &search_paths).or_else(
+                || {
+                    std::env::var(ENV_LATENCY_LIB)
+                        .ok()
+                        .map(std::path::PathBuf::from)
+                },
+            ),
+            nvsync: Self::find_library_in_paths("libnvsync.so", &search_paths).or_else(|| {
+                std::env::var(ENV_SYNC_LIB)
+                    .ok()
+                    .map(std::path::PathBuf::from)
+            }),
             search_paths,
         }
     }
@@ -806,7 +1042,13 @@ impl LibraryDiscovery {
             }
         }
 
-        // 2. XDG data directories
+        // 2. User-local directories (the only writable option on immutable distros)
+        if let Some(user_lib) = crate::detection::distro::user_lib_dir() {
+            paths.push(user_lib);
+        }
+        if crate::detection::distro::in_flatpak() {
+            paths.push(std::path::PathBuf::from("/app/lib/nvproton"));
+        }
         if let Some(data_dir) = dirs::data_local_dir() {
             paths.push(data_dir.join("nvproton/lib"));
         }
@@ -833,16 +1075,16 @@ impl LibraryDiscovery {
         }
 
         // 5. Executable directory (for portable installs)
-        if let Ok(exe_path) = std::env::current_exe() {
-            if let Some(exe_dir) = exe_path.parent() {
-                let lib_dir = exe_dir.join("lib");
-                if lib_dir.is_dir() && !paths.contains(&lib_dir) {
-                    paths.push(lib_dir);
-                }
-                // Also check exe_dir itself
-                if !paths.contains(&exe_dir.to_path_buf()) {
-                    paths.push(exe_dir.to_path_buf());
-                }
+        if let Ok(exe_path) = std::env::current_exe()
+            && let Some(exe_dir) = exe_path.parent()
+        {
+            let lib_dir = exe_dir.join("lib");
+            if lib_dir.is_dir() && !paths.contains(&lib_dir) {
+                paths.push(lib_dir);
+            }
+            // Also check exe_dir itself
+            if !paths.contains(&exe_dir.to_path_buf()) {
+                paths.push(exe_dir.to_path_buf());
             }
         }
 
@@ -850,7 +1092,10 @@ impl LibraryDiscovery {
     }
 
     /// Find a library in the given search paths
-    fn find_library_in_paths(name: &str, paths: &[std::path::PathBuf]) -> Option<std::path::PathBuf> {
+    fn find_library_in_paths(
+        name: &str,
+        paths: &[std::path::PathBuf],
+    ) -> Option<std::path::PathBuf> {
         for base in paths {
             let path = base.join(name);
             if path.exists() && path.is_file() {
@@ -927,9 +1172,18 @@ impl LoadedLibraries {
     pub fn load_available() -> Self {
         let discovery = LibraryDiscovery::discover();
         Self {
-            shader: discovery.nvshader.as_ref().and_then(|p| unsafe { NvShader::load(p).ok() }),
-            latency: discovery.nvlatency.as_ref().and_then(|p| unsafe { NvLatency::load(p).ok() }),
-            sync: discovery.nvsync.as_ref().and_then(|p| unsafe { NvSync::load(p).ok() }),
+            shader: discovery
+                .nvshader
+                .as_ref()
+                .and_then(|p| unsafe { NvShader::load(p).ok() }),
+            latency: discovery
+                .nvlatency
+                .as_ref()
+                .and_then(|p| unsafe { NvLatency::load(p).ok() }),
+            sync: discovery
+                .nvsync
+                .as_ref()
+                .and_then(|p| unsafe { NvSync::load(p).ok() }),
             discovery,
         }
     }
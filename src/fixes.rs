@@ -0,0 +1,457 @@
+//! Fixes offered by `nvproton status --fix` (or `nvproton doctor --fix`)
+//!
+//! Only problems found on this system are offered. On a terminal each fix
+//! is confirmed first, `--yes` applies them all, and otherwise they are
+//! only listed:
+//! - CUDA / NVENC libraries the Proton container can't see: launch
+//!   environment pointing it at them (variables already in `env` are left
+//!   as the user set them)
+//! - `__GL_SHADER_DISK_CACHE_SKIP_CLEANUP=1` in the launch environment, so
+//!   the driver doesn't prune shader caches over its size limit
+//! - Missing cache directories
+//! - Steam games started without nvproton or its cache variables: the
+//!   per-game cache variables are added to their launch options while Steam
+//!   is closed (`nvproton undo` reverts it)
+//! - Missing companion libraries, downloaded as by `nvproton libs update`
+//! - nvidia-drm loaded without modesetting: a file explaining how to add
+//!   `nvidia-drm.modeset=1` to the kernel parameters, which nvproton doesn't
+//!   change itself
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+
+use anyhow::{Context, Result};
+
+use crate::cache::{CacheManager, CachePaths, CacheType};
+use crate::cli::{LibsUpdateArgs, UpdateChannel};
+use crate::config::{ConfigManager, NvConfig, TrackedConfig};
+use crate::detection::distro::{DistroInfo, ImmutableKind};
+use crate::detection::{DetectedGame, GameDatabase, GameSource};
+use crate::dxvk::game_prefix;
+use crate::ffi::LibraryDiscovery;
+use crate::libs;
+use crate::runner::shell_quote;
+use crate::status::{DRM_MODESET_PARAMETER, SystemStatus};
+use crate::steam::client::{SteamClient, SteamUser};
+use crate::undo::Recorder;
+
+/// Keeps the driver from pruning shader caches over its size limit
+const SKIP_CLEANUP_ENV: (&str, &str) = ("__GL_SHADER_DISK_CACHE_SKIP_CLEANUP", "1");
+
+/// Modeset instructions, in the config directory
+const MODESET_FILE: &str = "nvidia-drm-modeset.txt";
+
+type Apply<'a> = Box<dyn FnOnce(&mut TrackedConfig) -> Result<()> + 'a>;
+
+/// A change fixing one problem
+pub struct Fix<'a> {
+    pub description: String,
+    apply: Apply<'a>,
+}
+
+impl<'a> Fix<'a> {
+    fn new(
+        description: impl Into<String>,
+        apply: impl FnOnce(&mut TrackedConfig) -> Result<()> + 'a,
+    ) -> Self {
+        Self {
+            description: description.into(),
+            apply: Box::new(apply),
+        }
+    }
+}
+
+/// Fixes for the problems found on this system
+pub fn find<'a>(
+    status: &SystemStatus,
+    manager: &'a ConfigManager,
+    config: &NvConfig,
+) -> Result<Vec<Fix<'a>>> {
+    let mut fixes = Vec::new();
+
+    let (env, kept) = unset_env(&status.cuda.fix_env, config);
+    for key in kept {
+        println!(
+            "Keeping {}={} from the config; the CUDA/NVENC check suggests {}",
+            key, config.env[key], status.cuda.fix_env[key]
+        );
+    }
+    if !env.is_empty() {
+        let vars: Vec<String> = env.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        fixes.push(Fix::new(
+            format!(
+                "Add {} to the launch environment (CUDA/NVENC libraries outside the Proton container's view)",
+                vars.join(" ")
+            ),
+            move |config| {
+                config.edit().env.extend(env);
+                Ok(())
+            },
+        ));
+    }
+
+    let (key, value) = SKIP_CLEANUP_ENV;
+    if !config.env.contains_key(key) {
+        fixes.push(Fix::new(
+            format!(
+                "Add {}={} to the launch environment so the driver keeps large shader caches",
+                key, value
+            ),
+            move |config| {
+                config.edit().env.insert(key.into(), value.into());
+                Ok(())
+            },
+        ));
+    }
+
+    let cache = CachePaths::new();
+    let dirs = [
+        &cache.base,
+        &cache.dxvk,
+        &cache.vkd3d,
+        &cache.nvidia_gl,
+        &cache.mesa,
+    ];
+    if dirs.iter().any(|dir| !dir.is_dir()) {
+        fixes.push(Fix::new(
+            format!("Create the cache directories in {}", cache.base.display()),
+            move |_| cache.ensure(),
+        ));
+    }
+
+    fixes.extend(steam_launch_options(manager, config)?);
+
+    let discovery = LibraryDiscovery::discover();
+    let missing: Vec<&str> = [
+        ("nvshader", &discovery.nvshader),
+        ("nvlatency", &discovery.nvlatency),
+        ("nvsync", &discovery.nvsync),
+    ]
+    .into_iter()
+    .filter(|(_, path)| path.is_none())
+    .map(|(name, _)| name)
+    .collect();
+    if !missing.is_empty() {
+        fixes.push(Fix::new(
+            format!(
+                "Download the missing companion libraries ({})",
+                missing.join(", ")
+            ),
+            move |_| {
+                let args = LibsUpdateArgs {
+                    check_only: false,
+                    channel: UpdateChannel::Stable,
+                };
+                libs::handle_update(args, manager)
+            },
+        ));
+    }
+
+    if status.drm_modeset == Some(false) {
+        let path = manager.paths().user_config_dir.join(MODESET_FILE);
+        let instructions = modeset_instructions(&status.distro);
+        fixes.push(Fix::new(
+            format!(
+                "Write instructions for enabling nvidia-drm.modeset=1 to {}",
+                path.display()
+            ),
+            move |_| {
+                fs::write(&path, instructions)
+                    .with_context(|| format!("failed to write {:?}", path))?;
+                println!("  Follow {} and reboot", path.display());
+                Ok(())
+            },
+        ));
+    }
+
+    Ok(fixes)
+}
+
+/// Apply fixes, asking about each one on a terminal unless `yes`
+pub fn apply(fixes: Vec<Fix<'_>>, yes: bool, config: &mut TrackedConfig) -> Result<()> {
+    if fixes.is_empty() {
+        println!("Nothing to fix");
+        return Ok(());
+    }
+    let interactive = !yes && io::stdin().is_terminal();
+    if !yes && !interactive {
+        println!("Available fixes:");
+        for fix in &fixes {
+            println!("  - {}", fix.description);
+        }
+        println!("Run 'nvproton status --fix --yes' to apply them");
+        return Ok(());
+    }
+
+    let total = fixes.len();
+    let mut applied = 0;
+    for fix in fixes {
+        if interactive {
+            if !confirm(&fix.description)? {
+                continue;
+            }
+        } else {
+            println!("{}", fix.description);
+        }
+        // Keep going: the config changes of earlier fixes are still saved
+        match (fix.apply)(config) {
+            Ok(()) => applied += 1,
+            Err(e) => eprintln!("  failed: {:#}", e),
+        }
+    }
+    println!("Applied {} of {} fixes", applied, total);
+    Ok(())
+}
+
+/// Ask a yes/no question on stderr, defaulting to no
+fn confirm(question: &str) -> Result<bool> {
+    eprint!("{}? [y/N] ", question);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .context("failed to read the answer")?;
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+/// Variables of `env` the config doesn't set yet, and the keys it already
+/// sets to something else
+fn unset_env<'e>(
+    env: &'e BTreeMap<String, String>,
+    config: &NvConfig,
+) -> (BTreeMap<String, String>, Vec<&'e str>) {
+    let mut unset = BTreeMap::new();
+    let mut kept = Vec::new();
+    for (key, value) in env {
+        match config.env.get(key) {
+            None => {
+                unset.insert(key.clone(), value.clone());
+            }
+            Some(current) if current != value => kept.push(key.as_str()),
+            Some(_) => {}
+        }
+    }
+    (unset, kept)
+}
+
+/// Steam games with a Proton prefix that start without nvproton's caches
+fn steam_launch_options<'a>(
+    manager: &'a ConfigManager,
+    config: &NvConfig,
+) -> Result<Option<Fix<'a>>> {
+    let Some(steam) = SteamClient::from_config(config) else {
+        return Ok(None);
+    };
+    let db = GameDatabase::load_or_default(manager.paths())?;
+    let games: Vec<DetectedGame> = db
+        .games()
+        .filter(|game| game.source == GameSource::Steam)
+        .filter(|game| game_prefix(game).is_some_and(|prefix| prefix.exists()))
+        .collect();
+
+    let mut changes: Vec<(SteamUser, BTreeMap<String, String>, Vec<&DetectedGame>)> = Vec::new();
+    for user in steam.users() {
+        let options = user.launch_options();
+        let stale: Vec<&DetectedGame> = games
+            .iter()
+            .filter(|game| needs_cache_env(options.get(&game.id).map_or("", String::as_str)))
+            .collect();
+        if !stale.is_empty() {
+            changes.push((user, options, stale));
+        }
+    }
+    let ids: BTreeSet<&str> = changes
+        .iter()
+        .flat_map(|(_, _, games)| games.iter().map(|game| game.id.as_str()))
+        .collect();
+    if ids.is_empty() {
+        return Ok(None);
+    }
+    if steam.is_running() {
+        println!(
+            "Close Steam to add shader cache variables to the launch options of {} games",
+            ids.len()
+        );
+        return Ok(None);
+    }
+
+    let description = format!(
+        "Add per-game shader cache variables to the Steam launch options of {} games ('nvproton undo' reverts it)",
+        ids.len()
+    );
+    let changes: Vec<(SteamUser, BTreeMap<String, String>, Vec<DetectedGame>)> = changes
+        .into_iter()
+        .map(|(user, options, games)| (user, options, games.into_iter().cloned().collect()))
+        .collect();
+    Ok(Some(Fix::new(description, move |_| {
+        let caches = CacheManager::new()?;
+        let mut undo = Recorder::begin(manager.paths());
+        let result = changes
+            .into_iter()
+            .try_for_each(|(user, mut options, games)| -> Result<()> {
+                let mut updated = BTreeMap::new();
+                for game in &games {
+                    let env = caches.setup_for_game(game, &db)?;
+                    let current = options.remove(&game.id).unwrap_or_default();
+                    updated.insert(game.id.clone(), with_cache_env(&current, &env));
+                }
+                undo.backup(&user.local_config())?;
+                user.set_launch_options(&updated)
+            });
+        // Users written before a failure stay revertible with 'nvproton undo'
+        if result.is_ok() || !undo.is_empty() {
+            undo.commit()?;
+        }
+        result
+    })))
+}
+
+/// Whether launch options leave a game without nvproton's caches
+fn needs_cache_env(options: &str) -> bool {
+    let cache_vars = [
+        CacheType::Dxvk,
+        CacheType::Vkd3d,
+        CacheType::NvidiaGl,
+        CacheType::Mesa,
+    ];
+    !options.contains("nvproton")
+        && !cache_vars
+            .iter()
+            .any(|cache_type| options.contains(cache_type.env_var()))
+}
+
+/// Launch options with cache variables in front of `%command%`
+fn with_cache_env(options: &str, env: &[(String, String)]) -> String {
+    let vars: Vec<String> = env
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, shell_quote(value)))
+        .collect();
+    let vars = vars.join(" ");
+    let options = options.trim();
+    if options.contains("%command%") {
+        format!("{} {}", vars, options)
+    } else if options.is_empty() {
+        format!("{} %command%", vars)
+    } else {
+        // Options without %command% are arguments for the game
+        format!("{} %command% {}", vars, options)
+    }
+}
+
+/// How to enable nvidia-drm modesetting on this distribution
+fn modeset_instructions(distro: &DistroInfo) -> String {
+    let mut text = String::from(
+        "Enabling NVIDIA kernel modesetting (nvidia-drm.modeset=1)\n\
+         \n\
+         Wayland sessions, PRIME render offload and G-SYNC under Wayland need the\n\
+         nvidia-drm module loaded with modeset=1. nvproton doesn't change boot\n\
+         settings itself: use the method matching your system, then reboot.\n\n",
+    );
+    if distro.immutable == Some(ImmutableKind::RpmOstree) {
+        text.push_str(
+            "rpm-ostree (Bazzite, Silverblue, Kinoite, Bluefin):\n  \
+             rpm-ostree kargs --append=nvidia-drm.modeset=1\n\n",
+        );
+    } else {
+        if distro.id == "pop" {
+            text.push_str("Pop!_OS (kernelstub):\n  sudo kernelstub -a nvidia-drm.modeset=1\n\n");
+        }
+        text.push_str(
+            "GRUB:\n  \
+             Add nvidia-drm.modeset=1 to GRUB_CMDLINE_LINUX_DEFAULT in /etc/default/grub, then run\n  \
+             sudo grub-mkconfig -o /boot/grub/grub.cfg   (Debian/Ubuntu: sudo update-grub)\n\n\
+             systemd-boot:\n  \
+             Add nvidia-drm.modeset=1 to the options line of your entry in /boot/loader/entries/\n\n\
+             Any boot loader (module option):\n  \
+             echo 'options nvidia_drm modeset=1' | sudo tee /etc/modprobe.d/nvidia-drm.conf\n  \
+             Then rebuild the initramfs: sudo mkinitcpio -P, sudo dracut --force or sudo update-initramfs -u\n\n",
+        );
+    }
+    text.push_str(&format!(
+        "Check after rebooting (prints Y):\n  cat {}\n",
+        DRM_MODESET_PARAMETER
+    ));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_fix_keeps_user_values() {
+        let mut config = NvConfig::default();
+        config.env.insert(
+            "PRESSURE_VESSEL_FILESYSTEMS_RO".into(),
+            "/srv/nvidia".into(),
+        );
+        let env = BTreeMap::from([
+            (
+                "PRESSURE_VESSEL_APP_LD_LIBRARY_PATH".to_string(),
+                "/opt/nvidia/lib".to_string(),
+            ),
+            (
+                "PRESSURE_VESSEL_FILESYSTEMS_RO".to_string(),
+                "/opt/nvidia/lib".to_string(),
+            ),
+        ]);
+        let (unset, kept) = unset_env(&env, &config);
+        assert_eq!(
+            unset,
+            BTreeMap::from([(
+                "PRESSURE_VESSEL_APP_LD_LIBRARY_PATH".to_string(),
+                "/opt/nvidia/lib".to_string()
+            )])
+        );
+        assert_eq!(kept, ["PRESSURE_VESSEL_FILESYSTEMS_RO"]);
+    }
+
+    #[test]
+    fn test_launch_options_and_modeset_instructions() {
+        assert!(needs_cache_env(""));
+        assert!(needs_cache_env("gamemoderun %command%"));
+        assert!(!needs_cache_env("nvproton run 1145360 -- %command%"));
+        assert!(!needs_cache_env("DXVK_STATE_CACHE_PATH=/tmp %command%"));
+
+        let env = [
+            (
+                "DXVK_STATE_CACHE_PATH".to_string(),
+                "/home/gk/.cache/nvproton/dxvk/steam-1145360".to_string(),
+            ),
+            (
+                "MESA_SHADER_CACHE_DIR".to_string(),
+                "/home/gk/My Caches/mesa".to_string(),
+            ),
+        ];
+        let vars = "DXVK_STATE_CACHE_PATH=/home/gk/.cache/nvproton/dxvk/steam-1145360 MESA_SHADER_CACHE_DIR='/home/gk/My Caches/mesa'";
+        assert_eq!(with_cache_env("", &env), format!("{} %command%", vars));
+        assert_eq!(
+            with_cache_env("gamemoderun %command%", &env),
+            format!("{} gamemoderun %command%", vars)
+        );
+        assert_eq!(
+            with_cache_env(" -novid ", &env),
+            format!("{} %command% -novid", vars)
+        );
+
+        let mut distro = DistroInfo {
+            id: "bazzite".into(),
+            name: "Bazzite".into(),
+            immutable: Some(ImmutableKind::RpmOstree),
+            flatpak: false,
+        };
+        let text = modeset_instructions(&distro);
+        assert!(text.contains("rpm-ostree kargs --append=nvidia-drm.modeset=1"));
+        assert!(!text.contains("GRUB"));
+        distro.id = "arch".into();
+        distro.immutable = None;
+        let text = modeset_instructions(&distro);
+        assert!(text.contains("GRUB_CMDLINE_LINUX_DEFAULT"));
+        assert!(text.contains("options nvidia_drm modeset=1"));
+        assert!(!text.contains("kernelstub"));
+    }
+}
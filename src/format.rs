@@ -0,0 +1,96 @@
+//! YAML and TOML files for the config, profiles and game database
+//!
+//! Each file is looked up by stem (`config`, a profile name, `games`) in
+//! either format. New files use the format of the main config file, so
+//! converting it with `nvproton config convert` standardizes everything
+//! created afterwards.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::cli::FileFormat;
+
+impl FileFormat {
+    /// Format of a file by extension (`.yaml`, `.yml`, `.toml`)
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => Some(FileFormat::Yaml),
+            Some("toml") => Some(FileFormat::Toml),
+            _ => None,
+        }
+    }
+
+    /// Format of a file, reading anything but `.toml` as YAML
+    pub fn of(path: &Path) -> Self {
+        Self::from_path(path).unwrap_or(FileFormat::Yaml)
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            FileFormat::Yaml => "yaml",
+            FileFormat::Toml => "toml",
+        }
+    }
+
+    pub fn parse<T: DeserializeOwned>(self, contents: &str) -> Result<T> {
+        match self {
+            FileFormat::Yaml => serde_yaml::from_str(contents).context("failed to parse YAML"),
+            FileFormat::Toml => toml::from_str(contents).context("failed to parse TOML"),
+        }
+    }
+
+    pub fn encode<T: Serialize>(self, value: &T) -> Result<String> {
+        match self {
+            FileFormat::Yaml => serde_yaml::to_string(value).context("failed to encode YAML"),
+            FileFormat::Toml => toml::to_string_pretty(value).context("failed to encode TOML"),
+        }
+    }
+}
+
+/// Existing `<stem>.yaml`, `<stem>.yml` or `<stem>.toml` in `dir`
+pub fn locate(dir: &Path, stem: &str) -> Option<PathBuf> {
+    ["yaml", "yml", "toml"]
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", stem, ext)))
+        .find(|path| path.is_file())
+}
+
+/// Format for new files: that of the main config file in `config_dir`
+pub fn preferred(config_dir: &Path) -> FileFormat {
+    locate(config_dir, "config")
+        .and_then(|path| FileFormat::from_path(&path))
+        .unwrap_or(FileFormat::Yaml)
+}
+
+/// Path of `<stem>` in `dir`: the existing file, or a new one in `format`
+pub fn path_in(dir: &Path, stem: &str, format: FileFormat) -> PathBuf {
+    locate(dir, stem).unwrap_or_else(|| dir.join(format!("{}.{}", stem, format.extension())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_new_files_follow_config_format() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(preferred(dir.path()), FileFormat::Yaml);
+
+        fs::write(dir.path().join("config.toml"), "").unwrap();
+        assert_eq!(preferred(dir.path()), FileFormat::Toml);
+        assert_eq!(
+            path_in(dir.path(), "games", FileFormat::Toml),
+            dir.path().join("games.toml")
+        );
+
+        fs::write(dir.path().join("games.yaml"), "").unwrap();
+        assert_eq!(
+            path_in(dir.path(), "games", FileFormat::Toml),
+            dir.path().join("games.yaml")
+        );
+    }
+}
@@ -1,127 +1,533 @@
-use anyhow::Result;
+use std::collections::BTreeSet;
+use std::io::{self, BufWriter};
+use std::path::PathBuf;
+use std::time::Instant;
 
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+
+use crate::cache::format_bytes;
 use crate::cli::{
-    GamesArgs, GamesCommand, GamesInfoArgs, GamesListArgs, GamesScanArgs, GamesSetProfileArgs,
-    GamesShowArgs, OutputFormat,
+    DuSort, GameField, GamesArgs, GamesCommand, GamesDuArgs, GamesInfoArgs, GamesListArgs,
+    GamesPreferArgs, GamesScanArgs, GamesSessionArgs, GamesSetArgsArgs, GamesSetExeArgs,
+    GamesSetProfileArgs, GamesShowArgs, GamesTagArgs, GamesTargetsArgs, GamesWrappersArgs,
+    OutputFormat,
 };
 use crate::config::{ConfigManager, NvConfig};
-use crate::detection::{self, DetectionContext, GameDatabase, GameSource};
+use crate::detection::pe::PeInfo;
+use crate::detection::steam::{AppManifest, Depot};
+use crate::detection::{
+    self, DetectedGame, DetectionContext, Detector, GameDatabase, GameSource, VulkanCapabilities,
+};
+use crate::disk_usage::{self, SizeCache};
+use crate::dxvk::OverrideStore;
+use crate::errors::NvError;
+use crate::export;
+use crate::i18n::tr;
+use crate::launch_args::LaunchArgs;
+use crate::manifest;
+use crate::modloaders::{self, ModLoader};
+use crate::notify;
+use crate::nvapi::{self, DlssDlls};
+use crate::profile::ProfilePersistence;
+use crate::query::{GameFilter, Selection};
+use crate::runner;
+use crate::saves;
+use crate::session::{ProtonRecord, Session, SessionHistory};
+use crate::steam::client::{CloudFile, CloudSync, SteamClient};
+use crate::stream::{self, ListWriter};
+use crate::targets::{self, LaunchTarget, TargetOrigin};
+use crate::telemetry::{self, MetricStats, TelemetrySample};
+use crate::template::TemplateContext;
+use crate::term::{Cell, Progress, Table, Tone};
+use crate::undo::Recorder;
+use crate::wrappers::{WrapperChain, WrapperSpec};
+
+/// Width of telemetry sparklines in `games session --last`
+const SPARKLINE_WIDTH: usize = 60;
+
+type MetricExtractor = fn(&TelemetrySample) -> Option<f32>;
 
 /// Handle the `games` command
-pub fn handle_games(args: GamesArgs, manager: &ConfigManager, config: &mut NvConfig) -> Result<()> {
+pub fn handle_games(args: GamesArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
     match args.command {
         GamesCommand::List(list_args) => handle_list(list_args, manager, config),
         GamesCommand::Show(show_args) => handle_show(show_args, manager, config),
         GamesCommand::Scan(scan_args) => handle_scan(scan_args, manager, config),
         GamesCommand::SetProfile(set_args) => handle_set_profile(set_args, manager, config),
         GamesCommand::Info(info_args) => handle_info(info_args, manager, config),
+        GamesCommand::Session(session_args) => handle_session(session_args, manager),
+        GamesCommand::DiffSession(diff_args) => manifest::handle_diff_session(diff_args, manager),
+        GamesCommand::Prefer(prefer_args) => handle_prefer(prefer_args, manager),
+        GamesCommand::Du(du_args) => handle_du(du_args, manager, config),
+        GamesCommand::Wrappers(wrapper_args) => handle_wrappers(wrapper_args, manager),
+        GamesCommand::Tag(tag_args) => handle_tag(tag_args, manager),
+        GamesCommand::SetExe(exe_args) => handle_set_exe(exe_args, manager),
+        GamesCommand::SetArgs(set_args) => handle_set_args(set_args, manager),
+        GamesCommand::Targets(target_args) => handle_targets(target_args, manager, config),
+        GamesCommand::Export(export_args) => export::handle_export(export_args, manager),
+    }
+}
+
+impl GameField {
+    /// Name of the field in serialized games
+    fn key(self) -> &'static str {
+        match self {
+            GameField::Id => "id",
+            GameField::Name => "name",
+            GameField::Source => "source",
+            GameField::InstallDir => "install_dir",
+            GameField::Executable => "executable",
+            GameField::Fingerprint => "fingerprint",
+            GameField::Metadata => "metadata",
+        }
     }
 }
 
 fn handle_list(args: GamesListArgs, manager: &ConfigManager, _config: &NvConfig) -> Result<()> {
     let db = GameDatabase::load_or_default(manager.paths())?;
-    let games: Vec<_> = db
-        .games()
-        .filter(|g| {
-            if let Some(ref source) = args.source {
-                matches!(
-                    (&g.source, source.as_str()),
-                    (GameSource::Steam, "steam")
-                        | (GameSource::Heroic, "heroic")
-                        | (GameSource::Lutris, "lutris")
-                )
-            } else {
-                true
-            }
-        })
-        .collect();
 
-    if games.is_empty() {
-        println!("No games found. Run 'nvproton games scan' to detect games.");
+    // One row per game unless every entry (or one source) was asked for
+    let rows: Box<dyn Iterator<Item = (DetectedGame, Vec<String>)>> =
+        if args.all || args.source.is_some() {
+            let source = args.source.clone();
+            Box::new(
+                db.records()
+                    .filter(move |(_, record)| source.as_ref().is_none_or(|s| record.source == *s))
+                    .map(|(id, record)| (record.to_game(&id.id), Vec::new())),
+            )
+        } else {
+            Box::new(db.unique_games().into_iter().map(|group| {
+                let also = group
+                    .duplicates
+                    .iter()
+                    .map(|d| d.source.to_string())
+                    .collect();
+                (group.primary, also)
+            }))
+        };
+
+    let filter = GameFilter {
+        profile: args.profile,
+        missing_exe: args.missing_exe,
+        larger_than: args.larger_than,
+        not_played_since: args.not_played_since,
+        tags: args.tags,
+    };
+    let matches = (!filter.is_empty()).then(|| filter.matcher(&db, manager.paths()));
+    let mut rows = rows
+        .filter(|(game, _)| matches.as_ref().is_none_or(|matches| matches(game)))
+        .peekable();
+
+    if rows.peek().is_none() {
+        if matches.is_some() && db.records().next().is_some() {
+            println!("{}", tr!("games-no-matches"));
+        } else {
+            println!("{}", tr!("games-none"));
+        }
         return Ok(());
     }
 
     match args.format {
         OutputFormat::Text => {
-            println!("{:<12} {:<10} Name", "ID", "Source");
-            println!("{}", "-".repeat(60));
-            for game in &games {
-                println!("{:<12} {:<10} {}", game.id, game.source, game.name);
+            let mut table =
+                Table::new([tr!("column-id"), tr!("column-source"), tr!("column-name")]);
+            let mut count = 0;
+            for (game, also) in rows {
+                let name = if also.is_empty() {
+                    game.name
+                } else {
+                    let also = also.join(", ");
+                    format!("{} {}", game.name, tr!("games-also", sources = also))
+                };
+                table.row([game.id, game.source.to_string(), name]);
+                count += 1;
             }
-            println!("\n{} games found", games.len());
-        }
-        OutputFormat::Json => {
-            println!("{}", serde_json::to_string_pretty(&games)?);
+            print!("{}", table);
+            println!("\n{}", tr!("games-found", count = count));
         }
-        OutputFormat::Yaml => {
-            println!("{}", serde_yaml::to_string(&games)?);
+        format => {
+            let fields: Vec<_> = args.fields.iter().map(|field| field.key()).collect();
+            let mut writer = ListWriter::new(BufWriter::new(io::stdout().lock()), format);
+            for (game, _) in rows {
+                if fields.is_empty() {
+                    writer.write(&game)?;
+                } else {
+                    writer.write(&stream::select(&game, &fields)?)?;
+                }
+            }
+            writer.finish()?;
         }
     }
 
     Ok(())
 }
 
-fn handle_show(args: GamesShowArgs, manager: &ConfigManager, _config: &NvConfig) -> Result<()> {
+/// Save a user change to the database, recording it for `nvproton undo`
+fn save_undoable(db: &GameDatabase, manager: &ConfigManager) -> Result<()> {
+    let mut undo = Recorder::begin(manager.paths());
+    undo.backup(&GameDatabase::path(manager.paths()))?;
+    db.save(manager.paths())?;
+    undo.commit()
+}
+
+fn handle_prefer(args: GamesPreferArgs, manager: &ConfigManager) -> Result<()> {
+    let mut db = GameDatabase::load_or_default(manager.paths())?;
+    let game_id = db.resolve(&args.game_id)?;
+    let game = db.prefer(&game_id, &args.source)?;
+    save_undoable(&db, manager)?;
+    println!(
+        "{}",
+        tr!(
+            "games-prefer",
+            name = game.name,
+            source = game.source,
+            id = game.id
+        )
+    );
+    Ok(())
+}
+
+fn handle_du(args: GamesDuArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
     let db = GameDatabase::load_or_default(manager.paths())?;
+    let games: Vec<DetectedGame> = db.unique_games().into_iter().map(|g| g.primary).collect();
+    if games.is_empty() {
+        println!("{}", tr!("games-none"));
+        return Ok(());
+    }
+
+    let mut cache = SizeCache::load(manager.paths())?;
+    let progress = Progress::new("Measuring");
+    let mut usage = disk_usage::measure(config, &games, &mut cache, args.refresh, &progress);
+    drop(progress);
+    cache.save(manager.paths())?;
+
+    match args.sort {
+        DuSort::Total => usage.sort_by_key(|u| std::cmp::Reverse(u.total())),
+        DuSort::Install => usage.sort_by_key(|u| std::cmp::Reverse(u.install)),
+        DuSort::Prefix => usage.sort_by_key(|u| std::cmp::Reverse(u.prefix)),
+        DuSort::Cache => usage.sort_by_key(|u| std::cmp::Reverse(u.shader_cache)),
+        DuSort::Name => usage.sort_by_key(|u| u.name.to_lowercase()),
+    }
 
-    if let Some(game) = db.get(&args.game_id) {
-        println!("Name:        {}", game.name);
-        println!("ID:          {}", game.id);
-        println!("Source:      {}", game.source);
-        println!("Install Dir: {:?}", game.install_dir);
-        if let Some(exe) = &game.executable {
-            println!("Executable:  {:?}", exe);
-        }
-        if let Some(fp) = &game.fingerprint {
-            println!("Fingerprint: {}", fp);
-        }
-        if !game.metadata.is_empty() {
-            println!("Metadata:");
-            for (key, value) in &game.metadata {
-                println!("  {}: {}", key, value);
+    match args.format {
+        OutputFormat::Text => {
+            let mut table = Table::new([
+                tr!("column-id"),
+                tr!("column-install"),
+                tr!("column-prefix"),
+                tr!("column-cache"),
+                tr!("column-total"),
+                tr!("column-name"),
+            ])
+            .right(&[1, 2, 3, 4]);
+            for u in &usage {
+                table.row([
+                    u.game_id.clone(),
+                    format_bytes(u.install),
+                    format_bytes(u.prefix),
+                    format_bytes(u.shader_cache),
+                    format_bytes(u.total()),
+                    u.name.clone(),
+                ]);
             }
+            print!("{}", table);
+            let total: u64 = usage.iter().map(|u| u.total()).sum();
+            println!(
+                "\n{}",
+                tr!(
+                    "games-du-total",
+                    count = usage.len(),
+                    size = format_bytes(total)
+                )
+            );
         }
-    } else {
-        anyhow::bail!("Game '{}' not found in database", args.game_id);
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&usage)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&usage)?),
     }
 
     Ok(())
 }
 
-fn handle_scan(args: GamesScanArgs, manager: &ConfigManager, config: &mut NvConfig) -> Result<()> {
-    let ctx = DetectionContext::new(config, manager);
-    let mut all_games = Vec::new();
+/// `games show` output: the database entry plus live Steam install details
+#[derive(Serialize)]
+struct GameDetails {
+    #[serde(flatten)]
+    game: DetectedGame,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    install_state: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    installed_dlc: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    depots: Vec<Depot>,
+    /// Steam Cloud files and their sync state
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    cloud_files: Vec<CloudFile>,
+    /// The executable was set with `games set-exe`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    executable_override: bool,
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    tags: BTreeSet<String>,
+    #[serde(skip_serializing_if = "DlssDlls::is_empty")]
+    dlss: DlssDlls,
+    /// Why the shipped Frame Generation cannot work
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    frame_generation_blockers: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    mod_loaders: Vec<ModLoader>,
+    /// Launches per Proton build, from the session history
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    proton_versions: Vec<ProtonRecord>,
+}
 
-    println!("Scanning for games...\n");
+fn handle_show(args: GamesShowArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
+    let db = GameDatabase::load_or_default(manager.paths())?;
+    let game = db.resolve_game(&args.game_id)?;
+    let manifest = match game.source {
+        GameSource::Steam => AppManifest::locate(&game.install_dir, &game.id),
+        _ => None,
+    };
+    let cloud_files = match (&game.source, SteamClient::from_config(config)) {
+        (GameSource::Steam, Some(steam)) => steam.cloud_files(&game.id),
+        _ => Vec::new(),
+    };
+    let dlss = DlssDlls::find(&game.install_dir);
+    let frame_generation_blockers = if dlss.frame_generation.is_some() {
+        let caps = VulkanCapabilities::detect().ok();
+        nvapi::frame_generation_blockers(config, &game, caps.as_ref())
+    } else {
+        Vec::new()
+    };
+    let prefix = saves::wine_prefix(config, &game);
+    let mod_loaders = modloaders::detect(&game, prefix.as_deref());
+    let proton_versions =
+        SessionHistory::for_game(manager.paths(), &db, &game.game_id())?.proton_matrix();
+    let details = GameDetails {
+        install_state: manifest
+            .as_ref()
+            .map(|m| m.install_state().describe().to_string()),
+        installed_dlc: manifest
+            .as_ref()
+            .map(|m| m.installed_dlc())
+            .unwrap_or_default(),
+        depots: manifest.map(|m| m.depots).unwrap_or_default(),
+        cloud_files,
+        executable_override: db.has_executable_override(&game.game_id()),
+        tags: db
+            .get_game_tags(&game.game_id())
+            .cloned()
+            .unwrap_or_default(),
+        dlss,
+        frame_generation_blockers,
+        mod_loaders,
+        proton_versions,
+        game,
+    };
 
-    // Steam
-    print!("  Steam: ");
-    match detection::steam::SteamDetector::new().detect(&ctx, args.fingerprint) {
-        Ok(games) => {
-            println!("{} games found", games.len());
-            all_games.extend(games);
-        }
-        Err(e) => println!("error - {}", e),
+    match args.format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&details)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&details)?),
+        OutputFormat::Text => print_game_details(manager, &db, &details)?,
     }
+    Ok(())
+}
 
-    // Heroic
-    print!("  Heroic: ");
-    match detection::heroic::HeroicDetector::new().detect(&ctx, args.fingerprint) {
-        Ok(games) => {
-            println!("{} games found", games.len());
-            all_games.extend(games);
+fn print_game_details(
+    manager: &ConfigManager,
+    db: &GameDatabase,
+    details: &GameDetails,
+) -> Result<()> {
+    let game = &details.game;
+    print_field(tr!("field-name"), &game.name);
+    print_field(tr!("field-id"), &game.id);
+    print_field(tr!("field-source"), &game.source);
+    print_field(tr!("field-install-dir"), format!("{:?}", game.install_dir));
+    if let Some(state) = &details.install_state {
+        print_field(tr!("field-state"), state);
+    }
+    if !details.cloud_files.is_empty() {
+        print_cloud_files(&details.cloud_files);
+    }
+    if let Some(exe) = &game.executable {
+        let exe = format!("{:?}", exe);
+        if details.executable_override {
+            print_field(
+                tr!("field-executable"),
+                tr!("games-exe-override", exe = exe),
+            );
+        } else {
+            print_field(tr!("field-executable"), exe);
+        }
+    }
+    if let Some(fp) = &game.fingerprint {
+        print_field(tr!("field-fingerprint"), fp);
+    }
+    if let Some(info) = PeInfo::from_metadata(&game.metadata) {
+        print_field(tr!("field-architecture"), info.describe());
+    }
+    if !details.tags.is_empty() {
+        print_field(tr!("field-tags"), join_tags(&details.tags));
+    }
+    if !details.dlss.is_empty() {
+        print_field(tr!("field-dlss"), details.dlss.describe());
+        for reason in &details.frame_generation_blockers {
+            println!("  {}", tr!("games-dlss-fg-blocked", reason = reason));
+        }
+    }
+    for (i, loader) in details.mod_loaders.iter().enumerate() {
+        let label = if i == 0 {
+            tr!("field-mod-loaders")
+        } else {
+            String::new()
+        };
+        print_field(label, format!("{} ({:?})", loader.kind, loader.path));
+        if let Some(runtime) = loader.missing_runtime {
+            let id = &game.id;
+            println!(
+                "  {}",
+                tr!("games-mod-loader-missing", runtime = runtime, id = id)
+            );
+        }
+    }
+    let overrides = OverrideStore::load(manager.paths(), db)?;
+    let overrides = overrides.get(&game.game_id());
+    if overrides.is_empty() {
+        print_field(tr!("field-graphics"), tr!("games-graphics-default"));
+    } else {
+        print_field(tr!("field-graphics"), "");
+        for entry in overrides {
+            let prefix = format!("{:?}", entry.prefix);
+            println!(
+                "  {} {} {}",
+                entry.component.name(),
+                entry.version,
+                tr!("games-graphics-override", prefix = prefix)
+            );
         }
-        Err(e) => println!("error - {}", e),
     }
+    if !details.proton_versions.is_empty() {
+        print_field(tr!("field-proton"), "");
+        let mut table = Table::new([
+            tr!("column-proton"),
+            tr!("column-ok"),
+            tr!("column-failed"),
+            tr!("column-last"),
+        ])
+        .right(&[1, 2]);
+        for record in &details.proton_versions {
+            let tone = match (record.successes, record.failures) {
+                (0, _) => Tone::Bad,
+                (_, 0) => Tone::Good,
+                _ => Tone::Warn,
+            };
+            table.row([
+                Cell::toned(&record.proton, tone),
+                record.successes.to_string().into(),
+                record.failures.to_string().into(),
+                format_age(record.last_launched).into(),
+            ]);
+        }
+        for line in table.to_string().lines() {
+            println!("  {}", line);
+        }
+    }
+    if !details.installed_dlc.is_empty() {
+        print_field(tr!("field-dlc"), details.installed_dlc.join(", "));
+    }
+    if !details.depots.is_empty() {
+        print_field(tr!("field-depots"), "");
+        for depot in &details.depots {
+            let line = tr!(
+                "games-depot",
+                id = depot.id,
+                manifest = depot.manifest,
+                size = format_bytes(depot.size)
+            );
+            match &depot.dlc_appid {
+                Some(dlc) => println!("  {} - {}", line, tr!("games-depot-dlc", dlc = dlc)),
+                None => println!("  {}", line),
+            }
+        }
+    }
+    if !game.metadata.is_empty() {
+        print_field(tr!("field-metadata"), "");
+        for (key, value) in &game.metadata {
+            println!("  {}: {}", key, value);
+        }
+    }
+    Ok(())
+}
+
+/// Summarize the Steam Cloud state, listing the files that are not in sync
+fn print_cloud_files(files: &[CloudFile]) {
+    let unsynced: Vec<&CloudFile> = files
+        .iter()
+        .filter(|file| file.state != CloudSync::Synced)
+        .collect();
+    let count = files.len();
+    if unsynced.is_empty() {
+        print_field(tr!("field-cloud"), tr!("games-cloud-synced", count = count));
+        return;
+    }
+    let conflicts = unsynced
+        .iter()
+        .filter(|file| file.state == CloudSync::Conflict)
+        .count();
+    print_field(
+        tr!("field-cloud"),
+        tr!("games-cloud-unsynced", count = count, conflicts = conflicts),
+    );
+    for file in unsynced {
+        println!("  {:<40} {}", file.name, file.state.describe());
+    }
+}
+
+/// Print an aligned `Label: value` line of `games show`
+fn print_field(label: String, value: impl std::fmt::Display) {
+    println!("{:<13}{}", format!("{}:", label), value);
+}
+
+fn handle_scan(args: GamesScanArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
+    let started = Instant::now();
+    let mut ctx = DetectionContext::new(config, manager);
+    let mut all_games = Vec::new();
+
+    println!("{}\n", tr!("games-scanning"));
 
-    // Lutris
-    print!("  Lutris: ");
-    match detection::lutris::LutrisDetector::new().detect(&ctx, args.fingerprint) {
-        Ok(games) => {
-            println!("{} games found", games.len());
-            all_games.extend(games);
+    // Legendary is the standalone install, without Heroic
+    let sources: [(&str, Detector); 4] = [
+        ("Steam", |ctx, fp| {
+            detection::steam::SteamDetector::new().detect(ctx, fp)
+        }),
+        ("Heroic", |ctx, fp| {
+            detection::heroic::HeroicDetector::new().detect(ctx, fp)
+        }),
+        ("Lutris", |ctx, fp| {
+            detection::lutris::LutrisDetector::new().detect(ctx, fp)
+        }),
+        ("Legendary", |ctx, fp| {
+            detection::legendary::LegendaryDetector::new().detect(ctx, fp)
+        }),
+    ];
+    for (label, detect) in sources {
+        if !config.detectors.source_enabled(label) {
+            println!("  {}: {}", label, tr!("games-scan-disabled"));
+            continue;
         }
-        Err(e) => println!("error - {}", e),
+        ctx.progress = Progress::new(label);
+        let result = detect(&ctx, args.fingerprint);
+        // Clear the progress line before reporting
+        ctx.progress = Progress::hidden();
+        match result {
+            Ok(games) => {
+                println!("  {}: {}", label, tr!("games-found", count = games.len()));
+                all_games.extend(games);
+            }
+            Err(e) => println!("  {}: {}", label, tr!("games-scan-error", error = e)),
+        }
+    }
+    if let Err(e) = ctx.save_executables() {
+        log::warn!("failed to save the executable cache: {:#}", e);
     }
 
     // Update database
@@ -130,17 +536,27 @@ fn handle_scan(args: GamesScanArgs, manager: &ConfigManager, config: &mut NvConf
         .duration_since(std::time::UNIX_EPOCH)?
         .as_secs();
 
+    // With --tag, only refresh games that already carry the tags
+    all_games.retain(|game| db.has_tags(&game.game_id(), &args.tags));
+
     // Clean out old excluded entries (Proton, Runtime, etc.)
     let cleaned = db.cleanup_excluded();
     if cleaned > 0 {
-        println!("  Cleaned: {} excluded entries removed", cleaned);
+        println!("  {}", tr!("games-cleaned", count = cleaned));
     }
 
     db.merge_detected(&all_games, timestamp);
     db.save(manager.paths())?;
 
-    println!("\nTotal: {} games added to database", all_games.len());
-    println!("Use 'nvproton games list' to see all games");
+    println!("\n{}", tr!("games-scan-total", count = all_games.len()));
+    println!("{}", tr!("games-scan-hint"));
+    notify::finished(
+        &config.notifications,
+        notify::Kind::Scan,
+        started.elapsed(),
+        &tr!("games-scan-notify"),
+        &tr!("games-scan-total", count = all_games.len()),
+    );
 
     Ok(())
 }
@@ -152,82 +568,469 @@ fn handle_set_profile(
 ) -> Result<()> {
     let mut db = GameDatabase::load_or_default(manager.paths())?;
 
-    if db.get(&args.game_id).is_none() {
-        anyhow::bail!("Game '{}' not found in database", args.game_id);
-    }
+    // With --tag the only positional argument is the profile
+    let (games, profile) = match (args.target, args.profile) {
+        (Some(profile), None) if !args.tags.is_empty() => {
+            let tags = args.tags.iter().cloned().map(Selection::Tag).collect();
+            let games = Selection::All(tags).select(&db);
+            if games.is_empty() {
+                bail!("no games are tagged {}", args.tags.join(", "));
+            }
+            (games, profile)
+        }
+        (Some(target), Some(profile)) if args.tags.is_empty() => {
+            (select_games(&db, &target)?, profile)
+        }
+        _ => bail!(
+            "usage: games set-profile <GAME_ID|SELECTION> <PROFILE> or games set-profile --tag <TAG> <PROFILE>"
+        ),
+    };
 
     // Verify profile exists
     let profile_manager = crate::profile::ProfileManager::new(manager.paths().profiles_dir.clone());
-    if !profile_manager.exists(&args.profile) {
-        anyhow::bail!(
-            "Profile '{}' not found. Use 'nvproton profile list' to see available profiles.",
-            args.profile
+    if !profile_manager.exists(&profile) {
+        return Err(NvError::ProfileNotFound(profile).into());
+    }
+
+    if args.dry_run {
+        println!(
+            "{}",
+            tr!(
+                "games-profile-dry-run",
+                profile = profile,
+                count = games.len()
+            )
         );
+        for game in &games {
+            println!("  {:<12} {}", game.id, game.name);
+        }
+        return Ok(());
     }
 
-    db.set_game_profile(&args.game_id, &args.profile);
+    // `run` and `prepare` read the binding, so it is written alongside the
+    // database entry and both come back with one undo
+    let persistence_path = ProfilePersistence::path(manager.paths());
+    let persistence = ProfilePersistence::open(&persistence_path)
+        .context("failed to open profile persistence database")?;
+    let mut undo = Recorder::begin(manager.paths());
+    undo.backup(&GameDatabase::path(manager.paths()))?;
+    undo.backup(&persistence_path)?;
+    for game in &games {
+        let game_id = game.game_id();
+        db.set_game_profile(&game_id, &profile);
+        persistence
+            .bind(&game_id.to_string(), &profile)
+            .with_context(|| {
+                format!("failed to bind profile '{}' to game '{}'", profile, game_id)
+            })?;
+    }
     db.save(manager.paths())?;
+    undo.commit()?;
 
-    println!(
-        "Profile '{}' assigned to game '{}'",
-        args.profile, args.game_id
-    );
+    for game in &games {
+        println!(
+            "{}",
+            tr!("games-profile-set", profile = profile, id = game.id)
+        );
+    }
     Ok(())
 }
 
-fn handle_info(args: GamesInfoArgs, manager: &ConfigManager, _config: &NvConfig) -> Result<()> {
-    let db = GameDatabase::load_or_default(manager.paths())?;
+/// Games named by an exact game ID, or matching a selection expression
+fn select_games(db: &GameDatabase, target: &str) -> Result<Vec<DetectedGame>> {
+    if db.lookup(target).is_some() {
+        return Ok(vec![db.resolve_game(target)?]);
+    }
+    let selection = Selection::parse(target).map_err(anyhow::Error::msg)?;
+    let games = selection.select(db);
+    if games.is_empty() {
+        if selection.is_plain_word() {
+            return Err(NvError::GameNotFound(target.to_string()).into());
+        }
+        bail!("no games match '{}'", target);
+    }
+    Ok(games)
+}
 
-    if let Some(game) = db.get(&args.game_id) {
-        println!("Game: {} ({})", game.name, game.id);
-        println!();
+fn handle_wrappers(args: GamesWrappersArgs, manager: &ConfigManager) -> Result<()> {
+    let mut db = GameDatabase::load_or_default(manager.paths())?;
+    let game_id = db.resolve(&args.game_id)?;
 
-        // Show recommended launch command
-        if args.command {
-            println!("Launch Command:");
-            match game.source {
-                GameSource::Steam => {
-                    println!("  nvproton run {} --reflex --vrr", game.id);
-                    println!();
-                    println!("Or with Steam directly:");
-                    println!("  steam -applaunch {}", game.id);
-                }
-                GameSource::Heroic => {
-                    println!("  nvproton run {} --reflex", game.id);
-                    println!();
-                    println!("Or with Heroic directly:");
-                    println!("  heroic --launch {}", game.id);
-                }
-                GameSource::Lutris => {
-                    println!("  nvproton run {}", game.id);
-                    println!();
-                    println!("Or with Lutris directly:");
-                    println!("  lutris lutris:rungame/{}", game.id);
-                }
-                GameSource::Unknown => {
-                    if let Some(exe) = &game.executable {
-                        println!("  {:?}", exe);
-                    } else {
-                        println!("  (no executable found)");
-                    }
-                }
+    if args.clear || !args.set.is_empty() {
+        let wrappers: Vec<WrapperSpec> = args
+            .set
+            .iter()
+            .map(|command| match args.priority {
+                Some(priority) => WrapperSpec::Prioritized {
+                    command: command.clone(),
+                    priority,
+                },
+                None => WrapperSpec::Command(command.clone()),
+            })
+            .collect();
+        let chain = WrapperChain::resolve(None, &wrappers)?;
+        if let Err(e) = chain.validate() {
+            eprintln!("{}", tr!("warning", message = e));
+        }
+        db.set_game_wrappers(&game_id, wrappers);
+        save_undoable(&db, manager)?;
+    }
+
+    let wrappers = db.get_game_wrappers(&game_id);
+    if wrappers.is_empty() {
+        println!("{}", tr!("games-wrappers-none", id = args.game_id));
+        return Ok(());
+    }
+    println!("{}", tr!("games-wrappers", id = args.game_id));
+    for wrapper in wrappers {
+        println!("  [{}] {}", wrapper.priority(), wrapper.command());
+    }
+    Ok(())
+}
+
+fn handle_tag(args: GamesTagArgs, manager: &ConfigManager) -> Result<()> {
+    let mut db = GameDatabase::load_or_default(manager.paths())?;
+    let game_id = db.resolve(&args.game_id)?;
+    let mut tags = db.get_game_tags(&game_id).cloned().unwrap_or_default();
+    if args.clear || !args.tags.is_empty() {
+        if args.clear {
+            tags.clear();
+        }
+        if args.remove {
+            for tag in &args.tags {
+                tags.remove(tag);
             }
         } else {
-            // Show quick info
-            println!("Source: {}", game.source);
-            println!("Install: {:?}", game.install_dir);
+            tags.extend(args.tags);
+        }
+        db.set_game_tags(&game_id, tags.clone());
+        save_undoable(&db, manager)?;
+    }
 
-            // Show associated profile if any
-            if let Some(profile) = db.get_game_profile(&args.game_id) {
-                println!("Profile: {}", profile);
-            }
+    if tags.is_empty() {
+        println!("{}", tr!("games-tags-none", id = args.game_id));
+    } else {
+        let tags = join_tags(&tags);
+        println!("{}", tr!("games-tags", id = args.game_id, tags = tags));
+    }
+    Ok(())
+}
+
+fn handle_set_exe(args: GamesSetExeArgs, manager: &ConfigManager) -> Result<()> {
+    let mut db = GameDatabase::load_or_default(manager.paths())?;
+    let game = db.resolve_game(&args.game_id)?;
+    let game_id = game.game_id();
+
+    let executable = match &args.path {
+        Some(path) => Some(game_file(&game, path)?),
+        None => None,
+    };
+
+    db.set_game_executable(&game_id, executable.clone());
+    save_undoable(&db, manager)?;
+    match executable {
+        Some(exe) => {
+            let exe = format!("{:?}", exe);
+            println!("{}", tr!("games-exe-set", name = game.name, exe = exe));
+        }
+        None => println!("{}", tr!("games-exe-cleared", name = game.name)),
+    }
+    Ok(())
+}
+
+fn handle_set_args(args: GamesSetArgsArgs, manager: &ConfigManager) -> Result<()> {
+    let mut db = GameDatabase::load_or_default(manager.paths())?;
+    let game = db.resolve_game(&args.game_id)?;
+    let game_id = game.game_id();
+
+    let (game_args, cwd) = db.get_game_args(&game_id);
+    let (mut game_args, mut cwd) = (game_args.to_vec(), cwd.map(String::from));
+    if args.clear || !args.args.is_empty() || args.cwd.is_some() {
+        if args.clear {
+            game_args.clear();
+            cwd = None;
+        }
+        if !args.args.is_empty() {
+            game_args = args.args;
+        }
+        if let Some(dir) = args.cwd {
+            cwd = Some(dir).filter(|dir| !dir.is_empty());
+        }
+        // Catch unknown placeholders now rather than at launch
+        let template = TemplateContext::for_game(&game, None);
+        LaunchArgs::resolve(None, &game_args, cwd.as_deref(), &template)?;
+        db.set_game_args(&game_id, game_args.clone(), cwd.clone());
+        save_undoable(&db, manager)?;
+    }
+
+    if game_args.is_empty() {
+        println!("{}", tr!("games-args-none", id = args.game_id));
+    } else {
+        let quoted: Vec<String> = game_args.iter().map(|a| runner::shell_quote(a)).collect();
+        let joined = quoted.join(" ");
+        println!("{}", tr!("games-args", id = args.game_id, args = joined));
+    }
+    if let Some(dir) = cwd {
+        println!("{}", tr!("games-args-cwd", dir = dir));
+    }
+    Ok(())
+}
+
+/// A file of a game given on the command line, looked up in its install
+/// directory first
+fn game_file(game: &DetectedGame, path: &str) -> Result<PathBuf> {
+    let in_install = game.install_dir.join(path);
+    let file = if in_install.is_file() {
+        in_install
+    } else {
+        std::path::absolute(path)?
+    };
+    if !file.is_file() {
+        bail!("{:?} is not a file", file);
+    }
+    Ok(file)
+}
+
+fn handle_targets(
+    args: GamesTargetsArgs,
+    manager: &ConfigManager,
+    config: &NvConfig,
+) -> Result<()> {
+    let mut db = GameDatabase::load_or_default(manager.paths())?;
+    let game = db.resolve_game(&args.game_id)?;
+    let game_id = game.game_id();
 
-            println!();
-            println!("Use --command to see launch options");
+    let mut user = db.get_game_targets(&game_id).to_vec();
+    if let [name, path] = args.add.as_slice() {
+        let target = LaunchTarget {
+            name: name.clone(),
+            executable: game_file(&game, path)?,
+            arguments: args
+                .args
+                .as_deref()
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            description: args.description,
+            origin: TargetOrigin::User,
+        };
+        user.retain(|t| t.name != target.name);
+        user.push(target);
+    } else if let Some(name) = &args.remove {
+        let before = user.len();
+        user.retain(|t| &t.name != name);
+        if user.len() == before {
+            bail!(
+                "'{}' has no launch target '{}' added with --add",
+                args.game_id,
+                name
+            );
         }
+    }
+    if !args.add.is_empty() || args.remove.is_some() {
+        db.set_game_targets(&game_id, user.clone());
+        save_undoable(&db, manager)?;
+    }
+
+    let steam = SteamClient::from_config(config);
+    let prefix = saves::wine_prefix(config, &game);
+    let loaders = modloaders::detect(&game, prefix.as_deref());
+    let targets = targets::available(&user, steam.as_ref(), &game, &loaders);
+    if targets.is_empty() {
+        println!("{}", tr!("games-targets-none", id = args.game_id));
+        return Ok(());
+    }
+    println!("{}", tr!("games-targets", id = args.game_id));
+    for target in &targets {
+        let mut line = format!("  {:<16} {:?}", target.name, target.executable);
+        if !target.arguments.is_empty() {
+            line.push(' ');
+            line.push_str(&target.arguments.join(" "));
+        }
+        if let Some(description) = &target.description {
+            line.push_str(&format!(" - {}", description));
+        }
+        match target.origin {
+            TargetOrigin::User => {}
+            TargetOrigin::Steam => line.push_str(&format!(" {}", tr!("games-target-steam"))),
+            TargetOrigin::ModLoader => {
+                line.push_str(&format!(" {}", tr!("games-target-mod-loader")))
+            }
+        }
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+fn join_tags(tags: &BTreeSet<String>) -> String {
+    tags.iter().cloned().collect::<Vec<_>>().join(", ")
+}
+
+fn handle_info(args: GamesInfoArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
+    let db = GameDatabase::load_or_default(manager.paths())?;
+    let game = db.resolve_game(&args.game_id)?;
+    let game_id = game.game_id();
+    println!("{}", tr!("games-info-game", name = game.name, id = game.id));
+    println!();
+
+    // Show the launch plan from the same code `run` uses
+    if args.command {
+        runner::print_launch_plan(manager, config, &game, &args.launch)?;
     } else {
-        anyhow::bail!("Game '{}' not found in database", args.game_id);
+        // Show quick info
+        println!("{}", tr!("games-info-source", source = game.source));
+        let dir = format!("{:?}", game.install_dir);
+        println!("{}", tr!("games-info-install", dir = dir));
+
+        // Show associated profile if any
+        if let Some(profile) = db.get_game_profile(&game_id) {
+            println!("{}", tr!("games-info-profile", profile = profile));
+        }
+        if let Some(tags) = db.get_game_tags(&game_id)
+            && !tags.is_empty()
+        {
+            println!("{}", tr!("games-info-tags", tags = join_tags(tags)));
+        }
+
+        println!();
+        println!("{}", tr!("games-info-hint"));
+    }
+
+    Ok(())
+}
+
+fn handle_session(args: GamesSessionArgs, manager: &ConfigManager) -> Result<()> {
+    let db = GameDatabase::load_or_default(manager.paths())?;
+    let game_id = db.resolve_game(&args.game_id)?.game_id();
+    let history = SessionHistory::for_game(manager.paths(), &db, &game_id)?;
+
+    if history.sessions.is_empty() {
+        println!("{}", tr!("session-none", id = args.game_id));
+        return Ok(());
+    }
+
+    if args.last {
+        let session = history.last().expect("history is not empty");
+        print_session_details(manager, &game_id.to_string(), session);
+        return Ok(());
+    }
+
+    let mut table = Table::new([
+        "#".to_string(),
+        tr!("column-started"),
+        tr!("column-runtime"),
+        tr!("column-exit"),
+        "Xid".to_string(),
+        tr!("column-profile"),
+    ])
+    .right(&[0, 2, 3, 4]);
+    for (index, session) in history.sessions.iter().rev().enumerate() {
+        let exit = match session.exit_code {
+            Some(code) => Cell::toned(code, if code == 0 { Tone::Good } else { Tone::Bad }),
+            None => "-".into(),
+        };
+        let xids = session.xid_errors.len();
+        table.row([
+            (index + 1).to_string().into(),
+            format_age(session.started_at).into(),
+            format_duration(session.runtime_secs).into(),
+            exit,
+            match xids {
+                0 => xids.to_string().into(),
+                _ => Cell::toned(xids, Tone::Bad),
+            },
+            session.profile.as_deref().unwrap_or("-").into(),
+        ]);
     }
+    print!("{}", table);
+    let count = history.sessions.len();
+    println!("\n{}", tr!("session-count", count = count));
 
     Ok(())
 }
+
+fn print_session_details(manager: &ConfigManager, game_id: &str, session: &Session) {
+    println!("{}", tr!("session-last", id = game_id));
+    let started = format_age(session.started_at);
+    println!("  {}", tr!("session-started", started = started));
+    let runtime = format_duration(session.runtime_secs);
+    println!("  {}", tr!("session-runtime", runtime = runtime));
+    let result = match (session.success, session.exit_code) {
+        (true, _) => tr!("session-result-ok"),
+        (false, Some(code)) => tr!("session-result-exit", code = code),
+        (false, None) => tr!("session-result-signal"),
+    };
+    println!("  {}", tr!("session-result", result = result));
+    if let Some(profile) = &session.profile {
+        println!("  {}", tr!("session-profile", profile = profile));
+    }
+    if let Some(proton) = &session.proton {
+        println!("  {}", tr!("session-proton", proton = proton));
+    }
+
+    if !session.xid_errors.is_empty() {
+        println!("\n{}", tr!("session-xid-errors"));
+        for xid in &session.xid_errors {
+            println!("  Xid {} - {}", xid.code, xid.description());
+        }
+    }
+
+    let path = SessionHistory::telemetry_path(manager.paths(), game_id, session);
+    let samples = match telemetry::load_samples(&path) {
+        Ok(samples) if !samples.is_empty() => samples,
+        _ => {
+            println!("\n{}", tr!("session-no-telemetry"));
+            return;
+        }
+    };
+
+    println!("\n{}", tr!("session-telemetry", count = samples.len()));
+    let metrics: [(&str, &str, MetricExtractor); 4] = [
+        ("Temp", "C", |s| s.temp_c.map(|v| v as f32)),
+        ("Power", "W", |s| s.power_w),
+        ("Clock", "MHz", |s| s.gpu_clock_mhz.map(|v| v as f32)),
+        ("VRAM", "MB", |s| s.vram_used_mb.map(|v| v as f32)),
+    ];
+    for (label, unit, extract) in metrics {
+        let values = telemetry::series(&samples, extract);
+        let Some(stats) = MetricStats::from_values(&values) else {
+            continue;
+        };
+        println!(
+            "  {:<6} {}  min {:.0} / avg {:.0} / max {:.0} {}",
+            label,
+            telemetry::sparkline(&values, SPARKLINE_WIDTH),
+            stats.min,
+            stats.avg,
+            stats.max,
+            unit
+        );
+    }
+}
+
+/// Format a unix timestamp relative to now (e.g., "3h ago")
+pub(crate) fn format_age(timestamp: u64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(timestamp);
+    let ago = now.saturating_sub(timestamp);
+    match ago {
+        0..60 => tr!("age-just-now"),
+        60..3600 => tr!("age-ago", age = format!("{}m", ago / 60)),
+        3600..86400 => tr!("age-ago", age = format!("{}h", ago / 3600)),
+        _ => tr!("age-ago", age = format!("{}d", ago / 86400)),
+    }
+}
+
+/// Format seconds as a compact duration (e.g., "1h 05m")
+pub(crate) fn format_duration(secs: u64) -> String {
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
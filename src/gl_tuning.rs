@@ -0,0 +1,133 @@
+//! NVIDIA OpenGL driver tuning
+//!
+//! Keys under a profile's `nvidia` section become `__GL_<KEY>` variables.
+//! The driver's tuning knobs have names of their own and values that are
+//! easy to get wrong, so these keys are validated and mapped explicitly:
+//!
+//! | key                    | variable                      | values                        |
+//! |------------------------|-------------------------------|-------------------------------|
+//! | threaded_optimizations | __GL_THREADED_OPTIMIZATIONS   | true / false                  |
+//! | max_frames_allowed     | __GL_MaxFramesAllowed         | 1-3                           |
+//! | yield                  | __GL_YIELD                    | usleep, nothing               |
+//! | shader_disk_cache_size | __GL_SHADER_DISK_CACHE_SIZE   | size, e.g. 4G (128M or more)  |
+//!
+//! - `threaded_optimizations` moves driver work to a second thread. It helps
+//!   CPU-bound OpenGL games and slows down ones that read back a lot of state.
+//! - `max_frames_allowed` is how many frames the CPU may queue ahead of the
+//!   GPU; 1 gives the lowest latency, 3 the smoothest frame pacing.
+//! - `yield` is how the driver waits for the GPU: `usleep` sleeps, lowering CPU
+//!   use; `nothing` spins, which can help frame times on spare cores.
+//! - `shader_disk_cache_size` is where the driver starts evicting compiled
+//!   shaders; large games outgrow the default and recompile every launch.
+
+use serde_yaml::Value;
+
+/// Smallest shader cache worth setting; less doesn't hold one game's shaders
+const MIN_SHADER_CACHE_SIZE: u64 = 128 << 20;
+
+/// A tuning key of the `nvidia` profile section
+pub struct GlKnob {
+    pub key: &'static str,
+    pub variable: &'static str,
+    parse: fn(&str) -> Result<String, String>,
+}
+
+pub const KNOBS: &[GlKnob] = &[
+    GlKnob {
+        key: "threaded_optimizations",
+        variable: "__GL_THREADED_OPTIMIZATIONS",
+        parse: parse_bool,
+    },
+    GlKnob {
+        key: "max_frames_allowed",
+        variable: "__GL_MaxFramesAllowed",
+        parse: parse_max_frames,
+    },
+    GlKnob {
+        key: "yield",
+        variable: "__GL_YIELD",
+        parse: parse_yield,
+    },
+    GlKnob {
+        key: "shader_disk_cache_size",
+        variable: "__GL_SHADER_DISK_CACHE_SIZE",
+        parse: parse_cache_size,
+    },
+];
+
+/// The knob behind an `nvidia` section key, if it is one
+pub fn knob(key: &str) -> Option<&'static GlKnob> {
+    KNOBS.iter().find(|knob| knob.key == key)
+}
+
+impl GlKnob {
+    /// Environment value for a profile value
+    pub fn value(&self, value: &Value) -> Result<String, String> {
+        let text = match value {
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::String(s) => s.trim().to_string(),
+            _ => return Err(format!("nvidia.{} must be a single value", self.key)),
+        };
+        (self.parse)(&text).map_err(|e| format!("nvidia.{}: {}", self.key, e))
+    }
+}
+
+fn parse_bool(value: &str) -> Result<String, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok("1".into()),
+        "false" | "no" | "off" | "0" => Ok("0".into()),
+        _ => Err(format!("expected true or false, got '{}'", value)),
+    }
+}
+
+fn parse_max_frames(value: &str) -> Result<String, String> {
+    match value.parse::<u8>() {
+        Ok(frames @ 1..=3) => Ok(frames.to_string()),
+        _ => Err(format!("expected 1, 2 or 3 frames, got '{}'", value)),
+    }
+}
+
+fn parse_yield(value: &str) -> Result<String, String> {
+    match value.to_lowercase().as_str() {
+        "usleep" => Ok("USLEEP".into()),
+        "nothing" => Ok("NOTHING".into()),
+        _ => Err(format!("expected usleep or nothing, got '{}'", value)),
+    }
+}
+
+fn parse_cache_size(value: &str) -> Result<String, String> {
+    let bytes = crate::query::parse_size(value)?;
+    if bytes < MIN_SHADER_CACHE_SIZE {
+        return Err(format!("'{}' is too small, use 128M or more", value));
+    }
+    Ok(bytes.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(key: &str, value: &str) -> Result<String, String> {
+        knob(key).unwrap().value(&Value::String(value.into()))
+    }
+
+    #[test]
+    fn test_knobs_validate_and_map() {
+        assert_eq!(set("threaded_optimizations", "on").unwrap(), "1");
+        assert_eq!(
+            knob("max_frames_allowed").unwrap().variable,
+            "__GL_MaxFramesAllowed"
+        );
+        assert_eq!(set("max_frames_allowed", "1").unwrap(), "1");
+        assert!(set("max_frames_allowed", "4").is_err());
+        assert_eq!(set("yield", "usleep").unwrap(), "USLEEP");
+        assert!(set("yield", "sched").is_err());
+        assert_eq!(
+            set("shader_disk_cache_size", "4G").unwrap(),
+            (4u64 << 30).to_string()
+        );
+        assert!(set("shader_disk_cache_size", "1M").is_err());
+        assert!(knob("sync_to_vblank").is_none());
+    }
+}
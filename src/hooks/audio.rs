@@ -0,0 +1,186 @@
+//! Per-game audio routing
+//!
+//! Configured through the `audio` profile section:
+//!
+//! ```yaml
+//! audio:
+//!   sink: alsa_output.usb-SteelSeries_Arctis_7-00.analog-stereo
+//!   volume: 80
+//! ```
+//!
+//! `sink` becomes `PULSE_SINK` and `PIPEWIRE_NODE`, so both PulseAudio and
+//! native PipeWire clients open their streams on that device while desktop
+//! audio stays on the default sink. `volume` (percent) is applied to the
+//! game's streams as they appear; they are recognised by a property
+//! nvproton adds through `PULSE_PROP`.
+
+use std::collections::{HashMap, HashSet};
+use std::process::Command;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use serde_yaml::Value;
+
+use super::LaunchHook;
+
+/// Stream property marking audio streams opened by this session's game
+const SESSION_PROPERTY: &str = "nvproton.session";
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Apply an `audio` profile section to the launch environment
+pub fn apply_env(section: &Value, env_vars: &mut HashMap<String, String>) {
+    if let Some(sink) = section.get("sink").and_then(Value::as_str) {
+        env_vars.insert("PULSE_SINK".into(), sink.to_string());
+        env_vars.insert("PIPEWIRE_NODE".into(), sink.to_string());
+    }
+    if volume(section).is_some() {
+        let marker = format!("{}={}", SESSION_PROPERTY, std::process::id());
+        let props = match env_vars.get("PULSE_PROP") {
+            Some(existing) if !existing.is_empty() => format!("{} {}", existing, marker),
+            _ => marker,
+        };
+        env_vars.insert("PULSE_PROP".into(), props);
+    }
+}
+
+/// Build the hooks enabled in an `audio` profile section
+pub fn hooks(section: &Value) -> Vec<Box<dyn LaunchHook>> {
+    match volume(section) {
+        Some(percent) => vec![Box::new(StreamVolume {
+            percent,
+            stop: Arc::new(AtomicBool::new(false)),
+            watcher: None,
+        })],
+        None => Vec::new(),
+    }
+}
+
+fn volume(section: &Value) -> Option<u32> {
+    let percent = section.get("volume").and_then(Value::as_u64)?;
+    if percent > 150 {
+        log::warn!("audio volume {}% is above 150%; clamping", percent);
+    }
+    Some(percent.min(150) as u32)
+}
+
+/// Set the volume of the game's streams while it runs
+///
+/// Streams only exist once the game opens its audio device, so a watcher
+/// polls for new streams carrying the session marker.
+struct StreamVolume {
+    percent: u32,
+    stop: Arc<AtomicBool>,
+    watcher: Option<JoinHandle<()>>,
+}
+
+impl LaunchHook for StreamVolume {
+    fn name(&self) -> &str {
+        "audio-volume"
+    }
+
+    fn on_start(&mut self) -> Result<()> {
+        // Fail early if pactl is missing rather than polling silently
+        list_sink_inputs()?;
+        let marker = std::process::id().to_string();
+        let percent = self.percent;
+        let stop = Arc::clone(&self.stop);
+        self.watcher = Some(thread::spawn(move || {
+            let mut adjusted = HashSet::new();
+            while !stop.load(Ordering::Relaxed) {
+                for index in list_sink_inputs()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|input| input.properties.get(SESSION_PROPERTY) == Some(&marker))
+                    .map(|input| input.index)
+                {
+                    if adjusted.insert(index)
+                        && let Err(e) = set_volume(index, percent)
+                    {
+                        log::warn!("{}", e);
+                    }
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        }));
+        Ok(())
+    }
+
+    fn on_exit(&mut self) -> Result<()> {
+        // Streams close with the game, so there is no volume to restore
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(watcher) = self.watcher.take() {
+            let _ = watcher.join();
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SinkInput {
+    index: u32,
+    #[serde(default)]
+    properties: HashMap<String, String>,
+}
+
+fn list_sink_inputs() -> Result<Vec<SinkInput>> {
+    let output = Command::new("pactl")
+        .args(["--format=json", "list", "sink-inputs"])
+        .output()
+        .context("failed to run pactl")?;
+    if !output.status.success() {
+        bail!(
+            "pactl list sink-inputs failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    serde_json::from_slice(&output.stdout).context("failed to parse pactl output")
+}
+
+fn set_volume(index: u32, percent: u32) -> Result<()> {
+    let status = Command::new("pactl")
+        .args([
+            "set-sink-input-volume",
+            &index.to_string(),
+            &format!("{}%", percent),
+        ])
+        .status()
+        .context("failed to run pactl")?;
+    if !status.success() {
+        bail!("failed to set volume of stream {}", index);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audio_section_env() {
+        let section: Value = serde_yaml::from_str("sink: headphones\nvolume: 80\n").unwrap();
+        let mut env_vars =
+            HashMap::from([("PULSE_PROP".to_string(), "media.role=game".to_string())]);
+        apply_env(&section, &mut env_vars);
+
+        assert_eq!(env_vars["PULSE_SINK"], "headphones");
+        assert_eq!(env_vars["PIPEWIRE_NODE"], "headphones");
+        assert_eq!(
+            env_vars["PULSE_PROP"],
+            format!("media.role=game nvproton.session={}", std::process::id())
+        );
+        assert_eq!(hooks(&section).len(), 1);
+    }
+
+    #[test]
+    fn test_sink_only_needs_no_hook() {
+        let section: Value = serde_yaml::from_str("sink: headphones\n").unwrap();
+        let mut env_vars = HashMap::new();
+        apply_env(&section, &mut env_vars);
+        assert!(!env_vars.contains_key("PULSE_PROP"));
+        assert!(hooks(&section).is_empty());
+    }
+}
@@ -0,0 +1,289 @@
+//! Desktop environment hooks
+//!
+//! Configured through the `desktop` profile section:
+//!
+//! ```yaml
+//! desktop:
+//!   do_not_disturb: true
+//!   disable_night_light: true
+//!   suspend_compositor: true
+//! ```
+//!
+//! KDE Plasma is driven over D-Bus (inhibitions are released when the game
+//! exits); GNOME is driven through gsettings with the previous values restored.
+
+use std::collections::HashMap;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use serde_yaml::Value;
+use zbus::blocking::Connection;
+use zbus::zvariant::Value as DbusValue;
+
+use super::LaunchHook;
+
+/// Desktop environment the session is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Desktop {
+    Kde,
+    Gnome,
+    Other,
+}
+
+impl Desktop {
+    pub fn detect() -> Self {
+        let current = std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+        Self::from_xdg(&current)
+    }
+
+    fn from_xdg(current: &str) -> Self {
+        let current = current.to_ascii_lowercase();
+        if current.split(':').any(|d| d == "kde") {
+            Desktop::Kde
+        } else if current.split(':').any(|d| d == "gnome") {
+            Desktop::Gnome
+        } else {
+            Desktop::Other
+        }
+    }
+}
+
+/// Build the hooks enabled in a `desktop` profile section
+pub fn hooks(section: &Value) -> Vec<Box<dyn LaunchHook>> {
+    let desktop = Desktop::detect();
+    let enabled = |key: &str| section.get(key).and_then(Value::as_bool).unwrap_or(false);
+    let mut hooks: Vec<Box<dyn LaunchHook>> = Vec::new();
+
+    if enabled("do_not_disturb") {
+        match desktop {
+            Desktop::Kde => hooks.push(Box::new(DbusInhibit::notifications())),
+            Desktop::Gnome => hooks.push(Box::new(GSettingsOverride::new(
+                "do-not-disturb",
+                "org.gnome.desktop.notifications",
+                "show-banners",
+                "false",
+            ))),
+            Desktop::Other => log::warn!("do_not_disturb is only supported on KDE and GNOME"),
+        }
+    }
+
+    if enabled("disable_night_light") {
+        match desktop {
+            Desktop::Kde => hooks.push(Box::new(DbusInhibit::night_light())),
+            Desktop::Gnome => hooks.push(Box::new(GSettingsOverride::new(
+                "night-light",
+                "org.gnome.settings-daemon.plugins.color",
+                "night-light-enabled",
+                "false",
+            ))),
+            Desktop::Other => log::warn!("disable_night_light is only supported on KDE and GNOME"),
+        }
+    }
+
+    if enabled("suspend_compositor") {
+        match desktop {
+            Desktop::Kde => hooks.push(Box::new(KWinCompositor)),
+            // Mutter unredirects fullscreen windows on its own
+            _ => log::info!("suspend_compositor is only supported on KWin"),
+        }
+    }
+
+    hooks
+}
+
+/// Temporarily override a GSettings key
+struct GSettingsOverride {
+    name: &'static str,
+    schema: &'static str,
+    key: &'static str,
+    value: &'static str,
+    previous: Option<String>,
+}
+
+impl GSettingsOverride {
+    fn new(
+        name: &'static str,
+        schema: &'static str,
+        key: &'static str,
+        value: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            schema,
+            key,
+            value,
+            previous: None,
+        }
+    }
+}
+
+impl LaunchHook for GSettingsOverride {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn on_start(&mut self) -> Result<()> {
+        self.previous = Some(gsettings(&["get", self.schema, self.key])?);
+        gsettings(&["set", self.schema, self.key, self.value])?;
+        Ok(())
+    }
+
+    fn on_exit(&mut self) -> Result<()> {
+        if let Some(previous) = self.previous.take() {
+            gsettings(&["set", self.schema, self.key, &previous])?;
+        }
+        Ok(())
+    }
+}
+
+pub(super) fn gsettings(args: &[&str]) -> Result<String> {
+    let output = Command::new("gsettings")
+        .args(args)
+        .output()
+        .context("failed to run gsettings")?;
+    if !output.status.success() {
+        bail!(
+            "gsettings {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// A KDE inhibition that returns a cookie and is released by cookie
+///
+/// The bus connection is kept open for the whole session since Plasma drops
+/// inhibitions when their owner disconnects.
+struct DbusInhibit {
+    name: &'static str,
+    destination: &'static str,
+    path: &'static str,
+    interface: &'static str,
+    inhibit: &'static str,
+    release: &'static str,
+    with_reason: bool,
+    conn: Option<Connection>,
+    cookie: Option<u32>,
+}
+
+impl DbusInhibit {
+    fn notifications() -> Self {
+        Self {
+            name: "do-not-disturb",
+            destination: "org.freedesktop.Notifications",
+            path: "/org/freedesktop/Notifications",
+            interface: "org.freedesktop.Notifications",
+            inhibit: "Inhibit",
+            release: "UnInhibit",
+            with_reason: true,
+            conn: None,
+            cookie: None,
+        }
+    }
+
+    fn night_light() -> Self {
+        Self {
+            name: "night-light",
+            destination: "org.kde.KWin",
+            path: "/org/kde/KWin/NightLight",
+            interface: "org.kde.KWin.NightLight",
+            inhibit: "inhibit",
+            release: "uninhibit",
+            with_reason: false,
+            conn: None,
+            cookie: None,
+        }
+    }
+}
+
+impl LaunchHook for DbusInhibit {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn on_start(&mut self) -> Result<()> {
+        let conn = Connection::session().context("failed to connect to the session bus")?;
+        let reply = if self.with_reason {
+            let hints: HashMap<&str, DbusValue> = HashMap::new();
+            conn.call_method(
+                Some(self.destination),
+                self.path,
+                Some(self.interface),
+                self.inhibit,
+                &("nvproton", "Game running", hints),
+            )
+        } else {
+            conn.call_method(
+                Some(self.destination),
+                self.path,
+                Some(self.interface),
+                self.inhibit,
+                &(),
+            )
+        }
+        .with_context(|| format!("{}.{} failed", self.interface, self.inhibit))?;
+
+        self.cookie = Some(reply.body().deserialize()?);
+        self.conn = Some(conn);
+        Ok(())
+    }
+
+    fn on_exit(&mut self) -> Result<()> {
+        if let (Some(conn), Some(cookie)) = (self.conn.take(), self.cookie.take()) {
+            conn.call_method(
+                Some(self.destination),
+                self.path,
+                Some(self.interface),
+                self.release,
+                &(cookie,),
+            )
+            .with_context(|| format!("{}.{} failed", self.interface, self.release))?;
+        }
+        Ok(())
+    }
+}
+
+/// Suspend KWin compositing (X11 sessions only)
+struct KWinCompositor;
+
+impl KWinCompositor {
+    fn call(method: &str) -> Result<()> {
+        let conn = Connection::session().context("failed to connect to the session bus")?;
+        conn.call_method(
+            Some("org.kde.KWin"),
+            "/Compositor",
+            Some("org.kde.kwin.Compositing"),
+            method,
+            &(),
+        )
+        .with_context(|| format!("KWin compositor {} failed (X11 only)", method))?;
+        Ok(())
+    }
+}
+
+impl LaunchHook for KWinCompositor {
+    fn name(&self) -> &str {
+        "suspend-compositor"
+    }
+
+    fn on_start(&mut self) -> Result<()> {
+        Self::call("suspend")
+    }
+
+    fn on_exit(&mut self) -> Result<()> {
+        Self::call("resume")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_desktop() {
+        assert_eq!(Desktop::from_xdg("KDE"), Desktop::Kde);
+        assert_eq!(Desktop::from_xdg("ubuntu:GNOME"), Desktop::Gnome);
+        assert_eq!(Desktop::from_xdg("Hyprland"), Desktop::Other);
+    }
+}
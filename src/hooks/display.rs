@@ -0,0 +1,776 @@
+//! Display mode hooks
+//!
+//! Configured through the `display` profile section:
+//!
+//! ```yaml
+//! display:
+//!   output: DP-1          # default: the primary (or first enabled) output
+//!   resolution: 2560x1440
+//!   refresh: 240          # default: the current rate
+//!   vrr: true
+//! ```
+//!
+//! The mode is switched with kscreen-doctor on KDE Wayland, wlr-randr on
+//! wlroots compositors and xrandr on X11. The nearest mode the output
+//! advertises is used, and the previous mode is restored when the game exits.
+//!
+//! On Wayland the compositor owns adaptive sync, so `vrr` alone (and
+//! `nvproton run --vrr`) changes only the output's VRR policy: through
+//! kscreen-doctor on KWin, wlr-randr on wlroots compositors, and Mutter's
+//! `variable-refresh-rate` experimental feature on GNOME 46+. Mutter still
+//! needs VRR selected once per monitor in Settings > Displays.
+
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use serde_yaml::Value;
+
+use super::LaunchHook;
+use super::desktop::{Desktop, gsettings};
+use crate::paths::in_path;
+
+/// Requested display settings from a `display` profile section
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DisplaySettings {
+    pub output: Option<String>,
+    pub resolution: Option<(u32, u32)>,
+    pub refresh: Option<f64>,
+    pub vrr: Option<bool>,
+}
+
+impl DisplaySettings {
+    pub fn from_section(section: &Value) -> Result<Self> {
+        let resolution = match section.get("resolution").and_then(Value::as_str) {
+            Some(value) => Some(parse_resolution(value)?),
+            None => None,
+        };
+        Ok(Self {
+            output: section
+                .get("output")
+                .and_then(Value::as_str)
+                .map(String::from),
+            resolution,
+            refresh: section.get("refresh").and_then(Value::as_f64),
+            vrr: section.get("vrr").and_then(Value::as_bool),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resolution.is_none() && self.refresh.is_none() && self.vrr.is_none()
+    }
+}
+
+/// Parse `2560x1440`
+fn parse_resolution(value: &str) -> Result<(u32, u32)> {
+    let parsed = value
+        .trim()
+        .split_once(['x', 'X'])
+        .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)));
+    match parsed {
+        Some(resolution) => Ok(resolution),
+        None => bail!("invalid resolution '{}' (expected WIDTHxHEIGHT)", value),
+    }
+}
+
+/// Tool used to change display modes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    KscreenDoctor,
+    WlrRandr,
+    Xrandr,
+}
+
+impl Backend {
+    fn detect() -> Option<Self> {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            match Desktop::detect() {
+                Desktop::Kde => Some(Backend::KscreenDoctor),
+                // Mutter has no output management protocol for clients
+                Desktop::Gnome => None,
+                Desktop::Other => in_path("wlr-randr").then_some(Backend::WlrRandr),
+            }
+        } else if std::env::var_os("DISPLAY").is_some() {
+            Some(Backend::Xrandr)
+        } else {
+            None
+        }
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Backend::KscreenDoctor => "kscreen-doctor",
+            Backend::WlrRandr => "wlr-randr",
+            Backend::Xrandr => "xrandr",
+        }
+    }
+
+    /// Current state of the requested output (or the primary one)
+    fn query(self, output: Option<&str>) -> Result<OutputState> {
+        let outputs = match self {
+            Backend::KscreenDoctor => parse_kscreen(&run(self.program(), &["--json"])?)?,
+            Backend::WlrRandr => parse_wlr_randr(&run(self.program(), &["--json"])?)?,
+            Backend::Xrandr => parse_xrandr(&run(self.program(), &["--query"])?),
+        };
+        let found = match output {
+            Some(name) => outputs.into_iter().find(|o| o.name == name),
+            None => {
+                let mut enabled: Vec<_> = outputs
+                    .into_iter()
+                    .filter(|o| o.current.is_some())
+                    .collect();
+                let primary = enabled.iter().position(|o| o.primary).unwrap_or(0);
+                (!enabled.is_empty()).then(|| enabled.swap_remove(primary))
+            }
+        };
+        match found {
+            Some(state) if state.current.is_some() => Ok(state),
+            Some(state) => bail!("output {} is not enabled", state.name),
+            None => match output {
+                Some(name) => bail!("output {} not found", name),
+                None => bail!("no enabled outputs found"),
+            },
+        }
+    }
+
+    fn apply(self, output: &str, mode: &Mode, vrr: Option<AdaptiveSync>) -> Result<()> {
+        let mut args: Vec<String> = Vec::new();
+        match self {
+            Backend::KscreenDoctor => {
+                let mode_arg = mode.id.clone().unwrap_or_else(|| {
+                    format!("{}x{}@{}", mode.width, mode.height, mode.refresh.round())
+                });
+                args.push(format!("output.{}.mode.{}", output, mode_arg));
+                if let Some(vrr) = vrr {
+                    args.push(format!("output.{}.vrrpolicy.{}", output, vrr.policy()));
+                }
+            }
+            Backend::WlrRandr => {
+                args.extend([
+                    "--output".to_string(),
+                    output.to_string(),
+                    "--mode".to_string(),
+                    format!("{}x{}@{}Hz", mode.width, mode.height, mode.refresh),
+                ]);
+                if let Some(vrr) = vrr {
+                    args.push("--adaptive-sync".to_string());
+                    args.push(
+                        if vrr == AdaptiveSync::Never {
+                            "disabled"
+                        } else {
+                            "enabled"
+                        }
+                        .to_string(),
+                    );
+                }
+            }
+            Backend::Xrandr => {
+                args.extend([
+                    "--output".to_string(),
+                    output.to_string(),
+                    "--mode".to_string(),
+                    format!("{}x{}", mode.width, mode.height),
+                    "--rate".to_string(),
+                    format!("{:.2}", mode.refresh),
+                ]);
+            }
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run(self.program(), &args).map(|_| ())
+    }
+
+    /// Change only the adaptive sync policy of an output
+    fn set_vrr(self, output: &str, vrr: AdaptiveSync) -> Result<()> {
+        match self {
+            Backend::KscreenDoctor => run(
+                self.program(),
+                &[&format!("output.{}.vrrpolicy.{}", output, vrr.policy())],
+            ),
+            Backend::WlrRandr => run(
+                self.program(),
+                &[
+                    "--output",
+                    output,
+                    "--adaptive-sync",
+                    if vrr == AdaptiveSync::Never {
+                        "disabled"
+                    } else {
+                        "enabled"
+                    },
+                ],
+            ),
+            Backend::Xrandr => {
+                bail!("xrandr can't change adaptive sync; use __GL_GSYNC_ALLOWED/__GL_VRR_ALLOWED")
+            }
+        }
+        .map(|_| ())
+    }
+}
+
+/// Compositor adaptive sync policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AdaptiveSync {
+    Never,
+    Always,
+    /// KDE: enabled for fullscreen windows only
+    Automatic,
+}
+
+impl AdaptiveSync {
+    /// kscreen-doctor policy name
+    fn policy(self) -> &'static str {
+        match self {
+            AdaptiveSync::Never => "never",
+            AdaptiveSync::Always => "always",
+            AdaptiveSync::Automatic => "automatic",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Mode {
+    /// Backend mode identifier (kscreen-doctor)
+    id: Option<String>,
+    width: u32,
+    height: u32,
+    refresh: f64,
+}
+
+#[derive(Debug, Clone)]
+struct OutputState {
+    name: String,
+    primary: bool,
+    current: Option<Mode>,
+    modes: Vec<Mode>,
+    /// `None` when the backend can't report or change adaptive sync
+    vrr: Option<AdaptiveSync>,
+}
+
+impl OutputState {
+    /// Closest advertised mode to the requested settings
+    fn select_mode(&self, settings: &DisplaySettings) -> Option<&Mode> {
+        let current = self.current.as_ref()?;
+        let (width, height) = settings
+            .resolution
+            .unwrap_or((current.width, current.height));
+        let refresh = settings.refresh.unwrap_or(current.refresh);
+        self.modes
+            .iter()
+            .filter(|m| m.width == width && m.height == height)
+            .min_by(|a, b| {
+                (a.refresh - refresh)
+                    .abs()
+                    .total_cmp(&(b.refresh - refresh).abs())
+            })
+    }
+}
+
+/// Switch the display mode for the session and switch back afterwards
+struct DisplayHook {
+    backend: Backend,
+    settings: DisplaySettings,
+    /// Output name, mode and adaptive sync policy before the game started
+    previous: Option<(String, Mode, Option<AdaptiveSync>)>,
+}
+
+impl LaunchHook for DisplayHook {
+    fn name(&self) -> &str {
+        "display-mode"
+    }
+
+    fn on_start(&mut self) -> Result<()> {
+        let state = self.backend.query(self.settings.output.as_deref())?;
+        let current = state
+            .current
+            .clone()
+            .context("output has no current mode")?;
+        let target = state
+            .select_mode(&self.settings)
+            .cloned()
+            .with_context(|| {
+                let (width, height) = self
+                    .settings
+                    .resolution
+                    .unwrap_or((current.width, current.height));
+                format!("{} does not support {}x{}", state.name, width, height)
+            })?;
+        let vrr = match (self.settings.vrr, state.vrr) {
+            (Some(wanted), Some(_)) => Some(if wanted {
+                AdaptiveSync::Always
+            } else {
+                AdaptiveSync::Never
+            }),
+            (Some(_), None) => {
+                log::warn!(
+                    "vrr can't be changed with {}; on X11 use __GL_GSYNC_ALLOWED/__GL_VRR_ALLOWED",
+                    self.backend.program()
+                );
+                None
+            }
+            (None, _) => None,
+        };
+
+        if target == current && (vrr.is_none() || vrr == state.vrr) {
+            return Ok(());
+        }
+        self.backend.apply(&state.name, &target, vrr)?;
+        log::info!(
+            "{}: {}x{}@{:.2} -> {}x{}@{:.2}",
+            state.name,
+            current.width,
+            current.height,
+            current.refresh,
+            target.width,
+            target.height,
+            target.refresh
+        );
+        self.previous = Some((state.name, current, vrr.and(state.vrr)));
+        Ok(())
+    }
+
+    fn on_exit(&mut self) -> Result<()> {
+        if let Some((output, mode, vrr)) = self.previous.take() {
+            self.backend.apply(&output, &mode, vrr)?;
+        }
+        Ok(())
+    }
+}
+
+/// Switch an output's adaptive sync for the session (KWin, wlroots)
+struct AdaptiveSyncHook {
+    backend: Backend,
+    output: Option<String>,
+    enabled: bool,
+    /// Output name and policy before the game started
+    previous: Option<(String, AdaptiveSync)>,
+}
+
+impl LaunchHook for AdaptiveSyncHook {
+    fn name(&self) -> &str {
+        "adaptive-sync"
+    }
+
+    fn on_start(&mut self) -> Result<()> {
+        let state = self.backend.query(self.output.as_deref())?;
+        let Some(current) = state.vrr else {
+            bail!(
+                "{} can't change adaptive sync on {}",
+                self.backend.program(),
+                state.name
+            );
+        };
+        let wanted = if self.enabled {
+            AdaptiveSync::Always
+        } else {
+            AdaptiveSync::Never
+        };
+        if current == wanted {
+            return Ok(());
+        }
+        self.backend.set_vrr(&state.name, wanted)?;
+        log::info!(
+            "{}: adaptive sync {} -> {}",
+            state.name,
+            current.policy(),
+            wanted.policy()
+        );
+        self.previous = Some((state.name, current));
+        Ok(())
+    }
+
+    fn on_exit(&mut self) -> Result<()> {
+        if let Some((output, vrr)) = self.previous.take() {
+            self.backend.set_vrr(&output, vrr)?;
+        }
+        Ok(())
+    }
+}
+
+const MUTTER_SCHEMA: &str = "org.gnome.mutter";
+const MUTTER_FEATURES: &str = "experimental-features";
+const MUTTER_VRR_FEATURE: &str = "variable-refresh-rate";
+
+/// Toggle Mutter's variable refresh rate support for the session
+struct MutterVrrHook {
+    enabled: bool,
+    previous: Option<String>,
+}
+
+impl LaunchHook for MutterVrrHook {
+    fn name(&self) -> &str {
+        "mutter-vrr"
+    }
+
+    fn on_start(&mut self) -> Result<()> {
+        let current = gsettings(&["get", MUTTER_SCHEMA, MUTTER_FEATURES])?;
+        if let Some(updated) = toggle_feature(&current, MUTTER_VRR_FEATURE, self.enabled) {
+            gsettings(&["set", MUTTER_SCHEMA, MUTTER_FEATURES, &updated])?;
+            self.previous = Some(current);
+        }
+        Ok(())
+    }
+
+    fn on_exit(&mut self) -> Result<()> {
+        if let Some(previous) = self.previous.take() {
+            gsettings(&["set", MUTTER_SCHEMA, MUTTER_FEATURES, &previous])?;
+        }
+        Ok(())
+    }
+}
+
+/// Add or remove `feature` in a GVariant string list such as `['a', 'b']`,
+/// returning the new list if it changed
+fn toggle_feature(list: &str, feature: &str, enabled: bool) -> Option<String> {
+    let inner = list
+        .trim()
+        .trim_start_matches("@as")
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let mut features: Vec<&str> = inner
+        .split(',')
+        .map(|f| f.trim().trim_matches(['\'', '"']))
+        .filter(|f| !f.is_empty())
+        .collect();
+    if features.contains(&feature) == enabled {
+        return None;
+    }
+    if enabled {
+        features.push(feature);
+    } else {
+        features.retain(|f| *f != feature);
+    }
+    let quoted: Vec<String> = features.iter().map(|f| format!("'{}'", f)).collect();
+    Some(format!("[{}]", quoted.join(", ")))
+}
+
+/// Hook switching compositor adaptive sync on or off while a game runs, or
+/// None if the session's compositor can't be driven
+pub fn adaptive_sync_hook(output: Option<String>, enabled: bool) -> Option<Box<dyn LaunchHook>> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() && Desktop::detect() == Desktop::Gnome {
+        return Some(Box::new(MutterVrrHook {
+            enabled,
+            previous: None,
+        }));
+    }
+    match Backend::detect()? {
+        backend @ (Backend::KscreenDoctor | Backend::WlrRandr) => {
+            Some(Box::new(AdaptiveSyncHook {
+                backend,
+                output,
+                enabled,
+                previous: None,
+            }))
+        }
+        Backend::Xrandr => None,
+    }
+}
+
+/// Build the hooks enabled in a `display` profile section
+pub fn hooks(section: &Value) -> Vec<Box<dyn LaunchHook>> {
+    let settings = match DisplaySettings::from_section(section) {
+        Ok(settings) => settings,
+        Err(e) => {
+            log::warn!("ignoring display section: {}", e);
+            return Vec::new();
+        }
+    };
+    if settings.is_empty() {
+        return Vec::new();
+    }
+    // Only VRR: leave the mode alone
+    if let (None, None, Some(vrr)) = (settings.resolution, settings.refresh, settings.vrr) {
+        return match adaptive_sync_hook(settings.output, vrr) {
+            Some(hook) => vec![hook],
+            None => {
+                log::warn!(
+                    "vrr needs KWin, Mutter or wlr-randr; on X11 use __GL_GSYNC_ALLOWED/__GL_VRR_ALLOWED"
+                );
+                Vec::new()
+            }
+        };
+    }
+    let Some(backend) = Backend::detect() else {
+        log::warn!("display mode switching needs kscreen-doctor (KDE), wlr-randr or xrandr (X11)");
+        return Vec::new();
+    };
+    vec![Box::new(DisplayHook {
+        backend,
+        settings,
+        previous: None,
+    })]
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("failed to run {}", program))?;
+    if !output.status.success() {
+        bail!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[derive(Deserialize)]
+struct KscreenConfig {
+    outputs: Vec<KscreenOutput>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KscreenOutput {
+    name: String,
+    #[serde(default)]
+    enabled: bool,
+    /// Plasma 6 (1 = primary)
+    #[serde(default)]
+    priority: Option<u32>,
+    /// Plasma 5
+    #[serde(default)]
+    primary: Option<bool>,
+    #[serde(default)]
+    current_mode_id: Option<String>,
+    #[serde(default)]
+    modes: Vec<KscreenMode>,
+    #[serde(default)]
+    vrr_policy: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KscreenMode {
+    id: String,
+    refresh_rate: f64,
+    size: KscreenSize,
+}
+
+#[derive(Deserialize)]
+struct KscreenSize {
+    width: u32,
+    height: u32,
+}
+
+fn parse_kscreen(json: &str) -> Result<Vec<OutputState>> {
+    let config: KscreenConfig =
+        serde_json::from_str(json).context("failed to parse kscreen-doctor output")?;
+    Ok(config
+        .outputs
+        .into_iter()
+        .map(|output| {
+            let modes: Vec<Mode> = output
+                .modes
+                .into_iter()
+                .map(|m| Mode {
+                    id: Some(m.id),
+                    width: m.size.width,
+                    height: m.size.height,
+                    refresh: m.refresh_rate,
+                })
+                .collect();
+            let current = output
+                .current_mode_id
+                .filter(|_| output.enabled)
+                .and_then(|id| modes.iter().find(|m| m.id.as_deref() == Some(&id)).cloned());
+            OutputState {
+                name: output.name,
+                primary: output.priority == Some(1) || output.primary == Some(true),
+                current,
+                modes,
+                vrr: output.vrr_policy.map(|policy| match policy {
+                    0 => AdaptiveSync::Never,
+                    1 => AdaptiveSync::Always,
+                    _ => AdaptiveSync::Automatic,
+                }),
+            }
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct WlrOutput {
+    name: String,
+    #[serde(default)]
+    enabled: bool,
+    #[serde(default)]
+    modes: Vec<WlrMode>,
+    #[serde(default)]
+    adaptive_sync: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct WlrMode {
+    width: u32,
+    height: u32,
+    refresh: f64,
+    #[serde(default)]
+    current: bool,
+}
+
+fn parse_wlr_randr(json: &str) -> Result<Vec<OutputState>> {
+    let outputs: Vec<WlrOutput> =
+        serde_json::from_str(json).context("failed to parse wlr-randr output")?;
+    Ok(outputs
+        .into_iter()
+        .map(|output| {
+            let current = output
+                .modes
+                .iter()
+                .find(|m| m.current && output.enabled)
+                .map(|m| Mode {
+                    id: None,
+                    width: m.width,
+                    height: m.height,
+                    refresh: m.refresh,
+                });
+            OutputState {
+                name: output.name,
+                // wlroots has no primary output
+                primary: false,
+                current,
+                modes: output
+                    .modes
+                    .iter()
+                    .map(|m| Mode {
+                        id: None,
+                        width: m.width,
+                        height: m.height,
+                        refresh: m.refresh,
+                    })
+                    .collect(),
+                vrr: output.adaptive_sync.map(|enabled| {
+                    if enabled {
+                        AdaptiveSync::Always
+                    } else {
+                        AdaptiveSync::Never
+                    }
+                }),
+            }
+        })
+        .collect())
+}
+
+/// Parse `xrandr --query`; `*` marks the current rate of an output
+fn parse_xrandr(text: &str) -> Vec<OutputState> {
+    let mut outputs: Vec<OutputState> = Vec::new();
+    for line in text.lines() {
+        if !line.starts_with(char::is_whitespace) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.get(1) == Some(&"connected") {
+                outputs.push(OutputState {
+                    name: fields[0].to_string(),
+                    primary: fields.get(2) == Some(&"primary"),
+                    current: None,
+                    modes: Vec::new(),
+                    vrr: None,
+                });
+            }
+            continue;
+        }
+        let Some(output) = outputs.last_mut() else {
+            continue;
+        };
+        let mut fields = line.split_whitespace();
+        let Some(Ok((width, height))) = fields.next().map(parse_resolution) else {
+            continue;
+        };
+        for field in fields {
+            let rate = field.trim_end_matches(['*', '+']);
+            let Ok(refresh) = rate.parse::<f64>() else {
+                continue;
+            };
+            let mode = Mode {
+                id: None,
+                width,
+                height,
+                refresh,
+            };
+            if field.contains('*') {
+                output.current = Some(mode.clone());
+            }
+            output.modes.push(mode);
+        }
+    }
+    outputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_display_section() {
+        let section: Value =
+            serde_yaml::from_str("resolution: 2560x1440\nrefresh: 240\nvrr: true\n").unwrap();
+        let settings = DisplaySettings::from_section(&section).unwrap();
+        assert_eq!(settings.resolution, Some((2560, 1440)));
+        assert_eq!(settings.refresh, Some(240.0));
+        assert_eq!(settings.vrr, Some(true));
+        assert!(parse_resolution("1440p").is_err());
+    }
+
+    #[test]
+    fn test_xrandr_mode_selection() {
+        let query = "Screen 0: minimum 8 x 8, current 3840 x 2160, maximum 32767 x 32767\n\
+                     DP-0 connected primary 3840x2160+0+0 (normal left inverted right) 597mm x 336mm\n\
+                     \x20  3840x2160     59.94 + 143.99*  119.98\n\
+                     \x20  2560x1440    239.96   143.97\n\
+                     HDMI-0 disconnected (normal left inverted right x axis y axis)\n";
+        let outputs = parse_xrandr(query);
+        assert_eq!(outputs.len(), 1);
+        let output = &outputs[0];
+        assert!(output.primary);
+        assert_eq!(output.current.as_ref().unwrap().refresh, 143.99);
+
+        let settings = DisplaySettings {
+            resolution: Some((2560, 1440)),
+            refresh: Some(240.0),
+            ..Default::default()
+        };
+        let mode = output.select_mode(&settings).unwrap();
+        assert_eq!(
+            (mode.width, mode.height, mode.refresh),
+            (2560, 1440, 239.96)
+        );
+    }
+
+    #[test]
+    fn test_toggle_mutter_feature() {
+        assert_eq!(
+            toggle_feature("@as []", MUTTER_VRR_FEATURE, true).as_deref(),
+            Some("['variable-refresh-rate']")
+        );
+        assert_eq!(
+            toggle_feature("['scale-monitor-framebuffer']", MUTTER_VRR_FEATURE, true).as_deref(),
+            Some("['scale-monitor-framebuffer', 'variable-refresh-rate']")
+        );
+        assert_eq!(
+            toggle_feature("['variable-refresh-rate']", MUTTER_VRR_FEATURE, true),
+            None
+        );
+        assert_eq!(
+            toggle_feature("['variable-refresh-rate']", MUTTER_VRR_FEATURE, false).as_deref(),
+            Some("[]")
+        );
+    }
+
+    #[test]
+    fn test_parse_kscreen() {
+        let json = r#"{"outputs": [{"name": "DP-1", "enabled": true, "priority": 1,
+            "currentModeId": "2", "vrrPolicy": 2, "modes": [
+            {"id": "1", "refreshRate": 239.97, "size": {"width": 2560, "height": 1440}},
+            {"id": "2", "refreshRate": 59.95, "size": {"width": 2560, "height": 1440}}]}]}"#;
+        let outputs = parse_kscreen(json).unwrap();
+        assert!(outputs[0].primary);
+        assert_eq!(outputs[0].vrr, Some(AdaptiveSync::Automatic));
+        assert_eq!(
+            outputs[0].current.as_ref().unwrap().id.as_deref(),
+            Some("2")
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! nvidia-settings attribute hooks
+//!
+//! Configured through the `driver` profile section:
+//!
+//! ```yaml
+//! driver:
+//!   powermizer: max_performance       # adaptive, max_performance or auto
+//!   image_sharpening: 5               # true/false, or the sharpening amount
+//!   force_composition_pipeline: true  # true, false or full
+//! ```
+//!
+//! The attributes are read with `nvidia-settings -q` before the game starts,
+//! assigned with `nvidia-settings -a` and set back to the read values when it
+//! exits. nvidia-settings talks to the X server through NV-CONTROL, so the
+//! section is ignored on Wayland.
+
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use serde_yaml::Value;
+
+use super::LaunchHook;
+use crate::detection::display_server::{DisplaySession, SessionType};
+use crate::paths::in_path;
+
+/// Build the hooks enabled in a `driver` profile section
+pub fn hooks(section: &Value) -> Vec<Box<dyn LaunchHook>> {
+    let session = DisplaySession::detect();
+    if session.session_type != SessionType::X11 {
+        log::warn!(
+            "the driver section needs an X11 session (nvidia-settings), not {}",
+            session.session_type
+        );
+        return Vec::new();
+    }
+    if !in_path("nvidia-settings") {
+        log::warn!("the driver section needs nvidia-settings");
+        return Vec::new();
+    }
+    overrides(section)
+}
+
+/// Hooks for the recognized keys of a `driver` section
+fn overrides(section: &Value) -> Vec<Box<dyn LaunchHook>> {
+    let mut hooks: Vec<Box<dyn LaunchHook>> = Vec::new();
+
+    if let Some(value) = section.get("powermizer") {
+        match powermizer_mode(value) {
+            Some(mode) => hooks.push(Box::new(AttributeOverride::new(
+                "powermizer",
+                "GPUPowerMizerMode",
+                mode.to_string(),
+            ))),
+            None => log::warn!("unknown powermizer mode {:?}", value),
+        }
+    }
+
+    if let Some(value) = section.get("image_sharpening") {
+        match (as_bool(value), value.as_u64()) {
+            (Some(enabled), _) => hooks.push(Box::new(AttributeOverride::new(
+                "image-sharpening",
+                "ImageSharpening",
+                u8::from(enabled).to_string(),
+            ))),
+            (None, Some(amount)) => {
+                hooks.push(Box::new(AttributeOverride::new(
+                    "image-sharpening",
+                    "ImageSharpening",
+                    "1".into(),
+                )));
+                hooks.push(Box::new(AttributeOverride::new(
+                    "image-sharpening-amount",
+                    "ImageSharpeningValue",
+                    amount.to_string(),
+                )));
+            }
+            (None, None) => log::warn!("unknown image_sharpening value {:?}", value),
+        }
+    }
+
+    if let Some(value) = section.get("force_composition_pipeline") {
+        let pipeline = match value.as_str() {
+            Some("full") => Some(Pipeline::Full),
+            _ => as_bool(value).map(|on| if on { Pipeline::On } else { Pipeline::Off }),
+        };
+        match pipeline {
+            Some(pipeline) => hooks.push(Box::new(CompositionPipeline {
+                pipeline,
+                previous: None,
+            })),
+            None => log::warn!("unknown force_composition_pipeline value {:?}", value),
+        }
+    }
+
+    hooks
+}
+
+/// Booleans, including the "true"/"false" strings written by `profile set`
+fn as_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(b) => Some(*b),
+        Value::String(s) => match s.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "enabled" => Some(true),
+            "false" | "no" | "off" | "disabled" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `GPUPowerMizerMode` value: 0 adaptive, 1 prefer maximum performance, 2 auto
+fn powermizer_mode(value: &Value) -> Option<u8> {
+    if let Some(mode) = value.as_u64() {
+        return (mode <= 2).then_some(mode as u8);
+    }
+    match value.as_str()? {
+        "adaptive" => Some(0),
+        "max_performance" | "prefer_maximum_performance" => Some(1),
+        "auto" => Some(2),
+        _ => None,
+    }
+}
+
+/// Read an attribute's current value (the first target's, if there are several)
+pub fn query(attribute: &str) -> Result<String> {
+    let output = nvidia_settings(&["-t", "-q", attribute])?;
+    match output.lines().next() {
+        Some(line) if !line.trim().is_empty() => Ok(line.trim().to_string()),
+        _ => bail!("nvidia-settings returned no value for {}", attribute),
+    }
+}
+
+/// Assign an attribute on every target it applies to
+pub fn assign(attribute: &str, value: &str) -> Result<()> {
+    nvidia_settings(&["-a", &format!("{}={}", attribute, value)])?;
+    Ok(())
+}
+
+fn nvidia_settings(args: &[&str]) -> Result<String> {
+    let output = Command::new("nvidia-settings")
+        .args(args)
+        .output()
+        .context("failed to run nvidia-settings")?;
+    // nvidia-settings reports unknown attributes on stderr with status 0
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() || stderr.contains("ERROR") {
+        bail!(
+            "nvidia-settings {} failed: {}",
+            args.join(" "),
+            stderr.trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Temporarily assign an nvidia-settings attribute
+struct AttributeOverride {
+    name: &'static str,
+    attribute: &'static str,
+    value: String,
+    previous: Option<String>,
+}
+
+impl AttributeOverride {
+    fn new(name: &'static str, attribute: &'static str, value: String) -> Self {
+        Self {
+            name,
+            attribute,
+            value,
+            previous: None,
+        }
+    }
+}
+
+impl LaunchHook for AttributeOverride {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn on_start(&mut self) -> Result<()> {
+        let previous = query(self.attribute)?;
+        assign(self.attribute, &self.value)?;
+        self.previous = Some(previous);
+        Ok(())
+    }
+
+    fn on_exit(&mut self) -> Result<()> {
+        if let Some(previous) = self.previous.take() {
+            assign(self.attribute, &previous)?;
+        }
+        Ok(())
+    }
+}
+
+/// Requested composition pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pipeline {
+    Off,
+    On,
+    Full,
+}
+
+/// Force (Full) Composition Pipeline through the current MetaMode
+struct CompositionPipeline {
+    pipeline: Pipeline,
+    previous: Option<String>,
+}
+
+impl LaunchHook for CompositionPipeline {
+    fn name(&self) -> &str {
+        "composition-pipeline"
+    }
+
+    fn on_start(&mut self) -> Result<()> {
+        let current = strip_metamode_prefix(&query("CurrentMetaMode")?).to_string();
+        assign("CurrentMetaMode", &with_pipeline(&current, self.pipeline))?;
+        self.previous = Some(current);
+        Ok(())
+    }
+
+    fn on_exit(&mut self) -> Result<()> {
+        if let Some(previous) = self.previous.take() {
+            assign("CurrentMetaMode", &previous)?;
+        }
+        Ok(())
+    }
+}
+
+/// `CurrentMetaMode` is reported as `id=50, switchable=no, source=nv-control :: <metamode>`
+fn strip_metamode_prefix(value: &str) -> &str {
+    value
+        .split_once("::")
+        .map_or(value, |(_, metamode)| metamode)
+        .trim()
+}
+
+/// Set the composition pipeline options of every display in a MetaMode
+///
+/// Each display's mode carries its options in braces, e.g.
+/// `DPY-1: nvidia-auto-select @1920x1080 +0+0 {ViewPortIn=1920x1080}`.
+fn with_pipeline(metamode: &str, pipeline: Pipeline) -> String {
+    let (force, full) = match pipeline {
+        Pipeline::Off => ("Off", "Off"),
+        Pipeline::On => ("On", "Off"),
+        Pipeline::Full => ("On", "On"),
+    };
+    let pipeline_options = format!(
+        "ForceCompositionPipeline={}, ForceFullCompositionPipeline={}",
+        force, full
+    );
+    let set_options = |options: &str| {
+        let mut kept: Vec<&str> = options
+            .split(',')
+            .map(str::trim)
+            .filter(|option| !option.is_empty() && !option.contains("CompositionPipeline"))
+            .collect();
+        kept.push(&pipeline_options);
+        format!("{{{}}}", kept.join(", "))
+    };
+
+    if !metamode.contains('{') {
+        return format!("{} {}", metamode, set_options(""));
+    }
+    let mut out = String::with_capacity(metamode.len() + 64);
+    let mut rest = metamode;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        out.push_str(&set_options(&rest[start + 1..start + end]));
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_composition_pipeline_metamode() {
+        let reported = "id=50, switchable=no, source=nv-control :: \
+            DPY-1: nvidia-auto-select @2560x1440 +0+0 {ViewPortIn=2560x1440, ForceCompositionPipeline=Off}, \
+            DPY-3: 1920x1080_144 @1920x1080 +2560+0 {ViewPortIn=1920x1080}";
+        let metamode = strip_metamode_prefix(reported);
+        assert_eq!(
+            with_pipeline(metamode, Pipeline::Full),
+            "DPY-1: nvidia-auto-select @2560x1440 +0+0 {ViewPortIn=2560x1440, \
+             ForceCompositionPipeline=On, ForceFullCompositionPipeline=On}, \
+             DPY-3: 1920x1080_144 @1920x1080 +2560+0 {ViewPortIn=1920x1080, \
+             ForceCompositionPipeline=On, ForceFullCompositionPipeline=On}"
+        );
+        assert_eq!(
+            with_pipeline("DPY-1: nvidia-auto-select +0+0", Pipeline::On),
+            "DPY-1: nvidia-auto-select +0+0 \
+             {ForceCompositionPipeline=On, ForceFullCompositionPipeline=Off}"
+        );
+
+        let section: Value = serde_yaml::from_str(
+            "powermizer: max_performance\nimage_sharpening: 5\nforce_composition_pipeline: \"false\"\n",
+        )
+        .unwrap();
+        let names: Vec<_> = overrides(&section)
+            .iter()
+            .map(|h| h.name().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "powermizer",
+                "image-sharpening",
+                "image-sharpening-amount",
+                "composition-pipeline"
+            ]
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Launch hooks
+//!
+//! Hooks run when a game starts and undo their changes when it exits.
+//! Built-in providers are enabled from profile sections:
+//! - `audio` - per-game stream volume (the sink is set in the launch env)
+//! - `desktop` - do-not-disturb, compositor suspension, night light
+//! - `display` - resolution, refresh rate and VRR for the session
+//! - `driver` - nvidia-settings attributes (PowerMizer, sharpening, composition pipeline) on X11
+//! - `replay` - gpu-screen-recorder instant replay (also `run --replay`)
+
+pub mod audio;
+pub mod desktop;
+pub mod display;
+pub mod driver;
+pub mod replay;
+
+use anyhow::Result;
+use serde_yaml::Value;
+
+/// A provider that changes system state for the duration of a game session
+pub trait LaunchHook {
+    /// Human-readable name for logs and dry runs
+    fn name(&self) -> &str;
+
+    /// Apply the change before the game launches
+    fn on_start(&mut self) -> Result<()>;
+
+    /// Restore the previous state after the game exits
+    fn on_exit(&mut self) -> Result<()>;
+}
+
+/// Runs hooks in order on start and in reverse order on exit
+///
+/// Exit hooks also run on drop, so state is restored even if the launch fails.
+#[derive(Default)]
+pub struct HookRunner {
+    hooks: Vec<Box<dyn LaunchHook>>,
+    /// Indices of the hooks whose start succeeded, in start order
+    started: Vec<usize>,
+}
+
+impl HookRunner {
+    /// Build the hooks enabled by a resolved profile
+    pub fn from_profile(settings: &Value) -> Self {
+        let mut runner = Self::default();
+        if let Some(section) = settings.get("audio") {
+            runner.hooks.extend(audio::hooks(section));
+        }
+        if let Some(section) = settings.get("desktop") {
+            runner.hooks.extend(desktop::hooks(section));
+        }
+        if let Some(section) = settings.get("display") {
+            runner.hooks.extend(display::hooks(section));
+        }
+        if let Some(section) = settings.get("driver") {
+            runner.hooks.extend(driver::hooks(section));
+        }
+        runner
+    }
+
+    /// Add a hook that isn't configured in the profile
+    pub fn push(&mut self, hook: Box<dyn LaunchHook>) {
+        self.hooks.push(hook);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Names of the configured hooks
+    pub fn names(&self) -> Vec<&str> {
+        self.hooks.iter().map(|h| h.name()).collect()
+    }
+
+    /// Run all start hooks; failures are reported but don't block the launch
+    pub fn start(&mut self) {
+        for (index, hook) in self.hooks.iter_mut().enumerate() {
+            match hook.on_start() {
+                Ok(()) => {
+                    println!("  Hook: {}", hook.name());
+                    self.started.push(index);
+                }
+                Err(e) => eprintln!("  Warning: hook '{}' failed: {}", hook.name(), e),
+            }
+        }
+    }
+
+    /// Run exit hooks for every hook that started successfully
+    pub fn finish(&mut self) {
+        while let Some(index) = self.started.pop() {
+            let hook = &mut self.hooks[index];
+            if let Err(e) = hook.on_exit() {
+                eprintln!("  Warning: failed to restore '{}': {}", hook.name(), e);
+            }
+        }
+    }
+}
+
+impl Drop for HookRunner {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    struct Recorder {
+        name: String,
+        log: Arc<Mutex<Vec<String>>>,
+        fail: bool,
+    }
+
+    impl LaunchHook for Recorder {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn on_start(&mut self) -> Result<()> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("start {}", self.name));
+            if self.fail {
+                anyhow::bail!("{} is unavailable", self.name);
+            }
+            Ok(())
+        }
+
+        fn on_exit(&mut self) -> Result<()> {
+            self.log.lock().unwrap().push(format!("exit {}", self.name));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_exit_hooks_run_in_reverse_on_drop() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        {
+            let mut runner = HookRunner::default();
+            for name in ["a", "b"] {
+                runner.hooks.push(Box::new(Recorder {
+                    name: name.to_string(),
+                    log: Arc::clone(&log),
+                    fail: false,
+                }));
+            }
+            runner.start();
+        }
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["start a", "start b", "exit b", "exit a"]
+        );
+    }
+
+    #[test]
+    fn test_failed_start_hook_is_not_restored() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut runner = HookRunner::default();
+        for (name, fail) in [("a", false), ("b", true), ("c", false)] {
+            runner.push(Box::new(Recorder {
+                name: name.to_string(),
+                log: Arc::clone(&log),
+                fail,
+            }));
+        }
+        runner.start();
+        runner.finish();
+        // A second finish (drop) must not restore anything again
+        drop(runner);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["start a", "start b", "start c", "exit c", "exit a"]
+        );
+    }
+
+    #[test]
+    fn test_from_profile_without_desktop_section() {
+        let settings: Value = serde_yaml::from_str("env:\n  FOO: bar\n").unwrap();
+        assert!(HookRunner::from_profile(&settings).is_empty());
+    }
+}
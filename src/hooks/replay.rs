@@ -0,0 +1,182 @@
+//! gpu-screen-recorder instant replay
+//!
+//! `run --replay`, or `enabled: true` in the `replay` profile section, starts
+//! gpu-screen-recorder in replay mode when the game starts and stops it when
+//! the game exits. Like ShadowPlay it keeps the last seconds of gameplay,
+//! encoded with NVENC; `pkill -SIGUSR1 -f gpu-screen-recorder` (bound to a
+//! hotkey) saves them.
+//!
+//! ```yaml
+//! replay:
+//!   enabled: true
+//!   seconds: 60               # buffer length
+//!   output: ~/Videos/Replays  # default: the game's media directory
+//!   fps: 60
+//!   window: screen            # screen, portal (Wayland) or focused (X11)
+//!   audio: default_output     # "" records no audio
+//!   quality: very_high
+//!   codec: auto               # h264, hevc, av1, ...
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde_yaml::Value;
+
+use super::LaunchHook;
+use crate::paths::expand_home;
+use crate::paths::in_path;
+
+const RECORDER: &str = "gpu-screen-recorder";
+
+/// How long the recorder gets to shut down before it is killed
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whether the profile section turns replay on by itself
+pub fn enabled(section: Option<&Value>) -> bool {
+    // `profile set` writes booleans as strings
+    section
+        .and_then(|s| s.get("enabled"))
+        .is_some_and(|value| match value {
+            Value::Bool(b) => *b,
+            Value::String(s) => matches!(s.to_ascii_lowercase().as_str(), "true" | "yes" | "on"),
+            _ => false,
+        })
+}
+
+/// Build the replay hook, saving into `default_output` unless the section
+/// names a directory
+pub fn hook(section: Option<&Value>, default_output: PathBuf) -> Option<Box<dyn LaunchHook>> {
+    if !in_path(RECORDER) {
+        log::warn!("replay needs {} in PATH", RECORDER);
+        return None;
+    }
+    Some(Box::new(ReplayRecorder::new(section, default_output)))
+}
+
+/// gpu-screen-recorder running in replay mode for the game session
+struct ReplayRecorder {
+    output: PathBuf,
+    args: Vec<String>,
+    child: Option<Child>,
+}
+
+impl ReplayRecorder {
+    fn new(section: Option<&Value>, default_output: PathBuf) -> Self {
+        let get = |key: &str| {
+            section
+                .and_then(|s| s.get(key))
+                .and_then(|value| match value {
+                    Value::String(s) => Some(s.clone()),
+                    Value::Number(n) => Some(n.to_string()),
+                    _ => None,
+                })
+        };
+        let output = output_dir(section, &default_output);
+
+        let mut args = vec![
+            "-w".to_string(),
+            get("window").unwrap_or_else(|| "screen".into()),
+            "-c".into(),
+            "mp4".into(),
+            "-f".into(),
+            get("fps").unwrap_or_else(|| "60".into()),
+            "-r".into(),
+            get("seconds").unwrap_or_else(|| "60".into()),
+            "-q".into(),
+            get("quality").unwrap_or_else(|| "very_high".into()),
+            "-k".into(),
+            get("codec").unwrap_or_else(|| "auto".into()),
+        ];
+        let audio = get("audio").unwrap_or_else(|| "default_output".into());
+        if !audio.is_empty() {
+            args.extend(["-a".into(), audio]);
+        }
+        args.extend(["-o".into(), output.display().to_string()]);
+        Self {
+            output,
+            args,
+            child: None,
+        }
+    }
+}
+
+impl LaunchHook for ReplayRecorder {
+    fn name(&self) -> &str {
+        "replay"
+    }
+
+    fn on_start(&mut self) -> Result<()> {
+        fs::create_dir_all(&self.output)
+            .with_context(|| format!("failed to create directory {:?}", self.output))?;
+        let child = Command::new(RECORDER)
+            .args(&self.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .spawn()
+            .with_context(|| format!("failed to start {}", RECORDER))?;
+        log::info!("{} replay running as PID {}", RECORDER, child.id());
+        self.child = Some(child);
+        Ok(())
+    }
+
+    fn on_exit(&mut self) -> Result<()> {
+        let Some(mut child) = self.child.take() else {
+            return Ok(());
+        };
+        if child.try_wait()?.is_some() {
+            log::warn!("{} exited while the game was running", RECORDER);
+            return Ok(());
+        }
+        // SIGINT lets the recorder release the encoder and capture cleanly
+        let _ = Command::new("kill")
+            .args(["-INT", &child.id().to_string()])
+            .status();
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while child.try_wait()?.is_none() {
+            if Instant::now() >= deadline {
+                child.kill().ok();
+                child.wait().ok();
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        Ok(())
+    }
+}
+
+/// Directory replays are saved to
+pub fn output_dir(section: Option<&Value>, default_output: &Path) -> PathBuf {
+    section
+        .and_then(|s| s.get("output"))
+        .and_then(Value::as_str)
+        .map(expand_home)
+        .unwrap_or_else(|| default_output.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recorder_arguments() {
+        let section: Value =
+            serde_yaml::from_str("enabled: true\nseconds: 120\nwindow: portal\naudio: \"\"\n")
+                .unwrap();
+        assert!(enabled(Some(&section)));
+        let recorder = ReplayRecorder::new(Some(&section), PathBuf::from("/videos/Hades"));
+        assert_eq!(
+            recorder.args.join(" "),
+            "-w portal -c mp4 -f 60 -r 120 -q very_high -k auto -o /videos/Hades"
+        );
+
+        let defaults = ReplayRecorder::new(None, PathBuf::from("/videos/Hades"));
+        assert!(defaults.args.join(" ").contains("-r 60 "));
+        assert!(defaults.args.join(" ").contains("-a default_output"));
+        assert!(!enabled(None));
+    }
+}
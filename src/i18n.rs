@@ -0,0 +1,300 @@
+//! Translated user-facing messages
+//!
+//! Human-readable output of `run`, `prepare`, `games` and `steam` is looked up
+//! in a message catalog written in a small subset of the Fluent format:
+//!
+//! ```text
+//! # Comment
+//! run-launching = Launching { $name }...
+//! steam-help =
+//!     First line
+//!     Second line
+//! ```
+//!
+//! The language comes from `NVPROTON_LANG`, then `LC_ALL`, `LC_MESSAGES` and
+//! `LANG`. English and German are built in; `<config>/locales/<lang>.ftl`
+//! adds a language or overrides single messages. Messages missing from a
+//! catalog fall back to English.
+//!
+//! JSON and YAML output, log lines and error messages stay in English so
+//! scripts can rely on them.
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+
+use once_cell::sync::OnceCell;
+
+const FALLBACK_LANGUAGE: &str = "en";
+
+const BUILTIN: &[(&str, &str)] = &[
+    ("en", include_str!("locales/en.ftl")),
+    ("de", include_str!("locales/de.ftl")),
+];
+
+static LOCALIZER: OnceCell<Localizer> = OnceCell::new();
+
+/// Look up a message, substituting `{ $name }` placeholders
+///
+/// ```ignore
+/// println!("{}", tr!("run-launching", name = game.name));
+/// ```
+macro_rules! tr {
+    ($id:literal) => {
+        $crate::i18n::message($id, &[])
+    };
+    ($id:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::message(
+            $id,
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+pub(crate) use tr;
+
+/// Messages of one language
+#[derive(Debug, Default)]
+struct Catalog {
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    fn parse(text: &str) -> Self {
+        let mut messages = HashMap::new();
+        let mut current: Option<(String, Vec<&str>)> = None;
+        for line in text.lines() {
+            // Indented (and blank) lines continue the previous message
+            if let Some((_, lines)) = current.as_mut()
+                && (line.starts_with([' ', '\t']) || line.trim().is_empty())
+            {
+                lines.push(line);
+                continue;
+            }
+            if let Some((id, lines)) = current.take() {
+                messages.insert(id, join_lines(lines));
+            }
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once('=') {
+                Some((id, value)) => current = Some((id.trim().to_string(), vec![value.trim()])),
+                None => log::warn!("ignoring malformed catalog line: {}", line),
+            }
+        }
+        if let Some((id, lines)) = current {
+            messages.insert(id, join_lines(lines));
+        }
+        Self { messages }
+    }
+
+    fn get(&self, id: &str) -> Option<&str> {
+        self.messages.get(id).map(String::as_str)
+    }
+}
+
+/// Join the inline value and continuation lines of a message, removing the
+/// continuation lines' common indentation
+fn join_lines(lines: Vec<&str>) -> String {
+    let indent = lines[1..]
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let mut parts: Vec<&str> = Vec::with_capacity(lines.len());
+    if !lines[0].is_empty() {
+        parts.push(lines[0]);
+    }
+    for line in &lines[1..] {
+        let line = line.get(indent..).unwrap_or_default().trim_end();
+        if !(parts.is_empty() && line.is_empty()) {
+            parts.push(line);
+        }
+    }
+    while parts.last() == Some(&"") {
+        parts.pop();
+    }
+    parts.join("\n")
+}
+
+/// Catalogs for the selected language, most specific first, ending in English
+#[derive(Debug)]
+struct Localizer {
+    catalogs: Vec<Catalog>,
+}
+
+impl Localizer {
+    fn new(languages: &[String], locales_dir: Option<&Path>) -> Self {
+        let mut catalogs = Vec::new();
+        let fallback = [FALLBACK_LANGUAGE.to_string()];
+        for language in languages.iter().chain(&fallback) {
+            if let Some(dir) = locales_dir {
+                let path = dir.join(format!("{}.ftl", language));
+                match fs::read_to_string(&path) {
+                    Ok(text) => catalogs.push(Catalog::parse(&text)),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => log::warn!("failed to read {:?}: {}", path, e),
+                }
+            }
+            if let Some((_, text)) = BUILTIN.iter().find(|(lang, _)| lang == language) {
+                catalogs.push(Catalog::parse(text));
+            }
+        }
+        Self { catalogs }
+    }
+
+    fn format(&self, id: &str, args: &[(&str, &dyn Display)]) -> String {
+        let Some(pattern) = self.catalogs.iter().find_map(|c| c.get(id)) else {
+            log::debug!("missing message '{}'", id);
+            return id.to_string();
+        };
+        substitute(pattern, args)
+    }
+}
+
+/// Replace `{ $name }` with the matching argument, leaving unknown ones as-is
+fn substitute(pattern: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut out = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start..];
+        let Some(end) = after.find('}') else {
+            break;
+        };
+        let name = after[1..end].trim();
+        match name
+            .strip_prefix('$')
+            .and_then(|name| args.iter().find(|(arg, _)| *arg == name))
+        {
+            Some((_, value)) => out.push_str(&value.to_string()),
+            None => out.push_str(&after[..=end]),
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Languages requested by the environment, e.g. `de_AT.UTF-8` -> `de_AT`, `de`
+fn requested_languages() -> Vec<String> {
+    let value = ["NVPROTON_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty());
+    let Some(value) = value else {
+        return Vec::new();
+    };
+    let locale = value
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .replace('-', "_");
+    if locale.is_empty() || locale == "C" || locale == "POSIX" {
+        return Vec::new();
+    }
+    let mut languages = vec![locale.clone()];
+    if let Some((language, _)) = locale.split_once('_') {
+        languages.push(language.to_string());
+    }
+    languages
+}
+
+/// Select the language, with user catalogs from `<config_dir>/locales`
+pub fn init(config_dir: &Path) {
+    let localizer = Localizer::new(&requested_languages(), Some(&config_dir.join("locales")));
+    let _ = LOCALIZER.set(localizer);
+}
+
+/// Translated message `id` (use the [`tr!`] macro)
+pub fn message(id: &str, args: &[(&str, &dyn Display)]) -> String {
+    LOCALIZER
+        .get_or_init(|| Localizer::new(&requested_languages(), None))
+        .format(id, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(pattern: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = pattern
+            .split("{ $")
+            .skip(1)
+            .filter_map(|part| part.split_once(" }").map(|(name, _)| name))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    #[test]
+    fn test_lookup_with_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("de.ftl"),
+            "# override\nrun-running = Starte { $name } ({ $id })\n",
+        )
+        .unwrap();
+        let localizer = Localizer::new(&["de_DE".into(), "de".into()], Some(dir.path()));
+
+        let name = "Hades";
+        assert_eq!(
+            localizer.format("run-running", &[("name", &name), ("id", &1145360)]),
+            "Starte Hades (1145360)"
+        );
+        // Built-in German, then English, then the ID itself
+        assert_eq!(
+            localizer.format("run-update-finished", &[]),
+            "Update abgeschlossen"
+        );
+        assert_eq!(localizer.format("no-such-message", &[]), "no-such-message");
+
+        let catalog = Catalog::parse("multi =\n    one\n\n      two { $n }\n\nnext = x\n");
+        assert_eq!(catalog.get("multi"), Some("one\n\n  two { $n }"));
+        assert_eq!(catalog.get("next"), Some("x"));
+        assert_eq!(
+            substitute("{ $n } and { $other }", &[("n", &2)]),
+            "2 and { $other }"
+        );
+    }
+
+    #[test]
+    fn test_catalogs_cover_used_messages() {
+        let english = Catalog::parse(BUILTIN[0].1);
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        for file in ["runner.rs", "games.rs", "steam/mod.rs", "undo.rs"] {
+            let code = fs::read_to_string(src.join(file)).unwrap();
+            for part in code.split("tr!(").skip(1) {
+                let Some(rest) = part.trim_start().strip_prefix('"') else {
+                    continue;
+                };
+                let id = rest.split('"').next().unwrap();
+                assert!(
+                    english.get(id).is_some(),
+                    "{} uses unknown message '{}'",
+                    file,
+                    id
+                );
+            }
+        }
+
+        for (language, text) in &BUILTIN[1..] {
+            for (id, pattern) in &Catalog::parse(text).messages {
+                let Some(original) = english.get(id) else {
+                    panic!("{}.ftl has unknown message '{}'", language, id);
+                };
+                assert_eq!(
+                    placeholders(pattern),
+                    placeholders(original),
+                    "placeholders of '{}' differ in {}.ftl",
+                    id,
+                    language
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Controller and Steam Input environment
+//!
+//! Configured through the `input` profile section (or `run`/`wrap` flags):
+//!
+//! ```yaml
+//! input:
+//!   controller_config: ~/.config/sdl/gamecontrollerdb.txt   # or a mapping string
+//!   steam_input: false
+//!   prefer_sdl: true
+//! ```
+//!
+//! Disabling Steam Input hides Steam's virtual gamepad and stops SDL from
+//! ignoring the physical devices behind it, which fixes doubled or missing
+//! input for Heroic/Lutris titles added to Steam as shortcuts.
+
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use crate::paths::expand_home;
+
+/// USB vendor IDs of common controller makers
+const CONTROLLER_VENDORS: &[(u32, &str)] = &[
+    (0x045e, "Microsoft"),
+    (0x054c, "Sony"),
+    (0x057e, "Nintendo"),
+    (0x0e6f, "PDP"),
+    (0x0f0d, "Hori"),
+    (0x28de, "Valve"),
+    (0x2dc8, "8BitDo"),
+    (0x3537, "GameSir"),
+];
+
+/// Apply an `input` profile section to the launch environment
+pub fn apply_profile(section: &Value, env_vars: &mut HashMap<String, String>) {
+    if let Some(config) = section.get("controller_config").and_then(Value::as_str) {
+        set_controller_config(config, env_vars);
+    }
+    if let Some(enabled) = section.get("steam_input").and_then(Value::as_bool) {
+        set_steam_input(enabled, env_vars);
+    }
+    if let Some(prefer_sdl) = section.get("prefer_sdl").and_then(Value::as_bool) {
+        env_vars.insert(
+            "PROTON_PREFER_SDL".into(),
+            if prefer_sdl { "1" } else { "0" }.into(),
+        );
+    }
+}
+
+/// Use an SDL controller mapping file or mapping string
+pub fn set_controller_config(config: &str, env_vars: &mut HashMap<String, String>) {
+    let path = expand_home(config);
+    if path.is_file() {
+        env_vars.insert(
+            "SDL_GAMECONTROLLERCONFIG_FILE".into(),
+            path.to_string_lossy().to_string(),
+        );
+    } else {
+        env_vars.insert("SDL_GAMECONTROLLERCONFIG".into(), config.to_string());
+    }
+}
+
+/// Allow or hide Steam's virtual gamepad
+///
+/// Steam exports `SDL_GAMECONTROLLER_IGNORE_DEVICES` so games only see its
+/// virtual pad; without Steam Input the physical devices must be visible
+/// again, so the list is cleared.
+pub fn set_steam_input(enabled: bool, env_vars: &mut HashMap<String, String>) {
+    if enabled {
+        env_vars.insert(
+            "SDL_GAMECONTROLLER_ALLOW_STEAM_VIRTUAL_GAMEPAD".into(),
+            "1".into(),
+        );
+    } else {
+        env_vars.insert(
+            "SDL_GAMECONTROLLER_ALLOW_STEAM_VIRTUAL_GAMEPAD".into(),
+            "0".into(),
+        );
+        env_vars.insert("SDL_GAMECONTROLLER_IGNORE_DEVICES".into(), String::new());
+        env_vars.insert("SteamVirtualGamepadInfo".into(), String::new());
+    }
+}
+
+/// A controller exposed through `/dev/hidraw*`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HidrawDevice {
+    pub path: PathBuf,
+    pub name: String,
+    pub vendor: String,
+    /// The current user can open the device read/write (needed by Proton's hidraw backend)
+    pub accessible: bool,
+}
+
+/// Controllers with hidraw nodes and whether the user can open them
+pub fn hidraw_controllers() -> Vec<HidrawDevice> {
+    let Ok(entries) = fs::read_dir("/sys/class/hidraw") else {
+        return Vec::new();
+    };
+    let mut devices: Vec<HidrawDevice> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let uevent = fs::read_to_string(entry.path().join("device/uevent")).ok()?;
+            let (vendor_id, name) = parse_uevent(&uevent)?;
+            let vendor = CONTROLLER_VENDORS
+                .iter()
+                .find(|(id, _)| *id == vendor_id)
+                .map(|(_, vendor)| vendor.to_string())?;
+            let path = Path::new("/dev").join(entry.file_name());
+            Some(HidrawDevice {
+                accessible: OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .open(&path)
+                    .is_ok(),
+                path,
+                name,
+                vendor,
+            })
+        })
+        .collect();
+    devices.sort_by(|a, b| a.path.cmp(&b.path));
+    devices
+}
+
+/// Vendor ID and name from a HID device's uevent (`HID_ID=0003:0000054C:00000CE6`)
+fn parse_uevent(uevent: &str) -> Option<(u32, String)> {
+    let mut vendor = None;
+    let mut name = String::new();
+    for line in uevent.lines() {
+        if let Some(id) = line.strip_prefix("HID_ID=") {
+            vendor = id
+                .split(':')
+                .nth(1)
+                .and_then(|v| u32::from_str_radix(v, 16).ok());
+        } else if let Some(value) = line.strip_prefix("HID_NAME=") {
+            name = value.to_string();
+        }
+    }
+    Some((vendor?, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disable_steam_input() {
+        let section: Value =
+            serde_yaml::from_str("steam_input: false\ncontroller_config: \"03000000,Pad,a:b0\"\n")
+                .unwrap();
+        let mut env_vars = HashMap::new();
+        apply_profile(&section, &mut env_vars);
+        assert_eq!(
+            env_vars["SDL_GAMECONTROLLER_ALLOW_STEAM_VIRTUAL_GAMEPAD"],
+            "0"
+        );
+        assert_eq!(env_vars["SDL_GAMECONTROLLER_IGNORE_DEVICES"], "");
+        assert_eq!(env_vars["SDL_GAMECONTROLLERCONFIG"], "03000000,Pad,a:b0");
+    }
+
+    #[test]
+    fn test_parse_uevent() {
+        let uevent = "DRIVER=playstation\nHID_ID=0003:0000054C:00000CE6\n\
+                      HID_NAME=Sony Interactive Entertainment DualSense Wireless Controller\n";
+        let (vendor, name) = parse_uevent(uevent).unwrap();
+        assert_eq!(vendor, 0x054c);
+        assert!(name.contains("DualSense"));
+    }
+}
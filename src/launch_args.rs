@@ -0,0 +1,165 @@
+//! Game arguments and working directory
+//!
+//! Profiles and game records can add arguments to the game's command line
+//! and pick the directory it starts in, for games that need `-skipintro
+//! -dx12` or have to be started from their `bin` directory:
+//!
+//! ```yaml
+//! launch:
+//!   args: -skipintro -dx12    # or a list, one argument per item
+//!   cwd: ${game.dir}/bin
+//! ```
+//!
+//! Per game they are set with `games set-args <id> --cwd <dir> -- <args>`.
+//! Both take the placeholders of profile values (see `template`).
+//!
+//! Arguments go on the command line in this order, so later layers win for
+//! games that honor the last occurrence of a flag:
+//! 1. the launch target's arguments (`run --target`)
+//! 2. the profile's `launch.args`
+//! 3. the game record's arguments
+//! 4. arguments given to `run` after `--`
+//!
+//! Launcher-composed commands (`nvproton run --wrapper -- %command%`) get the profile
+//! and record arguments right after the game executable instead, ahead of
+//! the launch options Steam appends.
+//!
+//! The game record's working directory replaces the profile's. It applies
+//! to commands nvproton starts itself; Steam, Heroic and Lutris start the
+//! game from the directory they pick.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use serde_yaml::Value;
+
+use crate::template::TemplateContext;
+
+/// Arguments and working directory for one launch
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchArgs {
+    pub args: Vec<String>,
+    pub cwd: Option<PathBuf>,
+}
+
+impl LaunchArgs {
+    /// Merge a resolved profile's `launch` section with a game record's
+    /// arguments and working directory
+    ///
+    /// Profile settings are already expanded; the record's values are
+    /// expanded here.
+    pub fn resolve(
+        profile: Option<&Value>,
+        game_args: &[String],
+        game_cwd: Option<&str>,
+        template: &TemplateContext,
+    ) -> Result<Self> {
+        let section = profile.and_then(|settings| settings.get("launch"));
+        let mut args = match section.and_then(|launch| launch.get("args")) {
+            None => Vec::new(),
+            Some(Value::String(line)) => line.split_whitespace().map(String::from).collect(),
+            Some(Value::Sequence(items)) => items
+                .iter()
+                .map(|item| match item {
+                    Value::String(s) => Ok(s.clone()),
+                    Value::Number(n) => Ok(n.to_string()),
+                    _ => bail!("launch.args entries must be strings"),
+                })
+                .collect::<Result<_>>()?,
+            Some(_) => bail!("launch.args must be a string or a list"),
+        };
+        for arg in game_args {
+            args.push(template.expand(arg)?);
+        }
+
+        let cwd = match game_cwd {
+            Some(dir) => Some(template.expand(dir)?),
+            None => match section.and_then(|launch| launch.get("cwd")) {
+                None => None,
+                Some(Value::String(dir)) => Some(dir.clone()),
+                Some(_) => bail!("launch.cwd must be a path"),
+            },
+        };
+        Ok(Self {
+            args,
+            cwd: cwd.map(PathBuf::from),
+        })
+    }
+
+    /// The game's arguments followed by those given on the command line
+    pub fn merge(&self, cli_args: &[String]) -> Vec<String> {
+        self.args.iter().chain(cli_args).cloned().collect()
+    }
+
+    /// Add the arguments to a launcher-composed command (Steam's `%command%`)
+    ///
+    /// They go right after the game executable, the first argument inside
+    /// the install directory, so the launcher's own launch options still
+    /// come last; without one they are appended.
+    pub fn compose(&self, mut command: Vec<String>, install_dir: &Path) -> Vec<String> {
+        let exe = command.iter().position(|arg| {
+            !install_dir.as_os_str().is_empty() && Path::new(arg).starts_with(install_dir)
+        });
+        let at = exe.map_or(command.len(), |index| index + 1);
+        command.splice(at..at, self.args.iter().cloned());
+        command
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::detection::{DetectedGame, GameSource};
+
+    #[test]
+    fn test_profile_and_game_layers() {
+        let game = DetectedGame {
+            source: GameSource::Unknown,
+            id: "witcher3".into(),
+            name: "The Witcher 3".into(),
+            install_dir: PathBuf::from("/games/witcher3"),
+            executable: None,
+            fingerprint: None,
+            metadata: HashMap::new(),
+        };
+        let template = TemplateContext::for_game(&game, None);
+        let profile: Value =
+            serde_yaml::from_str("launch:\n  args: -skipintro -dx12\n  cwd: /games/bin\n").unwrap();
+
+        let launch = LaunchArgs::resolve(Some(&profile), &[], None, &template).unwrap();
+        assert_eq!(launch.args, vec!["-skipintro", "-dx12"]);
+        assert_eq!(launch.cwd, Some(PathBuf::from("/games/bin")));
+
+        let record = vec!["-log=${game.id}.log".to_string()];
+        let launch = LaunchArgs::resolve(
+            Some(&profile),
+            &record,
+            Some("${game.dir}/bin/x64"),
+            &template,
+        )
+        .unwrap();
+        assert_eq!(
+            launch.merge(&["-windowed".to_string()]),
+            vec!["-skipintro", "-dx12", "-log=witcher3.log", "-windowed"]
+        );
+        assert_eq!(launch.cwd, Some(PathBuf::from("/games/witcher3/bin/x64")));
+
+        let command = [
+            "proton",
+            "waitforexitandrun",
+            "/games/witcher3/bin/witcher3.exe",
+            "-net",
+        ]
+        .map(String::from)
+        .to_vec();
+        assert_eq!(
+            launch.compose(command, &game.install_dir)[3..],
+            ["-skipintro", "-dx12", "-log=witcher3.log", "-net"]
+        );
+
+        let invalid: Value = serde_yaml::from_str("launch:\n  args: {a: 1}\n").unwrap();
+        assert!(LaunchArgs::resolve(Some(&invalid), &[], None, &template).is_err());
+    }
+}
@@ -0,0 +1,227 @@
+//! Companion native libraries (nvshader, nvlatency, nvsync)
+//!
+//! `nvproton libs update` reads the `libs.json` manifest attached to the
+//! nvproton release matching this build (or the newest nightly), which pins
+//! a version and minimum driver branch per library:
+//!
+//! ```json
+//! { "nvlatency": { "version": "0.3.1", "min_driver": 550 } }
+//! ```
+//!
+//! Each library is downloaded from its own GitHub release (`lib<name>.so`),
+//! checked against that release's `SHA256SUMS` and installed into
+//! `~/.local/lib/nvproton`, which the FFI loader searches first.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{LibsArgs, LibsCommand, LibsUpdateArgs, UpdateChannel};
+use crate::config::{ConfigManager, ConfigPaths};
+use crate::detection::VulkanCapabilities;
+use crate::detection::distro;
+use crate::ffi::LibraryDiscovery;
+use crate::self_update::{self, CHECKSUM_ASSET, RELEASES_URL, Release};
+
+/// Companion libraries nvproton loads through FFI
+const LIBRARIES: &[&str] = &["nvshader", "nvlatency", "nvsync"];
+
+const MANIFEST_ASSET: &str = "libs.json";
+
+/// Library versions pinned by an nvproton release
+#[derive(Debug, Clone, Deserialize)]
+struct LibSpec {
+    version: String,
+    /// Oldest driver branch the library supports
+    #[serde(default)]
+    min_driver: Option<u32>,
+}
+
+/// Library versions installed by `libs update`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct InstalledLibs {
+    #[serde(default)]
+    versions: BTreeMap<String, String>,
+}
+
+impl InstalledLibs {
+    const FILE: &str = "libs.yaml";
+
+    fn load(paths: &ConfigPaths) -> Result<Self> {
+        paths.load_state(Self::FILE)
+    }
+
+    fn save(&self, paths: &ConfigPaths) -> Result<()> {
+        paths.save_state(Self::FILE, self)
+    }
+}
+
+/// Handle the `libs` command
+pub fn handle_libs(args: LibsArgs, manager: &ConfigManager) -> Result<()> {
+    match args.command {
+        LibsCommand::Status => handle_status(manager),
+        LibsCommand::Update(update_args) => handle_update(update_args, manager),
+    }
+}
+
+fn handle_status(manager: &ConfigManager) -> Result<()> {
+    let discovery = LibraryDiscovery::discover();
+    let installed = InstalledLibs::load(manager.paths())?;
+    for (name, path) in [
+        ("nvshader", &discovery.nvshader),
+        ("nvlatency", &discovery.nvlatency),
+        ("nvsync", &discovery.nvsync),
+    ] {
+        let version = installed
+            .versions
+            .get(name)
+            .map(|v| format!(" {}", v))
+            .unwrap_or_default();
+        match path {
+            Some(path) => println!("  {}{}: {}", name, version, path.display()),
+            None => println!("  {}: not found", name),
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn handle_update(args: LibsUpdateArgs, manager: &ConfigManager) -> Result<()> {
+    let releases: Vec<Release> = serde_json::from_slice(&self_update::download(RELEASES_URL)?)
+        .context("failed to parse GitHub releases")?;
+    let release = manifest_release(&releases, args.channel).with_context(|| {
+        format!(
+            "no {} release found",
+            self_update::channel_name(args.channel)
+        )
+    })?;
+    let manifest_url = release.asset_url(MANIFEST_ASSET).with_context(|| {
+        format!(
+            "release {} has no {} manifest",
+            release.tag_name, MANIFEST_ASSET
+        )
+    })?;
+    let manifest: BTreeMap<String, LibSpec> =
+        serde_json::from_slice(&self_update::download(manifest_url)?)
+            .context("failed to parse library manifest")?;
+
+    let driver_branch = VulkanCapabilities::detect().ok().map(|c| c.driver_branch);
+    let lib_dir = distro::user_lib_dir().context("could not determine home directory")?;
+    let mut installed = InstalledLibs::load(manager.paths())?;
+
+    println!("Companion libraries for nvproton {}:", release.tag_name);
+    for name in LIBRARIES {
+        let Some(spec) = manifest.get(*name) else {
+            println!("  {}: not part of this release", name);
+            continue;
+        };
+        if let (Some(min), Some(branch)) = (spec.min_driver, driver_branch)
+            && branch < min
+        {
+            println!(
+                "  {} {}: skipped (needs driver {}+, found {})",
+                name, spec.version, min, branch
+            );
+            continue;
+        }
+        let file_name = format!("lib{}.so", name);
+        let target = lib_dir.join(&file_name);
+        if installed.versions.get(*name) == Some(&spec.version) && target.exists() {
+            println!("  {} {}: up to date", name, spec.version);
+            continue;
+        }
+        if args.check_only {
+            println!(
+                "  {} {}: update available (installed: {})",
+                name,
+                spec.version,
+                installed
+                    .versions
+                    .get(*name)
+                    .map(String::as_str)
+                    .unwrap_or("none")
+            );
+            continue;
+        }
+
+        install_library(name, &spec.version, &file_name, &lib_dir)?;
+        installed
+            .versions
+            .insert(name.to_string(), spec.version.clone());
+        println!(
+            "  {} {}: installed to {}",
+            name,
+            spec.version,
+            target.display()
+        );
+    }
+
+    installed.save(manager.paths())?;
+    Ok(())
+}
+
+/// nvproton release whose manifest applies to this build
+///
+/// Stable prefers the release tagged with the running version so libraries
+/// match the binary's FFI; otherwise the channel's newest release is used.
+fn manifest_release(releases: &[Release], channel: UpdateChannel) -> Option<&Release> {
+    let current_tag = format!("v{}", env!("CARGO_PKG_VERSION"));
+    if channel == UpdateChannel::Stable
+        && let Some(release) = releases.iter().find(|r| r.tag_name == current_tag)
+    {
+        return Some(release);
+    }
+    self_update::select_release(releases, channel)
+}
+
+/// Download `lib<name>.so` from the library's release and install it
+fn install_library(name: &str, version: &str, file_name: &str, lib_dir: &Path) -> Result<()> {
+    let releases_url = format!(
+        "https://api.github.com/repos/ghostkellz/{}/releases/tags/v{}",
+        name, version
+    );
+    let release: Release = serde_json::from_slice(&self_update::download(&releases_url)?)
+        .with_context(|| format!("failed to parse {} release v{}", name, version))?;
+    let binary_url = release
+        .asset_url(file_name)
+        .with_context(|| format!("{} v{} has no {} asset", name, version, file_name))?;
+    let Some(sums_url) = release.asset_url(CHECKSUM_ASSET) else {
+        bail!(
+            "{} v{} has no {}; refusing to install an unverified library",
+            name,
+            version,
+            CHECKSUM_ASSET
+        );
+    };
+    let sums = String::from_utf8_lossy(&self_update::download(sums_url)?).to_string();
+    let expected = self_update::expected_checksum(&sums, file_name)
+        .with_context(|| format!("{} does not list {}", CHECKSUM_ASSET, file_name))?;
+
+    fs::create_dir_all(lib_dir)
+        .with_context(|| format!("failed to create directory {:?}", lib_dir))?;
+    let staged = lib_dir.join(format!(".{}.update", file_name));
+    self_update::download_to(binary_url, &staged)?;
+    if let Err(err) = self_update::install_staged(&staged, &lib_dir.join(file_name), &expected) {
+        let _ = fs::remove_file(&staged);
+        return Err(err);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_parsing() {
+        let manifest: BTreeMap<String, LibSpec> = serde_json::from_str(
+            r#"{"nvlatency": {"version": "0.3.1", "min_driver": 550}, "nvsync": {"version": "0.2.0"}}"#,
+        )
+        .unwrap();
+        assert_eq!(manifest["nvlatency"].version, "0.3.1");
+        assert_eq!(manifest["nvlatency"].min_driver, Some(550));
+        assert_eq!(manifest["nvsync"].min_driver, None);
+    }
+}
@@ -0,0 +1,312 @@
+# Deutsche Meldungen
+#
+# Fehlende Meldungen werden aus en.ftl übernommen.
+
+warning = Warnung: { $message }
+
+## Tabellenspalten
+
+column-cache = Cache
+column-exit = Exit
+column-failed = Fehlgeschl.
+column-game = Spiel
+column-id = ID
+column-install = Installation
+column-last = Zuletzt
+column-name = Name
+column-ok = OK
+column-path = Pfad
+column-prefix = Präfix
+column-profile = Profil
+column-proton = Proton
+column-runtime = Laufzeit
+column-shaders = Shader
+column-source = Quelle
+column-started = Gestartet
+column-total = Gesamt
+
+## run / wrap
+
+run-running = Starte: { $name } ({ $id })
+run-target = Startziel: { $name } ({ $exe })
+run-mod-loader = Mod-Loader: { $loader }
+run-mod-loader-missing = Warnung: { $loader } braucht { $runtime } im Präfix: protontricks { $id } { $runtime }
+run-prewarming = Shader werden vorkompiliert...
+run-prewarm-failed = Warnung: Vorkompilieren der Shader fehlgeschlagen: { $error }
+run-shaders-compiled = Shader: { $completed }/{ $total } kompiliert ({ $failed } fehlgeschlagen, { $skipped } übersprungen)
+run-shaders-no-caches = Shader: Keine Fossilize-Caches für dieses Spiel gefunden
+run-script-written = Startskript geschrieben nach { $path }
+run-dry-run = [Probelauf] Würde ausführen:
+run-dry-run-command = Befehl: { $command }
+run-dry-run-cwd = Arbeitsverzeichnis: { $dir }
+run-dry-run-environment = Umgebung:
+run-dry-run-hooks = Hooks: { $hooks }
+run-env-conflicts = Widersprüchliche Variablen (spätere Ebenen gewinnen: environment, config, proton-nv, profile, streaming, game, flags):
+run-env-conflict = { $key }={ $value } aus { $layer } ersetzt { $previous } aus { $earlier }
+run-dry-run-session = Anzeigesitzung: { $session }, VRR über { $vrr }
+run-dry-run-dlss = Vom Spiel mitgeliefertes DLSS: { $features }
+run-dry-run-dlss-fg-off = Frame Generation ist nicht aktiviert (--dlss-fg oder dlss.frame_generation im Profil)
+run-launching = { $name } wird gestartet...
+run-manifest = Manifest: { $path }
+run-manifest-failed = Warnung: Start-Manifest konnte nicht geschrieben werden: { $error }
+run-exit-status = Spiel beendet mit Status: { $status }
+run-waiting-for-game = Warte auf den Start des Spiels...
+run-launcher-failed = Launcher beendet mit Status: { $status }
+run-game-not-seen = Innerhalb von { $secs }s ist kein Spielprozess erschienen (siehe --launch-timeout)
+run-vram-low = Nur { $free } von { $total } VRAM sind frei, das Spiel kann ruckeln
+run-vram-process = { $name } (PID { $pid }, { $kind }) belegt { $used } VRAM
+run-memory-low = Nur { $available } von { $total } RAM sind verfügbar, das Spiel kann ruckeln oder beendet werden
+run-max-map-count-low = vm.max_map_count ist { $current }, manche Spiele stürzen unter { $recommended } ab (Abhilfe: nvproton status --apply-sysctl)
+run-media-dir = Aufnahmen: { $path }
+run-replay = Sofortwiederholung mit gpu-screen-recorder, gespeichert in { $path } (speichern mit: pkill -SIGUSR1 -f gpu-screen-recorder)
+run-replay-unavailable = Warnung: --replay benötigt gpu-screen-recorder im PATH
+run-saves-backed-up = { $files } Spielstand-Datei(en) gesichert
+run-saves-failed = Spielstände konnten nicht gesichert werden: { $error }
+run-retrying = Das Spiel wurde nach { $secs }s beendet, starte es noch einmal
+run-retry-no-prewarm = Überspringe das Vorwärmen der Shader
+run-retry-cache-cleared = Shader-Caches des Spiels geleert ({ $size })
+run-retry-cache-failed = Shader-Caches konnten nicht geleert werden: { $error }
+run-retry-safe = Ohne Reflex, DLSS Frame Generation, Smooth Motion und Descriptor Heaps
+run-xid-errors = { $count } NVIDIA-Xid-Fehler in dieser Sitzung:
+run-telemetry-failed = Warnung: Telemetrie konnte nicht gespeichert werden: { $error }
+run-session-failed = Warnung: Sitzung konnte nicht aufgezeichnet werden: { $error }
+run-report-failed = Warnung: Kompatibilitätsbericht konnte nicht gesendet werden: { $error }
+run-crash-report = Absturzbericht gespeichert unter { $path }
+run-crash-report-hint = Anzeigen mit: nvproton report { $id }
+run-crash-notify = { $name } ist abgestürzt
+run-crash-report-failed = Warnung: Absturzbericht konnte nicht gespeichert werden: { $error }
+run-log-analysis-failed = Warnung: Proton-Log konnte nicht analysiert werden: { $error }
+run-recent-title = Zuletzt gespielt:
+run-recent-prompt = Welches Spiel starten? [1-{ $count }, Enter für 1]:
+run-recent-failed = Warnung: Liste der zuletzt gespielten Spiele konnte nicht aktualisiert werden: { $error }
+run-update-remaining = (noch { $size })
+run-update-pending = Warnung: Für { $name } steht ein Update aus{ $remaining }; Steam lädt es vor dem Spielstart herunter
+run-cloud-conflict = Warnung: { $count } Steam-Cloud-Datei(en) von { $name } wurden hier und in der Cloud geändert; Steam fragt, welche Kopie behalten wird
+run-cloud-conflict-hint = Die falsche Kopie kostet Fortschritt: zuerst mit 'nvproton saves backup { $id }' sichern und in 'nvproton games show { $id }' vergleichen
+run-update-pending-hint = Mit --wait-for-update zuerst auf das Ende des Updates warten
+run-steam-starting = Steam läuft nicht; es startet vor dem Spiel, das kann einen Moment dauern
+run-update-waiting = Warte auf das Ende des Updates von { $name }...
+run-update-paused = { $state } (in Steams Download-Seite starten)
+run-update-finished = Update abgeschlossen
+run-container = Steam Linux Runtime: { $suite }
+run-proton-nv = Proton-NV: { $version } erkannt
+run-plan-cached = Startplan: wiederverwendet (Profil und Konfiguration unverändert)
+run-profile = Profil: { $name }
+run-wrappers = Wrapper: { $wrappers }
+run-streaming = Streaming: { $reason } - Streaming-Einstellungen werden angewendet
+run-reflex-env-only = Reflex: aktiviert (nur Umgebungsvariablen)
+run-reflex-nvlatency = Reflex: aktiviert über nvlatency
+run-reflex2-env-only = Reflex 2.0: aktiviert (nur Umgebungsvariablen)
+run-reflex2-nvlatency = Reflex 2.0: aktiviert über nvlatency
+run-dlss-fg = DLSS Frame Generation: NVAPI und Reflex aktiviert
+run-dlss-fg-not-shipped = Warnung: { $name } liefert keine nvngx_dlssg.dll mit, Frame Generation bleibt wirkungslos
+run-dlss-fg-blocked = Warnung: { $reason }
+run-anticheat-runtime = Anti-Cheat: { $runtime } unter { $path }
+run-anticheat-missing = Warnung: { $name } nutzt { $anticheat }, aber die { $runtime } ist in Steam nicht installiert; das Spiel startet vermutlich nicht (Installation mit steam://install/{ $appid })
+run-smooth-motion = Smooth Motion: aktiviert (Bildinterpolation im Treiber)
+run-smooth-motion-unsupported = Warnung: Smooth Motion benötigt eine GPU ab RTX 40 und Treiber { $min } oder neuer ({ $gpu }, Treiber { $version }); nicht aktiviert
+run-vrr-env-only = VRR: aktiviert (nur Umgebungsvariablen)
+run-vrr-nvsync = VRR: aktiviert über nvsync
+run-vrr-compositor = VRR: { $session }-Sitzung, Adaptive Sync des Compositors wird für dieses Spiel eingeschaltet
+run-vrr-compositor-manual = VRR: { $session }-Sitzung, Adaptive Sync in den Compositor-Einstellungen aktivieren (__GL_GSYNC_ALLOWED wirkt nicht)
+run-fps-env-only = FPS-Limit: { $fps } (nur Umgebungsvariablen)
+run-fps-nvsync = Bildratenlimit: { $fps } FPS über nvsync
+run-descriptor-heap = Descriptor Heap: aktiviert (DX12-Optimierung)
+run-descriptor-heap-fix = Descriptor Heap: aktiviert (DX12-Optimierung + 595-Heap-Fix)
+run-proton-untested = Warnung: Start mit { $current }; der letzte erfolgreiche Start nutzte { $good }
+run-wow64-32bit = { $name } ist ein 32-Bit-Spiel; mit PROTON_USE_WOW64=1 läuft es über WoW64-Thunks, was langsamer sein kann. Entferne die Variable, falls das Spiel ruckelt.
+run-override = Überschrieben: { $component } { $version }
+run-beta-driver-595 = Hinweis: 595-Beta-Treiber { $version } - empfohlen für DX12-Spiele (enthält Heap-Fixes)
+run-beta-driver = Warnung: Beta-Treiber { $version } erkannt. Ein Update auf 595.x bringt Heap-Fixes.
+run-sync-disabled = Warnung: esync und fsync sind beide deaktiviert; Wine nutzt dann die langsame Synchronisierung über den Server
+run-sync = Wine-Synchronisierung: { $mechanism }
+run-sync-unsupported = Warnung: { $mechanism } benötigt { $requirement }; stattdessen wird der schnellste unterstützte Mechanismus genutzt
+run-sync-none = Warnung: der Kernel unterstützt weder ntsync noch fsync oder esync; Wine nutzt die Synchronisierung über den Server
+run-wine-wayland = Wine-Wayland-Treiber: aktiviert ({ $runtime })
+run-wine-wayland-fallback = Wine-Wayland-Treiber nicht verfügbar, Start über Xwayland: { $reason }
+wrap-wrapping = starte { $name } ({ $id }) mit Wrapper
+wrap-unchanged = { $error } - Befehl wird unverändert ausgeführt
+
+## prepare
+
+prepare-preparing = Vorbereitung: { $name } ({ $id })
+prepare-proton-nv = Proton-NV: { $version } (wird beim Start verwendet)
+prepare-proton-nv-driver = Benötigt: NVIDIA-Treiber { $driver }
+prepare-proton-nv-target = Ziel: { $gpu }
+prepare-proton-nv-missing = Proton-NV: nicht gefunden (System-Proton wird verwendet)
+prepare-profile-bound = Profil: { $name } (an das Spiel gebunden, wird beim Start angewendet)
+prepare-forcing = Shader-Caches des Spiels geleert ({ $size }), Neukompilierung
+prepare-shaders-ready = Shader bereit!
+prepare-install-ok = Installationsverzeichnis: OK
+prepare-install-missing = Warnung: Installationsverzeichnis nicht gefunden: { $dir }
+prepare-executable-ok = Programmdatei: OK
+prepare-executable-missing = Warnung: Programmdatei nicht gefunden: { $exe }
+prepare-ready = Das Spiel kann mit 'nvproton run { $id }' gestartet werden
+prepare-notify = { $name } ist spielbereit
+prepare-all = Bereite { $count } Spiele vor ({ $jobs } Jobs)...
+prepare-all-summary = { $count } Spiele vorbereitet, { $problems } mit Installationsproblemen
+prepare-all-notify = { $count } Spiele vorbereitet
+prepare-dry-run = Würde { $count } Spiele vorbereiten:
+prepare-row-compiled = { $completed }/{ $total } kompiliert ({ $failed } fehlgeschlagen)
+prepare-row-no-caches = keine Fossilize-Caches
+prepare-row-unavailable = Vorkompilieren nicht verfügbar
+prepare-row-failed = Vorkompilieren fehlgeschlagen
+prepare-row-install-missing = Installationsverzeichnis fehlt
+prepare-row-executable-missing = Programmdatei fehlt
+prepare-row-ok = ok
+
+## games
+
+games-none = Keine Spiele gefunden. 'nvproton games scan' sucht nach Spielen.
+games-no-matches = Keine Spiele entsprechen den Filtern.
+games-also = (auch: { $sources })
+games-found = { $count } Spiele gefunden
+games-prefer = { $name } wird über { $source } gestartet ({ $id })
+games-du-total = { $count } Spiele, { $size } insgesamt
+games-graphics-default = Proton-Standard
+games-graphics-override = (überschrieben in { $prefix })
+games-dlss-fg-blocked = Frame Generation nicht verfügbar: { $reason }
+games-depot = { $id } Manifest { $manifest } ({ $size })
+games-cloud-synced = { $count } Datei(en), synchron
+games-cloud-unsynced = { $count } Datei(en), { $conflicts } im Konflikt
+games-depot-dlc = DLC { $dlc }
+games-scanning = Suche nach Spielen...
+games-scan-error = Fehler - { $error }
+games-scan-disabled = übersprungen (nicht in detectors.enabled_sources)
+games-cleaned = Bereinigt: { $count } ausgeschlossene Einträge entfernt
+games-scan-total = Gesamt: { $count } Spiele zur Datenbank hinzugefügt
+games-scan-notify = Spielsuche abgeschlossen
+games-scan-hint = 'nvproton games list' zeigt alle Spiele
+games-profile-set = Profil '{ $profile }' dem Spiel '{ $id }' zugewiesen
+games-profile-dry-run = Würde Profil '{ $profile }' { $count } Spielen zuweisen:
+games-wrappers = Wrapper für '{ $id }':
+games-wrappers-none = Keine Wrapper für '{ $id }' gesetzt
+games-tags = Tags von '{ $id }': { $tags }
+games-tags-none = Keine Tags für '{ $id }' gesetzt
+games-exe-set = { $name } startet { $exe }
+games-exe-cleared = { $name } startet das erkannte Programm
+games-exe-override = { $exe } (gesetzt mit 'games set-exe')
+games-args = Argumente für '{ $id }': { $args }
+games-args-none = Keine Argumente für '{ $id }' gesetzt
+games-args-cwd = Arbeitsverzeichnis: { $dir }
+games-targets = Startziele von '{ $id }':
+games-targets-none = Keine Startziele für '{ $id }'
+games-target-steam = (von Steam)
+games-target-mod-loader = (Mod-Manager)
+games-mod-loader-missing = braucht { $runtime } im Präfix: protontricks { $id } { $runtime }
+games-info-game = Spiel: { $name } ({ $id })
+games-info-command = Startplan:
+games-info-standalone = Eigenständige Befehlszeile:
+games-info-steam-note =
+    Hinweis: Ein laufender Steam-Client startet das Spiel selbst, ohne diese Umgebung.
+    Mit 'nvproton steam launch-options { $id }' wird sie aus Steam heraus angewendet.
+games-info-source = Quelle: { $source }
+games-info-install = Installation: { $dir }
+games-info-profile = Profil: { $profile }
+games-info-tags = Tags: { $tags }
+games-info-hint = --command zeigt den Startplan
+
+field-name = Name
+field-id = ID
+field-source = Quelle
+field-install-dir = Verzeichnis
+field-state = Status
+field-executable = Programm
+field-fingerprint = Fingerabdr.
+field-architecture = Architektur
+field-proton = Proton
+field-graphics = Grafik
+field-dlc = DLC
+field-depots = Depots
+field-cloud = Cloud
+field-tags = Tags
+field-mod-loaders = Mod-Loader
+field-dlss = DLSS
+field-metadata = Metadaten
+
+session-none = Keine Sitzungen für { $id } aufgezeichnet
+session-count = { $count } Sitzungen aufgezeichnet
+session-last = Letzte Sitzung von { $id }
+session-started = Gestartet: { $started }
+session-runtime = Laufzeit:  { $runtime }
+session-result = Ergebnis:  { $result }
+session-result-ok = ok
+session-result-exit = Exit-Code { $code }
+session-result-signal = durch Signal beendet
+session-profile = Profil:    { $profile }
+session-proton = Proton:    { $proton }
+session-xid-errors = Xid-Fehler:
+session-no-telemetry = Keine Telemetrie aufgezeichnet (mit --telemetry starten)
+session-telemetry = Telemetrie ({ $count } Messwerte):
+
+age-just-now = gerade eben
+age-ago = vor { $age }
+
+## steam
+
+steam-launch-options = Startoptionen für: { $name } ({ $id })
+steam-copy-hint = In Steams „Startoptionen festlegen“ einfügen:
+steam-recommended-env = Empfohlene Umgebungsvariablen:
+steam-full-command = Vollständiger Startbefehl:
+steam-apply-steps =
+    So wird es in Steam angewendet:
+      1. Rechtsklick auf { $name } in der Bibliothek
+      2. Eigenschaften > Allgemein > Startoptionen
+      3. Den Befehl oben einfügen
+steam-proton-installed = Installierte Proton-Versionen:
+steam-proton-custom = compatibilitytools.d
+steam-proton-steam = Steam
+steam-proton-recommended =
+    Empfohlene Proton-Versionen für NVIDIA:
+
+    1. Proton Experimental (neueste Funktionen)
+       - Geeignet für: Die meisten aktuellen Spiele, VR
+       - DLSS: Volle Unterstützung
+       - Reflex: Volle Unterstützung
+
+    2. Proton GE (GloriousEggroll)
+       - Geeignet für: Spiele mit Codec-Problemen, ältere Titel
+       - Installation: https://github.com/GloriousEggroll/proton-ge-custom
+
+    3. Proton 9.x (stabil)
+       - Geeignet für: Spiele, die Stabilität brauchen
+       - DLSS: Unterstützt
+
+    Für kompetitives Spielen mit Reflex Proton Experimental verwenden.
+steam-proton-set-default =
+    Standard-Proton-Version wird gesetzt auf: { $version }
+
+    So wird Proton in Steam als Standard gesetzt:
+      1. Steam > Einstellungen > Kompatibilität
+      2. „Steam Play für alle anderen Titel aktivieren“ einschalten
+      3. '{ $version }' in der Liste auswählen
+
+    Hinweis: nvproton beachtet Steams Proton-Einstellungen pro Spiel.
+steam-shortcut-creating = Erstelle Nicht-Steam-Verknüpfung: { $name }
+steam-shortcut-details = Details der Verknüpfung:
+steam-shortcut-name = Name: { $name }
+steam-shortcut-exe = Programm: { $exe }
+steam-shortcut-start-in = Ausführen in: { $dir }
+steam-shortcut-icon = Symbol: { $icon }
+steam-shortcut-options = Startoptionen: { $options }
+steam-shortcut-steps =
+    Manuell in Steam hinzufügen:
+      1. Bibliothek > Spiel hinzufügen > Ein Nicht-Steam-Spiel hinzufügen
+      2. Durchsuchen: { $exe }
+      3. Rechtsklick auf die Verknüpfung > Eigenschaften
+steam-shortcut-step-options = 4. Startoptionen festlegen: { $options }
+steam-shortcut-note =
+    Hinweis: Verknüpfungen können nur bei geschlossenem Steam automatisch erstellt werden.
+    Die Datei shortcuts.vdf liegt unter: { $path }
+steam-shortcut-list = Nicht-Steam-Verknüpfungen:
+steam-shortcut-no-userdata = Keine Steam-Benutzerdaten gefunden.
+steam-shortcut-user = Benutzer: { $user }
+steam-shortcut-file = Verknüpfungsdatei: { $path }
+steam-shortcut-optimizing = Optimiere Verknüpfung für: { $name } ({ $id })
+steam-shortcut-profile = Profil wird angewendet: { $profile }
+steam-shortcut-recommended = Empfohlene Startoptionen:
+
+# undo
+undo-empty = Nichts rückgängig zu machen
+undo-dry-run = Würde 'nvproton { $command }' rückgängig machen und wiederherstellen:
+undo-done = 'nvproton { $command }' rückgängig gemacht
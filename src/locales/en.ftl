@@ -0,0 +1,313 @@
+# English messages (reference catalog)
+#
+# Every message used in the code must be defined here; other catalogs may
+# translate any subset and fall back to these.
+
+warning = Warning: { $message }
+
+## Table columns
+
+column-cache = Cache
+column-exit = Exit
+column-failed = Failed
+column-game = Game
+column-id = ID
+column-install = Install
+column-last = Last launch
+column-name = Name
+column-ok = OK
+column-path = Path
+column-prefix = Prefix
+column-profile = Profile
+column-proton = Proton
+column-runtime = Runtime
+column-shaders = Shaders
+column-source = Source
+column-started = Started
+column-total = Total
+
+## run / wrap
+
+run-running = Running: { $name } ({ $id })
+run-target = Target: { $name } ({ $exe })
+run-mod-loader = Mod loader: { $loader }
+run-mod-loader-missing = Warning: { $loader } needs { $runtime } in the prefix: protontricks { $id } { $runtime }
+run-prewarming = Pre-warming shaders...
+run-prewarm-failed = Warning: shader pre-warming failed: { $error }
+run-shaders-compiled = Shaders: { $completed }/{ $total } compiled ({ $failed } failed, { $skipped } skipped)
+run-shaders-no-caches = Shaders: No Fossilize caches found for this game
+run-script-written = Launch script written to { $path }
+run-dry-run = [Dry Run] Would execute:
+run-dry-run-command = Command: { $command }
+run-dry-run-cwd = Working directory: { $dir }
+run-dry-run-environment = Environment:
+run-dry-run-hooks = Hooks: { $hooks }
+run-env-conflicts = Conflicting variables (later layers win: environment, config, proton-nv, profile, streaming, game, flags):
+run-env-conflict = { $key }={ $value } from { $layer } replaces { $previous } from { $earlier }
+run-dry-run-session = Display session: { $session }, VRR via { $vrr }
+run-dry-run-dlss = DLSS shipped by the game: { $features }
+run-dry-run-dlss-fg-off = Frame Generation is not enabled (use --dlss-fg or the profile's dlss.frame_generation)
+run-launching = Launching { $name }...
+run-manifest = Manifest: { $path }
+run-manifest-failed = Warning: failed to write launch manifest: { $error }
+run-exit-status = Game exited with status: { $status }
+run-waiting-for-game = Waiting for the game to start...
+run-launcher-failed = Launcher exited with status: { $status }
+run-game-not-seen = No game process appeared within { $secs }s (see --launch-timeout)
+run-vram-low = Only { $free } of { $total } VRAM is free, the game may stutter
+run-vram-process = { $name } (PID { $pid }, { $kind }) holds { $used } of VRAM
+run-memory-low = Only { $available } of { $total } RAM is available, the game may stutter or be killed
+run-max-map-count-low = vm.max_map_count is { $current }, some games crash below { $recommended } (fix: nvproton status --apply-sysctl)
+run-media-dir = Captures: { $path }
+run-replay = Instant replay with gpu-screen-recorder, saved to { $path } (save with: pkill -SIGUSR1 -f gpu-screen-recorder)
+run-replay-unavailable = Warning: --replay needs gpu-screen-recorder in PATH
+run-saves-backed-up = Backed up { $files } save file(s)
+run-saves-failed = Failed to back up the saves: { $error }
+run-retrying = The game exited after { $secs }s, launching it once more
+run-retry-no-prewarm = Skipping shader pre-warming
+run-retry-cache-cleared = Cleared the game's shader caches ({ $size })
+run-retry-cache-failed = Failed to clear the shader caches: { $error }
+run-retry-safe = Without Reflex, DLSS Frame Generation, Smooth Motion and descriptor heaps
+run-xid-errors = { $count } NVIDIA Xid error(s) occurred during this session:
+run-telemetry-failed = Warning: failed to save telemetry: { $error }
+run-session-failed = Warning: failed to record session: { $error }
+run-report-failed = Warning: failed to submit compatibility report: { $error }
+run-crash-report = Crash report saved to { $path }
+run-crash-report-hint = View it with: nvproton report { $id }
+run-crash-notify = { $name } crashed
+run-crash-report-failed = Warning: failed to save crash report: { $error }
+run-log-analysis-failed = Warning: failed to analyze Proton log: { $error }
+run-recent-title = Recently played:
+run-recent-prompt = Launch which game? [1-{ $count }, Enter for 1]:
+run-recent-failed = Warning: failed to update recent games: { $error }
+run-update-remaining = ({ $size } left)
+run-update-pending = Warning: { $name } has a pending update{ $remaining }; Steam will download it before the game starts
+run-cloud-conflict = Warning: { $count } Steam Cloud file(s) of { $name } changed both here and in the cloud; Steam will ask which copy to keep
+run-cloud-conflict-hint = Keeping the wrong copy loses progress: back up first with 'nvproton saves backup { $id }' and compare in 'nvproton games show { $id }'
+run-update-pending-hint = Use --wait-for-update to wait for the update to finish first
+run-steam-starting = Steam isn't running; it starts before the game, which may take a moment
+run-update-waiting = Waiting for the { $name } update to finish...
+run-update-paused = { $state } (start it from Steam's Downloads page)
+run-update-finished = Update finished
+run-container = Steam Linux Runtime: { $suite }
+run-proton-nv = Proton-NV: { $version } detected
+run-plan-cached = Launch plan: reused (profile and config unchanged)
+run-profile = Profile: { $name }
+run-wrappers = Wrappers: { $wrappers }
+run-streaming = Streaming: { $reason } - applying streaming fragment
+run-reflex-env-only = Reflex: enabled (env vars only)
+run-reflex-nvlatency = Reflex: enabled via nvlatency
+run-reflex2-env-only = Reflex 2.0: enabled (env vars only)
+run-reflex2-nvlatency = Reflex 2.0: enabled via nvlatency
+run-dlss-fg = DLSS Frame Generation: NVAPI and Reflex enabled
+run-dlss-fg-not-shipped = Warning: { $name } does not ship nvngx_dlssg.dll, Frame Generation has no effect
+run-dlss-fg-blocked = Warning: { $reason }
+run-anticheat-runtime = Anti-cheat: { $runtime } at { $path }
+run-anticheat-missing = Warning: { $name } uses { $anticheat }, but the { $runtime } isn't installed in Steam; the game will likely fail to start (install it with steam://install/{ $appid })
+run-smooth-motion = Smooth Motion: enabled (driver frame interpolation)
+run-smooth-motion-unsupported = Warning: Smooth Motion needs an RTX 40 series or newer GPU and driver { $min } or newer ({ $gpu }, driver { $version }); not enabled
+run-vrr-env-only = VRR: enabled (env vars only)
+run-vrr-nvsync = VRR: enabled via nvsync
+run-vrr-compositor = VRR: { $session } session, switching the compositor's adaptive sync for this game
+run-vrr-compositor-manual = VRR: { $session } session, enable adaptive sync in the compositor settings (__GL_GSYNC_ALLOWED has no effect)
+run-fps-env-only = FPS Limit: { $fps } (env vars only)
+run-fps-nvsync = Frame limit: { $fps } FPS via nvsync
+run-descriptor-heap = Descriptor Heap: enabled (DX12 optimization)
+run-descriptor-heap-fix = Descriptor Heap: enabled (DX12 optimization + 595 heap fix)
+run-proton-untested = Warning: running with { $current }; the last successful launch used { $good }
+run-wow64-32bit = { $name } is a 32-bit game; under PROTON_USE_WOW64=1 it runs through WoW64 thunks, which can be slower. Unset it if the game stutters.
+run-override = Override: { $component } { $version }
+run-beta-driver-595 = Note: 595 beta driver { $version } - recommended for DX12 games (heap fixes included)
+run-beta-driver = Warning: Beta driver { $version } detected. Consider updating to 595.x for heap fixes.
+run-sync-disabled = Warning: both esync and fsync are disabled; Wine will fall back to slow server-side synchronization
+run-sync = Wine synchronization: { $mechanism }
+run-sync-unsupported = Warning: { $mechanism } needs { $requirement }; using the fastest supported mechanism instead
+run-sync-none = Warning: the kernel supports neither ntsync, fsync nor esync; Wine will use server-side synchronization
+run-wine-wayland = Wine Wayland driver: enabled ({ $runtime })
+run-wine-wayland-fallback = Wine Wayland driver unavailable, running through Xwayland: { $reason }
+wrap-wrapping = wrapping { $name } ({ $id })
+wrap-unchanged = { $error } - running command unchanged
+
+## prepare
+
+prepare-preparing = Preparing: { $name } ({ $id })
+prepare-proton-nv = Proton-NV: { $version } (will be used at launch)
+prepare-proton-nv-driver = Requires: NVIDIA driver { $driver }
+prepare-proton-nv-target = Target: { $gpu }
+prepare-proton-nv-missing = Proton-NV: not detected (using system Proton)
+prepare-profile-bound = Profile: { $name } (bound to game, will be applied at launch)
+prepare-forcing = Cleared the game's shader caches ({ $size }), recompiling
+prepare-shaders-ready = Shaders ready!
+prepare-install-ok = Install directory: OK
+prepare-install-missing = Warning: Install directory not found: { $dir }
+prepare-executable-ok = Executable: OK
+prepare-executable-missing = Warning: Executable not found: { $exe }
+prepare-ready = Game is ready to launch with 'nvproton run { $id }'
+prepare-notify = { $name } is ready to play
+prepare-all = Preparing { $count } games ({ $jobs } jobs)...
+prepare-all-summary = { $count } games prepared, { $problems } with install problems
+prepare-all-notify = { $count } games prepared
+prepare-dry-run = Would prepare { $count } games:
+prepare-row-compiled = { $completed }/{ $total } compiled ({ $failed } failed)
+prepare-row-no-caches = no Fossilize caches
+prepare-row-unavailable = pre-warm unavailable
+prepare-row-failed = pre-warm failed
+prepare-row-install-missing = install dir missing
+prepare-row-executable-missing = executable missing
+prepare-row-ok = ok
+
+## games
+
+games-none = No games found. Run 'nvproton games scan' to detect games.
+games-no-matches = No games match the given filters.
+games-also = (also: { $sources })
+games-found = { $count } games found
+games-prefer = { $name } will launch from { $source } ({ $id })
+games-du-total = { $count } games, { $size } total
+games-graphics-default = Proton default
+games-graphics-override = (override in { $prefix })
+games-dlss-fg-blocked = Frame Generation unavailable: { $reason }
+games-depot = { $id } manifest { $manifest } ({ $size })
+games-cloud-synced = { $count } file(s), in sync
+games-cloud-unsynced = { $count } file(s), { $conflicts } in conflict
+games-depot-dlc = DLC { $dlc }
+games-scanning = Scanning for games...
+games-scan-error = error - { $error }
+games-scan-disabled = skipped (not in detectors.enabled_sources)
+games-cleaned = Cleaned: { $count } excluded entries removed
+games-scan-total = Total: { $count } games added to database
+games-scan-notify = Game scan finished
+games-scan-hint = Use 'nvproton games list' to see all games
+games-profile-set = Profile '{ $profile }' assigned to game '{ $id }'
+games-profile-dry-run = Would assign profile '{ $profile }' to { $count } games:
+games-wrappers = Wrappers for '{ $id }':
+games-wrappers-none = No wrappers set for '{ $id }'
+games-tags = Tags for '{ $id }': { $tags }
+games-tags-none = No tags set for '{ $id }'
+games-exe-set = { $name } will launch { $exe }
+games-exe-cleared = { $name } will launch the detected executable
+games-exe-override = { $exe } (set with 'games set-exe')
+games-args = Arguments for '{ $id }': { $args }
+games-args-none = No arguments set for '{ $id }'
+games-args-cwd = Working directory: { $dir }
+games-targets = Launch targets for '{ $id }':
+games-targets-none = No launch targets for '{ $id }'
+games-target-steam = (from Steam)
+games-target-mod-loader = (mod manager)
+games-mod-loader-missing = needs { $runtime } in the prefix: protontricks { $id } { $runtime }
+games-info-game = Game: { $name } ({ $id })
+games-info-command = Launch plan:
+games-info-standalone = Standalone command line:
+games-info-steam-note =
+    Note: a running Steam client starts the game itself, without this environment.
+    Use 'nvproton steam launch-options { $id }' to apply it from Steam.
+games-info-source = Source: { $source }
+games-info-install = Install: { $dir }
+games-info-profile = Profile: { $profile }
+games-info-tags = Tags: { $tags }
+games-info-hint = Use --command to see the launch plan
+
+field-name = Name
+field-id = ID
+field-source = Source
+field-install-dir = Install Dir
+field-state = State
+field-executable = Executable
+field-fingerprint = Fingerprint
+field-architecture = Architecture
+field-proton = Proton
+field-graphics = Graphics
+field-dlc = DLC
+field-depots = Depots
+field-cloud = Cloud
+field-tags = Tags
+field-mod-loaders = Mod loaders
+field-dlss = DLSS
+field-metadata = Metadata
+
+session-none = No sessions recorded for { $id }
+session-count = { $count } sessions recorded
+session-last = Last session for { $id }
+session-started = Started: { $started }
+session-runtime = Runtime: { $runtime }
+session-result = Result:  { $result }
+session-result-ok = ok
+session-result-exit = exit code { $code }
+session-result-signal = killed by signal
+session-profile = Profile: { $profile }
+session-proton = Proton:  { $proton }
+session-xid-errors = Xid errors:
+session-no-telemetry = No telemetry recorded (run with --telemetry)
+session-telemetry = Telemetry ({ $count } samples):
+
+age-just-now = just now
+age-ago = { $age } ago
+
+## steam
+
+steam-launch-options = Launch Options for: { $name } ({ $id })
+steam-copy-hint = Copy this into Steam's "Set Launch Options":
+steam-recommended-env = Recommended environment variables:
+steam-full-command = Full launch command:
+steam-apply-steps =
+    To apply in Steam:
+      1. Right-click { $name } in your library
+      2. Properties > General > Launch Options
+      3. Paste the command above
+steam-proton-installed = Installed Proton versions:
+steam-proton-custom = compatibilitytools.d
+steam-proton-steam = Steam
+steam-proton-recommended =
+    Recommended Proton versions for NVIDIA:
+
+    1. Proton Experimental (latest features)
+       - Best for: Most modern games, VR
+       - DLSS: Full support
+       - Reflex: Full support
+
+    2. Proton GE (GloriousEggroll)
+       - Best for: Games with codec issues, older titles
+       - Install: https://github.com/GloriousEggroll/proton-ge-custom
+
+    3. Proton 9.x (stable)
+       - Best for: Games that need stability
+       - DLSS: Supported
+
+    For competitive gaming with Reflex, use Proton Experimental.
+steam-proton-set-default =
+    Setting default Proton version to: { $version }
+
+    To set default Proton in Steam:
+      1. Steam > Settings > Compatibility
+      2. Enable 'Enable Steam Play for all other titles'
+      3. Select '{ $version }' from the dropdown
+
+    Note: nvproton respects Steam's per-game Proton settings.
+steam-shortcut-creating = Creating non-Steam shortcut: { $name }
+steam-shortcut-details = Shortcut details:
+steam-shortcut-name = Name: { $name }
+steam-shortcut-exe = Executable: { $exe }
+steam-shortcut-start-in = Start In: { $dir }
+steam-shortcut-icon = Icon: { $icon }
+steam-shortcut-options = Launch Options: { $options }
+steam-shortcut-steps =
+    To add manually in Steam:
+      1. Library > Add a Game > Add a Non-Steam Game
+      2. Browse to: { $exe }
+      3. Right-click the shortcut > Properties
+steam-shortcut-step-options = 4. Set Launch Options: { $options }
+steam-shortcut-note =
+    Note: Automatic shortcut creation requires Steam to be closed.
+    The shortcuts.vdf file is located at: { $path }
+steam-shortcut-list = Non-Steam shortcuts:
+steam-shortcut-no-userdata = No Steam userdata found.
+steam-shortcut-user = User: { $user }
+steam-shortcut-file = Shortcuts file: { $path }
+steam-shortcut-optimizing = Optimizing shortcut for: { $name } ({ $id })
+steam-shortcut-profile = Applying profile: { $profile }
+steam-shortcut-recommended = Recommended launch options:
+
+# undo
+undo-empty = Nothing to undo
+undo-dry-run = Would undo 'nvproton { $command }', restoring:
+undo-done = Undid 'nvproton { $command }'
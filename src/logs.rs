@@ -0,0 +1,232 @@
+//! Proton log capture and analysis
+//!
+//! Parses `steam-<appid>.log` files written by Proton when `PROTON_LOG=1`
+//! and matches them against known failure signatures:
+//! - Missing Direct3D / DXGI DLLs
+//! - NVAPI disabled or not found
+//! - vkd3d-proton feature level errors
+//! - Vulkan device loss and adapter failures
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{LogsArgs, LogsCommand, OutputFormat};
+
+/// A known failure signature in Proton logs
+struct Signature {
+    id: &'static str,
+    pattern: Regex,
+    description: &'static str,
+    hint: &'static str,
+}
+
+static SIGNATURES: Lazy<Vec<Signature>> = Lazy::new(|| {
+    let sig = |id, pattern: &str, description, hint| Signature {
+        id,
+        pattern: Regex::new(pattern).expect("valid signature regex"),
+        description,
+        hint,
+    };
+    vec![
+        sig(
+            "missing-d3d-dll",
+            r"(?i)import_dll Library (d3d\w*|dxgi)\.dll.*not found",
+            "A Direct3D/DXGI DLL could not be loaded",
+            "Verify the Proton install or recreate the prefix (DXVK/vkd3d-proton DLLs missing)",
+        ),
+        sig(
+            "nvapi-disabled",
+            r"(?i)(nvapi64?\.dll.*(not found|failed)|NVAPI_NVIDIA_DEVICE_NOT_FOUND|nvapi.*disabled)",
+            "NVAPI is disabled or unavailable",
+            "Run with --proton-enable-nvapi and make sure PROTON_HIDE_NVIDIA_GPU is not set",
+        ),
+        sig(
+            "vkd3d-feature-level",
+            r"(?i)feature level.*not supported",
+            "vkd3d-proton rejected the requested D3D12 feature level",
+            "Lower VKD3D_FEATURE_LEVEL (vkd3d.feature_level) or update the NVIDIA driver",
+        ),
+        sig(
+            "device-lost",
+            r"VK_ERROR_DEVICE_LOST",
+            "The Vulkan device was lost (GPU hang or driver reset)",
+            "Check 'dmesg' for NVIDIA Xid errors and try disabling descriptor_heap",
+        ),
+        sig(
+            "no-vulkan-adapter",
+            r"(?i)(DXVK: No adapters found|vkCreateInstance failed|Failed to create Vulkan instance)",
+            "No usable Vulkan adapter was found",
+            "Verify the NVIDIA Vulkan ICD is installed (nvidia-utils / lib32-nvidia-utils)",
+        ),
+        sig(
+            "unhandled-exception",
+            r"(?i)err:seh:.*unhandled exception",
+            "The game raised an unhandled exception",
+            "Try a different Proton version or check ProtonDB for game-specific fixes",
+        ),
+    ]
+});
+
+/// A signature matched in a log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogFinding {
+    pub id: String,
+    pub description: String,
+    pub hint: String,
+    pub occurrences: usize,
+    pub first_line: usize,
+    pub sample: String,
+}
+
+/// Result of analyzing a Proton log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogAnalysis {
+    pub path: Option<PathBuf>,
+    pub lines: usize,
+    pub findings: Vec<LogFinding>,
+}
+
+/// Analyze Proton log contents for known failure signatures
+pub fn analyze(content: &str) -> LogAnalysis {
+    let mut findings: Vec<LogFinding> = Vec::new();
+    let mut lines = 0;
+
+    for (index, line) in content.lines().enumerate() {
+        lines += 1;
+        for signature in SIGNATURES.iter() {
+            if !signature.pattern.is_match(line) {
+                continue;
+            }
+            if let Some(finding) = findings.iter_mut().find(|f| f.id == signature.id) {
+                finding.occurrences += 1;
+            } else {
+                findings.push(LogFinding {
+                    id: signature.id.to_string(),
+                    description: signature.description.to_string(),
+                    hint: signature.hint.to_string(),
+                    occurrences: 1,
+                    first_line: index + 1,
+                    sample: line.trim().to_string(),
+                });
+            }
+        }
+    }
+
+    LogAnalysis {
+        path: None,
+        lines,
+        findings,
+    }
+}
+
+/// Analyze a Proton log file
+pub fn analyze_file(path: &Path) -> Result<LogAnalysis> {
+    let bytes = fs::read(path).with_context(|| format!("failed to read log at {:?}", path))?;
+    let mut analysis = analyze(&String::from_utf8_lossy(&bytes));
+    analysis.path = Some(path.to_path_buf());
+    Ok(analysis)
+}
+
+/// Directory Proton writes logs to: PROTON_LOG_DIR of the launch
+/// environment, then of nvproton's own, defaulting to $HOME
+pub fn proton_log_dir(env_vars: &HashMap<String, String>) -> Option<PathBuf> {
+    env_vars
+        .get("PROTON_LOG_DIR")
+        .cloned()
+        .or_else(|| std::env::var("PROTON_LOG_DIR").ok())
+        .map(PathBuf::from)
+        .or_else(dirs::home_dir)
+}
+
+/// Expected Proton log path for a Steam AppID launched with `env_vars`
+pub fn proton_log_path(app_id: &str, env_vars: &HashMap<String, String>) -> Option<PathBuf> {
+    proton_log_dir(env_vars).map(|d| d.join(format!("steam-{}.log", app_id)))
+}
+
+/// Print a human-readable diagnosis summary
+pub fn print_summary(analysis: &LogAnalysis) {
+    match &analysis.path {
+        Some(path) => println!("Proton log diagnosis: {}", path.display()),
+        None => println!("Proton log diagnosis:"),
+    }
+    println!("  {} lines scanned", analysis.lines);
+
+    if analysis.findings.is_empty() {
+        println!("  No known failure signatures found");
+        return;
+    }
+
+    for finding in &analysis.findings {
+        println!(
+            "  [{}] {} ({} occurrence{}, first at line {})",
+            finding.id,
+            finding.description,
+            finding.occurrences,
+            if finding.occurrences == 1 { "" } else { "s" },
+            finding.first_line
+        );
+        println!("    > {}", finding.sample);
+        println!("    hint: {}", finding.hint);
+    }
+}
+
+/// Handle the `logs` command
+pub fn handle_logs(args: LogsArgs) -> Result<()> {
+    match args.command {
+        LogsCommand::Analyze { path, format } => {
+            let analysis = analyze_file(Path::new(&path))?;
+            match format {
+                OutputFormat::Text => print_summary(&analysis),
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&analysis)?),
+                OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&analysis)?),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_missing_d3d_dll() {
+        let log = "0024:err:module:import_dll Library d3d12.dll (which is needed by L\"game.exe\") not found\n";
+        let analysis = analyze(log);
+        assert_eq!(analysis.findings.len(), 1);
+        assert_eq!(analysis.findings[0].id, "missing-d3d-dll");
+        assert_eq!(analysis.findings[0].first_line, 1);
+    }
+
+    #[test]
+    fn test_counts_repeated_signatures() {
+        let log = "info\nerr: VK_ERROR_DEVICE_LOST\nerr: VK_ERROR_DEVICE_LOST\n";
+        let analysis = analyze(log);
+        assert_eq!(analysis.lines, 3);
+        assert_eq!(analysis.findings[0].id, "device-lost");
+        assert_eq!(analysis.findings[0].occurrences, 2);
+        assert_eq!(analysis.findings[0].first_line, 2);
+    }
+
+    #[test]
+    fn test_proton_log_dir_prefers_launch_env() {
+        let env_vars =
+            HashMap::from([("PROTON_LOG_DIR".to_string(), "/tmp/proton-logs".to_string())]);
+        assert_eq!(
+            proton_log_path("1245620", &env_vars),
+            Some(PathBuf::from("/tmp/proton-logs/steam-1245620.log"))
+        );
+    }
+
+    #[test]
+    fn test_clean_log() {
+        let analysis = analyze("0024:trace:loaddll:build_module Loaded L\"d3d12.dll\"\n");
+        assert!(analysis.findings.is_empty());
+    }
+}
@@ -1,44 +1,133 @@
+mod alias;
+mod anticheat;
 mod cache;
 mod cli;
 mod config;
+mod daemon;
 mod detection;
+mod disk_usage;
+mod dxvk;
+mod dxvk_hud;
+mod env_layers;
+mod errors;
+mod export;
 mod ffi;
+mod fixes;
+mod format;
 mod gamemode;
 mod games;
+mod gl_tuning;
+mod hooks;
+mod i18n;
+mod input;
+mod launch_args;
+mod libs;
+mod logs;
 mod mangohud;
+mod manifest;
+mod media;
+mod memory;
+mod modloaders;
+mod notify;
+mod nvapi;
+mod paths;
+mod plan_cache;
 mod presets;
 mod profile;
+mod query;
+mod reporting;
 mod runner;
+mod running;
+mod runtimes;
+mod saves;
+mod self_update;
+mod session;
+mod status;
 mod steam;
+mod stream;
+mod streaming;
+mod targets;
+mod telemetry;
+mod template;
+mod term;
+mod tracking;
+mod triage;
+mod umu;
+mod undo;
+mod vram;
+mod wine_sync;
+mod wine_wayland;
+mod wrappers;
+mod xid;
+mod yaml_edit;
+
+use std::process::ExitCode;
 
 use anyhow::Result;
 use clap::Parser;
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
     env_logger::init();
 
-    let cli = cli::Cli::parse();
-    let config_manager = config::ConfigManager::new()?;
+    // Needed before parsing so usage errors can be reported as JSON too
+    let raw_json_errors = std::env::args_os()
+        .take_while(|arg| arg != "--")
+        .any(|arg| arg == "--json-errors");
+    let args = match alias::expand_args(std::env::args_os().collect()) {
+        Ok(args) => args,
+        Err(err) => return errors::report(&err, raw_json_errors),
+    };
+    let cli = match cli::Cli::try_parse_from(args) {
+        Ok(cli) => cli,
+        // --help and --version
+        Err(e) if !e.use_stderr() => {
+            let _ = e.print();
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => return errors::report_usage(&e, raw_json_errors),
+    };
+    let json_errors = cli.json_errors;
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => errors::report(&err, json_errors),
+    }
+}
+
+fn run(cli: cli::Cli) -> Result<()> {
+    term::init(cli.color);
+    let config_manager = match config::portable_root(cli.portable) {
+        Some(root) => {
+            cache::set_cache_base(root.join("cache"));
+            config::ConfigManager::portable(&root)
+        }
+        None => config::ConfigManager::new()?,
+    };
+    i18n::init(&config_manager.paths().user_config_dir);
     let mut config = config_manager.load()?;
+    config.select_context(cli.context.as_deref())?;
+    let mut config = config::TrackedConfig::new(config);
 
     match cli.command {
         cli::Commands::Run(args) => {
-            runner::handle_run(args, &config_manager, &mut config)?;
+            runner::handle_run(args, &config_manager, &config)?;
+        }
+        cli::Commands::Wrap(args) => {
+            runner::handle_wrap(args, &config_manager, &config)?;
         }
         cli::Commands::Prepare(args) => {
-            runner::handle_prepare(args, &config_manager, &mut config)?;
+            runner::handle_prepare(args, &config_manager, &config)?;
         }
         cli::Commands::Games(args) => {
-            games::handle_games(args, &config_manager, &mut config)?;
+            games::handle_games(args, &config_manager, &config)?;
         }
         cli::Commands::Steam(args) => {
-            steam::handle_steam(args, &config_manager, &mut config)?;
+            steam::handle_steam(args, &config_manager, &config)?;
         }
         cli::Commands::Detect(args) => {
-            detection::handle_detect(args, &config_manager, &mut config)?;
+            detection::handle_detect(args, &config_manager, &config)?;
         }
         cli::Commands::Profile(args) => {
-            profile::handle_profile(args, &config_manager, &mut config)?;
+            profile::handle_profile(args, &config_manager, &config)?;
         }
         cli::Commands::Preset(args) => {
             handle_preset(args, &config_manager)?;
@@ -52,9 +141,60 @@ fn main() -> Result<()> {
         cli::Commands::Config(args) => {
             config::handle_config(args.command, &config_manager, &mut config)?;
         }
+        cli::Commands::Alias(args) => {
+            alias::handle_alias(args.command, &mut config)?;
+        }
+        cli::Commands::Undo(args) => {
+            undo::handle_undo(args, &config_manager)?;
+        }
+        cli::Commands::Status(args) => {
+            status::handle_status(args, &config_manager, &mut config)?;
+        }
+        cli::Commands::Daemon(args) => {
+            daemon::handle_daemon(args, &config_manager, &config)?;
+        }
+        cli::Commands::Dxvk(args) => {
+            dxvk::handle_dxvk(args, &config_manager, &config)?;
+        }
+        cli::Commands::Nvapi(args) => {
+            nvapi::handle_nvapi(args, &config_manager, &config)?;
+        }
+        cli::Commands::Cache(args) => {
+            cache::handle_cache(args, &config_manager, &config)?;
+        }
+        cli::Commands::SelfUpdate(args) => {
+            self_update::handle_self_update(args, &config_manager)?;
+        }
+        cli::Commands::Libs(args) => {
+            libs::handle_libs(args, &config_manager)?;
+        }
+        cli::Commands::Report(args) => {
+            triage::handle_report(args, &config_manager, &config)?;
+        }
+        cli::Commands::Logs(args) => {
+            logs::handle_logs(args)?;
+        }
+        cli::Commands::Media(args) => {
+            media::handle_media(args, &config_manager, &config)?;
+        }
+        cli::Commands::Saves(args) => {
+            saves::handle_saves(args, &config_manager, &config)?;
+        }
+        cli::Commands::Ps(args) => {
+            running::handle_ps(args, &config_manager)?;
+        }
+        cli::Commands::Stop(args) => {
+            running::handle_stop(args, &config_manager, &config)?;
+        }
+        cli::Commands::Runtimes(args) => {
+            runtimes::handle_runtimes(args, &config_manager, &config)?;
+        }
     }
 
-    config_manager.save(&config)?;
+    // Read-only commands leave the config file (and its formatting) untouched
+    if config.is_dirty() {
+        config_manager.save(&config)?;
+    }
     Ok(())
 }
 
@@ -85,7 +225,10 @@ fn handle_preset(args: cli::PresetArgs, manager: &config::ConfigManager) -> Resu
         cli::PresetCommand::Recommend => {
             let preset = presets::recommended_preset();
             let is_deck = presets::is_steam_deck();
-            println!("Detected: {}", if is_deck { "Steam Deck" } else { "Desktop" });
+            println!(
+                "Detected: {}",
+                if is_deck { "Steam Deck" } else { "Desktop" }
+            );
             println!("Recommended preset: {}", preset.name());
             println!("Description: {}", preset.description());
         }
@@ -97,15 +240,24 @@ fn handle_mangohud(args: cli::MangohudArgs) -> Result<()> {
     match args.command {
         cli::MangohudCommand::Status => {
             let installed = mangohud::is_installed();
-            println!("MangoHud installed: {}", if installed { "Yes" } else { "No" });
+            println!(
+                "MangoHud installed: {}",
+                if installed { "Yes" } else { "No" }
+            );
             if let Some(path) = mangohud::global_config_path() {
                 let exists = path.exists();
-                println!("Global config: {} ({})",
+                println!(
+                    "Global config: {} ({})",
                     path.display(),
-                    if exists { "exists" } else { "not found" });
+                    if exists { "exists" } else { "not found" }
+                );
             }
         }
-        cli::MangohudCommand::Generate { preset, output, game } => {
+        cli::MangohudCommand::Generate {
+            preset,
+            output,
+            game,
+        } => {
             let mh_preset = match preset.to_lowercase().as_str() {
                 "minimal" => mangohud::MangoHudPreset::Minimal,
                 "compact" => mangohud::MangoHudPreset::Compact,
@@ -136,7 +288,7 @@ fn handle_mangohud(args: cli::MangohudArgs) -> Result<()> {
             let mh_preset = match preset.to_lowercase().as_str() {
                 "minimal" => mangohud::MangoHudPreset::Minimal,
                 "compact" => mangohud::MangoHudPreset::Compact,
-                "standard" | _ => mangohud::MangoHudPreset::Standard,
+                _ => mangohud::MangoHudPreset::Standard,
             };
             let config = mangohud::MangoHudConfig::from_preset(mh_preset);
             for (key, value) in mangohud::env_vars(&config) {
@@ -151,12 +303,18 @@ fn handle_gamemode(args: cli::GamemodeArgs) -> Result<()> {
     match args.command {
         cli::GamemodeCommand::Status => {
             let installed = gamemode::is_installed();
-            println!("GameMode installed: {}", if installed { "Yes" } else { "No" });
+            println!(
+                "GameMode installed: {}",
+                if installed { "Yes" } else { "No" }
+            );
 
             if installed {
                 match gamemode::status() {
                     Ok(status) => {
-                        println!("Daemon running: {}", if status.running { "Yes" } else { "No" });
+                        println!(
+                            "Daemon running: {}",
+                            if status.running { "Yes" } else { "No" }
+                        );
                         if status.running {
                             println!("Active clients: {}", status.client_count);
                         }
@@ -169,12 +327,17 @@ fn handle_gamemode(args: cli::GamemodeArgs) -> Result<()> {
 
             if let Some(path) = gamemode::config_path() {
                 let exists = path.exists();
-                println!("Config: {} ({})",
+                println!(
+                    "Config: {} ({})",
                     path.display(),
-                    if exists { "exists" } else { "not found" });
+                    if exists { "exists" } else { "not found" }
+                );
             }
         }
-        cli::GamemodeCommand::Generate { config_type, output } => {
+        cli::GamemodeCommand::Generate {
+            config_type,
+            output,
+        } => {
             let config = match config_type.to_lowercase().as_str() {
                 "default" => gamemode::GameModeConfig::default(),
                 "high-performance" | "performance" => gamemode::GameModeConfig::high_performance(),
@@ -0,0 +1,385 @@
+//! Launch reproducibility manifests
+//!
+//! `nvproton run --manifest` records everything that shaped a launch (game
+//! executable fingerprint, Proton and driver versions, the full environment,
+//! the resolved profile and the state of the shader caches) next to the
+//! session history, as `sessions/<game_id>-<started_at>.manifest.yaml`.
+//! `nvproton games diff-session <game_id> <a> <b>` compares two of them.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use sha2::{Digest, Sha256};
+
+use crate::cache::{self, CachePaths, CacheType};
+use crate::cli::GamesDiffSessionArgs;
+use crate::config::{ConfigManager, ConfigPaths};
+use crate::detection::fingerprint;
+use crate::detection::{DetectedGame, GameDatabase};
+use crate::runner::RunContext;
+use crate::session::{Session, SessionHistory};
+use crate::wine_sync::SyncMechanism;
+
+/// Caches whose state is recorded
+const CACHE_TYPES: &[CacheType] = &[
+    CacheType::Dxvk,
+    CacheType::Vkd3d,
+    CacheType::NvidiaGl,
+    CacheType::Mesa,
+];
+
+/// Everything that shaped one launch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchManifest {
+    pub game_id: String,
+    pub game_name: String,
+    /// Unix timestamp of the session this manifest belongs to
+    pub started_at: u64,
+    pub nvproton_version: String,
+    pub executable: Option<PathBuf>,
+    /// SHA-256 of the game executable
+    pub fingerprint: Option<String>,
+    pub gpu_name: Option<String>,
+    pub driver_version: Option<String>,
+    pub proton_version: Option<String>,
+    pub launch_command: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub profile: Option<String>,
+    /// SHA-256 of the resolved profile settings
+    pub profile_hash: Option<String>,
+    /// Resolved profile settings as dotted keys
+    #[serde(default)]
+    pub profile_settings: BTreeMap<String, String>,
+    #[serde(default)]
+    pub caches: BTreeMap<String, CacheState>,
+    /// Wine synchronization selected with `run --sync`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync: Option<SyncMechanism>,
+}
+
+/// Location and size of a shader cache when the game was launched
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheState {
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+impl LaunchManifest {
+    /// Capture the manifest for a launch about to start
+    pub fn capture(
+        ctx: &RunContext,
+        game: &DetectedGame,
+        session: &Session,
+        launch_command: &[String],
+        env_vars: &HashMap<String, String>,
+    ) -> Self {
+        // The stored fingerprint is from the last scan; a game update since
+        // then would make the manifest describe the wrong build
+        let fingerprint = game
+            .executable
+            .as_deref()
+            .and_then(|exe| fingerprint::fingerprint_file(exe).ok())
+            .or_else(|| game.fingerprint.clone());
+        let resolved = session
+            .profile
+            .as_deref()
+            .and_then(|name| ctx.profile_manager.resolve(name).ok());
+        let profile_settings = resolved
+            .as_ref()
+            .map(|r| flatten(&r.settings))
+            .unwrap_or_default();
+        let profile_hash = resolved
+            .as_ref()
+            .and_then(|r| serde_yaml::to_string(&r.settings).ok())
+            .map(|yaml| hex::encode(Sha256::digest(yaml.as_bytes())));
+
+        let cache_paths = CachePaths::new();
+        let caches = CACHE_TYPES
+            .iter()
+            .map(|cache_type| {
+                let path = env_vars
+                    .get(cache_type.env_var())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| cache_paths.find_for_game(*cache_type, &game.game_id()));
+                let bytes = cache::directory_size(&path);
+                (cache_type.name().to_string(), CacheState { path, bytes })
+            })
+            .collect();
+
+        Self {
+            game_id: game.game_id().to_string(),
+            game_name: game.name.clone(),
+            started_at: session.started_at,
+            nvproton_version: env!("CARGO_PKG_VERSION").to_string(),
+            executable: game.executable.clone(),
+            fingerprint,
+            gpu_name: ctx.vulkan_caps.as_ref().map(|c| c.gpu_name.clone()),
+            driver_version: ctx.vulkan_caps.as_ref().map(|c| c.driver_version.clone()),
+            proton_version: ctx.proton_nv.as_ref().map(|p| p.version.clone()),
+            launch_command: launch_command.to_vec(),
+            env: env_vars.clone().into_iter().collect(),
+            profile: session.profile.clone(),
+            profile_hash,
+            profile_settings,
+            caches,
+            sync: None,
+        }
+    }
+
+    pub fn save(&self, paths: &ConfigPaths) -> Result<PathBuf> {
+        let path = SessionHistory::manifest_path(paths, &self.game_id, self.started_at);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create directory {:?}", parent))?;
+        }
+        fs::write(&path, serde_yaml::to_string(self)?)
+            .with_context(|| format!("failed to write manifest {:?}", path))?;
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read manifest {:?}", path))?;
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("failed to parse manifest {:?}", path))
+    }
+}
+
+/// One difference between two manifests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// Compare two manifests field by field
+pub fn diff(a: &LaunchManifest, b: &LaunchManifest) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let mut compare = |field: &str, before: Option<String>, after: Option<String>| {
+        if before != after {
+            changes.push(Change {
+                field: field.to_string(),
+                before,
+                after,
+            });
+        }
+    };
+
+    compare(
+        "nvproton",
+        Some(a.nvproton_version.clone()),
+        Some(b.nvproton_version.clone()),
+    );
+    compare("executable", display(&a.executable), display(&b.executable));
+    compare("fingerprint", a.fingerprint.clone(), b.fingerprint.clone());
+    compare("gpu", a.gpu_name.clone(), b.gpu_name.clone());
+    compare("driver", a.driver_version.clone(), b.driver_version.clone());
+    compare("proton", a.proton_version.clone(), b.proton_version.clone());
+    compare(
+        "command",
+        Some(a.launch_command.join(" ")),
+        Some(b.launch_command.join(" ")),
+    );
+    compare("profile", a.profile.clone(), b.profile.clone());
+    compare(
+        "sync",
+        a.sync.map(|s| s.to_string()),
+        b.sync.map(|s| s.to_string()),
+    );
+    compare(
+        "profile hash",
+        a.profile_hash.clone(),
+        b.profile_hash.clone(),
+    );
+    for (key, before, after) in map_changes(&a.profile_settings, &b.profile_settings) {
+        compare(&format!("profile.{}", key), before, after);
+    }
+    for (key, before, after) in map_changes(&a.env, &b.env) {
+        compare(&format!("env {}", key), before, after);
+    }
+
+    let cache_summary = |state: Option<&CacheState>| {
+        state.map(|s| format!("{} ({})", s.path.display(), cache::format_bytes(s.bytes)))
+    };
+    let cache_names: BTreeSet<&String> = a.caches.keys().chain(b.caches.keys()).collect();
+    for name in cache_names {
+        compare(
+            &format!("cache {}", name),
+            cache_summary(a.caches.get(name)),
+            cache_summary(b.caches.get(name)),
+        );
+    }
+    changes
+}
+
+fn display(path: &Option<PathBuf>) -> Option<String> {
+    path.as_ref().map(|p| p.display().to_string())
+}
+
+/// Keys that differ between two maps with their values on each side
+fn map_changes(
+    a: &BTreeMap<String, String>,
+    b: &BTreeMap<String, String>,
+) -> Vec<(String, Option<String>, Option<String>)> {
+    let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    keys.into_iter()
+        .filter(|key| a.get(*key) != b.get(*key))
+        .map(|key| (key.clone(), a.get(key).cloned(), b.get(key).cloned()))
+        .collect()
+}
+
+/// Flatten profile settings into dotted keys
+fn flatten(settings: &Value) -> BTreeMap<String, String> {
+    fn walk(value: &Value, prefix: &str, out: &mut BTreeMap<String, String>) {
+        match value {
+            Value::Mapping(map) => {
+                for (key, child) in map {
+                    let Some(key) = key.as_str() else { continue };
+                    let path = if prefix.is_empty() {
+                        key.to_string()
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    walk(child, &path, out);
+                }
+            }
+            Value::String(s) if !prefix.is_empty() => {
+                out.insert(prefix.to_string(), s.clone());
+            }
+            other if !prefix.is_empty() => {
+                let text = serde_yaml::to_string(other).unwrap_or_default();
+                out.insert(prefix.to_string(), text.trim().to_string());
+            }
+            _ => {}
+        }
+    }
+    let mut out = BTreeMap::new();
+    walk(settings, "", &mut out);
+    out
+}
+
+/// Resolve a session reference: `last`, an index (1 = most recent) or a start timestamp
+fn find_session<'a>(history: &'a SessionHistory, reference: &str) -> Result<&'a Session> {
+    let sessions = &history.sessions;
+    let found = if reference == "last" {
+        sessions.last()
+    } else {
+        let Ok(value) = reference.parse::<u64>() else {
+            bail!(
+                "invalid session '{}' (use 'last', an index from 'games session' or a timestamp)",
+                reference
+            );
+        };
+        match usize::try_from(value) {
+            Ok(index) if (1..=sessions.len()).contains(&index) => {
+                sessions.get(sessions.len() - index)
+            }
+            _ => sessions.iter().find(|s| s.started_at == value),
+        }
+    };
+    found.with_context(|| format!("session '{}' not found", reference))
+}
+
+/// Handle `games diff-session`
+pub fn handle_diff_session(args: GamesDiffSessionArgs, manager: &ConfigManager) -> Result<()> {
+    let paths = manager.paths();
+    let db = GameDatabase::load_or_default(paths)?;
+    let game_id = db.resolve_game(&args.game_id)?.game_id();
+    let history = SessionHistory::for_game(paths, &db, &game_id)?;
+    let mut manifests = Vec::new();
+    for reference in [&args.a, &args.b] {
+        let session = find_session(&history, reference)?;
+        let path = SessionHistory::manifest_path(paths, &game_id.to_string(), session.started_at);
+        if !path.exists() {
+            bail!(
+                "session '{}' has no manifest (launch with 'nvproton run --manifest')",
+                reference
+            );
+        }
+        manifests.push(LaunchManifest::load(&path)?);
+    }
+
+    let changes = diff(&manifests[0], &manifests[1]);
+    if changes.is_empty() {
+        println!("No differences between sessions {} and {}", args.a, args.b);
+        return Ok(());
+    }
+    println!(
+        "{} differences between sessions {} and {}:",
+        changes.len(),
+        args.a,
+        args.b
+    );
+    for change in changes {
+        println!("  {}", change.field);
+        println!("    - {}", change.before.as_deref().unwrap_or("(unset)"));
+        println!("    + {}", change.after.as_deref().unwrap_or("(unset)"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(driver: &str, env: &[(&str, &str)]) -> LaunchManifest {
+        LaunchManifest {
+            game_id: "1245620".into(),
+            game_name: "Elden Ring".into(),
+            started_at: 0,
+            nvproton_version: "0.1.0".into(),
+            executable: None,
+            fingerprint: Some("abc".into()),
+            gpu_name: None,
+            driver_version: Some(driver.into()),
+            proton_version: None,
+            launch_command: vec!["proton".into(), "run".into()],
+            env: env
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            profile: None,
+            profile_hash: None,
+            profile_settings: BTreeMap::new(),
+            caches: BTreeMap::new(),
+            sync: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_changed_fields() {
+        let a = manifest("595.45", &[("DXVK_HUD", "fps"), ("PROTON_LOG", "1")]);
+        let b = manifest("595.58", &[("DXVK_HUD", "full")]);
+        let fields: Vec<String> = diff(&a, &b).into_iter().map(|c| c.field).collect();
+        assert_eq!(fields, vec!["driver", "env DXVK_HUD", "env PROTON_LOG"]);
+        assert!(diff(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn test_find_session_by_index_and_timestamp() {
+        let mut history = SessionHistory::default();
+        for started_at in [1_700_000_000, 1_700_000_100] {
+            let mut session = Session::begin(None);
+            session.started_at = started_at;
+            history.sessions.push(session);
+        }
+        assert_eq!(
+            find_session(&history, "1").unwrap().started_at,
+            1_700_000_100
+        );
+        assert_eq!(
+            find_session(&history, "2").unwrap().started_at,
+            1_700_000_000
+        );
+        assert_eq!(
+            find_session(&history, "1700000000").unwrap().started_at,
+            1_700_000_000
+        );
+        assert!(find_session(&history, "3").is_err());
+    }
+}
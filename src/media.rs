@@ -0,0 +1,300 @@
+//! Per-game screenshot and replay directories
+//!
+//! With `media.enabled`, `run` gives each game a capture directory,
+//! `<media.dir>/<game name>/` (by default under `~/Videos/nvproton`), and
+//! exports it to the game and its wrappers as `NVPROTON_MEDIA_DIR`. Clips
+//! that gpu-screen-recorder, OBS or Steam save into one of the
+//! `media.collect_from` directories while the game runs are moved into the
+//! game's directory when it exits.
+//!
+//! `nvproton media list <id>` shows a game's captures grouped by the session
+//! they were taken in, using the session history.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use walkdir::WalkDir;
+
+use crate::cache::format_bytes;
+use crate::cli::{MediaArgs, MediaCommand};
+use crate::config::{ConfigManager, MediaConfig, NvConfig};
+use crate::detection::{DetectedGame, GameDatabase};
+use crate::games::{format_age, format_duration};
+use crate::hooks::LaunchHook;
+use crate::paths::expand_home;
+use crate::session::{Session, SessionHistory};
+
+/// Environment variable holding the game's capture directory
+pub const MEDIA_DIR_ENV: &str = "NVPROTON_MEDIA_DIR";
+
+/// Captures saved this long after a session ended still belong to it
+/// (replays are often saved right after quitting)
+const SESSION_GRACE_SECS: u64 = 120;
+
+/// Base directory holding the per-game directories
+pub fn base_dir(config: &MediaConfig) -> PathBuf {
+    match &config.dir {
+        Some(dir) => expand_home(dir),
+        None => dirs::video_dir()
+            .or_else(|| dirs::home_dir().map(|home| home.join("Videos")))
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("nvproton"),
+    }
+}
+
+/// Capture directory of a game
+pub fn game_dir(config: &MediaConfig, game: &DetectedGame) -> PathBuf {
+    base_dir(config).join(folder_name(game))
+}
+
+/// Directory name for a game: its name without path separators
+fn folder_name(game: &DetectedGame) -> String {
+    let name: String = game
+        .name
+        .chars()
+        .map(|c| if c == '/' || c == '\0' { '-' } else { c })
+        .collect();
+    let name = name.trim().trim_start_matches('.');
+    if name.is_empty() {
+        game.id.clone()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Hook that prepares the game's directory and collects new captures into it
+pub fn hook(config: &MediaConfig, game: &DetectedGame) -> Box<dyn LaunchHook> {
+    Box::new(CollectCaptures {
+        target: game_dir(config, game),
+        sources: config.collect_from.iter().map(expand_home).collect(),
+        started: None,
+    })
+}
+
+struct CollectCaptures {
+    target: PathBuf,
+    sources: Vec<PathBuf>,
+    started: Option<SystemTime>,
+}
+
+impl LaunchHook for CollectCaptures {
+    fn name(&self) -> &str {
+        "media"
+    }
+
+    fn on_start(&mut self) -> Result<()> {
+        fs::create_dir_all(&self.target)
+            .with_context(|| format!("failed to create directory {:?}", self.target))?;
+        self.started = Some(SystemTime::now());
+        Ok(())
+    }
+
+    fn on_exit(&mut self) -> Result<()> {
+        let Some(started) = self.started.take() else {
+            return Ok(());
+        };
+        let mut moved = 0;
+        for source in &self.sources {
+            for file in new_files(source, started) {
+                let Some(name) = file.file_name() else {
+                    continue;
+                };
+                let target = self.target.join(name);
+                if target.exists() {
+                    continue;
+                }
+                move_file(&file, &target)
+                    .with_context(|| format!("failed to move {:?} to {:?}", file, target))?;
+                moved += 1;
+            }
+        }
+        if moved > 0 {
+            println!("  Moved {} capture(s) to {}", moved, self.target.display());
+        }
+        Ok(())
+    }
+}
+
+/// Files directly in `dir` modified since `since`
+fn new_files(dir: &Path, since: SystemTime) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .metadata()
+                .is_ok_and(|m| m.is_file() && m.modified().is_ok_and(|t| t >= since))
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+/// Rename, falling back to copy and remove across filesystems
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            fs::copy(from, to)?;
+            fs::remove_file(from)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// A file in a game's capture directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Capture {
+    path: PathBuf,
+    /// Unix timestamp of the last modification
+    modified: u64,
+    size: u64,
+}
+
+fn list_captures(dir: &Path) -> Vec<Capture> {
+    let mut captures: Vec<Capture> = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+            Some(Capture {
+                path: entry.into_path(),
+                modified: modified.as_secs(),
+                size: metadata.len(),
+            })
+        })
+        .collect();
+    captures.sort_by_key(|c| c.modified);
+    captures
+}
+
+/// Assign captures to the session they were taken in; the rest come last
+fn group_by_session<'a>(
+    captures: &'a [Capture],
+    sessions: &'a [Session],
+) -> (Vec<(&'a Session, Vec<&'a Capture>)>, Vec<&'a Capture>) {
+    let mut groups: Vec<(&Session, Vec<&Capture>)> = Vec::new();
+    let mut other = Vec::new();
+    for capture in captures {
+        let session = sessions.iter().find(|s| {
+            let end = s.started_at + s.runtime_secs + SESSION_GRACE_SECS;
+            (s.started_at..=end).contains(&capture.modified)
+        });
+        match session {
+            Some(session) => match groups
+                .iter_mut()
+                .find(|(s, _)| s.started_at == session.started_at)
+            {
+                Some((_, files)) => files.push(capture),
+                None => groups.push((session, vec![capture])),
+            },
+            None => other.push(capture),
+        }
+    }
+    (groups, other)
+}
+
+pub fn handle_media(args: MediaArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
+    match args.command {
+        MediaCommand::List(list_args) => {
+            let db = GameDatabase::load_or_default(manager.paths())?;
+            let game = db.resolve_game(&list_args.game_id)?;
+            let dir = game_dir(&config.media, &game);
+            let captures = list_captures(&dir);
+            if captures.is_empty() {
+                println!("No captures in {}", dir.display());
+                return Ok(());
+            }
+
+            let history = SessionHistory::for_game(manager.paths(), &db, &game.game_id())?;
+            let (groups, other) = group_by_session(&captures, &history.sessions);
+            println!("{} ({})", game.name, dir.display());
+            for (session, files) in groups.iter().rev() {
+                println!(
+                    "\nSession {} ({})",
+                    format_age(session.started_at),
+                    format_duration(session.runtime_secs)
+                );
+                print_captures(&dir, files);
+            }
+            if !other.is_empty() {
+                println!("\nOutside recorded sessions");
+                print_captures(&dir, &other);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_captures(dir: &Path, captures: &[&Capture]) {
+    for capture in captures {
+        let name = capture.path.strip_prefix(dir).unwrap_or(&capture.path);
+        println!(
+            "  {:<50} {:>10}",
+            name.display(),
+            format_bytes(capture.size)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_and_group_captures() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("Videos");
+        fs::create_dir_all(&source).unwrap();
+        let old = source.join("old.mp4");
+        fs::write(&old, "old").unwrap();
+        let an_hour_ago = SystemTime::now() - std::time::Duration::from_secs(3600);
+        fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(an_hour_ago)
+            .unwrap();
+        let mut hook = CollectCaptures {
+            target: dir.path().join("nvproton/Hades"),
+            sources: vec![source.clone()],
+            started: None,
+        };
+        hook.on_start().unwrap();
+        // File timestamps come from a coarser clock than SystemTime::now
+        hook.started = Some(SystemTime::now() - std::time::Duration::from_secs(1));
+        fs::write(source.join("Replay_2026-10-18.mp4"), "clip").unwrap();
+        hook.on_exit().unwrap();
+        assert!(old.exists());
+        assert!(
+            dir.path()
+                .join("nvproton/Hades/Replay_2026-10-18.mp4")
+                .exists()
+        );
+
+        let capture = |path: &str, modified| Capture {
+            path: PathBuf::from(path),
+            modified,
+            size: 1,
+        };
+        let captures = [
+            capture("a.mp4", 1000),
+            capture("b.png", 1100),
+            capture("c.mp4", 5000),
+        ];
+        let mut session = Session::begin(None);
+        session.started_at = 900;
+        session.runtime_secs = 100;
+        let sessions = [session];
+        let (groups, other) = group_by_session(&captures, &sessions);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].1.len(), 2);
+        assert_eq!(other, vec![&captures[2]]);
+    }
+}
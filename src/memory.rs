@@ -0,0 +1,235 @@
+//! System memory and swap advisor
+//!
+//! Games under Proton map a lot of memory: titles like DayZ, Hogwarts Legacy
+//! or Counter-Strike 2 (and many EAC-protected games) crash at startup when
+//! `vm.max_map_count` is below 1048576, and a system without swap or ZRAM
+//! has the OOM killer end the game once RAM runs out. `status` reports the
+//! memory situation and offers `--apply-sysctl` to write a sysctl drop-in;
+//! `run` warns before launching when available RAM is low or the map count
+//! is too small.
+
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::MetadataExt;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+/// `vm.max_map_count` needed by memory-hungry Proton games (SteamOS and
+/// Fedora ship at least this)
+pub const RECOMMENDED_MAX_MAP_COUNT: u64 = 1_048_576;
+
+/// Drop-in written by `status --apply-sysctl`
+pub const SYSCTL_DROP_IN: &str = "/etc/sysctl.d/80-nvproton.conf";
+
+/// Memory, swap and kernel limits relevant to games
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoryStatus {
+    pub total_bytes: u64,
+    /// RAM that can be used without swapping (`MemAvailable`)
+    pub available_bytes: u64,
+    pub swap_total_bytes: u64,
+    pub swap_free_bytes: u64,
+    /// A compressed swap device in RAM is active
+    pub zram: bool,
+    pub max_map_count: Option<u64>,
+}
+
+/// Something worth fixing in the memory setup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryAdvice {
+    LowAvailable {
+        available_bytes: u64,
+        total_bytes: u64,
+    },
+    NoSwap,
+    NoZram,
+    MaxMapCountLow {
+        current: u64,
+    },
+}
+
+impl MemoryAdvice {
+    /// Whether the problem is worth a warning right before a launch
+    pub fn affects_launch(&self) -> bool {
+        matches!(
+            self,
+            MemoryAdvice::LowAvailable { .. } | MemoryAdvice::MaxMapCountLow { .. }
+        )
+    }
+}
+
+impl MemoryStatus {
+    pub fn detect() -> Self {
+        let mut status = fs::read_to_string("/proc/meminfo")
+            .map(|text| parse_meminfo(&text))
+            .unwrap_or_default();
+        status.zram = fs::read_to_string("/proc/swaps")
+            .is_ok_and(|text| swap_devices(&text).any(|dev| dev.starts_with("/dev/zram")));
+        status.max_map_count = read_max_map_count();
+        status
+    }
+
+    /// Problems found, given the available RAM below which to warn
+    pub fn advice(&self, min_available_mb: u64) -> Vec<MemoryAdvice> {
+        let mut advice = Vec::new();
+        if self.total_bytes > 0 && self.available_bytes < min_available_mb * 1024 * 1024 {
+            advice.push(MemoryAdvice::LowAvailable {
+                available_bytes: self.available_bytes,
+                total_bytes: self.total_bytes,
+            });
+        }
+        if self.swap_total_bytes == 0 {
+            advice.push(MemoryAdvice::NoSwap);
+        } else if !self.zram {
+            advice.push(MemoryAdvice::NoZram);
+        }
+        if let Some(current) = self.max_map_count
+            && current < RECOMMENDED_MAX_MAP_COUNT
+        {
+            advice.push(MemoryAdvice::MaxMapCountLow { current });
+        }
+        advice
+    }
+}
+
+/// Totals from `/proc/meminfo`, whose values are in kB
+fn parse_meminfo(text: &str) -> MemoryStatus {
+    let mut status = MemoryStatus::default();
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let Some(kb) = value
+            .split_whitespace()
+            .next()
+            .and_then(|v| v.parse::<u64>().ok())
+        else {
+            continue;
+        };
+        let bytes = kb * 1024;
+        match key {
+            "MemTotal" => status.total_bytes = bytes,
+            "MemAvailable" => status.available_bytes = bytes,
+            "SwapTotal" => status.swap_total_bytes = bytes,
+            "SwapFree" => status.swap_free_bytes = bytes,
+            _ => {}
+        }
+    }
+    status
+}
+
+/// Device or file names from `/proc/swaps`, skipping the header
+fn swap_devices(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().next())
+}
+
+/// Current `vm.max_map_count`
+pub fn read_max_map_count() -> Option<u64> {
+    fs::read_to_string("/proc/sys/vm/max_map_count")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Whether the drop-in would raise `vm.max_map_count` (never lower it)
+pub fn sysctl_needed(current: Option<u64>) -> bool {
+    current.is_none_or(|value| value < RECOMMENDED_MAX_MAP_COUNT)
+}
+
+/// Contents of the sysctl drop-in
+pub fn sysctl_drop_in() -> String {
+    format!(
+        "# Written by nvproton: memory-hungry Proton games need more memory maps\n\
+         vm.max_map_count = {}\n",
+        RECOMMENDED_MAX_MAP_COUNT
+    )
+}
+
+/// Write the sysctl drop-in and load it, through sudo unless running as root
+///
+/// Returns false without writing anything when `vm.max_map_count` is
+/// already at least the recommended value.
+pub fn apply_sysctl() -> Result<bool> {
+    if !sysctl_needed(read_max_map_count()) {
+        return Ok(false);
+    }
+    let root = fs::metadata("/proc/self").is_ok_and(|m| m.uid() == 0);
+    let privileged = |program: &str| {
+        if root {
+            Command::new(program)
+        } else {
+            let mut cmd = Command::new("sudo");
+            cmd.arg(program);
+            cmd
+        }
+    };
+
+    let mut tee = privileged("tee")
+        .arg(SYSCTL_DROP_IN)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .context("failed to run tee")?;
+    tee.stdin
+        .take()
+        .context("tee has no stdin")?
+        .write_all(sysctl_drop_in().as_bytes())?;
+    if !tee.wait()?.success() {
+        bail!("failed to write {}", SYSCTL_DROP_IN);
+    }
+
+    let status = privileged("sysctl")
+        .args(["-p", SYSCTL_DROP_IN])
+        .status()
+        .context("failed to run sysctl")?;
+    if !status.success() {
+        bail!("sysctl -p {} failed", SYSCTL_DROP_IN);
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_advice() {
+        let mut status = parse_meminfo(
+            "MemTotal:       16318480 kB\n\
+             MemFree:          512000 kB\n\
+             MemAvailable:    2097152 kB\n\
+             SwapTotal:       8388604 kB\n\
+             SwapFree:        8388604 kB\n",
+        );
+        assert_eq!(status.total_bytes, 16318480 * 1024);
+        assert_eq!(status.available_bytes, 2 * 1024 * 1024 * 1024);
+        status.max_map_count = Some(65530);
+
+        let swaps = "Filename\tType\tSize\tUsed\tPriority\n\
+                     /dev/zram0                              partition\t8388604\t0\t100\n";
+        status.zram = swap_devices(swaps).any(|dev| dev.starts_with("/dev/zram"));
+        assert!(status.zram);
+
+        assert_eq!(
+            status.advice(4096),
+            vec![
+                MemoryAdvice::LowAvailable {
+                    available_bytes: 2 * 1024 * 1024 * 1024,
+                    total_bytes: 16318480 * 1024
+                },
+                MemoryAdvice::MaxMapCountLow { current: 65530 }
+            ]
+        );
+
+        status.swap_total_bytes = 0;
+        status.max_map_count = Some(RECOMMENDED_MAX_MAP_COUNT);
+        assert!(!sysctl_needed(status.max_map_count));
+        assert!(!sysctl_needed(Some(2_147_483_642)));
+        assert!(sysctl_needed(Some(65530)));
+        assert_eq!(status.advice(1024), vec![MemoryAdvice::NoSwap]);
+        assert!(!MemoryAdvice::NoSwap.affects_launch());
+    }
+}
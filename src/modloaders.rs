@@ -0,0 +1,234 @@
+//! Mod loader detection
+//!
+//! Looks for mod managers and loaders installed for a game, in its install
+//! directory and Wine prefix, and for what they need under Proton:
+//! - Mod Organizer 2 and Vortex are started instead of the game, so they
+//!   become launch targets (`run --target mo2`)
+//! - BepInEx is loaded by Doorstop through a proxy DLL next to the game
+//!   executable (winhttp.dll, version.dll or winmm.dll). Wine uses its
+//!   builtin of that DLL unless told otherwise, so launches add a native
+//!   override for it. BepInEx builds for .NET Framework games need .NET
+//!   Framework 4 in the prefix, which wine-mono doesn't replace.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::detection::DetectedGame;
+use crate::targets::{LaunchTarget, TargetOrigin};
+
+/// DLLs Doorstop can be installed as, in the order it is usually shipped
+const DOORSTOP_PROXIES: &[&str] = &["winhttp.dll", "version.dll", "winmm.dll"];
+
+/// Vortex's default install location inside a prefix
+const VORTEX_EXE: &str = "drive_c/Program Files/Black Tree Gaming Ltd/Vortex/Vortex.exe";
+
+/// .NET Framework 4 in a prefix (`protontricks <appid> dotnet48`)
+const DOTNET_FRAMEWORK: &str = "drive_c/windows/Microsoft.NET/Framework/v4.0.30319/mscorlib.dll";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoaderKind {
+    ModOrganizer,
+    Vortex,
+    BepInEx,
+}
+
+impl fmt::Display for LoaderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoaderKind::ModOrganizer => write!(f, "Mod Organizer 2"),
+            LoaderKind::Vortex => write!(f, "Vortex"),
+            LoaderKind::BepInEx => write!(f, "BepInEx"),
+        }
+    }
+}
+
+/// A mod manager or loader found for a game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModLoader {
+    pub kind: LoaderKind,
+    /// The manager's executable, or the loader's directory
+    pub path: PathBuf,
+    /// Proxy DLL Wine has to load from the game directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_dll: Option<String>,
+    /// Runtime the loader needs that the prefix lacks (protontricks verb)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub missing_runtime: Option<&'static str>,
+}
+
+impl ModLoader {
+    /// Launch target for managers that start the game themselves
+    pub fn launch_target(&self) -> Option<LaunchTarget> {
+        let name = match self.kind {
+            LoaderKind::ModOrganizer => "mo2",
+            LoaderKind::Vortex => "vortex",
+            LoaderKind::BepInEx => return None,
+        };
+        Some(LaunchTarget {
+            name: name.to_string(),
+            executable: self.path.clone(),
+            arguments: Vec::new(),
+            description: Some(self.kind.to_string()),
+            origin: TargetOrigin::ModLoader,
+        })
+    }
+}
+
+/// Mod loaders installed for a game
+pub fn detect(game: &DetectedGame, prefix: Option<&Path>) -> Vec<ModLoader> {
+    let mut loaders = Vec::new();
+
+    // Mod Organizer 2 is portable: in the game directory, one level below
+    // it, or anywhere near the top of the prefix
+    let mut mo2 = Vec::new();
+    if !game.install_dir.as_os_str().is_empty() {
+        mo2.extend(find_file(&game.install_dir, "ModOrganizer.exe", 2));
+    }
+    if let Some(prefix) = prefix {
+        mo2.extend(find_file(&prefix.join("drive_c"), "ModOrganizer.exe", 3));
+    }
+    if let Some(path) = mo2.into_iter().next() {
+        loaders.push(ModLoader {
+            kind: LoaderKind::ModOrganizer,
+            path,
+            proxy_dll: None,
+            missing_runtime: None,
+        });
+    }
+
+    if let Some(prefix) = prefix
+        && prefix.join(VORTEX_EXE).is_file()
+    {
+        loaders.push(ModLoader {
+            kind: LoaderKind::Vortex,
+            path: prefix.join(VORTEX_EXE),
+            proxy_dll: None,
+            missing_runtime: None,
+        });
+    }
+
+    if let Some(bepinex) = find_bepinex(game, prefix) {
+        loaders.push(bepinex);
+    }
+    loaders
+}
+
+/// `WINEDLLOVERRIDES` entries the loaders need
+pub fn wine_dll_overrides(loaders: &[ModLoader]) -> Option<String> {
+    let entries: Vec<String> = loaders
+        .iter()
+        .filter_map(|loader| loader.proxy_dll.as_deref())
+        .map(|dll| format!("{}=n,b", dll.trim_end_matches(".dll")))
+        .collect();
+    (!entries.is_empty()).then(|| entries.join(";"))
+}
+
+/// BepInEx next to the game executable or in the install directory
+fn find_bepinex(game: &DetectedGame, prefix: Option<&Path>) -> Option<ModLoader> {
+    let exe_dir = game.executable.as_deref().and_then(Path::parent);
+    let game_dir = exe_dir
+        .into_iter()
+        .chain([game.install_dir.as_path()])
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .find(|dir| dir.join("BepInEx").join("core").is_dir())?;
+
+    // Doorstop always ships its config next to the proxy
+    let proxy_dll = game_dir.join("doorstop_config.ini").is_file().then(|| {
+        DOORSTOP_PROXIES
+            .iter()
+            .find(|dll| game_dir.join(dll).is_file())
+            .unwrap_or(&DOORSTOP_PROXIES[0])
+            .to_string()
+    });
+
+    let core = game_dir.join("BepInEx").join("core");
+    let needs_framework = core.join("BepInEx.NET.Framework.Launcher.exe").is_file();
+    let missing_runtime = prefix
+        .filter(|prefix| needs_framework && !prefix.join(DOTNET_FRAMEWORK).is_file())
+        .map(|_| "dotnet48");
+
+    Some(ModLoader {
+        kind: LoaderKind::BepInEx,
+        path: game_dir.join("BepInEx"),
+        proxy_dll,
+        missing_runtime,
+    })
+}
+
+/// First file called `name` at most `depth` directories below `dir`
+fn find_file(dir: &Path, name: &str, depth: usize) -> Option<PathBuf> {
+    WalkDir::new(dir)
+        .max_depth(depth + 1)
+        .sort_by_file_name()
+        .into_iter()
+        // The Windows directory and user profiles are large and never hold it
+        .filter_entry(|e| {
+            e.depth() != 1 || !matches!(e.file_name().to_str(), Some("windows" | "users"))
+        })
+        .filter_map(|e| e.ok())
+        .find(|e| e.file_type().is_file() && e.file_name().eq_ignore_ascii_case(name))
+        .map(|e| e.into_path())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::fs;
+
+    use crate::detection::GameSource;
+
+    fn touch(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+    }
+
+    #[test]
+    fn test_detects_loaders_in_install_and_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let install = dir.path().join("common/Valheim");
+        let prefix = dir.path().join("compatdata/892970/pfx");
+        touch(&install.join("valheim.exe"));
+        touch(&install.join("BepInEx/core/BepInEx.dll"));
+        touch(&install.join("doorstop_config.ini"));
+        touch(&install.join("winhttp.dll"));
+        touch(&prefix.join("drive_c/Modding/MO2/ModOrganizer.exe"));
+        touch(&prefix.join(VORTEX_EXE));
+
+        let game = DetectedGame {
+            source: GameSource::Steam,
+            id: "892970".into(),
+            name: "Valheim".into(),
+            install_dir: install.clone(),
+            executable: Some(install.join("valheim.exe")),
+            fingerprint: None,
+            metadata: HashMap::new(),
+        };
+        let loaders = detect(&game, Some(&prefix));
+        let kinds: Vec<LoaderKind> = loaders.iter().map(|l| l.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                LoaderKind::ModOrganizer,
+                LoaderKind::Vortex,
+                LoaderKind::BepInEx
+            ]
+        );
+        assert_eq!(
+            loaders[0].path,
+            prefix.join("drive_c/Modding/MO2/ModOrganizer.exe")
+        );
+        assert_eq!(loaders[0].launch_target().unwrap().name, "mo2");
+        assert!(loaders[2].launch_target().is_none());
+        assert_eq!(wine_dll_overrides(&loaders).as_deref(), Some("winhttp=n,b"));
+        assert_eq!(loaders[2].missing_runtime, None);
+
+        // Without Doorstop there is nothing to override
+        fs::remove_file(install.join("doorstop_config.ini")).unwrap();
+        assert_eq!(wine_dll_overrides(&detect(&game, None)), None);
+    }
+}
@@ -0,0 +1,97 @@
+//! Desktop notifications
+//!
+//! Sends notifications through `org.freedesktop.Notifications` on the
+//! session bus, which every major desktop and notification daemon
+//! implements. Which finished operations notify is set in the
+//! `notifications` config section.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use zbus::blocking::Connection;
+use zbus::zvariant::Value;
+
+use crate::config::NotificationsConfig;
+
+/// What a notification is about, each with its own switch in `notifications`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Scan,
+    Prepare,
+    Cache,
+    Crash,
+}
+
+/// Whether `config` asks for a notification about an operation that took `elapsed`
+fn wanted(config: &NotificationsConfig, kind: Kind, elapsed: Duration) -> bool {
+    let (enabled, quick_ok) = match kind {
+        // Scanning or preparing a single game is often done in seconds
+        Kind::Scan => (config.scan, false),
+        Kind::Prepare => (config.prepare, false),
+        Kind::Cache => (config.cache, true),
+        Kind::Crash => (config.crash, true),
+    };
+    config.enabled && enabled && (quick_ok || elapsed.as_secs() >= config.min_duration_secs)
+}
+
+/// Notify about a finished operation if the config asks for it; failures
+/// (no session bus or notification daemon) are only logged
+pub fn finished(
+    config: &NotificationsConfig,
+    kind: Kind,
+    elapsed: Duration,
+    summary: &str,
+    body: &str,
+) {
+    if !wanted(config, kind, elapsed) {
+        return;
+    }
+    if let Err(e) = send(summary, body) {
+        log::debug!("notification failed: {:#}", e);
+    }
+}
+
+/// Show a notification; fails when no notification service is running
+pub fn send(summary: &str, body: &str) -> Result<()> {
+    let conn = Connection::session().context("failed to connect to the session bus")?;
+    let hints: HashMap<&str, Value> = HashMap::new();
+    conn.call_method(
+        Some("org.freedesktop.Notifications"),
+        "/org/freedesktop/Notifications",
+        Some("org.freedesktop.Notifications"),
+        "Notify",
+        &(
+            "nvproton",
+            0u32,
+            "applications-games",
+            summary,
+            body,
+            Vec::<&str>::new(),
+            hints,
+            -1i32,
+        ),
+    )
+    .context("org.freedesktop.Notifications.Notify failed")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wanted() {
+        let mut config = NotificationsConfig::default();
+        let quick = Duration::from_secs(2);
+        let slow = Duration::from_secs(60);
+        assert!(!wanted(&config, Kind::Scan, quick));
+        assert!(wanted(&config, Kind::Scan, slow));
+        assert!(wanted(&config, Kind::Crash, Duration::ZERO));
+
+        config.prepare = false;
+        assert!(!wanted(&config, Kind::Prepare, slow));
+        config.enabled = false;
+        assert!(!wanted(&config, Kind::Cache, slow));
+    }
+}
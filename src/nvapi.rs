@@ -0,0 +1,554 @@
+//! NVAPI / DXVK-NVAPI management
+//!
+//! DLSS and Reflex in Proton need three pieces:
+//! - dxvk-nvapi shipped with the selected Proton (`nvapi64.dll`)
+//! - `PROTON_ENABLE_NVAPI=1`, since Proton hides NVAPI by default
+//! - The driver's `nvngx.dll` / `_nvngx.dll` inside the prefix
+//!
+//! `nvproton nvapi enable <game_id>` records the game in the state directory
+//! (`nvapi.yaml`) so launches set `PROTON_ENABLE_NVAPI`, and copies the
+//! driver's NGX DLLs into the prefix when they are missing or outdated.
+//!
+//! DLSS Frame Generation additionally needs the game's `nvngx_dlssg.dll`, an
+//! RTX 40 series or newer GPU, a recent driver branch and Proton 9 or newer;
+//! `games show` and `run --dry-run` report what is missing.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::cli::{NvapiArgs, NvapiCommand, NvapiGameArgs};
+use crate::config::{ConfigManager, ConfigPaths, NvConfig};
+use crate::detection::{DetectedGame, GameDatabase, GameId, GameSource, VulkanCapabilities};
+use crate::dxvk;
+use crate::errors::NvError;
+use crate::ffi;
+use crate::steam::client::SteamClient;
+
+/// Driver NGX DLLs Proton games need for DLSS
+const NGX_DLLS: &[&str] = &["nvngx.dll", "_nvngx.dll"];
+
+/// DLSS libraries games ship: Super Resolution and Frame Generation
+const DLSS_SR_DLL: &str = "nvngx_dlss.dll";
+const DLSS_FG_DLL: &str = "nvngx_dlssg.dll";
+
+/// Oldest driver branch running DLSS Frame Generation under Proton
+const FRAME_GEN_MIN_DRIVER: u32 = 565;
+
+/// Oldest Proton release whose dxvk-nvapi supports Frame Generation
+const FRAME_GEN_MIN_PROTON: u32 = 9;
+
+/// Environment for DLSS Frame Generation: NVAPI for the game and DXVK, and
+/// Reflex, which Frame Generation always runs with
+pub const FRAME_GEN_ENV: &[(&str, &str)] = &[
+    ("PROTON_ENABLE_NVAPI", "1"),
+    ("DXVK_ENABLE_NVAPI", "1"),
+    ("DXVK_NVAPI_ALLOW_REFLEX", "1"),
+];
+
+/// Where distributions install the driver's Wine DLLs
+const DRIVER_WINE_DIRS: &[&str] = &[
+    "/usr/lib/nvidia/wine",
+    "/usr/lib64/nvidia/wine",
+    "/usr/lib/x86_64-linux-gnu/nvidia/wine",
+    "/usr/lib/x86_64-linux-gnu/nvidia/current/wine",
+];
+
+/// Games with NVAPI enabled, by `source:id`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NvapiStore {
+    pub enabled: BTreeSet<String>,
+}
+
+impl NvapiStore {
+    fn path(paths: &ConfigPaths) -> PathBuf {
+        paths.state_dir.join("nvapi.yaml")
+    }
+
+    /// Load the settings, moving bare-ID entries to the game they belong to
+    pub fn load(paths: &ConfigPaths, db: &GameDatabase) -> Result<Self> {
+        let path = Self::path(paths);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read NVAPI settings {:?}", path))?;
+        let mut store: Self = serde_yaml::from_str(&content)
+            .with_context(|| format!("failed to parse NVAPI settings {:?}", path))?;
+        store.migrate(db);
+        Ok(store)
+    }
+
+    /// Key entries written before games were keyed by source by their game,
+    /// unless several games share the ID
+    fn migrate(&mut self, db: &GameDatabase) {
+        let legacy: Vec<_> = self
+            .enabled
+            .iter()
+            .filter_map(|key| Some((key.clone(), db.legacy_owner(key)?.to_string())))
+            .collect();
+        for (bare, game_id) in legacy {
+            self.enabled.remove(&bare);
+            self.enabled.insert(game_id);
+        }
+    }
+
+    fn save(&self, paths: &ConfigPaths) -> Result<()> {
+        let path = Self::path(paths);
+        fs::create_dir_all(&paths.state_dir)
+            .with_context(|| format!("failed to create directory {:?}", paths.state_dir))?;
+        fs::write(&path, serde_yaml::to_string(self)?)
+            .with_context(|| format!("failed to write NVAPI settings {:?}", path))
+    }
+
+    pub fn is_enabled(&self, game_id: &GameId) -> bool {
+        self.enabled.contains(&game_id.to_string())
+    }
+}
+
+/// NVAPI readiness of a game
+#[derive(Debug, Clone)]
+pub struct NvapiStatus {
+    pub proton: Option<PathBuf>,
+    /// dxvk-nvapi DLL inside the selected Proton
+    pub dxvk_nvapi: Option<PathBuf>,
+    pub driver_ngx_dir: Option<PathBuf>,
+    pub prefix: Option<PathBuf>,
+    /// Driver NGX DLLs present in the prefix
+    pub prefix_ngx: bool,
+    pub rtx_gpu: Option<bool>,
+}
+
+impl NvapiStatus {
+    pub fn collect(config: &NvConfig, game: &DetectedGame, prefix: Option<PathBuf>) -> Self {
+        let proton = selected_proton(config, game);
+        let dxvk_nvapi = proton.as_deref().and_then(find_dxvk_nvapi);
+        let prefix = prefix.or_else(|| dxvk::game_prefix(game));
+        let prefix_ngx = prefix
+            .as_deref()
+            .is_some_and(|p| NGX_DLLS.iter().all(|dll| system32(p).join(dll).exists()));
+        let rtx_gpu = VulkanCapabilities::detect()
+            .ok()
+            .map(|caps| is_dlss_capable(&caps.gpu_name));
+
+        Self {
+            proton,
+            dxvk_nvapi,
+            driver_ngx_dir: driver_ngx_dir(),
+            prefix,
+            prefix_ngx,
+            rtx_gpu,
+        }
+    }
+
+    /// Reasons DLSS cannot work, empty when everything is in place
+    pub fn dlss_blockers(&self) -> Vec<String> {
+        let mut blockers = Vec::new();
+        if self.rtx_gpu == Some(false) {
+            blockers.push("GPU does not support DLSS (RTX required)".to_string());
+        }
+        if self.proton.is_some() && self.dxvk_nvapi.is_none() {
+            blockers.push("selected Proton does not ship dxvk-nvapi".to_string());
+        }
+        if self.driver_ngx_dir.is_none() {
+            blockers
+                .push("driver nvngx.dll not found (install the driver's Wine DLLs)".to_string());
+        }
+        if !self.prefix_ngx {
+            blockers.push("nvngx.dll is not exposed in the prefix".to_string());
+        }
+        blockers
+    }
+}
+
+/// DLSS libraries shipped with a game
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DlssDlls {
+    /// `nvngx_dlss.dll`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub super_resolution: Option<PathBuf>,
+    /// `nvngx_dlssg.dll`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_generation: Option<PathBuf>,
+}
+
+impl DlssDlls {
+    /// Look for the DLSS DLLs below a game's install directory
+    pub fn find(install_dir: &Path) -> Self {
+        let mut dlls = Self::default();
+        if install_dir.as_os_str().is_empty() {
+            return dlls;
+        }
+        for entry in WalkDir::new(install_dir)
+            .max_depth(6)
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            let name = entry.file_name().to_string_lossy().to_ascii_lowercase();
+            let slot = match name.as_str() {
+                DLSS_SR_DLL => &mut dlls.super_resolution,
+                DLSS_FG_DLL => &mut dlls.frame_generation,
+                _ => continue,
+            };
+            slot.get_or_insert_with(|| entry.into_path());
+            if dlls.super_resolution.is_some() && dlls.frame_generation.is_some() {
+                break;
+            }
+        }
+        dlls
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.super_resolution.is_none() && self.frame_generation.is_none()
+    }
+
+    /// Shipped features, e.g. `Super Resolution, Frame Generation`
+    pub fn describe(&self) -> String {
+        let mut features = Vec::new();
+        if self.super_resolution.is_some() {
+            features.push("Super Resolution");
+        }
+        if self.frame_generation.is_some() {
+            features.push("Frame Generation");
+        }
+        features.join(", ")
+    }
+}
+
+/// Reasons DLSS Frame Generation cannot work for a game, empty when nothing
+/// is known to be missing
+pub fn frame_generation_blockers(
+    config: &NvConfig,
+    game: &DetectedGame,
+    caps: Option<&VulkanCapabilities>,
+) -> Vec<String> {
+    let mut blockers = Vec::new();
+    if let Some(caps) = caps {
+        if !is_frame_gen_capable(&caps.gpu_name) {
+            blockers.push(format!(
+                "{} does not support Frame Generation (RTX 40 series or newer required)",
+                caps.gpu_name
+            ));
+        }
+        if caps.driver_branch < FRAME_GEN_MIN_DRIVER {
+            blockers.push(format!(
+                "driver {} is too old for Frame Generation ({} or newer required)",
+                caps.driver_version, FRAME_GEN_MIN_DRIVER
+            ));
+        }
+    }
+    if let Some(proton) = selected_proton(config, game) {
+        if let Some(major) = proton_major_version(&proton)
+            && major < FRAME_GEN_MIN_PROTON
+        {
+            blockers.push(format!(
+                "Proton {} is too old for Frame Generation ({}.0 or newer required)",
+                major, FRAME_GEN_MIN_PROTON
+            ));
+        }
+        if find_dxvk_nvapi(&proton).is_none() {
+            blockers.push("selected Proton does not ship dxvk-nvapi".to_string());
+        }
+    }
+    blockers
+}
+
+/// Handle the `nvapi` command
+pub fn handle_nvapi(args: NvapiArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
+    match args.command {
+        NvapiCommand::Status(game_args) => handle_status(game_args, manager, config),
+        NvapiCommand::Enable(game_args) => handle_enable(game_args, manager, config),
+        NvapiCommand::Disable(game_args) => handle_disable(game_args, manager),
+    }
+}
+
+fn handle_status(args: NvapiGameArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
+    let db = GameDatabase::load_or_default(manager.paths())?;
+    let game = db.resolve_game(&args.game_id)?;
+    let status = NvapiStatus::collect(config, &game, args.prefix.map(PathBuf::from));
+    let enabled = NvapiStore::load(manager.paths(), &db)?.is_enabled(&game.game_id());
+    print_status(&game, &status, enabled);
+    Ok(())
+}
+
+fn handle_enable(args: NvapiGameArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
+    // dxvk-nvapi answers the game's GPU queries through the driver's NVML
+    ffi::load_nvml().context(NvError::Ffi("NVML".to_string()))?;
+    let db = GameDatabase::load_or_default(manager.paths())?;
+    let game = db.resolve_game(&args.game_id)?;
+    let mut status = NvapiStatus::collect(config, &game, args.prefix.map(PathBuf::from));
+
+    if let (Some(prefix), Some(driver_dir)) = (&status.prefix, &status.driver_ngx_dir) {
+        if !prefix.join("drive_c").exists() {
+            bail!(
+                "{:?} is not an initialized Wine prefix (launch the game once first)",
+                prefix
+            );
+        }
+        for name in copy_ngx_dlls(driver_dir, &system32(prefix))? {
+            println!("Copied {} into the prefix", name);
+        }
+        status.prefix_ngx = true;
+    }
+
+    let mut store = NvapiStore::load(manager.paths(), &db)?;
+    store.enabled.insert(game.game_id().to_string());
+    store.save(manager.paths())?;
+    println!(
+        "PROTON_ENABLE_NVAPI will be set when launching {}",
+        game.name
+    );
+
+    print_status(&game, &status, true);
+    Ok(())
+}
+
+fn handle_disable(args: NvapiGameArgs, manager: &ConfigManager) -> Result<()> {
+    let db = GameDatabase::load_or_default(manager.paths())?;
+    let mut store = NvapiStore::load(manager.paths(), &db)?;
+    // Entries of games no longer installed are removed by their stored key
+    let key = db
+        .resolve_game(&args.game_id)
+        .map_or_else(|_| args.game_id.clone(), |game| game.game_id().to_string());
+    if !store.enabled.remove(&key) {
+        bail!("NVAPI is not enabled for '{}'", args.game_id);
+    }
+    store.save(manager.paths())?;
+    println!("NVAPI disabled for {}", args.game_id);
+    Ok(())
+}
+
+fn print_status(game: &DetectedGame, status: &NvapiStatus, enabled: bool) {
+    let path_or = |path: &Option<PathBuf>, missing: &str| {
+        path.as_ref()
+            .map(|p| format!("{:?}", p))
+            .unwrap_or_else(|| missing.to_string())
+    };
+
+    println!("\nNVAPI status for {} ({})", game.name, game.id);
+    println!("  Enabled:     {}", if enabled { "yes" } else { "no" });
+    println!("  Proton:      {}", path_or(&status.proton, "unknown"));
+    println!(
+        "  dxvk-nvapi:  {}",
+        path_or(&status.dxvk_nvapi, "not found")
+    );
+    println!(
+        "  Driver NGX:  {}",
+        path_or(&status.driver_ngx_dir, "not found")
+    );
+    println!("  Prefix:      {}", path_or(&status.prefix, "unknown"));
+    println!(
+        "  Prefix NGX:  {}",
+        if status.prefix_ngx {
+            "present"
+        } else {
+            "missing"
+        }
+    );
+
+    let blockers = status.dlss_blockers();
+    if blockers.is_empty() {
+        println!("  DLSS:        available");
+    } else {
+        println!("  DLSS:        unavailable");
+        for blocker in blockers {
+            println!("    - {}", blocker);
+        }
+    }
+}
+
+/// Proton selected for a game: Steam's per-game mapping, then Proton-NV
+fn selected_proton(config: &NvConfig, game: &DetectedGame) -> Option<PathBuf> {
+    if game.source == GameSource::Steam
+        && let Some(steam) = SteamClient::from_config(config)
+        && let Some(tool) = steam.compat_tool(&game.id)
+    {
+        return Some(tool);
+    }
+    crate::detection::proton_nv::get_proton_nv_path()
+}
+
+/// Locate dxvk-nvapi's 64-bit DLL inside a Proton installation
+fn find_dxvk_nvapi(proton: &Path) -> Option<PathBuf> {
+    ["files", "dist"]
+        .iter()
+        .map(|dir| proton.join(dir))
+        .filter(|dir| dir.exists())
+        .flat_map(|dir| {
+            WalkDir::new(dir)
+                .max_depth(6)
+                .into_iter()
+                .filter_map(|e| e.ok())
+        })
+        .find(|entry| entry.file_name() == "nvapi64.dll")
+        .map(|entry| entry.into_path())
+}
+
+fn driver_ngx_dir() -> Option<PathBuf> {
+    DRIVER_WINE_DIRS
+        .iter()
+        .map(PathBuf::from)
+        .find(|dir| dir.join(NGX_DLLS[0]).exists())
+}
+
+fn system32(prefix: &Path) -> PathBuf {
+    prefix.join("drive_c").join("windows").join("system32")
+}
+
+/// Copy NGX DLLs that are missing or differ in size; returns the copied names
+fn copy_ngx_dlls(driver_dir: &Path, target_dir: &Path) -> Result<Vec<&'static str>> {
+    let mut copied = Vec::new();
+    for name in NGX_DLLS {
+        let source = driver_dir.join(name);
+        if !source.exists() {
+            continue;
+        }
+        let target = target_dir.join(name);
+        let up_to_date = match (fs::metadata(&source), fs::metadata(&target)) {
+            (Ok(src), Ok(dst)) => src.len() == dst.len(),
+            _ => false,
+        };
+        if !up_to_date {
+            fs::create_dir_all(target_dir)
+                .with_context(|| format!("failed to create directory {:?}", target_dir))?;
+            fs::copy(&source, &target)
+                .with_context(|| format!("failed to copy {:?} to {:?}", source, target))?;
+            copied.push(*name);
+        }
+    }
+    Ok(copied)
+}
+
+/// Major version of a Proton installation, from its `version` file or
+/// directory name; None for Experimental and unrecognized builds
+fn proton_major_version(proton: &Path) -> Option<u32> {
+    let version_file = fs::read_to_string(proton.join("version")).unwrap_or_default();
+    let dir_name = proton.file_name()?.to_string_lossy().into_owned();
+    [version_file, dir_name]
+        .iter()
+        .find_map(|text| parse_proton_major(text))
+}
+
+/// `proton-9.0-2`, `Proton 8.0` and `GE-Proton9-20` all name their major version
+/// right after "proton"
+fn parse_proton_major(text: &str) -> Option<u32> {
+    let text = text.to_ascii_lowercase();
+    let rest = &text[text.find("proton")? + "proton".len()..];
+    let digits: String = rest
+        .trim_start_matches(['-', '_', ' '])
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+/// Frame Generation needs an Ada (RTX 40 series) or newer GPU
+pub(crate) fn is_frame_gen_capable(gpu_name: &str) -> bool {
+    let name = gpu_name.to_ascii_uppercase();
+    if name.contains("ADA") || name.contains("BLACKWELL") {
+        return true;
+    }
+    let Some((_, model)) = name.split_once("RTX ") else {
+        return false;
+    };
+    let digits: String = model.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.len() == 4 && digits.as_bytes()[0] >= b'4'
+}
+
+/// DLSS needs an RTX (Turing or newer) GPU
+fn is_dlss_capable(gpu_name: &str) -> bool {
+    let name = gpu_name.to_ascii_uppercase();
+    name.contains("RTX") || name.contains("TITAN V")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dlss_capable_gpus() {
+        assert!(is_dlss_capable("NVIDIA GeForce RTX 4090"));
+        assert!(!is_dlss_capable("NVIDIA GeForce GTX 1080 Ti"));
+    }
+
+    #[test]
+    fn test_store_is_keyed_by_source() {
+        let game = |source, id: &str| DetectedGame {
+            id: id.into(),
+            name: id.into(),
+            source,
+            install_dir: PathBuf::from("/games").join(id),
+            executable: None,
+            fingerprint: None,
+            metadata: Default::default(),
+        };
+        let (lutris, heroic) = (
+            game(GameSource::Lutris, "doom"),
+            game(GameSource::Heroic, "doom"),
+        );
+        let mut db = GameDatabase::default();
+        db.merge_detected(
+            &[
+                lutris.clone(),
+                heroic.clone(),
+                game(GameSource::Steam, "1145360"),
+            ],
+            0,
+        );
+
+        let mut store = NvapiStore {
+            enabled: ["doom", "1145360", "lutris:doom"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        };
+        store.migrate(&db);
+        assert!(store.is_enabled(&lutris.game_id()));
+        assert!(!store.is_enabled(&heroic.game_id()));
+        // A bare ID only one game has moves to that game
+        assert!(store.is_enabled(&GameId::new(GameSource::Steam, "1145360")));
+        assert!(store.enabled.contains("doom"));
+        assert!(!store.enabled.contains("1145360"));
+    }
+
+    #[test]
+    fn test_frame_generation_requirements() {
+        assert!(is_frame_gen_capable("NVIDIA GeForce RTX 4070 Laptop GPU"));
+        assert!(is_frame_gen_capable("NVIDIA RTX 2000 Ada Generation"));
+        assert!(!is_frame_gen_capable("NVIDIA GeForce RTX 3080"));
+        assert!(!is_frame_gen_capable("NVIDIA RTX A6000"));
+
+        assert_eq!(parse_proton_major("1718369893 proton-9.0-2\n"), Some(9));
+        assert_eq!(parse_proton_major("GE-Proton10-3"), Some(10));
+        assert_eq!(parse_proton_major("Proton 8.0"), Some(8));
+        assert_eq!(parse_proton_major("Proton - Experimental"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let bin = dir.path().join("Bin64");
+        fs::create_dir_all(&bin).unwrap();
+        fs::write(bin.join("NVNGX_DLSSG.DLL"), "").unwrap();
+        let dlls = DlssDlls::find(dir.path());
+        assert_eq!(dlls.frame_generation, Some(bin.join("NVNGX_DLSSG.DLL")));
+        assert_eq!(dlls.describe(), "Frame Generation");
+    }
+
+    #[test]
+    fn test_copy_ngx_dlls_skips_up_to_date() {
+        let dir = tempfile::tempdir().unwrap();
+        let driver = dir.path().join("driver");
+        let target = dir.path().join("system32");
+        fs::create_dir_all(&driver).unwrap();
+        fs::write(driver.join("nvngx.dll"), "ngx").unwrap();
+        fs::write(driver.join("_nvngx.dll"), "ngx-loader").unwrap();
+
+        assert_eq!(
+            copy_ngx_dlls(&driver, &target).unwrap(),
+            vec!["nvngx.dll", "_nvngx.dll"]
+        );
+        assert!(copy_ngx_dlls(&driver, &target).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,59 @@
+//! Path helpers shared by config values, state files and PATH lookups
+
+use std::path::{Path, PathBuf};
+
+/// Expand a leading `~` to the home directory
+pub fn expand_home(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Where `program` is found on PATH
+pub fn find_in_path(program: &str) -> Option<PathBuf> {
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|path| path.is_file())
+}
+
+/// Whether `program` is on PATH
+pub fn in_path(program: &str) -> bool {
+    find_in_path(program).is_some()
+}
+
+/// Make a game ID safe to use in a file name
+pub fn sanitize(game_id: &str) -> String {
+    game_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_home() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_home("~/Videos/clips"), home.join("Videos/clips"));
+        assert_eq!(expand_home("~"), home);
+        assert_eq!(expand_home("/srv/~/clips"), PathBuf::from("/srv/~/clips"));
+        assert_eq!(expand_home("~user/clips"), PathBuf::from("~user/clips"));
+    }
+
+    #[test]
+    fn test_sanitize_game_id() {
+        assert_eq!(sanitize("epic/Fortnite"), "epic_Fortnite");
+        assert_eq!(sanitize("1245620"), "1245620");
+        assert_eq!(sanitize("steam:1245620"), "steam_1245620");
+    }
+}
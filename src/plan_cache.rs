@@ -0,0 +1,181 @@
+//! Cached launch plans
+//!
+//! Before every launch the game's profile is resolved through its `extends`
+//! chain, its placeholders are expanded and the per-game shader cache
+//! directories are set up. The outcome is kept in the state directory
+//! (`plans/<source>-<id>.json`) under a key hashing everything it derives from:
+//! - the profile's name and the profile files (name, size, modification time)
+//! - the config
+//! - the placeholder values (game, install and cache directories, GPU)
+//! - whether shader caches are isolated, and the nvproton version
+//!
+//! A plan is reused while its key matches and its cache directories exist.
+//! `run --no-plan-cache` resolves everything from scratch.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use sha2::{Digest, Sha256};
+
+use crate::config::{ConfigPaths, NvConfig};
+use crate::detection::GameId;
+use crate::paths::sanitize;
+use crate::template::TemplateContext;
+
+/// The profile- and cache-derived part of a launch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaunchPlan {
+    /// Resolved and expanded profile settings
+    pub profile_settings: Option<Value>,
+    /// Per-game shader cache variables, empty without cache isolation
+    pub cache_env: Vec<(String, String)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredPlan {
+    key: String,
+    plan: LaunchPlan,
+}
+
+/// Launch plans in the state directory
+pub struct PlanCache {
+    dir: PathBuf,
+}
+
+impl PlanCache {
+    pub fn new(paths: &ConfigPaths) -> Self {
+        Self {
+            dir: paths.state_dir.join("plans"),
+        }
+    }
+
+    fn path(&self, game_id: &GameId) -> PathBuf {
+        self.dir
+            .join(format!("{}.json", sanitize(&game_id.dir_name())))
+    }
+
+    /// The game's plan, if it was stored under this key and is still usable
+    pub fn load(&self, game_id: &GameId, key: &str) -> Option<LaunchPlan> {
+        let content = fs::read_to_string(self.path(game_id)).ok()?;
+        let stored: StoredPlan = serde_json::from_str(&content).ok()?;
+        // `cache clear` removes the directories behind a plan's back
+        let usable = stored.key == key
+            && stored
+                .plan
+                .cache_env
+                .iter()
+                .all(|(_, dir)| Path::new(dir).is_dir());
+        usable.then_some(stored.plan)
+    }
+
+    pub fn store(&self, game_id: &GameId, key: &str, plan: LaunchPlan) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("failed to create directory {:?}", self.dir))?;
+        let stored = StoredPlan {
+            key: key.to_string(),
+            plan,
+        };
+        let path = self.path(game_id);
+        fs::write(&path, serde_json::to_string(&stored)?)
+            .with_context(|| format!("failed to write launch plan {:?}", path))
+    }
+}
+
+/// Key of the plan for a game, profile and config
+pub fn plan_key(
+    config: &NvConfig,
+    profiles_dir: &Path,
+    profile: Option<&str>,
+    template: &TemplateContext,
+    cache_isolation: bool,
+) -> Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    hasher.update([cache_isolation as u8]);
+    hasher.update(serde_yaml::to_string(config).context("failed to serialize config")?);
+    for (name, value) in template.vars() {
+        hasher.update(format!("{}={}\0", name, value));
+    }
+    if let Some(profile) = profile {
+        hasher.update(format!("profile={}\0", profile));
+        for (name, len, modified) in profile_files(profiles_dir) {
+            hasher.update(format!("{}:{}:{}\0", name, len, modified));
+        }
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Name, size and modification time (ns) of each profile file
+fn profile_files(dir: &Path) -> Vec<(String, u64, u128)> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+            Some((
+                entry.file_name().to_string_lossy().into_owned(),
+                metadata.len(),
+                modified.as_nanos(),
+            ))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::detection::{DetectedGame, GameSource};
+
+    #[test]
+    fn test_plan_reused_until_inputs_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let profiles = dir.path().join("profiles");
+        fs::create_dir_all(&profiles).unwrap();
+        fs::write(profiles.join("fast.yaml"), "name: fast\n").unwrap();
+        let game = DetectedGame {
+            source: GameSource::Unknown,
+            id: "heroic:abc".into(),
+            name: "Abc".into(),
+            install_dir: PathBuf::from("/games/abc"),
+            executable: None,
+            fingerprint: None,
+            metadata: HashMap::new(),
+        };
+        let template = TemplateContext::for_game(&game, None);
+        let config = NvConfig::default();
+        let key = |profile| plan_key(&config, &profiles, profile, &template, true).unwrap();
+
+        let cache = PlanCache {
+            dir: dir.path().join("plans"),
+        };
+        let plan = LaunchPlan {
+            profile_settings: Some(serde_yaml::from_str("env: {DXVK_HUD: fps}").unwrap()),
+            cache_env: vec![(
+                "DXVK_STATE_CACHE_PATH".into(),
+                dir.path().display().to_string(),
+            )],
+        };
+        let first = key(Some("fast"));
+        cache.store(&game.game_id(), &first, plan.clone()).unwrap();
+        // Another source's game with the same ID has its own plan
+        let other = GameId::new(GameSource::Lutris, game.id.clone());
+        assert_eq!(cache.load(&other, &first), None);
+        assert_eq!(cache.load(&game.game_id(), &first), Some(plan));
+        assert_ne!(key(None), first);
+
+        fs::write(profiles.join("fast.yaml"), "name: fast\nenv: {}\n").unwrap();
+        let changed = key(Some("fast"));
+        assert_ne!(changed, first);
+        assert_eq!(cache.load(&game.game_id(), &changed), None);
+    }
+}
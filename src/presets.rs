@@ -315,7 +315,6 @@ pub fn generate_preset(preset: PresetType) -> ProfileDocument {
         }
 
         // ===== DLSS 4.5 Presets =====
-
         PresetType::DlssQuality => {
             // DLSS Quality - best image quality for RTX 20+
             let mut dlss = Mapping::new();
@@ -560,9 +559,15 @@ mod tests {
 
     #[test]
     fn test_from_name() {
-        assert_eq!(PresetType::from_name("steam-deck"), Some(PresetType::SteamDeck));
+        assert_eq!(
+            PresetType::from_name("steam-deck"),
+            Some(PresetType::SteamDeck)
+        );
         assert_eq!(PresetType::from_name("deck"), Some(PresetType::SteamDeck));
-        assert_eq!(PresetType::from_name("competitive"), Some(PresetType::Competitive));
+        assert_eq!(
+            PresetType::from_name("competitive"),
+            Some(PresetType::Competitive)
+        );
         assert_eq!(PresetType::from_name("unknown"), None);
     }
 
@@ -570,7 +575,7 @@ mod tests {
     fn test_generate_steam_deck_preset() {
         let doc = generate_preset(PresetType::SteamDeck);
         assert_eq!(doc.name, "steam-deck");
-        assert!(doc.settings.contains_key(&val("display")));
-        assert!(doc.settings.contains_key(&val("gamescope")));
+        assert!(doc.settings.contains_key(val("display")));
+        assert!(doc.settings.contains_key(val("gamescope")));
     }
 }
@@ -6,6 +6,9 @@ use anyhow::{Context, Result};
 use serde_yaml::{Mapping, Value};
 
 use super::model::{ProfileDocument, ResolvedProfile};
+use crate::cli::FileFormat;
+use crate::errors::NvError;
+use crate::{format, yaml_edit};
 
 pub struct ProfileManager {
     root: PathBuf,
@@ -26,22 +29,27 @@ impl ProfileManager {
             if !entry.file_type()?.is_file() {
                 continue;
             }
-            if entry.path().extension().and_then(|ext| ext.to_str()) == Some("yaml")
+            if FileFormat::from_path(&entry.path()).is_some()
                 && let Some(stem) = entry.path().file_stem().and_then(|s| s.to_str())
             {
                 profiles.push(stem.to_string());
             }
         }
         profiles.sort();
+        profiles.dedup();
         Ok(profiles)
     }
 
     pub fn load(&self, name: &str) -> Result<ProfileDocument> {
         let path = self.path_for(name);
+        if !path.exists() {
+            return Err(NvError::ProfileNotFound(name.to_string()).into());
+        }
         let contents = fs::read_to_string(&path)
             .with_context(|| format!("failed to read profile at {:?}", path))?;
-        let document: ProfileDocument =
-            serde_yaml::from_str(&contents).context("failed to parse profile document")?;
+        let document: ProfileDocument = FileFormat::of(&path)
+            .parse(&contents)
+            .with_context(|| format!("failed to parse profile document at {:?}", path))?;
         Ok(document)
     }
 
@@ -49,8 +57,27 @@ impl ProfileManager {
         fs::create_dir_all(&self.root)
             .with_context(|| format!("failed to create profiles directory at {:?}", self.root))?;
         let path = self.path_for(&document.name);
-        let encoded =
-            serde_yaml::to_string(document).context("failed to encode profile document")?;
+        let encoded = if FileFormat::of(&path) == FileFormat::Toml {
+            FileFormat::Toml
+                .encode(document)
+                .context("failed to encode profile document")?
+        } else {
+            let value =
+                serde_yaml::to_value(document).context("failed to encode profile document")?;
+            // Edit an existing profile in place to keep its comments and key order
+            let edited = fs::read_to_string(&path).ok().and_then(|original| {
+                yaml_edit::edit_in_place(&original, &value, |text| {
+                    let document: ProfileDocument = serde_yaml::from_str(text)?;
+                    Ok(serde_yaml::to_value(document)?)
+                })
+            });
+            match edited {
+                Some(edited) => edited,
+                None => {
+                    serde_yaml::to_string(&value).context("failed to encode profile document")?
+                }
+            }
+        };
         let mut file = fs::File::create(&path)
             .with_context(|| format!("failed to open profile file at {:?}", path))?;
         file.write_all(encoded.as_bytes())
@@ -83,8 +110,14 @@ impl ProfileManager {
         })
     }
 
+    /// The profile's file, or where a new one goes in the config's format
     fn path_for(&self, name: &str) -> PathBuf {
-        self.root.join(format!("{}.yaml", name))
+        let format = self
+            .root
+            .parent()
+            .map(format::preferred)
+            .unwrap_or(FileFormat::Yaml);
+        format::path_in(&self.root, name, format)
     }
 }
 
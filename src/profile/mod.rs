@@ -5,12 +5,14 @@ mod persistence;
 use anyhow::{Context, Result};
 use serde_yaml::{Mapping, Value};
 use std::fs;
+use std::path::Path;
 
 use crate::cli::{
-    OutputFormat, ProfileArgs, ProfileCommand, ProfileCreateArgs, ProfileExportArgs,
+    FileFormat, ProfileArgs, ProfileCommand, ProfileCreateArgs, ProfileExportArgs, ProfileFormat,
     ProfileImportArgs, ProfileNameArgs, ProfileSetArgs,
 };
 use crate::config::{ConfigManager, NvConfig};
+use crate::gl_tuning;
 
 pub use manager::ProfileManager;
 pub use model::ProfileDocument;
@@ -20,7 +22,7 @@ pub use persistence::{ProfileBinding, ProfilePersistence};
 pub fn handle_profile(
     args: ProfileArgs,
     manager: &ConfigManager,
-    _config: &mut NvConfig,
+    _config: &NvConfig,
 ) -> Result<()> {
     let profile_manager = ProfileManager::new(manager.paths().profiles_dir.clone());
     match args.command {
@@ -52,9 +54,14 @@ pub fn handle_profile(
         ProfileCommand::Import(ProfileImportArgs { path, name }) => {
             let contents = fs::read_to_string(&path)
                 .with_context(|| format!("failed to read profile from {:?}", path))?;
-            let mut document: ProfileDocument = serde_yaml::from_str(&contents)
-                .or_else(|_| serde_json::from_str(&contents))
-                .context("failed to parse profile document")?;
+            let mut document: ProfileDocument = match FileFormat::from_path(Path::new(&path)) {
+                Some(format) => format.parse(&contents),
+                None => serde_yaml::from_str(&contents)
+                    .or_else(|_| serde_json::from_str(&contents))
+                    .or_else(|_| toml::from_str(&contents))
+                    .map_err(anyhow::Error::from),
+            }
+            .context("failed to parse profile document")?;
             if let Some(name) = name {
                 document.name = name;
             }
@@ -64,8 +71,9 @@ pub fn handle_profile(
         ProfileCommand::Export(ProfileExportArgs { name, format, path }) => {
             let document = profile_manager.load(&name)?;
             let encoded = match format {
-                OutputFormat::Text | OutputFormat::Yaml => serde_yaml::to_string(&document)?,
-                OutputFormat::Json => serde_json::to_string_pretty(&document)?,
+                ProfileFormat::Yaml => FileFormat::Yaml.encode(&document)?,
+                ProfileFormat::Json => serde_json::to_string_pretty(&document)?,
+                ProfileFormat::Toml => FileFormat::Toml.encode(&document)?,
             };
             if let Some(path) = path {
                 fs::write(&path, encoded)
@@ -81,6 +89,10 @@ pub fn handle_profile(
 
 fn apply_sets(document: &mut ProfileDocument, values: &[(String, String)]) -> Result<()> {
     for (key, value) in values {
+        if let Some(knob) = key.strip_prefix("nvidia.").and_then(gl_tuning::knob) {
+            knob.value(&Value::String(value.clone()))
+                .map_err(anyhow::Error::msg)?;
+        }
         set_nested_value(&mut document.settings, key, Value::String(value.clone()))?;
     }
     Ok(())
@@ -120,7 +132,7 @@ mod tests {
         .expect("apply sets");
         let resolved = document
             .settings
-            .get(&Value::String("graphics".into()))
+            .get(Value::String("graphics".into()))
             .unwrap();
         let graphics = resolved.as_mapping().unwrap();
         assert_eq!(
@@ -3,16 +3,19 @@
 //! Stores associations between games and their assigned profiles,
 //! enabling automatic profile loading when games are launched.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
-use rusqlite::{params, Connection, OptionalExtension};
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::config::ConfigPaths;
+use crate::detection::GameDatabase;
 
 /// Game-to-profile binding record
 #[derive(Debug, Clone)]
 #[allow(dead_code)] // Library API for game-profile persistence
 pub struct ProfileBinding {
-    /// Game ID (e.g., Steam app ID or launcher-specific identifier)
+    /// Game ID (`source:id`, e.g. `steam:1145360`)
     pub game_id: String,
     /// Profile name
     pub profile_name: String,
@@ -28,6 +31,11 @@ pub struct ProfilePersistence {
 }
 
 impl ProfilePersistence {
+    /// Location of the profile database in the user config directory
+    pub fn path(paths: &ConfigPaths) -> PathBuf {
+        paths.user_config_dir.join("profiles.db")
+    }
+
     /// Open or create the profile database at the given path
     pub fn open(db_path: &Path) -> Result<Self> {
         // Ensure parent directory exists
@@ -75,7 +83,12 @@ impl ProfilePersistence {
                      updated_at = strftime('%s', 'now')",
                 params![game_id, profile_name],
             )
-            .with_context(|| format!("failed to bind game '{}' to profile '{}'", game_id, profile_name))?;
+            .with_context(|| {
+                format!(
+                    "failed to bind game '{}' to profile '{}'",
+                    game_id, profile_name
+                )
+            })?;
         Ok(())
     }
 
@@ -138,7 +151,9 @@ impl ProfilePersistence {
     pub fn games_with_profile(&self, profile_name: &str) -> Result<Vec<String>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT game_id FROM profile_bindings WHERE profile_name = ?1 ORDER BY game_id")
+            .prepare(
+                "SELECT game_id FROM profile_bindings WHERE profile_name = ?1 ORDER BY game_id",
+            )
             .context("failed to prepare games query")?;
 
         let games = stmt
@@ -149,8 +164,32 @@ impl ProfilePersistence {
         Ok(games)
     }
 
+    /// Key bindings made before games were keyed by source by their game
+    ///
+    /// Bindings of a bare ID several games share are left alone, and a
+    /// binding made under the `source:id` wins over the bare one.
+    pub fn migrate(&self, db: &GameDatabase) -> Result<()> {
+        for binding in self.list_bindings()? {
+            let Some(game_id) = db.legacy_owner(&binding.game_id) else {
+                continue;
+            };
+            self.conn
+                .execute(
+                    "UPDATE OR IGNORE profile_bindings SET game_id = ?2 WHERE game_id = ?1",
+                    params![binding.game_id, game_id.to_string()],
+                )
+                .and_then(|_| {
+                    self.conn.execute(
+                        "DELETE FROM profile_bindings WHERE game_id = ?1",
+                        params![binding.game_id],
+                    )
+                })
+                .with_context(|| format!("failed to migrate binding of '{}'", binding.game_id))?;
+        }
+        Ok(())
+    }
+
     /// List all bindings
-    #[allow(dead_code)] // Library API
     pub fn list_bindings(&self) -> Result<Vec<ProfileBinding>> {
         let mut stmt = self
             .conn
@@ -193,7 +232,9 @@ impl ProfilePersistence {
     pub fn count(&self) -> Result<usize> {
         let count: i64 = self
             .conn
-            .query_row("SELECT COUNT(*) FROM profile_bindings", [], |row| row.get(0))
+            .query_row("SELECT COUNT(*) FROM profile_bindings", [], |row| {
+                row.get(0)
+            })
             .context("failed to count bindings")?;
         Ok(count as usize)
     }
@@ -204,6 +245,8 @@ mod tests {
     use super::*;
     use std::path::PathBuf;
 
+    use crate::detection::{DetectedGame, GameSource};
+
     fn temp_db() -> (tempfile::TempDir, PathBuf) {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("test.db");
@@ -271,4 +314,44 @@ mod tests {
         let bindings = persistence.list_bindings().unwrap();
         assert_eq!(bindings.len(), 2);
     }
+
+    #[test]
+    fn test_bare_id_bindings_are_migrated() {
+        let (_dir, path) = temp_db();
+        let persistence = ProfilePersistence::open(&path).unwrap();
+        let game = |source, id: &str| DetectedGame {
+            id: id.into(),
+            name: id.into(),
+            source,
+            install_dir: PathBuf::from("/games").join(id),
+            executable: None,
+            fingerprint: None,
+            metadata: Default::default(),
+        };
+        let mut db = GameDatabase::default();
+        db.merge_detected(
+            &[
+                game(GameSource::Lutris, "doom"),
+                game(GameSource::Heroic, "doom"),
+                game(GameSource::Steam, "1145360"),
+                game(GameSource::Steam, "620"),
+            ],
+            0,
+        );
+
+        persistence.bind("doom", "performance").unwrap();
+        persistence.bind("1145360", "quality").unwrap();
+        persistence.bind("620", "performance").unwrap();
+        persistence.bind("steam:620", "quality").unwrap();
+        persistence.migrate(&db).unwrap();
+
+        // Either DOOM could own the bare binding, so it stays
+        let binding = |id| persistence.get_binding(id).unwrap();
+        assert_eq!(binding("doom").as_deref(), Some("performance"));
+        assert_eq!(binding("lutris:doom"), None);
+        assert_eq!(binding("1145360"), None);
+        assert_eq!(binding("steam:1145360").as_deref(), Some("quality"));
+        assert_eq!(binding("620"), None);
+        assert_eq!(binding("steam:620").as_deref(), Some("quality"));
+    }
 }
@@ -0,0 +1,551 @@
+//! Filters for `games list` and selections for bulk operations
+//!
+//! Filters combine with AND. Size and age arguments use short suffixes:
+//! `50G`, `512M`, `90d`, `2w`, `12h`. Tags are case-insensitive.
+//!
+//! A [`Selection`] picks games for commands that change several at once:
+//!
+//! ```text
+//! source:steam AND tag:competitive
+//! (tag:coop OR tag:vr) AND NOT name:"half-life*"
+//! 12345*
+//! ```
+//!
+//! Terms are `source:`, `tag:`, `id:` and `name:`; a bare word matches the ID
+//! or the name. IDs match with or without their source (`steam:1245620`).
+//! IDs and names accept `*` and `?` wildcards, names match
+//! case-insensitively. Adjacent terms combine with AND, which binds tighter
+//! than OR.
+
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::cache;
+use crate::config::ConfigPaths;
+use crate::detection::{DetectedGame, GameDatabase, GameId};
+use crate::profile::ProfilePersistence;
+use crate::session::SessionHistory;
+
+/// Parse a size such as `50G`, `1.5T` or `700M` into bytes (binary units)
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid size '{}'", value))?;
+    let multiplier: u64 = match unit.to_ascii_uppercase().trim_end_matches(['B', 'I']) {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => {
+            return Err(format!(
+                "unknown size unit in '{}' (use K, M, G or T)",
+                value
+            ));
+        }
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// Parse an age such as `90d`, `2w` or `12h` into seconds
+pub fn parse_age(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let (number, unit) = value.split_at(value.len().saturating_sub(1));
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid age '{}' (e.g. 90d)", value))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 7 * 86400,
+        "y" => 365 * 86400,
+        _ => {
+            return Err(format!(
+                "unknown age unit in '{}' (use s, m, h, d, w or y)",
+                value
+            ));
+        }
+    };
+    Ok(number * seconds)
+}
+
+/// Parse a game tag, normalized to lowercase
+pub fn parse_tag(value: &str) -> Result<String, String> {
+    let tag = value.trim().to_lowercase();
+    if tag.is_empty() || tag.contains(|c: char| c.is_whitespace() || c == ':') {
+        return Err(format!(
+            "invalid tag '{}' (no spaces or ':' allowed)",
+            value
+        ));
+    }
+    Ok(tag)
+}
+
+/// Game sources accepted by `source:`
+const SOURCES: &[&str] = &["steam", "heroic", "lutris", "legendary", "unknown"];
+
+/// Whether `text` matches `pattern` with `*` (any run) and `?` (one character)
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it was tried at
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p + 1, t));
+            p += 1;
+        } else if let Some((after_star, tried)) = star {
+            // Let the last `*` swallow one more character
+            p = after_star;
+            t = tried + 1;
+            star = Some((after_star, t));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Games picked by an expression such as `source:steam AND tag:coop`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    /// `source:<source>`
+    Source(String),
+    /// `tag:<tag>`
+    Tag(String),
+    /// `id:<glob>`
+    Id(String),
+    /// `name:<glob>`, lowercased
+    Name(String),
+    /// A bare word, matching the ID or the name
+    Word(String),
+    Not(Box<Selection>),
+    All(Vec<Selection>),
+    Any(Vec<Selection>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Word(String),
+}
+
+impl Token {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+/// Split an expression into words and parentheses; quotes keep spaces in a word
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    for c in expr.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            }
+            _ if quoted => word.push(c),
+            '(' | ')' => {
+                if in_word {
+                    tokens.push(Token::Word(std::mem::take(&mut word)));
+                    in_word = false;
+                }
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    tokens.push(Token::Word(std::mem::take(&mut word)));
+                    in_word = false;
+                }
+            }
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quoted {
+        return Err(format!("unterminated quote in '{}'", expr));
+    }
+    if in_word {
+        tokens.push(Token::Word(word));
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over the tokens: OR of ANDs of (NOT) terms
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek().is_some_and(|t| t.is_keyword(keyword));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn parse_any(&mut self) -> Result<Selection, String> {
+        let mut terms = vec![self.parse_all()?];
+        while self.eat_keyword("or") {
+            terms.push(self.parse_all()?);
+        }
+        Ok(collapse(terms, Selection::Any))
+    }
+
+    fn parse_all(&mut self) -> Result<Selection, String> {
+        let mut terms = vec![self.parse_not()?];
+        loop {
+            if self.eat_keyword("and") {
+                terms.push(self.parse_not()?);
+                continue;
+            }
+            // Juxtaposed terms are an implicit AND
+            match self.peek() {
+                Some(Token::Close) | None => break,
+                Some(token) if token.is_keyword("or") => break,
+                Some(_) => terms.push(self.parse_not()?),
+            }
+        }
+        Ok(collapse(terms, Selection::All))
+    }
+
+    fn parse_not(&mut self) -> Result<Selection, String> {
+        if self.eat_keyword("not") {
+            return Ok(Selection::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_term()
+    }
+
+    fn parse_term(&mut self) -> Result<Selection, String> {
+        let token = self.peek().cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Open) => {
+                let inner = self.parse_any()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err("missing ')'".to_string());
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Some(Token::Word(word))
+                if !["and", "or", "not"]
+                    .iter()
+                    .any(|k| word.eq_ignore_ascii_case(k)) =>
+            {
+                Selection::term(&word)
+            }
+            Some(Token::Word(word)) => Err(format!("expected a term before '{}'", word)),
+            Some(Token::Close) => Err("unexpected ')'".to_string()),
+            None => Err("expected a term at the end".to_string()),
+        }
+    }
+}
+
+/// A single term as itself, several combined with `combine`
+fn collapse(mut terms: Vec<Selection>, combine: fn(Vec<Selection>) -> Selection) -> Selection {
+    if terms.len() == 1 {
+        terms.pop().expect("one term")
+    } else {
+        combine(terms)
+    }
+}
+
+impl Selection {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let tokens = tokenize(expr)?;
+        if tokens.is_empty() {
+            return Err("empty selection".to_string());
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let selection = parser
+            .parse_any()
+            .map_err(|e| format!("invalid selection '{}': {}", expr, e))?;
+        if parser.pos < parser.tokens.len() {
+            return Err(format!("invalid selection '{}': unexpected ')'", expr));
+        }
+        Ok(selection)
+    }
+
+    fn term(word: &str) -> Result<Self, String> {
+        let Some((key, value)) = word.split_once(':') else {
+            return Ok(Selection::Word(word.to_string()));
+        };
+        if GameId::parse(word).is_some() {
+            return Ok(Selection::Word(word.to_string()));
+        }
+        if value.is_empty() {
+            return Err(format!("'{}' needs a value", word));
+        }
+        match key.to_ascii_lowercase().as_str() {
+            "source" => {
+                let source = value.to_ascii_lowercase();
+                if !SOURCES.contains(&source.as_str()) {
+                    return Err(format!(
+                        "unknown source '{}' (use {})",
+                        value,
+                        SOURCES.join(", ")
+                    ));
+                }
+                Ok(Selection::Source(source))
+            }
+            "tag" => parse_tag(value).map(Selection::Tag),
+            "id" => Ok(Selection::Id(value.to_string())),
+            "name" => Ok(Selection::Name(value.to_lowercase())),
+            _ => Err(format!(
+                "unknown key '{}' (use source:, tag:, id: or name:)",
+                key
+            )),
+        }
+    }
+
+    /// Whether this is a single word without wildcards, i.e. meant as a game ID
+    pub fn is_plain_word(&self) -> bool {
+        matches!(self, Selection::Word(word) if !word.contains(['*', '?']))
+    }
+
+    pub fn matches(&self, game: &DetectedGame, db: &GameDatabase) -> bool {
+        match self {
+            Selection::Source(source) => game.source.to_string() == *source,
+            Selection::Tag(tag) => db.has_tags(&game.game_id(), std::slice::from_ref(tag)),
+            Selection::Id(pattern) => id_matches(pattern, game),
+            Selection::Name(pattern) => glob_match(pattern, &game.name.to_lowercase()),
+            Selection::Word(word) => {
+                id_matches(word, game)
+                    || glob_match(&word.to_lowercase(), &game.name.to_lowercase())
+            }
+            Selection::Not(inner) => !inner.matches(game, db),
+            Selection::All(terms) => terms.iter().all(|t| t.matches(game, db)),
+            Selection::Any(terms) => terms.iter().any(|t| t.matches(game, db)),
+        }
+    }
+
+    /// Matching games, one per title: the preferred source when it matches,
+    /// otherwise the first matching duplicate
+    pub fn select(&self, db: &GameDatabase) -> Vec<DetectedGame> {
+        db.unique_games()
+            .into_iter()
+            .filter_map(|group| {
+                std::iter::once(group.primary)
+                    .chain(group.duplicates)
+                    .find(|game| self.matches(game, db))
+            })
+            .collect()
+    }
+}
+
+fn id_matches(pattern: &str, game: &DetectedGame) -> bool {
+    glob_match(pattern, &game.id) || glob_match(pattern, &game.game_id().to_string())
+}
+
+/// Profile condition: no profile assigned, or a specific one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileFilter {
+    None,
+    Named(String),
+}
+
+impl ProfileFilter {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        Ok(match value {
+            "none" => ProfileFilter::None,
+            name => ProfileFilter::Named(name.to_string()),
+        })
+    }
+}
+
+/// Conditions a listed game must meet
+#[derive(Debug, Clone, Default)]
+pub struct GameFilter {
+    pub profile: Option<ProfileFilter>,
+    pub missing_exe: bool,
+    /// Minimum install size in bytes
+    pub larger_than: Option<u64>,
+    /// Seconds since the last recorded session
+    pub not_played_since: Option<u64>,
+    /// Tags the game must all have
+    pub tags: Vec<String>,
+}
+
+impl GameFilter {
+    pub fn is_empty(&self) -> bool {
+        self.profile.is_none()
+            && !self.missing_exe
+            && self.larger_than.is_none()
+            && self.not_played_since.is_none()
+            && self.tags.is_empty()
+    }
+
+    /// Build a predicate checking every condition
+    ///
+    /// Profile bindings, session history and install sizes are only read for
+    /// the conditions that need them.
+    pub fn matcher<'a>(
+        &'a self,
+        db: &'a GameDatabase,
+        paths: &'a ConfigPaths,
+    ) -> impl Fn(&DetectedGame) -> bool + 'a {
+        let persistence = self
+            .profile
+            .as_ref()
+            .and_then(|_| ProfilePersistence::open(&ProfilePersistence::path(paths)).ok())
+            .inspect(|p| {
+                let _ = p.migrate(db);
+            });
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        move |game| {
+            if !db.has_tags(&game.game_id(), &self.tags) {
+                return false;
+            }
+
+            if let Some(ref wanted) = self.profile {
+                let profile = db
+                    .get_game_profile(&game.game_id())
+                    .map(String::from)
+                    .or_else(|| {
+                        persistence
+                            .as_ref()
+                            .and_then(|p| p.get_binding(&game.game_id().to_string()).ok().flatten())
+                    });
+                let matches = match wanted {
+                    ProfileFilter::None => profile.is_none(),
+                    ProfileFilter::Named(name) => profile.as_deref() == Some(name.as_str()),
+                };
+                if !matches {
+                    return false;
+                }
+            }
+
+            if self.missing_exe && game.executable.as_deref().is_some_and(Path::exists) {
+                return false;
+            }
+
+            if let Some(max_age) = self.not_played_since {
+                let last_played = SessionHistory::for_game(paths, db, &game.game_id())
+                    .ok()
+                    .and_then(|h| h.last().map(|s| s.started_at));
+                if last_played.is_some_and(|t| now.saturating_sub(t) < max_age) {
+                    return false;
+                }
+            }
+
+            // Last: walking the install directory is the slowest check
+            if let Some(min_size) = self.larger_than
+                && cache::directory_size(&game.install_dir) <= min_size
+            {
+                return false;
+            }
+
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("50G"), Ok(50 << 30));
+        assert_eq!(parse_size("512MiB"), Ok(512 << 20));
+        assert_eq!(parse_size("1.5K"), Ok(1536));
+        assert!(parse_size("10Q").is_err());
+    }
+
+    #[test]
+    fn test_parse_tag() {
+        assert_eq!(parse_tag(" Co-op "), Ok("co-op".to_string()));
+        assert!(parse_tag("").is_err());
+        assert!(parse_tag("tag:vr").is_err());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("1245*", "1245620"));
+        assert!(glob_match("*ring", "elden ring"));
+        assert!(glob_match("h?des", "hades"));
+        assert!(glob_match("*a*b*", "xaxxbx"));
+        assert!(!glob_match("1245*", "9124562"));
+        assert!(!glob_match("hades", "hades ii"));
+    }
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(
+            Selection::parse("source:steam AND tag:Competitive"),
+            Ok(Selection::All(vec![
+                Selection::Source("steam".into()),
+                Selection::Tag("competitive".into()),
+            ]))
+        );
+        // AND binds tighter than OR; juxtaposition is AND
+        assert_eq!(
+            Selection::parse(r#"tag:coop OR not name:"Elden Ring" id:12*"#),
+            Ok(Selection::Any(vec![
+                Selection::Tag("coop".into()),
+                Selection::All(vec![
+                    Selection::Not(Box::new(Selection::Name("elden ring".into()))),
+                    Selection::Id("12*".into()),
+                ]),
+            ]))
+        );
+        assert_eq!(
+            Selection::parse("(tag:a or tag:b) and hades"),
+            Ok(Selection::All(vec![
+                Selection::Any(vec![Selection::Tag("a".into()), Selection::Tag("b".into())]),
+                Selection::Word("hades".into()),
+            ]))
+        );
+        assert!(Selection::parse("1245620").unwrap().is_plain_word());
+        assert!(Selection::parse("steam:1245620").unwrap().is_plain_word());
+        for invalid in [
+            "",
+            "source:gog",
+            "size:5",
+            "tag:a AND",
+            "(tag:a",
+            "tag:a)",
+            "OR tag:a",
+        ] {
+            assert!(Selection::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("90d"), Ok(90 * 86400));
+        assert_eq!(parse_age("2w"), Ok(14 * 86400));
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("5x").is_err());
+    }
+}
@@ -0,0 +1,257 @@
+//! Opt-in anonymous compatibility reports
+//!
+//! With `reporting.enabled` and `reporting.endpoint` set in the config, each
+//! session launched through `nvproton run` submits one datapoint (game ID,
+//! driver, Proton version, the profile keys in use, and the outcome) as JSON.
+//! `nvproton report share <game_id>` submits the latest crash report with
+//! home directory paths scrubbed and the environment reduced to
+//! graphics/Proton variables. Together these feed the community quirks
+//! database.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use serde_yaml::Value;
+
+use crate::config::{ConfigManager, NvConfig, ReportingConfig};
+use crate::detection::{DetectedGame, GameSource};
+use crate::runner::RunContext;
+use crate::triage::{self, TriageReport};
+
+/// Environment variable prefixes kept in shared triage bundles
+const SHARED_ENV_PREFIXES: &[&str] = &[
+    "DXVK_",
+    "VKD3D_",
+    "PROTON_",
+    "WINE",
+    "__GL_",
+    "VK_",
+    "MANGOHUD",
+    "ENABLE_",
+    "NVPROTON_",
+];
+
+/// One anonymous compatibility datapoint
+#[derive(Debug, Clone, Serialize)]
+pub struct CompatReport {
+    pub nvproton_version: String,
+    pub game_id: String,
+    pub source: GameSource,
+    pub gpu_name: Option<String>,
+    pub driver_version: Option<String>,
+    pub proton_version: Option<String>,
+    /// Dotted setting keys of the applied profile (values are not sent)
+    pub profile_keys: Vec<String>,
+    pub success: bool,
+    pub crashed: bool,
+    pub runtime_secs: u64,
+}
+
+impl CompatReport {
+    pub fn new(
+        ctx: &RunContext,
+        game: &DetectedGame,
+        profile: Option<&str>,
+        success: bool,
+        runtime: Duration,
+    ) -> Self {
+        let profile_keys = profile
+            .and_then(|name| ctx.profile_manager.resolve(name).ok())
+            .map(|resolved| setting_keys(&resolved.settings))
+            .unwrap_or_default();
+
+        Self {
+            nvproton_version: env!("CARGO_PKG_VERSION").to_string(),
+            game_id: game.id.clone(),
+            source: game.source.clone(),
+            gpu_name: ctx.vulkan_caps.as_ref().map(|c| c.gpu_name.clone()),
+            driver_version: ctx.vulkan_caps.as_ref().map(|c| c.driver_version.clone()),
+            proton_version: ctx.proton_nv.as_ref().map(|p| p.version.clone()),
+            profile_keys,
+            success,
+            crashed: triage::is_crash(success, runtime, &game.source),
+            runtime_secs: runtime.as_secs(),
+        }
+    }
+}
+
+/// Submit a session datapoint if reporting is enabled
+pub fn report_session(config: &ReportingConfig, report: &CompatReport) -> Result<()> {
+    if !config.enabled {
+        return Ok(());
+    }
+    let Some(endpoint) = config.endpoint.as_deref() else {
+        bail!("reporting is enabled but reporting.endpoint is not set");
+    };
+    submit(
+        &format!("{}/compat", endpoint.trim_end_matches('/')),
+        report,
+    )
+}
+
+/// Handle `report share`
+pub fn share_report(manager: &ConfigManager, config: &NvConfig, game_id: &str) -> Result<()> {
+    let Some(endpoint) = config.reporting.endpoint.as_deref() else {
+        bail!("set a reporting endpoint first: nvproton config set reporting.endpoint <URL>");
+    };
+    let reports = triage::list_reports(manager.paths(), game_id)?;
+    let Some(latest) = reports.last() else {
+        bail!("no crash reports found for '{}'", game_id);
+    };
+    let report = anonymize(TriageReport::load(latest)?, dirs::home_dir().as_deref());
+
+    submit(
+        &format!("{}/triage", endpoint.trim_end_matches('/')),
+        &report,
+    )?;
+    println!(
+        "Shared crash report for {} ({})",
+        report.game_name,
+        latest.display()
+    );
+    Ok(())
+}
+
+/// Drop personal details from a triage report before it leaves the machine
+fn anonymize(mut report: TriageReport, home: Option<&Path>) -> TriageReport {
+    report
+        .env
+        .retain(|key, _| SHARED_ENV_PREFIXES.iter().any(|p| key.starts_with(p)));
+
+    let Some(home) = home.map(|h| h.to_string_lossy().to_string()) else {
+        return report;
+    };
+    let scrub = |s: &mut String| *s = s.replace(&home, "~");
+    report.launch_command.iter_mut().for_each(scrub);
+    report.env.values_mut().for_each(scrub);
+    report.log_tails = std::mem::take(&mut report.log_tails)
+        .into_iter()
+        .map(|(mut path, mut lines)| {
+            scrub(&mut path);
+            lines.iter_mut().for_each(scrub);
+            (path, lines)
+        })
+        .collect();
+    for xid in &mut report.xid_errors {
+        scrub(&mut xid.message);
+    }
+    if let Some(ref mut analysis) = report.proton_log {
+        analysis.path = analysis
+            .path
+            .as_ref()
+            .map(|p| PathBuf::from(p.to_string_lossy().replace(&home, "~")));
+    }
+    report
+}
+
+/// Dotted paths of the leaf settings in a profile
+fn setting_keys(settings: &Value) -> Vec<String> {
+    fn walk(value: &Value, prefix: &str, keys: &mut Vec<String>) {
+        match value {
+            Value::Mapping(map) => {
+                for (key, child) in map {
+                    let Some(key) = key.as_str() else { continue };
+                    let path = if prefix.is_empty() {
+                        key.to_string()
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    walk(child, &path, keys);
+                }
+            }
+            _ if !prefix.is_empty() => keys.push(prefix.to_string()),
+            _ => {}
+        }
+    }
+    let mut keys = Vec::new();
+    walk(settings, "", &mut keys);
+    keys.sort();
+    keys
+}
+
+/// POST a JSON body with curl
+fn submit(url: &str, body: &impl Serialize) -> Result<()> {
+    let json = serde_json::to_vec(body)?;
+    let mut child = Command::new("curl")
+        .args([
+            "--fail",
+            "--silent",
+            "--show-error",
+            "--max-time",
+            "10",
+            "--header",
+            "Content-Type: application/json",
+            "--data-binary",
+            "@-",
+        ])
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to run curl")?;
+    child
+        .stdin
+        .take()
+        .context("failed to open curl stdin")?
+        .write_all(&json)?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "failed to submit to {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_setting_keys() {
+        let settings: Value =
+            serde_yaml::from_str("dxvk:\n  hud: fps\n  frame_rate: 60\nproton:\n  nvapi: true\n")
+                .unwrap();
+        assert_eq!(
+            setting_keys(&settings),
+            vec!["dxvk.frame_rate", "dxvk.hud", "proton.nvapi"]
+        );
+    }
+
+    #[test]
+    fn test_anonymize_scrubs_home_and_env() {
+        let report = TriageReport {
+            game_id: "1245620".into(),
+            game_name: "Elden Ring".into(),
+            source: GameSource::Steam,
+            created_at: 0,
+            exit_code: Some(1),
+            runtime_secs: 3,
+            driver_version: None,
+            gpu_name: None,
+            proton_version: None,
+            launch_command: vec!["/home/alex/.steam/proton".into(), "run".into()],
+            env: [
+                ("DXVK_HUD".to_string(), "fps".to_string()),
+                ("GITHUB_TOKEN".to_string(), "secret".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+            xid_errors: Vec::new(),
+            log_tails: Default::default(),
+            proton_log: None,
+        };
+
+        let report = anonymize(report, Some(Path::new("/home/alex")));
+        assert_eq!(report.launch_command[0], "~/.steam/proton");
+        assert!(report.env.contains_key("DXVK_HUD"));
+        assert!(!report.env.contains_key("GITHUB_TOKEN"));
+    }
+}
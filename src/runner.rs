@@ -1,37 +1,101 @@
 use std::collections::HashMap;
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::io::{self, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-
-use anyhow::{Context, Result};
-
-use crate::cli::{DescriptorHeapMode, PrepareArgs, RunArgs};
-use crate::config::{ConfigManager, NvConfig};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+
+use crate::anticheat;
+use crate::cache::{CacheManager, CachePaths, format_bytes};
+use crate::cli::{
+    DescriptorHeapMode, LaunchOptions, PrepareArgs, RetryPolicy, RunArgs, SteamInputMode, SyncMode,
+    WrapArgs,
+};
+use crate::config::{ConfigManager, ConfigPaths, NvConfig, RunDefaults, RunOverrides};
+use crate::detection::display_server::{DisplaySession, VrrMechanism};
+use crate::detection::distro;
+use crate::detection::pe::{Arch, PeInfo};
 use crate::detection::proton_nv::{ProtonNvDetector, ProtonNvEnv, ProtonNvInstallation};
-use crate::detection::{DetectedGame, GameDatabase, GameSource, VulkanCapabilities};
+use crate::detection::runtime;
+use crate::detection::steam::InstallState;
+use crate::detection::vulkan::SMOOTH_MOTION_MIN_DRIVER;
+use crate::detection::{DetectedGame, GameDatabase, GameId, GameSource, VulkanCapabilities};
+use crate::dxvk::{self, OverrideStore};
+use crate::dxvk_hud;
+use crate::env_layers::{EnvConflict, EnvLayers};
+use crate::errors::NvError;
 use crate::ffi;
+use crate::gl_tuning;
+use crate::hooks::{self, HookRunner, display, replay};
+use crate::i18n::tr;
+use crate::input;
+use crate::launch_args::LaunchArgs;
+use crate::logs;
+use crate::manifest::LaunchManifest;
+use crate::media;
+use crate::memory::{self, MemoryAdvice, MemoryStatus};
+use crate::modloaders;
+use crate::notify;
+use crate::nvapi::{self, DlssDlls, NvapiStore};
+use crate::paths;
+use crate::plan_cache::{self, LaunchPlan, PlanCache};
 use crate::profile::{ProfileManager, ProfilePersistence};
+use crate::reporting::{self, CompatReport};
+use crate::running::SessionLock;
+use crate::runtimes;
+use crate::saves;
+use crate::session::{RecentGame, RecentGames, Session, SessionHistory};
+use crate::steam::client::{CloudFile, CloudSync, SteamClient, app_id_from_env};
+use crate::streaming;
+use crate::targets::{self, LaunchTarget};
+use crate::telemetry::{self, TelemetrySampler, TelemetrySummary};
+use crate::template::TemplateContext;
+use crate::term::{Cell, Table, Tone};
+use crate::tracking::{self, GameMatcher, Tracked};
+use crate::triage::{self, TriageReport};
+use crate::umu;
+use crate::undo::Recorder;
+use crate::vram::{self, VramWarning};
+use crate::wine_sync::{self, SyncMechanism, SyncSupport};
+use crate::wine_wayland;
+use crate::wrappers::WrapperChain;
+use crate::xid::XidMonitor;
+
+/// How often the appmanifest is re-read while waiting for an update
+const UPDATE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Games offered by `run --recent`
+const RECENT_CHOICES: usize = 5;
 
 /// Runtime context for game launching
 pub struct RunContext<'a> {
-    #[allow(dead_code)]
     pub config: &'a NvConfig,
-    #[allow(dead_code)]
     pub manager: &'a ConfigManager,
     pub profile_manager: ProfileManager,
     pub profile_persistence: ProfilePersistence,
     pub game_db: GameDatabase,
     pub proton_nv: Option<ProtonNvInstallation>,
     pub vulkan_caps: Option<VulkanCapabilities>,
+    pub display: DisplaySession,
 }
 
 impl<'a> RunContext<'a> {
     pub fn new(config: &'a NvConfig, manager: &'a ConfigManager) -> Result<Self> {
         let profile_manager = ProfileManager::new(manager.paths().profiles_dir.clone());
-        let db_path = manager.paths().user_config_dir.join("profiles.db");
+        let db_path = ProfilePersistence::path(manager.paths());
         let profile_persistence = ProfilePersistence::open(&db_path)
             .context("failed to open profile persistence database")?;
         let game_db = GameDatabase::load_or_default(manager.paths())?;
+        if let Err(e) = profile_persistence.migrate(&game_db) {
+            log::warn!("{:#}", e);
+        }
 
         // Detect Proton-NV installation
         let proton_nv = {
@@ -52,11 +116,7 @@ impl<'a> RunContext<'a> {
         // Detect Vulkan capabilities (for descriptor_heap support)
         let vulkan_caps = match VulkanCapabilities::detect() {
             Ok(caps) => {
-                log::info!(
-                    "Vulkan: {} (driver {})",
-                    caps.gpu_name,
-                    caps.driver_version
-                );
+                log::info!("Vulkan: {} (driver {})", caps.gpu_name, caps.driver_version);
                 if caps.descriptor_heap {
                     log::info!("VK_EXT_descriptor_heap: supported");
                 }
@@ -76,71 +136,1023 @@ impl<'a> RunContext<'a> {
             game_db,
             proton_nv,
             vulkan_caps,
+            display: DisplaySession::detect(),
         })
     }
 
     /// Find a game by ID or name
     pub fn find_game(&self, id: Option<&str>, name: Option<&str>) -> Result<DetectedGame> {
+        // Inside Steam the appid comes from the launch environment
+        let steam_id = if id.is_none() && name.is_none() {
+            app_id_from_env()
+        } else {
+            None
+        };
+
         if let Some(game_id) = id
-            && let Some(game) = self.game_db.get(game_id)
+            && self.game_db.lookup(game_id).is_some()
         {
-            return Ok(game.clone());
+            return self.game_db.resolve_game(game_id);
+        }
+        if let Some(app_id) = &steam_id
+            && let Some(game) = self
+                .game_db
+                .get(&GameId::new(GameSource::Steam, app_id.clone()))
+        {
+            return Ok(game);
         }
 
         if let Some(game_name) = name {
             let name_lower = game_name.to_lowercase();
-            for game in self.game_db.games() {
-                if game.name.to_lowercase().contains(&name_lower) {
-                    return Ok(game.clone());
+            // Duplicates resolve to their preferred launch source
+            for group in self.game_db.unique_games() {
+                if group.primary.name.to_lowercase().contains(&name_lower) {
+                    return Ok(group.primary);
                 }
             }
         }
 
-        anyhow::bail!(
-            "Game not found. Run 'nvproton games scan' to detect games, or use 'nvproton games list' to see available games."
-        )
+        let query = id
+            .or(steam_id.as_deref())
+            .or(name)
+            .unwrap_or("(none given)");
+        Err(NvError::GameNotFound(query.to_string()).into())
     }
 }
 
 /// Handle the `run` command
-pub fn handle_run(args: RunArgs, manager: &ConfigManager, config: &mut NvConfig) -> Result<()> {
+pub fn handle_run(mut args: RunArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
     let ctx = RunContext::new(config, manager)?;
-    let game = ctx.find_game(args.game_id.as_deref(), args.name.as_deref())?;
+    let mut game = if args.last || args.recent {
+        let recent = pick_recent(manager.paths(), args.recent)?;
+        if args.launch.profile.is_none() {
+            args.launch.profile = recent.profile;
+        }
+        ctx.find_game(Some(&recent.game_id), None)?
+    } else {
+        ctx.find_game(args.game_id.as_deref(), args.name.as_deref())?
+    };
+
+    println!("{}", tr!("run-running", name = game.name, id = game.id));
+    let target = match &args.target {
+        Some(name) => Some(pick_target(&ctx, &game, name)?),
+        None => None,
+    };
+
+    let LaunchEnv {
+        mut env_vars,
+        mut hooks,
+        profile_name,
+        wrappers,
+        frame_generation,
+        sync,
+        launch_args,
+        conflicts,
+    } = build_launch_env(&ctx, &game, &args.launch)?;
+    if args.strict && !conflicts.is_empty() {
+        print_env_conflicts(&conflicts);
+        let mut keys: Vec<_> = conflicts.iter().map(|c| c.key.as_str()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        bail!(
+            "variables set differently by two layers (--strict): {}",
+            keys.join(", ")
+        );
+    }
+
+    // Proton updates (Experimental in particular) can break games that worked
+    let proton = runtimes::compat_tool(config, &game);
+    if let Some(current) = &proton
+        && let Ok(history) =
+            SessionHistory::for_game(manager.paths(), &ctx.game_db, &game.game_id())
+        && let Some(good) = history.last_known_good()
+        && good != current
+    {
+        eprintln!(
+            "  {}",
+            tr!("run-proton-untested", current = current, good = good)
+        );
+    }
+
+    // Shader pre-warming
+    if !args.no_prewarm {
+        println!("  {}", tr!("run-prewarming"));
+        if let Err(e) = prewarm_shaders(&game) {
+            eprintln!("  {}", tr!("run-prewarm-failed", error = e));
+        }
+    }
+
+    // With --wrapper (`nvproton run --wrapper -- %command%`) the game args
+    // are Steam's composed command; otherwise build the command from the
+    // game source
+    let steam_wrapper = args.wrapper;
+    // umu-run runs the game itself, even for launchers that hand it off
+    let via_umu = !steam_wrapper && umu::applies(config, args.umu, &game);
+    let mut game_args = run_game_args(&args, &launch_args, &game.install_dir);
+    if let Some(target) = &target {
+        let exe = format!("{:?}", target.executable);
+        println!("  {}", tr!("run-target", name = target.name, exe = exe));
+        if steam_wrapper {
+            game_args = targets::retarget(&game_args, &game.install_dir, target)?;
+        } else {
+            aim_at_target(&mut game, target, via_umu, &mut game_args)?;
+        }
+    }
+    let launch_cmd = if steam_wrapper {
+        game_args
+    } else if via_umu {
+        umu::launch_command(
+            config,
+            manager.paths(),
+            &ctx.game_db,
+            &game,
+            &game_args,
+            &mut env_vars,
+        )?
+    } else {
+        build_launch_command(&game, &game_args)?
+    };
+    let launch_cmd = wrappers.apply(&game, launch_cmd, steam_wrapper);
+    if steam_wrapper {
+        route_through_container(&launch_cmd, &mut env_vars);
+    } else if game.source == GameSource::Steam {
+        check_pending_update(&game, args.wait_for_update && !args.dry_run)?;
+        if let Some(steam) = SteamClient::from_config(config) {
+            check_cloud_sync(&steam, &game);
+            // `steam -applaunch` starts the client first, which takes a while
+            if !args.dry_run && !steam.is_running() {
+                println!("  {}", tr!("run-steam-starting"));
+            }
+        }
+    }
+
+    if let Some(ref path) = args.emit_script {
+        let script =
+            render_launch_script(&game, &env_vars, &launch_cmd, launch_args.cwd.as_deref());
+        write_launch_script(Path::new(path), &script)?;
+        println!("\n{}", tr!("run-script-written", path = path));
+        return Ok(());
+    }
+
+    if args.dry_run {
+        println!("\n{}", tr!("run-dry-run"));
+        print_launch_details(
+            &launch_cmd,
+            launch_args.cwd.as_deref(),
+            &env_vars,
+            &hooks,
+            &conflicts,
+        );
+        let session = ctx.display.describe();
+        let vrr = ctx.display.vrr;
+        println!(
+            "  {}",
+            tr!("run-dry-run-session", session = session, vrr = vrr)
+        );
+        let dlss = DlssDlls::find(&game.install_dir);
+        if !dlss.is_empty() {
+            let features = dlss.describe();
+            println!("  {}", tr!("run-dry-run-dlss", features = features));
+            if dlss.frame_generation.is_some() && !frame_generation {
+                println!("  {}", tr!("run-dry-run-dlss-fg-off"));
+            }
+        }
+        return Ok(());
+    }
+
+    pre_launch_checks(config, args.strict)?;
+    let mut lock = SessionLock::acquire(manager.paths(), &game, profile_name.clone(), args.force)?;
+    // Asked for explicitly, so a missing NVML stops the launch
+    let sampler = if args.telemetry {
+        Some(TelemetrySampler::start()?)
+    } else {
+        None
+    };
+
+    // Execute the game
+    println!("\n{}", tr!("run-launching", name = game.name));
+    let recent = RecentGame::now(&game, profile_name.clone());
+    if let Err(e) = RecentGames::record(manager.paths(), recent) {
+        eprintln!("  {}", tr!("run-recent-failed", error = e));
+    }
+
+    let mut cmd = Command::new(&launch_cmd[0]);
+    cmd.args(&launch_cmd[1..]);
+    cmd.envs(&env_vars);
+    if let Some(dir) = &launch_args.cwd {
+        cmd.current_dir(dir);
+    }
+
+    // Inherit current env
+    for (key, value) in env::vars() {
+        if !env_vars.contains_key(&key) {
+            cmd.env(key, value);
+        }
+    }
+
+    // As a Steam wrapper, replace this process so Steam tracks the game's PID
+    // (overlay, Reflex markers). Supervision features need nvproton to stay
+    // around as the parent, so they keep spawn mode.
+    let proton_log = env_vars
+        .get("PROTON_LOG")
+        .is_some_and(|v| !v.is_empty() && v != "0");
+    let supervise = args.supervise
+        || args.monitor
+        || args.telemetry
+        || args.manifest
+        || proton_log
+        || !hooks.is_empty();
+    if steam_wrapper && !supervise {
+        // The game takes over this PID, and with it the lock
+        lock.set_game_pid(std::process::id());
+        let err = cmd.exec();
+        return Err(err).context(NvError::LaunchFailed(launch_cmd[0].clone()));
+    }
+
+    let game_key = game.game_id().to_string();
+    let mut session = Session::begin(profile_name.clone());
+    session.proton = proton;
+    if args.manifest {
+        let mut manifest = LaunchManifest::capture(&ctx, &game, &session, &launch_cmd, &env_vars);
+        manifest.sync = sync;
+        match manifest.save(manager.paths()) {
+            Ok(path) => println!("  {}", tr!("run-manifest", path = path.display())),
+            Err(e) => eprintln!("  {}", tr!("run-manifest-failed", error = e)),
+        }
+    }
+    let monitor = if args.monitor {
+        XidMonitor::start()
+    } else {
+        None
+    };
+    hooks.start();
+    let started = Instant::now();
+    let (success, exit_code, game_runtime) = if !steam_wrapper && !via_umu && hands_off_game(&game)
+    {
+        let start_timeout = Duration::from_secs(args.launch_timeout);
+        run_handed_off(cmd, &game, &launch_cmd[0], start_timeout)?
+    } else {
+        let mut child = cmd
+            .spawn()
+            .context(NvError::LaunchFailed(launch_cmd[0].clone()))?;
+        lock.set_game_pid(child.id());
+        let status = child.wait()?;
+        if !status.success() {
+            eprintln!("{}", tr!("run-exit-status", status = status));
+        }
+        (status.success(), status.code(), None)
+    };
+    // Waiting for Steam or Heroic to start the game isn't play time, and
+    // would hide games dying right after they start
+    let runtime = game_runtime.unwrap_or_else(|| started.elapsed());
+    hooks.finish();
+
+    // Annotate the session history with Xid errors seen during the run
+    session.finish(success, exit_code, runtime);
+    if let Some(monitor) = monitor {
+        session.xid_errors = monitor.stop();
+        if !session.xid_errors.is_empty() {
+            let count = session.xid_errors.len();
+            eprintln!("{}", tr!("run-xid-errors", count = count));
+            for xid in &session.xid_errors {
+                eprintln!("  Xid {} - {}", xid.code, xid.description());
+            }
+        }
+    }
+    if let Some(sampler) = sampler {
+        let samples = sampler.stop();
+        session.telemetry = Some(TelemetrySummary::from_samples(&samples));
+        let path = SessionHistory::telemetry_path(manager.paths(), &game_key, &session);
+        if let Err(e) = telemetry::save_samples(&path, &samples) {
+            eprintln!("  {}", tr!("run-telemetry-failed", error = e));
+        }
+    }
+    if let Err(e) = SessionHistory::record(manager.paths(), &game_key, session) {
+        eprintln!("  {}", tr!("run-session-failed", error = e));
+    }
+
+    // Keep a copy of the saves in case the next Proton upgrade breaks them
+    if config.saves.backup_on_exit {
+        match saves::backup(manager.paths(), config, &game) {
+            Ok(Some(backup)) => {
+                let files = backup.files;
+                println!("  {}", tr!("run-saves-backed-up", files = files));
+            }
+            Ok(None) => log::info!("no save files found for {}", game.id),
+            Err(e) => eprintln!("  {}", tr!("run-saves-failed", error = e)),
+        }
+    }
+
+    // Opt-in anonymous compatibility datapoint
+    let compat = CompatReport::new(&ctx, &game, profile_name.as_deref(), success, runtime);
+    if let Err(e) = reporting::report_session(&ctx.config.reporting, &compat) {
+        eprintln!("  {}", tr!("run-report-failed", error = e));
+    }
+
+    // Gather a triage bundle for crashes
+    if triage::is_crash(success, runtime, &game.source) {
+        let report = TriageReport::collect(&ctx, &game, &launch_cmd, &env_vars, exit_code, runtime);
+        match report.save(manager.paths()) {
+            Ok(path) => {
+                eprintln!("{}", tr!("run-crash-report", path = path.display()));
+                eprintln!("  {}", tr!("run-crash-report-hint", id = game.id));
+                notify::finished(
+                    &ctx.config.notifications,
+                    notify::Kind::Crash,
+                    runtime,
+                    &tr!("run-crash-notify", name = game.name),
+                    &tr!("run-crash-report", path = path.display()),
+                );
+            }
+            Err(e) => eprintln!("  {}", tr!("run-crash-report-failed", error = e)),
+        }
+    }
+
+    // Diagnose the Proton log if logging was enabled
+    if proton_log {
+        match logs::proton_log_path(&game.id, &env_vars) {
+            Some(path) if path.exists() => match logs::analyze_file(&path) {
+                Ok(analysis) => {
+                    println!();
+                    logs::print_summary(&analysis);
+                }
+                Err(e) => eprintln!("  {}", tr!("run-log-analysis-failed", error = e)),
+            },
+            Some(path) => {
+                log::info!("Proton log not found at {:?}", path);
+            }
+            None => {}
+        }
+    }
+
+    // A game dying right after starting is often fixed by a second attempt
+    // with less in the way (stale shader caches after a driver update)
+    let window = Duration::from_secs(args.retry_within);
+    if args.retry != RetryPolicy::Off && triage::died_early(runtime, window, &game.source) {
+        let secs = runtime.as_secs();
+        eprintln!("\n{}", tr!("run-retrying", secs = secs));
+        prepare_retry(&mut args, &game);
+        drop(lock);
+        return handle_run(args, manager, config);
+    }
+
+    Ok(())
+}
+
+/// Append entries to `WINEDLLOVERRIDES`, after those already set
+fn add_dll_overrides(env_vars: &mut HashMap<String, String>, dll_overrides: String) {
+    let value = match env_vars.get("WINEDLLOVERRIDES") {
+        Some(existing) if !existing.is_empty() => format!("{};{}", existing, dll_overrides),
+        _ => dll_overrides,
+    };
+    env_vars.insert("WINEDLLOVERRIDES".into(), value);
+}
+
+/// Resolve `run --target` among the game's own, Steam's and mod manager targets
+fn pick_target(ctx: &RunContext, game: &DetectedGame, name: &str) -> Result<LaunchTarget> {
+    let steam = SteamClient::from_config(ctx.config);
+    let user = ctx.game_db.get_game_targets(&game.game_id());
+    let prefix = saves::wine_prefix(ctx.config, game);
+    let loaders = modloaders::detect(game, prefix.as_deref());
+    let available = targets::available(user, steam.as_ref(), game, &loaders);
+    targets::find(&available, name).cloned()
+}
+
+/// Point a launch nvproton starts itself at a target, its arguments first
+fn aim_at_target(
+    game: &mut DetectedGame,
+    target: &LaunchTarget,
+    via_umu: bool,
+    game_args: &mut Vec<String>,
+) -> Result<()> {
+    let mut args = target.arguments.clone();
+    match game.source {
+        _ if via_umu => {}
+        GameSource::Unknown => {}
+        GameSource::Legendary => {
+            // legendary looks the override up in the install directory
+            let exe = target
+                .executable
+                .strip_prefix(&game.install_dir)
+                .unwrap_or(&target.executable);
+            let exe = exe.to_string_lossy().into_owned();
+            args.splice(0..0, ["--override-exe".to_string(), exe]);
+        }
+        GameSource::Steam => bail!(
+            "Steam picks the executable of 'steam -applaunch'; set the game's launch options to 'nvproton run --target {} -- %command%' instead",
+            target.name
+        ),
+        GameSource::Heroic | GameSource::Lutris => bail!(
+            "{} picks the executable itself; add --umu to launch target '{}'",
+            game.source,
+            target.name
+        ),
+    }
+    game.executable = Some(target.executable.clone());
+    game_args.splice(0..0, args);
+    Ok(())
+}
+
+/// Warn about missing VRAM or RAM headroom, or fail with `strict`
+fn pre_launch_checks(config: &NvConfig, strict: bool) -> Result<()> {
+    let mut failed = check_vram(config);
+    if config.memory.check_before_launch {
+        failed |= check_memory(config);
+    }
+    if strict && failed {
+        bail!("not enough headroom to launch (--strict)");
+    }
+    Ok(())
+}
+
+/// Print VRAM warnings, returning whether there were any
+fn check_vram(config: &NvConfig) -> bool {
+    let warnings = vram::check(&config.vram);
+    for warning in &warnings {
+        match warning {
+            VramWarning::LowFree {
+                free_bytes,
+                total_bytes,
+            } => {
+                let free = format_bytes(*free_bytes);
+                let total = format_bytes(*total_bytes);
+                eprintln!("  {}", tr!("run-vram-low", free = free, total = total));
+            }
+            VramWarning::HeavyProcess(process) => {
+                let used = process
+                    .vram_used_bytes
+                    .map_or_else(|| "?".to_string(), format_bytes);
+                eprintln!(
+                    "  {}",
+                    tr!(
+                        "run-vram-process",
+                        name = process.name,
+                        pid = process.pid,
+                        kind = process.kind,
+                        used = used
+                    )
+                );
+            }
+        }
+    }
+    !warnings.is_empty()
+}
+
+/// Print the memory problems that matter for a launch, returning whether
+/// there were any
+fn check_memory(config: &NvConfig) -> bool {
+    let advice: Vec<_> = MemoryStatus::detect()
+        .advice(config.memory.min_available_mb)
+        .into_iter()
+        .filter(MemoryAdvice::affects_launch)
+        .collect();
+    for item in &advice {
+        match *item {
+            MemoryAdvice::LowAvailable {
+                available_bytes,
+                total_bytes,
+            } => {
+                let available = format_bytes(available_bytes);
+                let total = format_bytes(total_bytes);
+                eprintln!(
+                    "  {}",
+                    tr!("run-memory-low", available = available, total = total)
+                );
+            }
+            MemoryAdvice::MaxMapCountLow { current } => {
+                let recommended = memory::RECOMMENDED_MAX_MAP_COUNT;
+                eprintln!(
+                    "  {}",
+                    tr!(
+                        "run-max-map-count-low",
+                        current = current,
+                        recommended = recommended
+                    )
+                );
+            }
+            MemoryAdvice::NoSwap | MemoryAdvice::NoZram => {}
+        }
+    }
+    !advice.is_empty()
+}
+
+/// Adjust `run` arguments for the single retry of `--retry`
+fn prepare_retry(args: &mut RunArgs, game: &DetectedGame) {
+    let policy = args.retry;
+    args.retry = RetryPolicy::Off;
+    args.game_id = Some(game.game_id().to_string());
+    args.name = None;
+    args.last = false;
+    args.recent = false;
+    args.wait_for_update = false;
+    args.no_prewarm = true;
+    println!("  {}", tr!("run-retry-no-prewarm"));
+
+    if matches!(policy, RetryPolicy::CleanCache | RetryPolicy::Safe) {
+        match CacheManager::new().and_then(|cache| cache.clear_game(&game.game_id())) {
+            Ok(freed) => println!(
+                "  {}",
+                tr!("run-retry-cache-cleared", size = format_bytes(freed))
+            ),
+            Err(e) => eprintln!("  {}", tr!("run-retry-cache-failed", error = e)),
+        }
+    }
+
+    if policy == RetryPolicy::Safe {
+        let launch = &mut args.launch;
+        launch.reflex = false;
+        launch.dlss_fg = false;
+        launch.smooth_motion = false;
+        launch.descriptor_heap = DescriptorHeapMode::Off;
+        println!("  {}", tr!("run-retry-safe"));
+    }
+}
+
+/// Whether the game's launcher returns before the game exits
+fn hands_off_game(game: &DetectedGame) -> bool {
+    matches!(game.source, GameSource::Steam | GameSource::Heroic)
+}
+
+/// Start a launcher that hands the game off, then wait for the game's own
+/// processes; the launcher's exit code is only known if it failed
+///
+/// Also returns how long the game ran, counted from when its processes
+/// showed up.
+fn run_handed_off(
+    mut cmd: Command,
+    game: &DetectedGame,
+    program: &str,
+    start_timeout: Duration,
+) -> Result<(bool, Option<i32>, Option<Duration>)> {
+    let mut launcher = cmd
+        .spawn()
+        .context(NvError::LaunchFailed(program.to_string()))?;
+    println!("  {}", tr!("run-waiting-for-game"));
+    let mut failure = None;
+    let tracked = tracking::wait_for_game(&GameMatcher::for_game(game), start_timeout, || {
+        if let Ok(Some(status)) = launcher.try_wait()
+            && !status.success()
+        {
+            failure = Some(status);
+        }
+        failure.is_some()
+    });
+    match (tracked, failure) {
+        (Tracked::Exited(runtime), _) => Ok((true, None, Some(runtime))),
+        (Tracked::NotStarted, Some(status)) => {
+            eprintln!("{}", tr!("run-launcher-failed", status = status));
+            Ok((false, status.code(), None))
+        }
+        (Tracked::NotStarted, None) => {
+            let secs = start_timeout.as_secs();
+            eprintln!("{}", tr!("run-game-not-seen", secs = secs));
+            Ok((false, None, None))
+        }
+    }
+}
+
+/// Game chosen with `run --last`, or from a pick-list with `run --recent`
+fn pick_recent(paths: &ConfigPaths, choose: bool) -> Result<RecentGame> {
+    let mut games = RecentGames::load(paths)?.games;
+    if games.is_empty() {
+        bail!("no games launched yet (start one with 'nvproton run <GAME_ID>')");
+    }
+    if !choose {
+        return Ok(games.remove(0));
+    }
+
+    games.truncate(RECENT_CHOICES);
+    println!("{}", tr!("run-recent-title"));
+    for (i, game) in games.iter().enumerate() {
+        match &game.profile {
+            Some(profile) => println!(
+                "  {}) {} ({}) [{}]",
+                i + 1,
+                game.name,
+                game.game_id,
+                profile
+            ),
+            None => println!("  {}) {} ({})", i + 1, game.name, game.game_id),
+        }
+    }
+    print!("{} ", tr!("run-recent-prompt", count = games.len()));
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .context("failed to read the choice")?;
+
+    // Enter picks the most recent game
+    let answer = answer.trim();
+    let choice = match answer {
+        "" => 1,
+        _ => answer
+            .parse::<usize>()
+            .ok()
+            .filter(|n| (1..=games.len()).contains(n))
+            .with_context(|| format!("invalid choice '{}'", answer))?,
+    };
+    Ok(games.swap_remove(choice - 1))
+}
+
+/// Warn about (or wait out) a pending Steam update
+///
+/// `steam -applaunch` starts the download first and shows no progress in the
+/// terminal, so the launch looks hung until the update is done.
+fn check_pending_update(game: &DetectedGame, wait: bool) -> Result<()> {
+    let Some(state) = InstallState::read(&game.install_dir, &game.id) else {
+        return Ok(());
+    };
+    if !state.update_pending() {
+        return Ok(());
+    }
+    let remaining = match state.remaining_bytes() {
+        0 => String::new(),
+        bytes => format!(
+            " {}",
+            tr!("run-update-remaining", size = format_bytes(bytes))
+        ),
+    };
+    if !wait {
+        eprintln!(
+            "  {}",
+            tr!(
+                "run-update-pending",
+                name = game.name,
+                remaining = remaining
+            )
+        );
+        eprintln!("  {}", tr!("run-update-pending-hint"));
+        return Ok(());
+    }
 
-    println!("Running: {} ({})", game.name, game.id);
+    println!("  {}", tr!("run-update-waiting", name = game.name));
+    let mut state = state;
+    let mut last_line = String::new();
+    while state.update_pending() {
+        let line = if state.bytes_to_download > 0 {
+            format!(
+                "    {}: {} / {}",
+                state.describe(),
+                format_bytes(state.bytes_downloaded),
+                format_bytes(state.bytes_to_download)
+            )
+        } else {
+            let state = state.describe();
+            format!("    {}", tr!("run-update-paused", state = state))
+        };
+        if line != last_line {
+            println!("{}", line);
+            last_line = line;
+        }
+        thread::sleep(UPDATE_POLL_INTERVAL);
+        state = InstallState::read(&game.install_dir, &game.id)
+            .context("appmanifest disappeared while waiting for the update")?;
+    }
+    println!("  {}", tr!("run-update-finished"));
+    Ok(())
+}
 
+/// Warn about Steam Cloud files changed both locally and in the cloud
+///
+/// Steam asks which copy to keep when the game starts, and picking the wrong
+/// one throws away progress.
+fn check_cloud_sync(steam: &SteamClient, game: &DetectedGame) {
+    let conflicts: Vec<CloudFile> = steam
+        .cloud_files(&game.id)
+        .into_iter()
+        .filter(|file| file.state == CloudSync::Conflict)
+        .collect();
+    if conflicts.is_empty() {
+        return;
+    }
+    let count = conflicts.len();
+    eprintln!(
+        "  {}",
+        tr!("run-cloud-conflict", name = game.name, count = count)
+    );
+    for file in &conflicts {
+        eprintln!("    {}", file.name);
+    }
+    eprintln!("  {}", tr!("run-cloud-conflict-hint", id = game.id));
+}
+
+/// Handle the `wrap` command
+///
+/// Runs a launcher-composed command (Steam's `%command%`) with nvproton's
+/// environment injected. Failing to build the environment never blocks the
+/// game: the original command is executed unchanged.
+pub fn handle_wrap(args: WrapArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
+    let launch = RunContext::new(config, manager).and_then(|ctx| {
+        let game = resolve_wrapped_game(&ctx.game_db, args.game_id.as_deref())?;
+        println!(
+            "nvproton: {}",
+            tr!("wrap-wrapping", name = game.name, id = game.id)
+        );
+        let mut launch = build_launch_env(&ctx, &game, &args.launch)?;
+        route_through_container(&args.command, &mut launch.env_vars);
+        Ok((launch, game.install_dir))
+    });
+
+    let (command, env_vars, mut hooks, cwd) = match launch {
+        Ok((launch, install_dir)) => (
+            launch.wrappers.wrap(
+                launch
+                    .launch_args
+                    .compose(args.command.clone(), &install_dir),
+            ),
+            launch.env_vars,
+            launch.hooks,
+            launch.launch_args.cwd,
+        ),
+        Err(e) => {
+            let error = format!("{:#}", e);
+            eprintln!("nvproton: {}", tr!("wrap-unchanged", error = error));
+            (
+                args.command.clone(),
+                HashMap::new(),
+                HookRunner::default(),
+                None,
+            )
+        }
+    };
+
+    let mut cmd = Command::new(&command[0]);
+    cmd.args(&command[1..]);
+    cmd.envs(&env_vars);
+    if let Some(dir) = cwd {
+        cmd.current_dir(dir);
+    }
+
+    // Without hooks to restore there is nothing left to do after launch
+    if hooks.is_empty() {
+        let err = cmd.exec();
+        return Err(err).context(NvError::LaunchFailed(command[0].clone()));
+    }
+
+    hooks.start();
+    let status = cmd
+        .status()
+        .context(NvError::LaunchFailed(command[0].clone()))?;
+    hooks.finish();
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+/// Arguments `run` launches with: the launcher's composed command to wrap
+/// with `--wrapper`, otherwise extra arguments for the game
+fn run_game_args(args: &RunArgs, launch_args: &LaunchArgs, install_dir: &Path) -> Vec<String> {
+    if args.wrapper {
+        launch_args.compose(args.game_args.clone(), install_dir)
+    } else {
+        launch_args.merge(&args.game_args)
+    }
+}
+
+/// Adapt the environment when the command runs inside the Steam Linux Runtime
+fn route_through_container(command: &[String], env_vars: &mut HashMap<String, String>) {
+    let Some(container) = runtime::detect_container(command) else {
+        return;
+    };
+    println!("  {}", tr!("run-container", suite = container.suite));
+
+    // Keep paths Steam or the user already exposed
+    if !env_vars.contains_key("PRESSURE_VESSEL_FILESYSTEMS_RW")
+        && let Ok(existing) = env::var("PRESSURE_VESSEL_FILESYSTEMS_RW")
+    {
+        env_vars.insert("PRESSURE_VESSEL_FILESYSTEMS_RW".into(), existing);
+    }
+
+    let home = dirs::home_dir();
+    for warning in runtime::route_env(env_vars, home.as_deref()) {
+        eprintln!("  {}", tr!("warning", message = warning));
+    }
+}
+
+/// Resolve the game a wrapped command belongs to
+fn resolve_wrapped_game(db: &GameDatabase, game_id: Option<&str>) -> Result<DetectedGame> {
+    let (id, source) = match game_id {
+        Some(id) if id != "auto" => (id.to_string(), GameSource::Unknown),
+        _ => {
+            let app_id =
+                app_id_from_env().context("SteamAppId/SteamGameId are not set; pass a game ID")?;
+            (app_id, GameSource::Steam)
+        }
+    };
+
+    let known = match source {
+        GameSource::Steam => db.get(&GameId::new(GameSource::Steam, id.clone())),
+        _ if db.lookup(&id).is_some() => Some(db.resolve_game(&id)?),
+        _ => None,
+    };
+    // Games missing from the database still get config-level tuning
+    Ok(known.unwrap_or_else(|| DetectedGame {
+        source,
+        name: format!("{} (not in database)", id),
+        id,
+        install_dir: PathBuf::new(),
+        executable: None,
+        fingerprint: None,
+        metadata: HashMap::new(),
+    }))
+}
+
+/// Environment variables, hooks and wrapper commands for a launch
+pub struct LaunchEnv {
+    pub env_vars: HashMap<String, String>,
+    pub hooks: HookRunner,
+    pub profile_name: Option<String>,
+    pub wrappers: WrapperChain,
+    /// DLSS Frame Generation was requested
+    pub frame_generation: bool,
+    /// Wine synchronization selected with `--sync`
+    pub sync: Option<SyncMechanism>,
+    /// Arguments and working directory from the profile and game record
+    pub launch_args: LaunchArgs,
+    /// Variables two layers set to different values
+    pub conflicts: Vec<EnvConflict>,
+}
+
+/// Build the launch environment for a game from its profile, config and options
+pub fn build_launch_env(
+    ctx: &RunContext,
+    game: &DetectedGame,
+    opts: &LaunchOptions,
+) -> Result<LaunchEnv> {
     // Build environment variables
     let mut env_vars: HashMap<String, String> = HashMap::new();
 
+    // Config-wide launch environment (e.g., from `nvproton status --fix`)
+    for (key, value) in &ctx.config.env {
+        env_vars.insert(key.clone(), value.clone());
+    }
+    let mut layers = EnvLayers::default();
+    layers.record("config", &env_vars);
+
     // Apply Proton-NV optimizations if available
     if let Some(ref proton_nv) = ctx.proton_nv {
-        println!("  Proton-NV: {} detected", proton_nv.version);
+        println!("  {}", tr!("run-proton-nv", version = proton_nv.version));
         let pnv_env = ProtonNvEnv::from_installation(proton_nv);
         for (key, value) in pnv_env.vars() {
             env_vars.insert(key.clone(), value.clone());
         }
+        layers.record("proton-nv", &env_vars);
     }
 
     // Determine which profile to use: command-line arg takes precedence over persisted binding
-    let profile_name = if let Some(name) = &args.profile {
+    let profile_name = if let Some(name) = &opts.profile {
         Some(name.clone())
     } else {
-        // Check for persisted profile binding
-        ctx.profile_persistence.get_binding(&game.id).ok().flatten()
+        // Check for persisted profile binding, then the configured default
+        ctx.profile_persistence
+            .get_binding(&game.game_id().to_string())
+            .ok()
+            .flatten()
+            .or_else(|| ctx.config.profile.default_profile.clone())
     };
 
+    // Reuse the resolved profile and shader caches while their inputs are unchanged
+    let template = TemplateContext::for_game(game, ctx.vulkan_caps.as_ref());
+    let plans = PlanCache::new(ctx.manager.paths());
+    let plan_key = plan_cache::plan_key(
+        ctx.config,
+        &ctx.manager.paths().profiles_dir,
+        profile_name.as_deref(),
+        &template,
+        !opts.no_cache_isolation,
+    )?;
+    let cached_plan = if opts.no_plan_cache {
+        None
+    } else {
+        plans.load(&game.game_id(), &plan_key)
+    };
+    if cached_plan.is_some() {
+        println!("  {}", tr!("run-plan-cached"));
+    }
+
     // Apply profile settings
-    if let Some(profile_name) = &profile_name {
-        let resolved = ctx.profile_manager.resolve(profile_name)?;
-        println!("  Profile: {}", profile_name);
-        apply_profile_to_env(&resolved.settings, &mut env_vars);
+    let mut hooks = HookRunner::default();
+    let profile_settings = match (&cached_plan, &profile_name) {
+        (Some(plan), _) => plan.profile_settings.clone(),
+        (None, Some(profile_name)) => {
+            let mut resolved = ctx.profile_manager.resolve(profile_name)?;
+            template
+                .expand_settings(&mut resolved.settings)
+                .with_context(|| format!("failed to expand profile '{}'", profile_name))?;
+            Some(resolved.settings)
+        }
+        (None, None) => None,
+    };
+    if let (Some(profile_name), Some(settings)) = (&profile_name, &profile_settings) {
+        println!("  {}", tr!("run-profile", name = profile_name));
+        apply_profile_to_env(settings, &mut env_vars);
+        hooks = HookRunner::from_profile(settings);
+        layers.record("profile", &env_vars);
     }
 
+    // Wrapper commands from the profile and the game record, checked before launch
+    let wrappers = WrapperChain::resolve(
+        profile_settings.as_ref(),
+        ctx.game_db.get_game_wrappers(&game.game_id()),
+    )?;
+    if !wrappers.is_empty() {
+        println!("  {}", tr!("run-wrappers", wrappers = wrappers.describe()));
+        wrappers.validate()?;
+    }
+
+    // Arguments and working directory from the profile and the game record
+    let (record_args, record_cwd) = ctx.game_db.get_game_args(&game.game_id());
+    let launch_args = LaunchArgs::resolve(
+        profile_settings.as_ref(),
+        record_args,
+        record_cwd,
+        &template,
+    )
+    .with_context(|| format!("invalid launch arguments for '{}'", game.name))?;
+
+    // Reflex, VRR, frame limit and MangoHud: config defaults, then the
+    // profile's `run` section, then the command line
+    let toggles = run_toggles(ctx.config, profile_settings.as_ref(), opts)?;
+
+    // Layer the streaming fragment when OBS is running (or forced)
+    let streaming = if opts.streaming {
+        Some("--streaming")
+    } else if ctx.config.streaming.auto_detect {
+        streaming::detect_obs().map(|d| d.describe())
+    } else {
+        None
+    };
+    if let Some(reason) = streaming {
+        println!("  {}", tr!("run-streaming", reason = reason));
+        let fragment = serde_yaml::Value::Mapping(ctx.config.streaming.fragment.clone());
+        apply_profile_to_env(&fragment, &mut env_vars);
+        layers.record("streaming", &env_vars);
+    }
+
+    // Enabled with `nvproton nvapi enable`
+    if NvapiStore::load(ctx.manager.paths(), &ctx.game_db)?.is_enabled(&game.game_id()) {
+        env_vars.insert("PROTON_ENABLE_NVAPI".into(), "1".into());
+    }
+
+    // DLSS Frame Generation, warning about what keeps it from working
+    let frame_generation = frame_generation_requested(opts, profile_settings.as_ref());
+    if frame_generation {
+        for (key, value) in nvapi::FRAME_GEN_ENV {
+            env_vars.insert(key.to_string(), value.to_string());
+        }
+        println!("  {}", tr!("run-dlss-fg"));
+        if DlssDlls::find(&game.install_dir).frame_generation.is_none() {
+            eprintln!("  {}", tr!("run-dlss-fg-not-shipped", name = game.name));
+        }
+        for reason in nvapi::frame_generation_blockers(ctx.config, game, ctx.vulkan_caps.as_ref()) {
+            eprintln!("  {}", tr!("run-dlss-fg-blocked", reason = reason));
+        }
+    }
+
+    // Steam points Proton at the anti-cheat runtimes only for its own games
+    if game.source != GameSource::Steam {
+        apply_anticheat_runtimes(ctx.config, game, &mut env_vars);
+    }
+    layers.record("game", &env_vars);
+
+    // Proton and DXVK tunables from the command line override the profile
+    apply_proton_flags(opts, &mut env_vars);
+    let sync = opts.sync.and_then(|mode| select_sync(mode, &mut env_vars));
+    if opts.wine_wayland {
+        match wine_wayland::enable(ctx.config, game, &ctx.display, &mut env_vars) {
+            Ok(setup) => {
+                println!("  {}", tr!("run-wine-wayland", runtime = setup.runtime));
+                if let Some(hook) = setup.hook {
+                    hooks.push(hook);
+                }
+            }
+            Err(reason) => eprintln!("  {}", tr!("run-wine-wayland-fallback", reason = reason)),
+        }
+    }
+    if toggles.mangohud {
+        env_vars.insert("MANGOHUD".into(), "1".into());
+    }
+    if let Some(hud) = &opts.dxvk_hud {
+        env_vars.insert("DXVK_HUD".into(), hud.clone());
+    }
+    if let Some(level) = &opts.dxvk_log_level {
+        env_vars.insert("DXVK_LOG_LEVEL".into(), level.clone());
+    }
+    if let Some(ref config) = opts.controller_config {
+        input::set_controller_config(config, &mut env_vars);
+    }
+    if let Some(mode) = opts.steam_input {
+        input::set_steam_input(mode == SteamInputMode::On, &mut env_vars);
+    }
+    validate_proton_env(&env_vars)?;
+
     // NVIDIA-specific optimizations via FFI
     // Configure Reflex via nvlatency library
-    if args.reflex {
+    if toggles.reflex {
         // Check for Reflex 2.0 support (VK_NV_low_latency2 on 595+)
-        let has_reflex2 = ctx.vulkan_caps.as_ref().is_some_and(|c| c.supports_reflex2());
+        let has_reflex2 = ctx
+            .vulkan_caps
+            .as_ref()
+            .is_some_and(|c| c.supports_reflex2());
 
         // Set environment variables as fallback for DXVK/Wine
         env_vars.insert("__GL_REFLEX".into(), "1".into());
@@ -155,35 +1167,64 @@ pub fn handle_run(args: RunArgs, manager: &ConfigManager, config: &mut NvConfig)
         if let Err(e) = configure_reflex(true) {
             log::warn!("Reflex FFI configuration failed: {}", e);
             if has_reflex2 {
-                println!("  Reflex 2.0: enabled (env vars only)");
+                println!("  {}", tr!("run-reflex2-env-only"));
             } else {
-                println!("  Reflex: enabled (env vars only)");
+                println!("  {}", tr!("run-reflex-env-only"));
             }
         } else if has_reflex2 {
-            println!("  Reflex 2.0: enabled via nvlatency");
+            println!("  {}", tr!("run-reflex2-nvlatency"));
         }
     }
 
+    // Driver-level frame interpolation
+    if let Some(enabled) = smooth_motion_requested(opts, profile_settings.as_ref()) {
+        apply_smooth_motion(enabled, ctx.vulkan_caps.as_ref(), &mut env_vars);
+    }
+
     // Configure VRR and frame limiting via nvsync library
-    if args.fps > 0 {
-        env_vars.insert("DXVK_FRAME_RATE".into(), args.fps.to_string());
+    if toggles.fps > 0 {
+        env_vars.insert("DXVK_FRAME_RATE".into(), toggles.fps.to_string());
     }
 
-    if args.vrr {
+    if toggles.vrr {
         env_vars.insert("__GL_GSYNC_ALLOWED".into(), "1".into());
         env_vars.insert("__GL_VRR_ALLOWED".into(), "1".into());
+        // The variables only reach the driver's G-SYNC path on X11
+        if ctx.display.vrr == VrrMechanism::Compositor {
+            let session = ctx.display.describe();
+            // A profile `display.vrr` already brings its own hook
+            let from_profile = profile_settings
+                .as_ref()
+                .and_then(|s| s.get("display"))
+                .is_some_and(|d| d.get("vrr").is_some());
+            if !from_profile {
+                let hook = ctx
+                    .config
+                    .display
+                    .compositor_vrr
+                    .then(|| display::adaptive_sync_hook(None, true))
+                    .flatten();
+                match hook {
+                    Some(hook) => {
+                        println!("  {}", tr!("run-vrr-compositor", session = session));
+                        hooks.push(hook);
+                    }
+                    None => println!("  {}", tr!("run-vrr-compositor-manual", session = session)),
+                }
+            }
+        }
     }
 
     // Configure via FFI for system-level VRR and frame limiting
-    if args.vrr || args.fps > 0 {
-        if let Err(e) = configure_vrr(args.vrr, args.fps) {
-            log::warn!("VRR/FPS FFI configuration failed: {}", e);
-            if args.vrr {
-                println!("  VRR: enabled (env vars only)");
-            }
-            if args.fps > 0 {
-                println!("  FPS Limit: {} (env vars only)", args.fps);
-            }
+    if (toggles.vrr || toggles.fps > 0)
+        && let Err(e) = configure_vrr(toggles.vrr, toggles.fps)
+    {
+        log::warn!("VRR/FPS FFI configuration failed: {}", e);
+        if toggles.vrr {
+            println!("  {}", tr!("run-vrr-env-only"));
+        }
+        if toggles.fps > 0 {
+            println!("  {}", tr!("run-fps-env-only", fps = toggles.fps));
         }
     }
 
@@ -198,118 +1239,236 @@ pub fn handle_run(args: RunArgs, manager: &ConfigManager, config: &mut NvConfig)
         .is_some_and(|c| c.supports_dx12_heap_fix());
     let is_595 = ctx.vulkan_caps.as_ref().is_some_and(|c| c.is_595_series());
 
-    let use_descriptor_heap = match args.descriptor_heap {
+    let pe_info = PeInfo::from_metadata(&game.metadata);
+    let use_descriptor_heap = match opts.descriptor_heap {
         DescriptorHeapMode::On => true,
         DescriptorHeapMode::Off => false,
         DescriptorHeapMode::Auto => {
+            // Skip games whose executable doesn't import D3D12
+            let uses_d3d12 = pe_info.as_ref().is_none_or(PeInfo::uses_d3d12);
             // Auto-enable on 595+ if config allows, or if extension is available
-            (config.vkd3d.auto_enable_595 && is_595) || has_descriptor_heap
+            uses_d3d12 && ((ctx.config.vkd3d.auto_enable_595 && is_595) || has_descriptor_heap)
         }
     };
 
     if use_descriptor_heap {
         // Build VKD3D_CONFIG with all relevant flags
-        let vkd3d_config =
-            config
-                .vkd3d
-                .build_config_string(has_descriptor_heap, has_heap_fix);
+        let vkd3d_config = ctx
+            .config
+            .vkd3d
+            .build_config_string(has_descriptor_heap, has_heap_fix);
         if !vkd3d_config.is_empty() {
             env_vars.insert("VKD3D_CONFIG".into(), vkd3d_config);
         }
-        env_vars.insert("VKD3D_FEATURE_LEVEL".into(), config.vkd3d.feature_level.clone());
+        env_vars.insert(
+            "VKD3D_FEATURE_LEVEL".into(),
+            ctx.config.vkd3d.feature_level.clone(),
+        );
 
         if has_heap_fix {
-            println!("  Descriptor Heap: enabled (DX12 optimization + 595 heap fix)");
+            println!("  {}", tr!("run-descriptor-heap-fix"));
         } else {
-            println!("  Descriptor Heap: enabled (DX12 optimization)");
+            println!("  {}", tr!("run-descriptor-heap"));
         }
     }
-
-    // Warn about beta driver if configured (but 595 is recommended so note that)
-    if let Some(ref caps) = ctx.vulkan_caps {
-        if caps.is_beta_driver() && config.vkd3d.warn_beta_driver {
-            if caps.is_595_series() {
-                eprintln!(
-                    "  Note: 595 beta driver {} - recommended for DX12 games (heap fixes included)",
-                    caps.driver_version
-                );
-            } else {
-                eprintln!(
-                    "  Warning: Beta driver {} detected. Consider updating to 595.x for heap fixes.",
-                    caps.driver_version
-                );
-            }
-        }
+    layers.record("flags", &env_vars);
+    let conflicts = layers.finish();
+    for conflict in &conflicts {
+        let (earlier, previous) = &conflict.overridden;
+        let (layer, value) = &conflict.applied;
+        log::warn!(
+            "{}={} from {} replaces {} from {}",
+            conflict.key,
+            value,
+            layer,
+            previous,
+            earlier
+        );
     }
 
-    // Shader pre-warming
-    if !args.no_prewarm {
-        println!("  Pre-warming shaders...");
-        if let Err(e) = prewarm_shaders(&game) {
-            eprintln!("  Warning: shader pre-warming failed: {}", e);
+    // Per-game shader caches, unless the config or profile already chose paths
+    let cache_env = match &cached_plan {
+        Some(plan) => plan.cache_env.clone(),
+        None if opts.no_cache_isolation => Vec::new(),
+        None => CacheManager::new()?.setup_for_game(game, &ctx.game_db)?,
+    };
+    add_cache_env(&mut env_vars, &cache_env);
+    if cached_plan.is_none() {
+        let plan = LaunchPlan {
+            profile_settings: profile_settings.clone(),
+            cache_env,
+        };
+        if let Err(e) = plans.store(&game.game_id(), &plan_key, plan) {
+            log::warn!("failed to cache the launch plan: {:#}", e);
         }
     }
 
-    // Build launch command based on game source
-    let launch_cmd = build_launch_command(&game, &args.game_args)?;
+    // Per-game capture directory for screenshots and replays
+    if ctx.config.media.enabled {
+        let dir = media::game_dir(&ctx.config.media, game);
+        println!("  {}", tr!("run-media-dir", path = dir.display()));
+        env_vars.insert(media::MEDIA_DIR_ENV.into(), dir.display().to_string());
+        hooks.push(media::hook(&ctx.config.media, game));
+    }
 
-    if args.dry_run {
-        println!("\n[Dry Run] Would execute:");
-        println!("  Command: {:?}", launch_cmd);
-        println!("  Environment:");
-        for (key, value) in &env_vars {
-            println!("    {}={}", key, value);
+    // Instant replay for the length of the session
+    let replay_section = profile_settings.as_ref().and_then(|s| s.get("replay"));
+    if opts.replay || replay::enabled(replay_section) {
+        let default_output = media::game_dir(&ctx.config.media, game);
+        let output = replay::output_dir(replay_section, &default_output);
+        match replay::hook(replay_section, default_output) {
+            Some(hook) => {
+                println!("  {}", tr!("run-replay", path = output.display()));
+                hooks.push(hook);
+            }
+            None => eprintln!("  {}", tr!("run-replay-unavailable")),
         }
-        return Ok(());
     }
 
-    // Execute the game
-    println!("\nLaunching {}...", game.name);
+    // Windowing backend for native games, unless the config or profile chose one
+    if ctx.config.display.session_env {
+        for (key, value) in ctx.display.backend_env() {
+            if !env_vars.contains_key(*key) && env::var_os(key).is_none() {
+                env_vars.insert(key.to_string(), value.to_string());
+            }
+        }
+    }
 
-    let mut cmd = Command::new(&launch_cmd[0]);
-    cmd.args(&launch_cmd[1..]);
-    cmd.envs(&env_vars);
+    // Prefer DLLs installed with `nvproton dxvk install`
+    let overrides = OverrideStore::load(ctx.manager.paths(), &ctx.game_db)?;
+    if let Some(dll_overrides) = dxvk::wine_dll_overrides(overrides.get(&game.game_id())) {
+        for entry in overrides.get(&game.game_id()) {
+            let component = entry.component.name();
+            println!(
+                "  {}",
+                tr!(
+                    "run-override",
+                    component = component,
+                    version = entry.version
+                )
+            );
+        }
+        add_dll_overrides(&mut env_vars, dll_overrides);
+    }
 
-    // Inherit current env
-    for (key, value) in env::vars() {
-        if !env_vars.contains_key(&key) {
-            cmd.env(key, value);
+    // Mod loaders hooking in through a proxy DLL need it loaded natively
+    let prefix = saves::wine_prefix(ctx.config, game);
+    let loaders = modloaders::detect(game, prefix.as_deref());
+    for loader in &loaders {
+        let name = loader.kind.to_string();
+        println!("  {}", tr!("run-mod-loader", loader = name));
+        if let Some(runtime) = loader.missing_runtime {
+            let id = &game.id;
+            eprintln!(
+                "  {}",
+                tr!(
+                    "run-mod-loader-missing",
+                    loader = name,
+                    runtime = runtime,
+                    id = id
+                )
+            );
         }
     }
+    if let Some(dll_overrides) = modloaders::wine_dll_overrides(&loaders) {
+        add_dll_overrides(&mut env_vars, dll_overrides);
+    }
 
-    let status = cmd.status().context("Failed to launch game")?;
+    // 32-bit games under Proton's WoW64 mode run through its slower thunks
+    let wow64 = env_vars
+        .get("PROTON_USE_WOW64")
+        .cloned()
+        .or_else(|| env::var("PROTON_USE_WOW64").ok());
+    if let Some(info) = &pe_info
+        && info.arch == Arch::X86
+        && wow64.as_deref() == Some("1")
+    {
+        eprintln!("  {}", tr!("run-wow64-32bit", name = game.name));
+    }
 
-    if !status.success() {
-        eprintln!("Game exited with status: {}", status);
+    // Warn about beta driver if configured (but 595 is recommended so note that)
+    if let Some(ref caps) = ctx.vulkan_caps
+        && caps.is_beta_driver()
+        && ctx.config.vkd3d.warn_beta_driver
+    {
+        if caps.is_595_series() {
+            let version = &caps.driver_version;
+            eprintln!("  {}", tr!("run-beta-driver-595", version = version));
+        } else {
+            let version = &caps.driver_version;
+            eprintln!("  {}", tr!("run-beta-driver", version = version));
+        }
     }
 
-    Ok(())
+    Ok(LaunchEnv {
+        env_vars,
+        hooks,
+        profile_name,
+        wrappers,
+        frame_generation,
+        sync,
+        launch_args,
+        conflicts,
+    })
 }
 
 /// Handle the `prepare` command
-pub fn handle_prepare(
-    args: PrepareArgs,
-    manager: &ConfigManager,
-    config: &mut NvConfig,
-) -> Result<()> {
+pub fn handle_prepare(args: PrepareArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
+    let started = Instant::now();
     let ctx = RunContext::new(config, manager)?;
+    if args.all || !args.ids.is_empty() {
+        let mut ids = args.ids;
+        ids.sort();
+        ids.dedup();
+        let games = if args.all {
+            games_to_prepare(&ctx.game_db, args.source.as_ref(), &args.tags)?
+        } else {
+            ids.iter()
+                .map(|id| ctx.game_db.resolve_game(id))
+                .collect::<Result<Vec<_>>>()?
+        };
+        if args.dry_run {
+            print_prepare_dry_run(&games);
+            return Ok(());
+        }
+        let count = games.len();
+        let problems = prepare_all(games, args.jobs.or(config.run_defaults.jobs))?;
+        notify::finished(
+            &config.notifications,
+            notify::Kind::Prepare,
+            started.elapsed(),
+            &tr!("prepare-all-notify", count = count),
+            &tr!("prepare-all-summary", count = count, problems = problems),
+        );
+        return Ok(());
+    }
     let game = ctx.find_game(args.game_id.as_deref(), args.name.as_deref())?;
+    if args.dry_run {
+        print_prepare_dry_run(std::slice::from_ref(&game));
+        return Ok(());
+    }
 
-    println!("Preparing: {} ({})", game.name, game.id);
+    println!(
+        "{}",
+        tr!("prepare-preparing", name = game.name, id = game.id)
+    );
 
     // Report Proton-NV status
     if let Some(ref proton_nv) = ctx.proton_nv {
-        println!("  Proton-NV: {} (will be used at launch)", proton_nv.version);
+        println!(
+            "  {}",
+            tr!("prepare-proton-nv", version = proton_nv.version)
+        );
         if let Some(ref info) = proton_nv.version_info {
             if let Some(ref driver) = info.nvidia_driver_min {
-                println!("    Requires: NVIDIA driver {}", driver);
+                println!("    {}", tr!("prepare-proton-nv-driver", driver = driver));
             }
             if let Some(ref gpu) = info.target_gpu {
-                println!("    Target: {}", gpu);
+                println!("    {}", tr!("prepare-proton-nv-target", gpu = gpu));
             }
         }
     } else {
-        println!("  Proton-NV: not detected (using system Proton)");
+        println!("  {}", tr!("prepare-proton-nv-missing"));
     }
 
     // Apply profile if specified
@@ -317,44 +1476,226 @@ pub fn handle_prepare(
         // Verify profile exists by resolving it
         let _resolved = ctx.profile_manager.resolve(profile_name)?;
         // Persist game->profile binding
-        ctx.profile_persistence.bind(&game.id, profile_name)
-            .with_context(|| format!("failed to bind profile '{}' to game '{}'", profile_name, game.id))?;
-        println!("  Profile: {} (bound to game, will be applied at launch)", profile_name);
+        let game_id = game.game_id();
+        let mut undo = Recorder::begin(manager.paths());
+        undo.backup(&ProfilePersistence::path(manager.paths()))?;
+        ctx.profile_persistence
+            .bind(&game_id.to_string(), profile_name)
+            .with_context(|| {
+                format!(
+                    "failed to bind profile '{}' to game '{}'",
+                    profile_name, game_id
+                )
+            })?;
+        undo.commit()?;
+        println!("  {}", tr!("prepare-profile-bound", name = profile_name));
     }
 
     // Shader pre-warming
-    println!("  Pre-warming shaders...");
+    println!("  {}", tr!("run-prewarming"));
     if args.force {
-        println!("    (forcing recompilation)");
+        // Replaying into the existing caches would skip the stale pipelines
+        let freed = CacheManager::new()?.clear_game(&game.game_id())?;
+        println!("    {}", tr!("prepare-forcing", size = format_bytes(freed)));
     }
 
-    match prewarm_shaders(&game) {
-        Ok(()) => println!("  Shaders ready!"),
-        Err(e) => eprintln!("  Warning: shader pre-warming failed: {}", e),
+    let prewarmed = prewarm_shaders(&game);
+    match &prewarmed {
+        Ok(()) => println!("  {}", tr!("prepare-shaders-ready")),
+        Err(e) => eprintln!("  {}", tr!("run-prewarm-failed", error = e)),
     }
 
     // Verify game installation
     if game.install_dir.exists() {
-        println!("  Install directory: OK");
+        println!("  {}", tr!("prepare-install-ok"));
     } else {
-        eprintln!(
-            "  Warning: Install directory not found: {:?}",
-            game.install_dir
-        );
+        let dir = format!("{:?}", game.install_dir);
+        eprintln!("  {}", tr!("prepare-install-missing", dir = dir));
     }
 
     if let Some(exe) = &game.executable {
         if exe.exists() {
-            println!("  Executable: OK");
+            println!("  {}", tr!("prepare-executable-ok"));
         } else {
-            eprintln!("  Warning: Executable not found: {:?}", exe);
+            let exe = format!("{:?}", exe);
+            eprintln!("  {}", tr!("prepare-executable-missing", exe = exe));
         }
     }
 
-    println!("\nGame is ready to launch with 'nvproton run {}'", game.id);
+    // The daemon re-runs `prepare --force` until it succeeds
+    prewarmed?;
+    println!("\n{}", tr!("prepare-ready", id = game.id));
+    notify::finished(
+        &config.notifications,
+        notify::Kind::Prepare,
+        started.elapsed(),
+        &tr!("prepare-notify", name = game.name),
+        &tr!("prepare-ready", id = game.id),
+    );
     Ok(())
 }
 
+/// Outcome of preparing one game with `prepare --all`
+struct PrepareRow {
+    game: DetectedGame,
+    shaders: String,
+    install: String,
+    install_ok: bool,
+}
+
+/// Games for `prepare --all`, optionally limited to a source and tags
+fn games_to_prepare(
+    db: &GameDatabase,
+    source: Option<&GameSource>,
+    tags: &[String],
+) -> Result<Vec<DetectedGame>> {
+    let mut games: Vec<DetectedGame> = match source {
+        Some(source) => db
+            .records()
+            .filter(|(_, record)| record.source == *source)
+            .map(|(id, record)| record.to_game(&id.id))
+            .collect(),
+        None => db
+            .unique_games()
+            .into_iter()
+            .map(|group| group.primary)
+            .collect(),
+    };
+    games.retain(|g| db.has_tags(&g.game_id(), tags));
+    if games.is_empty() {
+        if !tags.is_empty() {
+            bail!("no games are tagged {}", tags.join(", "));
+        }
+        bail!("no games to prepare (run 'nvproton detect' first)");
+    }
+    Ok(games)
+}
+
+fn print_prepare_dry_run(games: &[DetectedGame]) {
+    println!("{}", tr!("prepare-dry-run", count = games.len()));
+    for game in games {
+        println!(
+            "  {:<12} {:<10} {}",
+            game.id,
+            game.source.to_string(),
+            game.name
+        );
+    }
+}
+
+/// Prepare every game in the database on a pool of worker threads
+///
+/// Returns the number of games with install problems
+fn prepare_all(mut games: Vec<DetectedGame>, jobs: Option<usize>) -> Result<usize> {
+    // Workers pop from the end, so games are prepared in name order
+    games.sort_by_key(|g| std::cmp::Reverse(g.name.to_lowercase()));
+
+    let total = games.len();
+    let workers = jobs
+        .unwrap_or_else(|| {
+            thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(4)
+        })
+        .clamp(1, total);
+    println!("{}", tr!("prepare-all", count = total, jobs = workers));
+
+    let queue = Mutex::new(games);
+    let rows = Mutex::new(Vec::with_capacity(total));
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                let shaders = ShaderPrewarmer::load();
+                loop {
+                    let Some(game) = queue.lock().unwrap().pop() else {
+                        break;
+                    };
+                    let row = prepare_one(&shaders, game);
+                    let mut rows = rows.lock().unwrap();
+                    rows.push(row);
+                    let row = rows.last().expect("row was just pushed");
+                    println!(
+                        "  [{}/{}] {}: {}, {}",
+                        rows.len(),
+                        total,
+                        row.game.name,
+                        row.shaders,
+                        row.install
+                    );
+                }
+            });
+        }
+    });
+
+    let mut rows = rows.into_inner().unwrap();
+    rows.sort_by_key(|r| r.game.name.to_lowercase());
+    let mut table = Table::new([
+        tr!("column-game"),
+        tr!("column-source"),
+        tr!("column-shaders"),
+        tr!("column-install"),
+    ]);
+    for row in &rows {
+        let tone = if row.install_ok {
+            Tone::Good
+        } else {
+            Tone::Bad
+        };
+        table.row([
+            Cell::from(truncate(&row.game.name, 40)),
+            Cell::from(row.game.source.to_string()),
+            Cell::from(row.shaders.as_str()),
+            Cell::toned(&row.install, tone),
+        ]);
+    }
+    print!("\n{}", table);
+
+    let problems = rows.iter().filter(|r| !r.install_ok).count();
+    println!(
+        "\n{}",
+        tr!("prepare-all-summary", count = total, problems = problems)
+    );
+    Ok(problems)
+}
+
+/// Pre-warm shaders and verify the install of one game
+fn prepare_one(shaders: &ShaderPrewarmer, game: DetectedGame) -> PrepareRow {
+    let shaders = match shaders.prewarm(&game) {
+        Prewarm::Compiled(result) if result.total > 0 => tr!(
+            "prepare-row-compiled",
+            completed = result.completed,
+            total = result.total,
+            failed = result.failed
+        ),
+        Prewarm::Compiled(_) | Prewarm::NoCaches => tr!("prepare-row-no-caches"),
+        Prewarm::Unavailable => tr!("prepare-row-unavailable"),
+        Prewarm::Failed(_) => tr!("prepare-row-failed"),
+    };
+    let (install, install_ok) = if !game.install_dir.exists() {
+        (tr!("prepare-row-install-missing"), false)
+    } else if game.executable.as_ref().is_some_and(|exe| !exe.exists()) {
+        (tr!("prepare-row-executable-missing"), false)
+    } else {
+        (tr!("prepare-row-ok"), true)
+    };
+    PrepareRow {
+        game,
+        shaders,
+        install,
+        install_ok,
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        text.to_string()
+    } else {
+        let mut truncated: String = text.chars().take(width - 3).collect();
+        truncated.push_str("...");
+        truncated
+    }
+}
+
 /// Configure Reflex low-latency mode using nvlatency library
 fn configure_reflex(enabled: bool) -> Result<()> {
     if !enabled {
@@ -384,7 +1725,7 @@ fn configure_reflex(enabled: bool) -> Result<()> {
                     if let Err(e) = nvlatency.set_reflex_mode(ffi::ReflexMode::On) {
                         log::warn!("Failed to enable Reflex: {}", e);
                     } else {
-                        println!("  Reflex: enabled via nvlatency");
+                        println!("  {}", tr!("run-reflex-nvlatency"));
                     }
                     return Ok(());
                 }
@@ -437,7 +1778,7 @@ fn configure_vrr(enabled: bool, fps_limit: u32) -> Result<()> {
                         if let Err(e) = nvsync.enable_vrr(None) {
                             log::warn!("Failed to enable VRR: {}", e);
                         } else {
-                            println!("  VRR: enabled via nvsync");
+                            println!("  {}", tr!("run-vrr-nvsync"));
                         }
                     }
 
@@ -446,7 +1787,7 @@ fn configure_vrr(enabled: bool, fps_limit: u32) -> Result<()> {
                         if let Err(e) = nvsync.set_frame_limit(fps_limit) {
                             log::warn!("Failed to set frame limit: {}", e);
                         } else {
-                            println!("  Frame limit: {} FPS via nvsync", fps_limit);
+                            println!("  {}", tr!("run-fps-nvsync", fps = fps_limit));
                         }
                     }
 
@@ -465,7 +1806,10 @@ fn configure_vrr(enabled: bool, fps_limit: u32) -> Result<()> {
 
 /// Get standard library search paths
 fn get_lib_paths() -> Vec<PathBuf> {
-    let mut lib_paths = vec![
+    let mut lib_paths = Vec::new();
+    // User-local first: /usr/lib/nvproton can't be written on immutable distros
+    lib_paths.extend(distro::user_lib_dir());
+    lib_paths.extend([
         PathBuf::from("/usr/lib/nvproton"),
         PathBuf::from("/usr/local/lib/nvproton"),
         PathBuf::from("/usr/lib"),
@@ -473,7 +1817,7 @@ fn get_lib_paths() -> Vec<PathBuf> {
         dirs::data_local_dir()
             .map(|d| d.join("nvproton/lib"))
             .unwrap_or_default(),
-    ];
+    ]);
 
     // Prepend custom path from environment if set
     if let Ok(custom_path) = env::var("NVPROTON_LIB_PATH") {
@@ -484,90 +1828,125 @@ fn get_lib_paths() -> Vec<PathBuf> {
 }
 
 /// Pre-warm shader cache for a game using nvshader library
+///
+/// Fails when nvshader fails or shaders fail to compile; a missing nvshader
+/// or game without caches is not an error.
 fn prewarm_shaders(game: &DetectedGame) -> Result<()> {
-    let lib_paths = get_lib_paths();
+    match ShaderPrewarmer::load().prewarm(game) {
+        Prewarm::Compiled(result) if result.total > 0 => {
+            println!(
+                "  {}",
+                tr!(
+                    "run-shaders-compiled",
+                    completed = result.completed,
+                    total = result.total,
+                    failed = result.failed,
+                    skipped = result.skipped
+                )
+            );
+            if result.failed > 0 {
+                bail!(
+                    "{} of {} shaders failed to compile",
+                    result.failed,
+                    result.total
+                );
+            }
+        }
+        Prewarm::Compiled(_) => println!("  {}", tr!("run-shaders-no-caches")),
+        Prewarm::NoCaches | Prewarm::Unavailable => {}
+        Prewarm::Failed(e) => return Err(e).context("nvshader failed to pre-warm the caches"),
+    }
+    Ok(())
+}
 
-    for path in &lib_paths {
-        let shader_lib = path.join("libnvshader.so");
-        if shader_lib.exists() {
+/// Result of pre-warming a game's shaders
+enum Prewarm {
+    Compiled(ffi::NvShaderPrewarmResult),
+    /// nvshader has no Fossilize cache for the game
+    NoCaches,
+    /// nvshader or fossilize_replay is missing
+    Unavailable,
+    Failed(ffi::FfiError),
+}
+
+/// An nvshader context whose caches have been scanned, reused for every game
+/// a thread pre-warms
+///
+/// A context can't be shared between threads, so `prepare --all` loads one
+/// per worker; each scans the caches once, not once per game.
+struct ShaderPrewarmer {
+    nvshader: Option<ffi::NvShader>,
+}
+
+impl ShaderPrewarmer {
+    /// Load nvshader and scan for Fossilize caches
+    fn load() -> Self {
+        for path in get_lib_paths() {
+            let shader_lib = path.join("libnvshader.so");
+            if !shader_lib.exists() {
+                continue;
+            }
             match unsafe { ffi::NvShader::load(&shader_lib) } {
                 Ok(nvshader) => {
                     // Check if pre-warming is available (fossilize_replay found)
                     if !nvshader.prewarm_available() {
                         log::info!("fossilize_replay not available - skipping shader pre-warm");
-                        return Ok(());
+                        return Self { nvshader: None };
                     }
-
-                    // Scan for caches first
                     if let Err(e) = nvshader.scan() {
                         log::warn!("Failed to scan shader caches: {}", e);
-                        return Ok(());
-                    }
-
-                    // Pre-warm shaders for this game
-                    match nvshader.prewarm_game(&game.id) {
-                        Ok(result) => {
-                            if result.total > 0 {
-                                println!(
-                                    "  Shaders: {}/{} compiled ({} failed, {} skipped)",
-                                    result.completed, result.total, result.failed, result.skipped
-                                );
-                            } else {
-                                println!("  Shaders: No Fossilize caches found for this game");
-                            }
-                            return Ok(());
-                        }
-                        Err(ffi::FfiError::Operation { code: -5 }) => {
-                            // Game not found in caches - that's OK
-                            log::debug!("No shader cache found for game {}", game.id);
-                        }
-                        Err(e) => {
-                            log::warn!("Failed to pre-warm shaders: {}", e);
-                        }
+                        return Self { nvshader: None };
                     }
-                    return Ok(());
+                    return Self {
+                        nvshader: Some(nvshader),
+                    };
                 }
                 Err(e) => {
                     log::debug!("Failed to load nvshader from {:?}: {}", shader_lib, e);
                 }
             }
         }
+        log::debug!("nvshader library not found - shader pre-warming unavailable");
+        Self { nvshader: None }
     }
 
-    // Fallback: check if DXVK cache exists
-    let cache_paths = get_shader_cache_paths(game);
-    for path in &cache_paths {
-        if path.exists() {
-            log::info!("Found existing shader cache at {:?}", path);
-            return Ok(());
+    /// Replay a game's Fossilize caches
+    fn prewarm(&self, game: &DetectedGame) -> Prewarm {
+        let Some(nvshader) = &self.nvshader else {
+            // Fallback: check if DXVK cache exists
+            if let Some(path) = get_shader_cache_paths(game).iter().find(|p| p.exists()) {
+                log::info!("Found existing shader cache at {:?}", path);
+            }
+            return Prewarm::Unavailable;
+        };
+        match nvshader.prewarm_game(&game.id) {
+            Ok(result) => Prewarm::Compiled(result),
+            Err(ffi::FfiError::Operation { code: -5 }) => {
+                // Game not found in caches - that's OK
+                log::debug!("No shader cache found for game {}", game.id);
+                Prewarm::NoCaches
+            }
+            Err(e) => Prewarm::Failed(e),
         }
     }
-
-    log::debug!("nvshader library not found - shader pre-warming unavailable");
-    Ok(())
 }
 
 /// Get potential shader cache paths for a game
 fn get_shader_cache_paths(game: &DetectedGame) -> Vec<PathBuf> {
     let mut paths = Vec::new();
 
-    if let Some(cache_dir) = dirs::cache_dir() {
-        // DXVK cache
-        paths.push(cache_dir.join("dxvk").join(&game.id));
-        // vkd3d-proton cache
-        paths.push(cache_dir.join("vkd3d-proton").join(&game.id));
-        // Mesa shader cache
-        paths.push(cache_dir.join("mesa_shader_cache"));
+    // nvproton's per-game DXVK, vkd3d-proton and GL caches (see CacheManager::setup_for_game)
+    let cache_paths = CachePaths::new();
+    for (_, path) in cache_paths.find_game_dirs(&game.game_id()) {
+        paths.push(path);
     }
 
-    // Steam shader cache
+    // Steam's shader cache, in the library holding the game
+    // (install_dir is <library>/steamapps/common/<dir>)
     if let GameSource::Steam = game.source
-        && let Some(home) = dirs::home_dir()
+        && let Some(steamapps) = game.install_dir.parent().and_then(Path::parent)
     {
-        paths.push(
-            home.join(".local/share/Steam/steamapps/shadercache")
-                .join(&game.id),
-        );
+        paths.push(steamapps.join("shadercache").join(&game.id));
     }
 
     paths
@@ -586,11 +1965,24 @@ fn build_launch_command(game: &DetectedGame, extra_args: &[String]) -> Result<Ve
             cmd.extend(extra_args.iter().cloned());
         }
         GameSource::Heroic => {
-            // Use heroic CLI
-            cmd.push("heroic".into());
-            cmd.push("--launch".into());
-            cmd.push(game.id.clone());
-            cmd.extend(extra_args.iter().cloned());
+            // Hand the launch to Heroic without opening its window; Heroic
+            // returns right away, so the game is tracked by its processes
+            let app_name = game.metadata.get("app_name").unwrap_or(&game.id);
+            let runner = game
+                .metadata
+                .get("runner")
+                .map(String::as_str)
+                .unwrap_or("legendary");
+            let url = format!("heroic://launch/{}/{}", runner, app_name);
+            if paths::in_path("heroic") {
+                cmd.extend(["heroic".into(), "--no-gui".into(), url]);
+            } else {
+                // Flatpak Heroic only registers the URL handler
+                cmd.extend(["xdg-open".into(), url]);
+            }
+            if !extra_args.is_empty() {
+                log::warn!("Heroic launches ignore extra game arguments");
+            }
         }
         GameSource::Lutris => {
             // Use lutris CLI
@@ -598,6 +1990,13 @@ fn build_launch_command(game: &DetectedGame, extra_args: &[String]) -> Result<Ve
             cmd.push(format!("lutris:rungame/{}", game.id));
             cmd.extend(extra_args.iter().cloned());
         }
+        GameSource::Legendary => {
+            // legendary runs the game itself and waits for it
+            cmd.push("legendary".into());
+            cmd.push("launch".into());
+            cmd.push(game.id.clone());
+            cmd.extend(extra_args.iter().cloned());
+        }
         GameSource::Unknown => {
             // Direct executable launch
             if let Some(exe) = &game.executable {
@@ -612,6 +2011,429 @@ fn build_launch_command(game: &DetectedGame, extra_args: &[String]) -> Result<Ve
     Ok(cmd)
 }
 
+/// Print the launch `run` would perform for a game: command, environment,
+/// hooks and a standalone shell command line
+pub fn print_launch_plan(
+    manager: &ConfigManager,
+    config: &NvConfig,
+    game: &DetectedGame,
+    opts: &LaunchOptions,
+) -> Result<()> {
+    let ctx = RunContext::new(config, manager)?;
+    let LaunchEnv {
+        mut env_vars,
+        hooks,
+        wrappers,
+        launch_args,
+        conflicts,
+        ..
+    } = build_launch_env(&ctx, game, opts)?;
+    let launch_cmd = if umu::applies(config, false, game) {
+        umu::launch_command(
+            config,
+            manager.paths(),
+            &ctx.game_db,
+            game,
+            &launch_args.args,
+            &mut env_vars,
+        )?
+    } else {
+        build_launch_command(game, &launch_args.args)?
+    };
+    let launch_cmd = wrappers.apply(game, launch_cmd, false);
+
+    println!("\n{}", tr!("games-info-command"));
+    print_launch_details(
+        &launch_cmd,
+        launch_args.cwd.as_deref(),
+        &env_vars,
+        &hooks,
+        &conflicts,
+    );
+
+    println!("\n{}", tr!("games-info-standalone"));
+    let standalone = standalone_command(&env_vars, &launch_cmd);
+    match &launch_args.cwd {
+        Some(cwd) => println!(
+            "  cd {} && {}",
+            shell_quote(&cwd.to_string_lossy()),
+            standalone
+        ),
+        None => println!("  {}", standalone),
+    }
+    // A running Steam client starts the game itself, without this environment
+    if game.source == GameSource::Steam {
+        println!("\n{}", tr!("games-info-steam-note", id = game.id));
+    }
+    Ok(())
+}
+
+/// Add per-game cache variables, keeping paths the config or profile chose
+fn add_cache_env(env_vars: &mut HashMap<String, String>, cache_env: &[(String, String)]) {
+    for (key, value) in cache_env {
+        env_vars.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+}
+
+/// Command, working directory, environment and hooks of a launch, as shown by
+/// `run --dry-run` and `games info`
+fn print_launch_details(
+    launch_cmd: &[String],
+    cwd: Option<&Path>,
+    env_vars: &HashMap<String, String>,
+    hooks: &HookRunner,
+    conflicts: &[EnvConflict],
+) {
+    let command = launch_cmd
+        .iter()
+        .map(|a| shell_quote(a))
+        .collect::<Vec<_>>()
+        .join(" ");
+    println!("  {}", tr!("run-dry-run-command", command = command));
+    if let Some(cwd) = cwd {
+        println!("  {}", tr!("run-dry-run-cwd", dir = cwd.display()));
+    }
+    println!("  {}", tr!("run-dry-run-environment"));
+    let mut keys: Vec<_> = env_vars.keys().collect();
+    keys.sort();
+    for key in keys {
+        println!("    {}={}", key, env_vars[key]);
+    }
+    if !hooks.is_empty() {
+        let names = hooks.names().join(", ");
+        println!("  {}", tr!("run-dry-run-hooks", hooks = names));
+    }
+    print_env_conflicts(conflicts);
+}
+
+/// `KEY=value ... command args` for pasting into a shell
+fn standalone_command(env_vars: &HashMap<String, String>, launch_cmd: &[String]) -> String {
+    let mut keys: Vec<_> = env_vars.keys().collect();
+    keys.sort();
+    keys.into_iter()
+        .map(|key| format!("{}={}", key, shell_quote(&env_vars[key])))
+        .chain(launch_cmd.iter().map(|a| shell_quote(a)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Render a standalone POSIX script reproducing a launch
+fn render_launch_script(
+    game: &DetectedGame,
+    env_vars: &HashMap<String, String>,
+    launch_cmd: &[String],
+    cwd: Option<&Path>,
+) -> String {
+    let mut script = String::from("#!/bin/sh\n");
+    script.push_str(&format!(
+        "# Launch script for {} ({}:{}) generated by nvproton\n\n",
+        game.name, game.source, game.id
+    ));
+
+    let mut keys: Vec<_> = env_vars.keys().collect();
+    keys.sort();
+    for key in keys {
+        script.push_str(&format!("export {}={}\n", key, shell_quote(&env_vars[key])));
+    }
+
+    if let Some(cwd) = cwd {
+        script.push_str(&format!(
+            "\ncd {} || exit 1\n",
+            shell_quote(&cwd.to_string_lossy())
+        ));
+    }
+
+    let command: Vec<String> = launch_cmd.iter().map(|a| shell_quote(a)).collect();
+    script.push_str(&format!("\nexec {} \"$@\"\n", command.join(" ")));
+    script
+}
+
+pub(crate) fn write_launch_script(path: &Path, script: &str) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory {:?}", parent))?;
+    }
+    fs::write(path, script).with_context(|| format!("failed to write script {:?}", path))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        .with_context(|| format!("failed to make {:?} executable", path))?;
+    Ok(())
+}
+
+/// List the variables two layers set differently and the value that is used
+fn print_env_conflicts(conflicts: &[EnvConflict]) {
+    if conflicts.is_empty() {
+        return;
+    }
+    println!("  {}", tr!("run-env-conflicts"));
+    for conflict in conflicts {
+        let (earlier, previous) = &conflict.overridden;
+        let (layer, value) = &conflict.applied;
+        println!(
+            "    {}",
+            tr!(
+                "run-env-conflict",
+                key = conflict.key,
+                layer = layer,
+                value = value,
+                earlier = earlier,
+                previous = previous
+            )
+        );
+    }
+}
+
+/// Quote a string for POSIX sh
+pub(crate) fn shell_quote(value: &str) -> String {
+    let safe = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if safe {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Set the runtime variables for the anti-cheats a game ships, warning when
+/// a runtime isn't installed
+fn apply_anticheat_runtimes(
+    config: &NvConfig,
+    game: &DetectedGame,
+    env_vars: &mut HashMap<String, String>,
+) {
+    for anticheat in anticheat::detect(&game.install_dir) {
+        if env_vars.contains_key(anticheat.env_var()) {
+            continue;
+        }
+        match anticheat.runtime(config) {
+            Some(runtime) => {
+                println!(
+                    "  {}",
+                    tr!(
+                        "run-anticheat-runtime",
+                        runtime = anticheat.runtime_name(),
+                        path = runtime.display()
+                    )
+                );
+                env_vars.insert(
+                    anticheat.env_var().to_string(),
+                    runtime.to_string_lossy().into_owned(),
+                );
+            }
+            None => eprintln!(
+                "  {}",
+                tr!(
+                    "run-anticheat-missing",
+                    name = game.name,
+                    anticheat = anticheat.name(),
+                    runtime = anticheat.runtime_name(),
+                    appid = anticheat.runtime_app_id()
+                )
+            ),
+        }
+    }
+}
+
+/// Apply `--proton-*` flags to environment variables
+fn apply_proton_flags(args: &LaunchOptions, env_vars: &mut HashMap<String, String>) {
+    let flags = [
+        ("PROTON_LOG", args.proton_log),
+        ("PROTON_ENABLE_NVAPI", args.proton_enable_nvapi),
+        ("PROTON_HIDE_NVIDIA_GPU", args.proton_hide_nvidia_gpu),
+        ("PROTON_NO_ESYNC", args.proton_no_esync),
+        ("PROTON_NO_FSYNC", args.proton_no_fsync),
+    ];
+    for (key, enabled) in flags {
+        if enabled {
+            env_vars.insert(key.into(), "1".into());
+        }
+    }
+}
+
+/// Select the Wine synchronization `--sync` asks for
+///
+/// A mechanism the kernel doesn't support falls back to the fastest one it
+/// does, since Wine would otherwise quietly use wineserver.
+fn select_sync(mode: SyncMode, env_vars: &mut HashMap<String, String>) -> Option<SyncMechanism> {
+    let support = SyncSupport::detect();
+    let selected = match wine_sync::requested(mode) {
+        Some(mechanism) if support.supports(mechanism) => Some(mechanism),
+        Some(mechanism) => {
+            let requirement = mechanism.requirement();
+            eprintln!(
+                "  {}",
+                tr!(
+                    "run-sync-unsupported",
+                    mechanism = mechanism.to_string(),
+                    requirement = requirement
+                )
+            );
+            support.best()
+        }
+        None => support.best(),
+    };
+    match selected {
+        Some(mechanism) => {
+            mechanism.apply(env_vars);
+            println!("  {}", tr!("run-sync", mechanism = mechanism.to_string()));
+        }
+        None => eprintln!("  {}", tr!("run-sync-none")),
+    }
+    selected
+}
+
+/// Frame Generation from `--dlss-fg` or the profile's `dlss.frame_generation`
+/// (`enabled`, `multi_4x`, `dynamic`, ...)
+fn frame_generation_requested(opts: &LaunchOptions, settings: Option<&serde_yaml::Value>) -> bool {
+    opts.dlss_fg
+        || settings
+            .and_then(|s| s.get("dlss"))
+            .and_then(|dlss| dlss.get("frame_generation"))
+            .and_then(profile_env_value)
+            .is_some_and(|value| value != "0" && value != "none")
+}
+
+/// Reflex, VRR, frame limit and MangoHud for a launch
+///
+/// `run_defaults` from the config, overridden by the profile's `run`
+/// section, overridden by the flags given to the command.
+fn run_toggles(
+    config: &NvConfig,
+    settings: Option<&serde_yaml::Value>,
+    opts: &LaunchOptions,
+) -> Result<RunDefaults> {
+    let profile = match settings.and_then(|s| s.get("run")) {
+        Some(section) => profile_run_overrides(section)?,
+        None => RunOverrides::default(),
+    };
+    let flag = |on: bool, off: bool| (on || off).then_some(on);
+    let cli = RunOverrides {
+        reflex: flag(opts.reflex, opts.no_reflex),
+        vrr: flag(opts.vrr, opts.no_vrr),
+        fps: opts.fps,
+        mangohud: flag(opts.mangohud, opts.no_mangohud),
+    };
+    Ok(config.run_defaults.with(profile).with(cli))
+}
+
+/// Values of a profile's `run` section
+fn profile_run_overrides(section: &serde_yaml::Value) -> Result<RunOverrides> {
+    let serde_yaml::Value::Mapping(map) = section else {
+        bail!("the profile's 'run' section must be a mapping");
+    };
+    let mut overrides = RunOverrides::default();
+    for (key, value) in map {
+        let key = key.as_str().unwrap_or_default();
+        let value = profile_env_value(value).unwrap_or_default();
+        let flag = || match value.as_str() {
+            "1" => Ok(true),
+            "0" => Ok(false),
+            _ => Err(anyhow::anyhow!(
+                "run.{} must be true or false, got '{}'",
+                key,
+                value
+            )),
+        };
+        match key {
+            "reflex" => overrides.reflex = Some(flag()?),
+            "vrr" => overrides.vrr = Some(flag()?),
+            "mangohud" => overrides.mangohud = Some(flag()?),
+            "fps" => {
+                let fps = value
+                    .parse()
+                    .with_context(|| format!("run.fps must be a frame rate, got '{}'", value))?;
+                overrides.fps = Some(fps);
+            }
+            _ => bail!(
+                "unknown profile key 'run.{}' (reflex, vrr, fps, mangohud)",
+                key
+            ),
+        }
+    }
+    Ok(overrides)
+}
+
+/// Smooth Motion from `--smooth-motion` or the profile's `nvidia.smooth_motion`
+fn smooth_motion_requested(
+    opts: &LaunchOptions,
+    settings: Option<&serde_yaml::Value>,
+) -> Option<bool> {
+    if opts.smooth_motion {
+        return Some(true);
+    }
+    settings
+        .and_then(|s| s.get("nvidia"))
+        .and_then(|nvidia| nvidia.get("smooth_motion"))
+        .and_then(profile_env_value)
+        .map(|value| value != "0")
+}
+
+/// Toggle Smooth Motion in the driver's present layer
+///
+/// Enabling is skipped with a warning when the GPU or driver is known not to
+/// support it; without Vulkan detection the variable is set regardless.
+fn apply_smooth_motion(
+    enabled: bool,
+    caps: Option<&VulkanCapabilities>,
+    env_vars: &mut HashMap<String, String>,
+) {
+    if enabled
+        && let Some(caps) = caps
+        && !caps.supports_smooth_motion()
+    {
+        eprintln!(
+            "  {}",
+            tr!(
+                "run-smooth-motion-unsupported",
+                gpu = caps.gpu_name,
+                version = caps.driver_version,
+                min = SMOOTH_MOTION_MIN_DRIVER
+            )
+        );
+        return;
+    }
+    let value = if enabled { "1" } else { "0" };
+    env_vars.insert("NVPRESENT_ENABLE_SMOOTH_MOTION".into(), value.into());
+    if enabled {
+        println!("  {}", tr!("run-smooth-motion"));
+    }
+}
+
+/// Check PROTON_* variables for combinations that cannot work together
+fn validate_proton_env(env_vars: &HashMap<String, String>) -> Result<()> {
+    let is_set = |key: &str| env_vars.get(key).is_some_and(|v| !v.is_empty() && v != "0");
+
+    if is_set("PROTON_ENABLE_NVAPI") && is_set("PROTON_HIDE_NVIDIA_GPU") {
+        anyhow::bail!(
+            "PROTON_ENABLE_NVAPI conflicts with PROTON_HIDE_NVIDIA_GPU: NVAPI is unavailable when the GPU is reported as AMD"
+        );
+    }
+
+    if is_set("PROTON_NO_ESYNC") && is_set("PROTON_NO_FSYNC") {
+        eprintln!("  {}", tr!("run-sync-disabled"));
+    }
+
+    Ok(())
+}
+
+/// Convert a profile value to an environment variable value
+///
+/// Booleans (including "true"/"false" strings written by `profile set`) become
+/// "1"/"0", since Proton treats any non-empty value other than "0" as enabled.
+fn profile_env_value(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::Bool(b) => Some(if *b { "1" } else { "0" }.into()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        serde_yaml::Value::String(s) => Some(match s.to_lowercase().as_str() {
+            "true" | "yes" | "on" | "enabled" => "1".into(),
+            "false" | "no" | "off" | "disabled" => "0".into(),
+            _ => s.clone(),
+        }),
+        _ => None,
+    }
+}
+
 /// Apply profile settings to environment variables
 fn apply_profile_to_env(settings: &serde_yaml::Value, env_vars: &mut HashMap<String, String>) {
     if let serde_yaml::Value::Mapping(map) = settings {
@@ -631,7 +2453,20 @@ fn apply_profile_to_env(settings: &serde_yaml::Value, env_vars: &mut HashMap<Str
             map.get(serde_yaml::Value::String("nvidia".into()))
         {
             for (key, value) in nvidia_map {
-                if let serde_yaml::Value::String(k) = key {
+                // smooth_motion is a driver layer toggle, not a __GL_ variable
+                if let serde_yaml::Value::String(k) = key
+                    && k != "smooth_motion"
+                {
+                    // Tuning knobs have their own variable names and values
+                    if let Some(knob) = gl_tuning::knob(k) {
+                        match knob.value(value) {
+                            Ok(v) => {
+                                env_vars.insert(knob.variable.into(), v);
+                            }
+                            Err(e) => eprintln!("  {}", tr!("warning", message = e)),
+                        }
+                        continue;
+                    }
                     let env_key = format!("__GL_{}", k.to_uppercase());
                     match value {
                         serde_yaml::Value::Bool(b) => {
@@ -656,6 +2491,17 @@ fn apply_profile_to_env(settings: &serde_yaml::Value, env_vars: &mut HashMap<Str
             for (key, value) in dxvk_map {
                 if let serde_yaml::Value::String(k) = key {
                     let env_key = format!("DXVK_{}", k.to_uppercase());
+                    // HUD presets expand to DXVK's item list
+                    if k == "hud"
+                        && let serde_yaml::Value::String(spec) = value
+                    {
+                        let hud = dxvk_hud::hud_value(spec).unwrap_or_else(|e| {
+                            eprintln!("  {}", tr!("warning", message = e));
+                            spec.clone()
+                        });
+                        env_vars.insert(env_key, hud);
+                        continue;
+                    }
                     match value {
                         serde_yaml::Value::Bool(b) => {
                             env_vars.insert(env_key, if *b { "1" } else { "0" }.into());
@@ -672,13 +2518,36 @@ fn apply_profile_to_env(settings: &serde_yaml::Value, env_vars: &mut HashMap<Str
             }
         }
 
+        // Handle proton section (enable_nvapi, hide_nvidia_gpu, no_esync, no_fsync, log)
+        if let Some(serde_yaml::Value::Mapping(proton_map)) =
+            map.get(serde_yaml::Value::String("proton".into()))
+        {
+            for (key, value) in proton_map {
+                if let serde_yaml::Value::String(k) = key
+                    && let Some(v) = profile_env_value(value)
+                {
+                    env_vars.insert(format!("PROTON_{}", k.to_uppercase()), v);
+                }
+            }
+        }
+
+        // Handle audio section (sink, volume)
+        if let Some(audio) = map.get(serde_yaml::Value::String("audio".into())) {
+            hooks::audio::apply_env(audio, env_vars);
+        }
+
+        // Handle input section (controller_config, steam_input, prefer_sdl)
+        if let Some(section) = map.get(serde_yaml::Value::String("input".into())) {
+            input::apply_profile(section, env_vars);
+        }
+
         // Handle vkd3d section
         if let Some(serde_yaml::Value::Mapping(vkd3d_map)) =
             map.get(serde_yaml::Value::String("vkd3d".into()))
         {
             // Handle descriptor_heap setting
             if let Some(serde_yaml::Value::String(mode)) =
-                vkd3d_map.get(&serde_yaml::Value::String("descriptor_heap".into()))
+                vkd3d_map.get(serde_yaml::Value::String("descriptor_heap".into()))
             {
                 match mode.as_str() {
                     "on" | "enabled" | "true" => {
@@ -696,17 +2565,343 @@ fn apply_profile_to_env(settings: &serde_yaml::Value, env_vars: &mut HashMap<Str
 
             // Handle config setting (VKD3D_CONFIG value)
             if let Some(serde_yaml::Value::String(config_val)) =
-                vkd3d_map.get(&serde_yaml::Value::String("config".into()))
+                vkd3d_map.get(serde_yaml::Value::String("config".into()))
             {
                 env_vars.insert("VKD3D_CONFIG".into(), config_val.clone());
             }
 
             // Handle feature_level setting
             if let Some(serde_yaml::Value::String(level)) =
-                vkd3d_map.get(&serde_yaml::Value::String("feature_level".into()))
+                vkd3d_map.get(serde_yaml::Value::String("feature_level".into()))
             {
                 env_vars.insert("VKD3D_FEATURE_LEVEL".into(), level.clone());
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn profile(yaml: &str) -> serde_yaml::Value {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn run_args(argv: &[&str]) -> RunArgs {
+        let cli = crate::cli::Cli::try_parse_from(["nvproton", "run"].iter().chain(argv)).unwrap();
+        match cli.command {
+            crate::cli::Commands::Run(args) => args,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_run_wraps_launcher_command_only_with_wrapper_flag() {
+        let install_dir = Path::new("/games/Hades");
+        let launch_args = LaunchArgs {
+            args: vec!["-nointro".into()],
+            cwd: None,
+        };
+
+        // Plain arguments stay arguments for the game, even inside Steam
+        let args = run_args(&["1145360", "--", "-dx11"]);
+        assert!(!args.wrapper);
+        assert_eq!(
+            run_game_args(&args, &launch_args, install_dir),
+            ["-nointro", "-dx11"]
+        );
+
+        let args = run_args(&[
+            "--wrapper",
+            "--",
+            "/proton",
+            "waitforexitandrun",
+            "/games/Hades/Hades.exe",
+            "-dx11",
+        ]);
+        assert!(args.wrapper);
+        assert_eq!(
+            run_game_args(&args, &launch_args, install_dir),
+            [
+                "/proton",
+                "waitforexitandrun",
+                "/games/Hades/Hades.exe",
+                "-nointro",
+                "-dx11"
+            ]
+        );
+
+        // --wrapper without a command to wrap is a usage error
+        assert!(crate::cli::Cli::try_parse_from(["nvproton", "run", "--wrapper"]).is_err());
+    }
+
+    #[test]
+    fn test_handed_off_runtime_starts_with_the_game() {
+        let dir = tempfile::tempdir().unwrap();
+        let game = DetectedGame {
+            id: "Sugar".into(),
+            name: "Sugar".into(),
+            source: GameSource::Heroic,
+            install_dir: dir.path().to_path_buf(),
+            executable: None,
+            fingerprint: None,
+            metadata: HashMap::new(),
+        };
+        // The launcher takes 3s to start a game that dies right away
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "sleep 3; cd \"$GAME_DIR\" && exec sleep 0.5"])
+            .env("GAME_DIR", dir.path());
+        let started = Instant::now();
+        let (success, _, runtime) =
+            run_handed_off(cmd, &game, "sh", Duration::from_secs(30)).unwrap();
+        let window = Duration::from_secs(3);
+        assert!(success);
+        assert!(started.elapsed() >= window);
+        assert!(triage::died_early(runtime.unwrap(), window, &game.source));
+    }
+
+    #[test]
+    fn test_proton_section_maps_to_env() {
+        let settings = profile(
+            "proton:\n  enable_nvapi: true\n  no_esync: \"false\"\n  log: \"+timestamp,+seh\"\n",
+        );
+        let mut env_vars = HashMap::new();
+        apply_profile_to_env(&settings, &mut env_vars);
+
+        assert_eq!(env_vars.get("PROTON_ENABLE_NVAPI"), Some(&"1".to_string()));
+        assert_eq!(env_vars.get("PROTON_NO_ESYNC"), Some(&"0".to_string()));
+        assert_eq!(
+            env_vars.get("PROTON_LOG"),
+            Some(&"+timestamp,+seh".to_string())
+        );
+    }
+
+    #[test]
+    fn test_gl_tuning_keys_from_profile() {
+        let settings = profile(
+            "nvidia:\n  max_frames_allowed: 1\n  yield: usleep\n  threaded_optimizations: 5\n  sync_to_vblank: false\n",
+        );
+        let mut env_vars = HashMap::new();
+        apply_profile_to_env(&settings, &mut env_vars);
+
+        assert_eq!(env_vars["__GL_MaxFramesAllowed"], "1");
+        assert_eq!(env_vars["__GL_YIELD"], "USLEEP");
+        assert_eq!(env_vars["__GL_SYNC_TO_VBLANK"], "0");
+        // Invalid values are left out rather than passed on
+        assert!(!env_vars.contains_key("__GL_THREADED_OPTIMIZATIONS"));
+    }
+
+    #[test]
+    fn test_smooth_motion_from_profile() {
+        let opts = run_args(&["1145360"]).launch;
+        let settings = profile("nvidia:\n  smooth_motion: true\n  threaded_optimization: 1\n");
+        assert_eq!(smooth_motion_requested(&opts, Some(&settings)), Some(true));
+        assert_eq!(smooth_motion_requested(&opts, None), None);
+        let opts = run_args(&["1145360", "--smooth-motion"]).launch;
+        let off = profile("nvidia:\n  smooth_motion: 0\n");
+        assert_eq!(smooth_motion_requested(&opts, Some(&off)), Some(true));
+
+        // The toggle goes to the present layer, not a __GL_ variable
+        let mut env_vars = HashMap::new();
+        apply_profile_to_env(&settings, &mut env_vars);
+        assert!(!env_vars.contains_key("__GL_SMOOTH_MOTION"));
+        assert!(env_vars.contains_key("__GL_THREADED_OPTIMIZATION"));
+
+        let mut caps = VulkanCapabilities {
+            gpu_name: "NVIDIA GeForce RTX 3080".into(),
+            driver_branch: 580,
+            ..Default::default()
+        };
+        apply_smooth_motion(true, Some(&caps), &mut env_vars);
+        assert!(!env_vars.contains_key("NVPRESENT_ENABLE_SMOOTH_MOTION"));
+        caps.gpu_name = "NVIDIA GeForce RTX 4080".into();
+        apply_smooth_motion(true, Some(&caps), &mut env_vars);
+        assert_eq!(env_vars["NVPRESENT_ENABLE_SMOOTH_MOTION"], "1");
+        apply_smooth_motion(false, None, &mut env_vars);
+        assert_eq!(env_vars["NVPRESENT_ENABLE_SMOOTH_MOTION"], "0");
+    }
+
+    #[test]
+    fn test_profile_run_overrides() {
+        // `profile set` stores values as strings
+        let settings = profile("run:\n  reflex: 'false'\n  fps: '60'\n  mangohud: on\n");
+        let overrides = profile_run_overrides(&settings["run"]).unwrap();
+        assert_eq!(overrides.reflex, Some(false));
+        assert_eq!(overrides.fps, Some(60));
+        assert_eq!(overrides.mangohud, Some(true));
+        assert_eq!(overrides.vrr, None);
+        assert!(profile_run_overrides(&profile("reflx: true\n")).is_err());
+        assert!(profile_run_overrides(&profile("fps: fast\n")).is_err());
+    }
+
+    #[test]
+    fn test_dxvk_hud_preset_from_profile() {
+        let settings = profile("dxvk:\n  hud: stutter\n  log_level: debug\n");
+        let mut env_vars = HashMap::new();
+        apply_profile_to_env(&settings, &mut env_vars);
+
+        assert_eq!(
+            env_vars.get("DXVK_HUD").map(String::as_str),
+            Some("fps,frametimes,compiler,pipelines")
+        );
+        assert_eq!(
+            env_vars.get("DXVK_LOG_LEVEL").map(String::as_str),
+            Some("debug")
+        );
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("steam"), "steam");
+        assert_eq!(
+            shell_quote("/games/My Game/game.exe"),
+            "'/games/My Game/game.exe'"
+        );
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn test_standalone_command() {
+        let env_vars = HashMap::from([
+            ("PROTON_LOG".to_string(), "1".to_string()),
+            ("DXVK_HUD".to_string(), "fps,frametimes".to_string()),
+            (
+                "WINEDLLOVERRIDES".to_string(),
+                "dxgi=n,b;d3d11=n".to_string(),
+            ),
+        ]);
+        let launch_cmd = vec![
+            "/games/My Game/run.sh".to_string(),
+            "-fullscreen".to_string(),
+        ];
+        assert_eq!(
+            standalone_command(&env_vars, &launch_cmd),
+            "DXVK_HUD=fps,frametimes PROTON_LOG=1 WINEDLLOVERRIDES='dxgi=n,b;d3d11=n' \
+             '/games/My Game/run.sh' -fullscreen"
+        );
+    }
+
+    fn game(source: GameSource, id: &str, name: &str, install_dir: &Path) -> DetectedGame {
+        DetectedGame {
+            id: id.into(),
+            name: name.into(),
+            source,
+            install_dir: install_dir.to_path_buf(),
+            executable: None,
+            fingerprint: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_games_to_prepare_by_source_and_tags() {
+        let mut db = GameDatabase::default();
+        let dir = Path::new("/games");
+        db.merge_detected(
+            &[
+                game(GameSource::Steam, "1145360", "Hades", dir),
+                game(GameSource::Lutris, "hades", "Hades", dir),
+                game(GameSource::Heroic, "Sugar", "Sugar", dir),
+            ],
+            0,
+        );
+
+        // Without a source, a game installed twice is prepared once
+        assert_eq!(games_to_prepare(&db, None, &[]).unwrap().len(), 2);
+
+        let lutris = GameSource::parse_arg("Lutris").unwrap();
+        let games = games_to_prepare(&db, Some(&lutris), &[]).unwrap();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].game_id().to_string(), "lutris:hades");
+
+        let hades = GameId::new(GameSource::Steam, "1145360");
+        db.set_game_tags(&hades, ["coop".to_string()].into());
+        let games = games_to_prepare(&db, None, &["coop".into()]).unwrap();
+        assert_eq!(games[0].game_id(), hades);
+        assert!(games_to_prepare(&db, None, &["vr".into()]).is_err());
+        assert!(games_to_prepare(&db, Some(&GameSource::Legendary), &[]).is_err());
+    }
+
+    #[test]
+    fn test_prepare_all_counts_install_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        let games = vec![
+            game(GameSource::Steam, "1145360", "Hades", dir.path()),
+            game(GameSource::Heroic, "Sugar", "Sugar", &missing),
+            game(GameSource::Lutris, "celeste", "Celeste", dir.path()),
+        ];
+        assert_eq!(prepare_all(games, Some(2)).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_wrap_resolves_game_and_keeps_command() {
+        let cli = crate::cli::Cli::try_parse_from([
+            "nvproton",
+            "wrap",
+            "lutris:hades",
+            "--",
+            "/games/Hades/Hades.exe",
+            "-dx11",
+        ])
+        .unwrap();
+        let crate::cli::Commands::Wrap(args) = cli.command else {
+            unreachable!()
+        };
+        assert_eq!(args.command, ["/games/Hades/Hades.exe", "-dx11"]);
+
+        let mut db = GameDatabase::default();
+        let dir = Path::new("/games/Hades");
+        db.merge_detected(&[game(GameSource::Lutris, "hades", "Hades", dir)], 0);
+        let hades = resolve_wrapped_game(&db, args.game_id.as_deref()).unwrap();
+        assert_eq!(hades.game_id().to_string(), "lutris:hades");
+        assert_eq!(hades.install_dir, dir);
+
+        // Unknown games are still wrapped, with config-level tuning only
+        let unknown = resolve_wrapped_game(&db, Some("celeste")).unwrap();
+        assert_eq!(unknown.source, GameSource::Unknown);
+        assert_eq!(unknown.name, "celeste (not in database)");
+    }
+
+    #[test]
+    fn test_cache_isolation_keeps_chosen_paths() {
+        assert!(!run_args(&["1145360"]).launch.no_cache_isolation);
+        let args = run_args(&["1145360", "--no-cache-isolation"]);
+        assert!(args.launch.no_cache_isolation);
+
+        let mut env_vars =
+            HashMap::from([("DXVK_STATE_CACHE_PATH".to_string(), "/srv/dxvk".to_string())]);
+        let cache_env = [
+            (
+                "DXVK_STATE_CACHE_PATH".to_string(),
+                "/home/user/.cache/nvproton/dxvk/steam-1145360".to_string(),
+            ),
+            (
+                "VKD3D_SHADER_CACHE_PATH".to_string(),
+                "/home/user/.cache/nvproton/vkd3d/steam-1145360".to_string(),
+            ),
+        ];
+        add_cache_env(&mut env_vars, &cache_env);
+        assert_eq!(env_vars["DXVK_STATE_CACHE_PATH"], "/srv/dxvk");
+        assert_eq!(
+            env_vars["VKD3D_SHADER_CACHE_PATH"],
+            "/home/user/.cache/nvproton/vkd3d/steam-1145360"
+        );
+    }
+
+    #[test]
+    fn test_proton_nvapi_conflict() {
+        let mut env_vars = HashMap::new();
+        env_vars.insert("PROTON_ENABLE_NVAPI".to_string(), "1".to_string());
+        assert!(validate_proton_env(&env_vars).is_ok());
+
+        env_vars.insert("PROTON_HIDE_NVIDIA_GPU".to_string(), "0".to_string());
+        assert!(validate_proton_env(&env_vars).is_ok());
+
+        env_vars.insert("PROTON_HIDE_NVIDIA_GPU".to_string(), "1".to_string());
+        assert!(validate_proton_env(&env_vars).is_err());
+    }
+}
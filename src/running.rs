@@ -0,0 +1,372 @@
+//! Running game sessions
+//!
+//! While a game runs, `run` holds a per-game lock in the runtime directory
+//! (`$XDG_RUNTIME_DIR/nvproton/sessions/<source>-<id>.yaml`). Launching the same
+//! game again, which is easy to do while Steam is slow to show the window,
+//! stops with an error instead of starting a second copy; `run --force`
+//! launches anyway. Locks whose process is gone are stale and ignored, so a
+//! crash never leaves a game locked.
+//!
+//! `nvproton ps` lists the games currently running through nvproton and
+//! `nvproton stop <id>` ends one: SIGTERM to the game's processes, then
+//! `wineserver -k` for its prefix so no Wine process is left behind.
+
+use std::fs;
+use std::io::{self, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{OutputFormat, PsArgs, StopArgs};
+use crate::config::{ConfigManager, ConfigPaths, NvConfig};
+use crate::detection::{DetectedGame, GameDatabase, GameId};
+use crate::errors::NvError;
+use crate::games::{format_age, format_duration};
+use crate::paths::sanitize;
+use crate::saves::wine_prefix;
+use crate::tracking::{self, GameMatcher};
+
+/// A game launched by a live `nvproton run`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunningSession {
+    /// `source:id` of the game
+    pub game_id: String,
+    pub name: String,
+    /// PID of the nvproton process supervising the game
+    pub pid: u32,
+    /// Start time of that process (clock ticks since boot), guarding
+    /// against a reused PID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process_start: Option<u64>,
+    /// PID of the game when nvproton started it directly (not through
+    /// Steam or Heroic)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_pid: Option<u32>,
+    /// Unix timestamp the session started at
+    pub started_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+}
+
+impl RunningSession {
+    fn dir(paths: &ConfigPaths) -> PathBuf {
+        paths.runtime_dir.join("sessions")
+    }
+
+    fn path(paths: &ConfigPaths, game_id: &GameId) -> PathBuf {
+        Self::dir(paths).join(format!("{}.yaml", sanitize(&game_id.dir_name())))
+    }
+
+    /// Whether the process that wrote the lock still runs
+    pub fn is_alive(&self) -> bool {
+        process_start_time(self.pid)
+            .is_some_and(|start| self.process_start.is_none_or(|recorded| recorded == start))
+    }
+
+    pub fn uptime_secs(&self) -> u64 {
+        now().saturating_sub(self.started_at)
+    }
+}
+
+/// Lock held for the duration of a game session; removed when dropped
+#[derive(Debug)]
+pub struct SessionLock {
+    path: PathBuf,
+    session: RunningSession,
+}
+
+impl SessionLock {
+    /// Take the game's lock, failing when another live session holds it
+    /// unless `force` is set
+    pub fn acquire(
+        paths: &ConfigPaths,
+        game: &DetectedGame,
+        profile: Option<String>,
+        force: bool,
+    ) -> Result<Self> {
+        let game_id = game.game_id();
+        let path = RunningSession::path(paths, &game_id);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create directory {:?}", parent))?;
+        }
+        let pid = std::process::id();
+        let session = RunningSession {
+            game_id: game_id.to_string(),
+            name: game.name.clone(),
+            pid,
+            process_start: process_start_time(pid),
+            game_pid: None,
+            started_at: now(),
+            profile,
+        };
+        let content = serde_yaml::to_string(&session)?;
+
+        // create_new keeps two launches racing for the lock from both winning
+        match fs::File::options().write(true).create_new(true).open(&path) {
+            Ok(mut file) => file.write_all(content.as_bytes())?,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                if let Some(holder) = read_session(&path).filter(RunningSession::is_alive)
+                    && !force
+                {
+                    bail!(
+                        "{} is already running through nvproton (PID {}, started {}); use --force to launch it again",
+                        holder.name,
+                        holder.pid,
+                        format_age(holder.started_at)
+                    );
+                }
+                fs::write(&path, content)
+                    .with_context(|| format!("failed to write session lock {:?}", path))?;
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to create session lock {:?}", path));
+            }
+        }
+        Ok(Self { path, session })
+    }
+
+    /// Record the PID of the game process for `ps` and `stop`
+    pub fn set_game_pid(&mut self, pid: u32) {
+        self.session.game_pid = Some(pid);
+        if let Err(e) = self.write() {
+            log::warn!("failed to update session lock {:?}: {}", self.path, e);
+        }
+    }
+
+    fn write(&self) -> Result<()> {
+        fs::write(&self.path, serde_yaml::to_string(&self.session)?)?;
+        Ok(())
+    }
+}
+
+impl Drop for SessionLock {
+    fn drop(&mut self) {
+        // A forced second instance may have taken the lock over
+        if read_session(&self.path).is_some_and(|s| s.pid == self.session.pid) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn read_session(path: &Path) -> Option<RunningSession> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_yaml::from_str(&content).ok()
+}
+
+/// Live sessions, oldest first; stale locks are cleaned up on the way
+pub fn running_sessions(paths: &ConfigPaths) -> Vec<RunningSession> {
+    let Ok(entries) = fs::read_dir(RunningSession::dir(paths)) else {
+        return Vec::new();
+    };
+    let mut sessions = Vec::new();
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        match read_session(&path) {
+            Some(session) if session.is_alive() => sessions.push(session),
+            _ => {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+    sessions.sort_by_key(|s| s.started_at);
+    sessions
+}
+
+/// Start time of a process from `/proc/<pid>/stat`, None when it doesn't exist
+fn process_start_time(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name may contain spaces; fields after it are plain
+    let fields = &stat[stat.rfind(')')? + 1..];
+    fields.split_whitespace().nth(19)?.parse().ok()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn handle_ps(args: PsArgs, manager: &ConfigManager) -> Result<()> {
+    let sessions = running_sessions(manager.paths());
+    match args.format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&sessions)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&sessions)?),
+        OutputFormat::Text => {
+            if sessions.is_empty() {
+                println!("No games running through nvproton");
+                return Ok(());
+            }
+            println!(
+                "{:<8} {:<12} {:<30} {:>10}  PROFILE",
+                "PID", "GAME", "NAME", "UPTIME"
+            );
+            for session in &sessions {
+                println!(
+                    "{:<8} {:<12} {:<30} {:>10}  {}",
+                    session.game_pid.unwrap_or(session.pid),
+                    session.game_id,
+                    session.name,
+                    format_duration(session.uptime_secs()),
+                    session.profile.as_deref().unwrap_or("-")
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn handle_stop(args: StopArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
+    let db = GameDatabase::load_or_default(manager.paths())?;
+    let game_id = db
+        .lookup(&args.game_id)
+        .ok_or_else(|| NvError::GameNotFound(args.game_id.clone()))?;
+    let Some(session) = running_sessions(manager.paths())
+        .into_iter()
+        .find(|s| s.game_id == game_id.to_string())
+    else {
+        bail!(
+            "{} is not running through nvproton (see 'nvproton ps')",
+            args.game_id
+        );
+    };
+    let game = db
+        .get(game_id)
+        .ok_or_else(|| NvError::GameNotFound(args.game_id.clone()))?;
+
+    // Steam's reaper and the Proton processes are found through the AppID
+    // and install dir; the supervising nvproton carries the AppID too
+    let mut pids = tracking::game_processes(&GameMatcher::for_game(&game));
+    if session.game_pid != Some(session.pid) {
+        pids.retain(|&pid| pid != session.pid);
+    }
+    if let Some(pid) = session.game_pid
+        && !pids.contains(&pid)
+    {
+        pids.push(pid);
+    }
+
+    println!("Stopping {} ({})", game.name, game.id);
+    for pid in &pids {
+        let _ = Command::new("kill")
+            .args(["-TERM", &pid.to_string()])
+            .status();
+    }
+    let timeout = Duration::from_secs(args.timeout);
+    let exited = wait_for_exit(&pids, timeout);
+    if !exited {
+        println!("  Still running after {}s", args.timeout);
+    }
+
+    // Wine services and helpers outlive the game unless the server goes
+    if let Some(prefix) = wine_prefix(config, &game)
+        && let Some(wineserver) = prefix_wineserver(&prefix)
+    {
+        println!("  Ending the Wine processes of {}", prefix.display());
+        let status = Command::new(&wineserver)
+            .arg("-k")
+            .env("WINEPREFIX", &prefix)
+            .status()
+            .with_context(|| format!("failed to run {:?}", wineserver))?;
+        if !status.success() {
+            bail!("{:?} -k failed with {}", wineserver, status);
+        }
+        if !exited && !wait_for_exit(&pids, timeout) {
+            bail!("{} did not exit", game.name);
+        }
+    } else if !exited {
+        bail!(
+            "{} did not exit; end it with 'kill -KILL {}'",
+            game.name,
+            pids.iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+    }
+    println!("  Stopped");
+    Ok(())
+}
+
+/// Wait until none of `pids` runs anymore
+fn wait_for_exit(pids: &[u32], timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if pids.iter().all(|&pid| process_start_time(pid).is_none()) {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+}
+
+/// The wineserver binary serving a prefix, if one runs
+///
+/// A wineserver works in `/tmp/.wine-<uid>/server-<dev>-<inode>`, named after
+/// the prefix directory, so the right Proton build's server is found even
+/// when several Wine versions are installed.
+fn prefix_wineserver(prefix: &Path) -> Option<PathBuf> {
+    let metadata = fs::metadata(prefix).ok()?;
+    let server_dir = format!("server-{:x}-{:x}", metadata.dev(), metadata.ino());
+    fs::read_dir("/proc")
+        .ok()?
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        .find_map(|pid| {
+            let exe = fs::read_link(format!("/proc/{}/exe", pid)).ok()?;
+            let cwd = fs::read_link(format!("/proc/{}/cwd", pid)).ok()?;
+            (exe.file_name()? == "wineserver" && cwd.file_name()? == server_dir.as_str())
+                .then_some(exe)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::detection::GameSource;
+
+    #[test]
+    fn test_session_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ConfigManager::portable(dir.path());
+        let paths = manager.paths();
+        let game = DetectedGame {
+            id: "1145360".into(),
+            name: "Hades".into(),
+            source: GameSource::Steam,
+            install_dir: PathBuf::from("/games/Hades"),
+            executable: None,
+            fingerprint: None,
+            metadata: HashMap::new(),
+        };
+
+        let lock = SessionLock::acquire(paths, &game, Some("competitive".into()), false).unwrap();
+        let running = running_sessions(paths);
+        assert_eq!(running.len(), 1);
+        assert_eq!(running[0].game_id, "steam:1145360");
+        assert_eq!(running[0].profile.as_deref(), Some("competitive"));
+        assert!(SessionLock::acquire(paths, &game, None, false).is_err());
+
+        let forced = SessionLock::acquire(paths, &game, None, true).unwrap();
+        assert_eq!(running_sessions(paths)[0].profile, None);
+        drop(forced);
+        drop(lock);
+        assert!(running_sessions(paths).is_empty());
+
+        // A lock left behind by a process that no longer runs is stale
+        let mut stale = running[0].clone();
+        stale.process_start = Some(0);
+        let path = RunningSession::path(paths, &game.game_id());
+        fs::write(&path, serde_yaml::to_string(&stale).unwrap()).unwrap();
+        assert!(running_sessions(paths).is_empty());
+        assert!(!path.exists());
+    }
+}
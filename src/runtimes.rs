@@ -0,0 +1,442 @@
+//! Wine / Proton runtime inventory
+//!
+//! `nvproton runtimes list` finds every Wine and Proton build on the system
+//! and the games set to run with each:
+//! - Steam: Valve's Proton in the libraries and custom tools in
+//!   `compatibilitytools.d` (games map to them through `CompatToolMapping`)
+//! - Lutris: `<lutris>/runners/wine/*` (games pick one with `wine.version`)
+//! - Heroic: `<heroic>/tools/{wine,proton}/*` (games pick one with `wineVersion`)
+//! - The system's `wine`
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_yaml::Value;
+
+use crate::cli::{OutputFormat, RuntimesArgs, RuntimesCommand};
+use crate::config::{ConfigManager, NvConfig};
+use crate::detection::{DetectedGame, GameDatabase, GameSource};
+use crate::dxvk::game_prefix;
+use crate::paths::find_in_path;
+use crate::steam::client::SteamClient;
+
+/// Directories holding system-wide Steam compatibility tools
+const SYSTEM_COMPAT_TOOL_DIRS: &[&str] = &[
+    "/usr/share/steam/compatibilitytools.d",
+    "/usr/local/share/steam/compatibilitytools.d",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuntimeKind {
+    Proton,
+    Wine,
+}
+
+/// One Wine or Proton build
+#[derive(Debug, Clone, Serialize)]
+pub struct WineRuntime {
+    /// Directory name, or "wine" for the system build
+    pub name: String,
+    pub kind: RuntimeKind,
+    /// Launcher it belongs to: steam, lutris, heroic or system
+    pub source: &'static str,
+    /// Install directory (the binary for the system build)
+    pub path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Games set to run with it
+    pub used_by: Vec<RuntimeUser>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeUser {
+    pub id: String,
+    pub name: String,
+}
+
+/// Handle the `runtimes` command
+pub fn handle_runtimes(
+    args: RuntimesArgs,
+    manager: &ConfigManager,
+    config: &NvConfig,
+) -> Result<()> {
+    match args.command {
+        RuntimesCommand::List(list_args) => {
+            let db = GameDatabase::load_or_default(manager.paths())?;
+            let mut runtimes = inventory(config);
+            assign_users(&mut runtimes, config, db.games());
+            print_runtimes(&runtimes, list_args.format)
+        }
+    }
+}
+
+/// Every Wine / Proton build nvproton knows where to look for
+pub fn inventory(config: &NvConfig) -> Vec<WineRuntime> {
+    let mut runtimes = Vec::new();
+
+    if let Some(steam) = SteamClient::from_config(config) {
+        let libraries = steam.libraries().unwrap_or_default();
+        let dirs = libraries
+            .iter()
+            .map(|library| library.join("steamapps").join("common"))
+            .chain(std::iter::once(steam.compat_tools_dir()))
+            .chain(SYSTEM_COMPAT_TOOL_DIRS.iter().map(PathBuf::from));
+        for dir in dirs {
+            runtimes.extend(
+                subdirs(&dir)
+                    .filter(|path| path.join("proton").is_file())
+                    .filter_map(|path| runtime_at(&path, "steam")),
+            );
+        }
+    }
+    if let Some(lutris_root) = &config.library_paths.lutris {
+        runtimes.extend(
+            subdirs(&lutris_root.join("runners").join("wine"))
+                .filter_map(|path| runtime_at(&path, "lutris")),
+        );
+    }
+    if let Some(heroic_root) = &config.library_paths.heroic {
+        for kind in ["wine", "proton"] {
+            runtimes.extend(
+                subdirs(&heroic_root.join("tools").join(kind))
+                    .filter_map(|path| runtime_at(&path, "heroic")),
+            );
+        }
+    }
+    runtimes.extend(system_wine());
+
+    // The same library can be listed twice in libraryfolders.vdf
+    runtimes.sort_by(|a, b| (a.source, &a.name, &a.path).cmp(&(b.source, &b.name, &b.path)));
+    runtimes.dedup_by(|a, b| a.path == b.path);
+    runtimes
+}
+
+fn subdirs(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+}
+
+/// A Proton build (has a `proton` script) or Wine build (has `bin/wine`)
+fn runtime_at(path: &Path, source: &'static str) -> Option<WineRuntime> {
+    let kind = if path.join("proton").is_file() {
+        RuntimeKind::Proton
+    } else if path.join("bin/wine").is_file() || path.join("files/bin/wine").is_file() {
+        RuntimeKind::Wine
+    } else {
+        return None;
+    };
+    // Proton's `version` holds "<timestamp> <build>", e.g. "1718199999 proton-9.0-2"
+    let version = fs::read_to_string(path.join("version"))
+        .ok()
+        .and_then(|content| content.split_whitespace().last().map(String::from));
+    Some(WineRuntime {
+        name: path.file_name()?.to_string_lossy().into_owned(),
+        kind,
+        source,
+        path: path.to_path_buf(),
+        version,
+        used_by: Vec::new(),
+    })
+}
+
+/// `wine` from PATH, with the version it reports
+fn system_wine() -> Option<WineRuntime> {
+    let path = find_in_path("wine")?;
+    let version = Command::new(&path)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|version| !version.is_empty());
+    Some(WineRuntime {
+        name: "wine".to_string(),
+        kind: RuntimeKind::Wine,
+        source: "system",
+        path,
+        version,
+        used_by: Vec::new(),
+    })
+}
+
+/// Record which runtime each game is set to run with
+fn assign_users(
+    runtimes: &mut [WineRuntime],
+    config: &NvConfig,
+    games: impl Iterator<Item = DetectedGame>,
+) {
+    for game in games {
+        let Some(index) = runtime_of(runtimes, config, &game) else {
+            continue;
+        };
+        runtimes[index].used_by.push(RuntimeUser {
+            id: game.id,
+            name: game.name,
+        });
+    }
+    for runtime in runtimes.iter_mut() {
+        runtime.used_by.sort_by_key(|user| user.name.to_lowercase());
+    }
+}
+
+/// The Wine or Proton build a game is set to run with
+pub(crate) fn game_runtime(config: &NvConfig, game: &DetectedGame) -> Option<WineRuntime> {
+    let mut runtimes = inventory(config);
+    let index = runtime_of(&runtimes, config, game)?;
+    Some(runtimes.swap_remove(index))
+}
+
+/// Index of the runtime a game runs with
+fn runtime_of(runtimes: &[WineRuntime], config: &NvConfig, game: &DetectedGame) -> Option<usize> {
+    let system = || runtimes.iter().position(|r| r.source == "system");
+    match game.source {
+        GameSource::Steam => {
+            // Native games have no prefix, though the default tool applies
+            game_prefix(game).filter(|prefix| prefix.exists())?;
+            let tool = SteamClient::from_config(config)?.compat_tool(&game.id)?;
+            runtimes.iter().position(|r| r.path == tool)
+        }
+        GameSource::Heroic => {
+            let root = config.library_paths.heroic.as_ref()?;
+            let wine = heroic_wine_version(root, game.metadata.get("app_name")?)?;
+            let bin = wine.bin?;
+            runtimes
+                .iter()
+                .position(|r| r.source != "system" && bin.starts_with(&r.path))
+                .or_else(|| system().filter(|&i| runtimes[i].path == bin))
+        }
+        GameSource::Lutris => {
+            let version = lutris_wine_version(config.library_paths.lutris.as_ref()?, &game.id)?;
+            if version == "system" {
+                return system();
+            }
+            runtimes
+                .iter()
+                .position(|r| r.source == "lutris" && r.name == version)
+        }
+        _ => None,
+    }
+}
+
+/// Proton or Wine build a game runs with, None for native games
+pub(crate) fn compat_tool(config: &NvConfig, game: &DetectedGame) -> Option<String> {
+    match game.source {
+        GameSource::Steam => {
+            // Native games have no prefix, though the default tool applies
+            game_prefix(game).filter(|prefix| prefix.exists())?;
+            let tool = SteamClient::from_config(config)?.compat_tool(&game.id)?;
+            let name = tool.file_name()?.to_string_lossy().into_owned();
+            // `version` holds "<timestamp> <build>", e.g. "1718199999 proton-9.0-2"
+            match fs::read_to_string(tool.join("version")) {
+                Ok(version) if !version.trim().is_empty() => {
+                    Some(format!("{} ({})", name, version.trim()))
+                }
+                _ => Some(name),
+            }
+        }
+        GameSource::Heroic => {
+            let root = config.library_paths.heroic.as_ref()?;
+            let app_name = game.metadata.get("app_name")?;
+            heroic_wine_version(root, app_name).map(|wine| wine.name)
+        }
+        GameSource::Lutris => {
+            let root = config.library_paths.lutris.as_ref()?;
+            lutris_wine_version(root, &game.id)
+        }
+        _ => None,
+    }
+}
+
+/// A Heroic game's `wineVersion`
+pub(crate) struct HeroicWine {
+    /// Display name, e.g. "Proton - GE-Proton9-20"
+    pub name: String,
+    /// Wine binary or Proton script
+    pub bin: Option<PathBuf>,
+}
+
+/// `wineVersion` from Heroic's `GamesConfig/<app_name>.json`
+pub(crate) fn heroic_wine_version(heroic_root: &Path, app_name: &str) -> Option<HeroicWine> {
+    let path = heroic_root
+        .join("GamesConfig")
+        .join(format!("{}.json", app_name));
+    let value: serde_json::Value = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    let wine = value.get(app_name)?.get("wineVersion")?;
+    Some(HeroicWine {
+        name: wine.get("name")?.as_str()?.to_string(),
+        bin: wine
+            .get("bin")
+            .and_then(|bin| bin.as_str())
+            .map(PathBuf::from),
+    })
+}
+
+/// `wine.version` of a Lutris game, falling back to the Wine runner default
+pub(crate) fn lutris_wine_version(lutris_root: &Path, slug: &str) -> Option<String> {
+    let keys = ["wine", "version"];
+    lutris_game_value(lutris_root, slug, &keys).or_else(|| {
+        lutris_roots(lutris_root)
+            .iter()
+            .find_map(|root| yaml_value(&root.join("runners/wine.yml"), &keys))
+    })
+}
+
+/// A string from a Lutris game's config (`games/<slug>-<timestamp>.yml`),
+/// e.g. `["game", "prefix"]`
+pub(crate) fn lutris_game_value(lutris_root: &Path, slug: &str, keys: &[&str]) -> Option<String> {
+    lutris_roots(lutris_root)
+        .iter()
+        .flat_map(|root| {
+            let pattern = root
+                .join("games")
+                .join(format!("{}-*.yml", glob::Pattern::escape(slug)));
+            glob::glob(&pattern.to_string_lossy())
+                .into_iter()
+                .flatten()
+                .filter_map(|path| path.ok())
+        })
+        .find_map(|path| yaml_value(&path, keys))
+}
+
+/// Game configs moved from ~/.config/lutris to the data dir in Lutris 0.5.13
+fn lutris_roots(lutris_root: &Path) -> Vec<PathBuf> {
+    let mut roots = vec![lutris_root.to_path_buf()];
+    roots.extend(dirs::config_dir().map(|dir| dir.join("lutris")));
+    roots
+}
+
+fn yaml_value(path: &Path, keys: &[&str]) -> Option<String> {
+    let mut value: Value = serde_yaml::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    for key in keys {
+        value = value.get(key)?.clone();
+    }
+    value.as_str().map(String::from)
+}
+
+fn print_runtimes(runtimes: &[WineRuntime], format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(runtimes)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(runtimes)?),
+        OutputFormat::Text => {
+            if runtimes.is_empty() {
+                println!("No Wine or Proton runtimes found");
+                return Ok(());
+            }
+            for runtime in runtimes {
+                let kind = match runtime.kind {
+                    RuntimeKind::Proton => "proton",
+                    RuntimeKind::Wine => "wine",
+                };
+                match &runtime.version {
+                    Some(version) => println!(
+                        "[{}] {} ({}, {})",
+                        runtime.source, runtime.name, kind, version
+                    ),
+                    None => println!("[{}] {} ({})", runtime.source, runtime.name, kind),
+                }
+                println!("  path: {}", runtime.path.display());
+                if runtime.used_by.is_empty() {
+                    println!("  used by: no games");
+                } else {
+                    let users: Vec<String> = runtime
+                        .used_by
+                        .iter()
+                        .map(|user| format!("{} ({})", user.name, user.id))
+                        .collect();
+                    println!("  used by: {}", users.join(", "));
+                }
+                println!();
+            }
+            let unused = runtimes.iter().filter(|r| r.used_by.is_empty()).count();
+            println!(
+                "{} runtimes, {} not used by any game",
+                runtimes.len(),
+                unused
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_inventory_maps_games_to_runtimes() {
+        let dir = tempfile::tempdir().unwrap();
+        let lutris = dir.path().join("lutris");
+        let heroic = dir.path().join("heroic");
+        let ge = lutris.join("runners/wine/wine-ge-8-26-x86_64");
+        fs::create_dir_all(ge.join("bin")).unwrap();
+        fs::write(ge.join("bin/wine"), "").unwrap();
+        let proton = heroic.join("tools/proton/GE-Proton9-20");
+        fs::create_dir_all(&proton).unwrap();
+        fs::write(proton.join("proton"), "").unwrap();
+        fs::write(proton.join("version"), "1718199999 GE-Proton9-20\n").unwrap();
+        fs::create_dir_all(heroic.join("tools/wine/empty")).unwrap();
+
+        fs::create_dir_all(lutris.join("games")).unwrap();
+        fs::write(
+            lutris.join("games/diablo-iv-1700000000.yml"),
+            "wine:\n  version: wine-ge-8-26-x86_64\n",
+        )
+        .unwrap();
+        fs::create_dir_all(heroic.join("GamesConfig")).unwrap();
+        fs::write(
+            heroic.join("GamesConfig/Sugar.json"),
+            format!(
+                r#"{{"Sugar": {{"wineVersion": {{"name": "Proton - GE-Proton9-20", "bin": "{}/proton"}}}}}}"#,
+                proton.display()
+            ),
+        )
+        .unwrap();
+
+        let mut config = NvConfig::default();
+        config.library_paths.steam = None;
+        config.library_paths.lutris = Some(lutris);
+        config.library_paths.heroic = Some(heroic);
+        let game = |source, id: &str, metadata: &[(&str, &str)]| DetectedGame {
+            source,
+            id: id.to_string(),
+            name: id.to_string(),
+            install_dir: PathBuf::new(),
+            executable: None,
+            fingerprint: None,
+            metadata: metadata
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+        };
+
+        let mut runtimes: Vec<WineRuntime> = inventory(&config)
+            .into_iter()
+            .filter(|r| r.source != "system")
+            .collect();
+        assign_users(
+            &mut runtimes,
+            &config,
+            [
+                game(GameSource::Lutris, "diablo-iv", &[]),
+                game(GameSource::Heroic, "Sugar", &[("app_name", "Sugar")]),
+            ]
+            .into_iter(),
+        );
+        assert_eq!(runtimes.len(), 2);
+        let (heroic, lutris) = (&runtimes[0], &runtimes[1]);
+        assert_eq!(heroic.kind, RuntimeKind::Proton);
+        assert_eq!(heroic.version.as_deref(), Some("GE-Proton9-20"));
+        assert_eq!(heroic.used_by[0].id, "Sugar");
+        assert_eq!(lutris.kind, RuntimeKind::Wine);
+        assert_eq!(lutris.used_by[0].id, "diablo-iv");
+    }
+}
@@ -0,0 +1,686 @@
+//! Save game backups
+//!
+//! `nvproton saves backup <id>` copies a game's save files into
+//! `<state>/saves/<source>-<id>/<timestamp>/`, keeping the newest `saves.keep`
+//! backups. `saves restore <id> [--at <timestamp>]` copies a backup back
+//! (after backing up the current saves) and `saves list <id>` shows the
+//! backups and where the saves were found. With `saves.backup_on_exit`,
+//! `run` makes a backup after every session, so a Proton upgrade that breaks
+//! saves can be rolled back.
+//!
+//! Save locations come from the Ludusavi manifest (`saves.manifest`, by
+//! default `~/.config/ludusavi/manifest.yaml`) when it lists the game. Its
+//! paths use placeholders like `<base>`, `<winAppData>` or `<home>` and glob
+//! patterns; Windows paths are mapped into the game's Wine prefix. Games it
+//! doesn't know fall back to directories named after the game in the usual
+//! Windows save folders of the prefix.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use walkdir::WalkDir;
+
+use crate::cache::format_bytes;
+use crate::cli::{SavesArgs, SavesCommand};
+use crate::config::{ConfigManager, ConfigPaths, NvConfig};
+use crate::detection::{DetectedGame, GameDatabase, GameId, GameSource};
+use crate::disk_usage::heroic_prefix;
+use crate::dxvk::game_prefix;
+use crate::games::format_age;
+use crate::paths::sanitize;
+
+/// Windows folders games usually keep saves in, relative to the user's profile
+const WINDOWS_SAVE_DIRS: &[&str] = &[
+    "Documents/My Games",
+    "Documents",
+    "Saved Games",
+    "AppData/Roaming",
+    "AppData/Local",
+    "AppData/LocalLow/*",
+];
+
+/// Metadata stored next to the copied files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    pub game_id: String,
+    pub game_name: String,
+    /// Unix timestamp, also the backup's directory name
+    pub created_at: u64,
+    /// Save files and directories that were backed up
+    pub roots: Vec<PathBuf>,
+    pub files: usize,
+    pub size: u64,
+}
+
+/// Directory holding a game's backups
+fn game_backups_dir(paths: &ConfigPaths, game_id: &GameId) -> PathBuf {
+    paths
+        .state_dir
+        .join("saves")
+        .join(sanitize(&game_id.dir_name()))
+}
+
+/// Move backups from the bare-ID directory used before games were keyed by
+/// source, unless another game shares the ID and could own them
+fn migrate_backups(paths: &ConfigPaths, db: &GameDatabase, game_id: &GameId) -> Result<()> {
+    let legacy = paths.state_dir.join("saves").join(sanitize(&game_id.id));
+    if !legacy.is_dir() || db.shares_id(game_id) {
+        return Ok(());
+    }
+    let dir = game_backups_dir(paths, game_id);
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create directory {:?}", dir))?;
+    for entry in fs::read_dir(&legacy)?.filter_map(|e| e.ok()) {
+        let target = dir.join(entry.file_name());
+        if !target.exists() {
+            fs::rename(entry.path(), &target)
+                .with_context(|| format!("failed to move backup {:?}", entry.path()))?;
+        }
+    }
+    // Left in place when a backup of the same second exists in both
+    let _ = fs::remove_dir(&legacy);
+    Ok(())
+}
+
+/// Copy of `path` inside a backup: the absolute path below `files/`
+fn stored_path(backup_dir: &Path, path: &Path) -> PathBuf {
+    backup_dir
+        .join("files")
+        .join(path.strip_prefix("/").unwrap_or(path))
+}
+
+/// Wine prefix (the directory holding `drive_c`) of a game, if it has one
+pub(crate) fn wine_prefix(config: &NvConfig, game: &DetectedGame) -> Option<PathBuf> {
+    let prefix = match game.source {
+        GameSource::Steam => game_prefix(game)?,
+        GameSource::Heroic => {
+            let root = config.library_paths.heroic.as_ref()?;
+            heroic_prefix(root, game.metadata.get("app_name")?)?
+        }
+        _ => return None,
+    };
+    // Proton keeps the actual prefix in pfx/
+    if prefix.join("drive_c").is_dir() {
+        Some(prefix)
+    } else {
+        Some(prefix.join("pfx")).filter(|p| p.join("drive_c").is_dir())
+    }
+}
+
+/// Windows user profile inside a prefix (`steamuser` under Proton)
+fn windows_home(prefix: &Path) -> PathBuf {
+    let users = prefix.join("drive_c/users");
+    if users.join("steamuser").is_dir() {
+        return users.join("steamuser");
+    }
+    fs::read_dir(&users)
+        .ok()
+        .and_then(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .find(|p| p.is_dir() && !p.ends_with("Public"))
+        })
+        .unwrap_or_else(|| users.join("steamuser"))
+}
+
+/// A path as a glob pattern matching only itself
+fn glob_escape(path: &Path) -> String {
+    glob::Pattern::escape(&path.to_string_lossy())
+}
+
+/// Values substituted for the manifest's placeholders
+struct Placeholders {
+    root: String,
+    game: String,
+    base: String,
+    store_game_id: String,
+    home: Option<PathBuf>,
+    windows_home: Option<PathBuf>,
+    prefix: Option<PathBuf>,
+}
+
+impl Placeholders {
+    fn new(game: &DetectedGame, prefix: Option<&Path>) -> Self {
+        Self {
+            root: game
+                .install_dir
+                .parent()
+                .map(glob_escape)
+                .unwrap_or_default(),
+            game: game
+                .install_dir
+                .file_name()
+                .map(|name| glob_escape(Path::new(name)))
+                .unwrap_or_else(|| glob::Pattern::escape(&game.name)),
+            base: glob_escape(&game.install_dir),
+            store_game_id: game.id.clone(),
+            home: dirs::home_dir(),
+            windows_home: prefix.map(windows_home),
+            prefix: prefix.map(Path::to_path_buf),
+        }
+    }
+
+    /// Expand a manifest path; None when it needs a prefix the game lacks
+    fn expand(&self, template: &str, windows: bool) -> Option<String> {
+        let win_home = self.windows_home.as_deref().map(glob_escape);
+        let drive_c = self
+            .prefix
+            .as_deref()
+            .map(|p| glob_escape(&p.join("drive_c")));
+        let home = if windows {
+            win_home.clone()
+        } else {
+            self.home.as_deref().map(glob_escape)
+        };
+        let xdg = |dir: Option<PathBuf>| dir.as_deref().map(glob_escape);
+
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('<') {
+            out.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find('>') else {
+                break;
+            };
+            let name = &rest[start + 1..start + end];
+            let value = match name {
+                "root" => Some(self.root.clone()),
+                // The installed folder, never a wildcard: <root> is the
+                // library holding every other game too
+                "game" => Some(self.game.clone()),
+                "base" => Some(self.base.clone()),
+                "home" => home.clone(),
+                "storeGameId" => Some(self.store_game_id.clone()),
+                "storeUserId" => Some("*".into()),
+                "osUserName" => Some("*".into()),
+                "winAppData" => win_home.as_ref().map(|h| format!("{}/AppData/Roaming", h)),
+                "winLocalAppData" => win_home.as_ref().map(|h| format!("{}/AppData/Local", h)),
+                "winLocalAppDataLow" => {
+                    win_home.as_ref().map(|h| format!("{}/AppData/LocalLow", h))
+                }
+                "winDocuments" => win_home.as_ref().map(|h| format!("{}/Documents", h)),
+                "winPublic" => drive_c.as_ref().map(|c| format!("{}/users/Public", c)),
+                "winProgramData" => drive_c.as_ref().map(|c| format!("{}/ProgramData", c)),
+                "winDir" => drive_c.as_ref().map(|c| format!("{}/windows", c)),
+                "xdgData" => xdg(dirs::data_dir()),
+                "xdgConfig" => xdg(dirs::config_dir()),
+                _ => None,
+            };
+            out.push_str(&value?);
+            rest = &rest[start + end + 1..];
+        }
+        out.push_str(rest);
+        Some(out)
+    }
+}
+
+/// The game's entry in a Ludusavi manifest, by Steam AppID or name
+fn manifest_entry<'a>(manifest: &'a Value, game: &DetectedGame) -> Option<&'a Value> {
+    let entries = manifest.as_mapping()?;
+    if game.source == GameSource::Steam
+        && let Ok(app_id) = game.id.parse::<u64>()
+        && let Some((_, entry)) = entries.iter().find(|(_, entry)| {
+            entry
+                .get("steam")
+                .and_then(|s| s.get("id"))
+                .and_then(Value::as_u64)
+                == Some(app_id)
+        })
+    {
+        return Some(entry);
+    }
+    entries
+        .iter()
+        .find(|(name, _)| {
+            name.as_str()
+                .is_some_and(|name| name.eq_ignore_ascii_case(&game.name))
+        })
+        .map(|(_, entry)| entry)
+}
+
+/// Glob patterns for the save files an entry lists
+fn manifest_patterns(entry: &Value, placeholders: &Placeholders) -> Vec<String> {
+    let Some(files) = entry.get("files").and_then(Value::as_mapping) else {
+        return Vec::new();
+    };
+    let has_prefix = placeholders.prefix.is_some();
+    let mut patterns = Vec::new();
+    for (template, rule) in files {
+        let Some(template) = template.as_str() else {
+            continue;
+        };
+        // Entries without tags are kept; tagged ones must be saves
+        let tags: Vec<&str> = rule
+            .get("tags")
+            .and_then(Value::as_sequence)
+            .map(|tags| tags.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        if !tags.is_empty() && !tags.contains(&"save") {
+            continue;
+        }
+        // Windows paths for games running under Wine, Linux paths otherwise
+        let oses: Vec<&str> = rule
+            .get("when")
+            .and_then(Value::as_sequence)
+            .map(|when| {
+                when.iter()
+                    .filter_map(|w| w.get("os").and_then(Value::as_str))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let windows = if oses.is_empty() {
+            has_prefix || template.contains("<win")
+        } else if has_prefix {
+            oses.contains(&"windows")
+        } else if oses.contains(&"linux") {
+            false
+        } else {
+            continue;
+        };
+        if let Some(pattern) = placeholders.expand(template, windows) {
+            patterns.push(pattern);
+        }
+    }
+    patterns
+}
+
+/// Lowercase letters and digits only, for comparing folder and game names
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Folders named after the game in the prefix's usual save locations
+fn prefix_save_dirs(prefix: &Path, game: &DetectedGame) -> Vec<PathBuf> {
+    let wanted = normalize(&game.name);
+    if wanted.is_empty() {
+        return Vec::new();
+    }
+    let home = glob_escape(&windows_home(prefix));
+    WINDOWS_SAVE_DIRS
+        .iter()
+        .filter_map(|dir| glob::glob(&format!("{}/{}/*", home, dir)).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|path| {
+            path.is_dir()
+                && path
+                    .file_name()
+                    .is_some_and(|name| normalize(&name.to_string_lossy()) == wanted)
+        })
+        .collect()
+}
+
+/// Save files and directories of a game
+pub fn locate_saves(config: &NvConfig, game: &DetectedGame) -> Result<Vec<PathBuf>> {
+    let prefix = wine_prefix(config, game);
+    let manifest_path = config
+        .saves
+        .manifest
+        .clone()
+        .or_else(|| dirs::config_dir().map(|dir| dir.join("ludusavi/manifest.yaml")));
+
+    let mut roots = Vec::new();
+    if let Some(path) = manifest_path.filter(|p| p.exists()) {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read Ludusavi manifest {:?}", path))?;
+        let manifest: Value = serde_yaml::from_str(&content)
+            .with_context(|| format!("failed to parse Ludusavi manifest {:?}", path))?;
+        if let Some(entry) = manifest_entry(&manifest, game) {
+            let placeholders = Placeholders::new(game, prefix.as_deref());
+            for pattern in manifest_patterns(entry, &placeholders) {
+                if let Ok(paths) = glob::glob(&pattern) {
+                    roots.extend(paths.filter_map(|p| p.ok()));
+                }
+            }
+        }
+    }
+    if roots.is_empty()
+        && let Some(prefix) = &prefix
+    {
+        roots = prefix_save_dirs(prefix, game);
+    }
+    roots.sort();
+    roots.dedup();
+    // Drop entries inside another entry
+    let mut unique: Vec<PathBuf> = Vec::new();
+    for root in roots {
+        if !unique.iter().any(|kept| root.starts_with(kept)) {
+            unique.push(root);
+        }
+    }
+    Ok(unique)
+}
+
+/// Back up a game's saves, returning None when no saves were found
+pub fn backup(
+    paths: &ConfigPaths,
+    config: &NvConfig,
+    game: &DetectedGame,
+) -> Result<Option<Backup>> {
+    backup_pinning(paths, config, game, None)
+}
+
+/// Back up a game's saves without rotating away the backup created at `pinned`
+fn backup_pinning(
+    paths: &ConfigPaths,
+    config: &NvConfig,
+    game: &DetectedGame,
+    pinned: Option<u64>,
+) -> Result<Option<Backup>> {
+    let roots = locate_saves(config, game)?;
+    if roots.is_empty() {
+        return Ok(None);
+    }
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let game_id = game.game_id();
+    let dir = game_backups_dir(paths, &game_id).join(created_at.to_string());
+    if dir.exists() {
+        bail!("a backup from this second already exists: {:?}", dir);
+    }
+
+    let mut backup = Backup {
+        game_id: game_id.to_string(),
+        game_name: game.name.clone(),
+        created_at,
+        roots: roots.clone(),
+        files: 0,
+        size: 0,
+    };
+    for root in &roots {
+        for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let target = stored_path(&dir, entry.path());
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("failed to create directory {:?}", parent))?;
+            }
+            backup.size += fs::copy(entry.path(), &target)
+                .with_context(|| format!("failed to back up {:?}", entry.path()))?;
+            backup.files += 1;
+        }
+    }
+    fs::write(dir.join("backup.yaml"), serde_yaml::to_string(&backup)?)
+        .with_context(|| format!("failed to write backup metadata in {:?}", dir))?;
+    rotate(paths, &game_id, config.saves.keep, pinned)?;
+    Ok(Some(backup))
+}
+
+/// A game's backups, oldest first
+pub fn list_backups(paths: &ConfigPaths, game_id: &GameId) -> Result<Vec<Backup>> {
+    let dir = game_backups_dir(paths, game_id);
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut backups = Vec::new();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let meta = entry.path().join("backup.yaml");
+        if let Ok(content) = fs::read_to_string(&meta) {
+            backups.push(
+                serde_yaml::from_str::<Backup>(&content)
+                    .with_context(|| format!("failed to parse {:?}", meta))?,
+            );
+        }
+    }
+    backups.sort_by_key(|b| b.created_at);
+    Ok(backups)
+}
+
+/// Remove all but the newest `keep` backups and the `pinned` one
+fn rotate(paths: &ConfigPaths, game_id: &GameId, keep: usize, pinned: Option<u64>) -> Result<()> {
+    let mut backups = list_backups(paths, game_id)?;
+    backups.retain(|b| Some(b.created_at) != pinned);
+    let excess = backups.len().saturating_sub(keep.max(1));
+    for old in &backups[..excess] {
+        let dir = game_backups_dir(paths, game_id).join(old.created_at.to_string());
+        fs::remove_dir_all(&dir)
+            .with_context(|| format!("failed to remove old backup {:?}", dir))?;
+    }
+    Ok(())
+}
+
+/// Replace the save files with the ones from `backup` of `game_id`
+fn restore(paths: &ConfigPaths, game_id: &GameId, backup: &Backup) -> Result<usize> {
+    let dir = game_backups_dir(paths, game_id).join(backup.created_at.to_string());
+    // Check before clearing anything so a broken backup can't wipe the saves
+    let stored_files = backup
+        .roots
+        .iter()
+        .flat_map(|root| WalkDir::new(stored_path(&dir, root)).into_iter())
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .count();
+    if stored_files == 0 {
+        bail!("backup {} holds no files: {:?}", backup.created_at, dir);
+    }
+    let mut restored = 0;
+    for root in &backup.roots {
+        let stored = stored_path(&dir, root);
+        // Files created after the backup would otherwise survive the restore
+        if root.is_dir() {
+            fs::remove_dir_all(root).with_context(|| format!("failed to clear {:?}", root))?;
+        }
+        for entry in WalkDir::new(&stored).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(&stored).unwrap_or(Path::new(""));
+            let target = if relative.as_os_str().is_empty() {
+                root.clone()
+            } else {
+                root.join(relative)
+            };
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("failed to create directory {:?}", parent))?;
+            }
+            fs::copy(entry.path(), &target)
+                .with_context(|| format!("failed to restore {:?}", target))?;
+            restored += 1;
+        }
+    }
+    Ok(restored)
+}
+
+/// Restore `chosen` after backing up the current saves
+fn restore_over_current(
+    paths: &ConfigPaths,
+    config: &NvConfig,
+    game: &DetectedGame,
+    chosen: &Backup,
+) -> Result<(Option<Backup>, usize)> {
+    // The current saves become a backup of their own first, without rotating
+    // away the one being restored
+    let current = backup_pinning(paths, config, game, Some(chosen.created_at))?;
+    let restored = restore(paths, &game.game_id(), chosen)?;
+    Ok((current, restored))
+}
+
+pub fn handle_saves(args: SavesArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
+    let paths = manager.paths();
+    let db = GameDatabase::load_or_default(paths)?;
+    let game_id = match &args.command {
+        SavesCommand::Backup(game_args) | SavesCommand::List(game_args) => &game_args.game_id,
+        SavesCommand::Restore(restore_args) => &restore_args.game_id,
+    };
+    let game = db.resolve_game(game_id)?;
+    migrate_backups(paths, &db, &game.game_id())?;
+
+    match args.command {
+        SavesCommand::Backup(_) => match backup(paths, config, &game)? {
+            Some(backup) => println!(
+                "Backed up {} file(s) ({}) of {} as {}",
+                backup.files,
+                format_bytes(backup.size),
+                game.name,
+                backup.created_at
+            ),
+            None => bail!(
+                "no save files found for {} (install the Ludusavi manifest or check the prefix)",
+                game.name
+            ),
+        },
+        SavesCommand::List(_) => {
+            let backups = list_backups(paths, &game.game_id())?;
+            println!("Save locations of {}:", game.name);
+            let roots = locate_saves(config, &game)?;
+            if roots.is_empty() {
+                println!("  (none found)");
+            }
+            for root in &roots {
+                println!("  {}", root.display());
+            }
+            println!("\nBackups:");
+            if backups.is_empty() {
+                println!("  (none)");
+            }
+            for backup in backups.iter().rev() {
+                println!(
+                    "  {:<12} {:<10} {:>5} file(s) {:>10}",
+                    backup.created_at,
+                    format_age(backup.created_at),
+                    backup.files,
+                    format_bytes(backup.size)
+                );
+            }
+        }
+        SavesCommand::Restore(restore_args) => {
+            let backups = list_backups(paths, &game.game_id())?;
+            let chosen = match restore_args.at {
+                Some(at) => backups.iter().find(|b| b.created_at == at),
+                None => backups.last(),
+            };
+            let Some(chosen) = chosen.cloned() else {
+                bail!(
+                    "no backup of {} to restore (see 'nvproton saves list {}')",
+                    game.name,
+                    game.game_id()
+                );
+            };
+            let (current, restored) = restore_over_current(paths, config, &game, &chosen)?;
+            if let Some(current) = current {
+                println!("Backed up the current saves as {}", current.created_at);
+            }
+            println!(
+                "Restored {} file(s) of {} from {}",
+                restored, game.name, chosen.created_at
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_backup_and_restore_prefix_saves() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = dir.path().join("steamapps");
+        let saves =
+            library.join("compatdata/1145360/pfx/drive_c/users/steamuser/Saved Games/Hades");
+        fs::create_dir_all(&saves).unwrap();
+        fs::create_dir_all(library.join("common/Hades")).unwrap();
+        fs::write(saves.join("Profile1.sav"), "run 1").unwrap();
+        let game = DetectedGame {
+            id: "1145360".into(),
+            name: "Hades".into(),
+            source: GameSource::Steam,
+            install_dir: library.join("common/Hades"),
+            executable: None,
+            fingerprint: None,
+            metadata: HashMap::new(),
+        };
+        let manager = ConfigManager::portable(dir.path());
+        let mut config = NvConfig::default();
+        config.saves.manifest = Some(dir.path().join("no-manifest.yaml"));
+        config.saves.keep = 1;
+
+        assert_eq!(locate_saves(&config, &game).unwrap(), vec![saves.clone()]);
+        let first = backup(manager.paths(), &config, &game).unwrap().unwrap();
+        assert_eq!(first.files, 1);
+
+        fs::write(saves.join("Profile1.sav"), "run 2").unwrap();
+        fs::write(saves.join("Profile2.sav"), "new").unwrap();
+        assert_eq!(
+            restore(manager.paths(), &game.game_id(), &first).unwrap(),
+            1
+        );
+        assert_eq!(
+            fs::read_to_string(saves.join("Profile1.sav")).unwrap(),
+            "run 1"
+        );
+        assert!(!saves.join("Profile2.sav").exists());
+
+        // Restoring the only (oldest) backup with keep = 1 must not rotate it
+        // away while backing up the current saves
+        let backups_dir = game_backups_dir(manager.paths(), &game.game_id());
+        assert!(backups_dir.ends_with("saves/steam-1145360"));
+        let mut oldest = first.clone();
+        oldest.created_at -= 60;
+        fs::rename(
+            backups_dir.join(first.created_at.to_string()),
+            backups_dir.join(oldest.created_at.to_string()),
+        )
+        .unwrap();
+        fs::write(
+            backups_dir
+                .join(oldest.created_at.to_string())
+                .join("backup.yaml"),
+            serde_yaml::to_string(&oldest).unwrap(),
+        )
+        .unwrap();
+        fs::write(saves.join("Profile1.sav"), "run 3").unwrap();
+        let (current, restored) =
+            restore_over_current(manager.paths(), &config, &game, &oldest).unwrap();
+        assert_eq!(restored, 1);
+        assert_eq!(
+            fs::read_to_string(saves.join("Profile1.sav")).unwrap(),
+            "run 1"
+        );
+        let kept: Vec<u64> = list_backups(manager.paths(), &game.game_id())
+            .unwrap()
+            .iter()
+            .map(|b| b.created_at)
+            .collect();
+        assert_eq!(kept, vec![oldest.created_at, current.unwrap().created_at]);
+
+        // An empty backup fails before the live saves are cleared
+        let empty = Backup {
+            created_at: 1,
+            ..oldest.clone()
+        };
+        assert!(restore(manager.paths(), &game.game_id(), &empty).is_err());
+        assert!(saves.join("Profile1.sav").exists());
+
+        // Manifest paths map Windows folders into the prefix
+        let manifest: Value = serde_yaml::from_str(
+            "Hades:\n  files:\n    <winDocuments>/Saved Games/<game>:\n      tags: [save]\n      \
+             when:\n        - os: windows\n    <base>/Content/Game.log:\n      tags: [config]\n  \
+             steam:\n    id: 1145360\n",
+        )
+        .unwrap();
+        let entry = manifest_entry(&manifest, &game).unwrap();
+        let prefix = library.join("compatdata/1145360/pfx");
+        let patterns = manifest_patterns(entry, &Placeholders::new(&game, Some(&prefix)));
+        assert_eq!(
+            patterns,
+            vec![format!(
+                "{}/drive_c/users/steamuser/Documents/Saved Games/Hades",
+                prefix.display()
+            )]
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! `nvproton self-update`
+//!
+//! Checks GitHub releases for a newer build, verifies the downloaded binary
+//! against the release's `SHA256SUMS` and atomically replaces the running
+//! executable (download next to it, then rename over it).
+//!
+//! Channels:
+//! - stable: the newest non-prerelease, compared by version number
+//! - nightly: the newest release including prereleases; the installed tag is
+//!   recorded in the state directory (`self-update.yaml`)
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{SelfUpdateArgs, UpdateChannel};
+use crate::config::{ConfigManager, ConfigPaths};
+use crate::detection::fingerprint;
+
+pub(crate) const RELEASES_URL: &str =
+    "https://api.github.com/repos/ghostkellz/nvproton/releases?per_page=20";
+const BINARY_ASSET: &str = "nvproton-x86_64-linux";
+pub(crate) const CHECKSUM_ASSET: &str = "SHA256SUMS";
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    pub fn asset_url(&self, name: &str) -> Option<&str> {
+        self.assets
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| a.browser_download_url.as_str())
+    }
+}
+
+/// Release installed by the last self-update
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct UpdateState {
+    #[serde(default)]
+    installed_tag: Option<String>,
+}
+
+impl UpdateState {
+    const FILE: &str = "self-update.yaml";
+
+    fn load(paths: &ConfigPaths) -> Result<Self> {
+        paths.load_state(Self::FILE)
+    }
+
+    fn save(&self, paths: &ConfigPaths) -> Result<()> {
+        paths.save_state(Self::FILE, self)
+    }
+}
+
+/// Handle the `self-update` command
+pub fn handle_self_update(args: SelfUpdateArgs, manager: &ConfigManager) -> Result<()> {
+    let current = env!("CARGO_PKG_VERSION");
+    let mut state = UpdateState::load(manager.paths())?;
+
+    let releases: Vec<Release> = serde_json::from_slice(&download(RELEASES_URL)?)
+        .context("failed to parse GitHub releases")?;
+    let Some(release) = select_release(&releases, args.channel) else {
+        bail!("no {} release found", channel_name(args.channel));
+    };
+
+    let newer = match args.channel {
+        UpdateChannel::Stable => is_newer(&release.tag_name, current),
+        UpdateChannel::Nightly => state.installed_tag.as_deref() != Some(release.tag_name.as_str()),
+    };
+    if !newer {
+        println!(
+            "nvproton {} is up to date ({} channel: {})",
+            current,
+            channel_name(args.channel),
+            release.tag_name
+        );
+        return Ok(());
+    }
+    println!("Update available: {} -> {}", current, release.tag_name);
+    if args.check_only {
+        return Ok(());
+    }
+
+    let exe = std::env::current_exe().context("failed to locate the running executable")?;
+    let exe = fs::canonicalize(&exe).unwrap_or(exe);
+    if exe.starts_with("/usr") && !exe.starts_with("/usr/local") {
+        bail!(
+            "{:?} is managed by the system package manager; update it there instead",
+            exe
+        );
+    }
+
+    let binary_url = release
+        .asset_url(BINARY_ASSET)
+        .with_context(|| format!("release {} has no {} asset", release.tag_name, BINARY_ASSET))?;
+    let sums_url = release.asset_url(CHECKSUM_ASSET).with_context(|| {
+        format!(
+            "release {} has no {}; refusing to install an unverified binary",
+            release.tag_name, CHECKSUM_ASSET
+        )
+    })?;
+    let sums = String::from_utf8_lossy(&download(sums_url)?).to_string();
+    let expected = expected_checksum(&sums, BINARY_ASSET)
+        .with_context(|| format!("{} does not list {}", CHECKSUM_ASSET, BINARY_ASSET))?;
+
+    println!("Downloading {}...", binary_url);
+    let staged = exe.with_file_name(".nvproton.update");
+    download_to(binary_url, &staged)?;
+    if let Err(err) = install_staged(&staged, &exe, &expected) {
+        let _ = fs::remove_file(&staged);
+        return Err(err);
+    }
+
+    state.installed_tag = Some(release.tag_name.clone());
+    state.save(manager.paths())?;
+    println!("Updated {:?} to {}", exe, release.tag_name);
+    Ok(())
+}
+
+/// Verify the staged file, make it executable and rename it over `target`
+pub(crate) fn install_staged(staged: &Path, target: &Path, expected: &str) -> Result<()> {
+    let actual = fingerprint::fingerprint_file(staged)?;
+    if !actual.eq_ignore_ascii_case(expected) {
+        bail!(
+            "checksum mismatch for {:?} (expected {}, got {})",
+            target.file_name().unwrap_or_default(),
+            expected,
+            actual
+        );
+    }
+    fs::set_permissions(staged, fs::Permissions::from_mode(0o755))
+        .with_context(|| format!("failed to make {:?} executable", staged))?;
+    fs::rename(staged, target).with_context(|| format!("failed to replace {:?}", target))
+}
+
+pub(crate) fn download(url: &str) -> Result<Vec<u8>> {
+    let output = curl().arg(url).output().context("failed to run curl")?;
+    if !output.status.success() {
+        bail!(
+            "failed to download {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+pub(crate) fn download_to(url: &str, target: &Path) -> Result<()> {
+    let status = curl()
+        .arg("--output")
+        .arg(target)
+        .arg(url)
+        .status()
+        .context("failed to run curl")?;
+    if !status.success() {
+        let _ = fs::remove_file(target);
+        bail!("failed to download {}", url);
+    }
+    Ok(())
+}
+
+fn curl() -> Command {
+    let mut command = Command::new("curl");
+    command.args([
+        "--fail",
+        "--location",
+        "--silent",
+        "--show-error",
+        "--header",
+        "Accept: application/vnd.github+json",
+    ]);
+    command
+}
+
+pub(crate) fn channel_name(channel: UpdateChannel) -> &'static str {
+    match channel {
+        UpdateChannel::Stable => "stable",
+        UpdateChannel::Nightly => "nightly",
+    }
+}
+
+/// Newest release of a channel (GitHub lists releases newest first)
+pub(crate) fn select_release(releases: &[Release], channel: UpdateChannel) -> Option<&Release> {
+    releases
+        .iter()
+        .filter(|r| !r.draft)
+        .find(|r| channel == UpdateChannel::Nightly || !r.prerelease)
+}
+
+/// Compare a release tag (`v1.2.0`) with the running version
+fn is_newer(tag: &str, current: &str) -> bool {
+    parse_version(tag) > parse_version(current)
+}
+
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['.', '-', '+'])
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+/// Find a file's digest in `sha256sum` output
+pub(crate) fn expected_checksum(sums: &str, file_name: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (digest, name) = line.split_once(char::is_whitespace)?;
+        (name.trim().trim_start_matches('*') == file_name).then(|| digest.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag: &str, prerelease: bool) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            prerelease,
+            draft: false,
+            assets: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_select_release_by_channel() {
+        let releases = vec![
+            release("nightly-2026-10-17", true),
+            release("v0.3.0", false),
+        ];
+        assert_eq!(
+            select_release(&releases, UpdateChannel::Stable)
+                .unwrap()
+                .tag_name,
+            "v0.3.0"
+        );
+        assert_eq!(
+            select_release(&releases, UpdateChannel::Nightly)
+                .unwrap()
+                .tag_name,
+            "nightly-2026-10-17"
+        );
+    }
+
+    #[test]
+    fn test_version_comparison() {
+        assert!(is_newer("v0.2.0", "0.1.9"));
+        assert!(is_newer("v0.10.0", "0.9.0"));
+        assert!(!is_newer("v0.1.0", "0.1.0"));
+    }
+
+    #[test]
+    fn test_expected_checksum() {
+        let sums = "abc123  nvproton-x86_64-linux\ndef456 *nvproton.tar.gz\n";
+        assert_eq!(
+            expected_checksum(sums, "nvproton-x86_64-linux").as_deref(),
+            Some("abc123")
+        );
+        assert_eq!(expected_checksum(sums, "missing"), None);
+    }
+
+    #[test]
+    fn test_install_staged_rejects_bad_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let staged = dir.path().join(".nvproton.update");
+        let exe = dir.path().join("nvproton");
+        fs::write(&staged, "new").unwrap();
+        fs::write(&exe, "old").unwrap();
+
+        assert!(install_staged(&staged, &exe, "0000").is_err());
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "old");
+
+        let digest = fingerprint::fingerprint_file(&staged).unwrap();
+        install_staged(&staged, &exe, &digest).unwrap();
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "new");
+    }
+}
@@ -0,0 +1,468 @@
+//! Per-game session history
+//!
+//! Every launch through `nvproton run` is recorded in the state directory
+//! (`sessions/<game_id>.yaml`, `steam_1145360.yaml` for `steam:1145360`) with
+//! its runtime, exit status and any NVIDIA Xid errors observed while the game
+//! was running. Raw GPU telemetry samples are kept in
+//! `sessions/<game_id>-<started_at>.telemetry.yaml` and launch manifests in
+//! `sessions/<game_id>-<started_at>.manifest.yaml`.
+//!
+//! Sessions also record the Proton or Wine build the game ran with, so the
+//! builds a game is known to work with can be listed and a launch with
+//! another build flagged.
+//!
+//! `recent.yaml` lists the most recently launched games with the profile
+//! they ran with, for `nvproton run --last` and `--recent`.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::ConfigPaths;
+use crate::detection::{DetectedGame, GameDatabase, GameId};
+use crate::paths;
+use crate::telemetry::TelemetrySummary;
+use crate::xid::XidError;
+
+/// Maximum number of sessions kept per game
+const MAX_SESSIONS: usize = 50;
+
+/// Maximum number of games kept in the recently played list
+const MAX_RECENT: usize = 20;
+
+/// A single game launch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// Unix timestamp the game was started at
+    pub started_at: u64,
+    pub runtime_secs: u64,
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Proton or Wine build the game ran with (see `runtimes::compat_tool`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proton: Option<String>,
+    /// Xid errors raised while the game was running (requires --monitor)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub xid_errors: Vec<XidError>,
+    /// GPU telemetry summary (requires --telemetry)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub telemetry: Option<TelemetrySummary>,
+}
+
+impl Session {
+    /// Start a session record for a launch happening now
+    pub fn begin(profile: Option<String>) -> Self {
+        Self {
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            runtime_secs: 0,
+            exit_code: None,
+            success: false,
+            profile,
+            proton: None,
+            xid_errors: Vec::new(),
+            telemetry: None,
+        }
+    }
+
+    /// Fill in the outcome once the game has exited
+    pub fn finish(&mut self, success: bool, exit_code: Option<i32>, runtime: Duration) {
+        self.success = success;
+        self.exit_code = exit_code;
+        self.runtime_secs = runtime.as_secs();
+    }
+}
+
+/// Launches of a game with one Proton or Wine build
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProtonRecord {
+    pub proton: String,
+    pub successes: usize,
+    pub failures: usize,
+    /// Unix timestamp of the latest launch
+    pub last_launched: u64,
+}
+
+/// Session history for one game
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionHistory {
+    pub sessions: Vec<Session>,
+}
+
+impl SessionHistory {
+    /// Path of the history file for a game
+    pub fn path(paths: &ConfigPaths, game_id: &str) -> PathBuf {
+        paths
+            .state_dir
+            .join("sessions")
+            .join(format!("{}.yaml", paths::sanitize(game_id)))
+    }
+
+    /// Path of the raw telemetry samples for a session
+    pub fn telemetry_path(paths: &ConfigPaths, game_id: &str, session: &Session) -> PathBuf {
+        paths.state_dir.join("sessions").join(format!(
+            "{}-{}.telemetry.yaml",
+            paths::sanitize(game_id),
+            session.started_at
+        ))
+    }
+
+    /// Path of the launch manifest for a session
+    pub fn manifest_path(paths: &ConfigPaths, game_id: &str, started_at: u64) -> PathBuf {
+        paths.state_dir.join("sessions").join(format!(
+            "{}-{}.manifest.yaml",
+            paths::sanitize(game_id),
+            started_at
+        ))
+    }
+
+    /// Load the history for a game (empty if none recorded yet)
+    pub fn load(paths: &ConfigPaths, game_id: &str) -> Result<Self> {
+        let path = Self::path(paths, game_id);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read session history {:?}", path))?;
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("failed to parse session history {:?}", path))
+    }
+
+    /// Load a game's history, moving it from its bare-ID files first
+    pub fn for_game(paths: &ConfigPaths, db: &GameDatabase, game_id: &GameId) -> Result<Self> {
+        Self::migrate(paths, db, game_id)?;
+        Self::load(paths, &game_id.to_string())
+    }
+
+    /// Move a game's history, telemetry and manifests from the bare-ID files
+    /// used before games were keyed by source, unless another game shares
+    /// the ID and could own them
+    pub fn migrate(paths: &ConfigPaths, db: &GameDatabase, game_id: &GameId) -> Result<()> {
+        let dir = paths.state_dir.join("sessions");
+        if !dir.is_dir() || db.shares_id(game_id) {
+            return Ok(());
+        }
+        let legacy = paths::sanitize(&game_id.id);
+        let current = paths::sanitize(&game_id.to_string());
+        for entry in fs::read_dir(&dir)
+            .with_context(|| format!("failed to read sessions directory {:?}", dir))?
+            .filter_map(|e| e.ok())
+        {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(rest) = name.strip_prefix(&legacy) else {
+                continue;
+            };
+            // `<id>-<started_at>.telemetry.yaml`, not another ID starting with this one
+            let session_file = rest.strip_prefix('-').is_some_and(|rest| {
+                rest.split_once('.')
+                    .is_some_and(|(ts, _)| !ts.is_empty() && ts.bytes().all(|b| b.is_ascii_digit()))
+            });
+            if rest != ".yaml" && !session_file {
+                continue;
+            }
+            let target = dir.join(format!("{}{}", current, rest));
+            if !target.exists() {
+                fs::rename(entry.path(), &target)
+                    .with_context(|| format!("failed to move session file {:?}", entry.path()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the histories of all games, keyed by the history file name
+    pub fn load_all(paths: &ConfigPaths) -> Result<Vec<(String, Self)>> {
+        let dir = paths.state_dir.join("sessions");
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut histories = Vec::new();
+        for entry in fs::read_dir(&dir)
+            .with_context(|| format!("failed to read sessions directory {:?}", dir))?
+            .filter_map(|e| e.ok())
+        {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.ends_with(".telemetry.yaml") || name.ends_with(".manifest.yaml") {
+                continue;
+            }
+            if let Some(game_id) = name.strip_suffix(".yaml") {
+                histories.push((game_id.to_string(), Self::load(paths, game_id)?));
+            }
+        }
+        histories.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(histories)
+    }
+
+    /// Append a session and write the history back to disk
+    pub fn record(paths: &ConfigPaths, game_id: &str, session: Session) -> Result<()> {
+        let mut history = Self::load(paths, game_id)?;
+        for dropped in history.push(session) {
+            let _ = fs::remove_file(Self::telemetry_path(paths, game_id, &dropped));
+            let _ = fs::remove_file(Self::manifest_path(paths, game_id, dropped.started_at));
+        }
+
+        let path = Self::path(paths, game_id);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create directory {:?}", parent))?;
+        }
+        let yaml = serde_yaml::to_string(&history)?;
+        fs::write(&path, yaml)
+            .with_context(|| format!("failed to write session history {:?}", path))?;
+        Ok(())
+    }
+
+    /// Append a session, returning sessions dropped to stay under the cap
+    fn push(&mut self, session: Session) -> Vec<Session> {
+        self.sessions.push(session);
+        let excess = self.sessions.len().saturating_sub(MAX_SESSIONS);
+        self.sessions.drain(..excess).collect()
+    }
+
+    /// Most recent session
+    pub fn last(&self) -> Option<&Session> {
+        self.sessions.last()
+    }
+
+    /// Launches per Proton build, most recently used first
+    pub fn proton_matrix(&self) -> Vec<ProtonRecord> {
+        let mut records: Vec<ProtonRecord> = Vec::new();
+        for session in self.sessions.iter().rev() {
+            let Some(proton) = &session.proton else {
+                continue;
+            };
+            let index = match records.iter().position(|r| &r.proton == proton) {
+                Some(index) => index,
+                None => {
+                    records.push(ProtonRecord {
+                        proton: proton.clone(),
+                        successes: 0,
+                        failures: 0,
+                        last_launched: session.started_at,
+                    });
+                    records.len() - 1
+                }
+            };
+            match session.success {
+                true => records[index].successes += 1,
+                false => records[index].failures += 1,
+            }
+        }
+        records
+    }
+
+    /// Proton build of the most recent successful launch
+    pub fn last_known_good(&self) -> Option<&str> {
+        self.sessions
+            .iter()
+            .rev()
+            .filter(|session| session.success)
+            .find_map(|session| session.proton.as_deref())
+    }
+}
+
+/// A game launched through `nvproton run`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentGame {
+    pub game_id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Unix timestamp of the launch
+    pub launched_at: u64,
+}
+
+impl RecentGame {
+    /// Entry for a launch happening now
+    pub fn now(game: &DetectedGame, profile: Option<String>) -> Self {
+        Self {
+            game_id: game.game_id().to_string(),
+            name: game.name.clone(),
+            profile,
+            launched_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// Recently launched games, newest first, one entry per game
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecentGames {
+    pub games: Vec<RecentGame>,
+}
+
+impl RecentGames {
+    pub fn path(paths: &ConfigPaths) -> PathBuf {
+        paths.state_dir.join("recent.yaml")
+    }
+
+    /// Load the list (empty if nothing was launched yet)
+    pub fn load(paths: &ConfigPaths) -> Result<Self> {
+        let path = Self::path(paths);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read recent games {:?}", path))?;
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("failed to parse recent games {:?}", path))
+    }
+
+    /// Move a launched game to the front and write the list back to disk
+    pub fn record(paths: &ConfigPaths, game: RecentGame) -> Result<()> {
+        let mut recent = Self::load(paths)?;
+        recent.push(game);
+
+        let path = Self::path(paths);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create directory {:?}", parent))?;
+        }
+        let yaml = serde_yaml::to_string(&recent)?;
+        fs::write(&path, yaml)
+            .with_context(|| format!("failed to write recent games {:?}", path))?;
+        Ok(())
+    }
+
+    fn push(&mut self, game: RecentGame) {
+        self.games.retain(|g| g.game_id != game.game_id);
+        self.games.insert(0, game);
+        self.games.truncate(MAX_RECENT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigManager;
+    use crate::detection::GameSource;
+
+    #[test]
+    fn test_history_is_capped() {
+        let mut history = SessionHistory::default();
+        for i in 0..(MAX_SESSIONS + 5) {
+            let mut session = Session::begin(None);
+            session.started_at = i as u64;
+            history.push(session);
+        }
+        assert_eq!(history.sessions.len(), MAX_SESSIONS);
+        assert_eq!(history.sessions[0].started_at, 5);
+        assert_eq!(
+            history.last().unwrap().started_at,
+            (MAX_SESSIONS + 4) as u64
+        );
+    }
+
+    #[test]
+    fn test_proton_matrix_and_last_known_good() {
+        let mut history = SessionHistory::default();
+        for (started_at, proton, success) in [
+            (1, Some("proton_9"), true),
+            (2, None, true),
+            (3, Some("experimental (1718199999)"), true),
+            (4, Some("proton_9"), false),
+            (5, Some("experimental (1720000000)"), false),
+        ] {
+            let mut session = Session::begin(None);
+            session.started_at = started_at;
+            session.proton = proton.map(String::from);
+            session.success = success;
+            history.push(session);
+        }
+
+        // An Experimental update counts as another build
+        assert_eq!(history.last_known_good(), Some("experimental (1718199999)"));
+        let matrix = history.proton_matrix();
+        let summary: Vec<_> = matrix
+            .iter()
+            .map(|r| (r.proton.as_str(), r.successes, r.failures, r.last_launched))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("experimental (1720000000)", 0, 1, 5),
+                ("proton_9", 1, 1, 4),
+                ("experimental (1718199999)", 1, 0, 3),
+            ]
+        );
+        assert_eq!(SessionHistory::default().last_known_good(), None);
+    }
+
+    #[test]
+    fn test_bare_id_histories_are_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ConfigManager::portable(dir.path());
+        let paths = manager.paths();
+        let sessions = paths.state_dir.join("sessions");
+        fs::create_dir_all(&sessions).unwrap();
+        for name in [
+            "doom.yaml",
+            "doom-1700000000.telemetry.yaml",
+            "doom-1700000000.manifest.yaml",
+            "doom-eternal.yaml",
+        ] {
+            fs::write(sessions.join(name), "sessions: []\n").unwrap();
+        }
+        let game = |source| DetectedGame {
+            id: "doom".into(),
+            name: "DOOM".into(),
+            source,
+            install_dir: PathBuf::from("/games/DOOM"),
+            executable: None,
+            fingerprint: None,
+            metadata: Default::default(),
+        };
+        let (lutris, heroic) = (game(GameSource::Lutris), game(GameSource::Heroic));
+
+        // Either game could own the bare-ID files while both are installed
+        let mut db = GameDatabase::default();
+        db.merge_detected(&[lutris.clone(), heroic], 0);
+        SessionHistory::migrate(paths, &db, &lutris.game_id()).unwrap();
+        assert!(sessions.join("doom.yaml").exists());
+
+        let mut db = GameDatabase::default();
+        db.merge_detected(std::slice::from_ref(&lutris), 0);
+        SessionHistory::for_game(paths, &db, &lutris.game_id()).unwrap();
+        for name in [
+            "lutris_doom.yaml",
+            "lutris_doom-1700000000.telemetry.yaml",
+            "lutris_doom-1700000000.manifest.yaml",
+            "doom-eternal.yaml",
+        ] {
+            assert!(sessions.join(name).exists(), "{}", name);
+        }
+        assert!(!sessions.join("doom.yaml").exists());
+    }
+
+    #[test]
+    fn test_recent_games_move_to_front() {
+        let game = |id: &str, profile: Option<&str>| RecentGame {
+            game_id: id.into(),
+            name: id.into(),
+            profile: profile.map(String::from),
+            launched_at: 0,
+        };
+        let mut recent = RecentGames::default();
+        recent.push(game("a", None));
+        recent.push(game("b", None));
+        recent.push(game("a", Some("competitive")));
+
+        assert_eq!(recent.games.len(), 2);
+        assert_eq!(recent.games[0].game_id, "a");
+        assert_eq!(recent.games[0].profile.as_deref(), Some("competitive"));
+
+        for i in 0..MAX_RECENT {
+            recent.push(game(&i.to_string(), None));
+        }
+        assert_eq!(recent.games.len(), MAX_RECENT);
+    }
+}
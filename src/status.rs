@@ -6,26 +6,52 @@
 //! - Proton-NV detection
 //! - DX12 readiness (descriptor_heap + extended sparse support)
 //! - Reflex 2.0 and frame pacing capabilities
-
-use std::path::PathBuf;
+//! - CUDA / NVENC libraries reachable from the Proton container
+//! - Immutable distribution (rpm-ostree, SteamOS) install advice
+//! - Wayland / X11 session and the VRR mechanism it implies
+//! - nvidia-drm kernel modesetting (needed for Wayland and PRIME)
+//! - Controller hidraw permissions
+//! - RAM, swap / ZRAM and vm.max_map_count
+//! - Kernel support for Wine's esync, fsync and ntsync
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+use crate::cache::format_bytes;
 use crate::cli::{OutputFormat, StatusArgs};
-use crate::config::ConfigManager;
-use crate::detection::proton_nv::ProtonNvDetector;
+use crate::config::{ConfigManager, NvConfig, TrackedConfig};
 use crate::detection::VulkanCapabilities;
+use crate::detection::display_server::{DisplaySession, SessionType, VrrMechanism};
+use crate::detection::distro::{DistroInfo, ImmutableKind};
+use crate::detection::proton_nv::ProtonNvDetector;
+use crate::fixes;
 use crate::gamemode;
+use crate::input::{self, HidrawDevice};
 use crate::mangohud;
+use crate::memory::{self, MemoryAdvice, MemoryStatus};
+use crate::steam::client::SteamClient;
+use crate::term::{Tone, paint};
+use crate::wine_sync::{SyncMechanism, SyncSupport};
 
 /// Comprehensive system status
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemStatus {
+    pub distro: DistroInfo,
+    pub display: DisplaySession,
+    /// `nvidia-drm.modeset`; None when the nvidia-drm module isn't loaded
+    pub drm_modeset: Option<bool>,
     pub vulkan: Option<VulkanStatus>,
     pub vkd3d_proton: Option<Vkd3dProtonStatus>,
     pub proton_nv: Option<ProtonNvStatus>,
     pub tools: ToolsStatus,
+    pub cuda: CudaStatus,
+    pub controllers: Vec<HidrawDevice>,
+    pub memory: MemoryStatus,
+    pub sync: SyncSupport,
     pub dx12_ready: bool,
     pub dx12_ready_reason: String,
 }
@@ -91,22 +117,149 @@ pub struct ToolsStatus {
     pub gamemode_running: bool,
 }
 
+/// CUDA / NVENC user-space libraries needed by mods, OptiX and in-game capture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CudaStatus {
+    pub libraries: Vec<DriverLibrary>,
+    /// Launch environment that exposes libraries the container can't see
+    pub fix_env: BTreeMap<String, String>,
+}
+
+/// A driver library and whether the Steam Linux Runtime can reach it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriverLibrary {
+    pub name: String,
+    pub purpose: String,
+    pub path: Option<PathBuf>,
+    /// Host system library directories are visible in the container under /run/host
+    pub container_visible: bool,
+}
+
+/// CUDA / NVENC / OptiX libraries to check
+const CUDA_LIBRARIES: &[(&str, &str)] = &[
+    ("libcuda.so.1", "CUDA (mods, PhysX)"),
+    ("libnvidia-encode.so.1", "NVENC (in-game replays, capture)"),
+    ("libnvoptix.so.1", "OptiX (denoisers)"),
+];
+
+/// nvidia-drm's modeset parameter
+pub(crate) const DRM_MODESET_PARAMETER: &str = "/sys/module/nvidia_drm/parameters/modeset";
+
+/// Directories checked when ldconfig doesn't know a library
+const CUDA_SEARCH_DIRS: &[&str] = &[
+    "/usr/lib/x86_64-linux-gnu",
+    "/usr/lib64",
+    "/usr/lib",
+    "/usr/lib/nvidia",
+    "/usr/lib64/nvidia",
+    "/usr/local/cuda/lib64",
+    "/opt/cuda/lib64",
+];
+
+impl CudaStatus {
+    pub fn detect() -> Self {
+        let ldconfig = Command::new("ldconfig")
+            .arg("-p")
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+            .unwrap_or_default();
+
+        let libraries: Vec<DriverLibrary> = CUDA_LIBRARIES
+            .iter()
+            .map(|(name, purpose)| {
+                let path = parse_ldconfig(&ldconfig, name).or_else(|| {
+                    CUDA_SEARCH_DIRS
+                        .iter()
+                        .map(|dir| Path::new(dir).join(name))
+                        .find(|p| p.exists())
+                });
+                let container_visible = path.as_deref().is_some_and(is_system_library_dir);
+                DriverLibrary {
+                    name: name.to_string(),
+                    purpose: purpose.to_string(),
+                    path,
+                    container_visible,
+                }
+            })
+            .collect();
+
+        let fix_env = container_fix_env(&libraries);
+        Self { libraries, fix_env }
+    }
+}
+
+/// Whether nvidia-drm was loaded with `modeset=1`
+fn detect_drm_modeset() -> Option<bool> {
+    let value = std::fs::read_to_string(DRM_MODESET_PARAMETER).ok()?;
+    Some(value.trim() == "Y")
+}
+
+/// Find the 64-bit path of a library in `ldconfig -p` output
+fn parse_ldconfig(output: &str, name: &str) -> Option<PathBuf> {
+    output.lines().find_map(|line| {
+        let line = line.trim();
+        let (lib, path) = line.split_once(" => ")?;
+        (lib.starts_with(&format!("{} ", name)) && lib.contains("x86-64"))
+            .then(|| PathBuf::from(path.trim()))
+    })
+}
+
+fn is_system_library_dir(path: &Path) -> bool {
+    ["/usr/lib", "/usr/lib64", "/lib", "/lib64"]
+        .iter()
+        .any(|dir| path.starts_with(dir))
+        && !path.starts_with("/usr/local")
+}
+
+/// pressure-vessel resets LD_LIBRARY_PATH, so libraries outside the system
+/// directories are mounted read-only and handed to the game via
+/// PRESSURE_VESSEL_APP_LD_LIBRARY_PATH
+fn container_fix_env(libraries: &[DriverLibrary]) -> BTreeMap<String, String> {
+    let mut dirs: Vec<String> = Vec::new();
+    for lib in libraries.iter().filter(|l| !l.container_visible) {
+        if let Some(dir) = lib.path.as_deref().and_then(Path::parent) {
+            let dir = dir.to_string_lossy().to_string();
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+
+    let mut env = BTreeMap::new();
+    if !dirs.is_empty() {
+        let joined = dirs.join(":");
+        env.insert("PRESSURE_VESSEL_FILESYSTEMS_RO".to_string(), joined.clone());
+        env.insert("PRESSURE_VESSEL_APP_LD_LIBRARY_PATH".to_string(), joined);
+    }
+    env
+}
+
 impl SystemStatus {
     /// Detect full system status
-    pub fn detect() -> Self {
-        let vulkan = VulkanCapabilities::detect().ok().map(|c| VulkanStatus::from(&c));
-        let vkd3d_proton = detect_vkd3d_proton();
+    pub fn detect(config: &NvConfig) -> Self {
+        let vulkan = VulkanCapabilities::detect()
+            .ok()
+            .map(|c| VulkanStatus::from(&c));
+        let vkd3d_proton = detect_vkd3d_proton(config);
         let proton_nv = detect_proton_nv();
         let tools = detect_tools();
+        let cuda = CudaStatus::detect();
 
         // Determine DX12 readiness
         let (dx12_ready, dx12_ready_reason) = evaluate_dx12_readiness(&vulkan, &vkd3d_proton);
 
         Self {
+            distro: DistroInfo::detect(),
+            display: DisplaySession::detect(),
+            drm_modeset: detect_drm_modeset(),
             vulkan,
             vkd3d_proton,
             proton_nv,
             tools,
+            cuda,
+            controllers: input::hidraw_controllers(),
+            memory: MemoryStatus::detect(),
+            sync: SyncSupport::detect(),
             dx12_ready,
             dx12_ready_reason,
         }
@@ -119,7 +272,7 @@ impl SystemStatus {
 }
 
 /// Detect vkd3d-proton installation
-fn detect_vkd3d_proton() -> Option<Vkd3dProtonStatus> {
+fn detect_vkd3d_proton(config: &NvConfig) -> Option<Vkd3dProtonStatus> {
     // Check common vkd3d-proton locations
     let search_paths = [
         // System installations
@@ -162,11 +315,12 @@ fn detect_vkd3d_proton() -> Option<Vkd3dProtonStatus> {
     }
 
     // Try to detect via Proton (vkd3d-proton is bundled)
-    if found_path.is_none() {
-        if let Some((path, ver)) = detect_vkd3d_from_proton() {
-            found_path = Some(path);
-            version = Some(ver);
-        }
+    if found_path.is_none()
+        && let Some(steam) = SteamClient::from_config(config)
+        && let Some((path, ver)) = detect_vkd3d_from_proton(steam.root())
+    {
+        found_path = Some(path);
+        version = Some(ver);
     }
 
     // Check if version supports descriptor_heap (PR #2805)
@@ -184,7 +338,7 @@ fn detect_vkd3d_proton() -> Option<Vkd3dProtonStatus> {
 }
 
 /// Read vkd3d-proton version from installation
-fn read_vkd3d_version(path: &PathBuf) -> Option<String> {
+fn read_vkd3d_version(path: &Path) -> Option<String> {
     // Try version file
     let version_file = path.join("version");
     if let Ok(content) = std::fs::read_to_string(&version_file) {
@@ -195,10 +349,10 @@ fn read_vkd3d_version(path: &PathBuf) -> Option<String> {
     let setup_script = path.join("setup_vkd3d_proton.sh");
     if let Ok(content) = std::fs::read_to_string(&setup_script) {
         for line in content.lines() {
-            if line.contains("VKD3D_PROTON_VERSION=") {
-                if let Some(ver) = line.split('=').nth(1) {
-                    return Some(ver.trim_matches('"').to_string());
-                }
+            if line.contains("VKD3D_PROTON_VERSION=")
+                && let Some(ver) = line.split('=').nth(1)
+            {
+                return Some(ver.trim_matches('"').to_string());
             }
         }
     }
@@ -206,12 +360,8 @@ fn read_vkd3d_version(path: &PathBuf) -> Option<String> {
     None
 }
 
-/// Detect vkd3d-proton bundled with Proton
-fn detect_vkd3d_from_proton() -> Option<(PathBuf, String)> {
-    // Check Steam Proton installations
-    let home = std::env::var("HOME").ok()?;
-    let steam_path = PathBuf::from(&home).join(".local/share/Steam");
-
+/// Detect vkd3d-proton bundled with Proton in a Steam install
+fn detect_vkd3d_from_proton(steam_path: &Path) -> Option<(PathBuf, String)> {
     // Check Proton Experimental
     let proton_exp = steam_path.join("steamapps/common/Proton - Experimental");
     if proton_exp.exists() {
@@ -229,15 +379,15 @@ fn detect_vkd3d_from_proton() -> Option<(PathBuf, String)> {
 
     // Check GE-Proton
     let ge_proton_dir = steam_path.join("compatibilitytools.d");
-    if ge_proton_dir.exists() {
-        if let Ok(entries) = std::fs::read_dir(&ge_proton_dir) {
-            for entry in entries.flatten() {
-                let name = entry.file_name().to_string_lossy().to_string();
-                if name.starts_with("GE-Proton") {
-                    let vkd3d_path = entry.path().join("files/lib64/vkd3d-proton");
-                    if vkd3d_path.exists() {
-                        return Some((vkd3d_path, format!("bundled ({})", name)));
-                    }
+    if ge_proton_dir.exists()
+        && let Ok(entries) = std::fs::read_dir(&ge_proton_dir)
+    {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with("GE-Proton") {
+                let vkd3d_path = entry.path().join("files/lib64/vkd3d-proton");
+                if vkd3d_path.exists() {
+                    return Some((vkd3d_path, format!("bundled ({})", name)));
                 }
             }
         }
@@ -305,9 +455,7 @@ fn detect_proton_nv() -> Option<ProtonNvStatus> {
 
 /// Detect external tools
 fn detect_tools() -> ToolsStatus {
-    let gamemode_running = gamemode::status()
-        .map(|s| s.running)
-        .unwrap_or(false);
+    let gamemode_running = gamemode::status().map(|s| s.running).unwrap_or(false);
 
     ToolsStatus {
         mangohud: mangohud::is_installed(),
@@ -412,8 +560,36 @@ fn evaluate_dx12_readiness(
 }
 
 /// Handle the status command
-pub fn handle_status(args: StatusArgs, _manager: &ConfigManager) -> Result<()> {
-    let status = SystemStatus::detect();
+pub fn handle_status(
+    args: StatusArgs,
+    manager: &ConfigManager,
+    config: &mut TrackedConfig,
+) -> Result<()> {
+    if args.apply_sysctl {
+        let current = memory::read_max_map_count();
+        if let Some(current) = current
+            && !memory::sysctl_needed(Some(current))
+        {
+            println!("vm.max_map_count is already {}, nothing to change", current);
+            return Ok(());
+        }
+        println!("Writing {}:", memory::SYSCTL_DROP_IN);
+        print!("{}", memory::sysctl_drop_in());
+        if memory::apply_sysctl()? {
+            println!(
+                "vm.max_map_count is now {}",
+                memory::RECOMMENDED_MAX_MAP_COUNT
+            );
+        }
+        return Ok(());
+    }
+
+    let status = SystemStatus::detect(config);
+
+    if args.fix {
+        let fixes = fixes::find(&status, manager, config)?;
+        return fixes::apply(fixes, args.yes, config);
+    }
 
     if args.check {
         // Exit with code based on descriptor_heap readiness
@@ -433,7 +609,7 @@ pub fn handle_status(args: StatusArgs, _manager: &ConfigManager) -> Result<()> {
             println!("{}", serde_yaml::to_string(&status)?);
         }
         OutputFormat::Text => {
-            print_status_text(&status, args.verbose);
+            print_status_text(&status, args.verbose, config);
         }
     }
 
@@ -441,12 +617,64 @@ pub fn handle_status(args: StatusArgs, _manager: &ConfigManager) -> Result<()> {
 }
 
 /// Print status in human-readable format
-fn print_status_text(status: &SystemStatus, verbose: bool) {
-    println!("nvproton System Status");
+fn print_status_text(status: &SystemStatus, verbose: bool, config: &NvConfig) {
+    println!("{}", paint("nvproton System Status", Tone::Bold));
     println!("{}", "=".repeat(50));
 
+    section("System");
+    print!("  Distribution: {}", status.distro.name);
+    match status.distro.immutable {
+        Some(ImmutableKind::RpmOstree) => println!(" (immutable, rpm-ostree)"),
+        Some(ImmutableKind::SteamOs) => println!(" (immutable, SteamOS)"),
+        Some(ImmutableKind::ReadOnlyUsr) => println!(" (read-only /usr)"),
+        None => println!(),
+    }
+    if status.distro.flatpak {
+        println!("  Running inside Flatpak");
+    }
+    for advice in status.distro.advice() {
+        println!("  - {}", advice);
+    }
+
+    section("Display Session");
+    print!("  Session: {}", status.display.describe());
+    if status.display.xwayland {
+        println!(" (Proton games run through Xwayland)");
+    } else {
+        println!();
+    }
+    match status.drm_modeset {
+        Some(true) => println!("  Kernel modesetting: {}", paint("enabled", Tone::Good)),
+        Some(false) => println!(
+            "  Kernel modesetting: {} (run 'nvproton status --fix')",
+            paint("disabled", Tone::Warn)
+        ),
+        None => {}
+    }
+    println!("  VRR: {}", status.display.vrr);
+    match status.display.vrr {
+        VrrMechanism::Compositor => println!(
+            "  - Enable adaptive sync in the compositor's display settings; \
+             __GL_GSYNC_ALLOWED/__GL_VRR_ALLOWED have no effect on Wayland"
+        ),
+        VrrMechanism::Driver => println!(
+            "  - G-SYNC follows __GL_GSYNC_ALLOWED/__GL_VRR_ALLOWED ('nvproton run --vrr')"
+        ),
+        VrrMechanism::None => {}
+    }
+    if verbose && config.display.session_env && status.display.session_type != SessionType::Unknown
+    {
+        let hints: Vec<String> = status
+            .display
+            .backend_env()
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        println!("  Launch hints: {}", hints.join(" "));
+    }
+
     // Vulkan/GPU section
-    println!("\nGPU & Driver:");
+    section("GPU & Driver");
     if let Some(ref vk) = status.vulkan {
         println!("  GPU: {}", vk.gpu_name);
         print!("  Driver: NVIDIA {}", vk.driver_version);
@@ -465,7 +693,7 @@ fn print_status_text(status: &SystemStatus, verbose: bool) {
         }
 
         // DX12/vkd3d-proton extensions
-        println!("\nDX12 Extensions (vkd3d-proton):");
+        section("DX12 Extensions (vkd3d-proton)");
         print_extension_status("VK_EXT_descriptor_heap", vk.descriptor_heap, true);
         print_extension_status(
             "VK_NV_extended_sparse_address_space",
@@ -476,12 +704,8 @@ fn print_status_text(status: &SystemStatus, verbose: bool) {
         print_extension_status("VK_NV_raw_access_chains", vk.raw_access_chains, false);
 
         // Gaming/latency extensions
-        println!("\nGaming Extensions:");
-        print_extension_status_with_note(
-            "VK_NV_low_latency2",
-            vk.low_latency2,
-            "Reflex 2.0",
-        );
+        section("Gaming Extensions");
+        print_extension_status_with_note("VK_NV_low_latency2", vk.low_latency2, "Reflex 2.0");
         print_extension_status_with_note(
             "VK_EXT_present_timing",
             vk.present_timing,
@@ -492,17 +716,15 @@ fn print_status_text(status: &SystemStatus, verbose: bool) {
     }
 
     // vkd3d-proton section
-    println!("\nvkd3d-proton:");
+    section("vkd3d-proton");
     if let Some(ref vkd3d) = status.vkd3d_proton {
         if vkd3d.installed {
             println!(
                 "  Version: {}",
                 vkd3d.version.as_deref().unwrap_or("unknown")
             );
-            if verbose {
-                if let Some(ref path) = vkd3d.path {
-                    println!("  Path: {}", path.display());
-                }
+            if verbose && let Some(ref path) = vkd3d.path {
+                println!("  Path: {}", path.display());
             }
             print!(
                 "  descriptor_heap support: {}",
@@ -521,17 +743,12 @@ fn print_status_text(status: &SystemStatus, verbose: bool) {
     }
 
     // Proton-NV section
-    println!("\nProton-NV:");
+    section("Proton-NV");
     if let Some(ref pnv) = status.proton_nv {
         if pnv.installed {
-            println!(
-                "  Version: {}",
-                pnv.version.as_deref().unwrap_or("unknown")
-            );
-            if verbose {
-                if let Some(ref path) = pnv.path {
-                    println!("  Path: {}", path.display());
-                }
+            println!("  Version: {}", pnv.version.as_deref().unwrap_or("unknown"));
+            if verbose && let Some(ref path) = pnv.path {
+                println!("  Path: {}", path.display());
             }
         } else {
             println!("  Not installed");
@@ -541,62 +758,188 @@ fn print_status_text(status: &SystemStatus, verbose: bool) {
     }
 
     // Tools section
-    println!("\nTools:");
-    println!(
-        "  MangoHud: {}",
-        if status.tools.mangohud {
-            "installed"
-        } else {
-            "not found"
-        }
-    );
-    print!(
-        "  GameMode: {}",
-        if status.tools.gamemode {
-            "installed"
-        } else {
-            "not found"
-        }
-    );
+    section("Tools");
+    println!("  MangoHud: {}", installed(status.tools.mangohud));
+    print!("  GameMode: {}", installed(status.tools.gamemode));
     if status.tools.gamemode && status.tools.gamemode_running {
         println!(" (daemon running)");
     } else {
         println!();
     }
 
+    // Memory section
+    let mem = &status.memory;
+    section("Memory");
+    println!(
+        "  RAM: {} available of {}",
+        format_bytes(mem.available_bytes),
+        format_bytes(mem.total_bytes)
+    );
+    match (mem.swap_total_bytes, mem.zram) {
+        (0, _) => println!("  Swap: none"),
+        (total, zram) => println!(
+            "  Swap: {} free of {}{}",
+            format_bytes(mem.swap_free_bytes),
+            format_bytes(total),
+            if zram { " (ZRAM)" } else { "" }
+        ),
+    }
+    if let Some(count) = mem.max_map_count {
+        println!("  vm.max_map_count: {}", count);
+    }
+    for advice in mem.advice(config.memory.min_available_mb) {
+        match advice {
+            MemoryAdvice::LowAvailable { .. } => {
+                println!("  - Little RAM is available; close other applications before playing")
+            }
+            MemoryAdvice::NoSwap => println!(
+                "  - No swap: enable ZRAM (zram-generator or zram-tools) so running out of \
+                 RAM doesn't get games killed"
+            ),
+            MemoryAdvice::NoZram => println!(
+                "  - Swap is disk-only; ZRAM (zram-generator or zram-tools) swaps without stutter"
+            ),
+            MemoryAdvice::MaxMapCountLow { .. } => println!(
+                "  - vm.max_map_count is below {} which crashes some games (EAC, DayZ, CS2); \
+                 run 'nvproton status --apply-sysctl'",
+                memory::RECOMMENDED_MAX_MAP_COUNT
+            ),
+        }
+    }
+
+    // Wine sync section
+    section("Wine Synchronization");
+    for mechanism in [
+        SyncMechanism::Ntsync,
+        SyncMechanism::Fsync,
+        SyncMechanism::Esync,
+    ] {
+        if status.sync.supports(mechanism) {
+            println!("  {}: {}", mechanism, paint("supported", Tone::Good));
+        } else {
+            println!(
+                "  {}: {}, needs {}",
+                mechanism,
+                paint("not available", Tone::Dim),
+                mechanism.requirement()
+            );
+        }
+    }
+    match status.sync.best() {
+        Some(best) => println!("  - 'nvproton run --sync auto' uses {}", best),
+        None => println!("  - Wine falls back to wineserver synchronization, which costs CPU time"),
+    }
+
+    // CUDA/NVENC section
+    section("CUDA / NVENC (Proton container)");
+    for lib in &status.cuda.libraries {
+        match &lib.path {
+            Some(path) if lib.container_visible => {
+                print!("  {}: {}", lib.purpose, paint("reachable", Tone::Good));
+                if verbose {
+                    print!(" ({})", path.display());
+                }
+                println!();
+            }
+            Some(path) => println!(
+                "  {}: {} (run 'nvproton status --fix')",
+                lib.purpose,
+                paint(
+                    format!("{} is outside the container's view", path.display()),
+                    Tone::Warn
+                )
+            ),
+            None => println!(
+                "  {}: {}",
+                lib.purpose,
+                paint(format!("{} not found", lib.name), Tone::Bad)
+            ),
+        }
+    }
+
+    // Controller section
+    if !status.controllers.is_empty() {
+        section("Controllers (hidraw)");
+        for device in &status.controllers {
+            if device.accessible {
+                println!(
+                    "  {} ({}): {}",
+                    device.name,
+                    device.path.display(),
+                    paint("accessible", Tone::Good)
+                );
+            } else {
+                println!(
+                    "  {} ({}): {} - install your distro's steam-devices \
+                     (or game-devices-udev) rules and replug",
+                    device.name,
+                    device.path.display(),
+                    paint("no read/write access", Tone::Warn)
+                );
+            }
+        }
+    }
+
     // DX12 readiness summary
     println!("\n{}", "=".repeat(50));
-    println!("DX12 Optimization Status:");
+    println!("{}", paint("DX12 Optimization Status:", Tone::Bold));
     if status.dx12_ready {
-        println!("  [READY] {}", status.dx12_ready_reason);
+        println!(
+            "  {} {}",
+            paint("[READY]", Tone::Good),
+            status.dx12_ready_reason
+        );
     } else {
-        println!("  [NOT READY] {}", status.dx12_ready_reason);
+        println!(
+            "  {} {}",
+            paint("[NOT READY]", Tone::Bad),
+            status.dx12_ready_reason
+        );
     }
 
     // Recommendations
     if !status.dx12_ready {
-        println!("\nRecommendations:");
+        section("Recommendations");
         print_recommendations(status);
     }
 }
 
+/// Section heading of the status report
+fn section(title: &str) {
+    println!("\n{}", paint(format!("{}:", title), Tone::Bold));
+}
+
+fn installed(found: bool) -> String {
+    if found {
+        paint("installed", Tone::Good)
+    } else {
+        paint("not found", Tone::Dim)
+    }
+}
+
 fn print_extension_status(name: &str, supported: bool, important: bool) {
-    let status = if supported { "supported" } else { "not available" };
+    let status = if supported {
+        paint("supported", Tone::Good)
+    } else if important {
+        paint("not available", Tone::Warn)
+    } else {
+        paint("not available", Tone::Dim)
+    };
     let marker = if important && supported {
-        " [DX12 FIX]"
+        format!(" {}", paint("[DX12 FIX]", Tone::Good))
     } else if important && !supported {
-        " [WAITING]"
+        format!(" {}", paint("[WAITING]", Tone::Warn))
     } else {
-        ""
+        String::new()
     };
     println!("  {}: {}{}", name, status, marker);
 }
 
 fn print_extension_status_with_note(name: &str, supported: bool, note: &str) {
     if supported {
-        println!("  {}: supported ({})", name, note);
+        println!("  {}: {} ({})", name, paint("supported", Tone::Good), note);
     } else {
-        println!("  {}: not available", name);
+        println!("  {}: {}", name, paint("not available", Tone::Dim));
     }
 }
 
@@ -628,6 +971,10 @@ fn print_recommendations(status: &SystemStatus) {
         println!("  - Verify nvidia-utils matches kernel module version");
     }
 
+    if status.cuda.libraries.iter().any(|l| l.path.is_none()) {
+        println!("  - Install the driver's CUDA/NVENC libraries (libcuda, libnvidia-encode)");
+    }
+
     if let Some(ref vkd3d) = status.vkd3d_proton {
         if !vkd3d.installed {
             println!("  - Install vkd3d-proton (bundled with Proton/GE-Proton)");
@@ -699,3 +1046,44 @@ pub fn driver_readiness_level() -> u8 {
     }
     0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ldconfig() {
+        let output = "\tlibcuda.so.1 (libc6) => /usr/lib32/libcuda.so.1
+\tlibcuda.so.1 (libc6,x86-64) => /usr/lib/libcuda.so.1
+\tlibcudart.so.12 (libc6,x86-64) => /opt/cuda/lib64/libcudart.so.12";
+        assert_eq!(
+            parse_ldconfig(output, "libcuda.so.1"),
+            Some(PathBuf::from("/usr/lib/libcuda.so.1"))
+        );
+        assert_eq!(parse_ldconfig(output, "libnvidia-encode.so.1"), None);
+    }
+
+    #[test]
+    fn test_container_fix_env() {
+        let libraries = vec![
+            DriverLibrary {
+                name: "libcuda.so.1".into(),
+                purpose: "CUDA".into(),
+                path: Some(PathBuf::from("/usr/lib/libcuda.so.1")),
+                container_visible: true,
+            },
+            DriverLibrary {
+                name: "libnvoptix.so.1".into(),
+                purpose: "OptiX".into(),
+                path: Some(PathBuf::from("/opt/nvidia/lib/libnvoptix.so.1")),
+                container_visible: false,
+            },
+        ];
+        let env = container_fix_env(&libraries);
+        assert_eq!(
+            env["PRESSURE_VESSEL_APP_LD_LIBRARY_PATH"],
+            "/opt/nvidia/lib"
+        );
+        assert_eq!(env["PRESSURE_VESSEL_FILESYSTEMS_RO"], "/opt/nvidia/lib");
+    }
+}
@@ -0,0 +1,323 @@
+//! Steam's app info cache (`appcache/appinfo.vdf`)
+//!
+//! The client keeps the store metadata of every app it has seen in one binary
+//! file: a header, then one record per app holding a binary VDF tree. Since
+//! version 29 the tree's keys are indices into a string table at the end of
+//! the file. Only the records asked for are decoded; the rest are skipped.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+use super::client::{Vdf, vdf_get};
+
+const MAGIC_V27: u32 = 0x0756_4427;
+const MAGIC_V28: u32 = 0x0756_4428;
+const MAGIC_V29: u32 = 0x0756_4429;
+
+/// Binary VDF value types
+const TYPE_SECTION: u8 = 0x00;
+const TYPE_STRING: u8 = 0x01;
+const TYPE_INT32: u8 = 0x02;
+const TYPE_FLOAT32: u8 = 0x03;
+const TYPE_POINTER: u8 = 0x04;
+const TYPE_COLOR: u8 = 0x06;
+const TYPE_UINT64: u8 = 0x07;
+const TYPE_END: u8 = 0x08;
+const TYPE_INT64: u8 = 0x0a;
+const TYPE_END_ALT: u8 = 0x0b;
+
+/// An entry of an app's `config/launch` section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchConfig {
+    /// Index of the entry ("0", "1", ...)
+    pub key: String,
+    /// Path relative to the install directory, with `/` separators
+    pub executable: String,
+    pub arguments: Option<String>,
+    pub description: Option<String>,
+    /// Steam's launch type (default, option1, server, editor, ...)
+    pub kind: Option<String>,
+    /// Operating systems the entry is for; empty means all
+    pub oslist: Vec<String>,
+}
+
+impl LaunchConfig {
+    /// Whether the entry can run on Linux, natively or through Proton
+    pub fn runs_here(&self) -> bool {
+        self.oslist.is_empty()
+            || self
+                .oslist
+                .iter()
+                .any(|os| os == "windows" || os == "linux")
+    }
+}
+
+type AppTree = Vec<(String, Vdf)>;
+
+/// The `appinfo` tree of one app, or None if the cache doesn't know it
+pub(crate) fn read_app(path: &Path, appid: u32) -> Result<Option<AppTree>> {
+    Ok(read_apps(path, &BTreeSet::from([appid]))?.remove(&appid))
+}
+
+/// The `appinfo` trees of the apps the cache knows among `appids`
+///
+/// The file is read once however many apps are asked for.
+pub(crate) fn read_apps(path: &Path, appids: &BTreeSet<u32>) -> Result<BTreeMap<u32, AppTree>> {
+    let file = File::open(path).with_context(|| format!("failed to open {:?}", path))?;
+    let mut reader = BufReader::new(file);
+    read_apps_from(&mut reader, appids).with_context(|| format!("failed to read {:?}", path))
+}
+
+#[cfg(test)]
+fn read_app_from<R: Read + Seek>(reader: &mut R, appid: u32) -> Result<Option<AppTree>> {
+    Ok(read_apps_from(reader, &BTreeSet::from([appid]))?.remove(&appid))
+}
+
+fn read_apps_from<R: Read + Seek>(
+    reader: &mut R,
+    appids: &BTreeSet<u32>,
+) -> Result<BTreeMap<u32, AppTree>> {
+    let magic = read_u32(reader)?;
+    let _universe = read_u32(reader)?;
+    let (checksum_len, strings) = match magic {
+        MAGIC_V27 => (20, None),
+        MAGIC_V28 => (40, None),
+        MAGIC_V29 => {
+            let offset = read_u64(reader)?;
+            let start = reader.stream_position()?;
+            reader.seek(SeekFrom::Start(offset))?;
+            let strings = read_string_table(reader)?;
+            reader.seek(SeekFrom::Start(start))?;
+            (40, Some(strings))
+        }
+        _ => bail!("unsupported appinfo version {:#010x}", magic),
+    };
+
+    let mut apps = BTreeMap::new();
+    while apps.len() < appids.len() {
+        let id = read_u32(reader)?;
+        if id == 0 {
+            break;
+        }
+        let size = read_u32(reader)? as i64;
+        if !appids.contains(&id) {
+            reader.seek(SeekFrom::Current(size))?;
+            continue;
+        }
+        // info state, last update, access token, checksums, change number
+        reader.seek(SeekFrom::Current(4 + 4 + 8 + checksum_len + 4))?;
+        let tree = read_section(reader, strings.as_deref())?;
+        if let Some(app) = vdf_get(&tree, "appinfo").and_then(Vdf::section) {
+            apps.insert(id, app.to_vec());
+        }
+    }
+    Ok(apps)
+}
+
+/// Launch configurations of an app's `appinfo` tree
+pub fn launch_configs(app: &[(String, Vdf)]) -> Vec<LaunchConfig> {
+    let Some(launch) = vdf_get(app, "config")
+        .and_then(Vdf::section)
+        .and_then(|config| vdf_get(config, "launch"))
+        .and_then(Vdf::section)
+    else {
+        return Vec::new();
+    };
+
+    launch
+        .iter()
+        .filter_map(|(key, entry)| {
+            let entry = entry.section()?;
+            let value = |name: &str| {
+                vdf_get(entry, name)
+                    .and_then(Vdf::value)
+                    .filter(|v| !v.is_empty())
+                    .map(str::to_string)
+            };
+            let oslist = vdf_get(entry, "config")
+                .and_then(Vdf::section)
+                .and_then(|config| vdf_get(config, "oslist"))
+                .and_then(Vdf::value)
+                .map(|list| list.split(',').map(|os| os.trim().to_string()).collect())
+                .unwrap_or_default();
+            Some(LaunchConfig {
+                key: key.clone(),
+                executable: value("executable")?.replace('\\', "/"),
+                arguments: value("arguments"),
+                description: value("description"),
+                kind: value("type"),
+                oslist,
+            })
+        })
+        .collect()
+}
+
+fn read_section<R: Read>(reader: &mut R, strings: Option<&[String]>) -> Result<Vec<(String, Vdf)>> {
+    let mut entries = Vec::new();
+    loop {
+        let kind = read_u8(reader)?;
+        if kind == TYPE_END || kind == TYPE_END_ALT {
+            return Ok(entries);
+        }
+        let key = match strings {
+            Some(strings) => {
+                let index = read_u32(reader)? as usize;
+                strings
+                    .get(index)
+                    .cloned()
+                    .with_context(|| format!("string index {} out of range", index))?
+            }
+            None => read_cstring(reader)?,
+        };
+        let value = match kind {
+            TYPE_SECTION => Vdf::Section(read_section(reader, strings)?),
+            TYPE_STRING => Vdf::Value(read_cstring(reader)?),
+            TYPE_INT32 => Vdf::Value(read_u32(reader)?.cast_signed().to_string()),
+            TYPE_FLOAT32 => Vdf::Value(f32::from_bits(read_u32(reader)?).to_string()),
+            TYPE_POINTER | TYPE_COLOR => Vdf::Value(read_u32(reader)?.to_string()),
+            TYPE_UINT64 => Vdf::Value(read_u64(reader)?.to_string()),
+            TYPE_INT64 => Vdf::Value(read_u64(reader)?.cast_signed().to_string()),
+            _ => bail!("unknown binary VDF type {:#04x} for key '{}'", kind, key),
+        };
+        entries.push((key, value));
+    }
+}
+
+fn read_string_table<R: Read>(reader: &mut R) -> Result<Vec<String>> {
+    let count = read_u32(reader)?;
+    (0..count).map(|_| read_cstring(reader)).collect()
+}
+
+fn read_cstring<R: Read>(reader: &mut R) -> Result<String> {
+    let mut bytes = Vec::new();
+    loop {
+        match read_u8(reader)? {
+            0 => return Ok(String::from_utf8_lossy(&bytes).into_owned()),
+            byte => bytes.push(byte),
+        }
+    }
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> Result<u8> {
+    let mut buf = [0; 1];
+    reader
+        .read_exact(&mut buf)
+        .context("unexpected end of file")?;
+    Ok(buf[0])
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+    let mut buf = [0; 4];
+    reader
+        .read_exact(&mut buf)
+        .context("unexpected end of file")?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut buf = [0; 8];
+    reader
+        .read_exact(&mut buf)
+        .context("unexpected end of file")?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// A version 29 cache with a record for `appid`, plus one before it
+    fn appinfo_v29(appid: u32) -> Vec<u8> {
+        let strings = [
+            "appinfo",
+            "config",
+            "launch",
+            "0",
+            "1",
+            "executable",
+            "description",
+            "oslist",
+            "installdir",
+            "appid",
+        ];
+        let key =
+            |name: &str| (strings.iter().position(|s| *s == name).unwrap() as u32).to_le_bytes();
+        let string = |out: &mut Vec<u8>, name: &str, value: &str| {
+            out.push(TYPE_STRING);
+            out.extend(key(name));
+            out.extend(value.as_bytes());
+            out.push(0);
+        };
+        let section = |out: &mut Vec<u8>, name: &str| {
+            out.push(TYPE_SECTION);
+            out.extend(key(name));
+        };
+
+        let mut tree = Vec::new();
+        section(&mut tree, "appinfo");
+        tree.push(TYPE_INT32);
+        tree.extend(key("appid"));
+        tree.extend(appid.to_le_bytes());
+        section(&mut tree, "config");
+        string(&mut tree, "installdir", "Baldurs Gate 3");
+        section(&mut tree, "launch");
+        section(&mut tree, "0");
+        string(&mut tree, "executable", "bin\\bg3.exe");
+        string(&mut tree, "description", "Vulkan");
+        tree.push(TYPE_END);
+        section(&mut tree, "1");
+        string(&mut tree, "executable", "bin\\bg3_dx11.exe");
+        string(&mut tree, "description", "DirectX 11");
+        section(&mut tree, "config");
+        string(&mut tree, "oslist", "windows");
+        tree.extend([TYPE_END, TYPE_END, TYPE_END, TYPE_END, TYPE_END, TYPE_END]);
+
+        let record = |out: &mut Vec<u8>, id: u32, tree: &[u8]| {
+            out.extend(id.to_le_bytes());
+            let header = 4 + 4 + 8 + 40 + 4;
+            out.extend(((header + tree.len()) as u32).to_le_bytes());
+            out.extend(vec![0; header]);
+            out.extend(tree);
+        };
+        let mut file = Vec::new();
+        file.extend(MAGIC_V29.to_le_bytes());
+        file.extend(1u32.to_le_bytes());
+        file.extend(0u64.to_le_bytes());
+        record(&mut file, 10, &[TYPE_END]);
+        record(&mut file, appid, &tree);
+        file.extend(0u32.to_le_bytes());
+        let offset = file.len() as u64;
+        file[8..16].copy_from_slice(&offset.to_le_bytes());
+        file.extend((strings.len() as u32).to_le_bytes());
+        for s in strings {
+            file.extend(s.as_bytes());
+            file.push(0);
+        }
+        file
+    }
+
+    #[test]
+    fn test_launch_configs_from_v29_cache() {
+        let mut cache = Cursor::new(appinfo_v29(1086940));
+        let app = read_app_from(&mut cache, 1086940).unwrap().unwrap();
+        assert_eq!(vdf_get(&app, "appid").and_then(Vdf::value), Some("1086940"));
+
+        let configs = launch_configs(&app);
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0].executable, "bin/bg3.exe");
+        assert_eq!(configs[1].description.as_deref(), Some("DirectX 11"));
+        assert_eq!(configs[1].oslist, vec!["windows"]);
+        assert!(configs.iter().all(LaunchConfig::runs_here));
+
+        cache.set_position(0);
+        assert!(read_app_from(&mut cache, 570).unwrap().is_none());
+        cache.set_position(0);
+        let apps = read_apps_from(&mut cache, &BTreeSet::from([570, 1086940])).unwrap();
+        assert_eq!(apps.keys().collect::<Vec<_>>(), [&1086940]);
+    }
+}
@@ -0,0 +1,855 @@
+//! Steam client install
+//!
+//! `SteamClient` wraps one Steam install: its libraries, user accounts,
+//! running state, per-app compatibility tools and the VDF files it keeps.
+//! Everything is read from the install directory, so a fixture tree can stand
+//! in for Steam in tests.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::appinfo::{self, LaunchConfig};
+use crate::config::NvConfig;
+use crate::detection::rebase;
+use crate::detection::steam::parse_manifest;
+
+/// Steam install locations, in order of preference
+const STEAM_ROOT_CANDIDATES: &[&str] = &[
+    ".steam/root",
+    ".steam/steam",
+    ".local/share/Steam",
+    // Flatpak
+    ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+    // Snap
+    "snap/steam/common/.local/share/Steam",
+];
+
+/// Sections of `localconfig.vdf` holding per-app settings
+const LOCAL_CONFIG_APPS: &[&str] = &["UserLocalConfigStore", "Software", "Valve", "Steam", "apps"];
+
+/// AppIDs that are Steam internals, not actual games
+const EXCLUDED_APPIDS: &[&str] = &[
+    "228980",  // Steamworks Common Redistributables
+    "1493710", // Proton Experimental
+    "1628350", // Steam Linux Runtime 3.0 (sniper)
+    "1887720", // Proton 8.0
+    "2180100", // Proton 9.0
+    "2348590", // Proton 9.0 (another)
+    "3658110", // Proton 10.0
+    "1391110", // Steam Linux Runtime
+    "2805730", // Steam Linux Runtime (soldier)
+];
+
+/// A Steam install (the directory holding `steamapps`, `userdata`, `config`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SteamClient {
+    root: PathBuf,
+    /// Directory the absolute paths in Steam's files are relative to (`detect --root`)
+    sysroot: Option<PathBuf>,
+}
+
+/// A Steam account with a `userdata/<id>` directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SteamUser {
+    /// Account ID (the lower 32 bits of the SteamID64)
+    pub id: String,
+    pub path: PathBuf,
+}
+
+impl SteamUser {
+    /// The account's non-Steam shortcuts (binary VDF)
+    pub fn shortcuts_file(&self) -> PathBuf {
+        self.path.join("config").join("shortcuts.vdf")
+    }
+
+    /// The account's client settings, including launch options
+    pub fn local_config(&self) -> PathBuf {
+        self.path.join("config").join("localconfig.vdf")
+    }
+
+    /// Launch options set in the client, by appid
+    pub fn launch_options(&self) -> BTreeMap<String, String> {
+        let Ok(content) = fs::read_to_string(self.local_config()) else {
+            return BTreeMap::new();
+        };
+        let root = parse_vdf(&content);
+        let apps = LOCAL_CONFIG_APPS
+            .iter()
+            .try_fold(root.as_slice(), |entries, key| {
+                vdf_get(entries, key)?.section()
+            })
+            .unwrap_or_default();
+        apps.iter()
+            .filter_map(|(appid, app)| {
+                let options = vdf_get(app.section()?, "LaunchOptions")?.value()?;
+                Some((appid.clone(), options.to_string()))
+            })
+            .collect()
+    }
+
+    /// Set the launch options of apps in `localconfig.vdf`
+    ///
+    /// Steam writes the file back when it exits, so it must not be running.
+    pub fn set_launch_options(&self, options: &BTreeMap<String, String>) -> Result<()> {
+        let path = self.local_config();
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("failed to read {:?}", path))?;
+        let mut root = parse_vdf(&content);
+        let apps = LOCAL_CONFIG_APPS
+            .iter()
+            .fold(&mut root, |entries, key| vdf_section_mut(entries, key));
+        for (appid, value) in options {
+            let app = vdf_section_mut(apps, appid);
+            app.retain(|(key, _)| !key.eq_ignore_ascii_case("LaunchOptions"));
+            app.push(("LaunchOptions".into(), Vdf::Value(value.clone())));
+        }
+        fs::write(&path, write_vdf(&root)).with_context(|| format!("failed to write {:?}", path))
+    }
+}
+
+impl SteamClient {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            sysroot: None,
+        }
+    }
+
+    /// Resolve library paths recorded by Steam inside `sysroot`
+    pub fn with_sysroot(mut self, sysroot: Option<PathBuf>) -> Self {
+        self.sysroot = sysroot;
+        self
+    }
+
+    /// The install set in `library_paths.steam`, if it exists
+    pub fn from_config(config: &NvConfig) -> Option<Self> {
+        config
+            .library_paths
+            .steam
+            .as_ref()
+            .filter(|root| root.exists())
+            .map(Self::new)
+    }
+
+    /// Find the Steam install in a home directory
+    ///
+    /// Returns the first candidate that contains `steamapps`, logging a
+    /// warning when several distinct installs exist. This runs while the
+    /// default config is built, so it never prints.
+    pub fn discover(home: &Path) -> Option<Self> {
+        let installs = find_steam_installs(home);
+        if installs.len() > 1 {
+            let list: Vec<String> = installs.iter().map(|p| p.display().to_string()).collect();
+            log::warn!(
+                "multiple Steam installs found ({}); using {}. Change it with 'nvproton config set library_paths.steam <path>'",
+                list.join(", "),
+                list[0]
+            );
+        }
+        installs.into_iter().next().map(Self::new)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Custom compatibility tools (GE-Proton, Proton-NV, ...)
+    pub fn compat_tools_dir(&self) -> PathBuf {
+        self.root.join("compatibilitytools.d")
+    }
+
+    /// Library folders from `libraryfolders.vdf`, including the install itself
+    pub fn libraries(&self) -> Result<Vec<PathBuf>> {
+        let library_file = self.root.join("steamapps").join("libraryfolders.vdf");
+        if !library_file.exists() {
+            return Ok(vec![self.root.clone()]);
+        }
+        let content = fs::read_to_string(&library_file)
+            .with_context(|| format!("failed to read {:?}", library_file))?;
+        let regex = Regex::new(r#"path"\s+"([^"]+)"#)?;
+        let mut directories = vec![self.root.clone()];
+        for captures in regex.captures_iter(&content) {
+            let path = captures.get(1).map(|m| m.as_str()).unwrap_or_default();
+            let expanded = rebase(self.sysroot.as_deref(), Path::new(&path.replace("\\", "/")));
+            if expanded.exists() {
+                directories.push(expanded);
+            }
+        }
+        directories.sort();
+        directories.dedup();
+        Ok(directories)
+    }
+
+    /// Accounts that have signed in to this install
+    pub fn users(&self) -> Vec<SteamUser> {
+        let Ok(entries) = fs::read_dir(self.root.join("userdata")) else {
+            return Vec::new();
+        };
+        let mut users: Vec<SteamUser> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| {
+                let id = e.file_name().to_str()?.to_string();
+                // `0` holds anonymous data, not an account
+                (id != "0" && id.bytes().all(|b| b.is_ascii_digit()))
+                    .then(|| SteamUser { id, path: e.path() })
+            })
+            .collect();
+        users.sort_by(|a, b| a.id.cmp(&b.id));
+        users
+    }
+
+    /// Whether this install's client is running
+    ///
+    /// Looks for the install's `steam` binary among running processes. The
+    /// Flatpak client lives in its own mount namespace and isn't seen.
+    pub fn is_running(&self) -> bool {
+        let Ok(root) = self.root.canonicalize() else {
+            return false;
+        };
+        let Ok(entries) = fs::read_dir("/proc") else {
+            return false;
+        };
+        entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| fs::read_link(e.path().join("exe")).ok())
+            .any(|exe| is_client_binary(&root, &exe))
+    }
+
+    /// Install directory of an app in any library
+    pub fn app_install_dir(&self, appid: &str) -> Option<PathBuf> {
+        self.libraries().ok()?.into_iter().find_map(|library| {
+            let steamapps = library.join("steamapps");
+            let manifest = parse_manifest(&steamapps.join(format!("appmanifest_{}.acf", appid)))
+                .ok()
+                .flatten()?;
+            Some(steamapps.join("common").join(manifest.installdir)).filter(|dir| dir.is_dir())
+        })
+    }
+
+    /// Launch configurations the app info cache lists for an app
+    pub fn launch_configs(&self, appid: &str) -> Result<Vec<LaunchConfig>> {
+        let path = self.root.join("appcache").join("appinfo.vdf");
+        let Ok(appid) = appid.parse() else {
+            return Ok(Vec::new());
+        };
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        Ok(appinfo::read_app(&path, appid)?
+            .map(|app| appinfo::launch_configs(&app))
+            .unwrap_or_default())
+    }
+
+    /// Launch configurations of several apps, reading the app info cache once
+    ///
+    /// Apps the cache doesn't know are left out.
+    pub fn launch_configs_of<'a>(
+        &self,
+        appids: impl IntoIterator<Item = &'a str>,
+    ) -> Result<BTreeMap<String, Vec<LaunchConfig>>> {
+        let path = self.root.join("appcache").join("appinfo.vdf");
+        let appids: BTreeSet<u32> = appids
+            .into_iter()
+            .filter_map(|id| id.parse().ok())
+            .collect();
+        if !path.exists() || appids.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        Ok(appinfo::read_apps(&path, &appids)?
+            .into_iter()
+            .map(|(appid, app)| (appid.to_string(), appinfo::launch_configs(&app)))
+            .collect())
+    }
+
+    /// Parse a text VDF file relative to the install
+    pub(crate) fn read_vdf(&self, relative: impl AsRef<Path>) -> Option<Vec<(String, Vdf)>> {
+        let content = fs::read_to_string(self.root.join(relative)).ok()?;
+        Some(parse_vdf(&content))
+    }
+
+    /// Proton / compatibility tool Steam uses for an app
+    ///
+    /// Reads `CompatToolMapping` from `config/config.vdf`, falling back to the
+    /// global default (app "0"). Returns the tool's directory.
+    pub fn compat_tool(&self, appid: &str) -> Option<PathBuf> {
+        let config = self.read_vdf("config/config.vdf")?;
+        let name = compat_tool_name(&config, appid).or_else(|| compat_tool_name(&config, "0"))?;
+        self.compat_tool_path(&name)
+    }
+
+    /// Resolve a compat tool's internal name (e.g. `proton_experimental`, `GE-Proton9-20`)
+    fn compat_tool_path(&self, name: &str) -> Option<PathBuf> {
+        // Custom tools declare their name in compatibilitytool.vdf
+        let custom = fs::read_dir(self.compat_tools_dir()).into_iter().flatten();
+        for tool in custom.filter_map(|e| e.ok()).map(|e| e.path()) {
+            let declared = fs::read_to_string(tool.join("compatibilitytool.vdf"))
+                .map(|content| parse_vdf(&content))
+                .unwrap_or_default();
+            let tools = vdf_get(&declared, "compatibilitytools")
+                .and_then(Vdf::section)
+                .and_then(|tools| vdf_get(tools, "compat_tools"))
+                .and_then(Vdf::section)
+                .unwrap_or_default();
+            if tools.iter().any(|(key, _)| key == name) {
+                return Some(tool);
+            }
+        }
+
+        // Valve's tools: proton_experimental -> "Proton - Experimental", proton_9 -> "Proton 9.0"
+        let wanted = normalize_tool_name(name);
+        let libraries = self.libraries().ok()?;
+        libraries
+            .iter()
+            .filter_map(|library| fs::read_dir(library.join("steamapps").join("common")).ok())
+            .flat_map(|entries| entries.filter_map(|e| e.ok()))
+            .map(|entry| entry.path())
+            .filter(|path| path.join("proton").exists())
+            .find(|path| {
+                path.file_name()
+                    .is_some_and(|n| normalize_tool_name(&n.to_string_lossy()).starts_with(&wanted))
+            })
+    }
+
+    /// Cloud files of an app for every account on this install
+    ///
+    /// Steam keeps `userdata/<account>/<appid>/remotecache.vdf` with, per file,
+    /// the time of the last sync (`time`) and the local and cloud modification
+    /// times; a file changed on both sides since the sync is in conflict.
+    pub fn cloud_files(&self, appid: &str) -> Vec<CloudFile> {
+        let mut files = Vec::new();
+        for user in self.users() {
+            let path = user.path.join(appid).join("remotecache.vdf");
+            if let Ok(content) = fs::read_to_string(&path) {
+                files.extend(cloud_files_from_vdf(&parse_vdf(&content), &user.id));
+            }
+        }
+        files
+    }
+}
+
+fn find_steam_installs(home: &Path) -> Vec<PathBuf> {
+    let mut installs: Vec<PathBuf> = Vec::new();
+    for candidate in STEAM_ROOT_CANDIDATES {
+        // ~/.steam/root and ~/.steam/steam are symlinks to the real install
+        let Ok(path) = home.join(candidate).canonicalize() else {
+            continue;
+        };
+        if path.join("steamapps").is_dir() && !installs.contains(&path) {
+            installs.push(path);
+        }
+    }
+    installs
+}
+
+/// The client binary is `<root>/ubuntu12_32/steam`
+fn is_client_binary(root: &Path, exe: &Path) -> bool {
+    exe.starts_with(root) && exe.file_name().is_some_and(|name| name == "steam")
+}
+
+/// Name of the tool mapped to an app in `CompatToolMapping`
+fn compat_tool_name(config: &[(String, Vdf)], appid: &str) -> Option<String> {
+    // The mapping sits under InstallConfigStore/Software/Valve/Steam
+    let mut section = vdf_get(config, "InstallConfigStore")
+        .and_then(Vdf::section)
+        .unwrap_or(config);
+    for key in ["Software", "Valve", "Steam"] {
+        if let Some(inner) = vdf_get(section, key).and_then(Vdf::section) {
+            section = inner;
+        }
+    }
+    let mapping = vdf_get(section, "CompatToolMapping")?.section()?;
+    let name = vdf_get(mapping, appid)?.section()?;
+    let name = vdf_get(name, "name")?.value()?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+fn normalize_tool_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Whether an appid is a Steam internal (Proton, runtimes, redistributables)
+pub fn is_excluded_appid(appid: &str) -> bool {
+    EXCLUDED_APPIDS.contains(&appid)
+}
+
+/// AppID of the game Steam is launching, read from the launch environment
+///
+/// Steam sets `SteamAppId` for store games. Non-Steam shortcuts only carry
+/// `SteamGameId`, a 64-bit game ID whose upper 32 bits are the shortcut appid.
+pub fn app_id_from_env() -> Option<String> {
+    app_id_from_vars(
+        std::env::var("SteamAppId").ok().as_deref(),
+        std::env::var("SteamGameId").ok().as_deref(),
+    )
+}
+
+fn app_id_from_vars(app_id: Option<&str>, game_id: Option<&str>) -> Option<String> {
+    if let Some(id) = app_id.filter(|v| !v.is_empty() && *v != "0") {
+        return Some(id.to_string());
+    }
+
+    let game_id: u64 = game_id?.parse().ok().filter(|v| *v != 0)?;
+    if game_id > u64::from(u32::MAX) {
+        Some((game_id >> 32).to_string())
+    } else {
+        Some(game_id.to_string())
+    }
+}
+
+/// Steam Cloud state of a file, compared to its last sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloudSync {
+    Synced,
+    /// Changed locally since the last sync
+    PendingUpload,
+    /// Changed in the cloud since the last sync
+    PendingDownload,
+    /// Changed on both sides: launching picks one and the other is lost
+    Conflict,
+}
+
+impl CloudSync {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            CloudSync::Synced => "in sync",
+            CloudSync::PendingUpload => "upload pending",
+            CloudSync::PendingDownload => "download pending",
+            CloudSync::Conflict => "conflict",
+        }
+    }
+}
+
+/// A Steam Cloud file listed in `remotecache.vdf`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudFile {
+    /// Steam account (userdata directory) the file belongs to
+    pub account: String,
+    pub name: String,
+    pub state: CloudSync,
+}
+
+fn cloud_files_from_vdf(root: &[(String, Vdf)], account: &str) -> Vec<CloudFile> {
+    let Some((_, Vdf::Section(entries))) = root.first() else {
+        return Vec::new();
+    };
+    entries
+        .iter()
+        .filter_map(|(name, file)| {
+            let file = file.section()?;
+            let time = |key: &str| {
+                vdf_get(file, key)
+                    .and_then(Vdf::value)
+                    .and_then(|v| v.parse::<u64>().ok())
+            };
+            let synced = time("time")?;
+            let local_changed = time("localtime").is_some_and(|t| t != synced);
+            let remote_changed = time("remotetime").is_some_and(|t| t != synced);
+            let state = match (local_changed, remote_changed) {
+                (true, true) => CloudSync::Conflict,
+                (true, false) => CloudSync::PendingUpload,
+                (false, true) => CloudSync::PendingDownload,
+                (false, false) => CloudSync::Synced,
+            };
+            Some(CloudFile {
+                account: account.to_string(),
+                name: name.clone(),
+                state,
+            })
+        })
+        .collect()
+}
+
+/// A node of Valve's KeyValues text format
+#[derive(Debug, Clone)]
+pub(crate) enum Vdf {
+    Value(String),
+    Section(Vec<(String, Vdf)>),
+}
+
+impl Vdf {
+    pub(crate) fn value(&self) -> Option<&str> {
+        match self {
+            Vdf::Value(value) => Some(value),
+            Vdf::Section(_) => None,
+        }
+    }
+
+    pub(crate) fn section(&self) -> Option<&[(String, Vdf)]> {
+        match self {
+            Vdf::Section(entries) => Some(entries),
+            Vdf::Value(_) => None,
+        }
+    }
+}
+
+/// Look up a key (Steam is inconsistent about key case)
+pub(crate) fn vdf_get<'a>(entries: &'a [(String, Vdf)], key: &str) -> Option<&'a Vdf> {
+    entries
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v)
+}
+
+/// The section under a key, added when missing
+pub(crate) fn vdf_section_mut<'a>(
+    entries: &'a mut Vec<(String, Vdf)>,
+    key: &str,
+) -> &'a mut Vec<(String, Vdf)> {
+    let index = entries
+        .iter()
+        .position(|(k, v)| k.eq_ignore_ascii_case(key) && v.section().is_some())
+        .unwrap_or_else(|| {
+            entries.push((key.to_string(), Vdf::Section(Vec::new())));
+            entries.len() - 1
+        });
+    match &mut entries[index].1 {
+        Vdf::Section(section) => section,
+        Vdf::Value(_) => unreachable!("only sections are matched"),
+    }
+}
+
+/// Entries in the layout the client writes (tab-indented, quoted)
+pub(crate) fn write_vdf(entries: &[(String, Vdf)]) -> String {
+    let mut out = String::new();
+    write_vdf_section(&mut out, entries, 0);
+    out
+}
+
+fn write_vdf_section(out: &mut String, entries: &[(String, Vdf)], depth: usize) {
+    let indent = "\t".repeat(depth);
+    for (key, value) in entries {
+        match value {
+            Vdf::Value(value) => out.push_str(&format!(
+                "{}\"{}\"\t\t\"{}\"\n",
+                indent,
+                escape_vdf(key),
+                escape_vdf(value)
+            )),
+            Vdf::Section(section) => {
+                out.push_str(&format!(
+                    "{}\"{}\"\n{}{{\n",
+                    indent,
+                    escape_vdf(key),
+                    indent
+                ));
+                write_vdf_section(out, section, depth + 1);
+                out.push_str(&format!("{}}}\n", indent));
+            }
+        }
+    }
+}
+
+fn escape_vdf(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+enum VdfToken {
+    Str(String),
+    Open,
+    Close,
+}
+
+pub(crate) fn parse_vdf(content: &str) -> Vec<(String, Vdf)> {
+    let mut tokens = tokenize_vdf(content).into_iter();
+    parse_vdf_section(&mut tokens)
+}
+
+fn parse_vdf_section(tokens: &mut impl Iterator<Item = VdfToken>) -> Vec<(String, Vdf)> {
+    let mut entries = Vec::new();
+    while let Some(VdfToken::Str(key)) = tokens.next() {
+        match tokens.next() {
+            Some(VdfToken::Str(value)) => entries.push((key, Vdf::Value(value))),
+            Some(VdfToken::Open) => entries.push((key, Vdf::Section(parse_vdf_section(tokens)))),
+            _ => break,
+        }
+    }
+    entries
+}
+
+fn tokenize_vdf(content: &str) -> Vec<VdfToken> {
+    let mut tokens = Vec::new();
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => tokens.push(VdfToken::Open),
+            '}' => tokens.push(VdfToken::Close),
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                let mut value = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('n') => value.push('\n'),
+                            Some('t') => value.push('\t'),
+                            Some(other) => value.push(other),
+                            None => break,
+                        },
+                        _ => value.push(c),
+                    }
+                }
+                tokens.push(VdfToken::Str(value));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut value = String::from(c);
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || next == '{' || next == '}' || next == '"' {
+                        break;
+                    }
+                    value.push(next);
+                    chars.next();
+                }
+                tokens.push(VdfToken::Str(value));
+            }
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_id_from_steam_env() {
+        assert_eq!(
+            app_id_from_vars(Some("1245620"), Some("1245620")).as_deref(),
+            Some("1245620")
+        );
+        // Non-Steam shortcut: SteamAppId=0, appid in the upper bits of SteamGameId
+        let shortcut = (3_000_000_000u64 << 32) | 0x0200_0000;
+        assert_eq!(
+            app_id_from_vars(Some("0"), Some(&shortcut.to_string())).as_deref(),
+            Some("3000000000")
+        );
+        assert_eq!(app_id_from_vars(None, None), None);
+    }
+
+    #[test]
+    fn test_find_steam_installs_dedupes_symlinks() {
+        let home = tempfile::tempdir().unwrap();
+        let native = home.path().join(".local/share/Steam");
+        let flatpak = home
+            .path()
+            .join(".var/app/com.valvesoftware.Steam/.local/share/Steam");
+        fs::create_dir_all(native.join("steamapps")).unwrap();
+        fs::create_dir_all(flatpak.join("steamapps")).unwrap();
+        fs::create_dir_all(home.path().join(".steam")).unwrap();
+        std::os::unix::fs::symlink(&native, home.path().join(".steam/root")).unwrap();
+
+        let installs = find_steam_installs(home.path());
+        assert_eq!(
+            installs,
+            vec![
+                native.canonicalize().unwrap(),
+                flatpak.canonicalize().unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cloud_sync_states() {
+        let remotecache = r#"
+            "1145360"
+            {
+                "ChangeNumber"      "42"
+                "ostype"            "-184"
+                "Profile1.sav"
+                {
+                    "root"          "0"
+                    "size"          "1024"
+                    "localtime"     "1760000000"
+                    "time"          "1760000000"
+                    "remotetime"    "1760000000"
+                }
+                "Profile2.sav"
+                {
+                    "localtime"     "1760000500"
+                    "time"          "1760000000"
+                    "remotetime"    "1760000900"
+                }
+                "Profile3.sav"
+                {
+                    "localtime"     "1760000500"
+                    "time"          "1760000000"
+                    "remotetime"    "1760000000"
+                }
+            }
+        "#;
+        let files = cloud_files_from_vdf(&parse_vdf(remotecache), "12345678");
+        let states: Vec<_> = files.iter().map(|f| (f.name.as_str(), f.state)).collect();
+        assert_eq!(
+            states,
+            vec![
+                ("Profile1.sav", CloudSync::Synced),
+                ("Profile2.sav", CloudSync::Conflict),
+                ("Profile3.sav", CloudSync::PendingUpload)
+            ]
+        );
+        assert_eq!(files[0].account, "12345678");
+    }
+
+    #[test]
+    fn test_compat_tool_name() {
+        let config = parse_vdf(
+            r#"
+            "InstallConfigStore"
+            {
+                "Software"
+                {
+                    "Valve"
+                    {
+                        "Steam"
+                        {
+                            "CompatToolMapping"
+                            {
+                                "0"
+                                {
+                                    "name"      "proton_experimental"
+                                    "config"    ""
+                                }
+                                "1245620"
+                                {
+                                    "name"      "GE-Proton9-20"
+                                    "config"    ""
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        "#,
+        );
+        assert_eq!(
+            compat_tool_name(&config, "1245620").as_deref(),
+            Some("GE-Proton9-20")
+        );
+        assert_eq!(
+            compat_tool_name(&config, "0").as_deref(),
+            Some("proton_experimental")
+        );
+        assert_eq!(compat_tool_name(&config, "42"), None);
+        assert!(normalize_tool_name("Proton 9.0").starts_with(&normalize_tool_name("proton_9")));
+    }
+
+    #[test]
+    fn test_client_reads_fixture_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("Steam");
+        let library = dir.path().join("games");
+        let write = |path: PathBuf, content: &str| {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write(
+            root.join("steamapps/libraryfolders.vdf"),
+            &format!(
+                "\"libraryfolders\"\n{{\n \"1\" {{ \"path\" \"{}\" }}\n}}\n",
+                library.display()
+            ),
+        );
+        write(
+            library.join("steamapps/appmanifest_1145360.acf"),
+            "\"AppState\" { \"appid\" \"1145360\" \"name\" \"Hades\" \"installdir\" \"Hades\" }",
+        );
+        fs::create_dir_all(library.join("steamapps/common/Hades")).unwrap();
+        write(
+            root.join("compatibilitytools.d/GE-Proton9-20/compatibilitytool.vdf"),
+            "\"compatibilitytools\" { \"compat_tools\" { \"GE-Proton9-20\" { \"display_name\" \"GE-Proton9-20\" } } }",
+        );
+        write(
+            root.join("config/config.vdf"),
+            "\"CompatToolMapping\" { \"1145360\" { \"name\" \"GE-Proton9-20\" } }",
+        );
+        write(
+            root.join("userdata/12345678/1145360/remotecache.vdf"),
+            "\"1145360\" { \"Profile1.sav\" { \"time\" \"1\" \"localtime\" \"2\" } }",
+        );
+        fs::create_dir_all(root.join("userdata/0")).unwrap();
+
+        let steam = SteamClient::new(&root);
+        assert_eq!(
+            steam.libraries().unwrap(),
+            vec![root.clone(), library.clone()]
+        );
+        assert_eq!(
+            steam.app_install_dir("1145360"),
+            Some(library.join("steamapps/common/Hades"))
+        );
+        assert_eq!(steam.app_install_dir("1086940"), None);
+        assert_eq!(
+            steam.compat_tool("1145360"),
+            Some(root.join("compatibilitytools.d/GE-Proton9-20"))
+        );
+        assert_eq!(steam.compat_tool("1086940"), None);
+
+        let users = steam.users();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].id, "12345678");
+        let files = steam.cloud_files("1145360");
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].state, CloudSync::PendingUpload);
+
+        assert!(is_client_binary(&root, &root.join("ubuntu12_32/steam")));
+        assert!(!is_client_binary(&root, Path::new("/usr/bin/steam")));
+    }
+
+    #[test]
+    fn test_launch_options_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = SteamUser {
+            id: "12345678".into(),
+            path: dir.path().to_path_buf(),
+        };
+        fs::create_dir_all(dir.path().join("config")).unwrap();
+        fs::write(
+            user.local_config(),
+            "\"UserLocalConfigStore\"\n{\n\t\"friends\" { \"PersonaName\" \"gk\" }\n\t\"Software\" { \"Valve\" { \"Steam\" { \"Apps\" {\n\t\t\"1145360\" { \"LastPlayed\" \"1\" \"LaunchOptions\" \"gamemoderun %command%\" }\n\t} } } }\n}\n",
+        )
+        .unwrap();
+        assert_eq!(
+            user.launch_options(),
+            BTreeMap::from([("1145360".to_string(), "gamemoderun %command%".to_string())])
+        );
+
+        let options = BTreeMap::from([
+            (
+                "1145360".to_string(),
+                "A=\"b c\" gamemoderun %command%".to_string(),
+            ),
+            ("1086940".to_string(), "B=1 %command%".to_string()),
+        ]);
+        user.set_launch_options(&options).unwrap();
+        assert_eq!(user.launch_options(), options);
+        // Other settings are kept
+        let root = parse_vdf(&fs::read_to_string(user.local_config()).unwrap());
+        let store = vdf_get(&root, "UserLocalConfigStore")
+            .unwrap()
+            .section()
+            .unwrap();
+        let friends = vdf_get(store, "friends").unwrap().section().unwrap();
+        assert_eq!(vdf_get(friends, "PersonaName").unwrap().value(), Some("gk"));
+        assert!(write_vdf(&root).contains("\t\t\t\t\t\"LastPlayed\"\t\t\"1\"\n"));
+    }
+}
@@ -6,17 +6,26 @@
 //! - Proton version management
 //! - Steam Input configuration
 
+pub mod appinfo;
+pub mod client;
+
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 
+use crate::cache::CacheManager;
 use crate::cli::{SteamArgs, SteamCommand};
 use crate::config::{ConfigManager, NvConfig};
-use crate::detection::GameDatabase;
+use crate::detection::{GameDatabase, GameId, GameSource};
+use crate::errors::NvError;
+use crate::i18n::tr;
+use crate::runner;
+use crate::term::Table;
+use client::SteamClient;
 
 /// Handle Steam subcommands
-pub fn handle_steam(args: SteamArgs, manager: &ConfigManager, config: &mut NvConfig) -> Result<()> {
+pub fn handle_steam(args: SteamArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
     match args.command {
         SteamCommand::LaunchOptions(opts) => handle_launch_options(opts, manager, config),
         SteamCommand::Proton(opts) => handle_proton(opts, manager, config),
@@ -32,14 +41,12 @@ fn handle_launch_options(
 ) -> Result<()> {
     let db = GameDatabase::load_or_default(manager.paths())?;
 
-    let game = db.get(&args.game_id).ok_or_else(|| {
-        anyhow::anyhow!(
-            "Game '{}' not found. Run 'nvproton games scan' first.",
-            args.game_id
-        )
-    })?;
+    let game = db.resolve_game(&args.game_id)?;
 
-    println!("Launch Options for: {} ({})", game.name, game.id);
+    println!(
+        "{}",
+        tr!("steam-launch-options", name = game.name, id = game.id)
+    );
     println!();
 
     // Build launch options
@@ -47,7 +54,7 @@ fn handle_launch_options(
 
     // Always use nvproton wrapper
     if args.use_nvproton {
-        options.push(format!("nvproton run {} --", game.id));
+        options.push(format!("nvproton run {} --wrapper --", game.game_id()));
     }
 
     // Reflex/low latency
@@ -67,12 +74,11 @@ fn handle_launch_options(
         options.push(format!("DXVK_FRAME_RATE={}", args.fps));
     }
 
-    // Shader cache path
+    // Per-game shader caches, with the same absolute paths `run` and `wrap` use
     if args.shader_cache {
-        options.push(format!(
-            "DXVK_STATE_CACHE_PATH=~/.cache/nvproton/{}",
-            game.id
-        ));
+        for (key, value) in CacheManager::new()?.setup_for_game(&game, &db)? {
+            options.push(format!("{}={}", key, runner::shell_quote(&value)));
+        }
     }
 
     // MangoHud
@@ -94,17 +100,17 @@ fn handle_launch_options(
     if args.copy_format {
         // Format for Steam's "Set Launch Options" dialog
         let steam_options = build_steam_launch_string(&options, args.use_nvproton);
-        println!("Copy this into Steam's \"Set Launch Options\":\n");
+        println!("{}\n", tr!("steam-copy-hint"));
         println!("{}", steam_options);
     } else {
-        println!("Recommended environment variables:");
+        println!("{}", tr!("steam-recommended-env"));
         for opt in &options {
             if opt.contains('=') {
                 println!("  {}", opt);
             }
         }
         println!();
-        println!("Full launch command:");
+        println!("{}", tr!("steam-full-command"));
         println!(
             "  {}",
             build_steam_launch_string(&options, args.use_nvproton)
@@ -112,10 +118,7 @@ fn handle_launch_options(
     }
 
     println!();
-    println!("To apply in Steam:");
-    println!("  1. Right-click {} in your library", game.name);
-    println!("  2. Properties > General > Launch Options");
-    println!("  3. Paste the command above");
+    println!("{}", tr!("steam-apply-steps", name = game.name));
 
     Ok(())
 }
@@ -151,7 +154,7 @@ fn build_steam_launch_string(options: &[String], use_nvproton: bool) -> String {
     }
 
     if use_nvproton {
-        result.push_str("nvproton run %appid% -- ");
+        result.push_str("nvproton wrap -- ");
     }
 
     // %command% is required by Steam
@@ -166,90 +169,85 @@ fn handle_proton(
     _manager: &ConfigManager,
     config: &NvConfig,
 ) -> Result<()> {
-    let steam_path = config
-        .library_paths
-        .steam
-        .as_ref()
-        .ok_or_else(|| anyhow::anyhow!("Steam path not configured"))?;
+    let steam = steam_client(config)?;
 
     match args.command {
         crate::cli::ProtonCommand::List => {
-            println!("Installed Proton versions:\n");
+            println!("{}\n", tr!("steam-proton-installed"));
+            let mut table =
+                Table::new([tr!("column-name"), tr!("column-source"), tr!("column-path")]);
 
             // Check compatibilitytools.d
-            let compat_dir = steam_path.join("compatibilitytools.d");
-            if compat_dir.exists() {
-                println!("Custom (compatibilitytools.d):");
-                list_proton_versions(&compat_dir, "  ")?;
+            let custom = tr!("steam-proton-custom");
+            for path in proton_versions(&steam.compat_tools_dir())? {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                table.row([
+                    name.into_owned(),
+                    custom.clone(),
+                    path.display().to_string(),
+                ]);
             }
 
             // Check Steam's Proton installs
-            let proton_dirs = [steam_path.join("steamapps/common")];
+            let proton_dirs: Vec<_> = steam
+                .libraries()?
+                .into_iter()
+                .map(|library| library.join("steamapps/common"))
+                .collect();
 
-            println!("\nSteam-installed:");
+            let bundled = tr!("steam-proton-steam");
             for dir in &proton_dirs {
                 if dir.exists() {
                     for entry in fs::read_dir(dir)? {
                         let entry = entry?;
                         let name = entry.file_name().to_string_lossy().to_string();
                         if name.contains("Proton") || name.contains("proton") {
-                            println!("  {}", name);
+                            let path = entry.path().display().to_string();
+                            table.row([name, bundled.clone(), path]);
                         }
                     }
                 }
             }
+            print!("{}", table);
         }
         crate::cli::ProtonCommand::Recommended => {
-            println!("Recommended Proton versions for NVIDIA:\n");
-            println!("1. Proton Experimental (latest features)");
-            println!("   - Best for: Most modern games, VR");
-            println!("   - DLSS: Full support");
-            println!("   - Reflex: Full support");
-            println!();
-            println!("2. Proton GE (GloriousEggroll)");
-            println!("   - Best for: Games with codec issues, older titles");
-            println!("   - Install: https://github.com/GloriousEggroll/proton-ge-custom");
-            println!();
-            println!("3. Proton 9.x (stable)");
-            println!("   - Best for: Games that need stability");
-            println!("   - DLSS: Supported");
-            println!();
-            println!("For competitive gaming with Reflex, use Proton Experimental.");
+            println!("{}", tr!("steam-proton-recommended"));
         }
         crate::cli::ProtonCommand::SetDefault { version } => {
-            println!("Setting default Proton version to: {}", version);
-            println!();
-            println!("To set default Proton in Steam:");
-            println!("  1. Steam > Settings > Compatibility");
-            println!("  2. Enable 'Enable Steam Play for all other titles'");
-            println!("  3. Select '{}' from the dropdown", version);
-            println!();
-            println!("Note: nvproton respects Steam's per-game Proton settings.");
+            println!("{}", tr!("steam-proton-set-default", version = version));
         }
     }
 
     Ok(())
 }
 
+/// The configured Steam install
+fn steam_client(config: &NvConfig) -> Result<SteamClient> {
+    SteamClient::from_config(config).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Steam install not found; set it with 'nvproton config set library_paths.steam <path>'"
+        )
+    })
+}
+
 /// List Proton versions in a directory
-fn list_proton_versions(dir: &Path, prefix: &str) -> Result<()> {
+fn proton_versions(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut versions = Vec::new();
     if !dir.exists() {
-        return Ok(());
+        return Ok(versions);
     }
 
     for entry in fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
-        if path.is_dir() {
-            let name = entry.file_name().to_string_lossy().to_string();
-            // Check for proton binary or toolmanifest
-            if path.join("proton").exists() || path.join("toolmanifest.vdf").exists() {
-                println!("{}{}", prefix, name);
-            }
+        // Check for proton binary or toolmanifest
+        if path.is_dir() && (path.join("proton").exists() || path.join("toolmanifest.vdf").exists())
+        {
+            versions.push(path);
         }
     }
 
-    Ok(())
+    Ok(versions)
 }
 
 /// Handle non-Steam shortcut creation
@@ -258,11 +256,7 @@ fn handle_shortcut(
     manager: &ConfigManager,
     config: &NvConfig,
 ) -> Result<()> {
-    let steam_path = config
-        .library_paths
-        .steam
-        .as_ref()
-        .ok_or_else(|| anyhow::anyhow!("Steam path not configured"))?;
+    let steam = steam_client(config)?;
 
     match args.command {
         crate::cli::ShortcutCommand::Create {
@@ -272,71 +266,55 @@ fn handle_shortcut(
             icon,
             launch_options,
         } => {
-            println!("Creating non-Steam shortcut: {}", name);
+            println!("{}", tr!("steam-shortcut-creating", name = name));
             println!();
 
-            // Find shortcuts.vdf
-            let userdata_dir = steam_path.join("userdata");
-            if !userdata_dir.exists() {
-                anyhow::bail!("Steam userdata directory not found");
-            }
-
-            // List Steam user IDs
-            let user_dirs: Vec<_> = fs::read_dir(&userdata_dir)?
-                .filter_map(Result::ok)
-                .filter(|e| e.path().is_dir())
-                .collect();
-
-            if user_dirs.is_empty() {
+            // Use the first user's shortcuts.vdf
+            let Some(user) = steam.users().into_iter().next() else {
                 anyhow::bail!("No Steam users found");
-            }
-
-            // Use first user or let user choose
-            let user_dir = &user_dirs[0].path();
-            let shortcuts_path = user_dir.join("config/shortcuts.vdf");
+            };
+            let shortcuts_path = user.shortcuts_file();
 
-            println!("Shortcut details:");
-            println!("  Name: {}", name);
-            println!("  Executable: {}", exe);
+            println!("{}", tr!("steam-shortcut-details"));
+            println!("  {}", tr!("steam-shortcut-name", name = name));
+            println!("  {}", tr!("steam-shortcut-exe", exe = exe));
             if let Some(ref dir) = start_dir {
-                println!("  Start In: {}", dir);
+                println!("  {}", tr!("steam-shortcut-start-in", dir = dir));
             }
             if let Some(ref ico) = icon {
-                println!("  Icon: {}", ico);
+                println!("  {}", tr!("steam-shortcut-icon", icon = ico));
             }
             if let Some(ref opts) = launch_options {
-                println!("  Launch Options: {}", opts);
+                println!("  {}", tr!("steam-shortcut-options", options = opts));
             }
 
             println!();
-            println!("To add manually in Steam:");
-            println!("  1. Library > Add a Game > Add a Non-Steam Game");
-            println!("  2. Browse to: {}", exe);
-            println!("  3. Right-click the shortcut > Properties");
+            println!("{}", tr!("steam-shortcut-steps", exe = exe));
             if let Some(opts) = launch_options {
-                println!("  4. Set Launch Options: {}", opts);
+                println!("  {}", tr!("steam-shortcut-step-options", options = opts));
             }
 
             // Note: Actually modifying shortcuts.vdf requires parsing its binary format
             // For now, provide instructions
             println!();
-            println!("Note: Automatic shortcut creation requires Steam to be closed.");
-            println!("The shortcuts.vdf file is located at: {:?}", shortcuts_path);
+            let path = format!("{:?}", shortcuts_path);
+            println!("{}", tr!("steam-shortcut-note", path = path));
         }
         crate::cli::ShortcutCommand::List => {
-            println!("Non-Steam shortcuts:\n");
+            println!("{}\n", tr!("steam-shortcut-list"));
 
-            let userdata_dir = steam_path.join("userdata");
-            if !userdata_dir.exists() {
-                println!("No Steam userdata found.");
+            let users = steam.users();
+            if users.is_empty() {
+                println!("{}", tr!("steam-shortcut-no-userdata"));
                 return Ok(());
             }
 
-            for user_entry in fs::read_dir(&userdata_dir)?.filter_map(Result::ok) {
-                let shortcuts_path = user_entry.path().join("config/shortcuts.vdf");
+            for user in users {
+                let shortcuts_path = user.shortcuts_file();
                 if shortcuts_path.exists() {
-                    println!("User: {}", user_entry.file_name().to_string_lossy());
-                    println!("  Shortcuts file: {:?}", shortcuts_path);
+                    println!("{}", tr!("steam-shortcut-user", user = user.id));
+                    let path = format!("{:?}", shortcuts_path);
+                    println!("  {}", tr!("steam-shortcut-file", path = path));
                     // Note: Full parsing would require VDF binary format support
                 }
             }
@@ -344,8 +322,11 @@ fn handle_shortcut(
         crate::cli::ShortcutCommand::Optimize { appid, profile } => {
             let db = GameDatabase::load_or_default(manager.paths())?;
 
-            if let Some(game) = db.get(&appid) {
-                println!("Optimizing shortcut for: {} ({})", game.name, appid);
+            if let Some(game) = db.get(&GameId::new(GameSource::Steam, appid.clone())) {
+                println!(
+                    "{}",
+                    tr!("steam-shortcut-optimizing", name = game.name, id = appid)
+                );
                 println!();
 
                 // Generate optimized launch options
@@ -356,11 +337,14 @@ fn handle_shortcut(
                 ];
 
                 if let Some(profile_name) = profile {
-                    println!("Applying profile: {}", profile_name);
+                    println!("{}", tr!("steam-shortcut-profile", profile = profile_name));
                     // Load profile and add its env vars
                     let profile_manager =
                         crate::profile::ProfileManager::new(manager.paths().profiles_dir.clone());
-                    if let Ok(resolved) = profile_manager.resolve(&profile_name) {
+                    if let Ok(mut resolved) = profile_manager.resolve(&profile_name) {
+                        let vulkan = crate::detection::VulkanCapabilities::detect().ok();
+                        crate::template::TemplateContext::for_game(&game, vulkan.as_ref())
+                            .expand_settings(&mut resolved.settings)?;
                         // Extract env vars from profile
                         if let serde_yaml::Value::Mapping(map) = &resolved.settings
                             && let Some(serde_yaml::Value::Mapping(env)) =
@@ -379,11 +363,11 @@ fn handle_shortcut(
                     }
                 }
 
-                println!("Recommended launch options:");
+                println!("{}", tr!("steam-shortcut-recommended"));
                 let launch_str = build_steam_launch_string(&options, false);
                 println!("  {}", launch_str);
             } else {
-                anyhow::bail!("Game '{}' not found in database", appid);
+                return Err(NvError::GameNotFound(appid).into());
             }
         }
     }
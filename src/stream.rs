@@ -0,0 +1,141 @@
+//! Streamed JSON and YAML lists
+//!
+//! Listing commands write each item as soon as it is produced instead of
+//! collecting the whole list into one string first, so frontends reading
+//! thousands of games get the first entries right away and nvproton's
+//! memory stays flat. JSON lists put one compact item per line; YAML
+//! lists are a block sequence, as `serde_yaml` writes them. Text has no
+//! list syntax, so items are written as JSON Lines.
+
+use std::io::Write;
+
+use anyhow::{Result, bail};
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+
+use crate::cli::OutputFormat;
+
+/// Writes the items of one list
+pub struct ListWriter<W: Write> {
+    out: W,
+    format: OutputFormat,
+    count: usize,
+}
+
+impl<W: Write> ListWriter<W> {
+    pub fn new(out: W, format: OutputFormat) -> Self {
+        Self {
+            out,
+            format,
+            count: 0,
+        }
+    }
+
+    pub fn write<T: Serialize>(&mut self, item: &T) -> Result<()> {
+        match self.format {
+            OutputFormat::Json => {
+                self.out
+                    .write_all(if self.count == 0 { b"[\n  " } else { b",\n  " })?;
+                serde_json::to_writer(&mut self.out, item)?;
+            }
+            OutputFormat::Yaml => serde_yaml::to_writer(&mut self.out, &[item])?,
+            OutputFormat::Text => {
+                serde_json::to_writer(&mut self.out, item)?;
+                self.out.write_all(b"\n")?;
+            }
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Close the list, returning the number of items written
+    pub fn finish(mut self) -> Result<usize> {
+        match (&self.format, self.count) {
+            (OutputFormat::Json, 0) => self.out.write_all(b"[]\n")?,
+            (OutputFormat::Json, _) => self.out.write_all(b"\n]\n")?,
+            (OutputFormat::Yaml, 0) => self.out.write_all(b"[]\n")?,
+            _ => {}
+        }
+        self.out.flush()?;
+        Ok(self.count)
+    }
+}
+
+/// An item reduced to some of its fields, in the order given
+///
+/// Fields the item doesn't have (like an unset executable) are left out.
+pub fn select<T: Serialize>(item: &T, fields: &[&str]) -> Result<Value> {
+    let Value::Mapping(mut all) = serde_yaml::to_value(item)? else {
+        bail!("only objects have fields");
+    };
+    let selected: Mapping = fields
+        .iter()
+        .filter_map(|field| all.remove_entry(*field))
+        .collect();
+    Ok(Value::Mapping(selected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Game {
+        id: &'static str,
+        name: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        executable: Option<&'static str>,
+    }
+
+    fn render(format: OutputFormat, items: &[Value]) -> String {
+        let mut out = Vec::new();
+        let mut writer = ListWriter::new(&mut out, format);
+        for item in items {
+            writer.write(item).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), items.len());
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_streamed_lists_and_fields() {
+        let games = [
+            Game {
+                id: "1145360",
+                name: "Hades",
+                executable: Some("Hades.exe"),
+            },
+            Game {
+                id: "witcher3",
+                name: "The Witcher 3",
+                executable: None,
+            },
+        ];
+        let items: Vec<_> = games
+            .iter()
+            .map(|game| select(game, &["name", "id", "executable"]).unwrap())
+            .collect();
+        assert_eq!(
+            items[0]
+                .as_mapping()
+                .unwrap()
+                .keys()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>(),
+            ["name", "id", "executable"]
+        );
+
+        let json = render(OutputFormat::Json, &items);
+        assert!(json.starts_with("[\n  {\"name\":\"Hades\",\"id\":\"1145360\""));
+        let parsed: Vec<Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, items);
+        assert_eq!(render(OutputFormat::Json, &[]), "[]\n");
+
+        let yaml = render(OutputFormat::Yaml, &items);
+        let parsed: Vec<Value> = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed, items);
+        assert!(yaml.starts_with("- name: Hades\n"));
+        let empty: Vec<Value> = serde_yaml::from_str(&render(OutputFormat::Yaml, &[])).unwrap();
+        assert!(empty.is_empty());
+    }
+}
@@ -0,0 +1,78 @@
+//! OBS / streaming detection
+//!
+//! Detects a running OBS Studio instance either by process name or by a
+//! listening obs-websocket server, so launches can layer the `streaming`
+//! profile fragment from the config.
+
+use std::fs;
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+/// Default obs-websocket port (OBS 28+)
+const OBS_WEBSOCKET_PORT: u16 = 4455;
+
+/// Process names used by OBS Studio (native and Flatpak)
+const OBS_PROCESS_NAMES: &[&str] = &["obs", "obs-studio"];
+
+/// How OBS was detected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObsDetection {
+    Process,
+    WebSocket,
+}
+
+impl ObsDetection {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            ObsDetection::Process => "OBS process",
+            ObsDetection::WebSocket => "obs-websocket",
+        }
+    }
+}
+
+/// Detect a running OBS instance
+pub fn detect_obs() -> Option<ObsDetection> {
+    if obs_process_running() {
+        return Some(ObsDetection::Process);
+    }
+    if obs_websocket_listening() {
+        return Some(ObsDetection::WebSocket);
+    }
+    None
+}
+
+fn obs_process_running() -> bool {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return false;
+    };
+
+    entries.filter_map(|e| e.ok()).any(|entry| {
+        let is_pid = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.bytes().all(|b| b.is_ascii_digit()));
+        is_pid
+            && fs::read_to_string(entry.path().join("comm"))
+                .is_ok_and(|comm| is_obs_process(comm.trim()))
+    })
+}
+
+fn is_obs_process(comm: &str) -> bool {
+    OBS_PROCESS_NAMES.contains(&comm)
+}
+
+fn obs_websocket_listening() -> bool {
+    let addr = SocketAddr::from(([127, 0, 0, 1], OBS_WEBSOCKET_PORT));
+    TcpStream::connect_timeout(&addr, Duration::from_millis(100)).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_obs_process_names() {
+        assert!(is_obs_process("obs"));
+        assert!(!is_obs_process("obsidian"));
+    }
+}
@@ -0,0 +1,235 @@
+//! Named launch targets
+//!
+//! Some games ship more than one executable worth starting: a DirectX 11 and
+//! a DirectX 12 build, a mod loader, a dedicated server. A launch target names
+//! one of them with the arguments it needs, and `run --target <name>` starts
+//! it instead of the game's executable. Targets are added per game with
+//! `games targets --add`; Steam games also get the launch configurations
+//! Steam's app info cache lists, and games with a mod manager installed get
+//! one for it (see `modloaders`), unless a user target has the same name.
+//!
+//! Names match exactly, or as a unique fragment of a target's name or
+//! executable (`--target dx12` picks `bin/Game_DX12.exe`).
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::detection::{DetectedGame, GameSource};
+use crate::modloaders::ModLoader;
+use crate::steam::appinfo::LaunchConfig;
+use crate::steam::client::SteamClient;
+
+/// An executable of a game, with the arguments to start it with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchTarget {
+    pub name: String,
+    pub executable: PathBuf,
+    /// Split on whitespace; quoting is not supported
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arguments: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip)]
+    pub origin: TargetOrigin,
+}
+
+/// Where a target comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TargetOrigin {
+    /// Added with `games targets --add`
+    #[default]
+    User,
+    /// A launch configuration in Steam's app info cache
+    Steam,
+    /// A mod manager installed for the game
+    ModLoader,
+}
+
+/// Targets of a game: the user's, then Steam's launch configurations, then
+/// its mod managers
+pub fn available(
+    user: &[LaunchTarget],
+    steam: Option<&SteamClient>,
+    game: &DetectedGame,
+    loaders: &[ModLoader],
+) -> Vec<LaunchTarget> {
+    let mut targets = user.to_vec();
+    let configs = match steam.filter(|_| game.source == GameSource::Steam) {
+        Some(steam) => steam.launch_configs(&game.id).unwrap_or_else(|e| {
+            log::warn!("failed to read Steam launch configurations: {:#}", e);
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    for config in configs.iter().filter(|c| c.runs_here()) {
+        let mut name = target_name(config);
+        if targets.iter().any(|t| t.name == name) {
+            if user.iter().any(|t| t.name == name) {
+                continue;
+            }
+            name = format!("{}-{}", name, config.key);
+        }
+        targets.push(LaunchTarget {
+            name,
+            executable: game.install_dir.join(&config.executable),
+            arguments: config
+                .arguments
+                .as_deref()
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            description: config.description.clone(),
+            origin: TargetOrigin::Steam,
+        });
+    }
+    for target in loaders.iter().filter_map(ModLoader::launch_target) {
+        if !targets.iter().any(|t| t.name == target.name) {
+            targets.push(target);
+        }
+    }
+    targets
+}
+
+/// Pick the target `wanted` names
+pub fn find<'a>(targets: &'a [LaunchTarget], wanted: &str) -> Result<&'a LaunchTarget> {
+    if let Some(target) = targets.iter().find(|t| t.name.eq_ignore_ascii_case(wanted)) {
+        return Ok(target);
+    }
+
+    let fragment = normalize(wanted);
+    let matches: Vec<&LaunchTarget> = targets
+        .iter()
+        .filter(|t| {
+            let stem = t.executable.file_stem().unwrap_or_default();
+            normalize(&t.name).contains(&fragment)
+                || normalize(&stem.to_string_lossy()).contains(&fragment)
+        })
+        .collect();
+    let names = |targets: &mut dyn Iterator<Item = &LaunchTarget>| {
+        targets
+            .map(|t| t.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match matches.as_slice() {
+        [target] => Ok(target),
+        [] if targets.is_empty() => {
+            bail!("no launch targets known; add one with 'nvproton games targets --add'")
+        }
+        [] => bail!(
+            "no launch target matches '{}' (available: {})",
+            wanted,
+            names(&mut targets.iter())
+        ),
+        _ => bail!(
+            "'{}' matches several launch targets: {}",
+            wanted,
+            names(&mut matches.iter().copied())
+        ),
+    }
+}
+
+/// Swap the game executable in a launcher-composed command for `target`'s
+///
+/// The first argument inside the install directory is the executable the
+/// launcher picked; the target's arguments follow the new executable.
+pub fn retarget(
+    command: &[String],
+    install_dir: &Path,
+    target: &LaunchTarget,
+) -> Result<Vec<String>> {
+    let Some(index) = command.iter().position(|arg| {
+        !install_dir.as_os_str().is_empty() && Path::new(arg).starts_with(install_dir)
+    }) else {
+        bail!(
+            "the launch command doesn't start an executable in {:?}",
+            install_dir
+        );
+    };
+    let mut retargeted = command[..index].to_vec();
+    retargeted.push(target.executable.to_string_lossy().into_owned());
+    retargeted.extend(target.arguments.iter().cloned());
+    retargeted.extend(command[index + 1..].iter().cloned());
+    Ok(retargeted)
+}
+
+/// Name of a Steam launch configuration: its description, else its type
+fn target_name(config: &LaunchConfig) -> String {
+    let label = config
+        .description
+        .as_deref()
+        .or(config.kind.as_deref())
+        .unwrap_or(&config.key);
+    let slug = label
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        config.key.clone()
+    } else {
+        slug
+    }
+}
+
+/// Compare names ignoring case and punctuation
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(name: &str, exe: &str) -> LaunchTarget {
+        LaunchTarget {
+            name: name.to_string(),
+            executable: PathBuf::from(exe),
+            arguments: Vec::new(),
+            description: None,
+            origin: TargetOrigin::User,
+        }
+    }
+
+    #[test]
+    fn test_find_and_retarget() {
+        let targets = vec![
+            target("play", "/games/BG3/bin/bg3.exe"),
+            target("directx-11", "/games/BG3/bin/bg3_dx11.exe"),
+            target("launcher", "/games/BG3/Launcher/LariLauncher.exe"),
+        ];
+        assert_eq!(find(&targets, "Play").unwrap().name, "play");
+        assert_eq!(find(&targets, "dx11").unwrap().name, "directx-11");
+        assert!(find(&targets, "bg3").is_err());
+        assert!(find(&targets, "vulkan").is_err());
+
+        let mut dx11 = targets[1].clone();
+        dx11.arguments = vec!["--skip-launcher".into()];
+        let command: Vec<String> = [
+            "/steam/Proton/proton",
+            "waitforexitandrun",
+            "/games/BG3/Launcher/LariLauncher.exe",
+            "-fullscreen",
+        ]
+        .map(String::from)
+        .into();
+        assert_eq!(
+            retarget(&command, Path::new("/games/BG3"), &dx11).unwrap(),
+            vec![
+                "/steam/Proton/proton",
+                "waitforexitandrun",
+                "/games/BG3/bin/bg3_dx11.exe",
+                "--skip-launcher",
+                "-fullscreen",
+            ]
+        );
+        assert!(retarget(&command, Path::new("/games/Hades"), &dx11).is_err());
+    }
+}
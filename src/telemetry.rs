@@ -0,0 +1,235 @@
+//! GPU telemetry sampling during game sessions
+//!
+//! Samples GPU temperature, power draw, clocks and VRAM through NVML once
+//! per second while a game runs. Raw samples are stored next to the session
+//! history; the session itself keeps a min/avg/max summary.
+
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::NvError;
+use crate::ffi;
+
+/// Interval between samples
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Characters used to draw sparklines, lowest to highest
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One telemetry sample
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TelemetrySample {
+    /// Seconds since the game was started
+    pub t: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temp_c: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub power_w: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_clock_mhz: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mem_clock_mhz: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vram_used_mb: Option<u64>,
+}
+
+/// Min/avg/max of a single metric
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MetricStats {
+    pub min: f32,
+    pub avg: f32,
+    pub max: f32,
+}
+
+impl MetricStats {
+    pub fn from_values(values: &[f32]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let avg = values.iter().sum::<f32>() / values.len() as f32;
+        Some(Self { min, avg, max })
+    }
+}
+
+/// Summary of a session's telemetry, stored in the session history
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TelemetrySummary {
+    pub samples: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temp_c: Option<MetricStats>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub power_w: Option<MetricStats>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_clock_mhz: Option<MetricStats>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vram_used_mb: Option<MetricStats>,
+}
+
+impl TelemetrySummary {
+    pub fn from_samples(samples: &[TelemetrySample]) -> Self {
+        Self {
+            samples: samples.len(),
+            temp_c: MetricStats::from_values(&series(samples, |s| s.temp_c.map(|v| v as f32))),
+            power_w: MetricStats::from_values(&series(samples, |s| s.power_w)),
+            gpu_clock_mhz: MetricStats::from_values(&series(samples, |s| {
+                s.gpu_clock_mhz.map(|v| v as f32)
+            })),
+            vram_used_mb: MetricStats::from_values(&series(samples, |s| {
+                s.vram_used_mb.map(|v| v as f32)
+            })),
+        }
+    }
+}
+
+/// Extract one metric from a list of samples
+pub fn series(
+    samples: &[TelemetrySample],
+    f: impl Fn(&TelemetrySample) -> Option<f32>,
+) -> Vec<f32> {
+    samples.iter().filter_map(f).collect()
+}
+
+/// Render values as a sparkline, downsampled to at most `width` characters
+pub fn sparkline(values: &[f32], width: usize) -> String {
+    if values.is_empty() || width == 0 {
+        return String::new();
+    }
+
+    // Average values into buckets so long sessions fit the terminal
+    let buckets = values.len().min(width);
+    let points: Vec<f32> = (0..buckets)
+        .map(|i| {
+            let start = i * values.len() / buckets;
+            let end = ((i + 1) * values.len() / buckets).max(start + 1);
+            let chunk = &values[start..end];
+            chunk.iter().sum::<f32>() / chunk.len() as f32
+        })
+        .collect();
+
+    let min = points.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = points.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let range = max - min;
+
+    points
+        .iter()
+        .map(|v| {
+            if range <= f32::EPSILON {
+                SPARK_CHARS[0]
+            } else {
+                let index = ((v - min) / range * (SPARK_CHARS.len() - 1) as f32).round();
+                SPARK_CHARS[index as usize]
+            }
+        })
+        .collect()
+}
+
+/// Samples NVML in a background thread while a game runs
+pub struct TelemetrySampler {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Vec<TelemetrySample>>,
+}
+
+impl TelemetrySampler {
+    /// Start sampling; fails if NVML can't be loaded
+    pub fn start() -> Result<Self> {
+        let nvml = ffi::load_nvml().context(NvError::Ffi("NVML".to_string()))?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = Arc::clone(&stop);
+        let handle = thread::spawn(move || {
+            let started = Instant::now();
+            let mut samples = Vec::new();
+            while !stop_flag.load(Ordering::Relaxed) {
+                let reading = nvml.read();
+                samples.push(TelemetrySample {
+                    t: started.elapsed().as_secs(),
+                    temp_c: reading.temperature_c,
+                    power_w: reading.power_mw.map(|mw| mw as f32 / 1000.0),
+                    gpu_clock_mhz: reading.graphics_clock_mhz,
+                    mem_clock_mhz: reading.memory_clock_mhz,
+                    vram_used_mb: reading.vram_used_bytes.map(|b| b / (1024 * 1024)),
+                });
+
+                // Sleep in short steps so stopping doesn't wait a full interval
+                let next = Instant::now() + SAMPLE_INTERVAL;
+                while Instant::now() < next && !stop_flag.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(50));
+                }
+            }
+            samples
+        });
+
+        Ok(Self { stop, handle })
+    }
+
+    /// Stop sampling and return the collected samples
+    pub fn stop(self) -> Vec<TelemetrySample> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.join().unwrap_or_default()
+    }
+}
+
+/// Write raw samples to disk
+pub fn save_samples(path: &Path, samples: &[TelemetrySample]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory {:?}", parent))?;
+    }
+    let yaml = serde_yaml::to_string(samples)?;
+    fs::write(path, yaml).with_context(|| format!("failed to write telemetry {:?}", path))
+}
+
+/// Read raw samples from disk
+pub fn load_samples(path: &Path) -> Result<Vec<TelemetrySample>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read telemetry {:?}", path))?;
+    serde_yaml::from_str(&content).with_context(|| format!("failed to parse telemetry {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparkline_range() {
+        let line = sparkline(&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0], 80);
+        assert_eq!(line, "▁▂▃▄▅▆▇█");
+    }
+
+    #[test]
+    fn test_sparkline_downsamples() {
+        let values: Vec<f32> = (0..1000).map(|v| v as f32).collect();
+        assert_eq!(sparkline(&values, 40).chars().count(), 40);
+        assert_eq!(sparkline(&[5.0, 5.0], 10), "▁▁");
+    }
+
+    #[test]
+    fn test_summary() {
+        let samples = vec![
+            TelemetrySample {
+                temp_c: Some(60),
+                power_w: Some(100.0),
+                ..Default::default()
+            },
+            TelemetrySample {
+                temp_c: Some(80),
+                ..Default::default()
+            },
+        ];
+        let summary = TelemetrySummary::from_samples(&samples);
+        assert_eq!(summary.samples, 2);
+        let temp = summary.temp_c.unwrap();
+        assert_eq!((temp.min, temp.avg, temp.max), (60.0, 70.0, 80.0));
+        assert_eq!(summary.power_w.unwrap().avg, 100.0);
+        assert!(summary.gpu_clock_mhz.is_none());
+    }
+}
@@ -0,0 +1,186 @@
+//! Placeholders in profile values
+//!
+//! String values in a profile may reference the game being launched, expanded
+//! when the profile is resolved for that game:
+//!
+//! ```yaml
+//! env:
+//!   DXVK_STATE_CACHE_PATH: ${cache.dxvk}
+//!   DXVK_LOG_PATH: ${home}/logs/${game.id}
+//! ```
+//!
+//! | Placeholder | Value |
+//! |-------------|-------|
+//! | `${game.id}`, `${game.name}`, `${game.dir}` | Game ID, name and install directory |
+//! | `${cache.dxvk}`, `${cache.vkd3d}`, `${cache.gl}`, `${cache.mesa}` | Per-game shader cache directories (created when used) |
+//! | `${home}` | Home directory |
+//! | `${gpu.index}` | Vulkan device index of the NVIDIA GPU |
+//!
+//! `$${` produces a literal `${`.
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use anyhow::{Result, bail};
+use serde_yaml::Value;
+
+use crate::cache::{CachePaths, CacheType};
+use crate::detection::{DetectedGame, VulkanCapabilities};
+
+const CACHE_PLACEHOLDERS: &[(&str, CacheType)] = &[
+    ("cache.dxvk", CacheType::Dxvk),
+    ("cache.vkd3d", CacheType::Vkd3d),
+    ("cache.gl", CacheType::NvidiaGl),
+    ("cache.mesa", CacheType::Mesa),
+];
+
+/// Values available to placeholders for one game
+#[derive(Debug, Clone, Default)]
+pub struct TemplateContext {
+    vars: BTreeMap<String, String>,
+}
+
+impl TemplateContext {
+    pub fn for_game(game: &DetectedGame, vulkan: Option<&VulkanCapabilities>) -> Self {
+        let mut vars = BTreeMap::new();
+        vars.insert("game.id".into(), game.id.clone());
+        vars.insert("game.name".into(), game.name.clone());
+        vars.insert(
+            "game.dir".into(),
+            game.install_dir.to_string_lossy().to_string(),
+        );
+
+        let cache = CachePaths::new();
+        for (name, cache_type) in CACHE_PLACEHOLDERS {
+            vars.insert(
+                name.to_string(),
+                cache
+                    .for_game(*cache_type, &game.game_id())
+                    .to_string_lossy()
+                    .to_string(),
+            );
+        }
+
+        if let Some(home) = dirs::home_dir() {
+            vars.insert("home".into(), home.to_string_lossy().to_string());
+        }
+        // Without Vulkan detection the first device is the best guess
+        let gpu_index = vulkan.map(|caps| caps.device_index).unwrap_or(0);
+        vars.insert("gpu.index".into(), gpu_index.to_string());
+
+        Self { vars }
+    }
+
+    /// Placeholder names and their values
+    pub fn vars(&self) -> &BTreeMap<String, String> {
+        &self.vars
+    }
+
+    /// Expand the placeholders in one value
+    pub fn expand(&self, value: &str) -> Result<String> {
+        let mut out = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(start) = rest.find('$') {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            if let Some(escaped) = after.strip_prefix("${") {
+                out.push_str("${");
+                rest = escaped;
+                continue;
+            }
+            let Some(inner) = after.strip_prefix('{') else {
+                out.push('$');
+                rest = after;
+                continue;
+            };
+            let Some(end) = inner.find('}') else {
+                bail!("unterminated placeholder in '{}'", value);
+            };
+            let name = inner[..end].trim();
+            let Some(replacement) = self.vars.get(name) else {
+                bail!(
+                    "unknown placeholder '${{{}}}' (available: {})",
+                    name,
+                    self.vars.keys().cloned().collect::<Vec<_>>().join(", ")
+                );
+            };
+            if name.starts_with("cache.")
+                && let Err(e) = fs::create_dir_all(replacement)
+            {
+                log::warn!("failed to create {}: {}", replacement, e);
+            }
+            out.push_str(replacement);
+            rest = &inner[end + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    /// Expand the placeholders in every string of resolved profile settings
+    pub fn expand_settings(&self, settings: &mut Value) -> Result<()> {
+        match settings {
+            Value::String(s) if s.contains('$') => *s = self.expand(s)?,
+            Value::Sequence(items) => {
+                for item in items {
+                    self.expand_settings(item)?;
+                }
+            }
+            Value::Mapping(map) => {
+                for (_, value) in map.iter_mut() {
+                    self.expand_settings(value)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn context() -> TemplateContext {
+        let game = DetectedGame {
+            source: crate::detection::GameSource::Unknown,
+            id: "1091500".into(),
+            name: "Cyberpunk 2077".into(),
+            install_dir: PathBuf::from("/games/cp2077"),
+            executable: None,
+            fingerprint: None,
+            metadata: HashMap::new(),
+        };
+        let mut context = TemplateContext::for_game(&game, None);
+        context.vars.insert("home".into(), "/home/user".into());
+        context
+    }
+
+    #[test]
+    fn test_expand_placeholders() {
+        let context = context();
+        assert_eq!(
+            context.expand("${home}/logs/${game.id}").unwrap(),
+            "/home/user/logs/1091500"
+        );
+        assert_eq!(context.expand("${gpu.index}").unwrap(), "0");
+        assert_eq!(
+            context.expand("$HOME $${game.id} 100$").unwrap(),
+            "$HOME ${game.id} 100$"
+        );
+        assert!(context.expand("${game.idd}").is_err());
+        assert!(context.expand("${game.id").is_err());
+    }
+
+    #[test]
+    fn test_expand_settings() {
+        let context = context();
+        let mut settings: Value = serde_yaml::from_str(
+            "env:\n  DXVK_LOG_PATH: ${game.dir}/logs\nwrappers:\n  - mangohud\n",
+        )
+        .unwrap();
+        context.expand_settings(&mut settings).unwrap();
+        assert_eq!(settings["env"]["DXVK_LOG_PATH"], "/games/cp2077/logs");
+    }
+}
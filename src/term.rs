@@ -0,0 +1,412 @@
+//! Terminal tables and colors
+//!
+//! Text output shares one look: tables with a bold header row and columns
+//! sized to their widest cell, and four tones for values that need a
+//! glance: green for good, yellow for warnings, red for failures and dim
+//! for secondary details.
+//!
+//! Colors follow `--color`: `auto` (the default) colors output to a
+//! terminal unless `NO_COLOR` is set or `TERM` is `dumb`; `always` and
+//! `never` override that.
+//!
+//! Long operations (scans, sizing, copies) draw a progress line on stderr
+//! while it is a terminal, and clear it when they are done.
+
+use std::env;
+use std::fmt::{self, Display};
+use std::io::{self, IsTerminal, Write};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::cache::format_bytes;
+use crate::cli::ColorChoice;
+
+static COLOR: OnceLock<bool> = OnceLock::new();
+
+/// Gap between table columns
+const COLUMN_GAP: &str = "  ";
+
+/// Width of progress bars
+const BAR_WIDTH: usize = 24;
+
+/// Minimum time between progress redraws
+const REDRAW_INTERVAL: Duration = Duration::from_millis(80);
+
+/// Decide once whether output is colored
+pub fn init(choice: ColorChoice) {
+    let _ = COLOR.set(resolve(choice, no_color()));
+}
+
+fn no_color() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+fn resolve(choice: ColorChoice, no_color: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            !no_color
+                && env::var("TERM").map_or(true, |term| term != "dumb")
+                && io::stdout().is_terminal()
+        }
+    }
+}
+
+fn colors_enabled() -> bool {
+    *COLOR.get_or_init(|| resolve(ColorChoice::Auto, no_color()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    Good,
+    Warn,
+    Bad,
+    Dim,
+    Bold,
+}
+
+impl Tone {
+    fn code(self) -> &'static str {
+        match self {
+            Tone::Good => "32",
+            Tone::Warn => "33",
+            Tone::Bad => "31",
+            Tone::Dim => "2",
+            Tone::Bold => "1",
+        }
+    }
+}
+
+/// Text in a tone, when colors are enabled
+pub fn paint(text: impl Display, tone: Tone) -> String {
+    paint_if(colors_enabled(), text, tone)
+}
+
+fn paint_if(enabled: bool, text: impl Display, tone: Tone) -> String {
+    if enabled {
+        format!("\x1b[{}m{}\x1b[0m", tone.code(), text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// One table cell, optionally in a tone
+#[derive(Debug, Clone)]
+pub struct Cell {
+    text: String,
+    tone: Option<Tone>,
+}
+
+impl Cell {
+    pub fn toned(text: impl Display, tone: Tone) -> Self {
+        Self {
+            text: text.to_string(),
+            tone: Some(tone),
+        }
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Self { text, tone: None }
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        text.to_string().into()
+    }
+}
+
+/// Column alignment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// A table printed with aligned columns
+#[derive(Debug, Clone)]
+pub struct Table {
+    headers: Vec<String>,
+    align: Vec<Align>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub fn new<S: Into<String>>(headers: impl IntoIterator<Item = S>) -> Self {
+        let headers: Vec<String> = headers.into_iter().map(Into::into).collect();
+        Self {
+            align: vec![Align::Left; headers.len()],
+            headers,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Right-align these columns (sizes, counts, durations)
+    pub fn right(mut self, columns: &[usize]) -> Self {
+        for &column in columns {
+            self.align[column] = Align::Right;
+        }
+        self
+    }
+
+    pub fn row<C: Into<Cell>>(&mut self, cells: impl IntoIterator<Item = C>) {
+        self.rows.push(cells.into_iter().map(Into::into).collect());
+    }
+
+    fn render(&self, f: &mut fmt::Formatter<'_>, colors: bool) -> fmt::Result {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.text.chars().count());
+            }
+        }
+
+        let header: Vec<Cell> = self
+            .headers
+            .iter()
+            .map(|h| Cell::toned(h, Tone::Bold))
+            .collect();
+        self.render_row(f, &header, &widths, colors)?;
+        let total = widths.iter().sum::<usize>() + COLUMN_GAP.len() * (widths.len() - 1);
+        writeln!(f, "{}", "-".repeat(total))?;
+        for row in &self.rows {
+            self.render_row(f, row, &widths, colors)?;
+        }
+        Ok(())
+    }
+
+    fn render_row(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        row: &[Cell],
+        widths: &[usize],
+        colors: bool,
+    ) -> fmt::Result {
+        let mut line = String::new();
+        for (index, (cell, width)) in row.iter().zip(widths).enumerate() {
+            let pad = " ".repeat(width - cell.text.chars().count());
+            let last = index + 1 == row.len();
+            let text = match cell.tone {
+                Some(tone) => paint_if(colors, &cell.text, tone),
+                None => cell.text.clone(),
+            };
+            if index > 0 {
+                line.push_str(COLUMN_GAP);
+            }
+            match self.align[index] {
+                Align::Right => line.push_str(&format!("{}{}", pad, text)),
+                // No trailing spaces after the last column
+                Align::Left if last => line.push_str(&text),
+                Align::Left => line.push_str(&format!("{}{}", text, pad)),
+            }
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render(f, colors_enabled())
+    }
+}
+
+/// A progress line on stderr, cleared when dropped
+///
+/// Updates take `&self`, so worker threads can share one.
+#[derive(Debug)]
+pub struct Progress {
+    visible: bool,
+    state: Mutex<ProgressState>,
+}
+
+#[derive(Debug, Default)]
+struct ProgressState {
+    label: String,
+    done: u64,
+    /// 0 while unknown
+    total: u64,
+    bytes: bool,
+    detail: String,
+    drawn: Option<Instant>,
+}
+
+impl ProgressState {
+    fn line(&self, width: usize) -> String {
+        let amount = |n| match self.bytes {
+            true => format_bytes(n),
+            false => n.to_string(),
+        };
+        let filled = (BAR_WIDTH as u64 * self.done.min(self.total)).checked_div(self.total);
+        let mut line = if let Some(filled) = filled {
+            let filled = filled as usize;
+            format!(
+                "{} [{}{}] {}/{}",
+                self.label,
+                "#".repeat(filled),
+                "-".repeat(BAR_WIDTH - filled),
+                amount(self.done),
+                amount(self.total)
+            )
+        } else {
+            format!("{} {}", self.label, amount(self.done))
+        };
+        if !self.detail.is_empty() {
+            line.push_str("  ");
+            line.push_str(&self.detail);
+        }
+        line.chars().take(width).collect()
+    }
+}
+
+impl Progress {
+    /// Progress counted in items
+    pub fn new(label: impl Into<String>) -> Self {
+        Self::with_state(ProgressState {
+            label: label.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Progress counted in bytes
+    pub fn bytes(label: impl Into<String>, total: u64) -> Self {
+        Self::with_state(ProgressState {
+            label: label.into(),
+            total,
+            bytes: true,
+            ..Default::default()
+        })
+    }
+
+    /// Progress that is never drawn
+    pub fn hidden() -> Self {
+        Self {
+            visible: false,
+            state: Mutex::default(),
+        }
+    }
+
+    fn with_state(state: ProgressState) -> Self {
+        let visible = io::stderr().is_terminal() && env::var("TERM").map_or(true, |t| t != "dumb");
+        Self {
+            visible,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Expect `n` more items (or bytes)
+    pub fn add_total(&self, n: u64) {
+        self.update(|state| state.total += n);
+    }
+
+    /// Count `n` items done, showing what is being worked on
+    pub fn inc(&self, n: u64, detail: impl Display) {
+        self.update(|state| {
+            state.done += n;
+            state.detail = detail.to_string();
+        });
+    }
+
+    /// Show what is being worked on without counting it
+    pub fn set_detail(&self, detail: impl Display) {
+        self.update(|state| state.detail = detail.to_string());
+    }
+
+    /// Items (or bytes) done and expected so far
+    #[cfg(test)]
+    pub fn position(&self) -> (u64, u64) {
+        let state = self.state.lock().unwrap();
+        (state.done, state.total)
+    }
+
+    fn update(&self, change: impl FnOnce(&mut ProgressState)) {
+        let mut state = self.state.lock().unwrap();
+        change(&mut state);
+        if !self.visible {
+            return;
+        }
+        let now = Instant::now();
+        if state
+            .drawn
+            .is_some_and(|drawn| now.duration_since(drawn) < REDRAW_INTERVAL)
+        {
+            return;
+        }
+        state.drawn = Some(now);
+        let width = env::var("COLUMNS")
+            .ok()
+            .and_then(|columns| columns.parse().ok())
+            .unwrap_or(80usize);
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K{}", state.line(width.saturating_sub(1)));
+        let _ = stderr.flush();
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        let drawn = self
+            .state
+            .get_mut()
+            .is_ok_and(|state| state.drawn.is_some());
+        if self.visible && drawn {
+            let _ = write!(io::stderr(), "\r\x1b[2K");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Rendered<'a>(&'a Table, bool);
+
+    impl Display for Rendered<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.render(f, self.1)
+        }
+    }
+
+    #[test]
+    fn test_tables_colors_and_progress() {
+        let mut table = Table::new(["ID", "Size", "Name"]).right(&[1]);
+        table.row(["1145360".to_string(), "12.1 GiB".into(), "Hades".into()]);
+        table.row(vec![
+            Cell::from("witcher3"),
+            Cell::from("980 MiB"),
+            Cell::toned("The Witcher 3", Tone::Bad),
+        ]);
+        assert_eq!(
+            Rendered(&table, false).to_string(),
+            "ID            Size  Name\n\
+             ---------------------------------\n\
+             1145360   12.1 GiB  Hades\n\
+             witcher3   980 MiB  The Witcher 3\n"
+        );
+        // Padding ignores the escape codes
+        let colored = Rendered(&table, true).to_string();
+        assert!(colored.contains("witcher3   980 MiB  \x1b[31mThe Witcher 3\x1b[0m\n"));
+        assert!(colored.starts_with("\x1b[1mID\x1b[0m        "));
+
+        assert!(!resolve(ColorChoice::Auto, true));
+        assert!(resolve(ColorChoice::Always, true));
+        assert!(!resolve(ColorChoice::Never, false));
+
+        let mut state = ProgressState {
+            label: "Steam".into(),
+            done: 3,
+            total: 4,
+            detail: "Hades".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            state.line(80),
+            "Steam [##################------] 3/4  Hades"
+        );
+        assert_eq!(state.line(5), "Steam");
+        state.total = 0;
+        assert_eq!(state.line(80), "Steam 3  Hades");
+    }
+}
@@ -0,0 +1,236 @@
+//! Game process tracking
+//!
+//! Some launchers hand the game off and return right away (`heroic --no-gui`
+//! passes the launch to a running Heroic, `steam -applaunch` to the running
+//! client), so the launcher's exit says nothing about the game. The game's
+//! processes are found in `/proc` instead: a process belongs to the game when
+//! its working directory, executable or one of its arguments lies inside the
+//! install directory, or when Steam started it for the game's AppID
+//! (`SteamAppId` / `SteamGameId` in its environment). Wine arguments like
+//! `Z:\home\me\Games\Hades\Hades.exe` are mapped back to Unix paths.
+//!
+//! The game is not a child of nvproton, so only the fact that it ran and for
+//! how long is known, not its exit status.
+//!
+//! Processes outside nvproton's user are unreadable and ignored, as are
+//! nvproton itself and its ancestors (e.g. a shell started in the game dir).
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::detection::{DetectedGame, GameSource};
+
+/// Polling interval while waiting for the game to appear and to exit
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What `/proc` tells about a process
+#[derive(Debug, Clone, Default)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub cwd: Option<PathBuf>,
+    pub exe: Option<PathBuf>,
+    pub cmdline: Vec<String>,
+    /// `SteamAppId` (or `SteamGameId`) from the environment
+    pub steam_app_id: Option<String>,
+}
+
+impl ProcessInfo {
+    fn read(pid: u32) -> Option<Self> {
+        let dir = PathBuf::from(format!("/proc/{}", pid));
+        let cmdline = fs::read(dir.join("cmdline")).ok()?;
+        let environ = fs::read(dir.join("environ")).unwrap_or_default();
+        Some(Self {
+            pid,
+            cwd: fs::read_link(dir.join("cwd")).ok(),
+            exe: fs::read_link(dir.join("exe")).ok(),
+            cmdline: cmdline
+                .split(|&b| b == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect(),
+            steam_app_id: steam_app_id(&environ),
+        })
+    }
+}
+
+/// AppID Steam exported to a process, from its NUL-separated environment
+fn steam_app_id(environ: &[u8]) -> Option<String> {
+    let vars: Vec<_> = environ
+        .split(|&b| b == 0)
+        .filter_map(|var| std::str::from_utf8(var).ok()?.split_once('='))
+        .collect();
+    ["SteamAppId", "SteamGameId"].iter().find_map(|name| {
+        vars.iter()
+            .find(|(key, value)| key == name && !value.is_empty() && *value != "0")
+            .map(|(_, value)| value.to_string())
+    })
+}
+
+/// Recognizes the processes of one game
+#[derive(Debug, Clone)]
+pub struct GameMatcher {
+    install_dir: PathBuf,
+    /// Steam AppID of a Steam game
+    app_id: Option<String>,
+}
+
+impl GameMatcher {
+    pub fn for_game(game: &DetectedGame) -> Self {
+        Self {
+            install_dir: game.install_dir.clone(),
+            app_id: (game.source == GameSource::Steam).then(|| game.id.clone()),
+        }
+    }
+
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        if self.app_id.is_some() && process.steam_app_id == self.app_id {
+            return true;
+        }
+        // An empty install dir would match every relative path
+        if self.install_dir.as_os_str().is_empty() {
+            return false;
+        }
+        let inside = |path: &Path| path.starts_with(&self.install_dir);
+        process.cwd.as_deref().is_some_and(inside)
+            || process.exe.as_deref().is_some_and(inside)
+            || process.cmdline.iter().any(|arg| inside(&unix_path(arg)))
+    }
+}
+
+/// Map a Wine path on the `Z:` drive (the Unix root) back to a Unix path
+fn unix_path(arg: &str) -> PathBuf {
+    match arg.get(..3) {
+        Some(drive) if drive.eq_ignore_ascii_case("z:\\") => {
+            PathBuf::from(arg[2..].replace('\\', "/"))
+        }
+        _ => PathBuf::from(arg),
+    }
+}
+
+/// nvproton's own PID and those of its ancestors
+fn own_lineage() -> HashSet<u32> {
+    let mut pids = HashSet::new();
+    let mut pid = std::process::id();
+    while pid > 1 && pids.insert(pid) {
+        let Some(parent) = fs::read_to_string(format!("/proc/{}/stat", pid))
+            .ok()
+            .and_then(|stat| parent_pid(&stat))
+        else {
+            break;
+        };
+        pid = parent;
+    }
+    pids
+}
+
+/// Parent PID from `/proc/<pid>/stat`; the command name may contain spaces and
+/// parentheses, so fields are counted from the last `)`
+fn parent_pid(stat: &str) -> Option<u32> {
+    let rest = &stat[stat.rfind(')')? + 1..];
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// PIDs of the running processes that belong to the game
+pub fn game_processes(matcher: &GameMatcher) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let lineage = own_lineage();
+    entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| !lineage.contains(pid))
+        .filter_map(ProcessInfo::read)
+        .filter(|process| matcher.matches(process))
+        .map(|process| process.pid)
+        .collect()
+}
+
+/// How waiting for a handed-off game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tracked {
+    /// No game process showed up in time, or the launcher gave up
+    NotStarted,
+    /// The game ran for the given time (from when its processes were first
+    /// seen) and all of them have exited
+    Exited(Duration),
+}
+
+/// Wait up to `start_timeout` for the game's processes to appear, then until
+/// they are all gone
+///
+/// `launcher_failed` is polled while waiting for the start, so a launcher
+/// that exits with an error ends the wait early.
+pub fn wait_for_game(
+    matcher: &GameMatcher,
+    start_timeout: Duration,
+    mut launcher_failed: impl FnMut() -> bool,
+) -> Tracked {
+    let deadline = Instant::now() + start_timeout;
+    loop {
+        if !game_processes(matcher).is_empty() {
+            break;
+        }
+        if Instant::now() >= deadline || launcher_failed() {
+            return Tracked::NotStarted;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    log::info!("game processes found, waiting for them to exit");
+    let found = Instant::now();
+    while !game_processes(matcher).is_empty() {
+        thread::sleep(POLL_INTERVAL);
+    }
+    Tracked::Exited(found.elapsed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_game_processes() {
+        let matcher = GameMatcher {
+            install_dir: PathBuf::from("/games/Hades"),
+            app_id: Some("1145360".into()),
+        };
+        let wine = ProcessInfo {
+            pid: 2,
+            cwd: Some("/home/me".into()),
+            exe: Some("/opt/proton/files/bin/wine64-preloader".into()),
+            cmdline: vec!["Z:\\games\\Hades\\x64\\Hades.exe".into()],
+            ..ProcessInfo::default()
+        };
+        assert!(matcher.matches(&wine));
+        let native = ProcessInfo {
+            pid: 3,
+            cwd: Some("/games/Hades/x64".into()),
+            ..ProcessInfo::default()
+        };
+        assert!(matcher.matches(&native));
+        // Sibling directories sharing the prefix don't count
+        let other = ProcessInfo {
+            pid: 4,
+            exe: Some("/games/Hades II/Hades2".into()),
+            ..ProcessInfo::default()
+        };
+        assert!(!matcher.matches(&other));
+
+        // Steam's reaper and the Proton processes carry the AppID
+        let environ = b"HOME=/home/me\0SteamGameId=0\0SteamAppId=1145360\0";
+        let reaper = ProcessInfo {
+            pid: 5,
+            steam_app_id: steam_app_id(environ),
+            ..ProcessInfo::default()
+        };
+        assert_eq!(reaper.steam_app_id.as_deref(), Some("1145360"));
+        assert!(matcher.matches(&reaper));
+
+        assert_eq!(
+            parent_pid("1234 (Web Content (x)) S 987 1234 1234 0"),
+            Some(987)
+        );
+    }
+}
@@ -0,0 +1,357 @@
+//! Crash triage reports
+//!
+//! When a game exits with an error (or a direct launch dies within a few
+//! seconds), nvproton gathers everything needed for a bug report into a
+//! single YAML file under the state directory:
+//! - Driver, GPU and Proton versions
+//! - The launch command and environment
+//! - Recent NVIDIA Xid errors from the kernel log
+//! - Tails of DXVK, vkd3d-proton and Proton logs
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{OutputFormat, ReportArgs, ReportCommand};
+use crate::config::{ConfigManager, ConfigPaths, NvConfig};
+use crate::detection::{DetectedGame, GameSource};
+use crate::logs::{self, LogAnalysis};
+use crate::paths::sanitize;
+use crate::reporting;
+use crate::runner::RunContext;
+use crate::xid::{self, XidError};
+
+/// Direct launches that exit faster than this are treated as crashes
+const QUICK_EXIT_SECS: u64 = 10;
+
+/// Number of lines kept from the end of each log
+const LOG_TAIL_LINES: usize = 50;
+
+/// Number of Xid errors kept from the kernel log
+const XID_LIMIT: usize = 20;
+
+/// Everything gathered about a failed launch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriageReport {
+    pub game_id: String,
+    pub game_name: String,
+    pub source: GameSource,
+    /// Unix timestamp the report was created at
+    pub created_at: u64,
+    pub exit_code: Option<i32>,
+    pub runtime_secs: u64,
+    pub driver_version: Option<String>,
+    pub gpu_name: Option<String>,
+    pub proton_version: Option<String>,
+    pub launch_command: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub xid_errors: Vec<XidError>,
+    /// Log file path -> last lines of the log
+    pub log_tails: BTreeMap<String, Vec<String>>,
+    pub proton_log: Option<LogAnalysis>,
+}
+
+/// Whether a finished launch should produce a triage report
+///
+/// Lutris hands the game off to its client and returns immediately, so the
+/// quick-exit heuristic only applies to direct launches, legendary, which
+/// waits for the game, and Steam and Heroic, whose game processes are tracked.
+pub fn is_crash(success: bool, runtime: Duration, source: &GameSource) -> bool {
+    !success || died_early(runtime, Duration::from_secs(QUICK_EXIT_SECS), source)
+}
+
+/// Whether the game exited within `window` of the launch (never true for
+/// Lutris, whose launcher returns before the game exits)
+pub fn died_early(runtime: Duration, window: Duration, source: &GameSource) -> bool {
+    !matches!(source, GameSource::Lutris) && runtime < window
+}
+
+impl TriageReport {
+    /// Collect a report for a launch that just finished
+    pub fn collect(
+        ctx: &RunContext,
+        game: &DetectedGame,
+        launch_command: &[String],
+        env_vars: &HashMap<String, String>,
+        exit_code: Option<i32>,
+        runtime: Duration,
+    ) -> Self {
+        let proton_log = logs::proton_log_path(&game.id, env_vars)
+            .filter(|p| p.exists())
+            .and_then(|p| logs::analyze_file(&p).ok());
+
+        Self {
+            game_id: game.id.clone(),
+            game_name: game.name.clone(),
+            source: game.source.clone(),
+            created_at: now(),
+            exit_code,
+            runtime_secs: runtime.as_secs(),
+            driver_version: ctx.vulkan_caps.as_ref().map(|c| c.driver_version.clone()),
+            gpu_name: ctx.vulkan_caps.as_ref().map(|c| c.gpu_name.clone()),
+            proton_version: ctx.proton_nv.as_ref().map(|p| p.version.clone()),
+            launch_command: launch_command.to_vec(),
+            env: env_vars.clone().into_iter().collect(),
+            xid_errors: xid::recent_errors(XID_LIMIT),
+            log_tails: collect_log_tails(game, env_vars),
+            proton_log,
+        }
+    }
+
+    /// Write the report into the state directory, returning its path
+    pub fn save(&self, paths: &ConfigPaths) -> Result<PathBuf> {
+        let dir = reports_dir(paths);
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create reports directory {:?}", dir))?;
+        let path = dir.join(format!(
+            "{}-{}.yaml",
+            sanitize(&self.game_id),
+            self.created_at
+        ));
+        let yaml = serde_yaml::to_string(self)?;
+        fs::write(&path, yaml).with_context(|| format!("failed to write report {:?}", path))?;
+        Ok(path)
+    }
+
+    /// Load a report from disk
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read report {:?}", path))?;
+        serde_yaml::from_str(&content).with_context(|| format!("failed to parse report {:?}", path))
+    }
+
+    /// Render the report as human-readable text
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let mut line = |s: String| {
+            out.push_str(&s);
+            out.push('\n');
+        };
+
+        line(format!(
+            "Crash report: {} ({}:{})",
+            self.game_name, self.source, self.game_id
+        ));
+        line(format!("  Created: {} (unix)", self.created_at));
+        line(format!(
+            "  Exit code: {}",
+            self.exit_code
+                .map_or("signal".to_string(), |c| c.to_string())
+        ));
+        line(format!("  Runtime: {}s", self.runtime_secs));
+        line(format!(
+            "  GPU: {}",
+            self.gpu_name.as_deref().unwrap_or("unknown")
+        ));
+        line(format!(
+            "  Driver: {}",
+            self.driver_version.as_deref().unwrap_or("unknown")
+        ));
+        line(format!(
+            "  Proton: {}",
+            self.proton_version.as_deref().unwrap_or("unknown")
+        ));
+
+        line(String::new());
+        line("Launch command:".to_string());
+        line(format!("  {}", self.launch_command.join(" ")));
+
+        line(String::new());
+        line("Environment:".to_string());
+        for (key, value) in &self.env {
+            line(format!("  {}={}", key, value));
+        }
+
+        line(String::new());
+        if self.xid_errors.is_empty() {
+            line("Xid errors: none".to_string());
+        } else {
+            line("Xid errors:".to_string());
+            for xid in &self.xid_errors {
+                line(format!("  Xid {} - {}", xid.code, xid.description()));
+                line(format!("    {}", xid.message));
+            }
+        }
+
+        if let Some(ref analysis) = self.proton_log {
+            line(String::new());
+            line(format!("Proton log findings: {}", analysis.findings.len()));
+            for finding in &analysis.findings {
+                line(format!("  [{}] {}", finding.id, finding.description));
+                line(format!("    hint: {}", finding.hint));
+            }
+        }
+
+        for (path, tail) in &self.log_tails {
+            line(String::new());
+            line(format!("--- {} (last {} lines) ---", path, tail.len()));
+            for l in tail {
+                line(l.clone());
+            }
+        }
+
+        out
+    }
+}
+
+/// Directory triage reports are stored in
+pub fn reports_dir(paths: &ConfigPaths) -> PathBuf {
+    paths.state_dir.join("reports")
+}
+
+/// List stored reports for a game, oldest first
+pub fn list_reports(paths: &ConfigPaths, game_id: &str) -> Result<Vec<PathBuf>> {
+    let dir = reports_dir(paths);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let prefix = format!("{}-", sanitize(game_id));
+    let mut reports: Vec<(u64, PathBuf)> = fs::read_dir(&dir)
+        .with_context(|| format!("failed to read reports directory {:?}", dir))?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let timestamp = name
+                .strip_prefix(&prefix)?
+                .strip_suffix(".yaml")?
+                .parse()
+                .ok()?;
+            Some((timestamp, e.path()))
+        })
+        .collect();
+    reports.sort();
+    Ok(reports.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Handle the `report` command
+pub fn handle_report(args: ReportArgs, manager: &ConfigManager, config: &NvConfig) -> Result<()> {
+    if let Some(ReportCommand::Share(share_args)) = args.command {
+        return reporting::share_report(manager, config, &share_args.game_id);
+    }
+    let game_id = args.game_id.unwrap_or_default();
+    let reports = list_reports(manager.paths(), &game_id)?;
+
+    if args.list {
+        if reports.is_empty() {
+            println!("No reports for {}", game_id);
+        }
+        for path in &reports {
+            println!("{}", path.display());
+        }
+        return Ok(());
+    }
+
+    let Some(latest) = reports.last() else {
+        bail!("no crash reports found for '{}'", game_id);
+    };
+    let report = TriageReport::load(latest)?;
+
+    let rendered = match args.format {
+        OutputFormat::Text => report.to_text(),
+        OutputFormat::Json => serde_json::to_string_pretty(&report)?,
+        OutputFormat::Yaml => serde_yaml::to_string(&report)?,
+    };
+
+    match args.output {
+        Some(output) => {
+            fs::write(&output, rendered)
+                .with_context(|| format!("failed to write report to {}", output))?;
+            println!("Report written to {}", output);
+        }
+        None => print!("{}", rendered),
+    }
+
+    Ok(())
+}
+
+/// Gather DXVK, vkd3d-proton and Proton log tails
+fn collect_log_tails(
+    game: &DetectedGame,
+    env_vars: &HashMap<String, String>,
+) -> BTreeMap<String, Vec<String>> {
+    let mut candidates: Vec<PathBuf> = Vec::new();
+
+    // DXVK writes <exe>_d3d11.log / <exe>_dxgi.log next to the executable
+    // unless DXVK_LOG_PATH points elsewhere
+    let dxvk_dir = env_vars
+        .get("DXVK_LOG_PATH")
+        .map(PathBuf::from)
+        .or_else(|| {
+            game.executable
+                .as_ref()
+                .and_then(|e| e.parent().map(Path::to_path_buf))
+        });
+    if let Some(dir) = dxvk_dir
+        && let Ok(entries) = fs::read_dir(&dir)
+    {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.ends_with(".log") && (name.contains("_d3d") || name.contains("_dxgi")) {
+                candidates.push(entry.path());
+            }
+        }
+    }
+
+    if let Some(path) = env_vars.get("VKD3D_LOG_FILE") {
+        candidates.push(PathBuf::from(path));
+    }
+
+    if let Some(path) = logs::proton_log_path(&game.id, env_vars) {
+        candidates.push(path);
+    }
+
+    candidates
+        .into_iter()
+        .filter_map(|path| {
+            let tail = tail_file(&path, LOG_TAIL_LINES)?;
+            Some((path.display().to_string(), tail))
+        })
+        .collect()
+}
+
+/// Read the last `n` lines of a file
+fn tail_file(path: &Path, n: usize) -> Option<Vec<String>> {
+    let bytes = fs::read(path).ok()?;
+    let content = String::from_utf8_lossy(&bytes);
+    let lines: Vec<&str> = content.lines().collect();
+    let skip = lines.len().saturating_sub(n);
+    Some(lines[skip..].iter().map(|l| l.to_string()).collect())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_crash() {
+        let quick = Duration::from_secs(2);
+        let long = Duration::from_secs(600);
+        assert!(is_crash(false, long, &GameSource::Steam));
+        assert!(is_crash(true, quick, &GameSource::Unknown));
+        assert!(is_crash(true, quick, &GameSource::Steam));
+        assert!(!is_crash(true, quick, &GameSource::Lutris));
+        assert!(!is_crash(true, long, &GameSource::Unknown));
+        let window = Duration::from_secs(900);
+        assert!(died_early(long, window, &GameSource::Heroic));
+    }
+
+    #[test]
+    fn test_tail_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("game_d3d11.log");
+        fs::write(&path, "a\nb\nc\nd\n").unwrap();
+        assert_eq!(tail_file(&path, 2).unwrap(), vec!["c", "d"]);
+    }
+}
@@ -0,0 +1,351 @@
+//! umu-launcher integration
+//!
+//! With `run --umu` (or `umu.enabled`), Heroic, Lutris and manually added
+//! games start their executable through `umu-run` instead of their launcher,
+//! so protonfixes applies the game's fixes as it does for Steam. Native
+//! Linux games keep their usual launch. umu is configured through the
+//! environment:
+//! - GAMEID: the `umu.game_ids` entry (by `source:id`), the umu-database row
+//!   for the store and codename (`umu.database`), or `umu-default` (no fixes)
+//! - STORE: egs, gog or amazon for Heroic games, none otherwise
+//! - WINEPREFIX: the launcher's prefix, or `<state>/prefixes/<source>-<id>`
+//! - PROTONPATH: `umu.proton`, or the Proton build Heroic is set to use
+//!
+//! Variables already set by the profile or config are left alone.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::config::{ConfigPaths, NvConfig};
+use crate::detection::pe::PeInfo;
+use crate::detection::{DetectedGame, GameDatabase, GameId, GameSource};
+use crate::disk_usage::heroic_prefix;
+use crate::paths::{in_path, sanitize};
+use crate::runtimes::{heroic_wine_version, lutris_game_value};
+
+/// GAMEID umu uses when a game has no entry in the umu database
+const DEFAULT_GAME_ID: &str = "umu-default";
+
+/// Whether a launch goes through umu-run
+pub fn applies(config: &NvConfig, requested: bool, game: &DetectedGame) -> bool {
+    (requested || config.umu.enabled)
+        && matches!(
+            game.source,
+            GameSource::Heroic | GameSource::Lutris | GameSource::Unknown
+        )
+        && windows_executable(game)
+}
+
+/// Whether the game is a Windows one, going by the launcher's platform or
+/// runner and otherwise the executable's PE headers
+fn windows_executable(game: &DetectedGame) -> bool {
+    if let Some(platform) = game.metadata.get("platform") {
+        return platform.eq_ignore_ascii_case("windows");
+    }
+    if game.metadata.get("runner").is_some_and(|r| r == "linux") {
+        return false;
+    }
+    PeInfo::from_metadata(&game.metadata).is_some()
+        || game
+            .executable
+            .as_deref()
+            .is_some_and(|exe| PeInfo::read(exe).is_ok())
+}
+
+/// `umu-run` command for a game, adding umu's variables to `env_vars`
+pub fn launch_command(
+    config: &NvConfig,
+    paths: &ConfigPaths,
+    db: &GameDatabase,
+    game: &DetectedGame,
+    extra_args: &[String],
+    env_vars: &mut HashMap<String, String>,
+) -> Result<Vec<String>> {
+    if !in_path("umu-run") {
+        bail!("umu-run not found in PATH; install umu-launcher or launch without --umu");
+    }
+    let Some(exe) = &game.executable else {
+        bail!(
+            "Cannot launch '{}' through umu-run - no executable found",
+            game.name
+        );
+    };
+
+    for (key, value) in umu_env(config, paths, db, game)? {
+        env_vars.entry(key.to_string()).or_insert(value);
+    }
+
+    let mut cmd = vec!["umu-run".to_string(), exe.to_string_lossy().into_owned()];
+    cmd.extend(extra_args.iter().cloned());
+    Ok(cmd)
+}
+
+fn umu_env(
+    config: &NvConfig,
+    paths: &ConfigPaths,
+    db: &GameDatabase,
+    game: &DetectedGame,
+) -> Result<Vec<(&'static str, String)>> {
+    let store = store(game);
+    let codename = game.metadata.get("app_name").unwrap_or(&game.id);
+    let game_id = match configured_game_id(config, db, &game.game_id()) {
+        Some(id) => id.clone(),
+        None => match &config.umu.database {
+            Some(database) => lookup_game_id(database, store, codename)?,
+            None => None,
+        }
+        .unwrap_or_else(|| DEFAULT_GAME_ID.to_string()),
+    };
+
+    let mut env = vec![
+        ("GAMEID", game_id),
+        ("STORE", store.to_string()),
+        (
+            "WINEPREFIX",
+            prefix(config, paths, db, game).display().to_string(),
+        ),
+    ];
+    if let Some(proton) = proton_path(config, game) {
+        env.push(("PROTONPATH", proton));
+    }
+    Ok(env)
+}
+
+/// `umu.game_ids` entry of a game
+///
+/// Entries under the bare ID (from before games were keyed by source) still
+/// apply unless several games share the ID.
+fn configured_game_id<'a>(
+    config: &'a NvConfig,
+    db: &GameDatabase,
+    game_id: &GameId,
+) -> Option<&'a String> {
+    let game_ids = &config.umu.game_ids;
+    game_ids
+        .get(&game_id.to_string())
+        .or_else(|| game_ids.get(&game_id.id).filter(|_| !db.shares_id(game_id)))
+}
+
+/// umu's name for the store a game comes from
+fn store(game: &DetectedGame) -> &'static str {
+    if game.source != GameSource::Heroic {
+        return "none";
+    }
+    match game.metadata.get("runner").map(String::as_str) {
+        Some("legendary") | None => "egs",
+        Some("gog") => "gog",
+        Some("nile") => "amazon",
+        Some(_) => "none",
+    }
+}
+
+/// Prefix the game's launcher created, so saves and settings carry over
+fn prefix(
+    config: &NvConfig,
+    paths: &ConfigPaths,
+    db: &GameDatabase,
+    game: &DetectedGame,
+) -> PathBuf {
+    let launcher_prefix = match game.source {
+        GameSource::Heroic => config
+            .library_paths
+            .heroic
+            .as_ref()
+            .zip(game.metadata.get("app_name"))
+            .and_then(|(root, app_name)| heroic_prefix(root, app_name)),
+        GameSource::Lutris => config
+            .library_paths
+            .lutris
+            .as_ref()
+            .and_then(|root| lutris_game_value(root, &game.id, &["game", "prefix"]))
+            .map(PathBuf::from),
+        _ => None,
+    };
+    launcher_prefix.unwrap_or_else(|| own_prefix(paths, db, &game.game_id()))
+}
+
+/// nvproton's prefix for a game, moved from the bare-ID directory used before
+/// games were keyed by source unless another game shares the ID
+fn own_prefix(paths: &ConfigPaths, db: &GameDatabase, game_id: &GameId) -> PathBuf {
+    let dir = paths.state_dir.join("prefixes");
+    let prefix = dir.join(sanitize(&game_id.dir_name()));
+    let legacy = dir.join(sanitize(&game_id.id));
+    if !prefix.exists()
+        && legacy.is_dir()
+        && !db.shares_id(game_id)
+        && let Err(e) = fs::rename(&legacy, &prefix)
+    {
+        log::warn!("failed to move prefix {:?} to {:?}: {}", legacy, prefix, e);
+        return legacy;
+    }
+    prefix
+}
+
+/// `umu.proton`, or the Proton build a Heroic game is set to use
+fn proton_path(config: &NvConfig, game: &DetectedGame) -> Option<String> {
+    if let Some(proton) = &config.umu.proton {
+        return Some(proton.clone());
+    }
+    let root = config.library_paths.heroic.as_ref()?;
+    let bin = heroic_wine_version(root, game.metadata.get("app_name")?)?.bin?;
+    // Proton builds are set through their `proton` script; Wine builds can't be used
+    (bin.file_name()? == "proton").then(|| bin.parent().map(|dir| dir.display().to_string()))?
+}
+
+/// GAMEID from an umu-database CSV (`TITLE,STORE,CODENAME,UMU_ID,...`)
+fn lookup_game_id(database: &Path, store: &str, codename: &str) -> Result<Option<String>> {
+    let content = fs::read_to_string(database)
+        .with_context(|| format!("failed to read umu database {:?}", database))?;
+    let mut rows = content.lines().map(split_csv_line);
+    let Some(header) = rows.next() else {
+        return Ok(None);
+    };
+    let column = |name: &str| header.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let (Some(store_col), Some(codename_col), Some(id_col)) =
+        (column("STORE"), column("CODENAME"), column("UMU_ID"))
+    else {
+        bail!(
+            "{:?} is not an umu database (missing STORE, CODENAME or UMU_ID)",
+            database
+        );
+    };
+    Ok(rows
+        .find(|row| {
+            row.get(store_col).is_some_and(|s| s == store)
+                && row
+                    .get(codename_col)
+                    .is_some_and(|c| c.eq_ignore_ascii_case(codename))
+        })
+        .and_then(|row| row.get(id_col).cloned())
+        .filter(|id| !id.is_empty()))
+}
+
+/// Fields of one CSV line, honouring double-quoted fields
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::detection::pe::ARCH_KEY;
+
+    #[test]
+    fn test_applies_only_to_windows_games() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("start.sh");
+        fs::write(&script, "#!/bin/sh\n").unwrap();
+        let game = |source, metadata: &[(&str, &str)], executable: Option<&Path>| DetectedGame {
+            id: "hades".into(),
+            name: "Hades".into(),
+            source,
+            install_dir: dir.path().to_path_buf(),
+            executable: executable.map(Path::to_path_buf),
+            fingerprint: None,
+            metadata: metadata
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        };
+        let config = NvConfig::default();
+
+        let windows = game(GameSource::Heroic, &[("platform", "Windows")], None);
+        assert!(applies(&config, true, &windows));
+        assert!(!applies(&config, false, &windows));
+        let native = game(GameSource::Heroic, &[("platform", "linux")], None);
+        assert!(!applies(&config, true, &native));
+        let lutris_native = game(GameSource::Lutris, &[("runner", "linux")], Some(&script));
+        assert!(!applies(&config, true, &lutris_native));
+        let manual_native = game(GameSource::Unknown, &[], Some(&script));
+        assert!(!applies(&config, true, &manual_native));
+        let manual_pe = game(GameSource::Unknown, &[(ARCH_KEY, "x86_64")], None);
+        assert!(applies(&config, true, &manual_pe));
+        let steam = game(GameSource::Steam, &[("platform", "Windows")], None);
+        assert!(!applies(&config, true, &steam));
+    }
+
+    #[test]
+    fn test_configured_game_ids_are_keyed_by_source() {
+        let mut config = NvConfig::default();
+        config.umu.game_ids = BTreeMap::from([
+            ("doom".to_string(), "umu-379720".to_string()),
+            ("heroic:doom".to_string(), "umu-782330".to_string()),
+        ]);
+        let lutris = DetectedGame {
+            id: "doom".into(),
+            name: "DOOM".into(),
+            source: GameSource::Lutris,
+            install_dir: PathBuf::from("/games/DOOM"),
+            executable: None,
+            fingerprint: None,
+            metadata: HashMap::new(),
+        };
+        let mut db = GameDatabase::default();
+        db.merge_detected(std::slice::from_ref(&lutris), 0);
+
+        let heroic = GameId::new(GameSource::Heroic, lutris.id.clone());
+        assert_eq!(
+            configured_game_id(&config, &db, &heroic).map(String::as_str),
+            Some("umu-782330")
+        );
+        // The bare entry applies while no other game shares the ID
+        assert_eq!(
+            configured_game_id(&config, &db, &lutris.game_id()).map(String::as_str),
+            Some("umu-379720")
+        );
+        db.merge_detected(
+            &[DetectedGame {
+                source: GameSource::Steam,
+                ..lutris.clone()
+            }],
+            0,
+        );
+        assert_eq!(configured_game_id(&config, &db, &lutris.game_id()), None);
+    }
+
+    #[test]
+    fn test_game_id_lookup() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("umu-database.csv");
+        fs::write(
+            &database,
+            "TITLE,STORE,CODENAME,UMU_ID,COMMON ACRONYM (Optional),NOTE (Optional)\n\
+             \"Borderlands 3, Director's Cut\",egs,Catnip,umu-397540,BL3,\n\
+             Borderlands 3,steam,397540,umu-397540,BL3,\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            lookup_game_id(&database, "egs", "catnip")
+                .unwrap()
+                .as_deref(),
+            Some("umu-397540")
+        );
+        assert_eq!(lookup_game_id(&database, "gog", "Catnip").unwrap(), None);
+        assert_eq!(
+            split_csv_line("\"a, \"\"b\"\"\",c"),
+            vec!["a, \"b\"".to_string(), "c".to_string()]
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! Undo journal for database-changing commands
+//!
+//! Before a command rewrites the game database or the profile bindings, it
+//! copies the files into `<state>/undo/<id>/` and, once done, records the
+//! operation in `<state>/undo/journal.yaml`. `nvproton undo` copies the files
+//! of the newest operation back and drops it from the journal. Only the last
+//! [`MAX_OPERATIONS`] operations are kept.
+//!
+//! An operation whose files changed again afterwards is only undone with
+//! `--force`, so undo never silently throws away later changes.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::alias;
+use crate::cli::UndoArgs;
+use crate::config::{ConfigManager, ConfigPaths};
+use crate::games;
+use crate::i18n::tr;
+
+/// Number of operations kept in the journal
+pub const MAX_OPERATIONS: usize = 10;
+
+/// A file changed by an operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileBackup {
+    pub path: PathBuf,
+    /// Copy of the file from before the operation; None if it did not exist
+    pub backup: Option<PathBuf>,
+    /// SHA-256 of the file after the operation; None if it was removed
+    pub after: Option<String>,
+}
+
+/// One undoable command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub id: u64,
+    /// Command line that made the change
+    pub command: String,
+    pub recorded_at: u64,
+    pub files: Vec<FileBackup>,
+}
+
+/// Recorded operations, oldest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Journal {
+    pub operations: Vec<Operation>,
+}
+
+fn undo_dir(paths: &ConfigPaths) -> PathBuf {
+    paths.state_dir.join("undo")
+}
+
+/// SHA-256 of a file, or None if it does not exist
+fn file_hash(path: &Path) -> Result<Option<String>> {
+    match fs::read(path) {
+        Ok(contents) => Ok(Some(hex::encode(Sha256::digest(contents)))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("failed to read {:?}", path)),
+    }
+}
+
+impl Journal {
+    pub fn path(paths: &ConfigPaths) -> PathBuf {
+        undo_dir(paths).join("journal.yaml")
+    }
+
+    /// Load the journal (empty if nothing was recorded yet)
+    pub fn load(paths: &ConfigPaths) -> Result<Self> {
+        let path = Self::path(paths);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read undo journal {:?}", path))?;
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("failed to parse undo journal {:?}", path))
+    }
+
+    fn save(&self, paths: &ConfigPaths) -> Result<()> {
+        let path = Self::path(paths);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create directory {:?}", parent))?;
+        }
+        let yaml = serde_yaml::to_string(self)?;
+        fs::write(&path, yaml).with_context(|| format!("failed to write undo journal {:?}", path))
+    }
+
+    /// Append an operation, dropping the oldest ones and their backups
+    fn push(&mut self, paths: &ConfigPaths, operation: Operation) {
+        self.operations.push(operation);
+        while self.operations.len() > MAX_OPERATIONS {
+            let old = self.operations.remove(0);
+            let _ = fs::remove_dir_all(undo_dir(paths).join(old.id.to_string()));
+        }
+    }
+}
+
+/// An operation being recorded: back up the files, change them, then commit
+///
+/// Dropping it without [`Recorder::commit`] (e.g. because the command failed)
+/// removes the backups again.
+pub struct Recorder<'a> {
+    paths: &'a ConfigPaths,
+    operation: Operation,
+    committed: bool,
+}
+
+impl<'a> Recorder<'a> {
+    /// Start recording the running command
+    pub fn begin(paths: &'a ConfigPaths) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let command = env::args_os()
+            .skip(1)
+            .map(|arg| alias::quote_word(&arg.to_string_lossy()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self {
+            paths,
+            operation: Operation {
+                id: now.as_nanos() as u64,
+                command,
+                recorded_at: now.as_secs(),
+                files: Vec::new(),
+            },
+            committed: false,
+        }
+    }
+
+    fn backup_dir(&self) -> PathBuf {
+        undo_dir(self.paths).join(self.operation.id.to_string())
+    }
+
+    /// Copy `path` aside before the command changes it
+    pub fn backup(&mut self, path: &Path) -> Result<()> {
+        if self.operation.files.iter().any(|f| f.path == path) {
+            return Ok(());
+        }
+        let backup = if path.exists() {
+            let dir = self.backup_dir();
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("failed to create directory {:?}", dir))?;
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let backup = dir.join(format!("{}-{}", self.operation.files.len(), name));
+            fs::copy(path, &backup)
+                .with_context(|| format!("failed to back up {:?} to {:?}", path, backup))?;
+            Some(backup)
+        } else {
+            None
+        };
+        self.operation.files.push(FileBackup {
+            path: path.to_path_buf(),
+            backup,
+            after: None,
+        });
+        Ok(())
+    }
+
+    /// Whether no file has been backed up yet
+    pub fn is_empty(&self) -> bool {
+        self.operation.files.is_empty()
+    }
+
+    /// Record the finished operation in the journal
+    pub fn commit(mut self) -> Result<()> {
+        for file in &mut self.operation.files {
+            file.after = file_hash(&file.path)?;
+        }
+        let mut journal = Journal::load(self.paths)?;
+        journal.push(self.paths, self.operation.clone());
+        journal.save(self.paths)?;
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Drop for Recorder<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_dir_all(self.backup_dir());
+        }
+    }
+}
+
+/// Put the files of `operation` back as they were before it
+fn restore(operation: &Operation) -> Result<()> {
+    for file in &operation.files {
+        match &file.backup {
+            Some(backup) => {
+                fs::copy(backup, &file.path)
+                    .with_context(|| format!("failed to restore {:?}", file.path))?;
+            }
+            None if file.path.exists() => {
+                fs::remove_file(&file.path)
+                    .with_context(|| format!("failed to remove {:?}", file.path))?;
+            }
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+/// Files of `operation` that changed after it was recorded
+fn changed_since(operation: &Operation) -> Result<Vec<&Path>> {
+    let mut changed = Vec::new();
+    for file in &operation.files {
+        if file_hash(&file.path)? != file.after {
+            changed.push(file.path.as_path());
+        }
+    }
+    Ok(changed)
+}
+
+pub fn handle_undo(args: UndoArgs, manager: &ConfigManager) -> Result<()> {
+    let paths = manager.paths();
+    let mut journal = Journal::load(paths)?;
+
+    if args.list {
+        if journal.operations.is_empty() {
+            println!("{}", tr!("undo-empty"));
+        }
+        for operation in journal.operations.iter().rev() {
+            println!(
+                "{:<10} nvproton {}",
+                games::format_age(operation.recorded_at),
+                operation.command
+            );
+        }
+        return Ok(());
+    }
+
+    let Some(operation) = journal.operations.last() else {
+        bail!("nothing to undo");
+    };
+    let changed = changed_since(operation)?;
+    if !changed.is_empty() && !args.force {
+        bail!(
+            "{:?} changed after 'nvproton {}' (use --force to undo it anyway)",
+            changed[0],
+            operation.command
+        );
+    }
+
+    if args.dry_run {
+        println!("{}", tr!("undo-dry-run", command = operation.command));
+        for file in &operation.files {
+            println!("  {}", file.path.display());
+        }
+        return Ok(());
+    }
+
+    restore(operation)?;
+    let operation = journal.operations.pop().expect("journal is not empty");
+    journal.save(paths)?;
+    let _ = fs::remove_dir_all(undo_dir(paths).join(operation.id.to_string()));
+    println!("{}", tr!("undo-done", command = operation.command));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_restores_backed_up_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = ConfigManager::portable(dir.path()).paths().clone();
+        let existing = dir.path().join("games.yaml");
+        let created = dir.path().join("profiles.db");
+        fs::write(&existing, "before").unwrap();
+
+        let mut recorder = Recorder::begin(&paths);
+        recorder.backup(&existing).unwrap();
+        recorder.backup(&created).unwrap();
+        fs::write(&existing, "after").unwrap();
+        fs::write(&created, "new").unwrap();
+        recorder.commit().unwrap();
+
+        let journal = Journal::load(&paths).unwrap();
+        let operation = &journal.operations[0];
+        assert!(changed_since(operation).unwrap().is_empty());
+        fs::write(&existing, "later").unwrap();
+        assert_eq!(changed_since(operation).unwrap(), vec![existing.as_path()]);
+
+        restore(operation).unwrap();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "before");
+        assert!(!created.exists());
+
+        // An abandoned recording leaves no backups behind
+        let mut recorder = Recorder::begin(&paths);
+        recorder.backup(&existing).unwrap();
+        let backup_dir = recorder.backup_dir();
+        assert!(backup_dir.exists());
+        drop(recorder);
+        assert!(!backup_dir.exists());
+    }
+}
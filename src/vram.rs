@@ -0,0 +1,229 @@
+//! VRAM headroom check before launch
+//!
+//! A game that starts with little free VRAM has its allocations evicted to
+//! system memory and stutters without any error. Before launching, `run`
+//! reads the free VRAM and the processes holding GPU memory from NVML and
+//! warns when less than `vram.min_free_mb` is free, or when a browser with
+//! hardware acceleration, a local LLM runner or any other process holding
+//! more than `vram.heavy_process_mb` is running. `run --strict` aborts
+//! instead.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::config::VramConfig;
+use crate::ffi::{self, NvmlProcess};
+
+const MB: u64 = 1024 * 1024;
+
+/// Browsers that composite and decode video on the GPU
+const BROWSERS: &[&str] = &[
+    "firefox",
+    "firefox-bin",
+    "librewolf",
+    "chrome",
+    "chromium",
+    "chromium-browser",
+    "brave",
+    "vivaldi-bin",
+    "opera",
+    "msedge",
+];
+
+/// Local LLM runners, which keep whole models resident in VRAM
+const LLM_RUNNERS: &[&str] = &[
+    "ollama",
+    "ollama_llama_server",
+    "llama-server",
+    "llama-cli",
+    "koboldcpp",
+    "lms",
+    "lm-studio",
+    "vllm",
+];
+
+/// Display servers and compositors, whose VRAM use is expected
+const DESKTOP: &[&str] = &[
+    "Xorg",
+    "Xwayland",
+    "kwin_wayland",
+    "kwin_x11",
+    "gnome-shell",
+    "plasmashell",
+    "sway",
+    "Hyprland",
+];
+
+/// Why a process is reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeavyKind {
+    Browser,
+    LocalLlm,
+    /// Holds more than `vram.heavy_process_mb`
+    Other,
+}
+
+impl fmt::Display for HeavyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeavyKind::Browser => write!(f, "browser"),
+            HeavyKind::LocalLlm => write!(f, "local LLM"),
+            HeavyKind::Other => write!(f, "GPU process"),
+        }
+    }
+}
+
+/// A process competing with the game for VRAM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeavyProcess {
+    pub pid: u32,
+    pub name: String,
+    pub kind: HeavyKind,
+    pub vram_used_bytes: Option<u64>,
+}
+
+/// A problem found by the check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VramWarning {
+    LowFree { free_bytes: u64, total_bytes: u64 },
+    HeavyProcess(HeavyProcess),
+}
+
+/// Check the first GPU's VRAM headroom; empty when NVML is unavailable
+pub fn check(config: &VramConfig) -> Vec<VramWarning> {
+    let nvml = match ffi::load_nvml() {
+        Ok(nvml) => nvml,
+        Err(e) => {
+            log::debug!("skipping the VRAM check, NVML is unavailable: {}", e);
+            return Vec::new();
+        }
+    };
+    let reading = nvml.read();
+    let processes: Vec<_> = nvml
+        .processes()
+        .into_iter()
+        .map(|process| (process_name(process.pid), process))
+        .collect();
+    let memory = reading.vram_free_bytes.zip(reading.vram_total_bytes);
+    evaluate(memory, &processes, config)
+}
+
+/// Warnings for the free and total VRAM and the named GPU processes
+fn evaluate(
+    memory: Option<(u64, u64)>,
+    processes: &[(String, NvmlProcess)],
+    config: &VramConfig,
+) -> Vec<VramWarning> {
+    let mut warnings = Vec::new();
+    if let Some((free_bytes, total_bytes)) = memory
+        && free_bytes < config.min_free_mb * MB
+    {
+        warnings.push(VramWarning::LowFree {
+            free_bytes,
+            total_bytes,
+        });
+    }
+    for (name, process) in processes {
+        let large = config.heavy_process_mb > 0
+            && process
+                .vram_used_bytes
+                .is_some_and(|used| used >= config.heavy_process_mb * MB);
+        let kind = match classify(name) {
+            Some(kind) => kind,
+            None if large && !DESKTOP.contains(&name.as_str()) => HeavyKind::Other,
+            None => continue,
+        };
+        warnings.push(VramWarning::HeavyProcess(HeavyProcess {
+            pid: process.pid,
+            name: name.clone(),
+            kind,
+            vram_used_bytes: process.vram_used_bytes,
+        }));
+    }
+    warnings
+}
+
+fn classify(name: &str) -> Option<HeavyKind> {
+    if BROWSERS.contains(&name) {
+        Some(HeavyKind::Browser)
+    } else if LLM_RUNNERS.contains(&name) {
+        Some(HeavyKind::LocalLlm)
+    } else {
+        None
+    }
+}
+
+/// Executable name of a process, falling back to its (truncated) `comm`
+fn process_name(pid: u32) -> String {
+    let exe = fs::read_link(format!("/proc/{}/exe", pid)).ok();
+    if let Some(name) = exe
+        .as_deref()
+        .and_then(Path::file_name)
+        .and_then(|name| name.to_str())
+    {
+        return name.to_string();
+    }
+    fs::read_to_string(format!("/proc/{}/comm", pid))
+        .map(|comm| comm.trim().to_string())
+        .unwrap_or_else(|_| format!("pid {}", pid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, name: &str, used_mb: Option<u64>) -> (String, NvmlProcess) {
+        let process = NvmlProcess {
+            pid,
+            vram_used_bytes: used_mb.map(|mb| mb * MB),
+        };
+        (name.to_string(), process)
+    }
+
+    #[test]
+    fn test_evaluate_headroom() {
+        let config = VramConfig {
+            min_free_mb: 2048,
+            heavy_process_mb: 1024,
+        };
+        let processes = [
+            process(10, "Xorg", Some(1500)),
+            process(11, "firefox", None),
+            process(12, "ollama_llama_server", Some(6000)),
+            process(13, "python3", Some(3000)),
+            process(14, "steamwebhelper", Some(200)),
+        ];
+        let warnings = evaluate(Some((1024 * MB, 12288 * MB)), &processes, &config);
+        assert_eq!(
+            warnings[0],
+            VramWarning::LowFree {
+                free_bytes: 1024 * MB,
+                total_bytes: 12288 * MB
+            }
+        );
+        let kinds: Vec<_> = warnings[1..]
+            .iter()
+            .map(|w| match w {
+                VramWarning::HeavyProcess(p) => (p.pid, p.kind),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (11, HeavyKind::Browser),
+                (12, HeavyKind::LocalLlm),
+                (13, HeavyKind::Other)
+            ]
+        );
+
+        let relaxed = VramConfig {
+            min_free_mb: 0,
+            heavy_process_mb: 0,
+        };
+        // Only known kinds are reported without thresholds
+        let warnings = evaluate(Some((0, 8192 * MB)), &processes[2..], &relaxed);
+        assert_eq!(warnings.len(), 1);
+    }
+}
@@ -0,0 +1,187 @@
+//! Wine synchronization primitives
+//!
+//! Wine implements Windows events, mutexes and semaphores in wineserver,
+//! which costs a round trip per wait, unless the kernel offers a faster path:
+//! - esync uses eventfds, one per object, and needs an open file limit of
+//!   at least 524288
+//! - fsync waits on futexes with `futex_waitv` (Linux 5.16)
+//! - ntsync implements the NT primitives in the kernel (`/dev/ntsync`,
+//!   Linux 6.14), used by Wine 10 and newer Proton builds
+//!
+//! `run --sync` selects one (auto: the fastest the kernel supports) and
+//! `status` reports which ones the running kernel supports.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cli::SyncMode;
+
+/// Open file limit esync needs (one eventfd per sync object)
+const ESYNC_MIN_OPEN_FILES: u64 = 524_288;
+
+/// First kernel with `futex_waitv`
+const FUTEX_WAITV_KERNEL: (u32, u32) = (5, 16);
+
+const NTSYNC_DEVICE: &str = "/dev/ntsync";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncMechanism {
+    Esync,
+    Fsync,
+    Ntsync,
+}
+
+impl fmt::Display for SyncMechanism {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncMechanism::Esync => write!(f, "esync"),
+            SyncMechanism::Fsync => write!(f, "fsync"),
+            SyncMechanism::Ntsync => write!(f, "ntsync"),
+        }
+    }
+}
+
+impl SyncMechanism {
+    /// What the kernel needs to provide
+    pub fn requirement(self) -> &'static str {
+        match self {
+            SyncMechanism::Esync => "an open file limit of 524288 or more",
+            SyncMechanism::Fsync => "futex_waitv (Linux 5.16 or newer)",
+            SyncMechanism::Ntsync => "/dev/ntsync from the ntsync module (Linux 6.14 or newer)",
+        }
+    }
+
+    /// Select the mechanism in Proton and in Wine builds that read WINE*SYNC
+    ///
+    /// Proton prefers ntsync, then fsync, then esync, so the faster ones are
+    /// turned off to get a slower one.
+    pub fn apply(self, env_vars: &mut HashMap<String, String>) {
+        let (disabler, set): (&str, &[(&str, &str)]) = match self {
+            SyncMechanism::Esync => (
+                "PROTON_NO_ESYNC",
+                &[
+                    ("PROTON_NO_FSYNC", "1"),
+                    ("PROTON_NO_NTSYNC", "1"),
+                    ("WINEESYNC", "1"),
+                ],
+            ),
+            SyncMechanism::Fsync => (
+                "PROTON_NO_FSYNC",
+                &[("PROTON_NO_NTSYNC", "1"), ("WINEFSYNC", "1")],
+            ),
+            SyncMechanism::Ntsync => (
+                "PROTON_NO_NTSYNC",
+                &[("PROTON_USE_NTSYNC", "1"), ("WINENTSYNC", "1")],
+            ),
+        };
+        env_vars.remove(disabler);
+        for (key, value) in set {
+            env_vars.insert(key.to_string(), value.to_string());
+        }
+    }
+}
+
+/// Mechanisms the running kernel supports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncSupport {
+    pub esync: bool,
+    pub fsync: bool,
+    pub ntsync: bool,
+}
+
+impl SyncSupport {
+    pub fn detect() -> Self {
+        let open_files = fs::read_to_string("/proc/self/limits")
+            .ok()
+            .and_then(|limits| hard_open_file_limit(&limits));
+        let kernel = fs::read_to_string("/proc/sys/kernel/osrelease")
+            .ok()
+            .and_then(|release| kernel_version(&release));
+        Self {
+            esync: open_files.is_some_and(|limit| limit >= ESYNC_MIN_OPEN_FILES),
+            fsync: kernel.is_some_and(|version| version >= FUTEX_WAITV_KERNEL),
+            ntsync: Path::new(NTSYNC_DEVICE).exists(),
+        }
+    }
+
+    pub fn supports(&self, mechanism: SyncMechanism) -> bool {
+        match mechanism {
+            SyncMechanism::Esync => self.esync,
+            SyncMechanism::Fsync => self.fsync,
+            SyncMechanism::Ntsync => self.ntsync,
+        }
+    }
+
+    /// The fastest supported mechanism
+    pub fn best(&self) -> Option<SyncMechanism> {
+        [
+            SyncMechanism::Ntsync,
+            SyncMechanism::Fsync,
+            SyncMechanism::Esync,
+        ]
+        .into_iter()
+        .find(|mechanism| self.supports(*mechanism))
+    }
+}
+
+/// The mechanism `--sync` asks for, or None for auto
+pub fn requested(mode: SyncMode) -> Option<SyncMechanism> {
+    match mode {
+        SyncMode::Auto => None,
+        SyncMode::Esync => Some(SyncMechanism::Esync),
+        SyncMode::Fsync => Some(SyncMechanism::Fsync),
+        SyncMode::Ntsync => Some(SyncMechanism::Ntsync),
+    }
+}
+
+/// Hard "Max open files" limit from `/proc/<pid>/limits`
+fn hard_open_file_limit(limits: &str) -> Option<u64> {
+    let line = limits
+        .lines()
+        .find(|line| line.starts_with("Max open files"))?;
+    match line.split_whitespace().nth(4)? {
+        "unlimited" => Some(u64::MAX),
+        limit => limit.parse().ok(),
+    }
+}
+
+/// Major and minor version of a kernel release (`6.18.4-arch1-1`)
+fn kernel_version(release: &str) -> Option<(u32, u32)> {
+    let mut parts = release.trim().split(['.', '-']);
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_support_detection_and_env() {
+        let limits = "Limit                     Soft Limit           Hard Limit           Units\n\
+                      Max open files            1024                 524288               files\n";
+        assert_eq!(hard_open_file_limit(limits), Some(524_288));
+        assert_eq!(kernel_version("6.18.44-fc-v139\n"), Some((6, 18)));
+        assert_eq!(kernel_version("5.15.0-91-generic"), Some((5, 15)));
+
+        let support = SyncSupport {
+            esync: true,
+            fsync: true,
+            ntsync: false,
+        };
+        assert_eq!(support.best(), Some(SyncMechanism::Fsync));
+        assert_eq!(SyncSupport::default().best(), None);
+
+        let mut env_vars = HashMap::from([("PROTON_NO_ESYNC".to_string(), "1".to_string())]);
+        SyncMechanism::Esync.apply(&mut env_vars);
+        assert!(!env_vars.contains_key("PROTON_NO_ESYNC"));
+        assert_eq!(env_vars["PROTON_NO_FSYNC"], "1");
+        assert_eq!(env_vars["WINEESYNC"], "1");
+    }
+}
@@ -0,0 +1,228 @@
+//! Wine's native Wayland driver
+//!
+//! Proton games normally draw through Xwayland on a Wayland desktop. Wine's
+//! Wayland driver (winewayland) talks to the compositor directly, which
+//! saves a copy per frame and the latency that comes with it. `run
+//! --wine-wayland` turns it on when the game's build ships the driver:
+//! - Proton builds with the driver read `PROTON_ENABLE_WAYLAND=1`
+//! - Wine builds use it when `DISPLAY` is empty, provided the prefix lists it
+//!   among its graphics drivers (`HKCU\Software\Wine\Drivers\Graphics`);
+//!   Wine 9 doesn't by default, so the value is set for the session and
+//!   restored when the game exits
+//!
+//! Outside a Wayland session, or with a build lacking the driver, the game
+//! runs through Xwayland as usual.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+
+use crate::config::NvConfig;
+use crate::detection::DetectedGame;
+use crate::detection::display_server::{DisplaySession, SessionType};
+use crate::hooks::LaunchHook;
+use crate::runtimes::{self, RuntimeKind, WineRuntime};
+use crate::saves;
+
+/// Where builds keep the driver's Unix side, relative to their install
+const DRIVER_PATHS: &[&str] = &[
+    "files/lib/wine/x86_64-unix/winewayland.so",
+    "files/lib64/wine/x86_64-unix/winewayland.so",
+    "lib/wine/x86_64-unix/winewayland.so",
+    "lib64/wine/x86_64-unix/winewayland.so",
+    "lib/x86_64-linux-gnu/wine/x86_64-unix/winewayland.so",
+];
+
+const DRIVERS_KEY: &str = r"HKCU\Software\Wine\Drivers";
+
+/// How `user.reg` spells the drivers key
+const DRIVERS_SECTION: &str = r"[Software\\Wine\\Drivers]";
+
+/// The Wayland driver set up for a launch
+pub struct WaylandSetup {
+    /// Name of the Proton or Wine build
+    pub runtime: String,
+    /// Registry change the prefix needs while the game runs
+    pub hook: Option<Box<dyn LaunchHook>>,
+}
+
+/// Enable the Wayland driver for a game, or say why it stays on Xwayland
+pub fn enable(
+    config: &NvConfig,
+    game: &DetectedGame,
+    display: &DisplaySession,
+    env_vars: &mut HashMap<String, String>,
+) -> Result<WaylandSetup, String> {
+    let wayland_display = env::var("WAYLAND_DISPLAY").unwrap_or_default();
+    if display.session_type != SessionType::Wayland || wayland_display.is_empty() {
+        return Err("not running in a Wayland session".to_string());
+    }
+    let runtime = runtimes::game_runtime(config, game)
+        .ok_or_else(|| "no Proton or Wine build found for the game".to_string())?;
+    if !has_driver(&runtime) {
+        return Err(format!("{} doesn't ship the Wayland driver", runtime.name));
+    }
+
+    let hook = match runtime.kind {
+        RuntimeKind::Proton => {
+            env_vars.insert("PROTON_ENABLE_WAYLAND".into(), "1".into());
+            None
+        }
+        RuntimeKind::Wine => {
+            let prefix = saves::wine_prefix(config, game)
+                .ok_or_else(|| "the game's Wine prefix wasn't found".to_string())?;
+            env_vars.insert("DISPLAY".into(), String::new());
+            env_vars.insert("WAYLAND_DISPLAY".into(), wayland_display);
+            Some(Box::new(DriverRegistry {
+                wine: wine_binary(&runtime),
+                prefix,
+                previous: None,
+            }) as Box<dyn LaunchHook>)
+        }
+    };
+    Ok(WaylandSetup {
+        runtime: runtime.name,
+        hook,
+    })
+}
+
+/// Whether a build ships the Wayland driver
+pub fn has_driver(runtime: &WineRuntime) -> bool {
+    // The system build's path is its binary, /usr/bin/wine
+    let root = match runtime.source {
+        "system" => runtime.path.parent().and_then(Path::parent),
+        _ => Some(runtime.path.as_path()),
+    };
+    root.is_some_and(|root| DRIVER_PATHS.iter().any(|path| root.join(path).is_file()))
+}
+
+fn wine_binary(runtime: &WineRuntime) -> PathBuf {
+    if runtime.source == "system" {
+        return runtime.path.clone();
+    }
+    ["bin/wine", "files/bin/wine"]
+        .iter()
+        .map(|path| runtime.path.join(path))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| runtime.path.join("bin/wine"))
+}
+
+/// Adds `wayland` to the prefix's graphics drivers for the session
+struct DriverRegistry {
+    wine: PathBuf,
+    prefix: PathBuf,
+    /// The value before the launch, `Some(None)` if it wasn't set
+    previous: Option<Option<String>>,
+}
+
+impl DriverRegistry {
+    fn reg(&self, args: &[&str]) -> Result<()> {
+        let output = Command::new(&self.wine)
+            .arg("reg")
+            .args(args)
+            .env("WINEPREFIX", &self.prefix)
+            .env("WINEDEBUG", "-all")
+            .output()
+            .with_context(|| format!("failed to run {:?}", self.wine))?;
+        if !output.status.success() {
+            bail!(
+                "wine reg {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+}
+
+impl LaunchHook for DriverRegistry {
+    fn name(&self) -> &str {
+        "wine-wayland-driver"
+    }
+
+    fn on_start(&mut self) -> Result<()> {
+        let user_reg = fs::read_to_string(self.prefix.join("user.reg")).unwrap_or_default();
+        let current = graphics_drivers(&user_reg);
+        let Some(drivers) = with_wayland(current.as_deref()) else {
+            return Ok(());
+        };
+        self.reg(&["add", DRIVERS_KEY, "/v", "Graphics", "/d", &drivers, "/f"])?;
+        self.previous = Some(current);
+        Ok(())
+    }
+
+    fn on_exit(&mut self) -> Result<()> {
+        match self.previous.take() {
+            Some(Some(previous)) => {
+                self.reg(&["add", DRIVERS_KEY, "/v", "Graphics", "/d", &previous, "/f"])
+            }
+            Some(None) => self.reg(&["delete", DRIVERS_KEY, "/v", "Graphics", "/f"]),
+            None => Ok(()),
+        }
+    }
+}
+
+/// `Graphics` value of the drivers key in a prefix's `user.reg`
+fn graphics_drivers(user_reg: &str) -> Option<String> {
+    user_reg
+        .lines()
+        .skip_while(|line| !line.starts_with(DRIVERS_SECTION))
+        .skip(1)
+        .take_while(|line| !line.starts_with('['))
+        .find_map(|line| line.strip_prefix("\"Graphics\"=\""))
+        .map(|value| value.trim_end_matches('"').to_string())
+}
+
+/// Driver list with `wayland` added, or None if it is already there
+///
+/// X11 stays first so other launches in the prefix keep using it while
+/// `DISPLAY` is set.
+fn with_wayland(current: Option<&str>) -> Option<String> {
+    match current {
+        Some(drivers) if drivers.split(',').any(|d| d.trim() == "wayland") => None,
+        Some(drivers) if !drivers.trim().is_empty() => Some(format!("{},wayland", drivers)),
+        _ => Some("x11,wayland".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_driver_detection_and_registry() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut runtime = WineRuntime {
+            name: "GE-Proton10-4".into(),
+            kind: RuntimeKind::Proton,
+            source: "steam",
+            path: dir.path().to_path_buf(),
+            version: None,
+            used_by: Vec::new(),
+        };
+        assert!(!has_driver(&runtime));
+        let driver = dir.path().join(DRIVER_PATHS[0]);
+        fs::create_dir_all(driver.parent().unwrap()).unwrap();
+        fs::write(&driver, "").unwrap();
+        assert!(has_driver(&runtime));
+        // /usr/bin/wine finds /usr/lib/wine
+        runtime.source = "system";
+        runtime.path = dir.path().join("files/bin/wine");
+        assert!(has_driver(&runtime));
+
+        let user_reg = "WINE REGISTRY Version 2\n\n\
+                        [Software\\\\Wine\\\\Drivers] 1700000000\n\
+                        #time=1da0000000000000\n\
+                        \"Graphics\"=\"x11\"\n\n\
+                        [Software\\\\Wine\\\\X11 Driver] 1700000000\n";
+        assert_eq!(graphics_drivers(user_reg).as_deref(), Some("x11"));
+        assert_eq!(graphics_drivers("WINE REGISTRY Version 2\n"), None);
+        assert_eq!(with_wayland(Some("x11")).as_deref(), Some("x11,wayland"));
+        assert_eq!(with_wayland(None).as_deref(), Some("x11,wayland"));
+        assert_eq!(with_wayland(Some("x11,wayland")), None);
+    }
+}
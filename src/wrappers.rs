@@ -0,0 +1,250 @@
+//! Wrapper commands around the game
+//!
+//! Profiles and per-game records can list wrappers that are chained in
+//! front of the game command, outermost first:
+//!
+//! ```yaml
+//! wrappers:
+//!   - mangohud
+//!   - gamemoderun
+//!   - command: taskset -c 0-7
+//!     priority: 80
+//! ```
+//!
+//! Wrappers are ordered by priority (lower runs outermost, default 50), then
+//! by declaration order with profile entries before per-game ones. A
+//! per-game wrapper replaces a profile wrapper for the same program.
+//! Commands are split on whitespace; quoting is not supported.
+
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use crate::detection::{DetectedGame, GameSource};
+use crate::paths::in_path;
+
+/// Priority of wrappers declared without one
+pub const DEFAULT_PRIORITY: i32 = 50;
+
+/// A wrapper as written in a profile or game record
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum WrapperSpec {
+    Command(String),
+    Prioritized {
+        command: String,
+        #[serde(default = "default_priority")]
+        priority: i32,
+    },
+}
+
+fn default_priority() -> i32 {
+    DEFAULT_PRIORITY
+}
+
+impl WrapperSpec {
+    pub fn command(&self) -> &str {
+        match self {
+            WrapperSpec::Command(command) | WrapperSpec::Prioritized { command, .. } => command,
+        }
+    }
+
+    pub fn priority(&self) -> i32 {
+        match self {
+            WrapperSpec::Command(_) => DEFAULT_PRIORITY,
+            WrapperSpec::Prioritized { priority, .. } => *priority,
+        }
+    }
+}
+
+/// Ordered wrapper commands for one launch
+#[derive(Debug, Clone, Default)]
+pub struct WrapperChain {
+    wrappers: Vec<Vec<String>>,
+}
+
+impl WrapperChain {
+    /// Merge the wrappers of a resolved profile and a game record
+    pub fn resolve(profile: Option<&Value>, game: &[WrapperSpec]) -> Result<Self> {
+        let profile_specs: Vec<WrapperSpec> = match profile.and_then(|s| s.get("wrappers")) {
+            Some(value) => serde_yaml::from_value(value.clone())
+                .map_err(|e| anyhow::anyhow!("invalid wrappers in profile: {}", e))?,
+            None => Vec::new(),
+        };
+
+        let mut specs: Vec<(i32, Vec<String>)> = Vec::new();
+        for spec in profile_specs.iter().chain(game) {
+            let argv: Vec<String> = spec
+                .command()
+                .split_whitespace()
+                .map(String::from)
+                .collect();
+            let Some(program) = argv.first() else {
+                bail!("empty wrapper command");
+            };
+            specs.retain(|(_, existing)| existing.first() != Some(program));
+            specs.push((spec.priority(), argv));
+        }
+        // Stable sort keeps declaration order for equal priorities
+        specs.sort_by_key(|(priority, _)| *priority);
+
+        Ok(Self {
+            wrappers: specs.into_iter().map(|(_, argv)| argv).collect(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.wrappers.is_empty()
+    }
+
+    /// Human-readable chain (`mangohud -> gamemoderun`)
+    pub fn describe(&self) -> String {
+        self.wrappers
+            .iter()
+            .map(|argv| argv.join(" "))
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    }
+
+    /// Check that every wrapper program can be found
+    pub fn validate(&self) -> Result<()> {
+        let missing: Vec<&str> = self
+            .wrappers
+            .iter()
+            .filter_map(|argv| argv.first())
+            .filter(|program| !program_exists(program))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            bail!("wrapper command(s) not found: {}", missing.join(", "));
+        }
+        Ok(())
+    }
+
+    /// Prefix a command with the chain
+    pub fn wrap(&self, command: Vec<String>) -> Vec<String> {
+        self.wrappers
+            .iter()
+            .flatten()
+            .cloned()
+            .chain(command)
+            .collect()
+    }
+
+    /// Insert the chain into a launch command built for a game source
+    ///
+    /// `composed` is set for launcher-composed commands (Steam's `%command%`),
+    /// which are always prefixed directly.
+    pub fn apply(&self, game: &DetectedGame, command: Vec<String>, composed: bool) -> Vec<String> {
+        if self.is_empty() {
+            return command;
+        }
+        if composed {
+            return self.wrap(command);
+        }
+        match game.source {
+            GameSource::Unknown => self.wrap(command),
+            GameSource::Legendary => {
+                // legendary launch <id> --wrapper "<chain>"
+                let mut command = command;
+                let at = command.len().min(3);
+                let chain = self.wrappers.iter().flatten().cloned().collect::<Vec<_>>();
+                command.splice(at..at, ["--wrapper".to_string(), chain.join(" ")]);
+                command
+            }
+            GameSource::Steam | GameSource::Heroic | GameSource::Lutris => {
+                eprintln!(
+                    "  Warning: wrappers can't be applied to {} client launches; \
+                     set the launch options to 'nvproton wrap -- %command%' instead",
+                    game.source
+                );
+                command
+            }
+        }
+    }
+}
+
+/// Whether a program is an existing path or can be found in PATH
+pub fn program_exists(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).exists();
+    }
+    in_path(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn game(source: GameSource) -> DetectedGame {
+        DetectedGame {
+            source,
+            id: "Fortnite".into(),
+            name: "Fortnite".into(),
+            install_dir: PathBuf::new(),
+            executable: None,
+            fingerprint: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_orders_by_priority() {
+        let profile: Value = serde_yaml::from_str(
+            "wrappers:\n  - mangohud\n  - command: taskset -c 0-7\n    priority: 80\n  - gamemoderun\n",
+        )
+        .unwrap();
+        let game_wrappers = vec![WrapperSpec::Prioritized {
+            command: "mangohud --dlsym".into(),
+            priority: 10,
+        }];
+        let chain = WrapperChain::resolve(Some(&profile), &game_wrappers).unwrap();
+        assert_eq!(
+            chain.describe(),
+            "mangohud --dlsym -> gamemoderun -> taskset -c 0-7"
+        );
+        assert_eq!(
+            chain.wrap(vec!["game.exe".into()]),
+            vec![
+                "mangohud",
+                "--dlsym",
+                "gamemoderun",
+                "taskset",
+                "-c",
+                "0-7",
+                "game.exe"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_to_legendary() {
+        let chain =
+            WrapperChain::resolve(None, &[WrapperSpec::Command("gamemoderun".into())]).unwrap();
+        let command = vec!["legendary".into(), "launch".into(), "Fortnite".into()];
+        assert_eq!(
+            chain.apply(&game(GameSource::Legendary), command, false),
+            vec![
+                "legendary",
+                "launch",
+                "Fortnite",
+                "--wrapper",
+                "gamemoderun"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_missing_programs() {
+        let chain = WrapperChain::resolve(
+            None,
+            &[WrapperSpec::Command("nvproton-no-such-wrapper".into())],
+        )
+        .unwrap();
+        assert!(chain.validate().is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! NVIDIA Xid error detection
+//!
+//! Xid errors are reported by the NVIDIA kernel module as
+//! `NVRM: Xid (PCI:0000:01:00): 79, pid=..., GPU has fallen off the bus.`
+//! and usually explain GPU hangs and crashes that games only report as
+//! a generic device loss.
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use serde::{Deserialize, Serialize};
+
+/// A parsed Xid kernel message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct XidError {
+    /// Xid code (e.g., 79)
+    pub code: u32,
+    /// PCI device the error was raised on
+    pub device: Option<String>,
+    /// Original kernel log line
+    pub message: String,
+}
+
+impl XidError {
+    /// Short explanation for well-known Xid codes
+    pub fn description(&self) -> &'static str {
+        match self.code {
+            13 => "Graphics engine exception",
+            31 => "GPU memory page fault",
+            32 => "Invalid or corrupted push buffer stream",
+            43 => "GPU stopped processing (application fault)",
+            45 => "Preemptive cleanup after a previous error",
+            48 => "Double-bit ECC error",
+            61 | 62 => "Internal micro-controller error",
+            63 | 64 => "ECC page retirement or row remapping event",
+            69 => "Graphics engine class error",
+            74 => "NVLink error",
+            79 => "GPU has fallen off the bus",
+            109 => "Context switch timeout",
+            119 | 120 => "GSP firmware error",
+            _ => "Unknown Xid",
+        }
+    }
+}
+
+/// Parse a kernel log line into an Xid error
+pub fn parse_line(line: &str) -> Option<XidError> {
+    let rest = &line[line.find("NVRM: Xid")? + "NVRM: Xid".len()..];
+    let rest = rest.trim_start();
+
+    // Optional "(PCI:0000:01:00)" device tag
+    let (device, rest) = match rest.strip_prefix('(') {
+        Some(tagged) => {
+            let end = tagged.find(')')?;
+            (Some(tagged[..end].to_string()), &tagged[end + 1..])
+        }
+        None => (None, rest),
+    };
+
+    let code = rest
+        .trim_start_matches([':', ' '])
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()?;
+
+    Some(XidError {
+        code,
+        device,
+        message: line.trim().to_string(),
+    })
+}
+
+/// Read recent Xid errors from the kernel log (dmesg, falling back to journalctl)
+pub fn recent_errors(limit: usize) -> Vec<XidError> {
+    let output = Command::new("dmesg")
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .or_else(|| {
+            Command::new("journalctl")
+                .args(["-k", "-b", "--no-pager", "-q"])
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+        });
+
+    let Some(output) = output else {
+        log::debug!("kernel log unavailable - cannot check for Xid errors");
+        return Vec::new();
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let errors: Vec<XidError> = stdout.lines().filter_map(parse_line).collect();
+    let skip = errors.len().saturating_sub(limit);
+    errors.into_iter().skip(skip).collect()
+}
+
+/// Follows the kernel log and collects Xid errors raised while a game runs
+pub struct XidMonitor {
+    child: Child,
+    reader: Option<JoinHandle<()>>,
+    errors: Arc<Mutex<Vec<XidError>>>,
+}
+
+impl XidMonitor {
+    /// Start following new kernel messages (dmesg, falling back to journalctl)
+    pub fn start() -> Option<Self> {
+        Self::start_with(|xid| eprintln!("  Xid {} detected: {}", xid.code, xid.description()))
+    }
+
+    /// Like `start`, calling `on_error` for each Xid error as it is raised
+    pub fn start_with(on_error: impl Fn(&XidError) + Send + 'static) -> Option<Self> {
+        let child = spawn_follower("dmesg", &["--follow-new"])
+            .or_else(|| spawn_follower("journalctl", &["-k", "-f", "-n", "0", "--no-pager", "-q"]));
+        let Some(mut child) = child else {
+            log::warn!("kernel log unavailable - Xid monitoring disabled");
+            return None;
+        };
+
+        let stdout = child.stdout.take()?;
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let collected = Arc::clone(&errors);
+        let reader = thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+                if let Some(xid) = parse_line(&line) {
+                    on_error(&xid);
+                    if let Ok(mut errors) = collected.lock() {
+                        errors.push(xid);
+                    }
+                }
+            }
+        });
+
+        Some(Self {
+            child,
+            reader: Some(reader),
+            errors,
+        })
+    }
+
+    /// Stop following the kernel log and return the collected errors
+    pub fn stop(mut self) -> Vec<XidError> {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+        self.errors.lock().map(|e| e.clone()).unwrap_or_default()
+    }
+}
+
+fn spawn_follower(program: &str, args: &[&str]) -> Option<Child> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    // Followers that exit immediately (e.g. dmesg without permission) are unusable
+    thread::sleep(std::time::Duration::from_millis(100));
+    match child.try_wait() {
+        Ok(None) => Some(child),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_xid_with_device() {
+        let line = "[ 1234.5678] NVRM: Xid (PCI:0000:01:00): 79, pid=1234, name=game.exe, GPU has fallen off the bus.";
+        let xid = parse_line(line).unwrap();
+        assert_eq!(xid.code, 79);
+        assert_eq!(xid.device.as_deref(), Some("PCI:0000:01:00"));
+        assert_eq!(xid.description(), "GPU has fallen off the bus");
+    }
+
+    #[test]
+    fn test_parse_xid_without_device() {
+        let xid = parse_line("NVRM: Xid: 13, Graphics Exception").unwrap();
+        assert_eq!(xid.code, 13);
+        assert!(xid.device.is_none());
+    }
+
+    #[test]
+    fn test_ignores_other_lines() {
+        assert!(parse_line("nvidia-modeset: Allocated GPU:0").is_none());
+    }
+}
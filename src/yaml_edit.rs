@@ -0,0 +1,365 @@
+//! Targeted edits to hand-written YAML files
+//!
+//! Re-serializing a config or profile drops the user's comments, blank lines
+//! and key order. Instead, the differences between the file's current content
+//! and the new value are applied line by line: changed scalars are replaced in
+//! place (keeping trailing comments), new keys are appended to their parent
+//! block and removed keys are cut out. Sequences and multi-line values are
+//! rewritten as a whole. Only block-style YAML is edited; when the result
+//! doesn't parse back to the new value the caller falls back to a full
+//! rewrite.
+
+use anyhow::Result;
+use serde_yaml::{Mapping, Value};
+
+/// Apply the changes between `original` and `new` as targeted edits
+///
+/// `normalize` parses a document into the value compared against `new`, e.g.
+/// through the config struct so missing keys get their defaults. Returns
+/// `None` if the edits can't be made safely.
+pub fn edit_in_place<F>(original: &str, new: &Value, normalize: F) -> Option<String>
+where
+    F: Fn(&str) -> Result<Value>,
+{
+    let old = normalize(original).ok()?;
+    let mut changes = Vec::new();
+    diff(&old, new, &mut Vec::new(), &mut changes);
+    if changes.is_empty() {
+        return Some(original.to_string());
+    }
+
+    let mut document = Document::parse(original);
+    for change in &changes {
+        document.apply(change, new)?;
+    }
+    let edited = document.render();
+    (normalize(&edited).ok()? == *new).then_some(edited)
+}
+
+/// A value to set (or remove, for `None`) at a key path
+struct Change {
+    path: Vec<String>,
+    value: Option<Value>,
+}
+
+fn diff(old: &Value, new: &Value, path: &mut Vec<String>, changes: &mut Vec<Change>) {
+    let (Value::Mapping(old_map), Value::Mapping(new_map)) = (old, new) else {
+        if old != new {
+            changes.push(Change {
+                path: path.clone(),
+                value: Some(new.clone()),
+            });
+        }
+        return;
+    };
+    // Emptying a mapping leaves a bare `key:` (null); write `{}` instead
+    let keys_are_text = old_map.keys().chain(new_map.keys()).all(|k| k.is_string());
+    if !keys_are_text || (new_map.is_empty() && !old_map.is_empty()) {
+        changes.push(Change {
+            path: path.clone(),
+            value: Some(new.clone()),
+        });
+        return;
+    }
+
+    for (key, new_value) in new_map {
+        path.push(key.as_str().unwrap_or_default().to_string());
+        match old_map.get(key) {
+            Some(old_value) => diff(old_value, new_value, path, changes),
+            None => changes.push(Change {
+                path: path.clone(),
+                value: Some(new_value.clone()),
+            }),
+        }
+        path.pop();
+    }
+    for key in old_map.keys().filter(|k| !new_map.contains_key(*k)) {
+        path.push(key.as_str().unwrap_or_default().to_string());
+        changes.push(Change {
+            path: path.clone(),
+            value: None,
+        });
+        path.pop();
+    }
+}
+
+/// Value at a key path
+fn lookup<'a>(root: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(root, |value, key| value.get(key.as_str()))
+}
+
+struct Document {
+    lines: Vec<String>,
+    trailing_newline: bool,
+}
+
+impl Document {
+    fn parse(text: &str) -> Self {
+        Self {
+            lines: text.lines().map(String::from).collect(),
+            trailing_newline: text.ends_with('\n') || text.is_empty(),
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut text = self.lines.join("\n");
+        if self.trailing_newline && !text.is_empty() {
+            text.push('\n');
+        }
+        text
+    }
+
+    fn apply(&mut self, change: &Change, new_root: &Value) -> Option<()> {
+        let path = &change.path;
+        let (depth, found) = self.locate(path);
+
+        let Some(value) = &change.value else {
+            if depth == path.len()
+                && let Some((line, end)) = found
+            {
+                self.lines.drain(line..end);
+            }
+            return Some(());
+        };
+        if depth == path.len() {
+            let (line, end) = found?;
+            return self.replace(line, end, &path[depth - 1], value);
+        }
+
+        // The key is missing: add it (with any missing parents) under the
+        // deepest ancestor present in the file
+        let (start, end, parent_indent) = match found {
+            Some((line, end)) => {
+                if inline_value(&self.lines[line]).is_some() {
+                    // `env: {}` can't take children; rewrite the parent whole
+                    let parent = lookup(new_root, &path[..depth])?;
+                    return self.replace(line, end, &path[depth - 1], parent);
+                }
+                (line + 1, end, Some(indent_of(&self.lines[line])))
+            }
+            None => (0, self.lines.len(), None),
+        };
+        let indent = match self.first_entry(start, end) {
+            Some(first) if self.lines[first].trim_start().starts_with('-') => return None,
+            Some(first) => indent_of(&self.lines[first]),
+            None => parent_indent.map(|i| i + 2).unwrap_or(0),
+        };
+        let subtree = lookup(new_root, &path[..=depth])?;
+        let rendered = render_entry(&path[depth], subtree, indent)?;
+        let at = self.content_end(start, end);
+        self.lines.splice(at..at, rendered);
+        Some(())
+    }
+
+    /// Replace the entry spanning lines [line, end) with `key: value`
+    fn replace(&mut self, line: usize, end: usize, key: &str, value: &Value) -> Option<()> {
+        let indent = indent_of(&self.lines[line]);
+        let current = &self.lines[line];
+        let simple_inline = end == line + 1
+            && inline_value(current).is_some_and(|v| !v.starts_with(['|', '>', '&', '*', '!']));
+        if simple_inline
+            && !matches!(value, Value::Mapping(_) | Value::Sequence(_))
+            && let Some(scalar) = render_scalar(value)
+        {
+            let (token, rest) = split_entry(current.trim_start())?;
+            let comment = trailing_comment(rest);
+            self.lines[line] = format!("{}{}: {}{}", " ".repeat(indent), token, scalar, comment);
+            return Some(());
+        }
+        let rendered = render_entry(key, value, indent)?;
+        self.lines.splice(line..end, rendered);
+        Some(())
+    }
+
+    /// Follow `path` through the file: how many keys were found, and the
+    /// lines of the deepest one
+    fn locate(&self, path: &[String]) -> (usize, Option<(usize, usize)>) {
+        let (mut start, mut end) = (0, self.lines.len());
+        let mut found = None;
+        for (depth, key) in path.iter().enumerate() {
+            match self.find_key(start, end, key) {
+                Some((line, block_end)) => {
+                    found = Some((line, block_end));
+                    start = line + 1;
+                    end = block_end;
+                }
+                None => return (depth, found),
+            }
+        }
+        (path.len(), found)
+    }
+
+    /// Find `key` among the entries in lines [start, end)
+    fn find_key(&self, start: usize, end: usize, key: &str) -> Option<(usize, usize)> {
+        let indent = indent_of(&self.lines[self.first_entry(start, end)?]);
+        (start..end)
+            .filter(|&i| is_content(&self.lines[i]) && indent_of(&self.lines[i]) == indent)
+            .find(|&i| {
+                split_entry(self.lines[i].trim_start()).map(|(token, _)| unquote(token))
+                    == Some(key)
+            })
+            .map(|line| (line, self.block_end(line, end, indent)))
+    }
+
+    /// End of the entry starting at `line`, excluding trailing blank and
+    /// comment lines (those usually describe the next entry)
+    fn block_end(&self, line: usize, end: usize, indent: usize) -> usize {
+        let mut last = line;
+        for i in line + 1..end {
+            let text = &self.lines[i];
+            if !is_content(text) {
+                continue;
+            }
+            let text_indent = indent_of(text);
+            // Sequences may sit at the same indent as their key
+            if text_indent < indent
+                || (text_indent == indent && !text.trim_start().starts_with('-'))
+            {
+                break;
+            }
+            last = i;
+        }
+        last + 1
+    }
+
+    fn first_entry(&self, start: usize, end: usize) -> Option<usize> {
+        (start..end).find(|&i| is_content(&self.lines[i]))
+    }
+
+    /// Position after the last content line in [start, end)
+    fn content_end(&self, start: usize, end: usize) -> usize {
+        (start..end)
+            .rev()
+            .find(|&i| is_content(&self.lines[i]))
+            .map(|i| i + 1)
+            .unwrap_or(start)
+    }
+}
+
+fn is_content(line: &str) -> bool {
+    let trimmed = line.trim();
+    !(trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---" || trimmed == "...")
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Split `key: rest` into the key token and what follows the colon
+fn split_entry(trimmed: &str) -> Option<(&str, &str)> {
+    if trimmed.starts_with(['-', '#', '?']) {
+        return None;
+    }
+    let colon = if let Some(quote) = trimmed.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        trimmed[1..].find(quote)? + 2
+    } else {
+        match trimmed.find(": ") {
+            Some(idx) => idx,
+            None => trimmed.strip_suffix(':').map(str::len)?,
+        }
+    };
+    let rest = trimmed[colon..].strip_prefix(':')?;
+    Some((trimmed[..colon].trim_end(), rest))
+}
+
+fn unquote(token: &str) -> &str {
+    token
+        .strip_prefix('"')
+        .and_then(|t| t.strip_suffix('"'))
+        .or_else(|| token.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')))
+        .unwrap_or(token)
+}
+
+/// Value written on the key's own line, without its comment
+fn inline_value(line: &str) -> Option<&str> {
+    let (_, rest) = split_entry(line.trim_start())?;
+    let comment = trailing_comment(rest);
+    let value = rest[..rest.len() - comment.len()].trim();
+    (!value.is_empty()).then_some(value)
+}
+
+/// The ` # comment` at the end of an entry, with its leading whitespace
+fn trailing_comment(rest: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (idx, c) in rest.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '#') if previous.is_whitespace() => {
+                return &rest[rest[..idx].trim_end().len()..];
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    ""
+}
+
+fn render_scalar(value: &Value) -> Option<String> {
+    let rendered = serde_yaml::to_string(value).ok()?;
+    let rendered = rendered.trim_end();
+    (!rendered.contains('\n')).then(|| rendered.to_string())
+}
+
+fn render_entry(key: &str, value: &Value, indent: usize) -> Option<Vec<String>> {
+    let mut entry = Mapping::new();
+    entry.insert(Value::String(key.to_string()), value.clone());
+    let rendered = serde_yaml::to_string(&entry).ok()?;
+    let pad = " ".repeat(indent);
+    Some(rendered.lines().map(|l| format!("{}{}", pad, l)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(original: &str, new: &str) -> Option<String> {
+        let new: Value = serde_yaml::from_str(new).unwrap();
+        edit_in_place(original, &new, |text| Ok(serde_yaml::from_str(text)?))
+    }
+
+    #[test]
+    fn test_scalar_change_keeps_comments() {
+        let original = "# nvproton config\nstreaming:\n  # pause on OBS\n  auto_detect: true  # default\n\nenv:\n  DXVK_HUD: fps\n";
+        let edited = edit(
+            original,
+            "streaming:\n  auto_detect: false\nenv:\n  DXVK_HUD: fps\n",
+        )
+        .unwrap();
+        assert_eq!(
+            edited,
+            "# nvproton config\nstreaming:\n  # pause on OBS\n  auto_detect: false  # default\n\nenv:\n  DXVK_HUD: fps\n"
+        );
+    }
+
+    #[test]
+    fn test_insert_and_remove_keys() {
+        let original =
+            "env:\n  DXVK_HUD: fps # overlay\n  PROTON_LOG: '1'\n\n# reporting\nreporting: {}\n";
+        let edited = edit(
+            original,
+            "env:\n  DXVK_HUD: fps\n  VKD3D_DEBUG: none\nreporting:\n  endpoint: https://example.com\nlibrary_paths:\n  steam: /mnt/Steam\n",
+        )
+        .unwrap();
+        assert_eq!(
+            edited,
+            "env:\n  DXVK_HUD: fps # overlay\n  VKD3D_DEBUG: none\n\n# reporting\nreporting:\n  endpoint: https://example.com\nlibrary_paths:\n  steam: /mnt/Steam\n"
+        );
+    }
+
+    #[test]
+    fn test_sequence_replaced_whole() {
+        let original = "settings:\n  wrappers:\n  - mangohud # overlay\n  env:\n    A: b\n";
+        let edited = edit(
+            original,
+            "settings:\n  wrappers: [gamemoderun, mangohud]\n  env:\n    A: b\n",
+        )
+        .unwrap();
+        assert_eq!(
+            edited,
+            "settings:\n  wrappers:\n  - gamemoderun\n  - mangohud\n  env:\n    A: b\n"
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! End-to-end detector tests against fixture trees
+//!
+//! `Fixture` builds a fake home directory holding Steam, Heroic, Lutris and
+//! legendary data; `detect` runs `nvproton detect --root` on it with a config
+//! and state of its own, so nothing on the machine running the tests is read.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use serde_json::Value;
+use tempfile::TempDir;
+
+const STEAM: &str = ".local/share/Steam";
+
+struct Fixture {
+    /// The layout passed as `--root`
+    root: TempDir,
+    /// HOME of the nvproton process (config, state, game database)
+    home: TempDir,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        Self {
+            root: tempfile::tempdir().unwrap(),
+            home: tempfile::tempdir().unwrap(),
+        }
+    }
+
+    fn path(&self, relative: &str) -> PathBuf {
+        self.root.path().join(relative.trim_start_matches('/'))
+    }
+
+    fn write(&self, relative: &str, content: &str) {
+        let path = self.path(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    /// An installed Steam app; `library` is "" for the Steam install itself
+    fn steam_app(&self, library: &str, appid: &str, name: &str, exe: &str) {
+        let steamapps = if library.is_empty() {
+            format!("{}/steamapps", STEAM)
+        } else {
+            format!("{}/steamapps", library)
+        };
+        self.write(
+            &format!("{}/appmanifest_{}.acf", steamapps, appid),
+            &format!(
+                "\"AppState\"\n{{\n\t\"appid\"\t\t\"{}\"\n\t\"name\"\t\t\"{}\"\n\t\"StateFlags\"\t\t\"4\"\n\t\"installdir\"\t\t\"{}\"\n}}\n",
+                appid, name, name
+            ),
+        );
+        self.write(&format!("{}/common/{}/{}", steamapps, name, exe), "MZ");
+    }
+
+    /// `libraryfolders.vdf` listing libraries by the paths Steam records
+    fn steam_libraries(&self, libraries: &[&str]) {
+        let mut vdf = String::from("\"libraryfolders\"\n{\n");
+        for (i, library) in libraries.iter().enumerate() {
+            vdf.push_str(&format!(
+                "\t\"{}\"\n\t{{\n\t\t\"path\"\t\t\"{}\"\n\t}}\n",
+                i, library
+            ));
+        }
+        vdf.push_str("}\n");
+        self.write(&format!("{}/steamapps/libraryfolders.vdf", STEAM), &vdf);
+    }
+
+    fn heroic_game(&self, app_name: &str, title: &str, install_path: &str) {
+        self.write(
+            ".config/heroic/store/legendary/library.json",
+            &format!(
+                r#"{{"games": [{{"app_name": "{}", "title": "{}", "install_path": "{}"}}]}}"#,
+                app_name, title, install_path
+            ),
+        );
+    }
+
+    fn lutris_game(&self, slug: &str, name: &str, directory: &str) {
+        let db = self.path(".local/share/lutris/pga.db");
+        fs::create_dir_all(db.parent().unwrap()).unwrap();
+        let connection = rusqlite::Connection::open(db).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS games (slug TEXT, name TEXT, directory TEXT, runner TEXT)",
+            )
+            .unwrap();
+        connection
+            .execute(
+                "INSERT INTO games VALUES (?1, ?2, ?3, 'wine')",
+                [slug, name, directory],
+            )
+            .unwrap();
+    }
+
+    fn legendary_game(&self, app_name: &str, title: &str, install_path: &str) {
+        self.write(
+            ".config/legendary/installed.json",
+            &format!(
+                r#"{{"{}": {{"title": "{}", "install_path": "{}", "executable": "Game.exe"}}}}"#,
+                app_name, title, install_path
+            ),
+        );
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_nvproton"))
+            .args(args)
+            .arg("--root")
+            .arg(self.root.path())
+            .env("HOME", self.home.path())
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("XDG_DATA_HOME")
+            .env_remove("XDG_STATE_HOME")
+            .env_remove("NVPROTON_CONTEXT")
+            .output()
+            .unwrap()
+    }
+
+    /// JSON output of `detect <source>`
+    fn detect(&self, source: &str) -> Vec<Value> {
+        let output = self.run(&["detect", source, "--format", "json"]);
+        assert!(
+            output.status.success(),
+            "detect {} failed: {}",
+            source,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        serde_json::from_slice(&output.stdout).unwrap()
+    }
+}
+
+fn ids(games: &[Value]) -> Vec<&str> {
+    let mut ids: Vec<&str> = games.iter().map(|g| g["id"].as_str().unwrap()).collect();
+    ids.sort();
+    ids
+}
+
+fn install_dir(game: &Value) -> &Path {
+    Path::new(game["install_dir"].as_str().unwrap())
+}
+
+#[test]
+fn test_detects_every_launcher() {
+    let fixture = Fixture::new();
+    fixture.steam_app("", "1145360", "Hades", "x64/Hades.exe");
+    // Proton itself is installed as an app, but isn't a game
+    fixture.steam_app("", "2180100", "Proton 9.0", "proton.exe");
+    fixture.heroic_game("Sugar", "Rainbow Six Siege", "/games/heroic/R6");
+    fixture.lutris_game("diablo-iv", "Diablo IV", "/games/lutris/diablo-iv");
+    fixture.legendary_game("Fortnite", "Fortnite", "/games/legendary/Fortnite");
+
+    let steam = fixture.detect("steam");
+    assert_eq!(ids(&steam), vec!["1145360"]);
+    assert_eq!(
+        steam[0]["executable"].as_str(),
+        Some(
+            fixture
+                .path(STEAM)
+                .canonicalize()
+                .unwrap()
+                .join("steamapps/common/Hades/x64/Hades.exe")
+                .to_str()
+                .unwrap()
+        )
+    );
+
+    // Paths launchers record are looked up inside the fixture
+    let heroic = fixture.detect("heroic");
+    assert_eq!(ids(&heroic), vec!["Sugar"]);
+    assert_eq!(install_dir(&heroic[0]), fixture.path("games/heroic/R6"));
+    let lutris = fixture.detect("lutris");
+    assert_eq!(ids(&lutris), vec!["diablo-iv"]);
+    assert_eq!(
+        install_dir(&lutris[0]),
+        fixture.path("games/lutris/diablo-iv")
+    );
+    let legendary = fixture.detect("legendary");
+    assert_eq!(ids(&legendary), vec!["Fortnite"]);
+
+    let all = fixture.detect("all");
+    assert_eq!(ids(&all), vec!["1145360", "Fortnite", "Sugar", "diablo-iv"]);
+}
+
+#[test]
+fn test_libraries_resolve_inside_root() {
+    let fixture = Fixture::new();
+    fixture.steam_app("", "1145360", "Hades", "Hades.exe");
+    fixture.steam_app(
+        "/mnt/games/SteamLibrary",
+        "1086940",
+        "Baldurs Gate 3",
+        "bg3.exe",
+    );
+    // A library that wasn't copied is skipped, never read from the host
+    fixture.steam_libraries(&["/mnt/games/SteamLibrary", "/media/usb/SteamLibrary"]);
+
+    let games = fixture.detect("steam");
+    assert_eq!(ids(&games), vec!["1086940", "1145360"]);
+    let bg3 = games.iter().find(|g| g["id"] == "1086940").unwrap();
+    assert_eq!(
+        install_dir(bg3),
+        fixture.path("mnt/games/SteamLibrary/steamapps/common/Baldurs Gate 3")
+    );
+}
+
+#[test]
+fn test_same_game_from_two_launchers_is_folded() {
+    let fixture = Fixture::new();
+    fixture.steam_app("", "1145360", "Hades", "Hades.exe");
+    fixture.heroic_game("Min", "Hades", "/games/heroic/Hades");
+
+    let all = fixture.detect("all");
+    assert_eq!(all.len(), 1);
+    assert_eq!(all[0]["source"], "steam");
+    assert_eq!(all[0]["also_in"][0]["source"], "heroic");
+    assert_eq!(all[0]["also_in"][0]["id"], "Min");
+}
+
+#[test]
+fn test_root_never_updates_the_database() {
+    let fixture = Fixture::new();
+    fixture.steam_app("", "1145360", "Hades", "Hades.exe");
+
+    let output = fixture.run(&["detect", "steam", "--update-db"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--root"));
+}
+
+#[test]
+fn test_explain_reports_skips_and_scores() {
+    let fixture = Fixture::new();
+    fixture.steam_app("", "1145360", "Hades", "Hades.exe");
+    fixture.write(
+        &format!("{}/steamapps/common/Hades/unins000.exe", STEAM),
+        "MZ",
+    );
+    fixture.steam_app("", "2180100", "Proton 9.0", "proton.exe");
+
+    let output = fixture.run(&["detect", "steam", "--format", "json", "--explain"]);
+    assert!(output.status.success());
+    // Explanations go to stderr, leaving the JSON intact
+    let games: Vec<Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(ids(&games), vec!["1145360"]);
+    let explain = String::from_utf8_lossy(&output.stderr);
+    assert!(explain.contains("Proton 9.0 (2180100) skipped: Steam tool or runtime"));
+    assert!(explain.contains("skipped \"unins000.exe\": name contains 'unins'"));
+    assert!(explain.contains("\"Hades.exe\" (name matches directory +50, in install root +30"));
+}